@@ -0,0 +1,797 @@
+use crate::memory_interface::{MemoryInterface, MemoryOperation, Payload};
+use std::cell::RefCell;
+use std::convert::TryInto;
+use std::rc::Rc;
+
+type AddressType = u64;
+
+// Same layout as QEMU's virt machine with aia=aplic-imsic, for the single hart
+pub const APLIC_M_BASE: AddressType = 0x0c00_0000;
+pub const APLIC_S_BASE: AddressType = 0x0d00_0000;
+pub const APLIC_SIZE: AddressType = 0x8000;
+pub const IMSIC_M_BASE: AddressType = 0x2400_0000;
+pub const IMSIC_S_BASE: AddressType = 0x2800_0000;
+pub const IMSIC_SIZE: AddressType = 0x1000;
+pub const MMIO_REGIONS: [(AddressType, AddressType); 4] = [
+    (APLIC_M_BASE, APLIC_SIZE),
+    (APLIC_S_BASE, APLIC_SIZE),
+    (IMSIC_M_BASE, IMSIC_SIZE),
+    (IMSIC_S_BASE, IMSIC_SIZE),
+];
+
+// Registers reached through miselect/siselect
+pub const ISELECT_EIDELIVERY: AddressType = 0x70;
+pub const ISELECT_EITHRESHOLD: AddressType = 0x72;
+pub const ISELECT_EIP0: AddressType = 0x80;
+pub const ISELECT_EIE0: AddressType = 0xc0;
+
+// Interrupt identities 1..=255 in each interrupt file
+const IMSIC_NUM_IDS: AddressType = 255;
+const IMSIC_SETEIPNUM_LE: AddressType = 0x0;
+const IMSIC_SETEIPNUM_BE: AddressType = 0x4;
+
+// Interrupt sources 1..=63
+const APLIC_NUM_SOURCES: usize = 63;
+const APLIC_DOMAINCFG: AddressType = 0x0000;
+const APLIC_MMSIADDRCFG: AddressType = 0x1bc0;
+const APLIC_SMSIADDRCFGH: AddressType = 0x1bcc;
+const APLIC_SETIP: AddressType = 0x1c00;
+const APLIC_SETIPNUM: AddressType = 0x1cdc;
+const APLIC_IN_CLRIP: AddressType = 0x1d00;
+const APLIC_CLRIPNUM: AddressType = 0x1ddc;
+const APLIC_SETIE: AddressType = 0x1e00;
+const APLIC_SETIENUM: AddressType = 0x1edc;
+const APLIC_CLRIE: AddressType = 0x1f00;
+const APLIC_CLRIENUM: AddressType = 0x1fdc;
+const APLIC_SETIPNUM_LE: AddressType = 0x2000;
+const APLIC_SETIPNUM_BE: AddressType = 0x2004;
+const APLIC_GENMSI: AddressType = 0x3000;
+const APLIC_IDELIVERY: AddressType = 0x4000;
+const APLIC_IFORCE: AddressType = 0x4004;
+const APLIC_ITHRESHOLD: AddressType = 0x4008;
+const APLIC_TOPI: AddressType = 0x4018;
+const APLIC_CLAIMI: AddressType = 0x401c;
+// The upper byte of domaincfg reads 0x80 so it can't be mistaken for a PLIC
+const DOMAINCFG_FIXED: u32 = 0x80 << 24;
+const DOMAINCFG_IE: u32 = 1 << 8;
+const DOMAINCFG_DM: u32 = 1 << 2;
+const SOURCECFG_D: u32 = 1 << 10;
+const SM_INACTIVE: u32 = 0;
+const SM_DETACHED: u32 = 1;
+const SM_EDGE1: u32 = 4;
+const SM_EDGE0: u32 = 5;
+const SM_LEVEL1: u32 = 6;
+const SM_LEVEL0: u32 = 7;
+const MSIADDRCFGH_L: u32 = 1 << 31;
+const TARGET_EIID: u32 = 0x7ff;
+const TARGET_GUEST_INDEX: u32 = 0x3f << 12;
+const TARGET_IPRIO: u32 = 0xff;
+const GENMSI_EIID: u32 = 0x7ff;
+
+// An IMSIC interrupt file, a hart has one for M-level and one for S-level
+#[derive(Default)]
+pub struct InterruptFile {
+    eidelivery: AddressType,
+    eithreshold: AddressType,
+    eip: [u64; 4],
+    eie: [u64; 4],
+}
+
+impl InterruptFile {
+    pub fn set_pending(&mut self, id: AddressType) {
+        if id != 0 && id <= IMSIC_NUM_IDS {
+            self.eip[(id / 64) as usize] |= 1 << (id % 64);
+        }
+    }
+
+    // Lowest pending and enabled identity when it is below eithreshold, 0 otherwise
+    pub fn top(&self) -> AddressType {
+        for (i, (eip, eie)) in self.eip.iter().zip(self.eie.iter()).enumerate() {
+            let bits = eip & eie;
+            if bits != 0 {
+                let id = i as AddressType * 64 + bits.trailing_zeros() as AddressType;
+                if self.eithreshold == 0 || id < self.eithreshold {
+                    return id;
+                }
+                return 0;
+            }
+        }
+        0
+    }
+
+    // mtopei/stopei, the identity is also the priority
+    pub fn topei(&self) -> AddressType {
+        let id = self.top();
+        (id << 16) | id
+    }
+
+    pub fn claim(&mut self) {
+        let id = self.top();
+        self.eip[(id / 64) as usize] &= !(1 << (id % 64));
+    }
+
+    pub fn interrupt_pending(&self) -> bool {
+        self.eidelivery == 1 && self.top() != 0
+    }
+
+    // RV64 only has the even-numbered eip/eie registers, each covering 64 identities
+    pub fn read_reg(&self, iselect: AddressType) -> AddressType {
+        let idx = ((iselect & 0x3f) / 2) as usize;
+        match iselect {
+            ISELECT_EIDELIVERY => self.eidelivery,
+            ISELECT_EITHRESHOLD => self.eithreshold,
+            ISELECT_EIP0..=0xbf if idx < self.eip.len() => self.eip[idx],
+            ISELECT_EIE0..=0xff if idx < self.eie.len() => self.eie[idx],
+            _ => 0,
+        }
+    }
+
+    pub fn write_reg(&mut self, iselect: AddressType, val: AddressType) {
+        let idx = ((iselect & 0x3f) / 2) as usize;
+        // Identity 0 does not exist
+        let wmask = if idx == 0 { !1 } else { AddressType::MAX };
+        match iselect {
+            ISELECT_EIDELIVERY => self.eidelivery = val & 1,
+            ISELECT_EITHRESHOLD => self.eithreshold = val & IMSIC_NUM_IDS,
+            ISELECT_EIP0..=0xbf if idx < self.eip.len() => self.eip[idx] = val & wmask,
+            ISELECT_EIE0..=0xff if idx < self.eie.len() => self.eie[idx] = val & wmask,
+            _ => (),
+        }
+    }
+
+    fn access_mmio(&mut self, offset: AddressType, val: u32) {
+        match offset {
+            IMSIC_SETEIPNUM_LE => self.set_pending(val as AddressType),
+            IMSIC_SETEIPNUM_BE => self.set_pending(val.swap_bytes() as AddressType),
+            _ => (),
+        }
+    }
+}
+
+// iselect values that select a register, the major interrupt priorities 0x30..=0x3f
+// are read-only zero
+pub fn iselect_valid(iselect: AddressType) -> bool {
+    match iselect {
+        0x30..=0x3f | 0x80..=0xff => iselect & 1 == 0,
+        ISELECT_EIDELIVERY | ISELECT_EITHRESHOLD => true,
+        _ => false,
+    }
+}
+
+// One interrupt domain of the APLIC, bit i of the bitmaps is for source i
+struct AplicDomain {
+    domaincfg: u32,
+    sourcecfg: [u32; APLIC_NUM_SOURCES + 1],
+    target: [u32; APLIC_NUM_SOURCES + 1],
+    pending: u64,
+    enabled: u64,
+    genmsi: u32,
+    idelivery: u32,
+    iforce: u32,
+    ithreshold: u32,
+}
+
+impl AplicDomain {
+    fn new() -> AplicDomain {
+        AplicDomain {
+            domaincfg: 0,
+            sourcecfg: [0; APLIC_NUM_SOURCES + 1],
+            target: [0; APLIC_NUM_SOURCES + 1],
+            pending: 0,
+            enabled: 0,
+            genmsi: 0,
+            idelivery: 0,
+            iforce: 0,
+            ithreshold: 0,
+        }
+    }
+
+    fn msi_mode(&self) -> bool {
+        self.domaincfg & DOMAINCFG_DM != 0
+    }
+
+    fn enabled(&self) -> bool {
+        self.domaincfg & DOMAINCFG_IE != 0
+    }
+
+    // Source mode, inactive for the sources delegated to the child domain
+    fn source_mode(&self, source: usize) -> u32 {
+        if self.sourcecfg[source] & SOURCECFG_D != 0 {
+            SM_INACTIVE
+        } else {
+            self.sourcecfg[source] & 7
+        }
+    }
+
+    fn is_level(&self, source: usize) -> bool {
+        matches!(self.source_mode(source), SM_LEVEL1 | SM_LEVEL0)
+    }
+
+    // Input after the inversion of the Edge0/Level0 modes
+    fn rectified_input(&self, source: usize, input: bool) -> bool {
+        match self.source_mode(source) {
+            SM_EDGE1 | SM_LEVEL1 => input,
+            SM_EDGE0 | SM_LEVEL0 => !input,
+            _ => false,
+        }
+    }
+
+    // Highest priority pending and enabled source for the direct delivery mode, as
+    // its topi value
+    fn topi(&self) -> u32 {
+        if !self.enabled() || self.msi_mode() {
+            return 0;
+        }
+        let mut top = 0;
+        let mut top_prio = u32::MAX;
+        for source in 1..=APLIC_NUM_SOURCES {
+            let iprio = self.target[source] & TARGET_IPRIO;
+            let ready = (self.pending & self.enabled) >> source & 1 != 0
+                && (self.ithreshold == 0 || iprio < self.ithreshold);
+            if ready && iprio < top_prio {
+                top = source as u32;
+                top_prio = iprio;
+            }
+        }
+        if top == 0 {
+            0
+        } else {
+            (top << 16) | top_prio
+        }
+    }
+
+    fn interrupt_pending(&self) -> bool {
+        self.idelivery == 1 && (self.topi() != 0 || (self.enabled() && self.iforce == 1))
+    }
+}
+
+// IMSIC interrupt files and APLIC domains of the platform. The APLIC root domain is
+// M-level, its delegated sources go to the S-level child domain.
+pub struct Aia {
+    pub imsic_m: InterruptFile,
+    pub imsic_s: InterruptFile,
+    aplic_m: AplicDomain,
+    aplic_s: AplicDomain,
+    // mmsiaddrcfg, mmsiaddrcfgh, smsiaddrcfg and smsiaddrcfgh of the root domain
+    msiaddrcfg: [u32; 4],
+    // Level of the interrupt source wires
+    inputs: u64,
+}
+
+impl Aia {
+    pub fn new() -> Aia {
+        Aia {
+            imsic_m: InterruptFile::default(),
+            imsic_s: InterruptFile::default(),
+            aplic_m: AplicDomain::new(),
+            aplic_s: AplicDomain::new(),
+            msiaddrcfg: [0; 4],
+            inputs: 0,
+        }
+    }
+
+    // External interrupt lines of the hart, from the IMSIC or the direct mode APLIC
+    pub fn m_external(&self) -> bool {
+        self.imsic_m.interrupt_pending() || self.aplic_m.interrupt_pending()
+    }
+
+    pub fn s_external(&self) -> bool {
+        self.imsic_s.interrupt_pending() || self.aplic_s.interrupt_pending()
+    }
+
+    // Drive an interrupt source wire, devices do it through an InterruptLine
+    pub fn set_source(&mut self, source: usize, level: bool) {
+        if source == 0 || source > APLIC_NUM_SOURCES {
+            return;
+        }
+        let old_level = (self.inputs >> source) & 1 != 0;
+        self.inputs = (self.inputs & !(1 << source)) | ((level as u64) << source);
+        for s_level in [false, true] {
+            let domain = self.domain_mut(s_level);
+            // Level-sensitive sources in MSI mode also become pending on the rising edge
+            let edge = match domain.source_mode(source) {
+                SM_EDGE1 | SM_EDGE0 => true,
+                SM_LEVEL1 | SM_LEVEL0 => domain.msi_mode(),
+                _ => false,
+            };
+            let rising =
+                !domain.rectified_input(source, old_level) && domain.rectified_input(source, level);
+            if edge && rising {
+                domain.pending |= 1 << source;
+            }
+        }
+        self.update();
+    }
+
+    fn domain(&self, s_level: bool) -> &AplicDomain {
+        if s_level {
+            &self.aplic_s
+        } else {
+            &self.aplic_m
+        }
+    }
+
+    fn domain_mut(&mut self, s_level: bool) -> &mut AplicDomain {
+        if s_level {
+            &mut self.aplic_s
+        } else {
+            &mut self.aplic_m
+        }
+    }
+
+    fn input(&self, source: usize) -> bool {
+        (self.inputs >> source) & 1 != 0
+    }
+
+    fn set_pending(&mut self, s_level: bool, source: usize, pending: bool) {
+        if source == 0 || source > APLIC_NUM_SOURCES {
+            return;
+        }
+        let input = self.input(source);
+        let domain = self.domain_mut(s_level);
+        let mode = domain.source_mode(source);
+        // The pending bit of a level-sensitive source follows its input in direct
+        // mode, in MSI mode it can only be set while the input is asserted
+        let allowed = match mode {
+            SM_INACTIVE => false,
+            SM_LEVEL1 | SM_LEVEL0 => {
+                domain.msi_mode() && (!pending || domain.rectified_input(source, input))
+            }
+            _ => true,
+        };
+        if allowed {
+            if pending {
+                domain.pending |= 1 << source;
+            } else {
+                domain.pending &= !(1 << source);
+            }
+        }
+    }
+
+    fn set_enabled(&mut self, s_level: bool, source: usize, enabled: bool) {
+        if source == 0 || source > APLIC_NUM_SOURCES {
+            return;
+        }
+        let domain = self.domain_mut(s_level);
+        if domain.source_mode(source) == SM_INACTIVE {
+            return;
+        }
+        if enabled {
+            domain.enabled |= 1 << source;
+        } else {
+            domain.enabled &= !(1 << source);
+        }
+    }
+
+    // Level-sensitive pending bits follow their inputs in direct mode and are cleared
+    // with them in MSI mode, where the pending and enabled sources are forwarded to the IMSIC
+    fn update(&mut self) {
+        for s_level in [false, true] {
+            for source in 1..=APLIC_NUM_SOURCES {
+                let input = self.input(source);
+                let domain = self.domain_mut(s_level);
+                let level = domain.rectified_input(source, input);
+                if domain.is_level(source) && (!domain.msi_mode() || !level) {
+                    domain.pending = (domain.pending & !(1 << source)) | ((level as u64) << source);
+                }
+            }
+            let domain = self.domain(s_level);
+            if !domain.msi_mode() || !domain.enabled() {
+                continue;
+            }
+            let ready = domain.pending & domain.enabled;
+            for source in 1..=APLIC_NUM_SOURCES {
+                if (ready >> source) & 1 != 0 {
+                    let target = self.domain(s_level).target[source];
+                    self.domain_mut(s_level).pending &= !(1 << source);
+                    self.send_msi(s_level, target);
+                }
+            }
+        }
+    }
+
+    // Only guest index 0 exists, the message goes to the interrupt file at the MSI address
+    fn send_msi(&mut self, s_level: bool, target: u32) {
+        if target & TARGET_GUEST_INDEX != 0 {
+            return;
+        }
+        let (low, high) = if s_level {
+            (self.msiaddrcfg[2], self.msiaddrcfg[3])
+        } else {
+            (self.msiaddrcfg[0], self.msiaddrcfg[1])
+        };
+        let ppn = ((high as AddressType & 0xfff) << 32) | low as AddressType;
+        let eiid = (target & TARGET_EIID) as AddressType;
+        match ppn << 12 {
+            IMSIC_M_BASE => self.imsic_m.set_pending(eiid),
+            IMSIC_S_BASE => self.imsic_s.set_pending(eiid),
+            _ => (),
+        }
+    }
+
+    // Bits of setip/setie/in_clrip register `idx` from the source bitmap
+    fn bitmap_reg(bits: u64, idx: AddressType) -> u32 {
+        match idx {
+            0 => bits as u32 & !1,
+            1 => (bits >> 32) as u32,
+            _ => 0,
+        }
+    }
+
+    fn read_aplic(&mut self, s_level: bool, offset: AddressType) -> u32 {
+        let domain = self.domain(s_level);
+        let idx = (offset & 0x7f) / 4;
+        match offset {
+            APLIC_DOMAINCFG => domain.domaincfg | DOMAINCFG_FIXED,
+            0x4..=0xffc => {
+                let source = (offset / 4) as usize;
+                if source > APLIC_NUM_SOURCES {
+                    0
+                } else if s_level && self.aplic_m.sourcecfg[source] & SOURCECFG_D == 0 {
+                    // Not delegated to the child domain
+                    0
+                } else {
+                    domain.sourcecfg[source]
+                }
+            }
+            APLIC_MMSIADDRCFG..=APLIC_SMSIADDRCFGH if !s_level => {
+                self.msiaddrcfg[((offset - APLIC_MMSIADDRCFG) / 4) as usize]
+            }
+            APLIC_SETIP..=0x1c7c => Aia::bitmap_reg(domain.pending, idx),
+            APLIC_IN_CLRIP..=0x1d7c => {
+                let mut inputs = 0;
+                for source in 1..=APLIC_NUM_SOURCES {
+                    inputs |= (domain.rectified_input(source, self.input(source)) as u64) << source;
+                }
+                Aia::bitmap_reg(inputs, idx)
+            }
+            APLIC_SETIE..=0x1e7c => Aia::bitmap_reg(domain.enabled, idx),
+            APLIC_GENMSI => domain.genmsi,
+            0x3004..=0x3ffc => {
+                let source = ((offset - APLIC_GENMSI) / 4) as usize;
+                if source <= APLIC_NUM_SOURCES && domain.source_mode(source) != SM_INACTIVE {
+                    domain.target[source]
+                } else {
+                    0
+                }
+            }
+            APLIC_IDELIVERY => domain.idelivery,
+            APLIC_IFORCE => domain.iforce,
+            APLIC_ITHRESHOLD => domain.ithreshold,
+            APLIC_TOPI => domain.topi(),
+            APLIC_CLAIMI => {
+                let topi = domain.topi();
+                let source = (topi >> 16) as usize;
+                let domain = self.domain_mut(s_level);
+                if source == 0 {
+                    domain.iforce = 0;
+                } else if !domain.is_level(source) {
+                    domain.pending &= !(1 << source);
+                }
+                topi
+            }
+            _ => 0,
+        }
+    }
+
+    fn write_aplic(&mut self, s_level: bool, offset: AddressType, val: u32) {
+        let msi_locked = self.msiaddrcfg[1] & MSIADDRCFGH_L != 0;
+        match offset {
+            APLIC_DOMAINCFG => {
+                self.domain_mut(s_level).domaincfg = val & (DOMAINCFG_IE | DOMAINCFG_DM)
+            }
+            0x4..=0xffc => self.write_sourcecfg(s_level, (offset / 4) as usize, val),
+            APLIC_MMSIADDRCFG..=APLIC_SMSIADDRCFGH if !s_level && !msi_locked => {
+                // PPN[43:32] and the hart/guest index fields, only hart 0 exists
+                let wmask = match offset - APLIC_MMSIADDRCFG {
+                    0x4 => MSIADDRCFGH_L | 0x1f70_7fff,
+                    0xc => 0x0070_0fff,
+                    _ => u32::MAX,
+                };
+                self.msiaddrcfg[((offset - APLIC_MMSIADDRCFG) / 4) as usize] = val & wmask;
+            }
+            APLIC_SETIP..=0x1c7c
+            | APLIC_IN_CLRIP..=0x1d7c
+            | APLIC_SETIE..=0x1e7c
+            | APLIC_CLRIE..=0x1f7c => {
+                let base = offset & !0x7f;
+                let first = ((offset & 0x7f) / 4 * 32) as usize;
+                for bit in (0..32).filter(|bit| (val >> bit) & 1 != 0) {
+                    match base {
+                        APLIC_SETIP => self.set_pending(s_level, first + bit, true),
+                        APLIC_IN_CLRIP => self.set_pending(s_level, first + bit, false),
+                        APLIC_SETIE => self.set_enabled(s_level, first + bit, true),
+                        _ => self.set_enabled(s_level, first + bit, false),
+                    }
+                }
+            }
+            APLIC_SETIPNUM | APLIC_SETIPNUM_LE => self.set_pending(s_level, val as usize, true),
+            APLIC_SETIPNUM_BE => self.set_pending(s_level, val.swap_bytes() as usize, true),
+            APLIC_CLRIPNUM => self.set_pending(s_level, val as usize, false),
+            APLIC_SETIENUM => self.set_enabled(s_level, val as usize, true),
+            APLIC_CLRIENUM => self.set_enabled(s_level, val as usize, false),
+            APLIC_GENMSI => {
+                // The message is sent at once, so Busy is never observed
+                self.domain_mut(s_level).genmsi = val & GENMSI_EIID;
+                if self.domain(s_level).msi_mode() {
+                    self.send_msi(s_level, val & GENMSI_EIID);
+                }
+            }
+            0x3004..=0x3ffc => {
+                let source = ((offset - APLIC_GENMSI) / 4) as usize;
+                let domain = self.domain_mut(s_level);
+                if source <= APLIC_NUM_SOURCES && domain.source_mode(source) != SM_INACTIVE {
+                    domain.target[source] = if domain.msi_mode() {
+                        // Guest interrupt files only exist for the S-level domain
+                        val & (TARGET_EIID | if s_level { TARGET_GUEST_INDEX } else { 0 })
+                    } else if val & TARGET_IPRIO == 0 {
+                        // Priority 0 is not allowed, it reads back as 1
+                        1
+                    } else {
+                        val & TARGET_IPRIO
+                    };
+                }
+            }
+            APLIC_IDELIVERY => self.domain_mut(s_level).idelivery = val & 1,
+            APLIC_IFORCE => self.domain_mut(s_level).iforce = val & 1,
+            APLIC_ITHRESHOLD => self.domain_mut(s_level).ithreshold = val & TARGET_IPRIO,
+            _ => (),
+        }
+        self.update();
+    }
+
+    // The root domain can delegate a source to the child domain, a source leaving
+    // a domain or becoming inactive loses its pending and enabled bits
+    fn write_sourcecfg(&mut self, s_level: bool, source: usize, val: u32) {
+        if source > APLIC_NUM_SOURCES {
+            return;
+        }
+        let new_val = if !s_level && val & SOURCECFG_D != 0 {
+            SOURCECFG_D
+        } else if s_level && self.aplic_m.sourcecfg[source] & SOURCECFG_D == 0 {
+            return;
+        } else {
+            match val & 7 {
+                SM_DETACHED | SM_EDGE1 | SM_EDGE0 | SM_LEVEL1 | SM_LEVEL0 => val & 7,
+                _ => SM_INACTIVE,
+            }
+        };
+        let domain = self.domain_mut(s_level);
+        domain.sourcecfg[source] = new_val;
+        if domain.source_mode(source) == SM_INACTIVE {
+            domain.pending &= !(1 << source);
+            domain.enabled &= !(1 << source);
+            domain.target[source] = 0;
+        }
+        if !s_level && new_val & SOURCECFG_D == 0 {
+            let child = &mut self.aplic_s;
+            child.sourcecfg[source] = 0;
+            child.pending &= !(1 << source);
+            child.enabled &= !(1 << source);
+            child.target[source] = 0;
+        }
+    }
+}
+
+// Interrupt output of a device, wired to an APLIC source
+pub struct InterruptLine {
+    aia: Rc<RefCell<Aia>>,
+    source: usize,
+}
+
+impl InterruptLine {
+    pub fn new(aia: Rc<RefCell<Aia>>, source: usize) -> InterruptLine {
+        InterruptLine { aia, source }
+    }
+
+    pub fn set(&self, level: bool) {
+        self.aia.borrow_mut().set_source(self.source, level);
+    }
+}
+
+impl MemoryInterface for Aia {
+    // The registers are 32 bits wide, other accesses read zero and are ignored
+    fn access_memory(&mut self, payload: &mut Payload) {
+        let addr = payload.addr;
+        let val = if payload.data.len() == 4 {
+            u32::from_le_bytes(payload.data[..].try_into().unwrap())
+        } else {
+            0
+        };
+        let is_write = payload.op == MemoryOperation::WRITE && payload.data.len() == 4;
+        let mut rdata = 0;
+        for (s_level, base) in [(false, APLIC_M_BASE), (true, APLIC_S_BASE)] {
+            if addr >= base && addr - base < APLIC_SIZE {
+                if is_write {
+                    self.write_aplic(s_level, addr - base, val);
+                } else if payload.data.len() == 4 {
+                    rdata = self.read_aplic(s_level, addr - base);
+                }
+            }
+        }
+        if is_write && addr >= IMSIC_M_BASE && addr - IMSIC_M_BASE < IMSIC_SIZE {
+            self.imsic_m.access_mmio(addr - IMSIC_M_BASE, val);
+        } else if is_write && addr >= IMSIC_S_BASE && addr - IMSIC_S_BASE < IMSIC_SIZE {
+            self.imsic_s.access_mmio(addr - IMSIC_S_BASE, val);
+        }
+        if payload.op == MemoryOperation::READ {
+            let len = payload.data.len();
+            payload.data = rdata.to_le_bytes().to_vec();
+            payload.data.resize(len, 0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(aia: &mut Aia, addr: AddressType, val: u32) {
+        let mut payload = Payload {
+            addr,
+            data: val.to_le_bytes().to_vec(),
+            op: MemoryOperation::WRITE,
+            is_amo: false,
+        };
+        aia.access_memory(&mut payload);
+    }
+
+    fn read(aia: &mut Aia, addr: AddressType) -> u32 {
+        let mut payload = Payload {
+            addr,
+            data: vec![0; 4],
+            op: MemoryOperation::READ,
+            is_amo: false,
+        };
+        aia.access_memory(&mut payload);
+        u32::from_le_bytes(payload.data[..].try_into().unwrap())
+    }
+
+    #[test]
+    fn test_imsic() {
+        let mut aia = Aia::new();
+        write(&mut aia, IMSIC_S_BASE, 70);
+        write(&mut aia, IMSIC_S_BASE + 4, 3u32.swap_bytes());
+        assert_eq!(1 << 6, aia.imsic_s.read_reg(ISELECT_EIP0 + 2));
+        assert_eq!(1 << 3, aia.imsic_s.read_reg(ISELECT_EIP0));
+        assert_eq!(0, aia.imsic_s.top());
+
+        aia.imsic_s.write_reg(ISELECT_EIE0, AddressType::MAX);
+        aia.imsic_s.write_reg(ISELECT_EIE0 + 2, AddressType::MAX);
+        assert_eq!(!1, aia.imsic_s.read_reg(ISELECT_EIE0));
+        assert_eq!((3 << 16) | 3, aia.imsic_s.topei());
+        assert!(!aia.s_external());
+        aia.imsic_s.write_reg(ISELECT_EIDELIVERY, 1);
+        assert!(aia.s_external());
+        assert!(!aia.m_external());
+
+        // Identities at or above the threshold are masked
+        aia.imsic_s.write_reg(ISELECT_EITHRESHOLD, 3);
+        assert_eq!(0, aia.imsic_s.top());
+        aia.imsic_s.write_reg(ISELECT_EITHRESHOLD, 0);
+        aia.imsic_s.claim();
+        assert_eq!(70, aia.imsic_s.top());
+        aia.imsic_s.claim();
+        assert!(!aia.s_external());
+
+        assert!(iselect_valid(ISELECT_EIE0 + 62));
+        assert!(!iselect_valid(ISELECT_EIP0 + 1));
+        assert!(!iselect_valid(0x71));
+    }
+
+    #[test]
+    fn test_aplic_direct() {
+        let mut aia = Aia::new();
+        assert_eq!(0x8000_0000, read(&mut aia, APLIC_M_BASE));
+        write(&mut aia, APLIC_M_BASE, DOMAINCFG_IE);
+        write(&mut aia, APLIC_M_BASE + 4 * 2, SM_EDGE1);
+        write(&mut aia, APLIC_M_BASE + 4 * 5, SM_LEVEL0);
+        write(&mut aia, APLIC_M_BASE + APLIC_GENMSI + 4 * 2, 0);
+        write(&mut aia, APLIC_M_BASE + APLIC_GENMSI + 4 * 5, 3);
+        assert_eq!(1, read(&mut aia, APLIC_M_BASE + APLIC_GENMSI + 4 * 2));
+        write(
+            &mut aia,
+            APLIC_M_BASE + APLIC_SETIE,
+            (1 << 2) | (1 << 5) | (1 << 7),
+        );
+        // Source 7 is inactive
+        assert_eq!(
+            (1 << 2) | (1 << 5),
+            read(&mut aia, APLIC_M_BASE + APLIC_SETIE)
+        );
+        write(&mut aia, APLIC_M_BASE + APLIC_IDELIVERY, 1);
+
+        // The Level0 source is pending while its input is low
+        assert_eq!((5 << 16) | 3, read(&mut aia, APLIC_M_BASE + APLIC_TOPI));
+        assert!(aia.m_external());
+        aia.set_source(5, true);
+        assert!(!aia.m_external());
+
+        aia.set_source(2, true);
+        assert_eq!((2 << 16) | 1, read(&mut aia, APLIC_M_BASE + APLIC_CLAIMI));
+        assert_eq!(0, read(&mut aia, APLIC_M_BASE + APLIC_TOPI));
+        aia.set_source(2, false);
+        write(&mut aia, APLIC_M_BASE + APLIC_SETIPNUM, 2);
+        write(&mut aia, APLIC_M_BASE + APLIC_ITHRESHOLD, 1);
+        assert!(!aia.m_external());
+        write(&mut aia, APLIC_M_BASE + APLIC_ITHRESHOLD, 0);
+        write(&mut aia, APLIC_M_BASE + APLIC_CLRIPNUM, 2);
+        assert!(!aia.m_external());
+
+        // A delegated source belongs to the S-level domain
+        write(&mut aia, APLIC_M_BASE + 4 * 2, SOURCECFG_D);
+        assert_eq!(1 << 5, read(&mut aia, APLIC_M_BASE + APLIC_SETIE));
+        assert_eq!(0, read(&mut aia, APLIC_S_BASE + 4 * 3));
+        write(&mut aia, APLIC_S_BASE + 4 * 2, SM_EDGE0);
+        write(&mut aia, APLIC_S_BASE + 4 * 3, SM_EDGE0);
+        assert_eq!(SM_EDGE0, read(&mut aia, APLIC_S_BASE + 4 * 2));
+        assert_eq!(0, read(&mut aia, APLIC_S_BASE + 4 * 3));
+        write(&mut aia, APLIC_S_BASE, DOMAINCFG_IE);
+        write(&mut aia, APLIC_S_BASE + APLIC_SETIENUM, 2);
+        write(&mut aia, APLIC_S_BASE + APLIC_IDELIVERY, 1);
+        aia.set_source(2, true);
+        aia.set_source(2, false);
+        assert!(aia.s_external());
+        assert!(!aia.m_external());
+        write(&mut aia, APLIC_M_BASE + 4 * 2, SM_INACTIVE);
+        assert!(!aia.s_external());
+    }
+
+    #[test]
+    fn test_aplic_msi() {
+        let mut aia = Aia::new();
+        aia.imsic_m.write_reg(ISELECT_EIE0, AddressType::MAX);
+        aia.imsic_s.write_reg(ISELECT_EIE0, AddressType::MAX);
+        write(
+            &mut aia,
+            APLIC_M_BASE + APLIC_MMSIADDRCFG,
+            (IMSIC_M_BASE >> 12) as u32,
+        );
+        write(
+            &mut aia,
+            APLIC_M_BASE + APLIC_MMSIADDRCFG + 8,
+            (IMSIC_S_BASE >> 12) as u32,
+        );
+        write(&mut aia, APLIC_M_BASE, DOMAINCFG_IE | DOMAINCFG_DM);
+        write(&mut aia, APLIC_M_BASE + 4, SM_LEVEL1);
+        write(&mut aia, APLIC_M_BASE + APLIC_GENMSI + 4, 0x1_f009);
+        assert_eq!(9, read(&mut aia, APLIC_M_BASE + APLIC_GENMSI + 4));
+        write(&mut aia, APLIC_M_BASE + APLIC_SETIENUM, 1);
+
+        // Level-sensitive sources can only be set while asserted
+        write(&mut aia, APLIC_M_BASE + APLIC_SETIPNUM_LE, 1);
+        assert_eq!(0, aia.imsic_m.top());
+        aia.set_source(1, true);
+        assert_eq!(9, aia.imsic_m.top());
+        assert_eq!(0, read(&mut aia, APLIC_M_BASE + APLIC_SETIP));
+        assert_eq!(1 << 1, read(&mut aia, APLIC_M_BASE + APLIC_IN_CLRIP));
+        aia.imsic_m.claim();
+
+        // genmsi sends a message straight away
+        write(&mut aia, APLIC_M_BASE + APLIC_GENMSI, 12);
+        assert_eq!(12, aia.imsic_m.top());
+
+        // The S-level domain sends to the S-level interrupt file
+        write(&mut aia, APLIC_M_BASE + 4 * 4, SOURCECFG_D);
+        write(&mut aia, APLIC_S_BASE + 4 * 4, SM_EDGE1);
+        write(&mut aia, APLIC_S_BASE + APLIC_GENMSI + 4 * 4, 33);
+        write(&mut aia, APLIC_S_BASE + APLIC_SETIENUM, 4);
+        write(&mut aia, APLIC_S_BASE + APLIC_SETIPNUM, 4);
+        assert_eq!(1 << 4, read(&mut aia, APLIC_S_BASE + APLIC_SETIP));
+        write(&mut aia, APLIC_S_BASE, DOMAINCFG_IE | DOMAINCFG_DM);
+        assert_eq!(0, read(&mut aia, APLIC_S_BASE + APLIC_SETIP));
+        assert_eq!(33, aia.imsic_s.top());
+
+        // Locked MSI addresses are read-only
+        write(
+            &mut aia,
+            APLIC_M_BASE + APLIC_MMSIADDRCFG + 4,
+            MSIADDRCFGH_L,
+        );
+        write(&mut aia, APLIC_M_BASE + APLIC_MMSIADDRCFG, 0);
+        assert_eq!(
+            (IMSIC_M_BASE >> 12) as u32,
+            read(&mut aia, APLIC_M_BASE + APLIC_MMSIADDRCFG)
+        );
+    }
+}
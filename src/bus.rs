@@ -0,0 +1,82 @@
+use crate::memory_interface::{MemoryInterface, Payload};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+type AddressType = u64;
+type Device = Rc<RefCell<dyn MemoryInterface>>;
+
+// Routes accesses to the memory-mapped devices, everything else goes to memory
+pub struct Bus {
+    memory: Device,
+    devices: Vec<(AddressType, AddressType, Device)>,
+}
+
+impl Bus {
+    pub fn new(memory: Device) -> Bus {
+        Bus {
+            memory,
+            devices: Vec::new(),
+        }
+    }
+
+    // Devices see the absolute address of the access
+    pub fn add_device(&mut self, base: AddressType, size: AddressType, device: Device) {
+        self.devices.push((base, size, device));
+    }
+}
+
+impl MemoryInterface for Bus {
+    fn access_memory(&mut self, payload: &mut Payload) {
+        let device = self
+            .devices
+            .iter()
+            .find(|(base, size, _)| payload.addr >= *base && payload.addr - base < *size)
+            .map(|(_, _, device)| device);
+        match device {
+            Some(device) => device.borrow_mut().access_memory(payload),
+            None => self.memory.borrow_mut().access_memory(payload),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory_interface::MemoryOperation;
+    use crate::memory_model::MemoryModel;
+
+    #[derive(Default)]
+    struct Register {
+        val: u8,
+    }
+
+    impl MemoryInterface for Register {
+        fn access_memory(&mut self, payload: &mut Payload) {
+            if payload.op == MemoryOperation::WRITE {
+                self.val = payload.data[0];
+            } else {
+                payload.data[0] = self.val;
+            }
+        }
+    }
+
+    #[test]
+    fn test_bus() {
+        let mem = Rc::new(RefCell::new(MemoryModel::new()));
+        let reg = Rc::new(RefCell::new(Register::default()));
+        let mut bus = Bus::new(mem.clone());
+        bus.add_device(0x1000, 0x10, reg.clone());
+        let mut payload = Payload {
+            addr: 0x100f,
+            data: vec![0x5a],
+            op: MemoryOperation::WRITE,
+            is_amo: false,
+        };
+        bus.access_memory(&mut payload);
+        assert_eq!(0x5a, reg.borrow().val);
+        payload.addr = 0x1010;
+        bus.access_memory(&mut payload);
+        assert_eq!(0x5a, mem.borrow_mut().read_byte(0x1010));
+        assert_eq!(0, mem.borrow_mut().read_byte(0x100f));
+    }
+}
@@ -0,0 +1,221 @@
+use crate::memory_interface::{MemoryInterface, MemoryOperation, Payload};
+
+type AddressType = u64;
+
+// Interrupts 0..=15 are the local ones of mip, 16 and above come from devices
+pub const CLIC_NUM_INTERRUPTS: usize = 64;
+pub const CLIC_BASE: AddressType = 0x0280_0000;
+pub const CLIC_SIZE: AddressType = CLIC_INT + 4 * CLIC_NUM_INTERRUPTS as AddressType;
+
+const CLIC_CFG: AddressType = 0x0;
+const CLIC_INFO: AddressType = 0x4;
+const CLIC_INT: AddressType = 0x1000;
+// All 8 bits of clicintctl are implemented
+const CLIC_INTCTLBITS: u32 = 8;
+// mnlbits selects how many clicintctl bits hold the level, nmbits is zero so all
+// interrupts are M-mode ones
+const CFG_MNLBITS: u32 = 0xf;
+const INTATTR_SHV: u8 = 1 << 0;
+const INTATTR_TRIG_EDGE: u8 = 1 << 1;
+const INTATTR_TRIG_NEG: u8 = 1 << 2;
+const INTATTR_MODE_M: u8 = 3 << 6;
+
+#[derive(Default, Clone, Copy)]
+struct Interrupt {
+    ip: bool,
+    ie: bool,
+    attr: u8,
+    ctl: u8,
+    input: bool,
+}
+
+impl Interrupt {
+    fn active(&self) -> bool {
+        self.input != (self.attr & INTATTR_TRIG_NEG != 0)
+    }
+
+    fn is_edge(&self) -> bool {
+        self.attr & INTATTR_TRIG_EDGE != 0
+    }
+}
+
+pub struct Clic {
+    mnlbits: u32,
+    interrupts: [Interrupt; CLIC_NUM_INTERRUPTS],
+}
+
+impl Clic {
+    pub fn new() -> Clic {
+        Clic {
+            mnlbits: 0,
+            interrupts: [Interrupt::default(); CLIC_NUM_INTERRUPTS],
+        }
+    }
+
+    // Level-triggered interrupts follow their input, edge-triggered ones latch the
+    // inactive to active transition
+    pub fn set_input(&mut self, id: usize, level: bool) {
+        if let Some(int) = self.interrupts.get_mut(id) {
+            let was_active = int.active();
+            int.input = level;
+            if !int.is_edge() {
+                int.ip = int.active();
+            } else if !was_active && int.active() {
+                int.ip = true;
+            }
+        }
+    }
+
+    // The upper mnlbits of clicintctl with the lower bits filled with ones
+    pub fn level(&self, id: usize) -> AddressType {
+        let ctl = self.interrupts[id].ctl as AddressType;
+        let prio_mask = 0xff >> self.mnlbits;
+        (ctl & !prio_mask) | prio_mask
+    }
+
+    pub fn shv(&self, id: usize) -> bool {
+        self.interrupts[id].attr & INTATTR_SHV != 0
+    }
+
+    // Highest ranked pending and enabled interrupt, by level and priority (both
+    // are clicintctl) then by id
+    pub fn top(&self) -> Option<usize> {
+        self.interrupts
+            .iter()
+            .enumerate()
+            .filter(|(_, int)| int.ip && int.ie)
+            .max_by_key(|(id, int)| (int.ctl, *id))
+            .map(|(id, _)| id)
+    }
+
+    // Servicing an edge-triggered interrupt clears its pending bit
+    pub fn acknowledge(&mut self, id: usize) {
+        let int = &mut self.interrupts[id];
+        if int.is_edge() {
+            int.ip = false;
+        }
+    }
+
+    fn read_byte(&self, offset: AddressType) -> u8 {
+        let cfg = self.mnlbits;
+        let info = CLIC_NUM_INTERRUPTS as u32 | (CLIC_INTCTLBITS << 21);
+        match offset {
+            CLIC_CFG..=0x3 => (cfg >> (8 * (offset - CLIC_CFG))) as u8,
+            CLIC_INFO..=0x7 => (info >> (8 * (offset - CLIC_INFO))) as u8,
+            CLIC_INT..=0xffff if offset < CLIC_SIZE => {
+                let int = &self.interrupts[((offset - CLIC_INT) / 4) as usize];
+                match offset % 4 {
+                    0 => int.ip as u8,
+                    1 => int.ie as u8,
+                    2 => int.attr | INTATTR_MODE_M,
+                    _ => int.ctl,
+                }
+            }
+            _ => 0,
+        }
+    }
+
+    fn write_byte(&mut self, offset: AddressType, val: u8) {
+        match offset {
+            CLIC_CFG => self.mnlbits = (val as u32 & CFG_MNLBITS).min(CLIC_INTCTLBITS),
+            CLIC_INT..=0xffff if offset < CLIC_SIZE => {
+                let int = &mut self.interrupts[((offset - CLIC_INT) / 4) as usize];
+                match offset % 4 {
+                    // Only edge-triggered interrupts can be set or cleared by software
+                    0 if int.is_edge() => int.ip = val & 1 != 0,
+                    0 => (),
+                    1 => int.ie = val & 1 != 0,
+                    2 => {
+                        int.attr = val & (INTATTR_SHV | INTATTR_TRIG_EDGE | INTATTR_TRIG_NEG);
+                        if !int.is_edge() {
+                            int.ip = int.active();
+                        }
+                    }
+                    _ => int.ctl = val,
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+impl MemoryInterface for Clic {
+    // The registers are byte-accessible, wider accesses cover consecutive bytes
+    fn access_memory(&mut self, payload: &mut Payload) {
+        let offset = payload.addr - CLIC_BASE;
+        for (i, byte) in payload.data.iter_mut().enumerate() {
+            if payload.op == MemoryOperation::WRITE {
+                self.write_byte(offset + i as AddressType, *byte);
+            } else {
+                *byte = self.read_byte(offset + i as AddressType);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(clic: &mut Clic, offset: AddressType, data: &[u8]) {
+        let mut payload = Payload {
+            addr: CLIC_BASE + offset,
+            data: data.to_vec(),
+            op: MemoryOperation::WRITE,
+            is_amo: false,
+        };
+        clic.access_memory(&mut payload);
+    }
+
+    fn read(clic: &mut Clic, offset: AddressType, len: usize) -> Vec<u8> {
+        let mut payload = Payload {
+            addr: CLIC_BASE + offset,
+            data: vec![0; len],
+            op: MemoryOperation::READ,
+            is_amo: false,
+        };
+        clic.access_memory(&mut payload);
+        payload.data
+    }
+
+    #[test]
+    fn test_clic() {
+        let mut clic = Clic::new();
+        assert_eq!(vec![64, 0, 0, 1], read(&mut clic, CLIC_INFO, 4));
+        write(&mut clic, CLIC_CFG, &[0xf]);
+        assert_eq!(vec![8], read(&mut clic, CLIC_CFG, 1));
+        write(&mut clic, CLIC_CFG, &[2]);
+
+        // Level-triggered, the pending bit follows the input
+        write(&mut clic, CLIC_INT + 4 * 20, &[1, 1, 0, 0x40]);
+        assert_eq!(
+            vec![0, 1, 0xc0, 0x40],
+            read(&mut clic, CLIC_INT + 4 * 20, 4)
+        );
+        clic.set_input(20, true);
+        assert_eq!(Some(20), clic.top());
+        assert_eq!(0x7f, clic.level(20));
+        clic.acknowledge(20);
+        assert_eq!(Some(20), clic.top());
+        clic.set_input(20, false);
+        assert_eq!(None, clic.top());
+
+        // Negative edge-triggered and selectively vectored
+        write(&mut clic, CLIC_INT + 4 * 30 + 1, &[1, 0x7, 0x40]);
+        assert!(clic.shv(30));
+        clic.set_input(30, true);
+        assert_eq!(None, clic.top());
+        clic.set_input(30, false);
+        assert_eq!(Some(30), clic.top());
+        clic.acknowledge(30);
+        assert_eq!(None, clic.top());
+
+        // Same level and priority, the higher id wins
+        clic.set_input(20, true);
+        write(&mut clic, CLIC_INT + 4 * 30, &[1]);
+        assert_eq!(Some(30), clic.top());
+        write(&mut clic, CLIC_INT + 4 * 20 + 3, &[0xc0]);
+        assert_eq!(Some(20), clic.top());
+        assert_eq!(0xff, clic.level(20));
+    }
+}
@@ -0,0 +1,293 @@
+use crate::rv_core::RVCore;
+
+type AddressType = u64;
+
+// DMI addresses of the Debug Module registers
+const DM_DATA0: u32 = 0x04;
+const DM_DMCONTROL: u32 = 0x10;
+const DM_DMSTATUS: u32 = 0x11;
+const DM_ABSTRACTCS: u32 = 0x16;
+const DM_COMMAND: u32 = 0x17;
+const DM_PROGBUF0: u32 = 0x20;
+const DM_HALTSUM0: u32 = 0x40;
+
+// data0/data1 hold 64-bit registers, the program buffer ends with an implicit ebreak
+const DATA_COUNT: usize = 2;
+const PROGBUF_SIZE: usize = 8;
+
+const DMCONTROL_HALTREQ: u32 = 1 << 31;
+const DMCONTROL_RESUMEREQ: u32 = 1 << 30;
+const DMCONTROL_ACKHAVERESET: u32 = 1 << 28;
+const DMCONTROL_NDMRESET: u32 = 1 << 1;
+const DMCONTROL_DMACTIVE: u32 = 1 << 0;
+
+const DMSTATUS_IMPEBREAK: u32 = 1 << 22;
+const DMSTATUS_ALLHAVERESET: u32 = 1 << 19;
+const DMSTATUS_ANYHAVERESET: u32 = 1 << 18;
+const DMSTATUS_ALLRESUMEACK: u32 = 1 << 17;
+const DMSTATUS_ANYRESUMEACK: u32 = 1 << 16;
+const DMSTATUS_ALLRUNNING: u32 = 1 << 11;
+const DMSTATUS_ANYRUNNING: u32 = 1 << 10;
+const DMSTATUS_ALLHALTED: u32 = 1 << 9;
+const DMSTATUS_ANYHALTED: u32 = 1 << 8;
+const DMSTATUS_AUTHENTICATED: u32 = 1 << 7;
+// Debug Spec 1.0
+const DMSTATUS_VERSION: u32 = 3;
+
+const ABSTRACTCS_CMDERR_SHIFT: u32 = 8;
+const CMDERR_NONE: u32 = 0;
+const CMDERR_NOT_SUPPORTED: u32 = 2;
+const CMDERR_EXCEPTION: u32 = 3;
+const CMDERR_HALT_RESUME: u32 = 4;
+
+const COMMAND_CMDTYPE_SHIFT: u32 = 24;
+const CMDTYPE_ACCESS_REGISTER: u32 = 0;
+const COMMAND_AARSIZE_SHIFT: u32 = 20;
+const AARSIZE_32: u32 = 2;
+const AARSIZE_64: u32 = 3;
+const COMMAND_POSTEXEC: u32 = 1 << 18;
+const COMMAND_TRANSFER: u32 = 1 << 17;
+const COMMAND_WRITE: u32 = 1 << 16;
+const COMMAND_REGNO: u32 = 0xffff;
+
+// Debug Module of a single hart, hartsel is hardwired to 0. Abstract commands complete
+// at once and only Access Register is implemented, memory is reached through the
+// program buffer.
+pub struct DebugModule {
+    dmactive: bool,
+    ndmreset: bool,
+    havereset: bool,
+    resumeack: bool,
+    cmderr: u32,
+    data: [u32; DATA_COUNT],
+    progbuf: [u32; PROGBUF_SIZE],
+}
+
+impl DebugModule {
+    pub fn new() -> DebugModule {
+        DebugModule {
+            dmactive: false,
+            ndmreset: false,
+            havereset: true,
+            resumeack: false,
+            cmderr: CMDERR_NONE,
+            data: [0; DATA_COUNT],
+            progbuf: [0; PROGBUF_SIZE],
+        }
+    }
+
+    pub fn dmi_read(&mut self, core: &RVCore, addr: u32) -> u32 {
+        match addr {
+            _ if !self.dmactive => 0,
+            DM_DMCONTROL => {
+                let mut dmcontrol = DMCONTROL_DMACTIVE;
+                if self.ndmreset {
+                    dmcontrol |= DMCONTROL_NDMRESET;
+                }
+                dmcontrol
+            }
+            DM_DMSTATUS => {
+                let mut dmstatus = DMSTATUS_IMPEBREAK | DMSTATUS_AUTHENTICATED | DMSTATUS_VERSION;
+                dmstatus |= if core.halted() {
+                    DMSTATUS_ALLHALTED | DMSTATUS_ANYHALTED
+                } else {
+                    DMSTATUS_ALLRUNNING | DMSTATUS_ANYRUNNING
+                };
+                if self.resumeack {
+                    dmstatus |= DMSTATUS_ALLRESUMEACK | DMSTATUS_ANYRESUMEACK;
+                }
+                if self.havereset {
+                    dmstatus |= DMSTATUS_ALLHAVERESET | DMSTATUS_ANYHAVERESET;
+                }
+                dmstatus
+            }
+            DM_ABSTRACTCS => {
+                ((PROGBUF_SIZE as u32) << 24)
+                    | (self.cmderr << ABSTRACTCS_CMDERR_SHIFT)
+                    | DATA_COUNT as u32
+            }
+            DM_HALTSUM0 => core.halted() as u32,
+            _ => match self.register(addr) {
+                Some(reg) => *reg,
+                None => 0,
+            },
+        }
+    }
+
+    pub fn dmi_write(&mut self, core: &mut RVCore, addr: u32, val: u32) {
+        if addr == DM_DMCONTROL {
+            self.write_dmcontrol(core, val);
+            return;
+        }
+        if !self.dmactive {
+            return;
+        }
+        match addr {
+            // cmderr is write 1 to clear, commands are ignored until it is cleared
+            DM_ABSTRACTCS => self.cmderr &= !(val >> ABSTRACTCS_CMDERR_SHIFT),
+            DM_COMMAND if self.cmderr == CMDERR_NONE => {
+                self.cmderr = self.execute_command(core, val);
+            }
+            _ => {
+                if let Some(reg) = self.register(addr) {
+                    *reg = val;
+                }
+            }
+        }
+    }
+
+    // data0-1 and progbuf0-7
+    fn register(&mut self, addr: u32) -> Option<&mut u32> {
+        let data_end = DM_DATA0 + DATA_COUNT as u32;
+        let progbuf_end = DM_PROGBUF0 + PROGBUF_SIZE as u32;
+        match addr {
+            DM_DATA0..=0xf if addr < data_end => Some(&mut self.data[(addr - DM_DATA0) as usize]),
+            DM_PROGBUF0..=0x2f if addr < progbuf_end => {
+                Some(&mut self.progbuf[(addr - DM_PROGBUF0) as usize])
+            }
+            _ => None,
+        }
+    }
+
+    // Clearing dmactive resets the Debug Module, ndmreset resets the hart which then
+    // halts right away if haltreq is also set
+    fn write_dmcontrol(&mut self, core: &mut RVCore, val: u32) {
+        if val & DMCONTROL_DMACTIVE == 0 {
+            let havereset = self.havereset;
+            *self = DebugModule::new();
+            self.havereset = havereset;
+            core.set_halt_request(false);
+            return;
+        }
+        self.dmactive = true;
+        let ndmreset = val & DMCONTROL_NDMRESET != 0;
+        if ndmreset && !self.ndmreset {
+            core.reset();
+            self.havereset = true;
+        }
+        self.ndmreset = ndmreset;
+        if val & DMCONTROL_ACKHAVERESET != 0 {
+            self.havereset = false;
+        }
+        let haltreq = val & DMCONTROL_HALTREQ != 0;
+        core.set_halt_request(haltreq);
+        if val & DMCONTROL_RESUMEREQ != 0 && !haltreq {
+            if core.halted() {
+                core.resume();
+            }
+            self.resumeack = true;
+        }
+    }
+
+    // Access Register with an optional program buffer execution, returns the cmderr
+    fn execute_command(&mut self, core: &mut RVCore, command: u32) -> u32 {
+        if command >> COMMAND_CMDTYPE_SHIFT != CMDTYPE_ACCESS_REGISTER {
+            return CMDERR_NOT_SUPPORTED;
+        }
+        if !core.halted() {
+            return CMDERR_HALT_RESUME;
+        }
+        if command & COMMAND_TRANSFER != 0 {
+            let aarsize = (command >> COMMAND_AARSIZE_SHIFT) & 7;
+            if aarsize != AARSIZE_32 && aarsize != AARSIZE_64 {
+                return CMDERR_NOT_SUPPORTED;
+            }
+            let regno = command & COMMAND_REGNO;
+            if command & COMMAND_WRITE != 0 {
+                let mut val = self.data[0] as AddressType;
+                if aarsize == AARSIZE_64 {
+                    val |= (self.data[1] as AddressType) << 32;
+                }
+                if !core.write_debug_register(regno, val) {
+                    return CMDERR_EXCEPTION;
+                }
+            } else {
+                let val = match core.read_debug_register(regno) {
+                    Some(val) => val,
+                    None => return CMDERR_EXCEPTION,
+                };
+                self.data[0] = val as u32;
+                if aarsize == AARSIZE_64 {
+                    self.data[1] = (val >> 32) as u32;
+                }
+            }
+        }
+        if command & COMMAND_POSTEXEC != 0 && !core.execute_program_buffer(&self.progbuf) {
+            return CMDERR_EXCEPTION;
+        }
+        CMDERR_NONE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn access_register(regno: u32, write: bool) -> u32 {
+        let mut command = (AARSIZE_64 << COMMAND_AARSIZE_SHIFT) | COMMAND_TRANSFER | regno;
+        if write {
+            command |= COMMAND_WRITE;
+        }
+        command
+    }
+
+    fn cmderr(dm: &mut DebugModule, core: &RVCore) -> u32 {
+        (dm.dmi_read(core, DM_ABSTRACTCS) >> ABSTRACTCS_CMDERR_SHIFT) & 7
+    }
+
+    #[test]
+    fn test_debug_module() {
+        let mut core = RVCore::new();
+        let mut dm = DebugModule::new();
+        assert_eq!(0, dm.dmi_read(&core, DM_DMSTATUS));
+        dm.dmi_write(&mut core, DM_DMCONTROL, DMCONTROL_DMACTIVE);
+        let dmstatus = dm.dmi_read(&core, DM_DMSTATUS);
+        assert_eq!(3, dmstatus & 0xf);
+        assert_ne!(0, dmstatus & DMSTATUS_ALLRUNNING);
+        assert_eq!(0x0800_0002, dm.dmi_read(&core, DM_ABSTRACTCS));
+
+        // Commands need a halted hart
+        dm.dmi_write(&mut core, DM_COMMAND, access_register(0x1001, false));
+        assert_eq!(CMDERR_HALT_RESUME, cmderr(&mut dm, &core));
+        dm.dmi_write(&mut core, DM_ABSTRACTCS, 7 << 8);
+        let control = DMCONTROL_DMACTIVE | DMCONTROL_ACKHAVERESET | DMCONTROL_HALTREQ;
+        dm.dmi_write(&mut core, DM_DMCONTROL, control);
+        core.run(1);
+        let dmstatus = dm.dmi_read(&core, DM_DMSTATUS);
+        assert_ne!(0, dmstatus & DMSTATUS_ALLHALTED);
+        assert_eq!(0, dmstatus & DMSTATUS_ANYHAVERESET);
+        assert_eq!(1, dm.dmi_read(&core, DM_HALTSUM0));
+
+        // 64-bit register read and write through data0/data1
+        core.regs.write(1, 0x1234_5678_9abc_def0);
+        dm.dmi_write(&mut core, DM_COMMAND, access_register(0x1001, false));
+        assert_eq!(0x9abc_def0, dm.dmi_read(&core, DM_DATA0));
+        assert_eq!(0x1234_5678, dm.dmi_read(&core, DM_DATA0 + 1));
+        dm.dmi_write(&mut core, DM_DATA0, 0x2000);
+        dm.dmi_write(&mut core, DM_DATA0 + 1, 0);
+        dm.dmi_write(&mut core, DM_COMMAND, access_register(0x7b1, true));
+        assert_eq!(0, cmderr(&mut dm, &core));
+        dm.dmi_write(&mut core, DM_COMMAND, access_register(0x2000, false));
+        assert_eq!(CMDERR_EXCEPTION, cmderr(&mut dm, &core));
+        dm.dmi_write(&mut core, DM_ABSTRACTCS, 7 << 8);
+
+        // addi x1, x1, 1 from the program buffer, then an ecall
+        dm.dmi_write(&mut core, DM_PROGBUF0, 0x0010_8093);
+        dm.dmi_write(&mut core, DM_COMMAND, COMMAND_POSTEXEC);
+        assert_eq!(0x1234_5678_9abc_def1, core.regs.read(1));
+        dm.dmi_write(&mut core, DM_PROGBUF0 + 1, 0x0000_0073);
+        dm.dmi_write(&mut core, DM_COMMAND, COMMAND_POSTEXEC);
+        assert_eq!(CMDERR_EXCEPTION, cmderr(&mut dm, &core));
+        assert_eq!(0x1234_5678_9abc_def2, core.regs.read(1));
+
+        dm.dmi_write(
+            &mut core,
+            DM_DMCONTROL,
+            DMCONTROL_DMACTIVE | DMCONTROL_RESUMEREQ,
+        );
+        let dmstatus = dm.dmi_read(&core, DM_DMSTATUS);
+        assert_ne!(0, dmstatus & DMSTATUS_ALLRUNNING);
+        assert_ne!(0, dmstatus & DMSTATUS_ALLRESUMEACK);
+        assert_eq!(0x2000, core.pc);
+    }
+}
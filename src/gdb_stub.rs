@@ -0,0 +1,989 @@
+use crate::memory_interface::{MemoryInterface, MemoryOperation, Payload};
+use crate::rv_core::debug::{REGNO_FPR, REGNO_GPR};
+use crate::rv_core::triggers::{
+    ACTION_DEBUG_MODE, MATCH_GE, MATCH_LT, MATCH_NAPOT, MCONTROL6_ACTION_SHIFT, MCONTROL6_CHAIN,
+    MCONTROL6_EXECUTE, MCONTROL6_HIT0, MCONTROL6_LOAD, MCONTROL6_M, MCONTROL6_MATCH,
+    MCONTROL6_MATCH_SHIFT, MCONTROL6_S, MCONTROL6_STORE, MCONTROL6_U, MCONTROL6_VS, MCONTROL6_VU,
+    TDATA1_DMODE, TDATA1_TYPE_SHIFT, TYPE_DISABLED, TYPE_MCONTROL6,
+};
+use crate::rv_core::{csregs, RVCore};
+use std::cell::RefCell;
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::rc::Rc;
+use std::thread;
+use std::time::Duration;
+
+type AddressType = u64;
+
+// How long a halted hart waits for the debugger before giving the host loop a turn
+const HALTED_POLL: Duration = Duration::from_millis(10);
+const PACKET_SIZE: usize = 0x4000;
+
+// GDB register numbers of the RISC-V target description
+const REGNUM_PC: u32 = 32;
+const REGNUM_FPR: u32 = 33;
+const REGNUM_CSR: u32 = 65;
+const REGNUM_PRIV: u32 = REGNUM_CSR + 0x1000;
+
+// Debug interface numbers of the CSRs used by the stub
+const CSR_MISA: u32 = csregs::MISA as u32;
+const CSR_TSELECT: u32 = csregs::TSELECT as u32;
+const CSR_TDATA1: u32 = csregs::TDATA1 as u32;
+const CSR_TDATA2: u32 = csregs::TDATA2 as u32;
+const CSR_DCSR: u32 = csregs::DCSR as u32;
+const CSR_DPC: u32 = csregs::DPC as u32;
+
+const DCSR_EBREAK: AddressType = csregs::DCSR_EBREAKVS
+    | csregs::DCSR_EBREAKVU
+    | csregs::DCSR_EBREAKM
+    | csregs::DCSR_EBREAKS
+    | csregs::DCSR_EBREAKU;
+
+// mcontrol6 owned by the debugger: dmode, entering debug mode in every mode
+const TDATA1_DISABLED: AddressType = TYPE_DISABLED << TDATA1_TYPE_SHIFT;
+const MCONTROL6: AddressType = (TYPE_MCONTROL6 << TDATA1_TYPE_SHIFT)
+    | TDATA1_DMODE
+    | MCONTROL6_VS
+    | MCONTROL6_VU
+    | (ACTION_DEBUG_MODE << MCONTROL6_ACTION_SHIFT)
+    | MCONTROL6_M
+    | MCONTROL6_S
+    | MCONTROL6_U;
+const MCONTROL6_MATCH_NAPOT: AddressType = MATCH_NAPOT << MCONTROL6_MATCH_SHIFT;
+const MCONTROL6_MATCH_GE: AddressType = MATCH_GE << MCONTROL6_MATCH_SHIFT;
+const MCONTROL6_MATCH_LT: AddressType = MATCH_LT << MCONTROL6_MATCH_SHIFT;
+const MCONTROL6_ACCESS: AddressType = MCONTROL6_EXECUTE | MCONTROL6_STORE | MCONTROL6_LOAD;
+
+const SIGINT: u8 = 2;
+const SIGTRAP: u8 = 5;
+
+const GPR_NAMES: [&str; 32] = [
+    "zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2", "fp", "s1", "a0", "a1", "a2", "a3", "a4",
+    "a5", "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11", "t3", "t4",
+    "t5", "t6",
+];
+
+// CSRs listed in the target description when the core implements them
+const CSR_NAMES: [(&str, u32); 42] = [
+    ("fflags", 0x001),
+    ("frm", 0x002),
+    ("fcsr", 0x003),
+    ("sstatus", 0x100),
+    ("sie", 0x104),
+    ("stvec", 0x105),
+    ("scounteren", 0x106),
+    ("sscratch", 0x140),
+    ("sepc", 0x141),
+    ("scause", 0x142),
+    ("stval", 0x143),
+    ("sip", 0x144),
+    ("satp", 0x180),
+    ("mstatus", 0x300),
+    ("misa", 0x301),
+    ("medeleg", 0x302),
+    ("mideleg", 0x303),
+    ("mie", 0x304),
+    ("mtvec", 0x305),
+    ("mcounteren", 0x306),
+    ("mscratch", 0x340),
+    ("mepc", 0x341),
+    ("mcause", 0x342),
+    ("mtval", 0x343),
+    ("mip", 0x344),
+    ("tselect", 0x7a0),
+    ("tdata1", 0x7a1),
+    ("tdata2", 0x7a2),
+    ("tinfo", 0x7a4),
+    ("dcsr", 0x7b0),
+    ("dpc", 0x7b1),
+    ("dscratch0", 0x7b2),
+    ("dscratch1", 0x7b3),
+    ("mcycle", 0xb00),
+    ("minstret", 0xb02),
+    ("cycle", 0xc00),
+    ("time", 0xc01),
+    ("instret", 0xc02),
+    ("mvendorid", 0xf11),
+    ("marchid", 0xf12),
+    ("mimpid", 0xf13),
+    ("mhartid", 0xf14),
+];
+
+// GDB remote serial protocol server on localhost, one client at a time. The hart is
+// driven through debug mode: halts are debug mode entries, breakpoints are ebreaks
+// with dcsr.ebreak* set and triggers with dmode. Memory is accessed on the bus, by
+// physical address.
+pub struct GdbStub {
+    listener: TcpListener,
+    client: Option<TcpStream>,
+    mem: Rc<RefCell<dyn MemoryInterface>>,
+    // Received bytes not yet parsed as packets
+    input: Vec<u8>,
+    no_ack: bool,
+    // Resumed by c or s, the next halt is reported with a stop reply
+    running: bool,
+    interrupted: bool,
+    // The client left while the hart was running, clean up once it halts
+    detaching: bool,
+    // Original instructions under the software breakpoints
+    breakpoints: Vec<(AddressType, Vec<u8>)>,
+}
+
+impl GdbStub {
+    pub fn new(port: u16, mem: Rc<RefCell<dyn MemoryInterface>>) -> io::Result<GdbStub> {
+        let listener = TcpListener::bind(("127.0.0.1", port))?;
+        listener.set_nonblocking(true)?;
+        Ok(GdbStub {
+            listener,
+            client: None,
+            mem,
+            input: Vec::new(),
+            no_ack: false,
+            running: false,
+            interrupted: false,
+            detaching: false,
+            breakpoints: Vec::new(),
+        })
+    }
+
+    // Serves the packets received so far. The hart is halted when a client attaches
+    // and while it handles packets, when halted the call waits a little for packets.
+    pub fn tick(&mut self, core: &mut RVCore) {
+        if self.client.is_none() {
+            if self.detaching && core.halted() {
+                self.detaching = false;
+                self.cleanup(core);
+                core.set_halt_request(false);
+                core.resume();
+            }
+            match self.listener.accept() {
+                Ok((client, _)) => self.attach(core, client),
+                Err(_) => {
+                    if core.halted() {
+                        thread::sleep(HALTED_POLL);
+                    }
+                    return;
+                }
+            }
+        }
+        if !self.receive(core) {
+            self.disconnect(core);
+            return;
+        }
+        if !core.halted() {
+            return;
+        }
+        core.set_halt_request(false);
+        if self.running {
+            self.running = false;
+            let reply = self.stop_reply(core);
+            self.send_packet(&reply);
+        }
+        while let Some(packet) = self.next_packet() {
+            if !self.handle_packet(core, &packet) {
+                break;
+            }
+        }
+    }
+
+    // The simulated program ended, e.g. through tohost
+    pub fn exit(&mut self, code: u8) {
+        if self.client.is_some() {
+            self.send_packet(&format!("W{:02x}", code));
+            self.client = None;
+        }
+    }
+
+    fn attach(&mut self, core: &mut RVCore, client: TcpStream) {
+        let _ = client.set_nodelay(true);
+        let _ = client.set_read_timeout(Some(HALTED_POLL));
+        self.client = Some(client);
+        self.input.clear();
+        self.no_ack = false;
+        self.running = false;
+        self.interrupted = false;
+        self.detaching = false;
+        core.set_halt_request(true);
+    }
+
+    // Breakpoints and triggers are removed and the hart runs freely again
+    fn disconnect(&mut self, core: &mut RVCore) {
+        self.client = None;
+        self.running = false;
+        if core.halted() {
+            self.cleanup(core);
+            core.set_halt_request(false);
+            core.resume();
+        } else {
+            core.set_halt_request(true);
+            self.detaching = true;
+        }
+    }
+
+    fn cleanup(&mut self, core: &mut RVCore) {
+        for (addr, orig) in self.breakpoints.split_off(0) {
+            self.write_memory(addr, &orig);
+        }
+        for i in GdbStub::triggers(core) {
+            core.write_debug_register(CSR_TSELECT, i);
+            if core.read_debug_register(CSR_TDATA1).unwrap_or(0) & TDATA1_DMODE != 0 {
+                core.write_debug_register(CSR_TDATA1, TDATA1_DISABLED);
+            }
+        }
+        let dcsr = core.read_debug_register(CSR_DCSR).unwrap_or(0);
+        core.write_debug_register(CSR_DCSR, dcsr & !(DCSR_EBREAK | csregs::DCSR_STEP));
+    }
+
+    // false when the connection was closed. Ctrl-C, outside of packets, requests a halt.
+    fn receive(&mut self, core: &mut RVCore) -> bool {
+        let client = self.client.as_mut().unwrap();
+        let mut buf = [0; 4096];
+        let len = match client
+            .set_nonblocking(!core.halted())
+            .and_then(|_| client.read(&mut buf))
+        {
+            Ok(0) => return false,
+            Ok(len) => len,
+            Err(e) if e.kind() == ErrorKind::WouldBlock || e.kind() == ErrorKind::TimedOut => {
+                return true;
+            }
+            Err(_) => return false,
+        };
+        for &byte in &buf[..len] {
+            if self.input.is_empty() && byte == 0x03 {
+                if !core.halted() {
+                    core.set_halt_request(true);
+                    self.interrupted = true;
+                }
+            } else if !self.input.is_empty() || byte == b'$' {
+                self.input.push(byte);
+            }
+        }
+        true
+    }
+
+    // $<data>#<checksum>, packets with a wrong checksum are nacked
+    fn next_packet(&mut self) -> Option<Vec<u8>> {
+        loop {
+            let end = self.input.iter().position(|&byte| byte == b'#')?;
+            if self.input.len() < end + 3 {
+                return None;
+            }
+            let packet: Vec<u8> = self.input.drain(..end + 3).collect();
+            let data = packet[1..end].to_vec();
+            let checksum = std::str::from_utf8(&packet[end + 1..])
+                .ok()
+                .and_then(|checksum| u8::from_str_radix(checksum, 16).ok());
+            let valid = checksum == Some(GdbStub::checksum(&data));
+            if !self.no_ack {
+                self.send_raw(if valid { b"+" } else { b"-" });
+            }
+            if valid {
+                return Some(data);
+            }
+        }
+    }
+
+    fn checksum(data: &[u8]) -> u8 {
+        data.iter().fold(0, |sum, byte| sum.wrapping_add(*byte))
+    }
+
+    fn send_packet(&mut self, data: &str) {
+        let packet = format!("${}#{:02x}", data, GdbStub::checksum(data.as_bytes()));
+        self.send_raw(packet.as_bytes());
+    }
+
+    fn send_raw(&mut self, data: &[u8]) {
+        if let Some(client) = self.client.as_mut() {
+            let sent = client
+                .set_nonblocking(false)
+                .and_then(|_| client.write_all(data));
+            if sent.is_err() {
+                self.client = None;
+            }
+        }
+    }
+
+    // false when the hart was resumed or the client left, the remaining packets wait
+    fn handle_packet(&mut self, core: &mut RVCore, packet: &[u8]) -> bool {
+        let (command, args) = packet.split_at(packet.len().min(1));
+        let command = String::from_utf8_lossy(command);
+        let args = String::from_utf8_lossy(args);
+        let (command, args) = (command.as_ref(), args.as_ref());
+        let reply = match command {
+            "?" => self.stop_reply(core),
+            "g" => self.read_registers(core),
+            "G" => self.write_registers(core, args),
+            "p" => u32::from_str_radix(args, 16)
+                .ok()
+                .and_then(|regnum| self.read_register(core, regnum))
+                .unwrap_or_else(|| "E01".to_string()),
+            "P" => self.write_register(core, args),
+            "m" => self.read_memory_packet(args),
+            "M" => self.write_memory_packet(args),
+            "c" | "s" => {
+                if let Ok(addr) = AddressType::from_str_radix(args, 16) {
+                    core.write_debug_register(CSR_DPC, addr);
+                }
+                self.resume(core, command == "s");
+                return false;
+            }
+            "Z" | "z" => self.breakpoint_packet(core, command == "Z", args),
+            "D" => {
+                self.send_packet("OK");
+                self.disconnect(core);
+                return false;
+            }
+            "k" => {
+                self.disconnect(core);
+                return false;
+            }
+            "q" => self.query(core, args),
+            "Q" if args == "StartNoAckMode" => {
+                self.send_packet("OK");
+                self.no_ack = true;
+                return true;
+            }
+            "H" | "T" => "OK".to_string(),
+            _ => String::new(),
+        };
+        self.send_packet(&reply);
+        self.client.is_some()
+    }
+
+    fn query(&mut self, core: &RVCore, args: &str) -> String {
+        if args.starts_with("Supported") {
+            format!(
+                "PacketSize={:x};qXfer:features:read+;swbreak+;hwbreak+;QStartNoAckMode+",
+                PACKET_SIZE
+            )
+        } else if let Some(annex) = args.strip_prefix("Xfer:features:read:target.xml:") {
+            let xml = self.target_xml(core);
+            let mut range = annex.split(',');
+            let offset = range
+                .next()
+                .and_then(|val| usize::from_str_radix(val, 16).ok());
+            let length = range
+                .next()
+                .and_then(|val| usize::from_str_radix(val, 16).ok());
+            match (offset, length) {
+                (Some(offset), Some(length)) => {
+                    let start = offset.min(xml.len());
+                    let end = offset.saturating_add(length).min(xml.len());
+                    let prefix = if end == xml.len() { "l" } else { "m" };
+                    format!("{}{}", prefix, &xml[start..end])
+                }
+                _ => "E01".to_string(),
+            }
+        } else {
+            match args {
+                "Attached" => "1".to_string(),
+                "C" => "QC1".to_string(),
+                "fThreadInfo" => "m1".to_string(),
+                "sThreadInfo" => "l".to_string(),
+                _ => String::new(),
+            }
+        }
+    }
+
+    // The reason of the last halt, from dcsr.cause and the hit bits of the triggers
+    fn stop_reply(&mut self, core: &mut RVCore) -> String {
+        let dcsr = core.read_debug_register(CSR_DCSR).unwrap_or(0);
+        match (dcsr & csregs::DCSR_CAUSE) >> csregs::DCSR_CAUSE_SHIFT {
+            csregs::DCSR_CAUSE_EBREAK => format!("T{:02x}swbreak:;", SIGTRAP),
+            csregs::DCSR_CAUSE_TRIGGER => format!("T{:02x}{}", SIGTRAP, self.trigger_hit(core)),
+            _ if self.interrupted => format!("T{:02x}", SIGINT),
+            _ => format!("T{:02x}", SIGTRAP),
+        }
+    }
+
+    // A chain is reported by its first trigger, holding the start of the range
+    fn trigger_hit(&mut self, core: &mut RVCore) -> String {
+        let mut reason = String::new();
+        let mut chained = false;
+        for i in GdbStub::triggers(core) {
+            core.write_debug_register(CSR_TSELECT, i);
+            let tdata1 = core.read_debug_register(CSR_TDATA1).unwrap_or(0);
+            let follows_chain = chained;
+            chained = tdata1 & MCONTROL6_CHAIN != 0;
+            if tdata1 & TDATA1_DMODE == 0 || tdata1 & MCONTROL6_HIT0 == 0 {
+                continue;
+            }
+            core.write_debug_register(CSR_TDATA1, tdata1 & !MCONTROL6_HIT0);
+            if follows_chain {
+                continue;
+            }
+            let mut addr = core.read_debug_register(CSR_TDATA2).unwrap_or(0);
+            if tdata1 & MCONTROL6_MATCH == MCONTROL6_MATCH_NAPOT {
+                addr &= addr.wrapping_add(1);
+            }
+            reason = match tdata1 & MCONTROL6_ACCESS {
+                MCONTROL6_EXECUTE => "hwbreak:;".to_string(),
+                MCONTROL6_STORE => format!("watch:{:x};", addr),
+                MCONTROL6_LOAD => format!("rwatch:{:x};", addr),
+                _ => format!("awatch:{:x};", addr),
+            };
+        }
+        reason
+    }
+
+    // Debug mode keeps the dcsr.ebreak* bits so software breakpoints halt the hart
+    fn resume(&mut self, core: &mut RVCore, step: bool) {
+        let mut dcsr = core.read_debug_register(CSR_DCSR).unwrap_or(0) | DCSR_EBREAK;
+        if step {
+            dcsr |= csregs::DCSR_STEP;
+        } else {
+            dcsr &= !csregs::DCSR_STEP;
+        }
+        core.write_debug_register(CSR_DCSR, dcsr);
+        core.resume();
+        self.running = true;
+        self.interrupted = false;
+    }
+
+    // XLEN and FLEN come from misa
+    fn flen(core: &RVCore) -> Option<usize> {
+        let misa = core.read_debug_register(CSR_MISA).unwrap_or(0);
+        if misa & csregs::MISA_D != 0 {
+            Some(8)
+        } else if misa & csregs::MISA_F != 0 {
+            Some(4)
+        } else {
+            None
+        }
+    }
+
+    fn target_xml(&self, core: &RVCore) -> String {
+        let mut xml = String::from(
+            "<?xml version=\"1.0\"?><!DOCTYPE target SYSTEM \"gdb-target.dtd\"><target version=\"1.0\">\
+             <architecture>riscv:rv64</architecture><feature name=\"org.gnu.gdb.riscv.cpu\">",
+        );
+        for (i, name) in GPR_NAMES.iter().enumerate() {
+            let reg_type = match *name {
+                "ra" => "code_ptr",
+                "sp" | "gp" | "tp" | "fp" => "data_ptr",
+                _ => "int",
+            };
+            xml += &format!(
+                "<reg name=\"{}\" bitsize=\"64\" type=\"{}\" regnum=\"{}\"/>",
+                name, reg_type, i
+            );
+        }
+        xml += &format!(
+            "<reg name=\"pc\" bitsize=\"64\" type=\"code_ptr\" regnum=\"{}\"/></feature>",
+            REGNUM_PC
+        );
+        if let Some(flen) = GdbStub::flen(core) {
+            let reg_type = if flen == 8 {
+                "ieee_double"
+            } else {
+                "ieee_single"
+            };
+            xml += "<feature name=\"org.gnu.gdb.riscv.fpu\">";
+            for i in 0..32 {
+                xml += &format!(
+                    "<reg name=\"f{}\" bitsize=\"{}\" type=\"{}\" regnum=\"{}\"/>",
+                    i,
+                    flen * 8,
+                    reg_type,
+                    REGNUM_FPR + i
+                );
+            }
+            xml += "</feature>";
+        }
+        xml += "<feature name=\"org.gnu.gdb.riscv.csr\">";
+        for (name, csr) in CSR_NAMES.iter() {
+            if core.read_debug_register(*csr).is_some() {
+                xml += &format!(
+                    "<reg name=\"{}\" bitsize=\"64\" regnum=\"{}\" group=\"csr\"/>",
+                    name,
+                    REGNUM_CSR + csr
+                );
+            }
+        }
+        xml += &format!(
+            "</feature><feature name=\"org.gnu.gdb.riscv.virtual\">\
+             <reg name=\"priv\" bitsize=\"8\" regnum=\"{}\"/></feature></target>",
+            REGNUM_PRIV
+        );
+        xml
+    }
+
+    // Value and size in bytes of a GDB register, the pc is dpc and priv is dcsr.prv
+    fn register(core: &RVCore, regnum: u32) -> Option<(AddressType, usize)> {
+        match regnum {
+            0..=31 => Some((core.read_debug_register(REGNO_GPR + regnum)?, 8)),
+            REGNUM_PC => Some((core.read_debug_register(CSR_DPC)?, 8)),
+            REGNUM_FPR..=64 => {
+                let flen = GdbStub::flen(core)?;
+                let val = core.read_debug_register(REGNO_FPR + regnum - REGNUM_FPR)?;
+                Some((val, flen))
+            }
+            REGNUM_PRIV => Some((core.read_debug_register(CSR_DCSR)? & csregs::DCSR_PRV, 1)),
+            _ if regnum > REGNUM_PRIV => None,
+            _ => Some((core.read_debug_register(regnum - REGNUM_CSR)?, 8)),
+        }
+    }
+
+    fn set_register(core: &mut RVCore, regnum: u32, val: AddressType) -> bool {
+        match regnum {
+            0..=31 => core.write_debug_register(REGNO_GPR + regnum, val),
+            REGNUM_PC => core.write_debug_register(CSR_DPC, val),
+            REGNUM_FPR..=64 => {
+                // Single precision values are NaN-boxed
+                let val = match GdbStub::flen(core) {
+                    Some(4) => val | 0xffff_ffff_0000_0000,
+                    Some(_) => val,
+                    None => return false,
+                };
+                core.write_debug_register(REGNO_FPR + regnum - REGNUM_FPR, val)
+            }
+            REGNUM_PRIV => match core.read_debug_register(CSR_DCSR) {
+                Some(dcsr) => core.write_debug_register(CSR_DCSR, (dcsr & !csregs::DCSR_PRV) | val),
+                None => false,
+            },
+            _ if regnum > REGNUM_PRIV => false,
+            _ => core.write_debug_register(regnum - REGNUM_CSR, val),
+        }
+    }
+
+    fn read_register(&self, core: &RVCore, regnum: u32) -> Option<String> {
+        let (val, size) = GdbStub::register(core, regnum)?;
+        Some(GdbStub::to_hex(&val.to_le_bytes()[..size]))
+    }
+
+    fn write_register(&mut self, core: &mut RVCore, args: &str) -> String {
+        let mut parts = args.split('=');
+        let regnum = parts
+            .next()
+            .and_then(|val| u32::from_str_radix(val, 16).ok());
+        let val = parts.next().and_then(GdbStub::from_hex);
+        match (regnum, val) {
+            (Some(regnum), Some(bytes)) if bytes.len() <= 8 => {
+                let val = GdbStub::le_value(&bytes);
+                if GdbStub::set_register(core, regnum, val) {
+                    "OK".to_string()
+                } else {
+                    "E01".to_string()
+                }
+            }
+            _ => "E01".to_string(),
+        }
+    }
+
+    // x0-x31 and pc, GDB reads the other registers one by one
+    fn read_registers(&self, core: &RVCore) -> String {
+        (0..=REGNUM_PC)
+            .filter_map(|regnum| self.read_register(core, regnum))
+            .collect()
+    }
+
+    fn write_registers(&mut self, core: &mut RVCore, args: &str) -> String {
+        let bytes = match GdbStub::from_hex(args) {
+            Some(bytes) => bytes,
+            None => return "E01".to_string(),
+        };
+        for (regnum, val) in bytes
+            .chunks_exact(8)
+            .take(REGNUM_PC as usize + 1)
+            .enumerate()
+        {
+            GdbStub::set_register(core, regnum as u32, GdbStub::le_value(val));
+        }
+        "OK".to_string()
+    }
+
+    fn read_memory_packet(&mut self, args: &str) -> String {
+        match GdbStub::addr_length(args) {
+            Some((addr, length)) => {
+                let length = length.min(PACKET_SIZE / 2);
+                GdbStub::to_hex(&self.read_memory(addr, length))
+            }
+            None => "E01".to_string(),
+        }
+    }
+
+    fn write_memory_packet(&mut self, args: &str) -> String {
+        let mut parts = args.split(':');
+        let range = parts.next().and_then(GdbStub::addr_length);
+        let data = parts.next().and_then(GdbStub::from_hex);
+        match (range, data) {
+            (Some((addr, length)), Some(data)) if data.len() == length => {
+                self.write_memory(addr, &data);
+                "OK".to_string()
+            }
+            _ => "E01".to_string(),
+        }
+    }
+
+    fn read_memory(&mut self, addr: AddressType, length: usize) -> Vec<u8> {
+        let mut payload = Payload {
+            addr,
+            data: vec![0; length],
+            op: MemoryOperation::READ,
+            is_amo: false,
+        };
+        self.mem.borrow_mut().access_memory(&mut payload);
+        payload.data
+    }
+
+    fn write_memory(&mut self, addr: AddressType, data: &[u8]) {
+        let mut payload = Payload {
+            addr,
+            data: data.to_vec(),
+            op: MemoryOperation::WRITE,
+            is_amo: false,
+        };
+        self.mem.borrow_mut().access_memory(&mut payload);
+    }
+
+    // Z0 software breakpoints are ebreaks (c.ebreak for kind 2), Z1-Z4 use triggers
+    fn breakpoint_packet(&mut self, core: &mut RVCore, insert: bool, args: &str) -> String {
+        let mut parts = args.split(',');
+        let z_type = parts.next();
+        let addr = parts
+            .next()
+            .and_then(|val| AddressType::from_str_radix(val, 16).ok());
+        let kind = parts
+            .next()
+            .and_then(|val| AddressType::from_str_radix(val, 16).ok());
+        let (addr, kind) = match (addr, kind) {
+            (Some(addr), Some(kind)) => (addr, kind),
+            _ => return "E01".to_string(),
+        };
+        let access = match z_type {
+            Some("0") => {
+                if addr.checked_add(kind.saturating_sub(1)).is_none() {
+                    return "E01".to_string();
+                }
+                if insert {
+                    self.insert_breakpoint(addr, kind);
+                } else {
+                    self.remove_breakpoint(addr);
+                }
+                return "OK".to_string();
+            }
+            Some("1") => MCONTROL6_EXECUTE,
+            Some("2") => MCONTROL6_STORE,
+            Some("3") => MCONTROL6_LOAD,
+            Some("4") => MCONTROL6_LOAD | MCONTROL6_STORE,
+            _ => return String::new(),
+        };
+        if GdbStub::triggers(core).is_empty() {
+            return String::new();
+        }
+        // Execute triggers match the address of the instruction
+        let length = if access == MCONTROL6_EXECUTE { 1 } else { kind };
+        let done = if insert {
+            self.insert_trigger(core, access, addr, length)
+        } else {
+            self.remove_trigger(core, access, addr, length)
+        };
+        if done {
+            "OK".to_string()
+        } else {
+            "E01".to_string()
+        }
+    }
+
+    fn insert_breakpoint(&mut self, addr: AddressType, kind: AddressType) {
+        if self.breakpoints.iter().any(|(bp_addr, _)| *bp_addr == addr) {
+            return;
+        }
+        let ebreak = if kind == 2 {
+            0x9002u16.to_le_bytes().to_vec()
+        } else {
+            0x0010_0073u32.to_le_bytes().to_vec()
+        };
+        let orig = self.read_memory(addr, ebreak.len());
+        self.write_memory(addr, &ebreak);
+        self.breakpoints.push((addr, orig));
+    }
+
+    fn remove_breakpoint(&mut self, addr: AddressType) {
+        if let Some(i) = self
+            .breakpoints
+            .iter()
+            .position(|(bp_addr, _)| *bp_addr == addr)
+        {
+            let (addr, orig) = self.breakpoints.remove(i);
+            self.write_memory(addr, &orig);
+        }
+    }
+
+    // Indexes of the triggers, found by writing tselect as debuggers do
+    fn triggers(core: &mut RVCore) -> Vec<AddressType> {
+        let mut triggers = Vec::new();
+        let mut i = 0;
+        while core.write_debug_register(CSR_TSELECT, i)
+            && core.read_debug_register(CSR_TSELECT) == Some(i)
+        {
+            triggers.push(i);
+            i += 1;
+        }
+        triggers
+    }
+
+    // tdata2 and the match bits of the consecutive triggers covering a range: an exact
+    // match for a single byte, NAPOT for an aligned power of two and otherwise a ge/lt
+    // chain. A range reaching the end of the address space only needs the ge trigger.
+    fn trigger_match(addr: AddressType, length: AddressType) -> Vec<(AddressType, AddressType)> {
+        if length <= 1 {
+            vec![(addr, 0)]
+        } else if length.is_power_of_two() && addr & (length - 1) == 0 {
+            vec![(addr | (length / 2 - 1), MCONTROL6_MATCH_NAPOT)]
+        } else {
+            match addr.checked_add(length) {
+                Some(end) => vec![
+                    (addr, MCONTROL6_MATCH_GE | MCONTROL6_CHAIN),
+                    (end, MCONTROL6_MATCH_LT),
+                ],
+                None => vec![(addr, MCONTROL6_MATCH_GE)],
+            }
+        }
+    }
+
+    fn insert_trigger(
+        &mut self,
+        core: &mut RVCore,
+        access: AddressType,
+        addr: AddressType,
+        length: AddressType,
+    ) -> bool {
+        let matches = GdbStub::trigger_match(addr, length);
+        let triggers = GdbStub::triggers(core);
+        for window in triggers.windows(matches.len()) {
+            let free = window.iter().all(|&i| {
+                core.write_debug_register(CSR_TSELECT, i);
+                core.read_debug_register(CSR_TDATA1) == Some(TDATA1_DISABLED)
+            });
+            if !free {
+                continue;
+            }
+            let mut written = true;
+            for (&i, &(tdata2, match_bits)) in window.iter().zip(&matches) {
+                let tdata1 = MCONTROL6 | match_bits | access;
+                core.write_debug_register(CSR_TSELECT, i);
+                core.write_debug_register(CSR_TDATA2, tdata2);
+                core.write_debug_register(CSR_TDATA1, tdata1);
+                written &= GdbStub::trigger_set(core, tdata1);
+            }
+            if written {
+                return true;
+            }
+            for &i in window {
+                core.write_debug_register(CSR_TSELECT, i);
+                core.write_debug_register(CSR_TDATA1, TDATA1_DISABLED);
+            }
+        }
+        false
+    }
+
+    // The selected trigger was set by the stub as tdata1, the mode bits depend on the
+    // extensions
+    fn trigger_set(core: &mut RVCore, tdata1: AddressType) -> bool {
+        let owned = TDATA1_DMODE | MCONTROL6_MATCH | MCONTROL6_CHAIN | MCONTROL6_ACCESS;
+        core.read_debug_register(CSR_TDATA1).unwrap_or(0) & owned == tdata1 & owned
+    }
+
+    fn remove_trigger(
+        &mut self,
+        core: &mut RVCore,
+        access: AddressType,
+        addr: AddressType,
+        length: AddressType,
+    ) -> bool {
+        let matches = GdbStub::trigger_match(addr, length);
+        let triggers = GdbStub::triggers(core);
+        for window in triggers.windows(matches.len()) {
+            let found = window
+                .iter()
+                .zip(&matches)
+                .all(|(&i, &(tdata2, match_bits))| {
+                    core.write_debug_register(CSR_TSELECT, i);
+                    GdbStub::trigger_set(core, MCONTROL6 | match_bits | access)
+                        && core.read_debug_register(CSR_TDATA2) == Some(tdata2)
+                });
+            if found {
+                for &i in window {
+                    core.write_debug_register(CSR_TSELECT, i);
+                    core.write_debug_register(CSR_TDATA1, TDATA1_DISABLED);
+                }
+                return true;
+            }
+        }
+        false
+    }
+
+    // Ranges wrapping around the end of the address space are rejected
+    fn addr_length(args: &str) -> Option<(AddressType, usize)> {
+        let mut parts = args.split(',');
+        let addr = AddressType::from_str_radix(parts.next()?, 16).ok()?;
+        let length = usize::from_str_radix(parts.next()?, 16).ok()?;
+        addr.checked_add((length as AddressType).saturating_sub(1))?;
+        Some((addr, length))
+    }
+
+    fn to_hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    fn from_hex(hex: &str) -> Option<Vec<u8>> {
+        if hex.len() & 1 != 0 {
+            return None;
+        }
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+            .collect()
+    }
+
+    fn le_value(bytes: &[u8]) -> AddressType {
+        bytes
+            .iter()
+            .rev()
+            .fold(0, |val, byte| (val << 8) | *byte as AddressType)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory_model::MemoryModel;
+
+    #[test]
+    fn test_gdb_stub() {
+        let mem = Rc::new(RefCell::new(MemoryModel::new()));
+        // addi x5, x5, 1; sd x5, 0x100(x0); j -8
+        mem.borrow_mut().write_word(0x1000, 0x0012_8293);
+        mem.borrow_mut().write_word(0x1004, 0x1050_3023);
+        mem.borrow_mut().write_word(0x1008, 0xff9f_f06f);
+        let mut core = RVCore::new();
+        core.set_isa("rv64gc_sdtrig").unwrap();
+        core.enable_debug_mode();
+        core.bind_mem(mem.clone());
+        core.pc = 0x1000;
+        let mut stub = GdbStub::new(0, mem.clone()).unwrap();
+        core.set_halt_request(true);
+        core.run(1);
+        core.set_halt_request(false);
+        assert!(core.halted());
+        assert_eq!("T05", stub.stop_reply(&mut core));
+        // Unknown commands, non-ASCII ones too, get the empty reply
+        stub.handle_packet(&mut core, b"\xffab");
+        assert_eq!(
+            "l",
+            stub.query(&core, "Xfer:features:read:target.xml:ffffffffffffffff,1")
+        );
+
+        let xml = stub.target_xml(&core);
+        assert!(xml.contains("<reg name=\"pc\" bitsize=\"64\" type=\"code_ptr\" regnum=\"32\"/>"));
+        assert!(
+            xml.contains("<reg name=\"f31\" bitsize=\"64\" type=\"ieee_double\" regnum=\"64\"/>")
+        );
+        assert!(xml.contains("<reg name=\"dpc\" bitsize=\"64\" regnum=\"2034\" group=\"csr\"/>"));
+        assert_eq!(33 * 16, stub.read_registers(&core).len());
+        assert_eq!(
+            Some("0010000000000000".to_string()),
+            stub.read_register(&core, 32)
+        );
+        assert_eq!("OK", stub.write_register(&mut core, "5=2a00000000000000"));
+        assert_eq!(
+            Some("2a00000000000000".to_string()),
+            stub.read_register(&core, 5)
+        );
+        assert_eq!(
+            Some("03".to_string()),
+            stub.read_register(&core, REGNUM_PRIV)
+        );
+        assert_eq!(None, stub.read_register(&core, REGNUM_PRIV + 1));
+
+        // Software breakpoint on the store
+        assert_eq!("OK", stub.breakpoint_packet(&mut core, true, "0,1004,4"));
+        assert_eq!("73001000", stub.read_memory_packet("1004,4"));
+        stub.resume(&mut core, false);
+        core.run(10);
+        assert!(core.halted());
+        assert_eq!("T05swbreak:;", stub.stop_reply(&mut core));
+        assert_eq!(
+            Some("0410000000000000".to_string()),
+            stub.read_register(&core, 32)
+        );
+        assert_eq!("OK", stub.breakpoint_packet(&mut core, false, "0,1004,4"));
+        assert_eq!("23305010", stub.read_memory_packet("1004,4"));
+
+        // Single step
+        stub.resume(&mut core, true);
+        core.run(10);
+        assert!(core.halted());
+        assert_eq!(
+            Some("0810000000000000".to_string()),
+            stub.read_register(&core, 32)
+        );
+        assert_eq!("2b00000000000000", stub.read_memory_packet("100,8"));
+
+        // Write watchpoint halts before the store
+        assert_eq!("OK", stub.breakpoint_packet(&mut core, true, "2,100,8"));
+        stub.resume(&mut core, false);
+        core.run(10);
+        assert!(core.halted());
+        assert_eq!("T05watch:100;", stub.stop_reply(&mut core));
+        assert_eq!(
+            Some("0410000000000000".to_string()),
+            stub.read_register(&core, 32)
+        );
+        assert_eq!("OK", stub.breakpoint_packet(&mut core, false, "2,100,8"));
+        assert_eq!("E01", stub.breakpoint_packet(&mut core, false, "2,100,8"));
+
+        // Unaligned, non power of two ranges are a ge/lt chain
+        assert_eq!("OK", stub.breakpoint_packet(&mut core, true, "2,109,3"));
+        assert_eq!("OK", stub.breakpoint_packet(&mut core, true, "2,fd,5"));
+        stub.resume(&mut core, false);
+        core.run(10);
+        assert!(core.halted());
+        assert_eq!("T05watch:fd;", stub.stop_reply(&mut core));
+        assert_eq!("OK", stub.breakpoint_packet(&mut core, false, "2,fd,5"));
+        stub.resume(&mut core, false);
+        core.run(10);
+        assert!(!core.halted());
+        core.set_halt_request(true);
+        core.run(1);
+        core.set_halt_request(false);
+        assert_eq!("OK", stub.breakpoint_packet(&mut core, false, "2,109,3"));
+        assert_eq!("E01", stub.breakpoint_packet(&mut core, false, "2,109,3"));
+
+        assert_eq!("OK", stub.write_memory_packet("200,2:5aa5"));
+        assert_eq!("5aa5", stub.read_memory_packet("200,2"));
+        assert_eq!("E01", stub.write_memory_packet("200,2:5a"));
+        assert_eq!("E01", stub.read_memory_packet("ffffffffffffff00,200"));
+        assert_eq!("E01", stub.write_memory_packet("ffffffffffffffff,2:5aa5"));
+        assert_eq!("00", stub.read_memory_packet("ffffffffffffffff,1"));
+        assert_eq!(
+            "E01",
+            stub.breakpoint_packet(&mut core, true, "0,fffffffffffffffe,4")
+        );
+    }
+
+    #[test]
+    fn test_gdb_packet() {
+        assert_eq!(0x37, GdbStub::checksum(b"qSupported"));
+        assert_eq!(Some(vec![0x12, 0xab]), GdbStub::from_hex("12ab"));
+        assert_eq!(None, GdbStub::from_hex("12a"));
+        assert_eq!("12ab", GdbStub::to_hex(&[0x12, 0xab]));
+        assert_eq!(0xab12, GdbStub::le_value(&[0x12, 0xab]));
+        assert_eq!(
+            vec![(0x1003, MCONTROL6_MATCH_NAPOT)],
+            GdbStub::trigger_match(0x1000, 8)
+        );
+        assert_eq!(
+            vec![
+                (0x1004, MCONTROL6_MATCH_GE | MCONTROL6_CHAIN),
+                (0x100c, MCONTROL6_MATCH_LT)
+            ],
+            GdbStub::trigger_match(0x1004, 8)
+        );
+        assert_eq!(vec![(0x1004, 0)], GdbStub::trigger_match(0x1004, 1));
+        assert_eq!(Some((0x8000_0000, 4)), GdbStub::addr_length("80000000,4"));
+    }
+}
@@ -0,0 +1,234 @@
+use crate::debug_module::DebugModule;
+use crate::rv_core::RVCore;
+
+const IR_LENGTH: u32 = 5;
+const IR_IDCODE: u32 = 0x01;
+const IR_DTMCS: u32 = 0x10;
+const IR_DMI: u32 = 0x11;
+// Same IDCODE as Spike, existing OpenOCD configurations work unchanged
+const IDCODE: u64 = 0x10e3_1913;
+const DMI_ABITS: u32 = 7;
+// version 1 (0.13 and 1.0), abits and a single idle cycle between DMI accesses
+const DTMCS: u64 = 1 | ((DMI_ABITS as u64) << 4) | (1 << 12);
+const DMI_OP_READ: u64 = 1;
+const DMI_OP_WRITE: u64 = 2;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum TapState {
+    TestLogicReset,
+    RunTestIdle,
+    SelectDrScan,
+    CaptureDr,
+    ShiftDr,
+    Exit1Dr,
+    PauseDr,
+    Exit2Dr,
+    UpdateDr,
+    SelectIrScan,
+    CaptureIr,
+    ShiftIr,
+    Exit1Ir,
+    PauseIr,
+    Exit2Ir,
+    UpdateIr,
+}
+
+impl TapState {
+    fn next(self, tms: bool) -> TapState {
+        use TapState::*;
+        match (self, tms) {
+            (TestLogicReset, false) => RunTestIdle,
+            (TestLogicReset, true) => TestLogicReset,
+            (RunTestIdle, false) => RunTestIdle,
+            (RunTestIdle, true) => SelectDrScan,
+            (SelectDrScan, false) => CaptureDr,
+            (SelectDrScan, true) => SelectIrScan,
+            (CaptureDr, false) => ShiftDr,
+            (CaptureDr, true) => Exit1Dr,
+            (ShiftDr, false) => ShiftDr,
+            (ShiftDr, true) => Exit1Dr,
+            (Exit1Dr, false) => PauseDr,
+            (Exit1Dr, true) => UpdateDr,
+            (PauseDr, false) => PauseDr,
+            (PauseDr, true) => Exit2Dr,
+            (Exit2Dr, false) => ShiftDr,
+            (Exit2Dr, true) => UpdateDr,
+            (UpdateDr, false) => RunTestIdle,
+            (UpdateDr, true) => SelectDrScan,
+            (SelectIrScan, false) => CaptureIr,
+            (SelectIrScan, true) => TestLogicReset,
+            (CaptureIr, false) => ShiftIr,
+            (CaptureIr, true) => Exit1Ir,
+            (ShiftIr, false) => ShiftIr,
+            (ShiftIr, true) => Exit1Ir,
+            (Exit1Ir, false) => PauseIr,
+            (Exit1Ir, true) => UpdateIr,
+            (PauseIr, false) => PauseIr,
+            (PauseIr, true) => Exit2Ir,
+            (Exit2Ir, false) => ShiftIr,
+            (Exit2Ir, true) => UpdateIr,
+            (UpdateIr, false) => RunTestIdle,
+            (UpdateIr, true) => SelectDrScan,
+        }
+    }
+}
+
+// JTAG Debug Transport Module in front of the Debug Module. DMI accesses complete
+// during Update-DR, so the status shifted out is always success.
+pub struct JtagDtm {
+    dm: DebugModule,
+    state: TapState,
+    tck: bool,
+    tdo: bool,
+    ir: u32,
+    ir_shift: u32,
+    dr: u64,
+    dr_length: u32,
+    // Shifted out by the next DMI scan: address and data of the last operation
+    dmi: u64,
+}
+
+impl JtagDtm {
+    pub fn new() -> JtagDtm {
+        JtagDtm {
+            dm: DebugModule::new(),
+            state: TapState::TestLogicReset,
+            tck: false,
+            tdo: false,
+            ir: IR_IDCODE,
+            ir_shift: 0,
+            dr: 0,
+            dr_length: 1,
+            dmi: 0,
+        }
+    }
+
+    // TRST
+    pub fn reset(&mut self) {
+        self.state = TapState::TestLogicReset;
+        self.ir = IR_IDCODE;
+    }
+
+    pub fn tdo(&self) -> bool {
+        self.tdo
+    }
+
+    // TMS and TDI are sampled on the rising edge of TCK, TDO changes on the falling one
+    pub fn set_pins(&mut self, core: &mut RVCore, tck: bool, tms: bool, tdi: bool) {
+        if !self.tck && tck {
+            match self.state {
+                TapState::ShiftDr => {
+                    self.dr = (self.dr >> 1) | ((tdi as u64) << (self.dr_length - 1));
+                }
+                TapState::ShiftIr => {
+                    self.ir_shift = (self.ir_shift >> 1) | ((tdi as u32) << (IR_LENGTH - 1));
+                }
+                _ => (),
+            }
+            self.state = self.state.next(tms);
+            match self.state {
+                TapState::TestLogicReset => self.ir = IR_IDCODE,
+                TapState::CaptureDr => self.capture_dr(),
+                TapState::UpdateDr => self.update_dr(core),
+                TapState::CaptureIr => self.ir_shift = 1,
+                TapState::UpdateIr => self.ir = self.ir_shift,
+                _ => (),
+            }
+        } else if self.tck && !tck {
+            match self.state {
+                TapState::ShiftDr => self.tdo = self.dr & 1 != 0,
+                TapState::ShiftIr => self.tdo = self.ir_shift & 1 != 0,
+                _ => (),
+            }
+        }
+        self.tck = tck;
+    }
+
+    // Unknown instructions select BYPASS
+    fn capture_dr(&mut self) {
+        let (dr, dr_length) = match self.ir {
+            IR_IDCODE => (IDCODE, 32),
+            IR_DTMCS => (DTMCS, 32),
+            IR_DMI => (self.dmi, DMI_ABITS + 34),
+            _ => (0, 1),
+        };
+        self.dr = dr;
+        self.dr_length = dr_length;
+    }
+
+    // DMI scans are address, data and op, a nop keeps the result of the previous access
+    fn update_dr(&mut self, core: &mut RVCore) {
+        if self.ir != IR_DMI {
+            return;
+        }
+        let op = self.dr & 3;
+        let data = (self.dr >> 2) as u32;
+        let addr = (self.dr >> 34) as u32 & ((1 << DMI_ABITS) - 1);
+        let result = match op {
+            DMI_OP_READ => self.dm.dmi_read(core, addr),
+            DMI_OP_WRITE => {
+                self.dm.dmi_write(core, addr, data);
+                data
+            }
+            _ => return,
+        };
+        self.dmi = ((addr as u64) << 34) | ((result as u64) << 2);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clock(dtm: &mut JtagDtm, core: &mut RVCore, tms: bool, tdi: bool) -> bool {
+        dtm.set_pins(core, false, tms, tdi);
+        let tdo = dtm.tdo();
+        dtm.set_pins(core, true, tms, tdi);
+        tdo
+    }
+
+    // From Run-Test/Idle back to Run-Test/Idle
+    fn scan(dtm: &mut JtagDtm, core: &mut RVCore, ir: bool, val: u64, length: u32) -> u64 {
+        clock(dtm, core, true, false);
+        if ir {
+            clock(dtm, core, true, false);
+        }
+        clock(dtm, core, false, false);
+        clock(dtm, core, false, false);
+        let mut out = 0;
+        for i in 0..length {
+            let tdo = clock(dtm, core, i == length - 1, (val >> i) & 1 != 0);
+            out |= (tdo as u64) << i;
+        }
+        clock(dtm, core, true, false);
+        clock(dtm, core, false, false);
+        out
+    }
+
+    #[test]
+    fn test_jtag_dtm() {
+        let mut core = RVCore::new();
+        let mut dtm = JtagDtm::new();
+        for _ in 0..5 {
+            clock(&mut dtm, &mut core, true, false);
+        }
+        clock(&mut dtm, &mut core, false, false);
+        assert_eq!(IDCODE, scan(&mut dtm, &mut core, false, 0, 32));
+        // Capture-IR loads 0b00001
+        assert_eq!(
+            1,
+            scan(&mut dtm, &mut core, true, IR_DTMCS as u64, IR_LENGTH)
+        );
+        assert_eq!(0x1071, scan(&mut dtm, &mut core, false, 0, 32));
+        scan(&mut dtm, &mut core, true, 0x1f, IR_LENGTH);
+        assert_eq!(0, scan(&mut dtm, &mut core, false, 1, 1));
+
+        // dmcontrol.dmactive, then read dmcontrol back
+        scan(&mut dtm, &mut core, true, IR_DMI as u64, IR_LENGTH);
+        let dmi = |addr: u64, data: u64, op: u64| (addr << 34) | (data << 2) | op;
+        scan(&mut dtm, &mut core, false, dmi(0x10, 1, DMI_OP_WRITE), 41);
+        scan(&mut dtm, &mut core, false, dmi(0x10, 0, DMI_OP_READ), 41);
+        assert_eq!(dmi(0x10, 1, 0), scan(&mut dtm, &mut core, false, 0, 41));
+        assert_eq!(dmi(0x10, 1, 0), scan(&mut dtm, &mut core, false, 0, 41));
+    }
+}
@@ -1,12 +1,23 @@
 // Must import all dependencies here to run UT
+mod aia;
+mod bus;
+mod clic;
+mod debug_module;
+mod gdb_stub;
+mod jtag_dtm;
 mod memory_interface;
 mod memory_model;
+mod remote_bitbang;
 mod rv_core;
+mod uart;
 use goblin::elf;
 use std::cell::RefCell;
 use std::env;
 use std::fs;
+use std::io::{self, Read};
 use std::rc::Rc;
+use std::sync::mpsc;
+use std::thread;
 type AddressType = u64;
 
 struct InfoFromElf {
@@ -17,14 +28,151 @@ struct InfoFromElf {
 
 fn main() {
     let args: Vec<String> = env::args().collect();
-    if args.len() == 1 {
-        println!("Error, should provide the ELF to run");
-        return;
+    let mut elf_path: Option<&String> = None;
+    let mut isa_str: Option<&str> = None;
+    let mut vlen = rv_core::DEFAULT_VLEN;
+    let mut elen = rv_core::DEFAULT_ELEN;
+    let mut cache_block_size = rv_core::DEFAULT_CACHE_BLOCK_SIZE;
+    let mut entropy_seed = rv_core::DEFAULT_ENTROPY_SEED;
+    let mut reset_vector = rv_core::DEFAULT_RESET_VECTOR;
+    let mut nmi_vector = rv_core::DEFAULT_NMI_VECTOR;
+    let mut nmi_exception_vector = rv_core::DEFAULT_NMI_EXCEPTION_VECTOR;
+    let mut nmi_at: Option<u64> = None;
+    let mut rbb_port: Option<u16> = None;
+    let mut gdb_port: Option<u16> = None;
+    for arg in args.iter().skip(1) {
+        if let Some(val) = arg.strip_prefix("--isa=") {
+            isa_str = Some(val);
+        } else if let Some(val) = arg.strip_prefix("--vlen=") {
+            match val.parse() {
+                Ok(val) => vlen = val,
+                Err(_) => {
+                    println!("Error, invalid VLEN {}", val);
+                    return;
+                }
+            }
+        } else if let Some(val) = arg.strip_prefix("--elen=") {
+            match val.parse() {
+                Ok(val) => elen = val,
+                Err(_) => {
+                    println!("Error, invalid ELEN {}", val);
+                    return;
+                }
+            }
+        } else if let Some(val) = arg.strip_prefix("--cache-block-size=") {
+            match val.parse() {
+                Ok(val) => cache_block_size = val,
+                Err(_) => {
+                    println!("Error, invalid cache block size {}", val);
+                    return;
+                }
+            }
+        } else if let Some(val) = arg.strip_prefix("--entropy-seed=") {
+            match val.parse() {
+                Ok(val) => entropy_seed = val,
+                Err(_) => {
+                    println!("Error, invalid entropy seed {}", val);
+                    return;
+                }
+            }
+        } else if let Some(val) = arg.strip_prefix("--reset-vector=") {
+            // Optionally prefixed by the hart it applies to, rv-sim has the single hart 0
+            let (hart, addr) = match val.split_once(':') {
+                Some((hart, addr)) => (hart.parse::<u64>().ok(), addr),
+                None => (Some(0), val),
+            };
+            match (hart, parse_addr(addr)) {
+                (Some(0), Some(addr)) => reset_vector = addr,
+                (Some(hart), Some(_)) => {
+                    println!("Error, no hart {} for the reset vector", hart);
+                    return;
+                }
+                _ => {
+                    println!("Error, invalid reset vector {}", val);
+                    return;
+                }
+            }
+        } else if let Some(val) = arg.strip_prefix("--nmi-vector=") {
+            match parse_addr(val) {
+                Some(val) => nmi_vector = val,
+                None => {
+                    println!("Error, invalid NMI vector {}", val);
+                    return;
+                }
+            }
+        } else if let Some(val) = arg.strip_prefix("--nmi-exception-vector=") {
+            match parse_addr(val) {
+                Some(val) => nmi_exception_vector = val,
+                None => {
+                    println!("Error, invalid NMI exception vector {}", val);
+                    return;
+                }
+            }
+        } else if let Some(val) = arg.strip_prefix("--nmi-at=") {
+            match val.parse() {
+                Ok(val) => nmi_at = Some(val),
+                Err(_) => {
+                    println!("Error, invalid NMI cycle {}", val);
+                    return;
+                }
+            }
+        } else if let Some(val) = arg.strip_prefix("--rbb-port=") {
+            match val.parse() {
+                Ok(val) => rbb_port = Some(val),
+                Err(_) => {
+                    println!("Error, invalid remote bitbang port {}", val);
+                    return;
+                }
+            }
+        } else if let Some(val) = arg.strip_prefix("--gdb-port=") {
+            match val.parse() {
+                Ok(val) => gdb_port = Some(val),
+                Err(_) => {
+                    println!("Error, invalid GDB port {}", val);
+                    return;
+                }
+            }
+        } else if arg.starts_with("--") {
+            println!("Error, unknown option {}", arg);
+            return;
+        } else {
+            elf_path = Some(arg);
+        }
     }
 
-    let elf_path = &args[1];
+    let elf_path = match elf_path {
+        Some(path) => path,
+        None => {
+            println!("Error, should provide the ELF to run");
+            return;
+        }
+    };
 
     let mut core: rv_core::RVCore = rv_core::RVCore::new();
+    if let Some(isa_str) = isa_str {
+        if let Err(msg) = core.set_isa(isa_str) {
+            println!("Error, invalid ISA string {}", msg);
+            return;
+        }
+    }
+    if let Err(msg) = core.set_vlen(vlen, elen) {
+        println!("Error, invalid vector configuration {}", msg);
+        return;
+    }
+    if let Err(msg) = core.set_cache_block_size(cache_block_size) {
+        println!("Error, invalid cache configuration {}", msg);
+        return;
+    }
+    core.set_entropy_seed(entropy_seed);
+    core.set_reset_vector(reset_vector);
+    core.set_nmi_vectors(nmi_vector, nmi_exception_vector);
+    if rbb_port.is_some() && !core.has_debug_mode() {
+        println!("Error, the remote bitbang port needs Sdext in the ISA string");
+        return;
+    }
+    if gdb_port.is_some() {
+        core.enable_debug_mode();
+    }
     let mem = Rc::new(RefCell::new(memory_model::MemoryModel::new()));
 
     // Hack for hello world
@@ -45,25 +193,94 @@ fn main() {
         (start_pc >> 32) as u32,
     ];
 
+    // The boot ROM sits at the reset vector, the dtb right after it
     for i in 0..reset_vec.len() {
         mem.borrow_mut()
-            .write_word((0x1000 + i * 4) as AddressType, reset_vec[i]);
+            .write_word(reset_vector + (i * 4) as AddressType, reset_vec[i]);
     }
 
-    // Put dtb at 0x1020
+    let dtb_addr = reset_vector + (RESET_VEC_SIZE * 4) as AddressType;
     let dtb_bytes = fs::read("rv-sim.dtb").unwrap();
     for i in 0..dtb_bytes.len() {
         mem.borrow_mut()
-            .write_byte((0x1020 + i) as AddressType, dtb_bytes[i]);
+            .write_byte(dtb_addr + i as AddressType, dtb_bytes[i]);
     }
 
-    let mem_if: Rc<RefCell<dyn memory_interface::MemoryInterface>> = mem.clone();
+    let mut bus = bus::Bus::new(mem.clone());
+    if let Some(aia) = core.aia() {
+        for (base, size) in aia::MMIO_REGIONS.iter() {
+            bus.add_device(*base, *size, aia.clone());
+        }
+    }
+    if let Some(clic) = core.clic() {
+        bus.add_device(clic::CLIC_BASE, clic::CLIC_SIZE, clic);
+    }
+    // The console of the dtb, its interrupt goes to the APLIC when there is one
+    let uart_irq = core
+        .aia()
+        .map(|aia| aia::InterruptLine::new(aia, uart::UART_IRQ));
+    let uart = Rc::new(RefCell::new(uart::Uart::new(uart_irq)));
+    bus.add_device(uart::UART_BASE, uart::UART_SIZE, uart.clone());
+    let mem_if: Rc<RefCell<dyn memory_interface::MemoryInterface>> = Rc::new(RefCell::new(bus));
     core.bind_mem(mem_if.clone());
-    core.pc = 0x1000;
+    core.reset();
+
+    // OpenOCD connects with its remote_bitbang driver
+    let mut rbb = match rbb_port.map(remote_bitbang::RemoteBitbang::new) {
+        Some(Ok(rbb)) => Some(rbb),
+        Some(Err(e)) => {
+            println!("Error, cannot open the remote bitbang port {}", e);
+            return;
+        }
+        None => None,
+    };
+    // riscv64-unknown-elf-gdb attaches with target remote
+    let mut gdb = match gdb_port.map(|port| gdb_stub::GdbStub::new(port, mem_if.clone())) {
+        Some(Ok(gdb)) => Some(gdb),
+        Some(Err(e)) => {
+            println!("Error, cannot open the GDB port {}", e);
+            return;
+        }
+        None => None,
+    };
+
+    // stdin is read on its own thread, the UART receives what arrived in between
+    let (stdin_tx, stdin_rx) = mpsc::channel();
+    thread::spawn(move || {
+        for byte in io::stdin().lock().bytes() {
+            match byte {
+                Ok(byte) if stdin_tx.send(byte).is_ok() => (),
+                _ => break,
+            }
+        }
+    });
 
     //for _i in 0..1000 {
     while true {
-        core.run(5000);
+        // Stop at the requested cycle to raise the NMI, e.g. to fire a watchdog
+        let mut num_steps = 5000;
+        if let Some(cycle) = nmi_at {
+            if core.cycle() >= cycle {
+                core.raise_nmi(0);
+                nmi_at = None;
+            } else {
+                num_steps = num_steps.min(cycle - core.cycle());
+            }
+        }
+        core.run(num_steps as i32);
+        for byte in stdin_rx.try_iter() {
+            uart.borrow_mut().receive(byte);
+        }
+        if let Some(rbb) = rbb.as_mut() {
+            rbb.tick(&mut core);
+        }
+        if let Some(gdb) = gdb.as_mut() {
+            gdb.tick(&mut core);
+        }
+        if let Some(error) = core.critical_error() {
+            println!("Critical error: {}", error);
+            break;
+        }
         let tohost = mem.borrow_mut().read_word(elf_info.tohost_addr) as u64;
         if tohost != 0 {
             if (tohost & 1) == 1 {
@@ -74,6 +291,9 @@ fn main() {
                 } else {
                     println!("RISCV_TEST_FAIL");
                 }
+                if let Some(gdb) = gdb.as_mut() {
+                    gdb.exit(test_result as u8);
+                }
 
                 break;
             } else {
@@ -88,6 +308,14 @@ fn main() {
     //println!("Simulation ends");
 }
 
+// Addresses are decimal or 0x-prefixed hexadecimal
+fn parse_addr(val: &str) -> Option<AddressType> {
+    match val.strip_prefix("0x") {
+        Some(hex) => AddressType::from_str_radix(hex, 16).ok(),
+        None => val.parse().ok(),
+    }
+}
+
 fn load_elf(mem: &mut memory_model::MemoryModel, path: &str) -> InfoFromElf {
     let bytes = fs::read(path).unwrap();
     let elf = elf::Elf::parse(&bytes).unwrap();
@@ -5,6 +5,10 @@ type AddressType = u64;
 pub enum MemoryOperation {
     READ,
     WRITE,
+    // Cache-block maintenance of Zicbom, data holds a whole block
+    CLEAN,
+    FLUSH,
+    INVAL,
     INVALID,
 }
 
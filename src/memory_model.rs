@@ -97,6 +97,8 @@ impl MemoryModel {
                     }
                 }
             }
+            // There are no caches in front of the backing store
+            MemoryOperation::CLEAN | MemoryOperation::FLUSH | MemoryOperation::INVAL => {}
             MemoryOperation::INVALID => panic!("Invalid mem op"),
         }
     }
@@ -126,6 +128,7 @@ mod tests {
             addr: 0x66666666,
             data: [0, 0, 0, 0, 0].to_vec(),
             op: MemoryOperation::WRITE,
+            is_amo: false,
         };
 
         mem.access_memory(&mut payload);
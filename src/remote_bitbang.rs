@@ -0,0 +1,98 @@
+use crate::jtag_dtm::JtagDtm;
+use crate::rv_core::RVCore;
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::Duration;
+
+// How long a halted hart waits for the debugger before giving the host loop a turn
+const HALTED_POLL: Duration = Duration::from_millis(10);
+
+// OpenOCD remote_bitbang server on localhost, one client at a time. Each byte received
+// drives the JTAG pins or samples TDO.
+pub struct RemoteBitbang {
+    listener: TcpListener,
+    client: Option<TcpStream>,
+    dtm: JtagDtm,
+}
+
+impl RemoteBitbang {
+    pub fn new(port: u16) -> io::Result<RemoteBitbang> {
+        let listener = TcpListener::bind(("127.0.0.1", port))?;
+        listener.set_nonblocking(true)?;
+        Ok(RemoteBitbang {
+            listener,
+            client: None,
+            dtm: JtagDtm::new(),
+        })
+    }
+
+    // Serves the commands received so far. While the hart is halted there is nothing
+    // else to do, the call then waits a little for the debugger.
+    pub fn tick(&mut self, core: &mut RVCore) {
+        if self.client.is_none() {
+            match self.listener.accept() {
+                Ok((client, _)) => {
+                    let _ = client.set_nodelay(true);
+                    let _ = client.set_read_timeout(Some(HALTED_POLL));
+                    self.client = Some(client);
+                }
+                Err(_) => {
+                    if core.halted() {
+                        thread::sleep(HALTED_POLL);
+                    }
+                    return;
+                }
+            }
+        }
+        let mut buf = [0; 4096];
+        let len = match self.read(&mut buf, !core.halted()) {
+            Ok(0) => {
+                self.client = None;
+                return;
+            }
+            Ok(len) => len,
+            Err(e) if e.kind() == ErrorKind::WouldBlock || e.kind() == ErrorKind::TimedOut => {
+                return;
+            }
+            Err(_) => {
+                self.client = None;
+                return;
+            }
+        };
+        let mut response = Vec::new();
+        for &command in &buf[..len] {
+            match command {
+                b'0'..=b'7' => {
+                    let pins = command - b'0';
+                    let (tck, tms, tdi) = (pins & 4 != 0, pins & 2 != 0, pins & 1 != 0);
+                    self.dtm.set_pins(core, tck, tms, tdi);
+                }
+                b'R' => response.push(if self.dtm.tdo() { b'1' } else { b'0' }),
+                // TRST asserted, SRST is ignored
+                b't' | b'u' => self.dtm.reset(),
+                b'Q' => {
+                    self.client = None;
+                    return;
+                }
+                // Blink and the other reset combinations
+                _ => (),
+            }
+        }
+        if !response.is_empty() && self.write(&response).is_err() {
+            self.client = None;
+        }
+    }
+
+    fn read(&mut self, buf: &mut [u8], nonblocking: bool) -> io::Result<usize> {
+        let client = self.client.as_mut().unwrap();
+        client.set_nonblocking(nonblocking)?;
+        client.read(buf)
+    }
+
+    fn write(&mut self, buf: &[u8]) -> io::Result<()> {
+        let client = self.client.as_mut().unwrap();
+        client.set_nonblocking(false)?;
+        client.write_all(buf)
+    }
+}
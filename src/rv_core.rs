@@ -1,24 +1,52 @@
-mod csregs;
+mod crypto;
+pub(crate) mod csregs;
+pub(crate) mod debug;
 mod fregs;
 mod inst_decoder;
 mod inst_info;
 mod inst_type;
+mod isa;
+mod mmu;
+pub(crate) mod triggers;
+mod vector;
+mod vregs;
 mod xregs;
+use crate::aia::{self, Aia};
+use crate::clic::Clic;
 use crate::memory_interface::{MemoryInterface, MemoryOperation, Payload};
+pub use crate::rv_core::csregs::DEFAULT_ENTROPY_SEED;
 use crate::rv_core::inst_info::InstID;
+use crate::rv_core::mmu::{AccessType, Translation};
+pub use crate::rv_core::vregs::{DEFAULT_ELEN, DEFAULT_VLEN};
 use std::cell::RefCell;
 use std::convert::TryInto;
 use std::rc::Rc;
 use std::cmp;
 
-use softfloat_wrapper::{ExceptionFlags, Float, RoundingMode, F32, F64};
+use softfloat_wrapper::{ExceptionFlags, Float, RoundingMode, F16, F32, F64};
 
 type AddressType = u64;
 
+pub const DEFAULT_CACHE_BLOCK_SIZE: usize = 64;
+// The boot ROM sits at the reset vector, the NMI handlers default to the start of memory
+pub const DEFAULT_RESET_VECTOR: AddressType = 0x1000;
+pub const DEFAULT_NMI_VECTOR: AddressType = 0x0;
+pub const DEFAULT_NMI_EXCEPTION_VECTOR: AddressType = 0x100;
+
+// Single-precision encodings of the fli constants, indexed by rs1
+const FLI_TABLE: [u32; 32] = [
+    0xbf80_0000, 0x0080_0000, 0x3780_0000, 0x3800_0000, 0x3b80_0000, 0x3c00_0000, 0x3d80_0000,
+    0x3e00_0000, 0x3e80_0000, 0x3ea0_0000, 0x3ec0_0000, 0x3ee0_0000, 0x3f00_0000, 0x3f20_0000,
+    0x3f40_0000, 0x3f60_0000, 0x3f80_0000, 0x3fa0_0000, 0x3fc0_0000, 0x3fe0_0000, 0x4000_0000,
+    0x4020_0000, 0x4040_0000, 0x4080_0000, 0x4100_0000, 0x4180_0000, 0x4300_0000, 0x4380_0000,
+    0x4700_0000, 0x4780_0000, 0x7f80_0000, 0x7fc0_0000,
+];
+
+#[derive(PartialEq, Debug, Copy, Clone)]
 enum PrivilegeMode {
-    U,
-    S,
-    M,
+    U = 0,
+    S = 1,
+    M = 3,
 }
 
 pub struct RVCore {
@@ -26,33 +54,310 @@ pub struct RVCore {
     pub regs: xregs::XRegisters,
     pub fregs: fregs::FRegisters,
     pub csregs: csregs::CSRegisters,
+    pub vregs: vregs::VRegisters,
     id_instance: inst_decoder::InstDecoder,
     mem_if: Option<Rc<RefCell<dyn MemoryInterface>>>,
     mode: PrivilegeMode,
+    // V, set in VS/VU-mode
+    virt: bool,
+    isa: isa::Isa,
+    trap_taken: bool,
+    cache_block_size: usize,
+    reset_vector: AddressType,
+    nmi_vector: AddressType,
+    nmi_exception_vector: AddressType,
+    // Cause of the NMI requested by the host, until it is taken
+    nmi_pending: Option<AddressType>,
+    // Set by an unrecoverable double trap, the hart stops executing
+    critical_error: Option<String>,
+    // Halted by the debugger, only the program buffer runs
+    debug_mode: bool,
+    halt_request: bool,
+    // Debug mode enabled for the GDB stub without Sdext in the ISA string
+    debugger: bool,
 }
 
 impl RVCore {
     pub fn new() -> RVCore {
-        RVCore {
+        let mut core = RVCore {
             pc: 0,
             regs: xregs::XRegisters::new(),
             fregs: fregs::FRegisters::new(),
             csregs: csregs::CSRegisters::new(),
+            vregs: vregs::VRegisters::new(vregs::DEFAULT_VLEN, vregs::DEFAULT_ELEN),
             id_instance: inst_decoder::InstDecoder::new(),
             mem_if: None,
             mode: PrivilegeMode::M,
+            virt: false,
+            isa: isa::Isa::default(),
+            trap_taken: false,
+            cache_block_size: DEFAULT_CACHE_BLOCK_SIZE,
+            reset_vector: DEFAULT_RESET_VECTOR,
+            nmi_vector: DEFAULT_NMI_VECTOR,
+            nmi_exception_vector: DEFAULT_NMI_EXCEPTION_VECTOR,
+            nmi_pending: None,
+            critical_error: None,
+            debug_mode: false,
+            halt_request: false,
+            debugger: false,
+        };
+        core.csregs
+            .set_vlenb((vregs::DEFAULT_VLEN / 8) as AddressType);
+        core
+    }
+
+    pub fn set_isa(&mut self, isa_str: &str) -> Result<(), String> {
+        self.isa = isa::Isa::parse(isa_str)?;
+        self.csregs.set_isa(&self.isa);
+        Ok(())
+    }
+
+    pub fn set_vlen(&mut self, vlen: usize, elen: usize) -> Result<(), String> {
+        if elen != 32 && elen != 64 {
+            return Err(format!("ELEN {} must be 32 or 64", elen));
+        }
+        if !vlen.is_power_of_two() || vlen < elen || vlen > 65536 {
+            return Err(format!(
+                "VLEN {} must be a power of two between ELEN and 65536",
+                vlen
+            ));
+        }
+        self.vregs = vregs::VRegisters::new(vlen, elen);
+        self.csregs.set_vlenb((vlen / 8) as AddressType);
+        Ok(())
+    }
+
+    pub fn set_cache_block_size(&mut self, size: usize) -> Result<(), String> {
+        if !size.is_power_of_two() || !(8..=4096).contains(&size) {
+            return Err(format!(
+                "cache block size {} must be a power of two between 8 and 4096",
+                size
+            ));
+        }
+        self.cache_block_size = size;
+        Ok(())
+    }
+
+    pub fn set_entropy_seed(&mut self, seed: u64) {
+        self.csregs.set_entropy_seed(seed);
+    }
+
+    pub fn set_reset_vector(&mut self, addr: AddressType) {
+        self.reset_vector = addr;
+    }
+
+    // With Smrnmi, exceptions inside the NMI handler go to their own vector
+    pub fn set_nmi_vectors(&mut self, nmi: AddressType, exception: AddressType) {
+        self.nmi_vector = nmi;
+        self.nmi_exception_vector = exception;
+    }
+
+    // Start over in M-mode at the reset vector, NMIs stay masked until mnstatus.NMIE is set
+    pub fn reset(&mut self) {
+        self.pc = self.reset_vector;
+        self.mode = PrivilegeMode::M;
+        self.virt = false;
+        self.nmi_pending = None;
+        self.critical_error = None;
+        self.debug_mode = false;
+        self.csregs.triggers().set_debug_mode(false);
+        self.csregs.set_nmie(false);
+        if self.isa.has(isa::Extension::Smdbltrp) {
+            let mstatus = self.csregs.read(csregs::MSTATUS);
+            self.csregs
+                .write(csregs::MSTATUS, mstatus | csregs::MSTATUS_MDT);
+        }
+    }
+
+    // NMI input driven by the host, cause is reported in mncause (mcause without Smrnmi)
+    pub fn raise_nmi(&mut self, cause: AddressType) {
+        self.nmi_pending = Some(cause);
+    }
+
+    pub fn cycle(&self) -> u64 {
+        self.csregs.read(csregs::MCYCLE)
+    }
+
+    // Diagnostic of the double trap that stopped the hart
+    pub fn critical_error(&self) -> Option<&str> {
+        self.critical_error.as_deref()
+    }
+
+    // IMSIC and APLIC state to map on the bus, when Smaia is enabled
+    pub fn aia(&self) -> Option<Rc<RefCell<Aia>>> {
+        if self.isa.has(isa::Extension::Smaia) {
+            Some(self.csregs.aia())
+        } else {
+            None
+        }
+    }
+
+    // CLIC state to map on the bus, when Smclic is enabled
+    pub fn clic(&self) -> Option<Rc<RefCell<Clic>>> {
+        if self.isa.has(isa::Extension::Smclic) {
+            Some(self.csregs.clic())
+        } else {
+            None
         }
     }
 
+    fn extension_enabled(&self, ext: isa::Extension) -> bool {
+        if !self.isa.has(ext) {
+            return false;
+        }
+        match ext.misa_bit() {
+            Some(bit) => (self.csregs.read(csregs::MISA) & bit) != 0,
+            None => true,
+        }
+    }
+
+    // Instructions are fetched in 16-bit parcels at the end of a page, so that a
+    // compressed one does not touch the next page
+    fn fetch(&mut self) -> Option<AddressType> {
+        let translation = self.fetch_translation();
+        let paddr = self.translate(self.pc, AccessType::Fetch, translation)?;
+        let mut data = [0; 4];
+        if self.pc & 0xfff <= 0xffc {
+            self.access_physical(paddr, &mut data, MemoryOperation::READ, false);
+        } else {
+            self.access_physical(paddr, &mut data[..2], MemoryOperation::READ, false);
+            if data[0] & 0b11 == 0b11 {
+                let paddr = self.translate(self.pc + 2, AccessType::Fetch, translation)?;
+                self.access_physical(paddr, &mut data[2..], MemoryOperation::READ, false);
+            }
+        }
+        Some(RVCore::byte_array_to_addr_type(&data))
+    }
+
+    // mtime advances once per cycle
+    fn tick(&mut self) {
+        self.csregs
+            .write(csregs::MCYCLE, self.csregs.read(csregs::MCYCLE) + 1);
+        self.csregs
+            .write(csregs::TIME, self.csregs.read(csregs::TIME) + 1);
+    }
+
+    // Highest priority interrupt that is pending, enabled and not masked in the current mode.
+    // M-level interrupts go first, then the HS-level and the VS-level ones.
+    fn pending_interrupt(&self) -> Option<AddressType> {
+        if !self.nmi_enabled() || self.step_masks_interrupts() {
+            return None;
+        }
+        if self.csregs.clic_mode() {
+            return self.clic_interrupt();
+        }
+        let pending = self.csregs.read(csregs::MIP) & self.csregs.read(csregs::MIE);
+        if pending == 0 {
+            return None;
+        }
+        let mideleg = self.csregs.read(csregs::MIDELEG);
+        let hideleg = self.csregs.read(csregs::HIDELEG);
+        let mstatus = self.csregs.read(csregs::MSTATUS);
+        let vsstatus = self.csregs.read(csregs::VSSTATUS);
+        let m_enabled = self.mode != PrivilegeMode::M || mstatus & csregs::MSTATUS_MIE != 0;
+        let hs_enabled = match self.mode {
+            PrivilegeMode::M => false,
+            PrivilegeMode::S if !self.virt => mstatus & csregs::MSTATUS_SIE != 0,
+            _ => true,
+        };
+        let vs_enabled =
+            self.virt && (self.mode == PrivilegeMode::U || vsstatus & csregs::MSTATUS_SIE != 0);
+        let levels = [
+            (m_enabled, pending & !mideleg),
+            (hs_enabled, pending & mideleg & !hideleg),
+            (vs_enabled, pending & mideleg & hideleg),
+        ];
+        levels
+            .iter()
+            .filter(|(enabled, _)| *enabled)
+            .find_map(|(_, interrupts)| csregs::highest_priority(*interrupts))
+    }
+
+    // In CLIC mode all interrupts are M-level. In M-mode they must be above both the
+    // current interrupt level and mintthresh, lower modes take any non-zero level.
+    fn clic_interrupt(&self) -> Option<AddressType> {
+        let clic = self.csregs.clic();
+        let clic = clic.borrow();
+        let id = clic.top()?;
+        let threshold = if self.mode == PrivilegeMode::M {
+            if self.csregs.read(csregs::MSTATUS) & csregs::MSTATUS_MIE == 0 {
+                return None;
+            }
+            let mil = self.csregs.read(csregs::MINTSTATUS) >> csregs::MINTSTATUS_MIL_SHIFT;
+            cmp::max(mil, self.csregs.read(csregs::MINTTHRESH))
+        } else {
+            0
+        };
+        if clic.level(id) > threshold {
+            Some(id as AddressType)
+        } else {
+            None
+        }
+    }
+
+    // A halt request stops the hart before the next instruction, single-stepping after it
     fn step(&mut self) {
-        let mut data = [0; std::mem::size_of::<AddressType>()];
-        self.read_memory(self.pc, &mut data);
-        let inst_bytes = RVCore::byte_array_to_addr_type(&data);
+        if self.debug_mode {
+            return;
+        }
+        if self.halt_request {
+            self.enter_debug_mode(csregs::DCSR_CAUSE_HALTREQ);
+            return;
+        }
+        let single_step = self.single_stepping();
+        self.step_instruction();
+        if single_step && !self.debug_mode {
+            self.enter_debug_mode(csregs::DCSR_CAUSE_STEP);
+        }
+    }
+
+    fn step_instruction(&mut self) {
+        self.trap_taken = false;
+        if self.csregs.clic_mode() {
+            self.csregs.update_clic();
+        }
+        if self.nmi_enabled() && !self.step_masks_interrupts() {
+            if let Some(cause) = self.nmi_pending.take() {
+                self.take_nmi(csregs::CAUSE_INTERRUPT | cause);
+                self.tick();
+                return;
+            }
+        }
+        if let Some(irq) = self.pending_interrupt() {
+            self.raise_trap(csregs::CAUSE_INTERRUPT | irq, 0, 0, false);
+            self.tick();
+            return;
+        }
+        // icount triggers fire after the instruction that brought them to zero
+        let breakpoints = self.breakpoint_triggers_enabled();
+        if let Some(action) = self.csregs.triggers().fire_icount(breakpoints) {
+            self.take_trigger(action, 0);
+            self.tick();
+            return;
+        }
+        let inst_bytes = match self.fetch() {
+            Some(inst_bytes) => inst_bytes,
+            None => {
+                self.tick();
+                return;
+            }
+        };
 
-        let inst = self.id_instance.decode(inst_bytes);
+        let inst = self
+            .id_instance
+            .decode(inst_bytes, |ext| self.extension_enabled(ext));
+        let inst_data = inst_bytes & ((1 << (8 * inst.len)) - 1);
+        if let Some(action) =
+            self.trigger_fires(triggers::MCONTROL6_EXECUTE, self.pc, Some(inst_data))
+        {
+            self.take_trigger(action, self.pc);
+            self.tick();
+            return;
+        }
         if inst.id == InstID::INVALID {
-            println!("{:#010x} ({:#010x}) INVALID", self.pc, inst_bytes as u32);
-            panic!("Invalid instruction");
+            self.raise_illegal_inst(&inst);
+            self.tick();
+            return;
         }
 /*
         print!(
@@ -66,13 +371,441 @@ impl RVCore {
         }
         println!("");
 */
+        // A trapping instruction, e.g. a load hitting a page fault, leaves the registers untouched
+        let regs = self.regs.clone();
+        let (mode, virt) = (self.mode, self.virt);
         self.execute(&inst);
-        self.pc += inst.len;
 
-        self.csregs
-            .write(csregs::MCYCLE, self.csregs.read(csregs::MCYCLE) + 1);
+        self.tick();
+        if self.trap_taken {
+            self.regs = regs;
+            return;
+        }
+        self.pc += inst.len;
         self.csregs
             .write(csregs::MINSTRET, self.csregs.read(csregs::MINSTRET) + 1);
+        if self.isa.has(isa::Extension::Sdtrig) {
+            self.csregs.triggers().retire_instruction(mode, virt);
+        }
+    }
+
+    fn raise_exception(&mut self, cause: AddressType, tval: AddressType) {
+        self.raise_trap(cause, tval, 0, false);
+    }
+
+    // Trap into M-mode, into HS-mode when delegated by medeleg/mideleg, or into VS-mode
+    // when also delegated by hedeleg/hideleg. pc is left pointing at the handler. tval2
+    // is the guest physical address >> 2 of guest-page faults, gva tells tval holds a
+    // guest virtual address.
+    fn raise_trap(&mut self, cause: AddressType, tval: AddressType, tval2: AddressType, gva: bool) {
+        // Exceptions in debug mode only abort the program buffer
+        if self.debug_mode {
+            self.trap_taken = true;
+            return;
+        }
+        let interrupt = cause & csregs::CAUSE_INTERRUPT != 0;
+        let code = cause & !csregs::CAUSE_INTERRUPT;
+        let (m_deleg, h_deleg) = if interrupt {
+            (csregs::MIDELEG, csregs::HIDELEG)
+        } else {
+            (csregs::MEDELEG, csregs::HEDELEG)
+        };
+        let delegated = |deleg: AddressType| (deleg >> code) & 1 != 0;
+        let clic_mode = self.csregs.clic_mode();
+        // CLIC interrupts are not delegated
+        let to_hs = self.mode != PrivilegeMode::M
+            && !(interrupt && clic_mode)
+            && delegated(self.csregs.read(m_deleg));
+        let to_vs = to_hs && self.virt && delegated(self.csregs.read(h_deleg));
+        // A trap into (V)S-mode while SDT is set is a double trap, M-mode gets it with the
+        // original cause in mtval2
+        let sdt_csr = if to_vs {
+            csregs::VSSTATUS
+        } else {
+            csregs::MSTATUS
+        };
+        if to_hs && self.csregs.read(sdt_csr) & csregs::MSTATUS_SDT != 0 {
+            self.raise_m_trap(csregs::EXC_DOUBLE_TRAP, tval, cause, gva);
+        } else if to_vs {
+            let vsstatus = self.csregs.read(csregs::VSSTATUS);
+            self.csregs
+                .write(csregs::VSSTATUS, RVCore::trap_status(vsstatus, self.mode));
+            self.csregs.write(csregs::VSEPC, self.pc);
+            // VS-level interrupts are reported to VS-mode as their S-level counterparts
+            let vs_cause = if interrupt { cause - 1 } else { cause };
+            self.csregs.write(csregs::VSCAUSE, vs_cause);
+            self.csregs.write(csregs::VSTVAL, tval);
+            self.mode = PrivilegeMode::S;
+            self.pc = self.trap_vector(csregs::VSTVEC, vs_cause);
+        } else if to_hs {
+            let mstatus = self.csregs.read(csregs::MSTATUS);
+            self.csregs
+                .write(csregs::MSTATUS, RVCore::trap_status(mstatus, self.mode));
+            if self.isa.has(isa::Extension::H) {
+                let mut hstatus = self.csregs.read(csregs::HSTATUS)
+                    & !(csregs::HSTATUS_SPV | csregs::HSTATUS_GVA);
+                if self.virt {
+                    hstatus &= !csregs::HSTATUS_SPVP;
+                    hstatus |= csregs::HSTATUS_SPV;
+                    if self.mode == PrivilegeMode::S {
+                        hstatus |= csregs::HSTATUS_SPVP;
+                    }
+                }
+                if gva {
+                    hstatus |= csregs::HSTATUS_GVA;
+                }
+                self.csregs.write(csregs::HSTATUS, hstatus);
+                self.csregs.write(csregs::HTVAL, tval2);
+                self.csregs.write(csregs::HTINST, 0);
+            }
+            self.csregs.write(csregs::SEPC, self.pc);
+            self.csregs.write(csregs::SCAUSE, cause);
+            self.csregs.write(csregs::STVAL, tval);
+            self.mode = PrivilegeMode::S;
+            self.virt = false;
+            self.pc = self.trap_vector(csregs::STVEC, cause);
+        } else {
+            self.raise_m_trap(cause, tval, tval2, gva);
+        }
+        self.trap_taken = true;
+    }
+
+    // With Smdbltrp a trap into M-mode while MDT is set is a double trap. It is taken as
+    // an RNMI when possible, in the NMI handler or without Smrnmi the hart stops.
+    fn raise_m_trap(
+        &mut self,
+        cause: AddressType,
+        tval: AddressType,
+        tval2: AddressType,
+        gva: bool,
+    ) {
+        // Exceptions in the NMI handler (NMIE clear) can't use mtvec
+        let in_nmi_handler = self.mode == PrivilegeMode::M && !self.nmi_enabled();
+        let mdt = self.csregs.read(csregs::MSTATUS) & csregs::MSTATUS_MDT != 0;
+        if self.isa.has(isa::Extension::Smdbltrp) && (mdt || in_nmi_handler) {
+            if !in_nmi_handler && self.isa.has(isa::Extension::Smrnmi) {
+                self.take_nmi(csregs::EXC_DOUBLE_TRAP);
+            } else {
+                let (prev_cause, prev_pc) = if in_nmi_handler {
+                    (csregs::MNCAUSE, csregs::MNEPC)
+                } else {
+                    (csregs::MCAUSE, csregs::MEPC)
+                };
+                self.critical_error = Some(format!(
+                    "double trap, cause {:#x} at pc {:#x} while handling cause {:#x} from pc {:#x}",
+                    cause,
+                    self.pc,
+                    self.csregs.read(prev_cause),
+                    self.csregs.read(prev_pc)
+                ));
+            }
+            return;
+        }
+        self.enter_m_mode(cause, tval, tval2, gva);
+        self.pc = if in_nmi_handler {
+            self.nmi_exception_vector
+        } else if self.csregs.clic_mode() {
+            self.clic_vector(cause)
+        } else {
+            self.trap_vector(csregs::MTVEC, cause)
+        };
+    }
+
+    // Without Smrnmi NMIs can't be masked
+    fn nmi_enabled(&self) -> bool {
+        !self.isa.has(isa::Extension::Smrnmi)
+            || self.csregs.read(csregs::MNSTATUS) & csregs::MNSTATUS_NMIE != 0
+    }
+
+    // Smrnmi keeps the interrupted state in mnepc/mncause/mnstatus so that mnret can
+    // resume it, otherwise the NMI overwrites mepc/mcause like a trap
+    fn take_nmi(&mut self, cause: AddressType) {
+        if self.isa.has(isa::Extension::Smrnmi) {
+            let mut mnstatus = self.csregs.read(csregs::MNSTATUS)
+                & !(csregs::MNSTATUS_MNPP | csregs::MNSTATUS_MNPV);
+            mnstatus |= (self.mode as AddressType) << 11;
+            if self.virt {
+                mnstatus |= csregs::MNSTATUS_MNPV;
+            }
+            self.csregs.write(csregs::MNSTATUS, mnstatus);
+            self.csregs.set_nmie(false);
+            self.csregs.write(csregs::MNEPC, self.pc);
+            self.csregs.write(csregs::MNCAUSE, cause);
+            self.mode = PrivilegeMode::M;
+            self.virt = false;
+        } else {
+            self.enter_m_mode(cause, 0, 0, false);
+        }
+        self.pc = self.nmi_vector;
+    }
+
+    // Trap state of M-mode, the caller picks the handler
+    fn enter_m_mode(
+        &mut self,
+        cause: AddressType,
+        tval: AddressType,
+        tval2: AddressType,
+        gva: bool,
+    ) {
+        let clic_mode = self.csregs.clic_mode();
+        // mcause.mpil saves the interrupt level in CLIC mode, mcause is written first
+        // as its mpp/mpie alias mstatus
+        let mut mcause = cause;
+        if clic_mode {
+            let mil = self.csregs.read(csregs::MINTSTATUS) >> csregs::MINTSTATUS_MIL_SHIFT;
+            mcause |= mil << csregs::MCAUSE_MPIL_SHIFT;
+        }
+        self.csregs.write(csregs::MCAUSE, mcause);
+        let mstatus = self.csregs.read(csregs::MSTATUS);
+        let mut new_mstatus = mstatus
+            & !(csregs::MSTATUS_MPP
+                | csregs::MSTATUS_MPIE
+                | csregs::MSTATUS_MIE
+                | csregs::MSTATUS_MPV
+                | csregs::MSTATUS_GVA);
+        if (mstatus & csregs::MSTATUS_MIE) != 0 {
+            new_mstatus |= csregs::MSTATUS_MPIE;
+        }
+        new_mstatus |= (self.mode as AddressType) << 11;
+        if self.virt {
+            new_mstatus |= csregs::MSTATUS_MPV;
+        }
+        if gva {
+            new_mstatus |= csregs::MSTATUS_GVA;
+        }
+        if self.isa.has(isa::Extension::Smdbltrp) {
+            new_mstatus |= csregs::MSTATUS_MDT;
+        }
+        self.csregs.write(csregs::MSTATUS, new_mstatus);
+
+        self.csregs.write(csregs::MEPC, self.pc);
+        self.csregs.write(csregs::MTVAL, tval);
+        self.csregs.write(csregs::MTVAL2, tval2);
+        self.csregs.write(csregs::MTINST, 0);
+        self.mode = PrivilegeMode::M;
+        self.virt = false;
+    }
+
+    // Vectored mode sends interrupts to BASE + 4 * cause, exceptions always go to BASE
+    fn trap_vector(&self, tvec_csr: AddressType, cause: AddressType) -> AddressType {
+        let tvec = self.csregs.read(tvec_csr);
+        let base = tvec & !csregs::TVEC_MODE;
+        let interrupt = cause & csregs::CAUSE_INTERRUPT != 0;
+        if interrupt && tvec & csregs::TVEC_MODE == csregs::TVEC_MODE_VECTORED {
+            base + 4 * (cause & !csregs::CAUSE_INTERRUPT)
+        } else {
+            base
+        }
+    }
+
+    // Handler of a trap in CLIC mode. Interrupts raise the interrupt level, the
+    // selectively vectored ones jump through their mtvt entry, everything else
+    // goes to the mtvec base.
+    fn clic_vector(&mut self, cause: AddressType) -> AddressType {
+        let base = self.csregs.read(csregs::MTVEC) & csregs::MTVEC_CLIC_BASE;
+        if cause & csregs::CAUSE_INTERRUPT == 0 {
+            return base;
+        }
+        let id = (cause & !csregs::CAUSE_INTERRUPT) as usize;
+        let clic = self.csregs.clic();
+        let (level, shv) = {
+            let clic = clic.borrow();
+            (clic.level(id), clic.shv(id))
+        };
+        self.csregs.set_interrupt_level(level);
+        if !shv {
+            return base;
+        }
+        clic.borrow_mut().acknowledge(id);
+        let mut entry = [0; 8];
+        let entry_addr = self.csregs.read(csregs::MTVT) + 8 * id as AddressType;
+        self.access_physical(entry_addr, &mut entry, MemoryOperation::READ, false);
+        RVCore::byte_array_to_addr_type(&entry) & !1
+    }
+
+    // sstatus/vsstatus after a trap into (V)S-mode from mode
+    fn trap_status(status: AddressType, mode: PrivilegeMode) -> AddressType {
+        let mut new_status =
+            status & !(csregs::MSTATUS_SPP | csregs::MSTATUS_SPIE | csregs::MSTATUS_SIE);
+        if status & csregs::MSTATUS_SIE != 0 {
+            new_status |= csregs::MSTATUS_SPIE;
+        }
+        if mode != PrivilegeMode::U {
+            new_status |= csregs::MSTATUS_SPP;
+        }
+        // SDT stays read-only zero unless enabled by DTE
+        new_status | csregs::MSTATUS_SDT
+    }
+
+    // Returning to VU-mode leaves the VS-level trap handler as well
+    fn clear_vsstatus_sdt(&mut self) {
+        if self.virt && self.mode == PrivilegeMode::U {
+            let vsstatus = self.csregs.read(csregs::VSSTATUS);
+            self.csregs
+                .write(csregs::VSSTATUS, vsstatus & !csregs::MSTATUS_SDT);
+        }
+    }
+
+    // sstatus/vsstatus after an sret
+    fn sret_status(status: AddressType) -> AddressType {
+        let mut new_status = (status
+            & !(csregs::MSTATUS_SPP | csregs::MSTATUS_SIE | csregs::MSTATUS_SDT))
+            | csregs::MSTATUS_SPIE;
+        if status & csregs::MSTATUS_SPIE != 0 {
+            new_status |= csregs::MSTATUS_SIE;
+        }
+        new_status
+    }
+
+    fn raise_illegal_inst(&mut self, inst: &inst_type::InstType) {
+        let inst_mask = if inst.len == 2 { 0xffff } else { 0xffffffff };
+        self.raise_exception(csregs::EXC_ILLEGAL_INST, inst.data & inst_mask);
+    }
+
+    fn raise_virtual_inst(&mut self, inst: &inst_type::InstType) {
+        let inst_mask = if inst.len == 2 { 0xffff } else { 0xffffffff };
+        self.raise_exception(csregs::EXC_VIRTUAL_INST, inst.data & inst_mask);
+    }
+
+    // Privilege checks of the CSR instructions, returns the CSR to access or None when
+    // an exception was raised. When V=1 the supervisor CSRs are replaced by the VS ones.
+    fn csr_access(&mut self, inst: &inst_type::InstType, writes: bool) -> Option<AddressType> {
+        let csr = inst.get_csr();
+        let level = (csr >> 8) & 3;
+        let has_h = self.isa.has(isa::Extension::H);
+        // Level 2 holds the hypervisor and VS CSRs, accessible from HS-mode
+        let hs_allowed = level < 2 || (level == 2 && has_h);
+        let allowed = match (self.mode, self.virt) {
+            (PrivilegeMode::M, _) => level != 2 || has_h,
+            (PrivilegeMode::S, false) => hs_allowed,
+            (PrivilegeMode::S, true) => level < 2,
+            (PrivilegeMode::U, _) => level == 0,
+        };
+        let mstatus = self.csregs.read(csregs::MSTATUS);
+        let hstatus = self.csregs.read(csregs::HSTATUS);
+        let tvm_trapped = self.mode == PrivilegeMode::S
+            && ((csr == csregs::SATP && self.virt && hstatus & csregs::HSTATUS_VTVM != 0)
+                || ((csr == csregs::SATP || csr == csregs::HGATP)
+                    && !self.virt
+                    && mstatus & csregs::MSTATUS_TVM != 0));
+        if (writes && csr >> 10 == 3) || !(allowed || (self.virt && hs_allowed)) {
+            self.raise_illegal_inst(inst);
+            return None;
+        }
+        if !allowed || tvm_trapped {
+            if self.virt {
+                self.raise_virtual_inst(inst);
+            } else {
+                self.raise_illegal_inst(inst);
+            }
+            return None;
+        }
+        let fault = self
+            .counter_fault(csr)
+            .or_else(|| self.aia_fault(csr))
+            .or_else(|| self.extension_csr_fault(csr))
+            .or_else(|| self.debug_csr_fault(csr));
+        if let Some(cause) = fault {
+            if cause == csregs::EXC_VIRTUAL_INST {
+                self.raise_virtual_inst(inst);
+            } else {
+                self.raise_illegal_inst(inst);
+            }
+            return None;
+        }
+        let has_vs_csr = matches!(csr, 0x100 | 0x104 | 0x105 | 0x140..=0x144 | 0x14d | 0x180);
+        if self.virt && has_vs_csr {
+            Some(csr + 0x100)
+        } else {
+            Some(csr)
+        }
+    }
+
+    // The counters (only time is implemented) and stimecmp are gated by the counter-enable
+    // CSRs of the more privileged modes, stimecmp also by menvcfg/henvcfg.STCE
+    fn counter_fault(&self, csr: AddressType) -> Option<AddressType> {
+        let (bit, is_timecmp) = match csr {
+            0xc00..=0xc1f => (1 << (csr - 0xc00), false),
+            csregs::STIMECMP | csregs::VSTIMECMP => (csregs::COUNTEREN_TM, true),
+            _ => return None,
+        };
+        if is_timecmp && !self.isa.has(isa::Extension::Sstc) {
+            return Some(csregs::EXC_ILLEGAL_INST);
+        }
+        if self.mode == PrivilegeMode::M {
+            return None;
+        }
+        let enabled = |counteren: AddressType, envcfg: AddressType| {
+            self.csregs.read(counteren) & bit != 0
+                && (!is_timecmp || self.csregs.read(envcfg) & csregs::ENVCFG_STCE != 0)
+        };
+        if !enabled(csregs::MCOUNTEREN, csregs::MENVCFG) {
+            Some(csregs::EXC_ILLEGAL_INST)
+        } else if self.virt && !enabled(csregs::HCOUNTEREN, csregs::HENVCFG) {
+            Some(csregs::EXC_VIRTUAL_INST)
+        } else if self.mode == PrivilegeMode::U && self.csregs.read(csregs::SCOUNTEREN) & bit == 0 {
+            if self.virt {
+                Some(csregs::EXC_VIRTUAL_INST)
+            } else {
+                Some(csregs::EXC_ILLEGAL_INST)
+            }
+        } else {
+            None
+        }
+    }
+
+    // The AIA CSRs need Smaia/Ssaia and *ireg only reaches the registers that exist.
+    // There are no guest interrupt files, so VS-mode can't use the S-level ones.
+    fn aia_fault(&self, csr: AddressType) -> Option<AddressType> {
+        let (ext, iselect) = match csr {
+            csregs::MISELECT | csregs::MTOPEI | csregs::MTOPI | csregs::MVIEN | csregs::MVIP => {
+                (isa::Extension::Smaia, None)
+            }
+            csregs::MIREG => (isa::Extension::Smaia, Some(csregs::MISELECT)),
+            csregs::SISELECT | csregs::STOPEI | csregs::STOPI => (isa::Extension::Ssaia, None),
+            csregs::SIREG => (isa::Extension::Ssaia, Some(csregs::SISELECT)),
+            _ => return None,
+        };
+        if !self.isa.has(ext) {
+            Some(csregs::EXC_ILLEGAL_INST)
+        } else if self.virt && ext == isa::Extension::Ssaia {
+            Some(csregs::EXC_VIRTUAL_INST)
+        } else if iselect.is_some_and(|iselect| !aia::iselect_valid(self.csregs.read(iselect))) {
+            Some(csregs::EXC_ILLEGAL_INST)
+        } else {
+            None
+        }
+    }
+
+    // CSRs that only exist with their extension
+    fn extension_csr_fault(&self, csr: AddressType) -> Option<AddressType> {
+        let ext = match csr {
+            csregs::MTVT | csregs::MNXTI | csregs::MINTSTATUS | csregs::MINTTHRESH => {
+                isa::Extension::Smclic
+            }
+            csregs::MNSCRATCH | csregs::MNEPC | csregs::MNCAUSE | csregs::MNSTATUS => {
+                isa::Extension::Smrnmi
+            }
+            csregs::TSELECT..=csregs::TINFO => isa::Extension::Sdtrig,
+            _ => return None,
+        };
+        if self.isa.has(ext) {
+            None
+        } else {
+            Some(csregs::EXC_ILLEGAL_INST)
+        }
+    }
+
+    fn write_csr(&mut self, csr: AddressType, val: AddressType) {
+        let mut wdata = val;
+        if csr == csregs::MISA {
+            // Clearing C is ignored when the next instruction would be misaligned
+            let misa = self.csregs.read(csregs::MISA);
+            if (wdata & csregs::MISA_C) == 0 && ((self.pc + 4) & 2) != 0 {
+                wdata |= misa & csregs::MISA_C;
+            }
+        }
+        self.csregs.write(csr, wdata);
     }
 
     fn execute(&mut self, inst: &inst_type::InstType) {
@@ -82,6 +815,14 @@ impl RVCore {
             InstID::ADDI => self.inst_addi(inst),
             InstID::ADDIW => self.inst_addiw(inst),
             InstID::ADDW => self.inst_addw(inst),
+            InstID::ADD_UW => self.inst_add_uw(inst),
+            InstID::AES64DS => self.inst_aes64ds(inst),
+            InstID::AES64DSM => self.inst_aes64dsm(inst),
+            InstID::AES64ES => self.inst_aes64es(inst),
+            InstID::AES64ESM => self.inst_aes64esm(inst),
+            InstID::AES64IM => self.inst_aes64im(inst),
+            InstID::AES64KS1I => self.inst_aes64ks1i(inst),
+            InstID::AES64KS2 => self.inst_aes64ks2(inst),
             InstID::AMOADD_D => self.inst_amoadd_d(inst),
             InstID::AMOADD_W => self.inst_amoadd_w(inst),
             InstID::AMOAND_D => self.inst_amoand_d(inst),
@@ -100,14 +841,47 @@ impl RVCore {
             InstID::AMOSWAP_W => self.inst_amoswap_w(inst),
             InstID::AMOXOR_D => self.inst_amoxor_d(inst),
             InstID::AMOXOR_W => self.inst_amoxor_w(inst),
+            InstID::AMOADD_B => self.inst_amoadd_b(inst),
+            InstID::AMOADD_H => self.inst_amoadd_h(inst),
+            InstID::AMOAND_B => self.inst_amoand_b(inst),
+            InstID::AMOAND_H => self.inst_amoand_h(inst),
+            InstID::AMOMAX_B => self.inst_amomax_b(inst),
+            InstID::AMOMAX_H => self.inst_amomax_h(inst),
+            InstID::AMOMAXU_B => self.inst_amomaxu_b(inst),
+            InstID::AMOMAXU_H => self.inst_amomaxu_h(inst),
+            InstID::AMOMIN_B => self.inst_amomin_b(inst),
+            InstID::AMOMIN_H => self.inst_amomin_h(inst),
+            InstID::AMOMINU_B => self.inst_amominu_b(inst),
+            InstID::AMOMINU_H => self.inst_amominu_h(inst),
+            InstID::AMOOR_B => self.inst_amoor_b(inst),
+            InstID::AMOOR_H => self.inst_amoor_h(inst),
+            InstID::AMOSWAP_B => self.inst_amoswap_b(inst),
+            InstID::AMOSWAP_H => self.inst_amoswap_h(inst),
+            InstID::AMOXOR_B => self.inst_amoxor_b(inst),
+            InstID::AMOXOR_H => self.inst_amoxor_h(inst),
+            InstID::AMOCAS_B => self.inst_amocas_b(inst),
+            InstID::AMOCAS_H => self.inst_amocas_h(inst),
+            InstID::AMOCAS_W => self.inst_amocas_w(inst),
+            InstID::AMOCAS_D => self.inst_amocas_d(inst),
+            InstID::AMOCAS_Q => self.inst_amocas_q(inst),
             InstID::AND => self.inst_and(inst),
+            InstID::ANDN => self.inst_andn(inst),
             InstID::ANDI => self.inst_andi(inst),
+            InstID::BCLR => self.inst_bclr(inst),
+            InstID::BCLRI => self.inst_bclri(inst),
             InstID::BEQ => self.inst_beq(inst),
+            InstID::BEXT => self.inst_bext(inst),
+            InstID::BEXTI => self.inst_bexti(inst),
             InstID::BLT => self.inst_blt(inst),
             InstID::BLTU => self.inst_bltu(inst),
             InstID::BGEU => self.inst_bgeu(inst),
             InstID::BGE => self.inst_bge(inst),
+            InstID::BINV => self.inst_binv(inst),
+            InstID::BINVI => self.inst_binvi(inst),
             InstID::BNE => self.inst_bne(inst),
+            InstID::BSET => self.inst_bset(inst),
+            InstID::BSETI => self.inst_bseti(inst),
+            InstID::BREV8 => self.inst_brev8(inst),
             InstID::C_ADD => self.inst_c_add(inst),
             InstID::C_ADDI => self.inst_c_addi(inst),
             InstID::C_ADDIW => self.inst_c_addiw(inst),
@@ -118,6 +892,7 @@ impl RVCore {
             InstID::C_ANDI => self.inst_c_andi(inst),
             InstID::C_BEQZ => self.inst_c_beqz(inst),
             InstID::C_BNEZ => self.inst_c_bnez(inst),
+            InstID::C_EBREAK => self.inst_ebreak(inst),
             InstID::C_FSDSP => self.inst_c_fsdsp(inst),
             InstID::C_J => self.inst_c_j(inst),
             InstID::C_JALR => self.inst_c_jalr(inst),
@@ -140,79 +915,186 @@ impl RVCore {
             InstID::C_SUBW => self.inst_c_subw(inst),
             InstID::C_SD => self.inst_c_sd(inst),
             InstID::C_XOR => self.inst_c_xor(inst),
+            InstID::C_LBU => self.inst_c_lbu(inst),
+            InstID::C_LHU => self.inst_c_lhu(inst),
+            InstID::C_LH => self.inst_c_lh(inst),
+            InstID::C_SB => self.inst_c_sb(inst),
+            InstID::C_SH => self.inst_c_sh(inst),
+            InstID::C_ZEXT_B => self.inst_c_zext_b(inst),
+            InstID::C_SEXT_B => self.inst_c_sext_b(inst),
+            InstID::C_ZEXT_H => self.inst_c_zext_h(inst),
+            InstID::C_SEXT_H => self.inst_c_sext_h(inst),
+            InstID::C_ZEXT_W => self.inst_c_zext_w(inst),
+            InstID::C_NOT => self.inst_c_not(inst),
+            InstID::C_MUL => self.inst_c_mul(inst),
+            InstID::CM_PUSH => self.inst_cm_push(inst),
+            InstID::CM_POP => self.inst_cm_pop(inst),
+            InstID::CM_POPRETZ => self.inst_cm_popretz(inst),
+            InstID::CM_POPRET => self.inst_cm_popret(inst),
+            InstID::CM_MVSA01 => self.inst_cm_mvsa01(inst),
+            InstID::CM_MVA01S => self.inst_cm_mva01s(inst),
+            InstID::CM_JT => self.inst_cm_jt(inst),
+            InstID::CM_JALT => self.inst_cm_jalt(inst),
+            InstID::CBO_CLEAN => self.inst_cbo_clean(inst),
+            InstID::CBO_FLUSH => self.inst_cbo_flush(inst),
+            InstID::CBO_INVAL => self.inst_cbo_inval(inst),
+            InstID::CBO_ZERO => self.inst_cbo_zero(inst),
+            InstID::CLMUL => self.inst_clmul(inst),
+            InstID::CLMULH => self.inst_clmulh(inst),
+            InstID::CLMULR => self.inst_clmulr(inst),
+            InstID::CLZ => self.inst_clz(inst),
+            InstID::CLZW => self.inst_clzw(inst),
+            InstID::CPOP => self.inst_cpop(inst),
+            InstID::CPOPW => self.inst_cpopw(inst),
             InstID::CSRRCI => self.inst_csrrci(inst),
             InstID::CSRRS => self.inst_csrrs(inst),
             InstID::CSRRSI => self.inst_csrrsi(inst),
             InstID::CSRRW => self.inst_csrrw(inst),
             InstID::CSRRWI => self.inst_csrrwi(inst),
+            InstID::CTZ => self.inst_ctz(inst),
+            InstID::CTZW => self.inst_ctzw(inst),
             InstID::DIV => self.inst_div(inst),
             InstID::DIVU => self.inst_divu(inst),
             InstID::DIVUW => self.inst_divuw(inst),
             InstID::DIVW => self.inst_divw(inst),
+            InstID::DRET => self.inst_dret(inst),
+            InstID::EBREAK => self.inst_ebreak(inst),
             InstID::ECALL => self.inst_ecall(inst),
             InstID::FADD_D => self.inst_fadd_d(inst),
             InstID::FADD_S => self.inst_fadd_s(inst),
+            InstID::FADD_H => self.inst_fadd_h(inst),
             InstID::FCLASS_D => self.inst_fclass_d(inst),
             InstID::FCLASS_S => self.inst_fclass_s(inst),
+            InstID::FCLASS_H => self.inst_fclass_h(inst),
             InstID::FCVT_D_L => self.inst_fcvt_d_l(inst),
             InstID::FCVT_D_LU => self.inst_fcvt_d_lu(inst),
             InstID::FCVT_D_S => self.inst_fcvt_d_s(inst),
             InstID::FCVT_D_W => self.inst_fcvt_d_w(inst),
             InstID::FCVT_D_WU => self.inst_fcvt_d_wu(inst),
+            InstID::FCVT_D_H => self.inst_fcvt_d_h(inst),
             InstID::FCVT_L_D => self.inst_fcvt_l_d(inst),
             InstID::FCVT_L_S => self.inst_fcvt_l_s(inst),
+            InstID::FCVT_L_H => self.inst_fcvt_l_h(inst),
             InstID::FCVT_LU_D => self.inst_fcvt_lu_d(inst),
             InstID::FCVT_LU_S => self.inst_fcvt_lu_s(inst),
+            InstID::FCVT_LU_H => self.inst_fcvt_lu_h(inst),
             InstID::FCVT_S_D => self.inst_fcvt_s_d(inst),
             InstID::FCVT_S_L => self.inst_fcvt_s_l(inst),
             InstID::FCVT_S_LU => self.inst_fcvt_s_lu(inst),
             InstID::FCVT_S_W => self.inst_fcvt_s_w(inst),
             InstID::FCVT_S_WU => self.inst_fcvt_s_wu(inst),
+            InstID::FCVT_H_WU => self.inst_fcvt_h_wu(inst),
+            InstID::FCVT_H_W => self.inst_fcvt_h_w(inst),
+            InstID::FCVT_H_S => self.inst_fcvt_h_s(inst),
+            InstID::FCVT_H_LU => self.inst_fcvt_h_lu(inst),
+            InstID::FCVT_H_L => self.inst_fcvt_h_l(inst),
+            InstID::FCVT_H_D => self.inst_fcvt_h_d(inst),
+            InstID::FCVT_S_H => self.inst_fcvt_s_h(inst),
             InstID::FCVT_W_D => self.inst_fcvt_w_d(inst),
             InstID::FCVT_W_S => self.inst_fcvt_w_s(inst),
+            InstID::FCVT_W_H => self.inst_fcvt_w_h(inst),
             InstID::FCVT_WU_D => self.inst_fcvt_wu_d(inst),
             InstID::FCVT_WU_S => self.inst_fcvt_wu_s(inst),
+            InstID::FCVT_WU_H => self.inst_fcvt_wu_h(inst),
             InstID::FDIV_D => self.inst_fdiv_d(inst),
             InstID::FDIV_S => self.inst_fdiv_s(inst),
+            InstID::FDIV_H => self.inst_fdiv_h(inst),
             InstID::FMADD_D => self.inst_fmadd_d(inst),
             InstID::FMADD_S => self.inst_fmadd_s(inst),
+            InstID::FMADD_H => self.inst_fmadd_h(inst),
             InstID::FMAX_D => self.inst_fmax_d(inst),
             InstID::FMAX_S => self.inst_fmax_s(inst),
+            InstID::FMAX_H => self.inst_fmax_h(inst),
             InstID::FMIN_D => self.inst_fmin_d(inst),
             InstID::FMIN_S => self.inst_fmin_s(inst),
+            InstID::FMIN_H => self.inst_fmin_h(inst),
             InstID::FMSUB_D => self.inst_fmsub_d(inst),
             InstID::FMSUB_S => self.inst_fmsub_s(inst),
+            InstID::FMSUB_H => self.inst_fmsub_h(inst),
             InstID::FNMADD_D => self.inst_fnmadd_d(inst),
             InstID::FNMADD_S => self.inst_fnmadd_s(inst),
+            InstID::FNMADD_H => self.inst_fnmadd_h(inst),
             InstID::FNMSUB_D => self.inst_fnmsub_d(inst),
             InstID::FNMSUB_S => self.inst_fnmsub_s(inst),
+            InstID::FNMSUB_H => self.inst_fnmsub_h(inst),
             InstID::FSQRT_D => self.inst_fsqrt_d(inst),
             InstID::FSQRT_S => self.inst_fsqrt_s(inst),
+            InstID::FSQRT_H => self.inst_fsqrt_h(inst),
             InstID::FSGNJ_D => self.inst_fsgnj_d(inst),
             InstID::FSGNJ_S => self.inst_fsgnj_s(inst),
+            InstID::FSGNJ_H => self.inst_fsgnj_h(inst),
             InstID::FSGNJN_D => self.inst_fsgnjn_d(inst),
             InstID::FSGNJN_S => self.inst_fsgnjn_s(inst),
+            InstID::FSGNJN_H => self.inst_fsgnjn_h(inst),
             InstID::FSGNJX_D => self.inst_fsgnjx_d(inst),
             InstID::FSGNJX_S => self.inst_fsgnjx_s(inst),
+            InstID::FSGNJX_H => self.inst_fsgnjx_h(inst),
             InstID::FENCE => self.inst_fence(inst),
+            InstID::FENCE_I => self.inst_fence_i(inst),
             InstID::FEQ_D => self.inst_feq_d(inst),
             InstID::FEQ_S => self.inst_feq_s(inst),
+            InstID::FEQ_H => self.inst_feq_h(inst),
             InstID::FLD => self.inst_fld(inst),
             InstID::FLE_D => self.inst_fle_d(inst),
             InstID::FLE_S => self.inst_fle_s(inst),
+            InstID::FLE_H => self.inst_fle_h(inst),
             InstID::FLT_D => self.inst_flt_d(inst),
             InstID::FLT_S => self.inst_flt_s(inst),
+            InstID::FLT_H => self.inst_flt_h(inst),
             InstID::FLW => self.inst_flw(inst),
+            InstID::FLH => self.inst_flh(inst),
             InstID::FSD => self.inst_fsd(inst),
             InstID::FSW => self.inst_fsw(inst),
+            InstID::FSH => self.inst_fsh(inst),
             InstID::FMUL_D => self.inst_fmul_d(inst),
             InstID::FMUL_S => self.inst_fmul_s(inst),
+            InstID::FMUL_H => self.inst_fmul_h(inst),
             InstID::FMV_D_X => self.inst_fmv_d_x(inst),
+            InstID::FMV_H_X => self.inst_fmv_h_x(inst),
             InstID::FMV_W_X => self.inst_fmv_w_x(inst),
             InstID::FMV_X_D => self.inst_fmv_x_d(inst),
+            InstID::FMV_X_H => self.inst_fmv_x_h(inst),
             InstID::FMV_X_W => self.inst_fmv_x_w(inst),
             InstID::FSUB_D => self.inst_fsub_d(inst),
             InstID::FSUB_S => self.inst_fsub_s(inst),
+            InstID::FSUB_H => self.inst_fsub_h(inst),
+            InstID::HFENCE_GVMA => self.inst_hfence_gvma(inst),
+            InstID::HFENCE_VVMA => self.inst_hfence_vvma(inst),
+            InstID::HLV_B => self.inst_hlv_b(inst),
+            InstID::HLV_BU => self.inst_hlv_bu(inst),
+            InstID::HLV_D => self.inst_hlv_d(inst),
+            InstID::HLV_H => self.inst_hlv_h(inst),
+            InstID::HLV_HU => self.inst_hlv_hu(inst),
+            InstID::HLV_W => self.inst_hlv_w(inst),
+            InstID::HLV_WU => self.inst_hlv_wu(inst),
+            InstID::HLVX_HU => self.inst_hlvx_hu(inst),
+            InstID::HLVX_WU => self.inst_hlvx_wu(inst),
+            InstID::HSV_B => self.inst_hsv_b(inst),
+            InstID::HSV_D => self.inst_hsv_d(inst),
+            InstID::HSV_H => self.inst_hsv_h(inst),
+            InstID::HSV_W => self.inst_hsv_w(inst),
+            InstID::FCVTMOD_W_D => self.inst_fcvtmod_w_d(inst),
+            InstID::FLEQ_D => self.inst_fleq_d(inst),
+            InstID::FLEQ_S => self.inst_fleq_s(inst),
+            InstID::FLEQ_H => self.inst_fleq_h(inst),
+            InstID::FLI_D => self.inst_fli_d(inst),
+            InstID::FLI_S => self.inst_fli_s(inst),
+            InstID::FLI_H => self.inst_fli_h(inst),
+            InstID::FLTQ_D => self.inst_fltq_d(inst),
+            InstID::FLTQ_S => self.inst_fltq_s(inst),
+            InstID::FLTQ_H => self.inst_fltq_h(inst),
+            InstID::FMAXM_D => self.inst_fmaxm_d(inst),
+            InstID::FMAXM_S => self.inst_fmaxm_s(inst),
+            InstID::FMAXM_H => self.inst_fmaxm_h(inst),
+            InstID::FMINM_D => self.inst_fminm_d(inst),
+            InstID::FMINM_S => self.inst_fminm_s(inst),
+            InstID::FMINM_H => self.inst_fminm_h(inst),
+            InstID::FROUND_D => self.inst_fround_d(inst),
+            InstID::FROUND_S => self.inst_fround_s(inst),
+            InstID::FROUND_H => self.inst_fround_h(inst),
+            InstID::FROUNDNX_D => self.inst_froundnx_d(inst),
+            InstID::FROUNDNX_S => self.inst_froundnx_s(inst),
+            InstID::FROUNDNX_H => self.inst_froundnx_h(inst),
             InstID::JAL => self.inst_jal(inst),
             InstID::JALR => self.inst_jalr(inst),
             InstID::LB => self.inst_lb(inst),
@@ -225,27 +1107,66 @@ impl RVCore {
             InstID::LUI => self.inst_lui(inst),
             InstID::LW => self.inst_lw(inst),
             InstID::LWU => self.inst_lwu(inst),
+            InstID::MAX => self.inst_max(inst),
+            InstID::MAXU => self.inst_maxu(inst),
+            InstID::MIN => self.inst_min(inst),
+            InstID::MINU => self.inst_minu(inst),
             InstID::MUL => self.inst_mul(inst),
             InstID::MULH => self.inst_mulh(inst),
             InstID::MULHSU => self.inst_mulhsu(inst),
             InstID::MULHU => self.inst_mulhu(inst),
             InstID::MULW => self.inst_mulw(inst),
             InstID::MRET => self.inst_mret(inst),
+            InstID::MNRET => self.inst_mnret(inst),
             InstID::OR => self.inst_or(inst),
             InstID::ORI => self.inst_ori(inst),
+            InstID::ORC_B => self.inst_orc_b(inst),
+            InstID::PACK => self.inst_pack(inst),
+            InstID::PACKH => self.inst_packh(inst),
+            InstID::PACKW => self.inst_packw(inst),
+            InstID::ORN => self.inst_orn(inst),
             InstID::REM => self.inst_rem(inst),
             InstID::REMU => self.inst_remu(inst),
             InstID::REMUW => self.inst_remuw(inst),
             InstID::REMW => self.inst_remw(inst),
+            InstID::REV8 => self.inst_rev8(inst),
+            InstID::ROL => self.inst_rol(inst),
+            InstID::ROLW => self.inst_rolw(inst),
+            InstID::ROR => self.inst_ror(inst),
+            InstID::RORI => self.inst_rori(inst),
+            InstID::RORIW => self.inst_roriw(inst),
+            InstID::RORW => self.inst_rorw(inst),
             InstID::SB => self.inst_sb(inst),
             InstID::SC_D => self.inst_sc_d(inst),
             InstID::SC_W => self.inst_sc_w(inst),
             InstID::SD => self.inst_sd(inst),
+            InstID::SEXT_B => self.inst_sext_b(inst),
+            InstID::SEXT_H => self.inst_sext_h(inst),
+            InstID::SFENCE_VMA => self.inst_sfence_vma(inst),
+            InstID::SHA256SIG0 => self.inst_sha256sig0(inst),
+            InstID::SHA256SIG1 => self.inst_sha256sig1(inst),
+            InstID::SHA256SUM0 => self.inst_sha256sum0(inst),
+            InstID::SHA256SUM1 => self.inst_sha256sum1(inst),
+            InstID::SHA512SIG0 => self.inst_sha512sig0(inst),
+            InstID::SHA512SIG1 => self.inst_sha512sig1(inst),
+            InstID::SHA512SUM0 => self.inst_sha512sum0(inst),
+            InstID::SHA512SUM1 => self.inst_sha512sum1(inst),
+            InstID::SM3P0 => self.inst_sm3p0(inst),
+            InstID::SM3P1 => self.inst_sm3p1(inst),
+            InstID::SM4ED => self.inst_sm4ed(inst),
+            InstID::SM4KS => self.inst_sm4ks(inst),
+            InstID::SH1ADD => self.inst_sh1add(inst),
+            InstID::SH1ADD_UW => self.inst_sh1add_uw(inst),
+            InstID::SH2ADD => self.inst_sh2add(inst),
+            InstID::SH2ADD_UW => self.inst_sh2add_uw(inst),
+            InstID::SH3ADD => self.inst_sh3add(inst),
+            InstID::SH3ADD_UW => self.inst_sh3add_uw(inst),
             InstID::SH => self.inst_sh(inst),
             InstID::SW => self.inst_sw(inst),
             InstID::SLL => self.inst_sll(inst),
             InstID::SLLI => self.inst_slli(inst),
             InstID::SLLIW => self.inst_slliw(inst),
+            InstID::SLLI_UW => self.inst_slli_uw(inst),
             InstID::SLLW => self.inst_sllw(inst),
             InstID::SLT => self.inst_slt(inst),
             InstID::SLTI => self.inst_slti(inst),
@@ -259,30 +1180,244 @@ impl RVCore {
             InstID::SRAI => self.inst_srai(inst),
             InstID::SRAIW => self.inst_sraiw(inst),
             InstID::SRAW => self.inst_sraw(inst),
+            InstID::SRET => self.inst_sret(inst),
             InstID::SUB => self.inst_sub(inst),
             InstID::SUBW => self.inst_subw(inst),
+            InstID::VAADD => self.inst_vaadd(inst),
+            InstID::VAADDU => self.inst_vaaddu(inst),
+            InstID::VADC => self.inst_vadc(inst),
+            InstID::VADD => self.inst_vadd(inst),
+            InstID::VAND => self.inst_vand(inst),
+            InstID::VASUB => self.inst_vasub(inst),
+            InstID::VASUBU => self.inst_vasubu(inst),
+            InstID::VCOMPRESS => self.inst_vcompress(inst),
+            InstID::VCPOP_M => self.inst_vcpop_m(inst),
+            InstID::VDIV => self.inst_vdiv(inst),
+            InstID::VDIVU => self.inst_vdivu(inst),
+            InstID::VFADD => self.inst_vfadd(inst),
+            InstID::VFCLASS_V => self.inst_vfclass_v(inst),
+            InstID::VFCVT_F_X_V => self.inst_vfcvt_f_x_v(inst),
+            InstID::VFCVT_F_XU_V => self.inst_vfcvt_f_xu_v(inst),
+            InstID::VFCVT_RTZ_X_F_V => self.inst_vfcvt_rtz_x_f_v(inst),
+            InstID::VFCVT_RTZ_XU_F_V => self.inst_vfcvt_rtz_xu_f_v(inst),
+            InstID::VFCVT_X_F_V => self.inst_vfcvt_x_f_v(inst),
+            InstID::VFCVT_XU_F_V => self.inst_vfcvt_xu_f_v(inst),
+            InstID::VFDIV => self.inst_vfdiv(inst),
+            InstID::VFIRST_M => self.inst_vfirst_m(inst),
+            InstID::VFMACC => self.inst_vfmacc(inst),
+            InstID::VFMADD => self.inst_vfmadd(inst),
+            InstID::VFMAX => self.inst_vfmax(inst),
+            InstID::VFMERGE => self.inst_vfmerge(inst),
+            InstID::VFMIN => self.inst_vfmin(inst),
+            InstID::VFMSAC => self.inst_vfmsac(inst),
+            InstID::VFMSUB => self.inst_vfmsub(inst),
+            InstID::VFMUL => self.inst_vfmul(inst),
+            InstID::VFMV_F_S => self.inst_vfmv_f_s(inst),
+            InstID::VFMV_S_F => self.inst_vfmv_s_f(inst),
+            InstID::VFMV_V_F => self.inst_vfmv_v_f(inst),
+            InstID::VFNCVT_F_F_W => self.inst_vfncvt_f_f_w(inst),
+            InstID::VFNCVT_F_X_W => self.inst_vfncvt_f_x_w(inst),
+            InstID::VFNCVT_F_XU_W => self.inst_vfncvt_f_xu_w(inst),
+            InstID::VFNCVT_ROD_F_F_W => self.inst_vfncvt_rod_f_f_w(inst),
+            InstID::VFNCVT_RTZ_X_F_W => self.inst_vfncvt_rtz_x_f_w(inst),
+            InstID::VFNCVT_RTZ_XU_F_W => self.inst_vfncvt_rtz_xu_f_w(inst),
+            InstID::VFNCVT_X_F_W => self.inst_vfncvt_x_f_w(inst),
+            InstID::VFNCVT_XU_F_W => self.inst_vfncvt_xu_f_w(inst),
+            InstID::VFNMACC => self.inst_vfnmacc(inst),
+            InstID::VFNMADD => self.inst_vfnmadd(inst),
+            InstID::VFNMSAC => self.inst_vfnmsac(inst),
+            InstID::VFNMSUB => self.inst_vfnmsub(inst),
+            InstID::VFRDIV => self.inst_vfrdiv(inst),
+            InstID::VFREC7_V => self.inst_vfrec7_v(inst),
+            InstID::VFREDMAX => self.inst_vfredmax(inst),
+            InstID::VFREDMIN => self.inst_vfredmin(inst),
+            InstID::VFREDOSUM => self.inst_vfredosum(inst),
+            InstID::VFREDUSUM => self.inst_vfredusum(inst),
+            InstID::VFRSQRT7_V => self.inst_vfrsqrt7_v(inst),
+            InstID::VFRSUB => self.inst_vfrsub(inst),
+            InstID::VFSGNJ => self.inst_vfsgnj(inst),
+            InstID::VFSGNJN => self.inst_vfsgnjn(inst),
+            InstID::VFSGNJX => self.inst_vfsgnjx(inst),
+            InstID::VFSLIDE1DOWN => self.inst_vfslide1down(inst),
+            InstID::VFSLIDE1UP => self.inst_vfslide1up(inst),
+            InstID::VFSQRT_V => self.inst_vfsqrt_v(inst),
+            InstID::VFSUB => self.inst_vfsub(inst),
+            InstID::VFWADD => self.inst_vfwadd(inst),
+            InstID::VFWADD_W => self.inst_vfwadd_w(inst),
+            InstID::VFWCVT_F_F_V => self.inst_vfwcvt_f_f_v(inst),
+            InstID::VFWCVT_F_X_V => self.inst_vfwcvt_f_x_v(inst),
+            InstID::VFWCVT_F_XU_V => self.inst_vfwcvt_f_xu_v(inst),
+            InstID::VFWCVT_RTZ_X_F_V => self.inst_vfwcvt_rtz_x_f_v(inst),
+            InstID::VFWCVT_RTZ_XU_F_V => self.inst_vfwcvt_rtz_xu_f_v(inst),
+            InstID::VFWCVT_X_F_V => self.inst_vfwcvt_x_f_v(inst),
+            InstID::VFWCVT_XU_F_V => self.inst_vfwcvt_xu_f_v(inst),
+            InstID::VFWMACC => self.inst_vfwmacc(inst),
+            InstID::VFWMSAC => self.inst_vfwmsac(inst),
+            InstID::VFWMUL => self.inst_vfwmul(inst),
+            InstID::VFWNMACC => self.inst_vfwnmacc(inst),
+            InstID::VFWNMSAC => self.inst_vfwnmsac(inst),
+            InstID::VFWREDOSUM => self.inst_vfwredosum(inst),
+            InstID::VFWREDUSUM => self.inst_vfwredusum(inst),
+            InstID::VFWSUB => self.inst_vfwsub(inst),
+            InstID::VFWSUB_W => self.inst_vfwsub_w(inst),
+            InstID::VID_V => self.inst_vid_v(inst),
+            InstID::VIOTA_M => self.inst_viota_m(inst),
+            InstID::VLE_V => self.inst_vle_v(inst),
+            InstID::VLEFF_V => self.inst_vleff_v(inst),
+            InstID::VLM_V => self.inst_vlm_v(inst),
+            InstID::VLOXEI_V => self.inst_vloxei_v(inst),
+            InstID::VLR_V => self.inst_vlr_v(inst),
+            InstID::VLSE_V => self.inst_vlse_v(inst),
+            InstID::VLUXEI_V => self.inst_vluxei_v(inst),
+            InstID::VMACC => self.inst_vmacc(inst),
+            InstID::VMADC => self.inst_vmadc(inst),
+            InstID::VMADD => self.inst_vmadd(inst),
+            InstID::VMAND => self.inst_vmand(inst),
+            InstID::VMANDN => self.inst_vmandn(inst),
+            InstID::VMAX => self.inst_vmax(inst),
+            InstID::VMAXU => self.inst_vmaxu(inst),
+            InstID::VMERGE => self.inst_vmerge(inst),
+            InstID::VMFEQ => self.inst_vmfeq(inst),
+            InstID::VMFGE => self.inst_vmfge(inst),
+            InstID::VMFGT => self.inst_vmfgt(inst),
+            InstID::VMFLE => self.inst_vmfle(inst),
+            InstID::VMFLT => self.inst_vmflt(inst),
+            InstID::VMFNE => self.inst_vmfne(inst),
+            InstID::VMIN => self.inst_vmin(inst),
+            InstID::VMINU => self.inst_vminu(inst),
+            InstID::VMNAND => self.inst_vmnand(inst),
+            InstID::VMNOR => self.inst_vmnor(inst),
+            InstID::VMOR => self.inst_vmor(inst),
+            InstID::VMORN => self.inst_vmorn(inst),
+            InstID::VMSBC => self.inst_vmsbc(inst),
+            InstID::VMSBF_M => self.inst_vmsbf_m(inst),
+            InstID::VMSEQ => self.inst_vmseq(inst),
+            InstID::VMSGT => self.inst_vmsgt(inst),
+            InstID::VMSGTU => self.inst_vmsgtu(inst),
+            InstID::VMSIF_M => self.inst_vmsif_m(inst),
+            InstID::VMSLE => self.inst_vmsle(inst),
+            InstID::VMSLEU => self.inst_vmsleu(inst),
+            InstID::VMSLT => self.inst_vmslt(inst),
+            InstID::VMSLTU => self.inst_vmsltu(inst),
+            InstID::VMSNE => self.inst_vmsne(inst),
+            InstID::VMSOF_M => self.inst_vmsof_m(inst),
+            InstID::VMUL => self.inst_vmul(inst),
+            InstID::VMULH => self.inst_vmulh(inst),
+            InstID::VMULHSU => self.inst_vmulhsu(inst),
+            InstID::VMULHU => self.inst_vmulhu(inst),
+            InstID::VMV_S_X => self.inst_vmv_s_x(inst),
+            InstID::VMV_V => self.inst_vmv_v(inst),
+            InstID::VMV_X_S => self.inst_vmv_x_s(inst),
+            InstID::VMVNR_V => self.inst_vmvnr_v(inst),
+            InstID::VMXNOR => self.inst_vmxnor(inst),
+            InstID::VMXOR => self.inst_vmxor(inst),
+            InstID::VNCLIP => self.inst_vnclip(inst),
+            InstID::VNCLIPU => self.inst_vnclipu(inst),
+            InstID::VNMSAC => self.inst_vnmsac(inst),
+            InstID::VNMSUB => self.inst_vnmsub(inst),
+            InstID::VNSRA => self.inst_vnsra(inst),
+            InstID::VNSRL => self.inst_vnsrl(inst),
+            InstID::VOR => self.inst_vor(inst),
+            InstID::VREDAND => self.inst_vredand(inst),
+            InstID::VREDMAX => self.inst_vredmax(inst),
+            InstID::VREDMAXU => self.inst_vredmaxu(inst),
+            InstID::VREDMIN => self.inst_vredmin(inst),
+            InstID::VREDMINU => self.inst_vredminu(inst),
+            InstID::VREDOR => self.inst_vredor(inst),
+            InstID::VREDSUM => self.inst_vredsum(inst),
+            InstID::VREDXOR => self.inst_vredxor(inst),
+            InstID::VREM => self.inst_vrem(inst),
+            InstID::VREMU => self.inst_vremu(inst),
+            InstID::VRGATHER => self.inst_vrgather(inst),
+            InstID::VRGATHEREI16 => self.inst_vrgatherei16(inst),
+            InstID::VRSUB => self.inst_vrsub(inst),
+            InstID::VSADD => self.inst_vsadd(inst),
+            InstID::VSADDU => self.inst_vsaddu(inst),
+            InstID::VSBC => self.inst_vsbc(inst),
+            InstID::VSE_V => self.inst_vse_v(inst),
+            InstID::VSETIVLI => self.inst_vsetivli(inst),
+            InstID::VSETVL => self.inst_vsetvl(inst),
+            InstID::VSETVLI => self.inst_vsetvli(inst),
+            InstID::VSEXT_VF2 => self.inst_vsext_vf2(inst),
+            InstID::VSEXT_VF4 => self.inst_vsext_vf4(inst),
+            InstID::VSEXT_VF8 => self.inst_vsext_vf8(inst),
+            InstID::VSLIDE1DOWN => self.inst_vslide1down(inst),
+            InstID::VSLIDE1UP => self.inst_vslide1up(inst),
+            InstID::VSLIDEDOWN => self.inst_vslidedown(inst),
+            InstID::VSLIDEUP => self.inst_vslideup(inst),
+            InstID::VSLL => self.inst_vsll(inst),
+            InstID::VSM_V => self.inst_vsm_v(inst),
+            InstID::VSMUL => self.inst_vsmul(inst),
+            InstID::VSOXEI_V => self.inst_vsoxei_v(inst),
+            InstID::VSR_V => self.inst_vsr_v(inst),
+            InstID::VSRA => self.inst_vsra(inst),
+            InstID::VSRL => self.inst_vsrl(inst),
+            InstID::VSSE_V => self.inst_vsse_v(inst),
+            InstID::VSSRA => self.inst_vssra(inst),
+            InstID::VSSRL => self.inst_vssrl(inst),
+            InstID::VSSUB => self.inst_vssub(inst),
+            InstID::VSSUBU => self.inst_vssubu(inst),
+            InstID::VSUB => self.inst_vsub(inst),
+            InstID::VSUXEI_V => self.inst_vsuxei_v(inst),
+            InstID::VWADD => self.inst_vwadd(inst),
+            InstID::VWADD_W => self.inst_vwadd_w(inst),
+            InstID::VWADDU => self.inst_vwaddu(inst),
+            InstID::VWADDU_W => self.inst_vwaddu_w(inst),
+            InstID::VWMACC => self.inst_vwmacc(inst),
+            InstID::VWMACCSU => self.inst_vwmaccsu(inst),
+            InstID::VWMACCU => self.inst_vwmaccu(inst),
+            InstID::VWMACCUS => self.inst_vwmaccus(inst),
+            InstID::VWMUL => self.inst_vwmul(inst),
+            InstID::VWMULSU => self.inst_vwmulsu(inst),
+            InstID::VWMULU => self.inst_vwmulu(inst),
+            InstID::VWREDSUM => self.inst_vwredsum(inst),
+            InstID::VWREDSUMU => self.inst_vwredsumu(inst),
+            InstID::VWSUB => self.inst_vwsub(inst),
+            InstID::VWSUB_W => self.inst_vwsub_w(inst),
+            InstID::VWSUBU => self.inst_vwsubu(inst),
+            InstID::VWSUBU_W => self.inst_vwsubu_w(inst),
+            InstID::VXOR => self.inst_vxor(inst),
+            InstID::VZEXT_VF2 => self.inst_vzext_vf2(inst),
+            InstID::VZEXT_VF4 => self.inst_vzext_vf4(inst),
+            InstID::VZEXT_VF8 => self.inst_vzext_vf8(inst),
+            InstID::C_MOP => self.inst_c_mop(inst),
+            InstID::C_NTL_P1 => self.inst_c_ntl(inst),
+            InstID::C_NTL_PALL => self.inst_c_ntl(inst),
+            InstID::C_NTL_S1 => self.inst_c_ntl(inst),
+            InstID::C_NTL_ALL => self.inst_c_ntl(inst),
+            InstID::CZERO_EQZ => self.inst_czero_eqz(inst),
+            InstID::CZERO_NEZ => self.inst_czero_nez(inst),
+            InstID::MOP_R => self.inst_mop_r(inst),
+            InstID::MOP_RR => self.inst_mop_rr(inst),
+            InstID::NTL_P1 => self.inst_ntl(inst),
+            InstID::NTL_PALL => self.inst_ntl(inst),
+            InstID::NTL_S1 => self.inst_ntl(inst),
+            InstID::NTL_ALL => self.inst_ntl(inst),
+            InstID::PAUSE => self.inst_pause(inst),
+            InstID::PREFETCH_I => self.inst_prefetch(inst),
+            InstID::PREFETCH_R => self.inst_prefetch(inst),
+            InstID::PREFETCH_W => self.inst_prefetch(inst),
             InstID::NOP => self.inst_nop(inst),
             InstID::WFI => self.inst_wfi(inst),
             InstID::XOR => self.inst_xor(inst),
             InstID::XORI => self.inst_xori(inst),
+            InstID::XPERM4 => self.inst_xperm4(inst),
+            InstID::XPERM8 => self.inst_xperm8(inst),
+            InstID::XNOR => self.inst_xnor(inst),
+            InstID::ZEXT_H => self.inst_zext_h(inst),
             InstID::INVALID => panic!("Execute: invalid instruction"),
         }
     }
 
     pub fn run(&mut self, num_steps: i32) {
         let mut step_count = 0;
-        while step_count < num_steps {
+        while step_count < num_steps && self.critical_error.is_none() {
             self.step();
             step_count += 1;
         }
     }
 
     fn write_memory(&mut self, address: AddressType, data: &mut [u8]) {
-        let UART_BASE = 0x1000_0000;
-        if address == UART_BASE {
-            println!("{:x}", data[0]);
-            println!("JC_DEBUG: uart access");
-        }
         self.access_memory(address, data, MemoryOperation::WRITE, false);
     }
 
@@ -298,7 +1433,99 @@ impl RVCore {
         self.access_memory(address, data, MemoryOperation::READ, true);
     }
 
-    fn access_memory(&mut self, addr_in: AddressType, data_in: &mut [u8], op_in: MemoryOperation, is_amo_in: bool) {
+    // Virtual memory access, AMOs and cache-block operations need write permission
+    fn access_memory(
+        &mut self,
+        addr_in: AddressType,
+        data_in: &mut [u8],
+        op_in: MemoryOperation,
+        is_amo_in: bool,
+    ) {
+        // Nothing more is accessed once an access of the instruction faulted
+        if self.trap_taken {
+            return;
+        }
+        let access = if op_in == MemoryOperation::READ && !is_amo_in {
+            AccessType::Load
+        } else {
+            AccessType::Store
+        };
+        // Stores match their data up front, loads once the data is read
+        let data = RVCore::trigger_data(data_in);
+        let (kind, store_data) = if op_in == MemoryOperation::READ {
+            (triggers::MCONTROL6_LOAD, None)
+        } else {
+            (triggers::MCONTROL6_STORE, Some(data))
+        };
+        if let Some(action) = self.trigger_fires(kind, addr_in, store_data) {
+            self.take_trigger(action, addr_in);
+            return;
+        }
+        let translation = self.data_translation();
+        if let Some(paddr) = self.translate(addr_in, access, translation) {
+            self.access_physical(paddr, data_in, op_in, is_amo_in);
+            if store_data.is_none() {
+                let load_data = Some(RVCore::trigger_data(data_in));
+                if let Some(action) = self.trigger_fires(kind, addr_in, load_data) {
+                    self.take_trigger(action, addr_in);
+                }
+            }
+        }
+    }
+
+    // Little-endian value of the accessed bytes, compared by data triggers
+    fn trigger_data(data: &[u8]) -> AddressType {
+        data.iter()
+            .rev()
+            .fold(0, |val, byte| (val << 8) | *byte as AddressType)
+    }
+
+    // Action of the mcontrol6 triggers of Sdtrig matching an access, none fire in debug mode
+    fn trigger_fires(
+        &mut self,
+        kind: AddressType,
+        addr: AddressType,
+        data: Option<AddressType>,
+    ) -> Option<AddressType> {
+        if !self.isa.has(isa::Extension::Sdtrig) || self.debug_mode {
+            return None;
+        }
+        let breakpoints = self.breakpoint_triggers_enabled();
+        let (mode, virt) = (self.mode, self.virt);
+        self.csregs
+            .triggers()
+            .match_access(kind, mode, virt, addr, data, breakpoints)
+    }
+
+    fn take_trigger(&mut self, action: AddressType, tval: AddressType) {
+        if action == triggers::ACTION_DEBUG_MODE {
+            self.enter_debug_mode(csregs::DCSR_CAUSE_TRIGGER);
+        } else {
+            self.raise_exception(csregs::EXC_BREAKPOINT, tval);
+        }
+    }
+
+    // Triggers don't fire in the handler of their own breakpoint exception: M-mode with
+    // MIE clear, or S-mode with SIE clear when breakpoints are delegated
+    fn breakpoint_triggers_enabled(&self) -> bool {
+        let mstatus = self.csregs.read(csregs::MSTATUS);
+        let medeleg = self.csregs.read(csregs::MEDELEG);
+        match self.mode {
+            PrivilegeMode::M => mstatus & csregs::MSTATUS_MIE != 0,
+            PrivilegeMode::S if !self.virt && (medeleg >> csregs::EXC_BREAKPOINT) & 1 != 0 => {
+                mstatus & csregs::MSTATUS_SIE != 0
+            }
+            _ => true,
+        }
+    }
+
+    fn access_physical(
+        &mut self,
+        addr_in: AddressType,
+        data_in: &mut [u8],
+        op_in: MemoryOperation,
+        is_amo_in: bool,
+    ) {
         let mut payload = Payload {
             addr: addr_in,
             data: data_in.to_vec(),
@@ -333,57 +1560,272 @@ impl RVCore {
     }
 
     fn update_fflags(&mut self, flags: &ExceptionFlags) {
-        let mut val = 0;
-        if flags.is_inexact() {
-            val |= 1;
-        }
-        if flags.is_underflow() {
-            val |= 2;
+        // softfloat's exception bits line up with the fflags layout
+        self.accrue_fflags(flags.to_bits() as AddressType);
+    }
+
+    fn fmin_max<T: Float + Copy>(&mut self, rs1_val: T, rs2_val: T, is_max: bool) -> T {
+        // Quiet comparison, only signaling NaN inputs raise NV
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let less = rs1_val.lt_quiet(rs2_val);
+        flag.get();
+        self.update_fflags(&flag);
+
+        if rs1_val.is_nan() && rs2_val.is_nan() {
+            T::quiet_nan()
+        } else if rs1_val.is_nan() {
+            rs2_val
+        } else if rs2_val.is_nan() {
+            rs1_val
+        } else if rs1_val.is_zero() && rs2_val.is_zero() {
+            if rs1_val.is_negative() != is_max {
+                rs1_val
+            } else {
+                rs2_val
+            }
+        } else if less != is_max {
+            rs1_val
+        } else {
+            rs2_val
         }
-        if flags.is_invalid() {
-            val |= 0x10;
+    }
+
+    // Zfa fminm/fmaxm propagate NaNs instead of returning the other operand
+    fn fmin_max_nan<T: Float + Copy>(&mut self, rs1_val: T, rs2_val: T, is_max: bool) -> T {
+        let result = self.fmin_max(rs1_val, rs2_val, is_max);
+        if rs1_val.is_nan() || rs2_val.is_nan() {
+            T::quiet_nan()
+        } else {
+            result
         }
+    }
+
+    // Rounds to an integral value in the same format, NX only for froundnx
+    fn fround<T: Float + Copy>(&mut self, rs1_val: T, rm: RoundingMode, exact: bool) -> T {
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let result = rs1_val.round_to_integral(rm);
+        flag.get();
+        self.update_fflags(&flag);
 
-        self.csregs.write(csregs::FFLAGS, val);
+        if rs1_val.is_nan() {
+            return T::quiet_nan();
+        }
+        if exact && result.to_bits() != rs1_val.to_bits() {
+            self.accrue_fflags(0x1);
+        }
+        result
     }
 
-    fn inst_auipc(&mut self, inst: &inst_type::InstType) {
-        let result = (self.pc + inst.get_imm_utype()) as u32;
-        self.regs.write(inst.get_rd(), result as AddressType);
+    fn decode_rounding_mode(rm: AddressType) -> Option<RoundingMode> {
+        match rm {
+            0 => Some(RoundingMode::TiesToEven),
+            1 => Some(RoundingMode::TowardZero),
+            2 => Some(RoundingMode::TowardNegative),
+            3 => Some(RoundingMode::TowardPositive),
+            4 => Some(RoundingMode::TiesToAway),
+            _ => None,
+        }
     }
 
-    fn inst_add(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self.regs.read(inst.get_rs1());
-        let rs2_val = self.regs.read(inst.get_rs2_rtype());
-        self.regs
-            .write(inst.get_rd(), rs1_val.wrapping_add(rs2_val));
+    // Dynamic rounding mode from frm, None for the reserved encodings
+    fn frm_rounding_mode(&self) -> Option<RoundingMode> {
+        RVCore::decode_rounding_mode(self.csregs.read(csregs::FRM))
     }
 
-    fn inst_addi(&mut self, inst: &inst_type::InstType) {
-        self.regs.write(
-            inst.get_rd(),
-            self.regs
-                .read(inst.get_rs1())
-                .wrapping_add(RVCore::sign_extend(inst.get_imm_itype(), 12)),
-        );
+    // Rounding mode of an instruction's rm field with DYN resolved from frm,
+    // reserved encodings raise an illegal instruction exception
+    fn rounding_mode(&mut self, inst: &inst_type::InstType) -> Option<RoundingMode> {
+        let rm = match inst.get_rm() {
+            7 => self.frm_rounding_mode(),
+            rm => RVCore::decode_rounding_mode(rm),
+        };
+        if rm.is_none() {
+            self.raise_illegal_inst(inst);
+        }
+        rm
     }
 
-    fn inst_addiw(&mut self, inst: &inst_type::InstType) {
-        let imm = RVCore::sign_extend(inst.get_imm_itype(), 12);
-        let mut wdata = self.regs.read(inst.get_rs1()).wrapping_add(imm);
-        wdata = wdata as u32 as AddressType;
-        wdata = RVCore::sign_extend(wdata, 32);
-        self.regs.write(inst.get_rd(), wdata);
+    // softfloat does not produce the RISC-V canonical NaN on its own
+    fn canonical_nan<T: Float>(val: T) -> T {
+        if val.is_nan() {
+            T::quiet_nan()
+        } else {
+            val
+        }
     }
 
-    fn inst_addw(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self.regs.read(inst.get_rs1());
-        let rs2_val = self.regs.read(inst.get_rs2_rtype());
-        let result = RVCore::sign_extend(rs1_val.wrapping_add(rs2_val) as u32 as u64, 32);
-        self.regs.write(inst.get_rd(), result);
+    // With Zfinx the operands live in the x registers, narrower values are
+    // sign-extended there instead of NaN-boxed
+    fn fp_read(&self, reg: usize) -> F64 {
+        if self.isa.has(isa::Extension::Zfinx) {
+            F64::from_bits(self.regs.read(reg))
+        } else {
+            self.fregs.read(reg)
+        }
     }
 
-    fn inst_amoadd_d(&mut self, inst: &inst_type::InstType) {
+    fn fp_write(&mut self, reg: usize, val: F64) {
+        if self.isa.has(isa::Extension::Zfinx) {
+            self.regs.write(reg, val.to_bits());
+        } else {
+            self.fregs.write(reg, val);
+        }
+    }
+
+    fn fp_read_s(&self, reg: usize) -> F32 {
+        if self.isa.has(isa::Extension::Zfinx) {
+            F32::from_bits(self.regs.read(reg) as u32)
+        } else {
+            self.fregs.read_s(reg)
+        }
+    }
+
+    fn fp_write_s(&mut self, reg: usize, val: F32) {
+        if self.isa.has(isa::Extension::Zfinx) {
+            let bits = RVCore::sign_extend(val.to_bits() as AddressType, 32);
+            self.regs.write(reg, bits);
+        } else {
+            self.fregs.write_s(reg, val);
+        }
+    }
+
+    fn fp_read_h(&self, reg: usize) -> F16 {
+        if self.isa.has(isa::Extension::Zfinx) {
+            F16::from_bits(self.regs.read(reg) as u16)
+        } else {
+            self.fregs.read_h(reg)
+        }
+    }
+
+    fn fp_write_h(&mut self, reg: usize, val: F16) {
+        if self.isa.has(isa::Extension::Zfinx) {
+            let bits = RVCore::sign_extend(val.to_bits() as AddressType, 16);
+            self.regs.write(reg, bits);
+        } else {
+            self.fregs.write_h(reg, val);
+        }
+    }
+
+    fn fcvt_int_to_h(&mut self, inst: &inst_type::InstType, val: i128) {
+        let rm = match self.rounding_mode(inst) {
+            Some(rm) => rm,
+            None => return,
+        };
+
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let result = if val < 0 {
+            F16::from_i64(val as i64, rm)
+        } else {
+            F16::from_u64(val as u64, rm)
+        };
+        flag.get();
+        self.update_fflags(&flag);
+
+        self.fp_write_h(inst.get_rd(), result);
+    }
+
+    // Out of range inputs saturate and NaN converts to the largest value, both raise NV only
+    // Saturating conversion, NaN converts to the largest value
+    fn fcvt_to_int<T: Float>(
+        &mut self,
+        inst: &inst_type::InstType,
+        rs1_val: T,
+        signed: bool,
+        width: usize,
+    ) {
+        let rm = match self.rounding_mode(inst) {
+            Some(rm) => rm,
+            None => return,
+        };
+        let (min, max) = if signed {
+            (-(1i128 << (width - 1)), (1i128 << (width - 1)) - 1)
+        } else {
+            (0, (1i128 << width) - 1)
+        };
+
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let result = if signed {
+            rs1_val.to_i64(rm, true) as i128
+        } else {
+            rs1_val.to_u64(rm, true) as i128
+        };
+        flag.get();
+
+        let result = if rs1_val.is_nan() {
+            flag = ExceptionFlags::from_bits(0x10);
+            max
+        } else if flag.is_invalid() || result < min || result > max {
+            flag = ExceptionFlags::from_bits(0x10);
+            if rs1_val.is_negative() {
+                min
+            } else {
+                max
+            }
+        } else {
+            result
+        };
+        self.update_fflags(&flag);
+
+        let result = if width == 32 {
+            RVCore::sign_extend(result as AddressType & 0xffffffff, 32)
+        } else {
+            result as AddressType
+        };
+        self.regs.write(inst.get_rd(), result);
+    }
+
+    fn accrue_fflags(&mut self, flags: AddressType) {
+        let fflags = self.csregs.read(csregs::FFLAGS);
+        self.csregs.write(csregs::FFLAGS, fflags | flags);
+    }
+
+    fn inst_auipc(&mut self, inst: &inst_type::InstType) {
+        let result = (self.pc + inst.get_imm_utype()) as u32;
+        self.regs.write(inst.get_rd(), result as AddressType);
+    }
+
+    fn inst_add(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1());
+        let rs2_val = self.regs.read(inst.get_rs2_rtype());
+        self.regs
+            .write(inst.get_rd(), rs1_val.wrapping_add(rs2_val));
+    }
+
+    fn inst_addi(&mut self, inst: &inst_type::InstType) {
+        self.regs.write(
+            inst.get_rd(),
+            self.regs
+                .read(inst.get_rs1())
+                .wrapping_add(RVCore::sign_extend(inst.get_imm_itype(), 12)),
+        );
+    }
+
+    fn inst_addiw(&mut self, inst: &inst_type::InstType) {
+        let imm = RVCore::sign_extend(inst.get_imm_itype(), 12);
+        let mut wdata = self.regs.read(inst.get_rs1()).wrapping_add(imm);
+        wdata = wdata as u32 as AddressType;
+        wdata = RVCore::sign_extend(wdata, 32);
+        self.regs.write(inst.get_rd(), wdata);
+    }
+
+    fn inst_addw(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1());
+        let rs2_val = self.regs.read(inst.get_rs2_rtype());
+        let result = RVCore::sign_extend(rs1_val.wrapping_add(rs2_val) as u32 as u64, 32);
+        self.regs.write(inst.get_rd(), result);
+    }
+
+    fn inst_add_uw(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1()) as u32 as AddressType;
+        let rs2_val = self.regs.read(inst.get_rs2_rtype());
+        self.regs.write(inst.get_rd(), rs2_val.wrapping_add(rs1_val));
+    }
+
+    fn inst_amoadd_d(&mut self, inst: &inst_type::InstType) {
         let rs1_val = self.regs.read(inst.get_rs1());
         let rs2_val = self.regs.read(inst.get_rs2_rtype());
         
@@ -617,18 +2059,200 @@ impl RVCore {
         self.write_memory_amo(rs1_val, &mut result.to_le_bytes());
     }
 
+    // Zabha byte/halfword AMOs. Both operands are sign-extended, which keeps
+    // the unsigned ordering of the narrow values for amominu/amomaxu.
+    fn amo_narrow(
+        &mut self,
+        inst: &inst_type::InstType,
+        size: usize,
+        op: fn(AddressType, AddressType) -> AddressType,
+    ) {
+        let rs1_val = self.regs.read(inst.get_rs1());
+        let rs2_val = RVCore::sign_extend(self.regs.read(inst.get_rs2_rtype()), size * 8);
+
+        let mut data = vec![0; size];
+        self.read_memory_amo(rs1_val, &mut data);
+        let rdata = RVCore::sign_extend(RVCore::byte_array_to_addr_type(&data), size * 8);
+        self.regs.write(inst.get_rd(), rdata);
+
+        let result = op(rdata, rs2_val);
+        self.write_memory_amo(rs1_val, &mut result.to_le_bytes()[..size]);
+    }
+
+    fn inst_amoadd_b(&mut self, inst: &inst_type::InstType) {
+        self.amo_narrow(inst, 1, |a, b| a.wrapping_add(b));
+    }
+
+    fn inst_amoadd_h(&mut self, inst: &inst_type::InstType) {
+        self.amo_narrow(inst, 2, |a, b| a.wrapping_add(b));
+    }
+
+    fn inst_amoand_b(&mut self, inst: &inst_type::InstType) {
+        self.amo_narrow(inst, 1, |a, b| a & b);
+    }
+
+    fn inst_amoand_h(&mut self, inst: &inst_type::InstType) {
+        self.amo_narrow(inst, 2, |a, b| a & b);
+    }
+
+    fn inst_amomax_b(&mut self, inst: &inst_type::InstType) {
+        self.amo_narrow(inst, 1, |a, b| cmp::max(a as i64, b as i64) as AddressType);
+    }
+
+    fn inst_amomax_h(&mut self, inst: &inst_type::InstType) {
+        self.amo_narrow(inst, 2, |a, b| cmp::max(a as i64, b as i64) as AddressType);
+    }
+
+    fn inst_amomaxu_b(&mut self, inst: &inst_type::InstType) {
+        self.amo_narrow(inst, 1, cmp::max);
+    }
+
+    fn inst_amomaxu_h(&mut self, inst: &inst_type::InstType) {
+        self.amo_narrow(inst, 2, cmp::max);
+    }
+
+    fn inst_amomin_b(&mut self, inst: &inst_type::InstType) {
+        self.amo_narrow(inst, 1, |a, b| cmp::min(a as i64, b as i64) as AddressType);
+    }
+
+    fn inst_amomin_h(&mut self, inst: &inst_type::InstType) {
+        self.amo_narrow(inst, 2, |a, b| cmp::min(a as i64, b as i64) as AddressType);
+    }
+
+    fn inst_amominu_b(&mut self, inst: &inst_type::InstType) {
+        self.amo_narrow(inst, 1, cmp::min);
+    }
+
+    fn inst_amominu_h(&mut self, inst: &inst_type::InstType) {
+        self.amo_narrow(inst, 2, cmp::min);
+    }
+
+    fn inst_amoor_b(&mut self, inst: &inst_type::InstType) {
+        self.amo_narrow(inst, 1, |a, b| a | b);
+    }
+
+    fn inst_amoor_h(&mut self, inst: &inst_type::InstType) {
+        self.amo_narrow(inst, 2, |a, b| a | b);
+    }
+
+    fn inst_amoswap_b(&mut self, inst: &inst_type::InstType) {
+        self.amo_narrow(inst, 1, |_, b| b);
+    }
+
+    fn inst_amoswap_h(&mut self, inst: &inst_type::InstType) {
+        self.amo_narrow(inst, 2, |_, b| b);
+    }
+
+    fn inst_amoxor_b(&mut self, inst: &inst_type::InstType) {
+        self.amo_narrow(inst, 1, |a, b| a ^ b);
+    }
+
+    fn inst_amoxor_h(&mut self, inst: &inst_type::InstType) {
+        self.amo_narrow(inst, 2, |a, b| a ^ b);
+    }
+
+    // rd holds the expected value and receives the loaded one, rs2 is only
+    // stored when the comparison succeeds
+    fn amocas(&mut self, inst: &inst_type::InstType, size: usize) {
+        let rs1_val = self.regs.read(inst.get_rs1());
+        let rs2_val = self.regs.read(inst.get_rs2_rtype());
+        let compare = self.regs.read(inst.get_rd());
+
+        let mut data = vec![0; size];
+        self.read_memory_amo(rs1_val, &mut data);
+        let rdata = RVCore::byte_array_to_addr_type(&data);
+        if rdata.to_le_bytes()[..size] == compare.to_le_bytes()[..size] {
+            self.write_memory_amo(rs1_val, &mut rs2_val.to_le_bytes()[..size]);
+        }
+
+        let rdata = if size < 8 {
+            RVCore::sign_extend(rdata, size * 8)
+        } else {
+            rdata
+        };
+        self.regs.write(inst.get_rd(), rdata);
+    }
+
+    fn inst_amocas_b(&mut self, inst: &inst_type::InstType) {
+        self.amocas(inst, 1);
+    }
+
+    fn inst_amocas_h(&mut self, inst: &inst_type::InstType) {
+        self.amocas(inst, 2);
+    }
+
+    fn inst_amocas_w(&mut self, inst: &inst_type::InstType) {
+        self.amocas(inst, 4);
+    }
+
+    fn inst_amocas_d(&mut self, inst: &inst_type::InstType) {
+        self.amocas(inst, 8);
+    }
+
+    // 128-bit values live in even/odd register pairs, a pair at x0 reads as
+    // zero and discards writes to both halves
+    fn read_reg_pair(&self, reg: usize) -> u128 {
+        if reg == 0 {
+            0
+        } else {
+            (self.regs.read(reg) as u128) | ((self.regs.read(reg + 1) as u128) << 64)
+        }
+    }
+
+    fn inst_amocas_q(&mut self, inst: &inst_type::InstType) {
+        let rd = inst.get_rd();
+        let rs2 = inst.get_rs2_rtype();
+        if !rd.is_multiple_of(2) || !rs2.is_multiple_of(2) {
+            self.raise_illegal_inst(inst);
+            return;
+        }
+        let rs1_val = self.regs.read(inst.get_rs1());
+        let swap = self.read_reg_pair(rs2);
+        let compare = self.read_reg_pair(rd);
+
+        let mut data = [0; 16];
+        self.read_memory_amo(rs1_val, &mut data);
+        let rdata = u128::from_le_bytes(data);
+        if rdata == compare {
+            self.write_memory_amo(rs1_val, &mut swap.to_le_bytes());
+        }
+
+        if rd != 0 {
+            self.regs.write(rd, rdata as AddressType);
+            self.regs.write(rd + 1, (rdata >> 64) as AddressType);
+        }
+    }
+
     fn inst_and(&mut self, inst: &inst_type::InstType) {
         let rs1_val = self.regs.read(inst.get_rs1());
         let rs2_val = self.regs.read(inst.get_rs2_rtype());
         self.regs.write(inst.get_rd(), rs1_val & rs2_val);
     }
 
+    fn inst_andn(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1());
+        let rs2_val = self.regs.read(inst.get_rs2_rtype());
+        self.regs.write(inst.get_rd(), rs1_val & !rs2_val);
+    }
+
     fn inst_andi(&mut self, inst: &inst_type::InstType) {
         let imm = RVCore::sign_extend(inst.get_imm_itype(), 12);
         self.regs
             .write(inst.get_rd(), self.regs.read(inst.get_rs1()) & imm);
     }
 
+    fn inst_bclr(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1());
+        let index = self.regs.read(inst.get_rs2_rtype()) & 0x3f;
+        self.regs.write(inst.get_rd(), rs1_val & !(1 << index));
+    }
+
+    fn inst_bclri(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1());
+        let index = inst.get_shamt6_itype();
+        self.regs.write(inst.get_rd(), rs1_val & !(1 << index));
+    }
+
     fn inst_beq(&mut self, inst: &inst_type::InstType) {
         let imm = inst.get_imm_btype();
         let offset = (((imm >> 11) & 1) << 12)
@@ -644,6 +2268,18 @@ impl RVCore {
         }
     }
 
+    fn inst_bext(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1());
+        let index = self.regs.read(inst.get_rs2_rtype()) & 0x3f;
+        self.regs.write(inst.get_rd(), (rs1_val >> index) & 1);
+    }
+
+    fn inst_bexti(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1());
+        let index = inst.get_shamt6_itype();
+        self.regs.write(inst.get_rd(), (rs1_val >> index) & 1);
+    }
+
     fn inst_blt(&mut self, inst: &inst_type::InstType) {
         let imm = inst.get_imm_btype();
         let offset = (((imm >> 11) & 1) << 12)
@@ -687,6 +2323,18 @@ impl RVCore {
         }
     }
 
+    fn inst_binv(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1());
+        let index = self.regs.read(inst.get_rs2_rtype()) & 0x3f;
+        self.regs.write(inst.get_rd(), rs1_val ^ (1 << index));
+    }
+
+    fn inst_binvi(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1());
+        let index = inst.get_shamt6_itype();
+        self.regs.write(inst.get_rd(), rs1_val ^ (1 << index));
+    }
+
     fn inst_bgeu(&mut self, inst: &inst_type::InstType) {
         let imm = inst.get_imm_btype();
         let offset = (((imm >> 11) & 1) << 12)
@@ -716,6 +2364,18 @@ impl RVCore {
         }
     }
 
+    fn inst_bset(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1());
+        let index = self.regs.read(inst.get_rs2_rtype()) & 0x3f;
+        self.regs.write(inst.get_rd(), rs1_val | (1 << index));
+    }
+
+    fn inst_bseti(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1());
+        let index = inst.get_shamt6_itype();
+        self.regs.write(inst.get_rd(), rs1_val | (1 << index));
+    }
+
     fn inst_c_add(&mut self, inst: &inst_type::InstType) {
         let result = self
             .regs
@@ -990,64 +2650,498 @@ impl RVCore {
         self.regs.write(inst.get_rd_3b(), a ^ b);
     }
 
-    fn inst_csrrci(&mut self, inst: &inst_type::InstType) {
-        let rd = inst.get_rd();
-        let imm = inst.get_rs1() as AddressType;
-        let csr = inst.get_csr();
-        self.regs.write(rd, self.csregs.read(csr));
-        self.csregs.write(csr, !imm & self.csregs.read(csr));
-        //println!("JC_DEBUG: csrrs: writing csr {}, val {:#x}, rs1={:#x}", csr
-        //            , self.regs.read(rs1) | self.csregs.read(csr), self.regs.read(rs1));
+    // Zcb loads/stores take uimm[0] from bit 6 and uimm[1] from bit 5
+    fn inst_c_lbu(&mut self, inst: &inst_type::InstType) {
+        let offset = ((inst.data >> 6) & 1) | (((inst.data >> 5) & 1) << 1);
+        let address = self.regs.read(inst.get_rs1_3b()).wrapping_add(offset);
+        let mut data = [0; 1];
+        self.read_memory(address, &mut data);
+        self.regs
+            .write(inst.get_rd_cl(), RVCore::byte_array_to_addr_type(&data));
     }
 
-    fn inst_csrrs(&mut self, inst: &inst_type::InstType) {
-        let rd = inst.get_rd();
-        let rs1 = inst.get_rs1();
-        let csr = inst.get_csr();
-        self.regs.write(rd, self.csregs.read(csr));
-        self.csregs
-            .write(csr, self.regs.read(rs1) | self.csregs.read(csr));
-        //println!("JC_DEBUG: csrrs: writing csr {}, val {:#x}, rs1={:#x}", csr
-        //            , self.regs.read(rs1) | self.csregs.read(csr), self.regs.read(rs1));
+    fn inst_c_lhu(&mut self, inst: &inst_type::InstType) {
+        let offset = ((inst.data >> 5) & 1) << 1;
+        let address = self.regs.read(inst.get_rs1_3b()).wrapping_add(offset);
+        let mut data = [0; 2];
+        self.read_memory(address, &mut data);
+        self.regs
+            .write(inst.get_rd_cl(), RVCore::byte_array_to_addr_type(&data));
     }
 
-    fn inst_csrrsi(&mut self, inst: &inst_type::InstType) {
-        let rd = inst.get_rd();
-        let imm = inst.get_rs1() as AddressType;
-        let csr = inst.get_csr();
-        self.regs.write(rd, self.csregs.read(csr));
-        self.csregs.write(csr, imm | self.csregs.read(csr));
-        //println!("JC_DEBUG: csrrs: writing csr {}, val {:#x}, rs1={:#x}", csr
-        //            , self.regs.read(rs1) | self.csregs.read(csr), self.regs.read(rs1));
+    fn inst_c_lh(&mut self, inst: &inst_type::InstType) {
+        let offset = ((inst.data >> 5) & 1) << 1;
+        let address = self.regs.read(inst.get_rs1_3b()).wrapping_add(offset);
+        let mut data = [0; 2];
+        self.read_memory(address, &mut data);
+        let result = RVCore::sign_extend(RVCore::byte_array_to_addr_type(&data), 16);
+        self.regs.write(inst.get_rd_cl(), result);
     }
 
-    fn inst_csrrw(&mut self, inst: &inst_type::InstType) {
-        let rd = inst.get_rd();
-        let rs1 = inst.get_rs1();
-        let csr = inst.get_csr();
-        self.regs.write(rd, self.csregs.read(csr));
-        self.csregs.write(csr, self.regs.read(rs1));
+    fn inst_c_sb(&mut self, inst: &inst_type::InstType) {
+        let offset = ((inst.data >> 6) & 1) | (((inst.data >> 5) & 1) << 1);
+        let address = self.regs.read(inst.get_rs1_3b()).wrapping_add(offset);
+        let data = self.regs.read(inst.get_rs2_3b()) as u8;
+        self.write_memory(address, &mut data.to_le_bytes());
     }
 
-    fn inst_csrrwi(&mut self, inst: &inst_type::InstType) {
-        let rd = inst.get_rd();
-        let imm = inst.get_rs1() as AddressType;
-        let csr = inst.get_csr();
+    fn inst_c_sh(&mut self, inst: &inst_type::InstType) {
+        let offset = ((inst.data >> 5) & 1) << 1;
+        let address = self.regs.read(inst.get_rs1_3b()).wrapping_add(offset);
+        let data = self.regs.read(inst.get_rs2_3b()) as u16;
+        self.write_memory(address, &mut data.to_le_bytes());
+    }
 
-        // Prevent csr read when rd == 0
-        if rd != 0 {
-            self.regs.write(rd, self.csregs.read(csr));
-        }
+    fn inst_c_zext_b(&mut self, inst: &inst_type::InstType) {
+        let a = self.regs.read(inst.get_rd_3b());
+        self.regs.write(inst.get_rd_3b(), a & 0xff);
+    }
 
-        //println!("JC_DEBUG: csrrwi: read csr {}, val = {:#x}, write val={:#x}"
-        //            , csr, self.csregs.read(csr), imm);
-        self.csregs.write(csr, imm);
+    fn inst_c_sext_b(&mut self, inst: &inst_type::InstType) {
+        let a = self.regs.read(inst.get_rd_3b());
+        self.regs
+            .write(inst.get_rd_3b(), a as i8 as i64 as AddressType);
     }
 
-    fn inst_div(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self.regs.read(inst.get_rs1()) as i64;
-        let rs2_val = self.regs.read(inst.get_rs2_rtype()) as i64;
-        if rs2_val == 0 {
+    fn inst_c_zext_h(&mut self, inst: &inst_type::InstType) {
+        let a = self.regs.read(inst.get_rd_3b());
+        self.regs.write(inst.get_rd_3b(), a & 0xffff);
+    }
+
+    fn inst_c_sext_h(&mut self, inst: &inst_type::InstType) {
+        let a = self.regs.read(inst.get_rd_3b());
+        self.regs
+            .write(inst.get_rd_3b(), a as i16 as i64 as AddressType);
+    }
+
+    fn inst_c_zext_w(&mut self, inst: &inst_type::InstType) {
+        let a = self.regs.read(inst.get_rd_3b());
+        self.regs.write(inst.get_rd_3b(), a & 0xffff_ffff);
+    }
+
+    fn inst_c_not(&mut self, inst: &inst_type::InstType) {
+        let a = self.regs.read(inst.get_rd_3b());
+        self.regs.write(inst.get_rd_3b(), !a);
+    }
+
+    fn inst_c_mul(&mut self, inst: &inst_type::InstType) {
+        let a = self.regs.read(inst.get_rd_3b());
+        let b = self.regs.read(inst.get_rs2_3b());
+        self.regs.write(inst.get_rd_3b(), a.wrapping_mul(b));
+    }
+
+    // ra, s0-s11 as selected by rlist, there is no encoding saving s10 without s11
+    fn zcmp_reg_list(inst: &inst_type::InstType) -> Vec<usize> {
+        let rlist = ((inst.data >> 4) & 0xf) as usize;
+        let count = if rlist == 15 { 13 } else { rlist - 3 };
+        [1, 8, 9, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27][..count].to_vec()
+    }
+
+    fn zcmp_stack_adj(inst: &inst_type::InstType, num_regs: usize) -> AddressType {
+        let base = (num_regs as AddressType * 8 + 15) & !15;
+        base + ((inst.data >> 2) & 0x3) * 16
+    }
+
+    // The last register of the list sits just below the old sp, ra lowest
+    fn inst_cm_push(&mut self, inst: &inst_type::InstType) {
+        let regs = RVCore::zcmp_reg_list(inst);
+        let sp = self.regs.read(2);
+        for (i, reg) in regs.iter().enumerate() {
+            let address = sp.wrapping_sub(((regs.len() - i) * 8) as AddressType);
+            let data = self.regs.read(*reg);
+            self.write_memory(address, &mut data.to_le_bytes());
+        }
+        self.regs
+            .write(2, sp.wrapping_sub(RVCore::zcmp_stack_adj(inst, regs.len())));
+    }
+
+    fn inst_cm_pop(&mut self, inst: &inst_type::InstType) {
+        let regs = RVCore::zcmp_reg_list(inst);
+        let new_sp = self.regs.read(2) + RVCore::zcmp_stack_adj(inst, regs.len());
+        for (i, reg) in regs.iter().enumerate() {
+            let address = new_sp.wrapping_sub(((regs.len() - i) * 8) as AddressType);
+            let mut data = [0; 8];
+            self.read_memory(address, &mut data);
+            self.regs
+                .write(*reg, RVCore::byte_array_to_addr_type(&data));
+        }
+        self.regs.write(2, new_sp);
+    }
+
+    fn inst_cm_popretz(&mut self, inst: &inst_type::InstType) {
+        self.inst_cm_pop(inst);
+        self.regs.write(10, 0);
+        self.pc = self.regs.read(1) - inst.len;
+    }
+
+    fn inst_cm_popret(&mut self, inst: &inst_type::InstType) {
+        self.inst_cm_pop(inst);
+        self.pc = self.regs.read(1) - inst.len;
+    }
+
+    // r1s'/r2s' index s0-s7, which are x8, x9 and x18-x23
+    fn zcmp_sreg(idx: AddressType) -> usize {
+        let idx = (idx & 0x7) as usize;
+        if idx < 2 {
+            idx + 8
+        } else {
+            idx + 16
+        }
+    }
+
+    fn inst_cm_mvsa01(&mut self, inst: &inst_type::InstType) {
+        let a0 = self.regs.read(10);
+        let a1 = self.regs.read(11);
+        self.regs.write(RVCore::zcmp_sreg(inst.data >> 7), a0);
+        self.regs.write(RVCore::zcmp_sreg(inst.data >> 2), a1);
+    }
+
+    fn inst_cm_mva01s(&mut self, inst: &inst_type::InstType) {
+        let r1s = self.regs.read(RVCore::zcmp_sreg(inst.data >> 7));
+        let r2s = self.regs.read(RVCore::zcmp_sreg(inst.data >> 2));
+        self.regs.write(10, r1s);
+        self.regs.write(11, r2s);
+    }
+
+    fn zcmt_target(&mut self, inst: &inst_type::InstType) -> AddressType {
+        let index = (inst.data >> 2) & 0xff;
+        let base = self.csregs.read(csregs::JVT) & !csregs::JVT_MODE;
+        let mut data = [0; 8];
+        self.read_memory(base + index * 8, &mut data);
+        RVCore::byte_array_to_addr_type(&data) & !1
+    }
+
+    fn inst_cm_jt(&mut self, inst: &inst_type::InstType) {
+        self.pc = self.zcmt_target(inst) - inst.len;
+    }
+
+    fn inst_cm_jalt(&mut self, inst: &inst_type::InstType) {
+        self.regs.write(1, self.pc + 2);
+        self.pc = self.zcmt_target(inst) - inst.len;
+    }
+
+    // Below M-mode the enables of every more privileged level must be set. When V=1 a
+    // clear henvcfg bit, or senvcfg bit in VU-mode, raises a virtual instruction exception.
+    fn cbo_enabled(&mut self, inst: &inst_type::InstType, enable: AddressType) -> bool {
+        let enabled = |envcfg: AddressType| self.csregs.read(envcfg) & enable != 0;
+        let fault = if self.mode == PrivilegeMode::M {
+            None
+        } else if !enabled(csregs::MENVCFG) {
+            Some(csregs::EXC_ILLEGAL_INST)
+        } else if self.virt && !enabled(csregs::HENVCFG) {
+            Some(csregs::EXC_VIRTUAL_INST)
+        } else if self.mode == PrivilegeMode::U && !enabled(csregs::SENVCFG) {
+            if self.virt {
+                Some(csregs::EXC_VIRTUAL_INST)
+            } else {
+                Some(csregs::EXC_ILLEGAL_INST)
+            }
+        } else {
+            None
+        };
+        match fault {
+            Some(csregs::EXC_VIRTUAL_INST) => self.raise_virtual_inst(inst),
+            Some(_) => self.raise_illegal_inst(inst),
+            None => (),
+        }
+        fault.is_none()
+    }
+
+    // clean/flush/inval are passed to the memory interface for any cache
+    // model behind it, cbo.zero is a plain block-sized store
+    fn cache_block_op(&mut self, inst: &inst_type::InstType, op: MemoryOperation) {
+        let address = self.regs.read(inst.get_rs1()) & !(self.cache_block_size as AddressType - 1);
+        let mut data = vec![0; self.cache_block_size];
+        if op == MemoryOperation::WRITE {
+            self.write_memory(address, &mut data);
+        } else {
+            self.access_memory(address, &mut data, op, false);
+        }
+    }
+
+    fn inst_cbo_clean(&mut self, inst: &inst_type::InstType) {
+        if !self.cbo_enabled(inst, csregs::ENVCFG_CBCFE) {
+            return;
+        }
+        self.cache_block_op(inst, MemoryOperation::CLEAN);
+    }
+
+    fn inst_cbo_flush(&mut self, inst: &inst_type::InstType) {
+        if !self.cbo_enabled(inst, csregs::ENVCFG_CBCFE) {
+            return;
+        }
+        self.cache_block_op(inst, MemoryOperation::FLUSH);
+    }
+
+    // CBIE=1 at any level below M turns the invalidate into a flush
+    fn inst_cbo_inval(&mut self, inst: &inst_type::InstType) {
+        if !self.cbo_enabled(inst, csregs::ENVCFG_CBIE) {
+            return;
+        }
+        let flushes =
+            |envcfg: AddressType| self.csregs.read(envcfg) & csregs::ENVCFG_CBIE == 1 << 4;
+        let flush = self.mode != PrivilegeMode::M
+            && (flushes(csregs::MENVCFG)
+                || (self.virt && flushes(csregs::HENVCFG))
+                || (self.mode == PrivilegeMode::U && flushes(csregs::SENVCFG)));
+        if flush {
+            self.cache_block_op(inst, MemoryOperation::FLUSH);
+        } else {
+            self.cache_block_op(inst, MemoryOperation::INVAL);
+        }
+    }
+
+    fn inst_cbo_zero(&mut self, inst: &inst_type::InstType) {
+        if !self.cbo_enabled(inst, csregs::ENVCFG_CBZE) {
+            return;
+        }
+        self.cache_block_op(inst, MemoryOperation::WRITE);
+    }
+
+    fn clmul_full(a: AddressType, b: AddressType) -> u128 {
+        let mut result: u128 = 0;
+        for i in 0..64 {
+            if (b >> i) & 1 == 1 {
+                result ^= (a as u128) << i;
+            }
+        }
+        result
+    }
+
+    fn inst_clmul(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1());
+        let rs2_val = self.regs.read(inst.get_rs2_rtype());
+        let result = RVCore::clmul_full(rs1_val, rs2_val);
+        self.regs.write(inst.get_rd(), result as AddressType);
+    }
+
+    fn inst_clmulh(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1());
+        let rs2_val = self.regs.read(inst.get_rs2_rtype());
+        let result = RVCore::clmul_full(rs1_val, rs2_val);
+        self.regs.write(inst.get_rd(), (result >> 64) as AddressType);
+    }
+
+    fn inst_clmulr(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1());
+        let rs2_val = self.regs.read(inst.get_rs2_rtype());
+        let result = RVCore::clmul_full(rs1_val, rs2_val);
+        self.regs.write(inst.get_rd(), (result >> 63) as AddressType);
+    }
+
+    fn inst_clz(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1());
+        self.regs
+            .write(inst.get_rd(), rs1_val.leading_zeros() as AddressType);
+    }
+
+    fn inst_clzw(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1()) as u32;
+        self.regs
+            .write(inst.get_rd(), rs1_val.leading_zeros() as AddressType);
+    }
+
+    fn inst_cpop(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1());
+        self.regs
+            .write(inst.get_rd(), rs1_val.count_ones() as AddressType);
+    }
+
+    fn inst_cpopw(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1()) as u32;
+        self.regs
+            .write(inst.get_rd(), rs1_val.count_ones() as AddressType);
+    }
+
+    // seed must be accessed with a write, lower modes are gated by mseccfg.
+    // The written value is ignored.
+    fn access_seed(&mut self, inst: &inst_type::InstType, writes: bool) {
+        let mseccfg = self.csregs.read(csregs::MSECCFG);
+        let allowed = match self.mode {
+            PrivilegeMode::M => true,
+            PrivilegeMode::S => mseccfg & csregs::MSECCFG_SSEED != 0,
+            PrivilegeMode::U => mseccfg & csregs::MSECCFG_USEED != 0,
+        };
+        if !self.isa.has(isa::Extension::Zkr) || !writes || !allowed {
+            self.raise_illegal_inst(inst);
+            return;
+        }
+        // VS/VU-mode access is never allowed, it traps to HS-mode where mseccfg permits it
+        if self.virt {
+            self.raise_virtual_inst(inst);
+            return;
+        }
+        let val = self.csregs.poll_seed();
+        self.regs.write(inst.get_rd(), val);
+    }
+
+    // mnxti returns the mtvt entry of the highest ranked interrupt that is not
+    // vectored and is above mcause.mpil and mintthresh, 0 otherwise. The set/clear
+    // goes to mstatus and, with a write, the handler takes the interrupt over.
+    fn access_mnxti(
+        &mut self,
+        inst: &inst_type::InstType,
+        writes: bool,
+        set: AddressType,
+        clear: AddressType,
+    ) {
+        let clic = self.csregs.clic();
+        let mcause = self.csregs.read(csregs::MCAUSE);
+        let mpil = (mcause & csregs::MCAUSE_MPIL) >> csregs::MCAUSE_MPIL_SHIFT;
+        let threshold = cmp::max(mpil, self.csregs.read(csregs::MINTTHRESH));
+        let next = {
+            let clic = clic.borrow();
+            clic.top()
+                .filter(|&id| !clic.shv(id) && clic.level(id) > threshold)
+        };
+        let mut val = 0;
+        if let Some(id) = next.filter(|_| self.csregs.clic_mode()) {
+            val = self.csregs.read(csregs::MTVT) + 8 * id as AddressType;
+            if writes {
+                let level = clic.borrow().level(id);
+                self.csregs.set_interrupt_level(level);
+                let cause = csregs::CAUSE_INTERRUPT | id as AddressType;
+                self.csregs
+                    .write(csregs::MCAUSE, (mcause & !csregs::MCAUSE_EXCCODE) | cause);
+                clic.borrow_mut().acknowledge(id);
+            }
+        }
+        if writes {
+            let mstatus = self.csregs.read(csregs::MSTATUS);
+            self.write_csr(csregs::MSTATUS, (mstatus | set) & !clear);
+        }
+        self.regs.write(inst.get_rd(), val);
+    }
+
+    fn inst_csrrci(&mut self, inst: &inst_type::InstType) {
+        let rd = inst.get_rd();
+        let imm = inst.get_rs1() as AddressType;
+        let writes = imm != 0;
+        let csr = match self.csr_access(inst, writes) {
+            Some(csr) => csr,
+            None => return,
+        };
+        if csr == csregs::SEED {
+            self.access_seed(inst, writes);
+            return;
+        }
+        if csr == csregs::MNXTI {
+            self.access_mnxti(inst, writes, 0, imm);
+            return;
+        }
+        self.regs.write(rd, self.csregs.read(csr));
+        self.write_csr(csr, !imm & self.csregs.read(csr));
+        //println!("JC_DEBUG: csrrs: writing csr {}, val {:#x}, rs1={:#x}", csr
+        //            , self.regs.read(rs1) | self.csregs.read(csr), self.regs.read(rs1));
+    }
+
+    fn inst_csrrs(&mut self, inst: &inst_type::InstType) {
+        let rd = inst.get_rd();
+        let rs1 = inst.get_rs1();
+        let writes = rs1 != 0;
+        let csr = match self.csr_access(inst, writes) {
+            Some(csr) => csr,
+            None => return,
+        };
+        if csr == csregs::SEED {
+            self.access_seed(inst, writes);
+            return;
+        }
+        if csr == csregs::MNXTI {
+            self.access_mnxti(inst, writes, self.regs.read(rs1), 0);
+            return;
+        }
+        self.regs.write(rd, self.csregs.read(csr));
+        self.write_csr(csr, self.regs.read(rs1) | self.csregs.read(csr));
+        //println!("JC_DEBUG: csrrs: writing csr {}, val {:#x}, rs1={:#x}", csr
+        //            , self.regs.read(rs1) | self.csregs.read(csr), self.regs.read(rs1));
+    }
+
+    fn inst_csrrsi(&mut self, inst: &inst_type::InstType) {
+        let rd = inst.get_rd();
+        let imm = inst.get_rs1() as AddressType;
+        let writes = imm != 0;
+        let csr = match self.csr_access(inst, writes) {
+            Some(csr) => csr,
+            None => return,
+        };
+        if csr == csregs::SEED {
+            self.access_seed(inst, writes);
+            return;
+        }
+        if csr == csregs::MNXTI {
+            self.access_mnxti(inst, writes, imm, 0);
+            return;
+        }
+        self.regs.write(rd, self.csregs.read(csr));
+        self.write_csr(csr, imm | self.csregs.read(csr));
+        //println!("JC_DEBUG: csrrs: writing csr {}, val {:#x}, rs1={:#x}", csr
+        //            , self.regs.read(rs1) | self.csregs.read(csr), self.regs.read(rs1));
+    }
+
+    fn inst_csrrw(&mut self, inst: &inst_type::InstType) {
+        let rd = inst.get_rd();
+        let rs1 = inst.get_rs1();
+        let csr = match self.csr_access(inst, true) {
+            Some(csr) => csr,
+            None => return,
+        };
+        if csr == csregs::SEED {
+            self.access_seed(inst, true);
+            return;
+        }
+        // mnxti is only accessed with the set/clear forms
+        if csr == csregs::MNXTI {
+            self.raise_illegal_inst(inst);
+            return;
+        }
+        self.regs.write(rd, self.csregs.read(csr));
+        self.write_csr(csr, self.regs.read(rs1));
+    }
+
+    fn inst_csrrwi(&mut self, inst: &inst_type::InstType) {
+        let rd = inst.get_rd();
+        let imm = inst.get_rs1() as AddressType;
+        let csr = match self.csr_access(inst, true) {
+            Some(csr) => csr,
+            None => return,
+        };
+        if csr == csregs::SEED {
+            self.access_seed(inst, true);
+            return;
+        }
+        // mnxti is only accessed with the set/clear forms
+        if csr == csregs::MNXTI {
+            self.raise_illegal_inst(inst);
+            return;
+        }
+
+        // Prevent csr read when rd == 0
+        if rd != 0 {
+            self.regs.write(rd, self.csregs.read(csr));
+        }
+
+        //println!("JC_DEBUG: csrrwi: read csr {}, val = {:#x}, write val={:#x}"
+        //            , csr, self.csregs.read(csr), imm);
+        self.write_csr(csr, imm);
+    }
+
+    fn inst_ctz(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1());
+        self.regs
+            .write(inst.get_rd(), rs1_val.trailing_zeros() as AddressType);
+    }
+
+    fn inst_ctzw(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1()) as u32;
+        self.regs
+            .write(inst.get_rd(), rs1_val.trailing_zeros() as AddressType);
+    }
+
+    fn inst_div(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1()) as i64;
+        let rs2_val = self.regs.read(inst.get_rs2_rtype()) as i64;
+        if rs2_val == 0 {
             self.regs.write(inst.get_rd(), AddressType::MAX);
         } else {
             self.regs
@@ -1089,69 +3183,95 @@ impl RVCore {
         }
     }
 
-    fn inst_ecall(&mut self, _inst: &inst_type::InstType) {
-        match self.mode {
-            PrivilegeMode::U => {
-                self.mode = PrivilegeMode::S;
-                self.csregs.write(csregs::MCAUSE, csregs::EXC_ECALL_FROM_U);
-            }
-            PrivilegeMode::S => {
-                self.mode = PrivilegeMode::M;
-                self.csregs.write(csregs::MCAUSE, csregs::EXC_ECALL_FROM_S);
-            }
-            PrivilegeMode::M => {
-                self.mode = PrivilegeMode::M;
-                self.csregs.write(csregs::MCAUSE, csregs::EXC_ECALL_FROM_M);
-            }
+    fn inst_dret(&mut self, inst: &inst_type::InstType) {
+        if !self.debug_mode {
+            self.raise_illegal_inst(inst);
+            return;
+        }
+        self.resume();
+        self.pc -= inst.len;
+    }
+
+    // ebreak enters debug mode when dcsr enables it for the current mode
+    fn inst_ebreak(&mut self, _inst: &inst_type::InstType) {
+        let ebreak = match (self.mode, self.virt) {
+            (PrivilegeMode::M, _) => csregs::DCSR_EBREAKM,
+            (PrivilegeMode::S, false) => csregs::DCSR_EBREAKS,
+            (PrivilegeMode::U, false) => csregs::DCSR_EBREAKU,
+            (PrivilegeMode::S, true) => csregs::DCSR_EBREAKVS,
+            (PrivilegeMode::U, true) => csregs::DCSR_EBREAKVU,
+        };
+        if self.has_debug_mode() && self.csregs.read(csregs::DCSR) & ebreak != 0 {
+            self.enter_debug_mode(csregs::DCSR_CAUSE_EBREAK);
+        } else {
+            self.raise_exception(csregs::EXC_BREAKPOINT, self.pc);
         }
+    }
 
-        self.pc = self.csregs.read(csregs::MTVEC);
-        self.pc -= 4;
-        //panic!("ECALL: Exceptions are not supported now");
+    fn inst_ecall(&mut self, _inst: &inst_type::InstType) {
+        let cause = match self.mode {
+            PrivilegeMode::U => csregs::EXC_ECALL_FROM_U,
+            PrivilegeMode::S if self.virt => csregs::EXC_ECALL_FROM_VS,
+            PrivilegeMode::S => csregs::EXC_ECALL_FROM_S,
+            PrivilegeMode::M => csregs::EXC_ECALL_FROM_M,
+        };
+        self.raise_exception(cause, 0);
     }
 
     fn inst_fadd_d(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self
-            .fregs
-            .read(inst.get_rs1());
-        let rs2_val = self
-            .fregs
-            .read(inst.get_rs2_stype());
+        let rm = match self.rounding_mode(inst) {
+            Some(rm) => rm,
+            None => return,
+        };
+        let rs1_val = self.fp_read(inst.get_rs1());
+        let rs2_val = self.fp_read(inst.get_rs2_stype());
 
         let mut flag = ExceptionFlags::default();
         flag.set();
-        let result = rs1_val.add(rs2_val, RoundingMode::TiesToEven);
+        let result = rs1_val.add(rs2_val, rm);
         flag.get();
         self.update_fflags(&flag);
 
-        self.fregs.write(inst.get_rd(), result);
+        self.fp_write(inst.get_rd(), RVCore::canonical_nan(result));
     }
 
     fn inst_fadd_s(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self
-            .fregs
-            .read(inst.get_rs1())
-            .to_f32(RoundingMode::TiesToEven);
-        let rs2_val = self
-            .fregs
-            .read(inst.get_rs2_stype())
-            .to_f32(RoundingMode::TiesToEven);
+        let rm = match self.rounding_mode(inst) {
+            Some(rm) => rm,
+            None => return,
+        };
+        let rs1_val = self.fp_read_s(inst.get_rs1());
+        let rs2_val = self.fp_read_s(inst.get_rs2_stype());
 
         let mut flag = ExceptionFlags::default();
         flag.set();
-        let result = rs1_val.add(rs2_val, RoundingMode::TiesToEven);
+        let result = rs1_val.add(rs2_val, rm);
         flag.get();
         self.update_fflags(&flag);
 
-        self.fregs
-            .write(inst.get_rd(), result.to_f64(RoundingMode::TiesToEven));
+        self.fp_write_s(inst.get_rd(), RVCore::canonical_nan(result));
+    }
+
+    fn inst_fadd_h(&mut self, inst: &inst_type::InstType) {
+        let rm = match self.rounding_mode(inst) {
+            Some(rm) => rm,
+            None => return,
+        };
+        let rs1_val = self.fp_read_h(inst.get_rs1());
+        let rs2_val = self.fp_read_h(inst.get_rs2_stype());
+
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let result = rs1_val.add(rs2_val, rm);
+        flag.get();
+        self.update_fflags(&flag);
+
+        self.fp_write_h(inst.get_rd(), RVCore::canonical_nan(result));
     }
 
     fn inst_fclass_d(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self
-            .fregs
-            .read(inst.get_rs1());
-        let f64_val = self.fregs.read(inst.get_rs1());
+        let rs1_val = self.fp_read(inst.get_rs1());
+        let f64_val = self.fp_read(inst.get_rs1());
         if rs1_val.is_negative_infinity() {
             self.regs.write(inst.get_rd(), 1 << 0);
         } else if rs1_val.is_negative_normal() {
@@ -1176,11 +3296,7 @@ impl RVCore {
     }
 
     fn inst_fclass_s(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self
-            .fregs
-            .read(inst.get_rs1())
-            .to_f32(RoundingMode::TiesToEven);
-        let f64_val = self.fregs.read(inst.get_rs1());
+        let rs1_val = self.fp_read_s(inst.get_rs1());
         if rs1_val.is_negative_infinity() {
             self.regs.write(inst.get_rd(), 1 << 0);
         } else if rs1_val.is_negative_normal() {
@@ -1197,684 +3313,809 @@ impl RVCore {
             self.regs.write(inst.get_rd(), 1 << 6);
         } else if rs1_val.is_positive_infinity() {
             self.regs.write(inst.get_rd(), 1 << 7);
-        } else if f64_val.is_signaling_nan() {
+        } else if rs1_val.is_signaling_nan() {
             self.regs.write(inst.get_rd(), 1 << 8);
         } else if rs1_val.is_nan() {
             self.regs.write(inst.get_rd(), 1 << 9);
         }
     }
 
-    fn inst_fcvt_d_l(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self.regs.read(inst.get_rs1());
-        let result = Float::from_i64(rs1_val as i64, RoundingMode::TiesToEven);
-        self.fregs.write(inst.get_rd(), result);
+    fn inst_fclass_h(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.fp_read_h(inst.get_rs1());
+        let class = if rs1_val.is_negative_infinity() {
+            1 << 0
+        } else if rs1_val.is_negative_normal() {
+            1 << 1
+        } else if rs1_val.is_negative_subnormal() {
+            1 << 2
+        } else if rs1_val.is_negative_zero() {
+            1 << 3
+        } else if rs1_val.is_positive_zero() {
+            1 << 4
+        } else if rs1_val.is_positive_subnormal() {
+            1 << 5
+        } else if rs1_val.is_positive_normal() {
+            1 << 6
+        } else if rs1_val.is_positive_infinity() {
+            1 << 7
+        } else if rs1_val.is_signaling_nan() {
+            1 << 8
+        } else {
+            1 << 9
+        };
+        self.regs.write(inst.get_rd(), class);
     }
 
-    fn inst_fcvt_d_lu(&mut self, inst: &inst_type::InstType) {
+    fn inst_fcvt_d_l(&mut self, inst: &inst_type::InstType) {
+        let rm = match self.rounding_mode(inst) {
+            Some(rm) => rm,
+            None => return,
+        };
         let rs1_val = self.regs.read(inst.get_rs1());
-        let result = Float::from_u64(rs1_val, RoundingMode::TiesToEven);
-        self.fregs.write(inst.get_rd(), result);
-    }
-
-    fn inst_fcvt_d_w(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self.regs.read(inst.get_rs1()) & 0xffffffff;
-        let result = Float::from_i32(rs1_val as i32, RoundingMode::TiesToEven);
-        self.fregs.write(inst.get_rd(), result);
-    }
-
-    fn inst_fcvt_d_wu(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self.regs.read(inst.get_rs1()) & 0xffffffff;
-        let result = Float::from_u32(rs1_val as u32, RoundingMode::TiesToEven);
-        self.fregs.write(inst.get_rd(), result);
-    }
 
-    fn inst_fcvt_l_d(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self.fregs.read(inst.get_rs1());
         let mut flag = ExceptionFlags::default();
         flag.set();
-        let result = rs1_val.to_i64(RoundingMode::TowardZero, true);
+        let result = Float::from_i64(rs1_val as i64, rm);
         flag.get();
         self.update_fflags(&flag);
 
-        if rs1_val.is_nan() || rs1_val.is_positive_infinity() || (F64::from_i64(i64::MAX, RoundingMode::TiesToEven)).lt(rs1_val) {
-            self.regs.write(inst.get_rd(), i64::MAX as AddressType);
-        } else {
-            self.regs.write(inst.get_rd(), result as AddressType);
-        }
+        self.fp_write(inst.get_rd(), result);
     }
 
-    fn inst_fcvt_l_s(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self.fregs.read(inst.get_rs1());
+    fn inst_fcvt_d_lu(&mut self, inst: &inst_type::InstType) {
+        let rm = match self.rounding_mode(inst) {
+            Some(rm) => rm,
+            None => return,
+        };
+        let rs1_val = self.regs.read(inst.get_rs1());
+
         let mut flag = ExceptionFlags::default();
         flag.set();
-        let result = rs1_val.to_i64(RoundingMode::TowardZero, true);
+        let result = Float::from_u64(rs1_val, rm);
         flag.get();
         self.update_fflags(&flag);
 
-        if rs1_val.is_nan() || rs1_val.is_positive_infinity() {
-            self.regs.write(inst.get_rd(), i64::MAX as AddressType);
-        } else {
-            self.regs.write(inst.get_rd(), result as AddressType);
-        }
+        self.fp_write(inst.get_rd(), result);
     }
 
-    fn inst_fcvt_lu_d(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self.fregs.read(inst.get_rs1());
+    fn inst_fcvt_d_w(&mut self, inst: &inst_type::InstType) {
+        let rm = match self.rounding_mode(inst) {
+            Some(rm) => rm,
+            None => return,
+        };
+        let rs1_val = self.regs.read(inst.get_rs1()) & 0xffffffff;
+
         let mut flag = ExceptionFlags::default();
         flag.set();
-        let result = rs1_val.to_u64(RoundingMode::TowardZero, true);
+        let result = Float::from_i32(rs1_val as i32, rm);
         flag.get();
         self.update_fflags(&flag);
 
-        if rs1_val.is_nan() {
-            self.regs.write(inst.get_rd(), AddressType::MAX);
-        } else if rs1_val.is_negative() {
-            self.regs.write(inst.get_rd(), 0);
-        } else {
-            self.regs.write(inst.get_rd(), result as AddressType);
-        }
+        self.fp_write(inst.get_rd(), result);
     }
 
-    fn inst_fcvt_lu_s(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self.fregs.read(inst.get_rs1());
+    fn inst_fcvt_d_wu(&mut self, inst: &inst_type::InstType) {
+        let rm = match self.rounding_mode(inst) {
+            Some(rm) => rm,
+            None => return,
+        };
+        let rs1_val = self.regs.read(inst.get_rs1()) & 0xffffffff;
+
         let mut flag = ExceptionFlags::default();
         flag.set();
-        let result = rs1_val.to_u64(RoundingMode::TowardZero, true);
+        let result = Float::from_u32(rs1_val as u32, rm);
         flag.get();
         self.update_fflags(&flag);
 
-        if rs1_val.is_nan() {
-            self.regs.write(inst.get_rd(), AddressType::MAX);
-        } else if rs1_val.is_negative() {
-            self.regs.write(inst.get_rd(), 0);
-        } else {
-            self.regs.write(inst.get_rd(), result as AddressType);
-        }
+        self.fp_write(inst.get_rd(), result);
     }
 
-    fn inst_fcvt_s_d(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self.fregs.read(inst.get_rs1());
-        let result = rs1_val.to_f32(RoundingMode::TiesToEven);
-
-        if rs1_val.is_nan() {
-            self.fregs.write(inst.get_rd(), F64::quiet_nan());
-        } else {
-            self.fregs.write(inst.get_rd(), result.to_f64(RoundingMode::TiesToEven));
+    fn inst_fcvt_d_h(&mut self, inst: &inst_type::InstType) {
+        // Widening is exact, rm is only checked for reserved encodings
+        if self.rounding_mode(inst).is_none() {
+            return;
         }
-    }
+        let rs1_val = self.fp_read_h(inst.get_rs1());
 
-    fn inst_fcvt_d_s(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self.fregs.read(inst.get_rs1());
+        let mut flag = ExceptionFlags::default();
+        flag.set();
         let result = rs1_val.to_f64(RoundingMode::TiesToEven);
-        self.fregs.write(inst.get_rd(), result);
+        flag.get();
+        self.update_fflags(&flag);
+
+        self.fp_write(inst.get_rd(), RVCore::canonical_nan(result));
     }
 
-    fn inst_fcvt_s_l(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self.regs.read(inst.get_rs1());
-        let result = Float::from_i64(rs1_val as i64, RoundingMode::TiesToEven);
-        self.fregs.write(inst.get_rd(), result);
+    fn inst_fcvt_l_d(&mut self, inst: &inst_type::InstType) {
+        self.fcvt_to_int(inst, self.fp_read(inst.get_rs1()), true, 64);
     }
 
-    fn inst_fcvt_s_lu(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self.regs.read(inst.get_rs1());
-        let result = Float::from_u64(rs1_val, RoundingMode::TiesToEven);
-        self.fregs.write(inst.get_rd(), result);
+    fn inst_fcvt_l_s(&mut self, inst: &inst_type::InstType) {
+        self.fcvt_to_int(inst, self.fp_read_s(inst.get_rs1()), true, 64);
     }
 
-    fn inst_fcvt_s_w(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self.regs.read(inst.get_rs1()) & 0xffffffff;
-        let result = Float::from_i32(rs1_val as i32, RoundingMode::TiesToEven);
-        self.fregs.write(inst.get_rd(), result);
+    fn inst_fcvt_l_h(&mut self, inst: &inst_type::InstType) {
+        self.fcvt_to_int(inst, self.fp_read_h(inst.get_rs1()), true, 64);
     }
 
-    fn inst_fcvt_s_wu(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self.regs.read(inst.get_rs1()) & 0xffffffff;
-        let result = Float::from_u32(rs1_val as u32, RoundingMode::TiesToEven);
-        self.fregs.write(inst.get_rd(), result);
+    fn inst_fcvt_lu_d(&mut self, inst: &inst_type::InstType) {
+        self.fcvt_to_int(inst, self.fp_read(inst.get_rs1()), false, 64);
     }
 
-    fn inst_fcvt_w_d(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self.fregs.read(inst.get_rs1());
+    fn inst_fcvt_lu_s(&mut self, inst: &inst_type::InstType) {
+        self.fcvt_to_int(inst, self.fp_read_s(inst.get_rs1()), false, 64);
+    }
+
+    fn inst_fcvt_lu_h(&mut self, inst: &inst_type::InstType) {
+        self.fcvt_to_int(inst, self.fp_read_h(inst.get_rs1()), false, 64);
+    }
+
+    fn inst_fcvt_s_d(&mut self, inst: &inst_type::InstType) {
+        let rm = match self.rounding_mode(inst) {
+            Some(rm) => rm,
+            None => return,
+        };
+        let rs1_val = self.fp_read(inst.get_rs1());
 
         let mut flag = ExceptionFlags::default();
         flag.set();
-        let result = rs1_val.to_i32(RoundingMode::TowardZero, true);
+        let result = rs1_val.to_f32(rm);
         flag.get();
         self.update_fflags(&flag);
 
-        if (result.is_negative() && rs1_val.is_positive())
-            || (rs1_val.is_nan() && rs1_val.is_negative())
-        {
-            self.regs.write(inst.get_rd(), i32::MAX as AddressType);
-        } else {
-            self.regs.write(inst.get_rd(), result as AddressType);
-        }
+        self.fp_write_s(inst.get_rd(), RVCore::canonical_nan(result));
     }
 
-    fn inst_fcvt_w_s(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self.fregs.read(inst.get_rs1());
+    fn inst_fcvt_d_s(&mut self, inst: &inst_type::InstType) {
+        // Widening is exact, rm is only checked for reserved encodings
+        if self.rounding_mode(inst).is_none() {
+            return;
+        }
+        let rs1_val = self.fp_read_s(inst.get_rs1());
 
         let mut flag = ExceptionFlags::default();
         flag.set();
-        let result = rs1_val.to_i32(RoundingMode::TowardZero, true);
+        let result = rs1_val.to_f64(RoundingMode::TiesToEven);
         flag.get();
         self.update_fflags(&flag);
 
-        if (result.is_negative() && rs1_val.is_positive())
-            || (rs1_val.is_nan() && rs1_val.is_negative())
-        {
-            self.regs.write(inst.get_rd(), i32::MAX as AddressType);
-        } else {
-            self.regs.write(inst.get_rd(), result as AddressType);
-        }
+        self.fp_write(inst.get_rd(), RVCore::canonical_nan(result));
     }
 
-    fn inst_fcvt_wu_d(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self.fregs.read(inst.get_rs1());
+    fn inst_fcvt_s_l(&mut self, inst: &inst_type::InstType) {
+        let rm = match self.rounding_mode(inst) {
+            Some(rm) => rm,
+            None => return,
+        };
+        let rs1_val = self.regs.read(inst.get_rs1());
 
         let mut flag = ExceptionFlags::default();
         flag.set();
-        let result = rs1_val.to_u32(RoundingMode::TowardZero, true);
+        let result = F32::from_i64(rs1_val as i64, rm);
         flag.get();
         self.update_fflags(&flag);
 
-        if rs1_val.is_nan() {
-            self.regs.write(inst.get_rd(), AddressType::MAX);
-        } else if rs1_val.is_negative() {
-            self.regs.write(inst.get_rd(), 0 as AddressType);
-        } else {
-            self.regs.write(
-                inst.get_rd(),
-                RVCore::sign_extend(result as AddressType, 32)
-            );
-        }
+        self.fp_write_s(inst.get_rd(), result);
     }
 
-    fn inst_fcvt_wu_s(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self.fregs.read(inst.get_rs1());
+    fn inst_fcvt_s_lu(&mut self, inst: &inst_type::InstType) {
+        let rm = match self.rounding_mode(inst) {
+            Some(rm) => rm,
+            None => return,
+        };
+        let rs1_val = self.regs.read(inst.get_rs1());
 
         let mut flag = ExceptionFlags::default();
         flag.set();
-        let result = rs1_val.to_u32(RoundingMode::TowardZero, true);
+        let result = F32::from_u64(rs1_val, rm);
         flag.get();
         self.update_fflags(&flag);
 
-        if rs1_val.is_nan() {
-            self.regs.write(inst.get_rd(), AddressType::MAX);
-        } else if rs1_val.is_negative() {
-            self.regs.write(inst.get_rd(), 0 as AddressType);
-        } else {
-            self.regs.write(
-                inst.get_rd(),
-                RVCore::sign_extend(result as AddressType, 32),
-            );
-        }
+        self.fp_write_s(inst.get_rd(), result);
     }
 
-    fn inst_fdiv_d(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self.fregs.read(inst.get_rs1());
-        let rs2_val = self.fregs.read(inst.get_rs2_stype());
+    fn inst_fcvt_s_w(&mut self, inst: &inst_type::InstType) {
+        let rm = match self.rounding_mode(inst) {
+            Some(rm) => rm,
+            None => return,
+        };
+        let rs1_val = self.regs.read(inst.get_rs1()) & 0xffffffff;
 
         let mut flag = ExceptionFlags::default();
         flag.set();
-        let result = rs1_val.div(rs2_val, RoundingMode::TiesToEven);
+        let result = F32::from_i32(rs1_val as i32, rm);
         flag.get();
         self.update_fflags(&flag);
 
-        self.fregs.write(inst.get_rd(), result);
+        self.fp_write_s(inst.get_rd(), result);
     }
 
-    fn inst_fdiv_s(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self.fregs.read(inst.get_rs1());
-        let rs2_val = self.fregs.read(inst.get_rs2_stype());
+    fn inst_fcvt_s_wu(&mut self, inst: &inst_type::InstType) {
+        let rm = match self.rounding_mode(inst) {
+            Some(rm) => rm,
+            None => return,
+        };
+        let rs1_val = self.regs.read(inst.get_rs1()) & 0xffffffff;
 
         let mut flag = ExceptionFlags::default();
         flag.set();
-        let result = rs1_val.div(rs2_val, RoundingMode::TiesToEven);
+        let result = F32::from_u32(rs1_val as u32, rm);
         flag.get();
         self.update_fflags(&flag);
 
-        self.fregs.write(inst.get_rd(), result);
+        self.fp_write_s(inst.get_rd(), result);
     }
 
-    fn inst_fmadd_d(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self
-            .fregs
-            .read(inst.get_rs1());
-        let rs2_val = self
-            .fregs
-            .read(inst.get_rs2_stype());
-        let rs3_val = self
-            .fregs
-            .read(inst.get_rs3());
+    fn inst_fcvt_s_h(&mut self, inst: &inst_type::InstType) {
+        // Widening is exact, rm is only checked for reserved encodings
+        if self.rounding_mode(inst).is_none() {
+            return;
+        }
+        let rs1_val = self.fp_read_h(inst.get_rs1());
 
         let mut flag = ExceptionFlags::default();
         flag.set();
-        let mul_result = rs1_val.mul(rs2_val, RoundingMode::TiesToEven);
-        let result = mul_result.add(rs3_val, RoundingMode::TiesToEven);
+        let result = rs1_val.to_f32(RoundingMode::TiesToEven);
         flag.get();
         self.update_fflags(&flag);
 
-        self.fregs
-            .write(inst.get_rd(), result);
+        self.fp_write_s(inst.get_rd(), RVCore::canonical_nan(result));
     }
 
-    fn inst_fmadd_s(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self
-            .fregs
-            .read(inst.get_rs1())
-            .to_f32(RoundingMode::TiesToEven);
-        let rs2_val = self
-            .fregs
-            .read(inst.get_rs2_stype())
-            .to_f32(RoundingMode::TiesToEven);
-        let rs3_val = self
-            .fregs
-            .read(inst.get_rs3())
-            .to_f32(RoundingMode::TiesToEven);
+    fn inst_fcvt_h_d(&mut self, inst: &inst_type::InstType) {
+        let rm = match self.rounding_mode(inst) {
+            Some(rm) => rm,
+            None => return,
+        };
+        let rs1_val = self.fp_read(inst.get_rs1());
 
         let mut flag = ExceptionFlags::default();
         flag.set();
-        let mul_result = rs1_val.mul(rs2_val, RoundingMode::TiesToEven);
-        let result = mul_result.add(rs3_val, RoundingMode::TiesToEven);
+        let result = rs1_val.to_f16(rm);
         flag.get();
         self.update_fflags(&flag);
 
-        self.fregs
-            .write(inst.get_rd(), result.to_f64(RoundingMode::TiesToEven));
+        self.fp_write_h(inst.get_rd(), RVCore::canonical_nan(result));
     }
 
-    fn inst_fmax_d(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self
-            .fregs
-            .read(inst.get_rs1());
-        let rs1_val_f64 = self.fregs.read(inst.get_rs1());
-        let rs2_val = self
-            .fregs
-            .read(inst.get_rs2_stype());
-
-        if rs1_val.is_nan() {
-            if rs2_val.is_nan() {
-                self.fregs.write(inst.get_rd(), F64::quiet_nan());
-            } else {
-                self.fregs
-                    .write(inst.get_rd(), rs2_val);
-                if rs1_val_f64.is_signaling_nan() {
-                    self.csregs.write(csregs::FFLAGS, 0x10);
-                }
-            }
-        } else if rs2_val.is_nan() {
-            self.fregs
-                .write(inst.get_rd(), rs1_val);
-        } else {
-            if (rs1_val.is_positive_zero() && rs2_val.is_negative_zero())
-                || (rs1_val.is_negative_zero() && rs2_val.is_positive_zero())
-            {
-                self.fregs.write(inst.get_rd(), F64::positive_zero());
-            } else if rs1_val.lt(rs2_val) {
-                self.fregs
-                    .write(inst.get_rd(), rs2_val);
-            } else {
-                self.fregs
-                    .write(inst.get_rd(), rs1_val);
-            }
-        }
+    fn inst_fcvt_h_l(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1());
+        self.fcvt_int_to_h(inst, rs1_val as i64 as i128);
     }
 
-    fn inst_fmax_s(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self
-            .fregs
-            .read(inst.get_rs1())
-            .to_f32(RoundingMode::TiesToEven);
-        let rs1_val_f64 = self.fregs.read(inst.get_rs1());
-        let rs2_val = self
-            .fregs
-            .read(inst.get_rs2_stype())
-            .to_f32(RoundingMode::TiesToEven);
-
-        if rs1_val.is_nan() {
-            if rs2_val.is_nan() {
-                self.fregs.write(inst.get_rd(), F64::quiet_nan());
-            } else {
-                self.fregs
-                    .write(inst.get_rd(), rs2_val.to_f64(RoundingMode::TiesToEven));
-                if rs1_val_f64.is_signaling_nan() {
-                    self.csregs.write(csregs::FFLAGS, 0x10);
-                }
-            }
-        } else if rs2_val.is_nan() {
-            self.fregs
-                .write(inst.get_rd(), rs1_val.to_f64(RoundingMode::TiesToEven));
-        } else {
-            if (rs1_val.is_positive_zero() && rs2_val.is_negative_zero())
-                || (rs1_val.is_negative_zero() && rs2_val.is_positive_zero())
-            {
-                self.fregs.write(inst.get_rd(), F64::positive_zero());
-            } else if rs1_val.lt(rs2_val) {
-                self.fregs
-                    .write(inst.get_rd(), rs2_val.to_f64(RoundingMode::TiesToEven));
-            } else {
-                self.fregs
-                    .write(inst.get_rd(), rs1_val.to_f64(RoundingMode::TiesToEven));
-            }
-        }
+    fn inst_fcvt_h_lu(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1());
+        self.fcvt_int_to_h(inst, rs1_val as i128);
     }
 
-    fn inst_fmin_d(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self
-            .fregs
-            .read(inst.get_rs1());
-        let rs2_val = self
-            .fregs
-            .read(inst.get_rs2_stype());
+    fn inst_fcvt_h_s(&mut self, inst: &inst_type::InstType) {
+        let rm = match self.rounding_mode(inst) {
+            Some(rm) => rm,
+            None => return,
+        };
+        let rs1_val = self.fp_read_s(inst.get_rs1());
 
-        if rs1_val.is_nan() {
-            if rs2_val.is_nan() {
-                self.fregs.write(inst.get_rd(), F64::quiet_nan());
-            } else {
-                self.fregs
-                    .write(inst.get_rd(), rs2_val);
-            }
-        } else if rs2_val.is_nan() {
-            self.fregs
-                .write(inst.get_rd(), rs1_val);
-        } else {
-            if (rs1_val.is_positive_zero() && rs2_val.is_negative_zero())
-                || (rs1_val.is_negative_zero() && rs2_val.is_positive_zero())
-            {
-                self.fregs.write(inst.get_rd(), F64::negative_zero());
-            } else if rs1_val.lt(rs2_val) {
-                self.fregs
-                    .write(inst.get_rd(), rs1_val);
-            } else {
-                self.fregs
-                    .write(inst.get_rd(), rs2_val);
-            }
-        }
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let result = rs1_val.to_f16(rm);
+        flag.get();
+        self.update_fflags(&flag);
+
+        self.fp_write_h(inst.get_rd(), RVCore::canonical_nan(result));
     }
 
-    fn inst_fmin_s(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self
-            .fregs
-            .read(inst.get_rs1())
-            .to_f32(RoundingMode::TiesToEven);
-        let rs2_val = self
-            .fregs
-            .read(inst.get_rs2_stype())
-            .to_f32(RoundingMode::TiesToEven);
+    fn inst_fcvt_h_w(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1()) & 0xffffffff;
+        self.fcvt_int_to_h(inst, rs1_val as i32 as i128);
+    }
 
-        if rs1_val.is_nan() {
-            if rs2_val.is_nan() {
-                self.fregs.write(inst.get_rd(), F64::quiet_nan());
-            } else {
-                self.fregs
-                    .write(inst.get_rd(), rs2_val.to_f64(RoundingMode::TiesToEven));
-            }
-        } else if rs2_val.is_nan() {
-            self.fregs
-                .write(inst.get_rd(), rs1_val.to_f64(RoundingMode::TiesToEven));
-        } else {
-            if (rs1_val.is_positive_zero() && rs2_val.is_negative_zero())
-                || (rs1_val.is_negative_zero() && rs2_val.is_positive_zero())
-            {
-                self.fregs.write(inst.get_rd(), F64::negative_zero());
-            } else if rs1_val.lt(rs2_val) {
-                self.fregs
-                    .write(inst.get_rd(), rs1_val.to_f64(RoundingMode::TiesToEven));
-            } else {
-                self.fregs
-                    .write(inst.get_rd(), rs2_val.to_f64(RoundingMode::TiesToEven));
-            }
-        }
+    fn inst_fcvt_h_wu(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1()) & 0xffffffff;
+        self.fcvt_int_to_h(inst, rs1_val as i128);
     }
 
-    fn inst_fmsub_d(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self
-            .fregs
-            .read(inst.get_rs1());
-        let rs2_val = self
-            .fregs
-            .read(inst.get_rs2_stype());
-        let rs3_val = self
-            .fregs
-            .read(inst.get_rs3());
+    fn inst_fcvt_w_d(&mut self, inst: &inst_type::InstType) {
+        self.fcvt_to_int(inst, self.fp_read(inst.get_rs1()), true, 32);
+    }
 
-        let mut flag = ExceptionFlags::default();
-        flag.set();
-        let mul_result = rs1_val.mul(rs2_val, RoundingMode::TiesToEven);
-        let result = mul_result.sub(rs3_val, RoundingMode::TiesToEven);
-        flag.get();
-        self.update_fflags(&flag);
+    fn inst_fcvt_w_s(&mut self, inst: &inst_type::InstType) {
+        self.fcvt_to_int(inst, self.fp_read_s(inst.get_rs1()), true, 32);
+    }
 
-        self.fregs
-            .write(inst.get_rd(), result);
+    fn inst_fcvt_w_h(&mut self, inst: &inst_type::InstType) {
+        self.fcvt_to_int(inst, self.fp_read_h(inst.get_rs1()), true, 32);
     }
 
-    fn inst_fmsub_s(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self
-            .fregs
-            .read(inst.get_rs1())
-            .to_f32(RoundingMode::TiesToEven);
-        let rs2_val = self
-            .fregs
-            .read(inst.get_rs2_stype())
-            .to_f32(RoundingMode::TiesToEven);
-        let rs3_val = self
-            .fregs
-            .read(inst.get_rs3())
-            .to_f32(RoundingMode::TiesToEven);
+    fn inst_fcvt_wu_d(&mut self, inst: &inst_type::InstType) {
+        self.fcvt_to_int(inst, self.fp_read(inst.get_rs1()), false, 32);
+    }
 
-        let mut flag = ExceptionFlags::default();
-        flag.set();
-        let mul_result = rs1_val.mul(rs2_val, RoundingMode::TiesToEven);
-        let result = mul_result.sub(rs3_val, RoundingMode::TiesToEven);
-        flag.get();
-        self.update_fflags(&flag);
+    fn inst_fcvt_wu_s(&mut self, inst: &inst_type::InstType) {
+        self.fcvt_to_int(inst, self.fp_read_s(inst.get_rs1()), false, 32);
+    }
 
-        self.fregs
-            .write(inst.get_rd(), result.to_f64(RoundingMode::TiesToEven));
+    fn inst_fcvt_wu_h(&mut self, inst: &inst_type::InstType) {
+        self.fcvt_to_int(inst, self.fp_read_h(inst.get_rs1()), false, 32);
     }
 
-    fn inst_fnmadd_d(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self
-            .fregs
-            .read(inst.get_rs1());
-        let rs2_val = self
-            .fregs
-            .read(inst.get_rs2_stype());
-        let rs3_val = self
-            .fregs
-            .read(inst.get_rs3());
+    fn inst_fdiv_d(&mut self, inst: &inst_type::InstType) {
+        let rm = match self.rounding_mode(inst) {
+            Some(rm) => rm,
+            None => return,
+        };
+        let rs1_val = self.fp_read(inst.get_rs1());
+        let rs2_val = self.fp_read(inst.get_rs2_stype());
 
         let mut flag = ExceptionFlags::default();
         flag.set();
-        let mul_result = rs1_val.mul(rs2_val, RoundingMode::TiesToEven).neg();
-        let result = mul_result.sub(rs3_val, RoundingMode::TiesToEven);
+        let result = rs1_val.div(rs2_val, rm);
         flag.get();
         self.update_fflags(&flag);
 
-        self.fregs
-            .write(inst.get_rd(), result);
+        self.fp_write(inst.get_rd(), RVCore::canonical_nan(result));
     }
 
-    fn inst_fnmadd_s(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self
-            .fregs
-            .read(inst.get_rs1())
-            .to_f32(RoundingMode::TiesToEven);
-        let rs2_val = self
-            .fregs
-            .read(inst.get_rs2_stype())
-            .to_f32(RoundingMode::TiesToEven);
-        let rs3_val = self
-            .fregs
-            .read(inst.get_rs3())
-            .to_f32(RoundingMode::TiesToEven);
+    fn inst_fdiv_s(&mut self, inst: &inst_type::InstType) {
+        let rm = match self.rounding_mode(inst) {
+            Some(rm) => rm,
+            None => return,
+        };
+        let rs1_val = self.fp_read_s(inst.get_rs1());
+        let rs2_val = self.fp_read_s(inst.get_rs2_stype());
 
         let mut flag = ExceptionFlags::default();
         flag.set();
-        let mul_result = rs1_val.mul(rs2_val, RoundingMode::TiesToEven).neg();
-        let result = mul_result.sub(rs3_val, RoundingMode::TiesToEven);
+        let result = rs1_val.div(rs2_val, rm);
         flag.get();
         self.update_fflags(&flag);
 
-        self.fregs
-            .write(inst.get_rd(), result.to_f64(RoundingMode::TiesToEven));
+        self.fp_write_s(inst.get_rd(), RVCore::canonical_nan(result));
     }
 
-    fn inst_fnmsub_d(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self
-            .fregs
-            .read(inst.get_rs1());
-        let rs2_val = self
-            .fregs
-            .read(inst.get_rs2_stype());
-        let rs3_val = self
-            .fregs
-            .read(inst.get_rs3());
+    fn inst_fdiv_h(&mut self, inst: &inst_type::InstType) {
+        let rm = match self.rounding_mode(inst) {
+            Some(rm) => rm,
+            None => return,
+        };
+        let rs1_val = self.fp_read_h(inst.get_rs1());
+        let rs2_val = self.fp_read_h(inst.get_rs2_stype());
 
         let mut flag = ExceptionFlags::default();
         flag.set();
-        let mul_result = rs1_val.mul(rs2_val, RoundingMode::TiesToEven).neg();
-        let result = mul_result.add(rs3_val, RoundingMode::TiesToEven);
+        let result = rs1_val.div(rs2_val, rm);
         flag.get();
         self.update_fflags(&flag);
 
-        self.fregs
-            .write(inst.get_rd(), result);
+        self.fp_write_h(inst.get_rd(), RVCore::canonical_nan(result));
     }
 
-    fn inst_fnmsub_s(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self
-            .fregs
-            .read(inst.get_rs1())
-            .to_f32(RoundingMode::TiesToEven);
-        let rs2_val = self
-            .fregs
-            .read(inst.get_rs2_stype())
-            .to_f32(RoundingMode::TiesToEven);
-        let rs3_val = self
-            .fregs
-            .read(inst.get_rs3())
-            .to_f32(RoundingMode::TiesToEven);
+    fn inst_fmadd_d(&mut self, inst: &inst_type::InstType) {
+        let rm = match self.rounding_mode(inst) {
+            Some(rm) => rm,
+            None => return,
+        };
+        let rs1_val = self.fp_read(inst.get_rs1());
+        let rs2_val = self.fp_read(inst.get_rs2_stype());
+        let rs3_val = self.fp_read(inst.get_rs3());
 
         let mut flag = ExceptionFlags::default();
         flag.set();
-        let mul_result = rs1_val.mul(rs2_val, RoundingMode::TiesToEven).neg();
-        let result = mul_result.add(rs3_val, RoundingMode::TiesToEven);
+        let result = rs1_val.fused_mul_add(rs2_val, rs3_val, rm);
         flag.get();
         self.update_fflags(&flag);
 
-        self.fregs
-            .write(inst.get_rd(), result.to_f64(RoundingMode::TiesToEven));
+        self.fp_write(inst.get_rd(), RVCore::canonical_nan(result));
     }
 
-    fn inst_fsqrt_d(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self.fregs.read(inst.get_rs1());
+    fn inst_fmadd_s(&mut self, inst: &inst_type::InstType) {
+        let rm = match self.rounding_mode(inst) {
+            Some(rm) => rm,
+            None => return,
+        };
+        let rs1_val = self.fp_read_s(inst.get_rs1());
+        let rs2_val = self.fp_read_s(inst.get_rs2_stype());
+        let rs3_val = self.fp_read_s(inst.get_rs3());
 
         let mut flag = ExceptionFlags::default();
         flag.set();
-        let result = rs1_val.sqrt(RoundingMode::TiesToEven);
+        let result = rs1_val.fused_mul_add(rs2_val, rs3_val, rm);
         flag.get();
         self.update_fflags(&flag);
 
-        if rs1_val.is_negative() {
-            self.fregs.write(inst.get_rd(), F64::quiet_nan());
-        } else {
-            self.fregs.write(inst.get_rd(), result);
-        }
+        self.fp_write_s(inst.get_rd(), RVCore::canonical_nan(result));
     }
 
-    fn inst_fsqrt_s(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self.fregs.read(inst.get_rs1());
+    fn inst_fmadd_h(&mut self, inst: &inst_type::InstType) {
+        let rm = match self.rounding_mode(inst) {
+            Some(rm) => rm,
+            None => return,
+        };
+        let rs1_val = self.fp_read_h(inst.get_rs1());
+        let rs2_val = self.fp_read_h(inst.get_rs2_stype());
+        let rs3_val = self.fp_read_h(inst.get_rs3());
 
         let mut flag = ExceptionFlags::default();
         flag.set();
-        let result = rs1_val.sqrt(RoundingMode::TiesToEven);
+        let result = rs1_val.fused_mul_add(rs2_val, rs3_val, rm);
         flag.get();
         self.update_fflags(&flag);
 
-        if rs1_val.is_negative() {
-            self.fregs.write(inst.get_rd(), F64::quiet_nan());
-        } else {
-            self.fregs.write(inst.get_rd(), result);
-        }
+        self.fp_write_h(inst.get_rd(), RVCore::canonical_nan(result));
     }
 
-    fn inst_fsgnj_d(&mut self, inst: &inst_type::InstType) {
-        let mut rs1_val = self.fregs.read(inst.get_rs1());
-        let rs2_val = self.fregs.read(inst.get_rs2_stype());
-        rs1_val.set_sign(rs2_val.sign());
-        self.fregs.write(inst.get_rd(), rs1_val);
+    fn inst_fmax_d(&mut self, inst: &inst_type::InstType) {
+        let result = self.fmin_max(
+            self.fp_read(inst.get_rs1()),
+            self.fp_read(inst.get_rs2_stype()),
+            true,
+        );
+        self.fp_write(inst.get_rd(), result);
     }
 
-    fn inst_fsgnj_s(&mut self, inst: &inst_type::InstType) {
-        let mut rs1_val = self.fregs.read(inst.get_rs1()).to_f32(RoundingMode::TiesToEven);
-        let rs2_val = self.fregs.read(inst.get_rs2_stype()).to_f32(RoundingMode::TiesToEven);
-        rs1_val.set_sign(rs2_val.sign());
-        self.fregs.write(inst.get_rd(), rs1_val.to_f64(RoundingMode::TiesToEven));
+    fn inst_fmax_s(&mut self, inst: &inst_type::InstType) {
+        let result = self.fmin_max(
+            self.fp_read_s(inst.get_rs1()),
+            self.fp_read_s(inst.get_rs2_stype()),
+            true,
+        );
+        self.fp_write_s(inst.get_rd(), result);
     }
 
-    fn inst_fsgnjn_d(&mut self, inst: &inst_type::InstType) {
-        let mut rs1_val = self.fregs.read(inst.get_rs1());
-        let rs2_val = self.fregs.read(inst.get_rs2_stype());
-        rs1_val.set_sign(rs2_val.sign() ^ 1);
-        self.fregs.write(inst.get_rd(), rs1_val);
+    fn inst_fmax_h(&mut self, inst: &inst_type::InstType) {
+        let result = self.fmin_max(
+            self.fp_read_h(inst.get_rs1()),
+            self.fp_read_h(inst.get_rs2_stype()),
+            true,
+        );
+        self.fp_write_h(inst.get_rd(), result);
     }
 
-    fn inst_fsgnjn_s(&mut self, inst: &inst_type::InstType) {
-        let mut rs1_val = self.fregs.read(inst.get_rs1());
-        let rs2_val = self.fregs.read(inst.get_rs2_stype());
-        rs1_val.set_sign(rs2_val.sign() ^ 1);
-        self.fregs.write(inst.get_rd(), rs1_val);
+    fn inst_fmin_d(&mut self, inst: &inst_type::InstType) {
+        let result = self.fmin_max(
+            self.fp_read(inst.get_rs1()),
+            self.fp_read(inst.get_rs2_stype()),
+            false,
+        );
+        self.fp_write(inst.get_rd(), result);
     }
 
-    fn inst_fsgnjx_d(&mut self, inst: &inst_type::InstType) {
-        let mut rs1_val = self.fregs.read(inst.get_rs1());
-        let rs2_val = self.fregs.read(inst.get_rs2_stype());
-        rs1_val.set_sign(rs2_val.sign() ^ rs1_val.sign());
-        self.fregs.write(inst.get_rd(), rs1_val);
+    fn inst_fmin_s(&mut self, inst: &inst_type::InstType) {
+        let result = self.fmin_max(
+            self.fp_read_s(inst.get_rs1()),
+            self.fp_read_s(inst.get_rs2_stype()),
+            false,
+        );
+        self.fp_write_s(inst.get_rd(), result);
     }
 
-    fn inst_fsgnjx_s(&mut self, inst: &inst_type::InstType) {
-        let mut rs1_val = self.fregs.read(inst.get_rs1());
-        let rs2_val = self.fregs.read(inst.get_rs2_stype());
-        rs1_val.set_sign(rs2_val.sign() ^ rs1_val.sign());
-        self.fregs.write(inst.get_rd(), rs1_val);
+    fn inst_fmin_h(&mut self, inst: &inst_type::InstType) {
+        let result = self.fmin_max(
+            self.fp_read_h(inst.get_rs1()),
+            self.fp_read_h(inst.get_rs2_stype()),
+            false,
+        );
+        self.fp_write_h(inst.get_rd(), result);
     }
 
-    fn inst_fence(&mut self, _inst: &inst_type::InstType) {}
+    fn inst_fmsub_d(&mut self, inst: &inst_type::InstType) {
+        let rm = match self.rounding_mode(inst) {
+            Some(rm) => rm,
+            None => return,
+        };
+        let rs1_val = self.fp_read(inst.get_rs1());
+        let rs2_val = self.fp_read(inst.get_rs2_stype());
+        let rs3_val = self.fp_read(inst.get_rs3());
 
-    fn inst_feq_d(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self.fregs.read(inst.get_rs1());
-        let rs2_val = self.fregs.read(inst.get_rs2_stype());
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let result = rs1_val.fused_mul_add(rs2_val, rs3_val.neg(), rm);
+        flag.get();
+        self.update_fflags(&flag);
 
-        if rs1_val.is_signaling_nan() || rs2_val.is_signaling_nan() {
-            self.csregs.write(0x1, 0x10);
-            self.regs.write(inst.get_rd(), 0);
-        } else {
-            if rs1_val.eq(rs2_val) {
-                self.regs.write(inst.get_rd(), 1);
-            } else {
-                self.regs.write(inst.get_rd(), 0);
-            }
-        }
+        self.fp_write(inst.get_rd(), RVCore::canonical_nan(result));
+    }
+
+    fn inst_fmsub_s(&mut self, inst: &inst_type::InstType) {
+        let rm = match self.rounding_mode(inst) {
+            Some(rm) => rm,
+            None => return,
+        };
+        let rs1_val = self.fp_read_s(inst.get_rs1());
+        let rs2_val = self.fp_read_s(inst.get_rs2_stype());
+        let rs3_val = self.fp_read_s(inst.get_rs3());
+
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let result = rs1_val.fused_mul_add(rs2_val, rs3_val.neg(), rm);
+        flag.get();
+        self.update_fflags(&flag);
+
+        self.fp_write_s(inst.get_rd(), RVCore::canonical_nan(result));
+    }
+
+    fn inst_fmsub_h(&mut self, inst: &inst_type::InstType) {
+        let rm = match self.rounding_mode(inst) {
+            Some(rm) => rm,
+            None => return,
+        };
+        let rs1_val = self.fp_read_h(inst.get_rs1());
+        let rs2_val = self.fp_read_h(inst.get_rs2_stype());
+        let rs3_val = self.fp_read_h(inst.get_rs3());
+
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let result = rs1_val.fused_mul_add(rs2_val, rs3_val.neg(), rm);
+        flag.get();
+        self.update_fflags(&flag);
+
+        self.fp_write_h(inst.get_rd(), RVCore::canonical_nan(result));
+    }
+
+    fn inst_fnmadd_d(&mut self, inst: &inst_type::InstType) {
+        let rm = match self.rounding_mode(inst) {
+            Some(rm) => rm,
+            None => return,
+        };
+        let rs1_val = self.fp_read(inst.get_rs1());
+        let rs2_val = self.fp_read(inst.get_rs2_stype());
+        let rs3_val = self.fp_read(inst.get_rs3());
+
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let result = rs1_val.neg().fused_mul_add(rs2_val, rs3_val.neg(), rm);
+        flag.get();
+        self.update_fflags(&flag);
+
+        self.fp_write(inst.get_rd(), RVCore::canonical_nan(result));
+    }
+
+    fn inst_fnmadd_s(&mut self, inst: &inst_type::InstType) {
+        let rm = match self.rounding_mode(inst) {
+            Some(rm) => rm,
+            None => return,
+        };
+        let rs1_val = self.fp_read_s(inst.get_rs1());
+        let rs2_val = self.fp_read_s(inst.get_rs2_stype());
+        let rs3_val = self.fp_read_s(inst.get_rs3());
+
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let result = rs1_val.neg().fused_mul_add(rs2_val, rs3_val.neg(), rm);
+        flag.get();
+        self.update_fflags(&flag);
+
+        self.fp_write_s(inst.get_rd(), RVCore::canonical_nan(result));
+    }
+
+    fn inst_fnmadd_h(&mut self, inst: &inst_type::InstType) {
+        let rm = match self.rounding_mode(inst) {
+            Some(rm) => rm,
+            None => return,
+        };
+        let rs1_val = self.fp_read_h(inst.get_rs1());
+        let rs2_val = self.fp_read_h(inst.get_rs2_stype());
+        let rs3_val = self.fp_read_h(inst.get_rs3());
+
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let result =
+            rs1_val
+                .neg()
+                .fused_mul_add(rs2_val, rs3_val.neg(), rm);
+        flag.get();
+        self.update_fflags(&flag);
+
+        self.fp_write_h(inst.get_rd(), RVCore::canonical_nan(result));
+    }
+
+    fn inst_fnmsub_d(&mut self, inst: &inst_type::InstType) {
+        let rm = match self.rounding_mode(inst) {
+            Some(rm) => rm,
+            None => return,
+        };
+        let rs1_val = self.fp_read(inst.get_rs1());
+        let rs2_val = self.fp_read(inst.get_rs2_stype());
+        let rs3_val = self.fp_read(inst.get_rs3());
+
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let result = rs1_val.neg().fused_mul_add(rs2_val, rs3_val, rm);
+        flag.get();
+        self.update_fflags(&flag);
+
+        self.fp_write(inst.get_rd(), RVCore::canonical_nan(result));
+    }
+
+    fn inst_fnmsub_s(&mut self, inst: &inst_type::InstType) {
+        let rm = match self.rounding_mode(inst) {
+            Some(rm) => rm,
+            None => return,
+        };
+        let rs1_val = self.fp_read_s(inst.get_rs1());
+        let rs2_val = self.fp_read_s(inst.get_rs2_stype());
+        let rs3_val = self.fp_read_s(inst.get_rs3());
+
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let result = rs1_val.neg().fused_mul_add(rs2_val, rs3_val, rm);
+        flag.get();
+        self.update_fflags(&flag);
+
+        self.fp_write_s(inst.get_rd(), RVCore::canonical_nan(result));
+    }
+
+    fn inst_fnmsub_h(&mut self, inst: &inst_type::InstType) {
+        let rm = match self.rounding_mode(inst) {
+            Some(rm) => rm,
+            None => return,
+        };
+        let rs1_val = self.fp_read_h(inst.get_rs1());
+        let rs2_val = self.fp_read_h(inst.get_rs2_stype());
+        let rs3_val = self.fp_read_h(inst.get_rs3());
+
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let result = rs1_val
+            .neg()
+            .fused_mul_add(rs2_val, rs3_val, rm);
+        flag.get();
+        self.update_fflags(&flag);
+
+        self.fp_write_h(inst.get_rd(), RVCore::canonical_nan(result));
+    }
+
+    fn inst_fsqrt_d(&mut self, inst: &inst_type::InstType) {
+        let rm = match self.rounding_mode(inst) {
+            Some(rm) => rm,
+            None => return,
+        };
+        let rs1_val = self.fp_read(inst.get_rs1());
+
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let result = rs1_val.sqrt(rm);
+        flag.get();
+        self.update_fflags(&flag);
+
+        self.fp_write(inst.get_rd(), RVCore::canonical_nan(result));
+    }
+
+    fn inst_fsqrt_s(&mut self, inst: &inst_type::InstType) {
+        let rm = match self.rounding_mode(inst) {
+            Some(rm) => rm,
+            None => return,
+        };
+        let rs1_val = self.fp_read_s(inst.get_rs1());
+
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let result = rs1_val.sqrt(rm);
+        flag.get();
+        self.update_fflags(&flag);
+
+        self.fp_write_s(inst.get_rd(), RVCore::canonical_nan(result));
+    }
+
+    fn inst_fsqrt_h(&mut self, inst: &inst_type::InstType) {
+        let rm = match self.rounding_mode(inst) {
+            Some(rm) => rm,
+            None => return,
+        };
+        let rs1_val = self.fp_read_h(inst.get_rs1());
+
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let result = rs1_val.sqrt(rm);
+        flag.get();
+        self.update_fflags(&flag);
+
+        self.fp_write_h(inst.get_rd(), RVCore::canonical_nan(result));
+    }
+
+    fn inst_fsgnj_d(&mut self, inst: &inst_type::InstType) {
+        let mut rs1_val = self.fp_read(inst.get_rs1());
+        let rs2_val = self.fp_read(inst.get_rs2_stype());
+        rs1_val.set_sign(rs2_val.sign());
+        self.fp_write(inst.get_rd(), rs1_val);
+    }
+
+    fn inst_fsgnj_s(&mut self, inst: &inst_type::InstType) {
+        let mut rs1_val = self.fp_read_s(inst.get_rs1());
+        let rs2_val = self.fp_read_s(inst.get_rs2_stype());
+        rs1_val.set_sign(rs2_val.sign());
+        self.fp_write_s(inst.get_rd(), rs1_val);
+    }
+
+    fn inst_fsgnj_h(&mut self, inst: &inst_type::InstType) {
+        let mut rs1_val = self.fp_read_h(inst.get_rs1());
+        let rs2_val = self.fp_read_h(inst.get_rs2_stype());
+        rs1_val.set_sign(rs2_val.sign());
+        self.fp_write_h(inst.get_rd(), rs1_val);
+    }
+
+    fn inst_fsgnjn_d(&mut self, inst: &inst_type::InstType) {
+        let mut rs1_val = self.fp_read(inst.get_rs1());
+        let rs2_val = self.fp_read(inst.get_rs2_stype());
+        rs1_val.set_sign(rs2_val.sign() ^ 1);
+        self.fp_write(inst.get_rd(), rs1_val);
+    }
+
+    fn inst_fsgnjn_s(&mut self, inst: &inst_type::InstType) {
+        let mut rs1_val = self.fp_read_s(inst.get_rs1());
+        let rs2_val = self.fp_read_s(inst.get_rs2_stype());
+        rs1_val.set_sign(rs2_val.sign() ^ 1);
+        self.fp_write_s(inst.get_rd(), rs1_val);
+    }
+
+    fn inst_fsgnjn_h(&mut self, inst: &inst_type::InstType) {
+        let mut rs1_val = self.fp_read_h(inst.get_rs1());
+        let rs2_val = self.fp_read_h(inst.get_rs2_stype());
+        rs1_val.set_sign(rs2_val.sign() ^ 1);
+        self.fp_write_h(inst.get_rd(), rs1_val);
+    }
+
+    fn inst_fsgnjx_d(&mut self, inst: &inst_type::InstType) {
+        let mut rs1_val = self.fp_read(inst.get_rs1());
+        let rs2_val = self.fp_read(inst.get_rs2_stype());
+        rs1_val.set_sign(rs2_val.sign() ^ rs1_val.sign());
+        self.fp_write(inst.get_rd(), rs1_val);
+    }
+
+    fn inst_fsgnjx_s(&mut self, inst: &inst_type::InstType) {
+        let mut rs1_val = self.fp_read_s(inst.get_rs1());
+        let rs2_val = self.fp_read_s(inst.get_rs2_stype());
+        rs1_val.set_sign(rs2_val.sign() ^ rs1_val.sign());
+        self.fp_write_s(inst.get_rd(), rs1_val);
+    }
+
+    fn inst_fsgnjx_h(&mut self, inst: &inst_type::InstType) {
+        let mut rs1_val = self.fp_read_h(inst.get_rs1());
+        let rs2_val = self.fp_read_h(inst.get_rs2_stype());
+        rs1_val.set_sign(rs2_val.sign() ^ rs1_val.sign());
+        self.fp_write_h(inst.get_rd(), rs1_val);
+    }
+
+    fn inst_fence(&mut self, _inst: &inst_type::InstType) {}
+
+    fn inst_fence_i(&mut self, _inst: &inst_type::InstType) {}
+
+    fn inst_feq_d(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.fp_read(inst.get_rs1());
+        let rs2_val = self.fp_read(inst.get_rs2_stype());
+
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let result = rs1_val.eq(rs2_val);
+        flag.get();
+        self.update_fflags(&flag);
+
+        self.regs.write(inst.get_rd(), result as AddressType);
     }
 
     fn inst_feq_s(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self.fregs.read(inst.get_rs1());
-        let rs2_val = self.fregs.read(inst.get_rs2_stype());
+        let rs1_val = self.fp_read_s(inst.get_rs1());
+        let rs2_val = self.fp_read_s(inst.get_rs2_stype());
 
-        if rs1_val.is_signaling_nan() || rs2_val.is_signaling_nan() {
-            self.csregs.write(0x1, 0x10);
-            self.regs.write(inst.get_rd(), 0);
-        } else {
-            if rs1_val.eq(rs2_val) {
-                self.regs.write(inst.get_rd(), 1);
-            } else {
-                self.regs.write(inst.get_rd(), 0);
-            }
-        }
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let result = rs1_val.eq(rs2_val);
+        flag.get();
+        self.update_fflags(&flag);
+
+        self.regs.write(inst.get_rd(), result as AddressType);
+    }
+
+    fn inst_feq_h(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.fp_read_h(inst.get_rs1());
+        let rs2_val = self.fp_read_h(inst.get_rs2_stype());
+
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let result = rs1_val.eq(rs2_val);
+        flag.get();
+        self.update_fflags(&flag);
+
+        self.regs.write(inst.get_rd(), result as AddressType);
     }
 
     fn inst_fld(&mut self, inst: &inst_type::InstType) {
@@ -1884,272 +4125,660 @@ impl RVCore {
         let mut data = [0; 8];
         self.read_memory(addr, &mut data);
 
-        let f64_val = F64::from_bits(u64::from_le_bytes(data));
-        if f64_val.is_signaling_nan() || u64::from_le_bytes(data) == 0x7f800001 {
-            self.fregs
-                .write(inst.get_rd(), F64::from_bits(0x7ff0000000000001));
-        } else {
-            self.fregs
-                .write(inst.get_rd(), f64_val);
-        }
+        self.fregs
+            .write_bits(inst.get_rd(), u64::from_le_bytes(data));
+    }
+
+    fn inst_fle_d(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.fp_read(inst.get_rs1());
+        let rs2_val = self.fp_read(inst.get_rs2_stype());
+
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let result = rs1_val.le(rs2_val);
+        flag.get();
+        self.update_fflags(&flag);
+
+        self.regs.write(inst.get_rd(), result as AddressType);
+    }
+
+    fn inst_fle_s(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.fp_read_s(inst.get_rs1());
+        let rs2_val = self.fp_read_s(inst.get_rs2_stype());
+
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let result = rs1_val.le(rs2_val);
+        flag.get();
+        self.update_fflags(&flag);
+
+        self.regs.write(inst.get_rd(), result as AddressType);
+    }
+
+    fn inst_fle_h(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.fp_read_h(inst.get_rs1());
+        let rs2_val = self.fp_read_h(inst.get_rs2_stype());
+
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let result = rs1_val.le(rs2_val);
+        flag.get();
+        self.update_fflags(&flag);
+
+        self.regs.write(inst.get_rd(), result as AddressType);
+    }
+
+    fn inst_flt_d(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.fp_read(inst.get_rs1());
+        let rs2_val = self.fp_read(inst.get_rs2_stype());
+
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let result = rs1_val.lt(rs2_val);
+        flag.get();
+        self.update_fflags(&flag);
+
+        self.regs.write(inst.get_rd(), result as AddressType);
+    }
+
+    fn inst_flt_s(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.fp_read_s(inst.get_rs1());
+        let rs2_val = self.fp_read_s(inst.get_rs2_stype());
+
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let result = rs1_val.lt(rs2_val);
+        flag.get();
+        self.update_fflags(&flag);
+
+        self.regs.write(inst.get_rd(), result as AddressType);
+    }
+
+    fn inst_flt_h(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.fp_read_h(inst.get_rs1());
+        let rs2_val = self.fp_read_h(inst.get_rs2_stype());
+
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let result = rs1_val.lt(rs2_val);
+        flag.get();
+        self.update_fflags(&flag);
+
+        self.regs.write(inst.get_rd(), result as AddressType);
+    }
+
+    fn inst_flw(&mut self, inst: &inst_type::InstType) {
+        let base = self.regs.read(inst.get_rs1());
+        let offset = RVCore::sign_extend(inst.get_imm_itype(), 12);
+        let addr = base.wrapping_add(offset);
+        let mut data = [0; 4];
+        self.read_memory(addr, &mut data);
+
+        self.fregs
+            .write_s(inst.get_rd(), F32::from_bits(u32::from_le_bytes(data)));
+    }
+
+    fn inst_flh(&mut self, inst: &inst_type::InstType) {
+        let base = self.regs.read(inst.get_rs1());
+        let offset = RVCore::sign_extend(inst.get_imm_itype(), 12);
+        let addr = base.wrapping_add(offset);
+        let mut data = [0; 2];
+        self.read_memory(addr, &mut data);
+        self.fregs
+            .write_h(inst.get_rd(), F16::from_bits(u16::from_le_bytes(data)));
+    }
+
+    fn inst_fsd(&mut self, inst: &inst_type::InstType) {
+        let base = self.regs.read(inst.get_rs1());
+        let offset = RVCore::sign_extend(inst.get_imm_btype(), 12);
+        let addr = base.wrapping_add(offset);
+        let data = self
+            .fregs
+            .read(inst.get_rs2_stype())
+            .to_bits();
+        self.write_memory(addr, &mut data.to_le_bytes());
+    }
+
+    fn inst_fsw(&mut self, inst: &inst_type::InstType) {
+        let base = self.regs.read(inst.get_rs1());
+        let offset = RVCore::sign_extend(inst.get_imm_btype(), 12);
+        let addr = base.wrapping_add(offset);
+        // Stores the raw low bits, even if the register is not NaN-boxed
+        let data = self.fregs.read_bits(inst.get_rs2_stype()) as u32;
+        self.write_memory(addr, &mut data.to_le_bytes());
+    }
+
+    // The low 16 bits are stored as is, whether or not the register holds a boxed half
+    fn inst_fsh(&mut self, inst: &inst_type::InstType) {
+        let base = self.regs.read(inst.get_rs1());
+        let offset = RVCore::sign_extend(inst.get_imm_btype(), 12);
+        let addr = base.wrapping_add(offset);
+        let data = self.fregs.read(inst.get_rs2_stype()).to_bits() as u16;
+        self.write_memory(addr, &mut data.to_le_bytes());
+    }
+
+    fn inst_fmul_d(&mut self, inst: &inst_type::InstType) {
+        let rm = match self.rounding_mode(inst) {
+            Some(rm) => rm,
+            None => return,
+        };
+        let rs1_val = self.fp_read(inst.get_rs1());
+        let rs2_val = self.fp_read(inst.get_rs2_stype());
+
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let result = rs1_val.mul(rs2_val, rm);
+        flag.get();
+
+        self.update_fflags(&flag);
+
+        self.fp_write(inst.get_rd(), RVCore::canonical_nan(result));
+    }
+
+    fn inst_fmul_s(&mut self, inst: &inst_type::InstType) {
+        let rm = match self.rounding_mode(inst) {
+            Some(rm) => rm,
+            None => return,
+        };
+        let rs1_val = self.fp_read_s(inst.get_rs1());
+        let rs2_val = self.fp_read_s(inst.get_rs2_stype());
+
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let result = rs1_val.mul(rs2_val, rm);
+        flag.get();
+
+        self.update_fflags(&flag);
+
+        self.fp_write_s(inst.get_rd(), RVCore::canonical_nan(result));
+    }
+
+    fn inst_fmul_h(&mut self, inst: &inst_type::InstType) {
+        let rm = match self.rounding_mode(inst) {
+            Some(rm) => rm,
+            None => return,
+        };
+        let rs1_val = self.fp_read_h(inst.get_rs1());
+        let rs2_val = self.fp_read_h(inst.get_rs2_stype());
+
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let result = rs1_val.mul(rs2_val, rm);
+        flag.get();
+        self.update_fflags(&flag);
+
+        self.fp_write_h(inst.get_rd(), RVCore::canonical_nan(result));
+    }
+
+    fn inst_fmv_d_x(&mut self, inst: &inst_type::InstType) {
+        let rs1 = inst.get_rs1();
+        let rs1_val = self.regs.read(rs1);
+        self.fregs.write_bits(inst.get_rd(), rs1_val);
+    }
+
+    fn inst_fmv_h_x(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1());
+        self.fregs
+            .write_h(inst.get_rd(), F16::from_bits(rs1_val as u16));
+    }
+
+    fn inst_fmv_w_x(&mut self, inst: &inst_type::InstType) {
+        let rs1 = inst.get_rs1();
+        let rs1_lower_val = self.regs.read(rs1) as u32;
+        self.fregs
+            .write_s(inst.get_rd(), F32::from_bits(rs1_lower_val));
+    }
+
+    fn inst_fmv_x_d(&mut self, inst: &inst_type::InstType) {
+        let rs1 = inst.get_rs1();
+        let rs1_val = self.fregs.read(rs1);
+        self.regs.write(inst.get_rd(), rs1_val.to_bits());
+    }
+
+    fn inst_fmv_x_h(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.fregs.read(inst.get_rs1()).to_bits();
+        self.regs.write(
+            inst.get_rd(),
+            RVCore::sign_extend(rs1_val & 0xffff, 16),
+        );
+    }
+
+    fn inst_fmv_x_w(&mut self, inst: &inst_type::InstType) {
+        let rs1 = inst.get_rs1();
+        let rs1_val = self.fregs.read_bits(rs1);
+        self.regs.write(
+            inst.get_rd(),
+            RVCore::sign_extend(rs1_val & 0xffffffff, 32),
+        );
+    }
+
+    fn inst_fsub_d(&mut self, inst: &inst_type::InstType) {
+        let rm = match self.rounding_mode(inst) {
+            Some(rm) => rm,
+            None => return,
+        };
+        let rs1_val = self.fp_read(inst.get_rs1());
+        let rs2_val = self.fp_read(inst.get_rs2_stype());
+
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let result = rs1_val.sub(rs2_val, rm);
+        flag.get();
+        self.update_fflags(&flag);
+
+        self.fp_write(inst.get_rd(), RVCore::canonical_nan(result));
+    }
+
+    fn inst_fsub_s(&mut self, inst: &inst_type::InstType) {
+        let rm = match self.rounding_mode(inst) {
+            Some(rm) => rm,
+            None => return,
+        };
+        let rs1_val = self.fp_read_s(inst.get_rs1());
+        let rs2_val = self.fp_read_s(inst.get_rs2_stype());
+
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let result = rs1_val.sub(rs2_val, rm);
+        flag.get();
+        self.update_fflags(&flag);
+
+        self.fp_write_s(inst.get_rd(), RVCore::canonical_nan(result));
+    }
+
+    fn inst_fsub_h(&mut self, inst: &inst_type::InstType) {
+        let rm = match self.rounding_mode(inst) {
+            Some(rm) => rm,
+            None => return,
+        };
+        let rs1_val = self.fp_read_h(inst.get_rs1());
+        let rs2_val = self.fp_read_h(inst.get_rs2_stype());
+
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let result = rs1_val.sub(rs2_val, rm);
+        flag.get();
+        self.update_fflags(&flag);
+
+        self.fp_write_h(inst.get_rd(), RVCore::canonical_nan(result));
+    }
+
+    fn inst_hfence_gvma(&mut self, inst: &inst_type::InstType) {
+        let tvm = self.csregs.read(csregs::MSTATUS) & csregs::MSTATUS_TVM != 0;
+        if self.virt {
+            self.raise_virtual_inst(inst);
+        } else if self.mode == PrivilegeMode::U || (self.mode == PrivilegeMode::S && tvm) {
+            self.raise_illegal_inst(inst);
+        }
+    }
+
+    fn inst_hfence_vvma(&mut self, inst: &inst_type::InstType) {
+        if self.virt {
+            self.raise_virtual_inst(inst);
+        } else if self.mode == PrivilegeMode::U {
+            self.raise_illegal_inst(inst);
+        }
+    }
+
+    // hlv/hlvx/hsv access memory like VS/VU-mode would, at the privilege of hstatus.SPVP
+    fn hypervisor_access(
+        &mut self,
+        inst: &inst_type::InstType,
+        data: &mut [u8],
+        op: MemoryOperation,
+        hlvx: bool,
+    ) -> bool {
+        let hstatus = self.csregs.read(csregs::HSTATUS);
+        if self.virt {
+            self.raise_virtual_inst(inst);
+            return false;
+        }
+        if self.mode == PrivilegeMode::U && hstatus & csregs::HSTATUS_HU == 0 {
+            self.raise_illegal_inst(inst);
+            return false;
+        }
+        let translation = Translation {
+            mode: if hstatus & csregs::HSTATUS_SPVP != 0 {
+                PrivilegeMode::S
+            } else {
+                PrivilegeMode::U
+            },
+            virt: true,
+            hlvx,
+        };
+        let access = if op == MemoryOperation::WRITE {
+            AccessType::Store
+        } else {
+            AccessType::Load
+        };
+        let address = self.regs.read(inst.get_rs1());
+        match self.translate(address, access, translation) {
+            Some(paddr) => {
+                self.access_physical(paddr, data, op, false);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn hypervisor_load(
+        &mut self,
+        inst: &inst_type::InstType,
+        size: usize,
+        signed: bool,
+        hlvx: bool,
+    ) {
+        let mut data = vec![0; size];
+        if self.hypervisor_access(inst, &mut data, MemoryOperation::READ, hlvx) {
+            let mut val = RVCore::byte_array_to_addr_type(&data);
+            if signed {
+                val = RVCore::sign_extend(val, size * 8);
+            }
+            self.regs.write(inst.get_rd(), val);
+        }
+    }
+
+    fn hypervisor_store(&mut self, inst: &inst_type::InstType, size: usize) {
+        let rs2_val = self.regs.read(inst.get_rs2_rtype());
+        let mut data = rs2_val.to_le_bytes()[..size].to_vec();
+        self.hypervisor_access(inst, &mut data, MemoryOperation::WRITE, false);
+    }
+
+    fn inst_hlv_b(&mut self, inst: &inst_type::InstType) {
+        self.hypervisor_load(inst, 1, true, false);
+    }
+
+    fn inst_hlv_bu(&mut self, inst: &inst_type::InstType) {
+        self.hypervisor_load(inst, 1, false, false);
+    }
+
+    fn inst_hlv_d(&mut self, inst: &inst_type::InstType) {
+        self.hypervisor_load(inst, 8, false, false);
+    }
+
+    fn inst_hlv_h(&mut self, inst: &inst_type::InstType) {
+        self.hypervisor_load(inst, 2, true, false);
+    }
+
+    fn inst_hlv_hu(&mut self, inst: &inst_type::InstType) {
+        self.hypervisor_load(inst, 2, false, false);
+    }
+
+    fn inst_hlv_w(&mut self, inst: &inst_type::InstType) {
+        self.hypervisor_load(inst, 4, true, false);
+    }
+
+    fn inst_hlv_wu(&mut self, inst: &inst_type::InstType) {
+        self.hypervisor_load(inst, 4, false, false);
+    }
+
+    fn inst_hlvx_hu(&mut self, inst: &inst_type::InstType) {
+        self.hypervisor_load(inst, 2, false, true);
+    }
+
+    fn inst_hlvx_wu(&mut self, inst: &inst_type::InstType) {
+        self.hypervisor_load(inst, 4, false, true);
+    }
+
+    fn inst_hsv_b(&mut self, inst: &inst_type::InstType) {
+        self.hypervisor_store(inst, 1);
+    }
+
+    fn inst_hsv_d(&mut self, inst: &inst_type::InstType) {
+        self.hypervisor_store(inst, 8);
+    }
+
+    fn inst_hsv_h(&mut self, inst: &inst_type::InstType) {
+        self.hypervisor_store(inst, 2);
+    }
+
+    fn inst_hsv_w(&mut self, inst: &inst_type::InstType) {
+        self.hypervisor_store(inst, 4);
+    }
+
+    fn inst_fli_s(&mut self, inst: &inst_type::InstType) {
+        let val = F32::from_bits(FLI_TABLE[inst.get_rs1()]);
+        self.fregs.write_s(inst.get_rd(), val);
+    }
+
+    fn inst_fli_d(&mut self, inst: &inst_type::InstType) {
+        // The minimum positive normal is the only entry that depends on the format
+        let val = match inst.get_rs1() {
+            1 => F64::from_bits(0x0010_0000_0000_0000),
+            i => F32::from_bits(FLI_TABLE[i]).to_f64(RoundingMode::TiesToEven),
+        };
+        self.fregs.write(inst.get_rd(), RVCore::canonical_nan(val));
+    }
+
+    fn inst_fli_h(&mut self, inst: &inst_type::InstType) {
+        // 2^-16 and 2^-15 become subnormals and 2^16 overflows to infinity
+        let val = match inst.get_rs1() {
+            1 => F16::from_bits(0x0400),
+            i => F32::from_bits(FLI_TABLE[i]).to_f16(RoundingMode::TiesToEven),
+        };
+        self.fregs.write_h(inst.get_rd(), RVCore::canonical_nan(val));
+    }
+
+    fn inst_fminm_s(&mut self, inst: &inst_type::InstType) {
+        let result = self.fmin_max_nan(
+            self.fp_read_s(inst.get_rs1()),
+            self.fp_read_s(inst.get_rs2_stype()),
+            false,
+        );
+        self.fp_write_s(inst.get_rd(), result);
+    }
+
+    fn inst_fminm_d(&mut self, inst: &inst_type::InstType) {
+        let result = self.fmin_max_nan(
+            self.fp_read(inst.get_rs1()),
+            self.fp_read(inst.get_rs2_stype()),
+            false,
+        );
+        self.fp_write(inst.get_rd(), result);
+    }
+
+    fn inst_fminm_h(&mut self, inst: &inst_type::InstType) {
+        let result = self.fmin_max_nan(
+            self.fp_read_h(inst.get_rs1()),
+            self.fp_read_h(inst.get_rs2_stype()),
+            false,
+        );
+        self.fp_write_h(inst.get_rd(), result);
+    }
+
+    fn inst_fmaxm_s(&mut self, inst: &inst_type::InstType) {
+        let result = self.fmin_max_nan(
+            self.fp_read_s(inst.get_rs1()),
+            self.fp_read_s(inst.get_rs2_stype()),
+            true,
+        );
+        self.fp_write_s(inst.get_rd(), result);
+    }
+
+    fn inst_fmaxm_d(&mut self, inst: &inst_type::InstType) {
+        let result = self.fmin_max_nan(
+            self.fp_read(inst.get_rs1()),
+            self.fp_read(inst.get_rs2_stype()),
+            true,
+        );
+        self.fp_write(inst.get_rd(), result);
     }
 
-    fn inst_fle_d(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self
-            .fregs
-            .read(inst.get_rs1());
-        let rs2_val = self
-            .fregs
-            .read(inst.get_rs2_stype());
+    fn inst_fmaxm_h(&mut self, inst: &inst_type::InstType) {
+        let result = self.fmin_max_nan(
+            self.fp_read_h(inst.get_rs1()),
+            self.fp_read_h(inst.get_rs2_stype()),
+            true,
+        );
+        self.fp_write_h(inst.get_rd(), result);
+    }
 
-        if rs1_val.is_nan() || rs2_val.is_nan() {
-            self.csregs.write(0x1, 0x10);
-            self.regs.write(inst.get_rd(), 0);
-        } else {
-            if rs1_val.le(rs2_val) {
-                self.regs.write(inst.get_rd(), 1);
-            } else {
-                self.regs.write(inst.get_rd(), 0);
-            }
-        }
+    fn inst_fround_s(&mut self, inst: &inst_type::InstType) {
+        let rm = match self.rounding_mode(inst) {
+            Some(rm) => rm,
+            None => return,
+        };
+        let result = self.fround(self.fp_read_s(inst.get_rs1()), rm, false);
+        self.fp_write_s(inst.get_rd(), result);
     }
 
-    fn inst_fle_s(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self
-            .fregs
-            .read(inst.get_rs1())
-            .to_f32(RoundingMode::TiesToEven);
-        let rs2_val = self
-            .fregs
-            .read(inst.get_rs2_stype())
-            .to_f32(RoundingMode::TiesToEven);
+    fn inst_fround_d(&mut self, inst: &inst_type::InstType) {
+        let rm = match self.rounding_mode(inst) {
+            Some(rm) => rm,
+            None => return,
+        };
+        let result = self.fround(self.fp_read(inst.get_rs1()), rm, false);
+        self.fp_write(inst.get_rd(), result);
+    }
 
-        if rs1_val.is_nan() || rs2_val.is_nan() {
-            self.csregs.write(0x1, 0x10);
-            self.regs.write(inst.get_rd(), 0);
-        } else {
-            if rs1_val.le(rs2_val) {
-                self.regs.write(inst.get_rd(), 1);
-            } else {
-                self.regs.write(inst.get_rd(), 0);
-            }
-        }
+    fn inst_fround_h(&mut self, inst: &inst_type::InstType) {
+        let rm = match self.rounding_mode(inst) {
+            Some(rm) => rm,
+            None => return,
+        };
+        let result = self.fround(self.fp_read_h(inst.get_rs1()), rm, false);
+        self.fp_write_h(inst.get_rd(), result);
     }
 
-    fn inst_flt_d(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self
-            .fregs
-            .read(inst.get_rs1());
-        let rs2_val = self
-            .fregs
-            .read(inst.get_rs2_stype());
+    fn inst_froundnx_s(&mut self, inst: &inst_type::InstType) {
+        let rm = match self.rounding_mode(inst) {
+            Some(rm) => rm,
+            None => return,
+        };
+        let result = self.fround(self.fp_read_s(inst.get_rs1()), rm, true);
+        self.fp_write_s(inst.get_rd(), result);
+    }
 
-        if rs1_val.is_nan() || rs2_val.is_nan() {
-            self.csregs.write(0x1, 0x10);
-            self.regs.write(inst.get_rd(), 0);
-        } else {
-            if rs1_val.lt(rs2_val) {
-                self.regs.write(inst.get_rd(), 1);
-            } else {
-                self.regs.write(inst.get_rd(), 0);
-            }
-        }
+    fn inst_froundnx_d(&mut self, inst: &inst_type::InstType) {
+        let rm = match self.rounding_mode(inst) {
+            Some(rm) => rm,
+            None => return,
+        };
+        let result = self.fround(self.fp_read(inst.get_rs1()), rm, true);
+        self.fp_write(inst.get_rd(), result);
     }
 
-    fn inst_flt_s(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self
-            .fregs
-            .read(inst.get_rs1())
-            .to_f32(RoundingMode::TiesToEven);
-        let rs2_val = self
-            .fregs
-            .read(inst.get_rs2_stype())
-            .to_f32(RoundingMode::TiesToEven);
+    fn inst_froundnx_h(&mut self, inst: &inst_type::InstType) {
+        let rm = match self.rounding_mode(inst) {
+            Some(rm) => rm,
+            None => return,
+        };
+        let result = self.fround(self.fp_read_h(inst.get_rs1()), rm, true);
+        self.fp_write_h(inst.get_rd(), result);
+    }
 
-        if rs1_val.is_nan() || rs2_val.is_nan() {
-            self.csregs.write(0x1, 0x10);
-            self.regs.write(inst.get_rd(), 0);
+    fn inst_fcvtmod_w_d(&mut self, inst: &inst_type::InstType) {
+        let bits = self.fp_read(inst.get_rs1()).to_bits();
+        let negative = (bits >> 63) != 0;
+        let exp = ((bits >> 52) & 0x7ff) as i64;
+        let frac = bits & ((1 << 52) - 1);
+
+        // Truncate towards zero and keep the integer modulo 2^32
+        let (int, inexact, overflow) = if exp == 0x7ff {
+            (0, false, true)
+        } else if exp == 0 && frac == 0 {
+            (0, false, false)
         } else {
-            if rs1_val.lt(rs2_val) {
-                self.regs.write(inst.get_rd(), 1);
+            let mant = frac | if exp == 0 { 0 } else { 1 << 52 };
+            let shift = exp.max(1) - 1075;
+            if shift >= 0 {
+                let int = if shift < 64 { mant << shift } else { 0 };
+                (int, false, true)
+            } else if shift > -64 {
+                let int = mant >> -shift;
+                let limit = if negative { 1 << 31 } else { (1 << 31) - 1 };
+                (int, (mant & ((1 << -shift) - 1)) != 0, int > limit)
             } else {
-                self.regs.write(inst.get_rd(), 0);
+                (0, true, false)
             }
-        }
-    }
-
-    fn inst_flw(&mut self, inst: &inst_type::InstType) {
-        let base = self.regs.read(inst.get_rs1());
-        let offset = RVCore::sign_extend(inst.get_imm_itype(), 12);
-        let addr = base.wrapping_add(offset);
-        let mut data = [0; 4];
-        self.read_memory(addr, &mut data);
+        };
 
-        let f32_val = F32::from_bits(u32::from_le_bytes(data));
-        if f32_val.is_signaling_nan() || u32::from_le_bytes(data) == 0x7f800001 {
-            self.fregs
-                .write(inst.get_rd(), F64::from_bits(0x7ff0000000000001));
-        } else {
-            self.fregs
-                .write(inst.get_rd(), f32_val.to_f64(RoundingMode::TiesToEven));
+        if overflow {
+            self.accrue_fflags(0x10);
+        } else if inexact {
+            self.accrue_fflags(0x1);
         }
+        let int = if negative { int.wrapping_neg() } else { int };
+        self.regs
+            .write(inst.get_rd(), RVCore::sign_extend(int & 0xffff_ffff, 32));
     }
 
-    fn inst_fsd(&mut self, inst: &inst_type::InstType) {
-        let base = self.regs.read(inst.get_rs1());
-        let offset = RVCore::sign_extend(inst.get_imm_btype(), 12);
-        let addr = base.wrapping_add(offset);
-        let data = self
-            .fregs
-            .read(inst.get_rs2_stype())
-            .to_bits();
-        self.write_memory(addr, &mut data.to_le_bytes());
-    }
-
-    fn inst_fsw(&mut self, inst: &inst_type::InstType) {
-        let base = self.regs.read(inst.get_rs1());
-        let offset = RVCore::sign_extend(inst.get_imm_btype(), 12);
-        let addr = base.wrapping_add(offset);
-        let data = self
-            .fregs
-            .read(inst.get_rs2_stype())
-            .to_f32(RoundingMode::TiesToEven)
-            .to_bits();
-        self.write_memory(addr, &mut data.to_le_bytes());
-    }
-
-    fn inst_fmul_d(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self
-            .fregs
-            .read(inst.get_rs1());
-        let rs2_val = self
-            .fregs
-            .read(inst.get_rs2_stype());
+    fn inst_fleq_s(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.fp_read_s(inst.get_rs1());
+        let rs2_val = self.fp_read_s(inst.get_rs2_stype());
 
         let mut flag = ExceptionFlags::default();
         flag.set();
-        let result = rs1_val.mul(rs2_val, RoundingMode::TiesToEven);
+        let result = rs1_val.le_quiet(rs2_val);
         flag.get();
+        self.update_fflags(&flag);
 
-        if flag.is_inexact() {
-            self.csregs.write(csregs::FFLAGS, 1);
-        }
-
-        self.fregs.write(inst.get_rd(), result);
+        self.regs.write(inst.get_rd(), result as AddressType);
     }
 
-    fn inst_fmul_s(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self
-            .fregs
-            .read(inst.get_rs1())
-            .to_f32(RoundingMode::TiesToEven);
-        let rs2_val = self
-            .fregs
-            .read(inst.get_rs2_stype())
-            .to_f32(RoundingMode::TiesToEven);
+    fn inst_fleq_d(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.fp_read(inst.get_rs1());
+        let rs2_val = self.fp_read(inst.get_rs2_stype());
 
         let mut flag = ExceptionFlags::default();
         flag.set();
-        let result = rs1_val.mul(rs2_val, RoundingMode::TiesToEven);
+        let result = rs1_val.le_quiet(rs2_val);
         flag.get();
+        self.update_fflags(&flag);
 
-        if flag.is_inexact() {
-            self.csregs.write(csregs::FFLAGS, 1);
-        }
-
-        self.fregs
-            .write(inst.get_rd(), result.to_f64(RoundingMode::TiesToEven));
+        self.regs.write(inst.get_rd(), result as AddressType);
     }
 
-    fn inst_fmv_d_x(&mut self, inst: &inst_type::InstType) {
-        let rs1 = inst.get_rs1();
-        let rs1_val = self.regs.read(rs1);
-
-        let f64_val = F64::from_bits(rs1_val);
-        if f64_val.is_signaling_nan() {
-            self.fregs
-                .write(inst.get_rd(), F64::from_bits(0x7ff0000000000001));
-        } else {
-            self.fregs
-                .write(inst.get_rd(), f64_val);
-        }
-    }
+    fn inst_fleq_h(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.fp_read_h(inst.get_rs1());
+        let rs2_val = self.fp_read_h(inst.get_rs2_stype());
 
-    fn inst_fmv_w_x(&mut self, inst: &inst_type::InstType) {
-        let rs1 = inst.get_rs1();
-        let rs1_lower_val = self.regs.read(rs1) as u32;
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let result = rs1_val.le_quiet(rs2_val);
+        flag.get();
+        self.update_fflags(&flag);
 
-        let f32_val = F32::from_bits(rs1_lower_val);
-        if f32_val.is_signaling_nan() {
-            self.fregs
-                .write(inst.get_rd(), F64::from_bits(0x7ff0000000000001));
-        } else {
-            self.fregs
-                .write(inst.get_rd(), f32_val.to_f64(RoundingMode::TiesToEven));
-        }
+        self.regs.write(inst.get_rd(), result as AddressType);
     }
 
-    fn inst_fmv_x_d(&mut self, inst: &inst_type::InstType) {
-        let rs1 = inst.get_rs1();
-        let rs1_val = self.fregs.read(rs1);
-        self.regs.write(inst.get_rd(), rs1_val.to_bits());
-    }
+    fn inst_fltq_s(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.fp_read_s(inst.get_rs1());
+        let rs2_val = self.fp_read_s(inst.get_rs2_stype());
 
-    fn inst_fmv_x_w(&mut self, inst: &inst_type::InstType) {
-        let rs1 = inst.get_rs1();
-        let rs1_val = self.fregs.read(rs1);
-        self.regs.write(
-            inst.get_rd(),
-            RVCore::sign_extend(
-                (rs1_val.to_f32(RoundingMode::TiesToEven).to_bits() & 0xffffffff) as AddressType,
-                32,
-            ),
-        );
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let result = rs1_val.lt_quiet(rs2_val);
+        flag.get();
+        self.update_fflags(&flag);
+
+        self.regs.write(inst.get_rd(), result as AddressType);
     }
 
-    fn inst_fsub_d(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self
-            .fregs
-            .read(inst.get_rs1());
-        let rs2_val = self
-            .fregs
-            .read(inst.get_rs2_stype());
+    fn inst_fltq_d(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.fp_read(inst.get_rs1());
+        let rs2_val = self.fp_read(inst.get_rs2_stype());
 
         let mut flag = ExceptionFlags::default();
         flag.set();
-        let result = rs1_val.sub(rs2_val, RoundingMode::TiesToEven);
+        let result = rs1_val.lt_quiet(rs2_val);
         flag.get();
         self.update_fflags(&flag);
 
-        if rs1_val.is_positive_infinity() && rs2_val.is_positive_infinity() {
-            self.fregs.write(inst.get_rd(), F64::quiet_nan());
-        } else {
-            self.fregs.write(inst.get_rd(), result);
-        }
+        self.regs.write(inst.get_rd(), result as AddressType);
     }
 
-    fn inst_fsub_s(&mut self, inst: &inst_type::InstType) {
-        let rs1_val = self
-            .fregs
-            .read(inst.get_rs1())
-            .to_f32(RoundingMode::TiesToEven);
-        let rs2_val = self
-            .fregs
-            .read(inst.get_rs2_stype())
-            .to_f32(RoundingMode::TiesToEven);
+    fn inst_fltq_h(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.fp_read_h(inst.get_rs1());
+        let rs2_val = self.fp_read_h(inst.get_rs2_stype());
 
         let mut flag = ExceptionFlags::default();
         flag.set();
-        let result = rs1_val.sub(rs2_val, RoundingMode::TiesToEven);
+        let result = rs1_val.lt_quiet(rs2_val);
         flag.get();
         self.update_fflags(&flag);
 
-        if rs1_val.is_positive_infinity() && rs2_val.is_positive_infinity() {
-            self.fregs.write(inst.get_rd(), F64::quiet_nan());
-        } else {
-            self.fregs
-                .write(inst.get_rd(), result.to_f64(RoundingMode::TiesToEven));
-        }
+        self.regs.write(inst.get_rd(), result as AddressType);
     }
 
     fn inst_jal(&mut self, inst: &inst_type::InstType) {
@@ -2178,7 +4807,7 @@ impl RVCore {
         self.pc = ((rs1_val.wrapping_add(offset)) & (AddressType::max_value() - 1)) - inst.len;
         //Hack for illegal address exception
         if (self.pc >> 63) == 1 {
-            self.pc = self.csregs.read(csregs::MTVEC) - inst.len;
+            self.pc = self.trap_vector(csregs::MTVEC, csregs::EXC_FETCH_ACCESS) - inst.len;
             self.csregs.write(csregs::MCAUSE, csregs::EXC_FETCH_ACCESS);
             self.csregs.write(csregs::MEPC, old_pc + 4);
         }
@@ -2190,7 +4819,7 @@ impl RVCore {
         let mut data = [0; 1];
         //Hack for illegal address exception
         if (address >> 63) == 1 {
-            self.pc = self.csregs.read(csregs::MTVEC) - inst.len;
+            self.pc = self.trap_vector(csregs::MTVEC, csregs::EXC_LOAD_ACCESS) - inst.len;
             self.csregs.write(csregs::MCAUSE, csregs::EXC_LOAD_ACCESS);
             self.csregs.write(csregs::MEPC, self.pc + 4);
         } else {
@@ -2285,6 +4914,32 @@ impl RVCore {
             .write(inst.get_rd(), RVCore::byte_array_to_addr_type(&data));
     }
 
+    fn inst_max(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1()) as i64;
+        let rs2_val = self.regs.read(inst.get_rs2_rtype()) as i64;
+        self.regs
+            .write(inst.get_rd(), cmp::max(rs1_val, rs2_val) as AddressType);
+    }
+
+    fn inst_maxu(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1());
+        let rs2_val = self.regs.read(inst.get_rs2_rtype());
+        self.regs.write(inst.get_rd(), cmp::max(rs1_val, rs2_val));
+    }
+
+    fn inst_min(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1()) as i64;
+        let rs2_val = self.regs.read(inst.get_rs2_rtype()) as i64;
+        self.regs
+            .write(inst.get_rd(), cmp::min(rs1_val, rs2_val) as AddressType);
+    }
+
+    fn inst_minu(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1());
+        let rs2_val = self.regs.read(inst.get_rs2_rtype());
+        self.regs.write(inst.get_rd(), cmp::min(rs1_val, rs2_val));
+    }
+
     fn inst_mul(&mut self, inst: &inst_type::InstType) {
         let rs1_val = self.regs.read(inst.get_rs1()) as i64;
         let rs2_val = self.regs.read(inst.get_rs2_rtype()) as i64;
@@ -2329,18 +4984,65 @@ impl RVCore {
     }
 
     fn inst_mret(&mut self, inst: &inst_type::InstType) {
-        // TODO: set mstatus.MIE, PPIE, MPP here
-        // should transit mode according to mstatus.MPP
-        let status_mpp = (self.csregs.read(csregs::MSTATUS) >> 11) & 0x3;
+        if self.mode != PrivilegeMode::M {
+            self.raise_illegal_inst(inst);
+            return;
+        }
+        let mstatus = self.csregs.read(csregs::MSTATUS);
+        let status_mpp = (mstatus >> 11) & 0x3;
         match status_mpp {
             0 => self.mode = PrivilegeMode::U,
             1 => self.mode = PrivilegeMode::S,
             3 => self.mode = PrivilegeMode::M,
             _ => panic!("Unsupported privileged level"),
         }
+        self.virt = self.mode != PrivilegeMode::M && mstatus & csregs::MSTATUS_MPV != 0;
+        let mut new_mstatus = (mstatus
+            & !(csregs::MSTATUS_MPP
+                | csregs::MSTATUS_MPV
+                | csregs::MSTATUS_MIE
+                | csregs::MSTATUS_MDT))
+            | csregs::MSTATUS_MPIE;
+        if mstatus & csregs::MSTATUS_MPIE != 0 {
+            new_mstatus |= csregs::MSTATUS_MIE;
+        }
+        if self.mode != PrivilegeMode::M {
+            new_mstatus &= !csregs::MSTATUS_MPRV;
+        }
+        if self.mode == PrivilegeMode::U || self.virt {
+            new_mstatus &= !csregs::MSTATUS_SDT;
+        }
+        self.clear_vsstatus_sdt();
+        if self.csregs.clic_mode() {
+            let mpil = self.csregs.read(csregs::MCAUSE) & csregs::MCAUSE_MPIL;
+            self.csregs
+                .set_interrupt_level(mpil >> csregs::MCAUSE_MPIL_SHIFT);
+        }
+        self.csregs.write(csregs::MSTATUS, new_mstatus);
         self.pc = self.csregs.read(csregs::MEPC) - inst.len;
     }
 
+    fn inst_mnret(&mut self, inst: &inst_type::InstType) {
+        if self.mode != PrivilegeMode::M {
+            self.raise_illegal_inst(inst);
+            return;
+        }
+        let mnstatus = self.csregs.read(csregs::MNSTATUS);
+        self.mode = match (mnstatus & csregs::MNSTATUS_MNPP) >> 11 {
+            0 => PrivilegeMode::U,
+            1 => PrivilegeMode::S,
+            _ => PrivilegeMode::M,
+        };
+        self.virt = self.mode != PrivilegeMode::M && mnstatus & csregs::MNSTATUS_MNPV != 0;
+        if self.mode != PrivilegeMode::M {
+            let mstatus = self.csregs.read(csregs::MSTATUS);
+            let mask = csregs::MSTATUS_MPRV | csregs::MSTATUS_MDT;
+            self.csregs.write(csregs::MSTATUS, mstatus & !mask);
+        }
+        self.csregs.set_nmie(true);
+        self.pc = self.csregs.read(csregs::MNEPC) - inst.len;
+    }
+
     fn inst_or(&mut self, inst: &inst_type::InstType) {
         let rs1_val = self.regs.read(inst.get_rs1());
         let rs2_val = self.regs.read(inst.get_rs2_rtype());
@@ -2353,6 +5055,23 @@ impl RVCore {
             .write(inst.get_rd(), self.regs.read(inst.get_rs1()) | imm);
     }
 
+    fn inst_orc_b(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1());
+        let mut result = 0;
+        for i in 0..8 {
+            if (rs1_val >> (i * 8)) & 0xff != 0 {
+                result |= 0xff << (i * 8);
+            }
+        }
+        self.regs.write(inst.get_rd(), result);
+    }
+
+    fn inst_orn(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1());
+        let rs2_val = self.regs.read(inst.get_rs2_rtype());
+        self.regs.write(inst.get_rd(), rs1_val | !rs2_val);
+    }
+
     fn inst_rem(&mut self, inst: &inst_type::InstType) {
         let rs1_val = self.regs.read(inst.get_rs1()) as i64;
         let rs2_val = self.regs.read(inst.get_rs2_rtype()) as i64;
@@ -2401,6 +5120,56 @@ impl RVCore {
         }
     }
 
+    fn inst_rev8(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1());
+        self.regs.write(inst.get_rd(), rs1_val.swap_bytes());
+    }
+
+    fn inst_rol(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1());
+        let shamt = self.regs.read(inst.get_rs2_rtype()) & 0x3f;
+        self.regs
+            .write(inst.get_rd(), rs1_val.rotate_left(shamt as u32));
+    }
+
+    fn inst_rolw(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1()) as u32;
+        let shamt = self.regs.read(inst.get_rs2_rtype()) & 0x1f;
+        let result = rs1_val.rotate_left(shamt as u32) as AddressType;
+        self.regs
+            .write(inst.get_rd(), RVCore::sign_extend(result, 32));
+    }
+
+    fn inst_ror(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1());
+        let shamt = self.regs.read(inst.get_rs2_rtype()) & 0x3f;
+        self.regs
+            .write(inst.get_rd(), rs1_val.rotate_right(shamt as u32));
+    }
+
+    fn inst_rori(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1());
+        let shamt = inst.get_shamt6_itype();
+        self.regs
+            .write(inst.get_rd(), rs1_val.rotate_right(shamt as u32));
+    }
+
+    fn inst_roriw(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1()) as u32;
+        let shamt = inst.get_shamt_itype();
+        let result = rs1_val.rotate_right(shamt as u32) as AddressType;
+        self.regs
+            .write(inst.get_rd(), RVCore::sign_extend(result, 32));
+    }
+
+    fn inst_rorw(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1()) as u32;
+        let shamt = self.regs.read(inst.get_rs2_rtype()) & 0x1f;
+        let result = rs1_val.rotate_right(shamt as u32) as AddressType;
+        self.regs
+            .write(inst.get_rd(), RVCore::sign_extend(result, 32));
+    }
+
     fn inst_sb(&mut self, inst: &inst_type::InstType) {
         let imm = RVCore::sign_extend(inst.get_imm_stype(), 12);
         let address = self.regs.read(inst.get_rs1()).wrapping_add(imm);
@@ -2415,18 +5184,85 @@ impl RVCore {
         self.write_memory_amo(address, &mut data.to_le_bytes());
     }
 
-    fn inst_sc_w(&mut self, inst: &inst_type::InstType) {
-        let imm = RVCore::sign_extend(inst.get_imm_stype(), 12);
-        let address = self.regs.read(inst.get_rs1()).wrapping_add(imm);
-        let data = self.regs.read(inst.get_rs2_stype()) as u32;
-        self.write_memory_amo(address, &mut data.to_le_bytes());
+    fn inst_sc_w(&mut self, inst: &inst_type::InstType) {
+        let imm = RVCore::sign_extend(inst.get_imm_stype(), 12);
+        let address = self.regs.read(inst.get_rs1()).wrapping_add(imm);
+        let data = self.regs.read(inst.get_rs2_stype()) as u32;
+        self.write_memory_amo(address, &mut data.to_le_bytes());
+    }
+
+    fn inst_sd(&mut self, inst: &inst_type::InstType) {
+        let imm = RVCore::sign_extend(inst.get_imm_stype(), 12);
+        let address = self.regs.read(inst.get_rs1()).wrapping_add(imm);
+        let data = self.regs.read(inst.get_rs2_stype()) as u64;
+        self.write_memory(address, &mut data.to_le_bytes());
+    }
+
+    fn inst_sext_b(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1());
+        self.regs
+            .write(inst.get_rd(), RVCore::sign_extend(rs1_val, 8));
+    }
+
+    fn inst_sext_h(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1());
+        self.regs
+            .write(inst.get_rd(), RVCore::sign_extend(rs1_val, 16));
+    }
+
+    // Translations are not cached, so the fence only checks that it is allowed
+    fn inst_sfence_vma(&mut self, inst: &inst_type::InstType) {
+        let tvm = self.csregs.read(csregs::MSTATUS) & csregs::MSTATUS_TVM != 0;
+        let vtvm = self.csregs.read(csregs::HSTATUS) & csregs::HSTATUS_VTVM != 0;
+        if self.virt && (self.mode == PrivilegeMode::U || vtvm) {
+            self.raise_virtual_inst(inst);
+        } else if self.mode == PrivilegeMode::U
+            || (self.mode == PrivilegeMode::S && !self.virt && tvm)
+        {
+            self.raise_illegal_inst(inst);
+        }
+    }
+
+    fn inst_sh1add(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1());
+        let rs2_val = self.regs.read(inst.get_rs2_rtype());
+        self.regs
+            .write(inst.get_rd(), rs2_val.wrapping_add(rs1_val << 1));
+    }
+
+    fn inst_sh1add_uw(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1()) as u32 as AddressType;
+        let rs2_val = self.regs.read(inst.get_rs2_rtype());
+        self.regs
+            .write(inst.get_rd(), rs2_val.wrapping_add(rs1_val << 1));
+    }
+
+    fn inst_sh2add(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1());
+        let rs2_val = self.regs.read(inst.get_rs2_rtype());
+        self.regs
+            .write(inst.get_rd(), rs2_val.wrapping_add(rs1_val << 2));
+    }
+
+    fn inst_sh2add_uw(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1()) as u32 as AddressType;
+        let rs2_val = self.regs.read(inst.get_rs2_rtype());
+        self.regs
+            .write(inst.get_rd(), rs2_val.wrapping_add(rs1_val << 2));
+    }
+
+    fn inst_sh3add(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1());
+        let rs2_val = self.regs.read(inst.get_rs2_rtype());
+        self.regs
+            .write(inst.get_rd(), rs2_val.wrapping_add(rs1_val << 3));
     }
 
-    fn inst_sd(&mut self, inst: &inst_type::InstType) {
-        let imm = RVCore::sign_extend(inst.get_imm_stype(), 12);
-        let address = self.regs.read(inst.get_rs1()).wrapping_add(imm);
-        let data = self.regs.read(inst.get_rs2_stype()) as u64;
-        self.write_memory(address, &mut data.to_le_bytes());
+    fn inst_sh3add_uw(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1()) as u32 as AddressType;
+        let rs2_val = self.regs.read(inst.get_rs2_rtype());
+        self.regs
+            .write(inst.get_rd(), rs2_val.wrapping_add(rs1_val << 3));
     }
 
     fn inst_sh(&mut self, inst: &inst_type::InstType) {
@@ -2462,6 +5298,12 @@ impl RVCore {
             .write(inst.get_rd(), RVCore::sign_extend(rs1_val << shamt, 32));
     }
 
+    fn inst_slli_uw(&mut self, inst: &inst_type::InstType) {
+        let shamt = inst.get_shamt6_itype();
+        let rs1_val = self.regs.read(inst.get_rs1()) as u32 as AddressType;
+        self.regs.write(inst.get_rd(), rs1_val << shamt);
+    }
+
     fn inst_sllw(&mut self, inst: &inst_type::InstType) {
         let rs1_val = self.regs.read(inst.get_rs1()) as u32;
         let rs2_val = self.regs.read(inst.get_rs2_rtype()) & 0x1f;
@@ -2550,6 +5392,49 @@ impl RVCore {
             .write(inst.get_rd(), RVCore::sign_extend(result, 32));
     }
 
+    fn inst_sret(&mut self, inst: &inst_type::InstType) {
+        let mstatus = self.csregs.read(csregs::MSTATUS);
+        let hstatus = self.csregs.read(csregs::HSTATUS);
+        if self.virt {
+            if self.mode == PrivilegeMode::U || hstatus & csregs::HSTATUS_VTSR != 0 {
+                self.raise_virtual_inst(inst);
+                return;
+            }
+            let vsstatus = self.csregs.read(csregs::VSSTATUS);
+            self.mode = if vsstatus & csregs::MSTATUS_SPP != 0 {
+                PrivilegeMode::S
+            } else {
+                PrivilegeMode::U
+            };
+            self.csregs
+                .write(csregs::VSSTATUS, RVCore::sret_status(vsstatus));
+            self.pc = self.csregs.read(csregs::VSEPC) - inst.len;
+            return;
+        }
+
+        if self.mode == PrivilegeMode::U
+            || (self.mode == PrivilegeMode::S && mstatus & csregs::MSTATUS_TSR != 0)
+        {
+            self.raise_illegal_inst(inst);
+            return;
+        }
+        let mut new_mstatus = RVCore::sret_status(mstatus) & !csregs::MSTATUS_MPRV;
+        if self.mode == PrivilegeMode::M {
+            new_mstatus &= !csregs::MSTATUS_MDT;
+        }
+        self.mode = if mstatus & csregs::MSTATUS_SPP != 0 {
+            PrivilegeMode::S
+        } else {
+            PrivilegeMode::U
+        };
+        self.virt = hstatus & csregs::HSTATUS_SPV != 0;
+        self.csregs
+            .write(csregs::HSTATUS, hstatus & !csregs::HSTATUS_SPV);
+        self.clear_vsstatus_sdt();
+        self.csregs.write(csregs::MSTATUS, new_mstatus);
+        self.pc = self.csregs.read(csregs::SEPC) - inst.len;
+    }
+
     fn inst_srl(&mut self, inst: &inst_type::InstType) {
         let rs1_val = self.regs.read(inst.get_rs1());
         let rs2_val = self.regs.read(inst.get_rs2_rtype()) & 0x3f;
@@ -2590,7 +5475,48 @@ impl RVCore {
     }
 
     fn inst_nop(&mut self, _inst: &inst_type::InstType) {}
-    fn inst_wfi(&mut self, _inst: &inst_type::InstType) {}
+
+    // Zihintntl/Zihintpause only hint the memory system, Zimop/Zcmop
+    // operations are unassigned and write zero
+    fn inst_ntl(&mut self, _inst: &inst_type::InstType) {}
+    fn inst_c_ntl(&mut self, _inst: &inst_type::InstType) {}
+    fn inst_pause(&mut self, _inst: &inst_type::InstType) {}
+    fn inst_prefetch(&mut self, _inst: &inst_type::InstType) {}
+    fn inst_c_mop(&mut self, _inst: &inst_type::InstType) {}
+
+    fn inst_mop_r(&mut self, inst: &inst_type::InstType) {
+        self.regs.write(inst.get_rd(), 0);
+    }
+
+    fn inst_mop_rr(&mut self, inst: &inst_type::InstType) {
+        self.regs.write(inst.get_rd(), 0);
+    }
+
+    fn inst_czero_eqz(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1());
+        let rs2_val = self.regs.read(inst.get_rs2_rtype());
+        self.regs
+            .write(inst.get_rd(), if rs2_val == 0 { 0 } else { rs1_val });
+    }
+
+    fn inst_czero_nez(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1());
+        let rs2_val = self.regs.read(inst.get_rs2_rtype());
+        self.regs
+            .write(inst.get_rd(), if rs2_val != 0 { 0 } else { rs1_val });
+    }
+    fn inst_wfi(&mut self, inst: &inst_type::InstType) {
+        let mstatus = self.csregs.read(csregs::MSTATUS);
+        let hstatus = self.csregs.read(csregs::HSTATUS);
+        if self.mode != PrivilegeMode::M && mstatus & csregs::MSTATUS_TW != 0 {
+            self.raise_illegal_inst(inst);
+        } else if self.virt && (self.mode == PrivilegeMode::U || hstatus & csregs::HSTATUS_VTW != 0)
+        {
+            self.raise_virtual_inst(inst);
+        } else if self.mode == PrivilegeMode::U {
+            self.raise_illegal_inst(inst);
+        }
+    }
 
     fn inst_xor(&mut self, inst: &inst_type::InstType) {
         let rs1_val = self.regs.read(inst.get_rs1());
@@ -2603,6 +5529,17 @@ impl RVCore {
         self.regs
             .write(inst.get_rd(), self.regs.read(inst.get_rs1()) ^ imm);
     }
+
+    fn inst_xnor(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1());
+        let rs2_val = self.regs.read(inst.get_rs2_rtype());
+        self.regs.write(inst.get_rd(), !(rs1_val ^ rs2_val));
+    }
+
+    fn inst_zext_h(&mut self, inst: &inst_type::InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1());
+        self.regs.write(inst.get_rd(), rs1_val & 0xffff);
+    }
 }
 
 #[cfg(test)]
@@ -2925,4 +5862,2411 @@ mod tests {
         fixture.core.inst_srai(&inst_srai_code(1, 2, 0x10));
         assert_eq!(AddressType::MAX, fixture.core.regs.read(1));
     }
+
+    #[test]
+    fn test_inst_fp_rounding_mode() {
+        let mut core: RVCore = RVCore::new();
+
+        // 1.0 + 2^-24 is a tie between 1.0 and the next single
+        core.fregs.write_s(1, F32::from_bits(0x3f80_0000));
+        core.fregs.write_s(2, F32::from_bits(0x3380_0000));
+        core.inst_fadd_s(&inst_fadd_s_code(3, 1, 2, 0));
+        assert_eq!(0x3f80_0000, core.fregs.read_s(3).to_bits());
+        core.inst_fadd_s(&inst_fadd_s_code(3, 1, 2, 3));
+        assert_eq!(0x3f80_0001, core.fregs.read_s(3).to_bits());
+
+        core.fregs.write_s(4, F32::from_bits(0x4020_0000));
+        core.fregs.write_s(5, F32::from_bits(0xc020_0000));
+        core.inst_fcvt_w_s(&inst_fcvt_w_s_code(6, 4, 0));
+        assert_eq!(2, core.regs.read(6));
+        core.inst_fcvt_w_s(&inst_fcvt_w_s_code(6, 4, 4));
+        assert_eq!(3, core.regs.read(6));
+        core.inst_fcvt_w_s(&inst_fcvt_w_s_code(6, 5, 2));
+        assert_eq!(-3i64 as AddressType, core.regs.read(6));
+
+        // DYN takes the rounding mode from frm
+        core.csregs.write(csregs::FRM, 3);
+        core.inst_fcvt_w_s(&inst_fcvt_w_s_code(6, 5, 7));
+        assert_eq!(-2i64 as AddressType, core.regs.read(6));
+
+        // Reserved encodings are illegal, also through frm
+        core.inst_fcvt_w_s(&inst_fcvt_w_s_code(6, 4, 5));
+        assert_eq!(csregs::EXC_ILLEGAL_INST, core.csregs.read(csregs::MCAUSE));
+        core.csregs.write(csregs::MCAUSE, 0);
+        core.csregs.write(csregs::FRM, 6);
+        core.inst_fadd_s(&inst_fadd_s_code(3, 1, 2, 7));
+        assert_eq!(csregs::EXC_ILLEGAL_INST, core.csregs.read(csregs::MCAUSE));
+    }
+
+    #[test]
+    fn test_inst_nan_boxing() {
+        let mut core: RVCore = RVCore::new();
+
+        // fmv.w.x/fmv.x.w move a signaling NaN bit-exactly
+        core.regs.write(1, 0x7f80_0001);
+        core.inst_fmv_w_x(&inst_fmv_w_x_code(1, 1));
+        assert_eq!(0xffff_ffff_7f80_0001, core.fregs.read_bits(1));
+        core.inst_fmv_x_w(&inst_fmv_x_w_code(2, 1));
+        assert_eq!(0x7f80_0001, core.regs.read(2));
+
+        // Arithmetic on it produces the canonical NaN and raises NV
+        core.inst_fadd_s(&inst_fadd_s_code(3, 1, 1, 0));
+        assert_eq!(0xffff_ffff_7fc0_0000, core.fregs.read_bits(3));
+        assert_eq!(0x10, core.csregs.read(csregs::FFLAGS));
+
+        // A double is not a boxed single, it reads as the canonical NaN
+        core.fregs.write_bits(4, 0x3ff0_0000_0000_0000);
+        core.inst_fadd_s(&inst_fadd_s_code(3, 4, 4, 0));
+        assert_eq!(0xffff_ffff_7fc0_0000, core.fregs.read_bits(3));
+        core.inst_fcvt_d_s(&inst_fcvt_d_s_code(5, 4));
+        assert_eq!(0x7ff8_0000_0000_0000, core.fregs.read_bits(5));
+
+        // fmv.x.w still returns the raw low bits, sign-extended
+        core.inst_fmv_x_w(&inst_fmv_x_w_code(2, 4));
+        assert_eq!(0, core.regs.read(2));
+        core.fregs.write_bits(4, 0x8000_0000);
+        core.inst_fmv_x_w(&inst_fmv_x_w_code(2, 4));
+        assert_eq!(0xffff_ffff_8000_0000, core.regs.read(2));
+    }
+
+    type FpHandler = fn(&mut RVCore, &inst_type::InstType);
+    type FlagsReference = fn(u64, u64, u64) -> AddressType;
+
+    const RNE: RoundingMode = RoundingMode::TiesToEven;
+
+    const EDGE_INT: [u64; 9] = [
+        0,
+        1,
+        0xffff_ffff_ffff_ffff,
+        0x7fff_ffff,
+        0x8000_0000,
+        0x0100_0001,
+        0xffe1,
+        0x7fff_ffff_ffff_ffff,
+        0x8000_0000_0000_0000,
+    ];
+
+    fn softfloat_flags(op: impl FnOnce()) -> AddressType {
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        op();
+        flag.get();
+        flag.to_bits() as AddressType
+    }
+
+    // Runs each handler on every combination of edge operands, fed through
+    // both f1..f3 and x1, and compares the fflags it raised with softfloat
+    fn check_fflags(
+        cases: &[(&str, FpHandler, inst_type::InstType, usize, FlagsReference)],
+        edges: &[u64],
+    ) {
+        let mut core: RVCore = RVCore::new();
+        for (name, handler, inst, arity, reference) in cases {
+            for &a in edges {
+                for &b in if *arity > 1 { edges } else { &edges[..1] } {
+                    for &c in if *arity > 2 { edges } else { &edges[..1] } {
+                        core.fregs.write_bits(1, a);
+                        core.fregs.write_bits(2, b);
+                        core.fregs.write_bits(3, c);
+                        core.regs.write(1, a);
+                        core.csregs.write(csregs::FFLAGS, 0);
+                        handler(&mut core, inst);
+                        assert_eq!(
+                            reference(a, b, c),
+                            core.csregs.read(csregs::FFLAGS),
+                            "{} {:#x} {:#x} {:#x}",
+                            name,
+                            a,
+                            b,
+                            c
+                        );
+                    }
+                }
+            }
+        }
+
+        // Flags are sticky, a later exception-free operation keeps them
+        core.csregs.write(csregs::FFLAGS, 0x1f);
+        core.fregs.write_bits(1, 0);
+        core.regs.write(1, 0);
+        let (_, handler, inst, _, _) = &cases[0];
+        handler(&mut core, inst);
+        assert_eq!(0x1f, core.csregs.read(csregs::FFLAGS));
+    }
+
+    const EDGE_S: [u64; 15] = [
+        0x0000_0000,
+        0x8000_0000,
+        0x3f80_0000,
+        0x3eaa_aaab,
+        0xbf00_0000,
+        0xc040_0000,
+        0x7f7f_ffff,
+        0x0000_0001,
+        0x0080_0000,
+        0x7f80_0000,
+        0xff80_0000,
+        0x7fc0_0000,
+        0x7f80_0001,
+        0x4f00_0000,
+        0x5f80_0000,
+    ];
+
+    #[test]
+    fn test_inst_fflags_s() {
+        let edges: Vec<u64> = EDGE_S.iter().map(|x| 0xffff_ffff_0000_0000 | x).collect();
+        check_fflags(
+            &[
+                (
+                    "fadd.s",
+                    RVCore::inst_fadd_s,
+                    inst_fp_code(InstID::FADD_S, 0x00, 4, 1, 2, 0),
+                    2,
+                    |a, b, _| {
+                        softfloat_flags(|| {
+                            F32::from_bits(a as u32).add(F32::from_bits(b as u32), RNE);
+                        })
+                    },
+                ),
+                (
+                    "fsub.s",
+                    RVCore::inst_fsub_s,
+                    inst_fp_code(InstID::FSUB_S, 0x04, 4, 1, 2, 0),
+                    2,
+                    |a, b, _| {
+                        softfloat_flags(|| {
+                            F32::from_bits(a as u32).sub(F32::from_bits(b as u32), RNE);
+                        })
+                    },
+                ),
+                (
+                    "fmul.s",
+                    RVCore::inst_fmul_s,
+                    inst_fp_code(InstID::FMUL_S, 0x08, 4, 1, 2, 0),
+                    2,
+                    |a, b, _| {
+                        softfloat_flags(|| {
+                            F32::from_bits(a as u32).mul(F32::from_bits(b as u32), RNE);
+                        })
+                    },
+                ),
+                (
+                    "fdiv.s",
+                    RVCore::inst_fdiv_s,
+                    inst_fp_code(InstID::FDIV_S, 0x0c, 4, 1, 2, 0),
+                    2,
+                    |a, b, _| {
+                        softfloat_flags(|| {
+                            F32::from_bits(a as u32).div(F32::from_bits(b as u32), RNE);
+                        })
+                    },
+                ),
+                (
+                    "fsqrt.s",
+                    RVCore::inst_fsqrt_s,
+                    inst_fp_code(InstID::FSQRT_S, 0x2c, 4, 1, 0, 0),
+                    1,
+                    |a, _, _| {
+                        softfloat_flags(|| {
+                            F32::from_bits(a as u32).sqrt(RNE);
+                        })
+                    },
+                ),
+                (
+                    "fmadd.s",
+                    RVCore::inst_fmadd_s,
+                    inst_fp_r4_code(InstID::FMADD_S, 0x43, 0, 4, 1, 2, 3),
+                    3,
+                    |a, b, c| {
+                        softfloat_flags(|| {
+                            F32::from_bits(a as u32).fused_mul_add(
+                                F32::from_bits(b as u32),
+                                F32::from_bits(c as u32),
+                                RNE,
+                            );
+                        })
+                    },
+                ),
+                (
+                    "fmsub.s",
+                    RVCore::inst_fmsub_s,
+                    inst_fp_r4_code(InstID::FMSUB_S, 0x47, 0, 4, 1, 2, 3),
+                    3,
+                    |a, b, c| {
+                        softfloat_flags(|| {
+                            F32::from_bits(a as u32).fused_mul_add(
+                                F32::from_bits(b as u32),
+                                F32::from_bits(c as u32).neg(),
+                                RNE,
+                            );
+                        })
+                    },
+                ),
+                (
+                    "fnmsub.s",
+                    RVCore::inst_fnmsub_s,
+                    inst_fp_r4_code(InstID::FNMSUB_S, 0x4b, 0, 4, 1, 2, 3),
+                    3,
+                    |a, b, c| {
+                        softfloat_flags(|| {
+                            F32::from_bits(a as u32).neg().fused_mul_add(
+                                F32::from_bits(b as u32),
+                                F32::from_bits(c as u32),
+                                RNE,
+                            );
+                        })
+                    },
+                ),
+                (
+                    "fnmadd.s",
+                    RVCore::inst_fnmadd_s,
+                    inst_fp_r4_code(InstID::FNMADD_S, 0x4f, 0, 4, 1, 2, 3),
+                    3,
+                    |a, b, c| {
+                        softfloat_flags(|| {
+                            F32::from_bits(a as u32).neg().fused_mul_add(
+                                F32::from_bits(b as u32),
+                                F32::from_bits(c as u32).neg(),
+                                RNE,
+                            );
+                        })
+                    },
+                ),
+                (
+                    "fmin.s",
+                    RVCore::inst_fmin_s,
+                    inst_fp_code(InstID::FMIN_S, 0x14, 4, 1, 2, 0),
+                    2,
+                    |a, b, _| {
+                        if F32::from_bits(a as u32).is_signaling_nan()
+                            || F32::from_bits(b as u32).is_signaling_nan()
+                        {
+                            0x10
+                        } else {
+                            0
+                        }
+                    },
+                ),
+                (
+                    "fmax.s",
+                    RVCore::inst_fmax_s,
+                    inst_fp_code(InstID::FMAX_S, 0x14, 4, 1, 2, 1),
+                    2,
+                    |a, b, _| {
+                        if F32::from_bits(a as u32).is_signaling_nan()
+                            || F32::from_bits(b as u32).is_signaling_nan()
+                        {
+                            0x10
+                        } else {
+                            0
+                        }
+                    },
+                ),
+                (
+                    "fle.s",
+                    RVCore::inst_fle_s,
+                    inst_fp_code(InstID::FLE_S, 0x50, 4, 1, 2, 0),
+                    2,
+                    |a, b, _| {
+                        softfloat_flags(|| {
+                            F32::from_bits(a as u32).le(F32::from_bits(b as u32));
+                        })
+                    },
+                ),
+                (
+                    "flt.s",
+                    RVCore::inst_flt_s,
+                    inst_fp_code(InstID::FLT_S, 0x50, 4, 1, 2, 1),
+                    2,
+                    |a, b, _| {
+                        softfloat_flags(|| {
+                            F32::from_bits(a as u32).lt(F32::from_bits(b as u32));
+                        })
+                    },
+                ),
+                (
+                    "feq.s",
+                    RVCore::inst_feq_s,
+                    inst_fp_code(InstID::FEQ_S, 0x50, 4, 1, 2, 2),
+                    2,
+                    |a, b, _| {
+                        softfloat_flags(|| {
+                            F32::from_bits(a as u32).eq(F32::from_bits(b as u32));
+                        })
+                    },
+                ),
+                (
+                    "fcvt.w.s",
+                    RVCore::inst_fcvt_w_s,
+                    inst_fp_code(InstID::FCVT_W_S, 0x60, 4, 1, 0, 0),
+                    1,
+                    |a, _, _| {
+                        softfloat_flags(|| {
+                            F32::from_bits(a as u32).to_i32(RNE, true);
+                        })
+                    },
+                ),
+                (
+                    "fcvt.wu.s",
+                    RVCore::inst_fcvt_wu_s,
+                    inst_fp_code(InstID::FCVT_WU_S, 0x60, 4, 1, 1, 0),
+                    1,
+                    |a, _, _| {
+                        softfloat_flags(|| {
+                            F32::from_bits(a as u32).to_u32(RNE, true);
+                        })
+                    },
+                ),
+                (
+                    "fcvt.l.s",
+                    RVCore::inst_fcvt_l_s,
+                    inst_fp_code(InstID::FCVT_L_S, 0x60, 4, 1, 2, 0),
+                    1,
+                    |a, _, _| {
+                        softfloat_flags(|| {
+                            F32::from_bits(a as u32).to_i64(RNE, true);
+                        })
+                    },
+                ),
+                (
+                    "fcvt.lu.s",
+                    RVCore::inst_fcvt_lu_s,
+                    inst_fp_code(InstID::FCVT_LU_S, 0x60, 4, 1, 3, 0),
+                    1,
+                    |a, _, _| {
+                        softfloat_flags(|| {
+                            F32::from_bits(a as u32).to_u64(RNE, true);
+                        })
+                    },
+                ),
+                (
+                    "fcvt.d.s",
+                    RVCore::inst_fcvt_d_s,
+                    inst_fp_code(InstID::FCVT_D_S, 0x21, 4, 1, 0, 0),
+                    1,
+                    |a, _, _| {
+                        softfloat_flags(|| {
+                            F32::from_bits(a as u32).to_f64(RNE);
+                        })
+                    },
+                ),
+                (
+                    "fcvt.h.s",
+                    RVCore::inst_fcvt_h_s,
+                    inst_fp_code(InstID::FCVT_H_S, 0x22, 4, 1, 0, 0),
+                    1,
+                    |a, _, _| {
+                        softfloat_flags(|| {
+                            F32::from_bits(a as u32).to_f16(RNE);
+                        })
+                    },
+                ),
+            ],
+            &edges,
+        );
+        check_fflags(
+            &[
+                (
+                    "fcvt.s.w",
+                    RVCore::inst_fcvt_s_w,
+                    inst_fp_code(InstID::FCVT_S_W, 0x68, 4, 1, 0, 0),
+                    1,
+                    |a, _, _| {
+                        softfloat_flags(|| {
+                            F32::from_i32(a as i32, RNE);
+                        })
+                    },
+                ),
+                (
+                    "fcvt.s.wu",
+                    RVCore::inst_fcvt_s_wu,
+                    inst_fp_code(InstID::FCVT_S_WU, 0x68, 4, 1, 1, 0),
+                    1,
+                    |a, _, _| {
+                        softfloat_flags(|| {
+                            F32::from_u32(a as u32, RNE);
+                        })
+                    },
+                ),
+                (
+                    "fcvt.s.l",
+                    RVCore::inst_fcvt_s_l,
+                    inst_fp_code(InstID::FCVT_S_L, 0x68, 4, 1, 2, 0),
+                    1,
+                    |a, _, _| {
+                        softfloat_flags(|| {
+                            F32::from_i64(a as i64, RNE);
+                        })
+                    },
+                ),
+                (
+                    "fcvt.s.lu",
+                    RVCore::inst_fcvt_s_lu,
+                    inst_fp_code(InstID::FCVT_S_LU, 0x68, 4, 1, 3, 0),
+                    1,
+                    |a, _, _| {
+                        softfloat_flags(|| {
+                            F32::from_u64(a, RNE);
+                        })
+                    },
+                ),
+            ],
+            &EDGE_INT,
+        );
+    }
+
+    const EDGE_D: [u64; 16] = [
+        0x0000_0000_0000_0000,
+        0x8000_0000_0000_0000,
+        0x3ff0_0000_0000_0000,
+        0x3fd5_5555_5555_5555,
+        0xbfe0_0000_0000_0000,
+        0xc008_0000_0000_0000,
+        0x7fef_ffff_ffff_ffff,
+        0x0000_0000_0000_0001,
+        0x0010_0000_0000_0000,
+        0x7ff0_0000_0000_0000,
+        0xfff0_0000_0000_0000,
+        0x7ff8_0000_0000_0000,
+        0x7ff0_0000_0000_0001,
+        0x41df_ffff_ffe0_0000,
+        0x43f0_0000_0000_0000,
+        0x3810_0000_0000_0000,
+    ];
+
+    #[test]
+    fn test_inst_fflags_d() {
+        let edges = EDGE_D.to_vec();
+        check_fflags(
+            &[
+                (
+                    "fadd.d",
+                    RVCore::inst_fadd_d,
+                    inst_fp_code(InstID::FADD_D, 0x01, 4, 1, 2, 0),
+                    2,
+                    |a, b, _| {
+                        softfloat_flags(|| {
+                            F64::from_bits(a).add(F64::from_bits(b), RNE);
+                        })
+                    },
+                ),
+                (
+                    "fsub.d",
+                    RVCore::inst_fsub_d,
+                    inst_fp_code(InstID::FSUB_D, 0x05, 4, 1, 2, 0),
+                    2,
+                    |a, b, _| {
+                        softfloat_flags(|| {
+                            F64::from_bits(a).sub(F64::from_bits(b), RNE);
+                        })
+                    },
+                ),
+                (
+                    "fmul.d",
+                    RVCore::inst_fmul_d,
+                    inst_fp_code(InstID::FMUL_D, 0x09, 4, 1, 2, 0),
+                    2,
+                    |a, b, _| {
+                        softfloat_flags(|| {
+                            F64::from_bits(a).mul(F64::from_bits(b), RNE);
+                        })
+                    },
+                ),
+                (
+                    "fdiv.d",
+                    RVCore::inst_fdiv_d,
+                    inst_fp_code(InstID::FDIV_D, 0x0d, 4, 1, 2, 0),
+                    2,
+                    |a, b, _| {
+                        softfloat_flags(|| {
+                            F64::from_bits(a).div(F64::from_bits(b), RNE);
+                        })
+                    },
+                ),
+                (
+                    "fsqrt.d",
+                    RVCore::inst_fsqrt_d,
+                    inst_fp_code(InstID::FSQRT_D, 0x2d, 4, 1, 0, 0),
+                    1,
+                    |a, _, _| {
+                        softfloat_flags(|| {
+                            F64::from_bits(a).sqrt(RNE);
+                        })
+                    },
+                ),
+                (
+                    "fmadd.d",
+                    RVCore::inst_fmadd_d,
+                    inst_fp_r4_code(InstID::FMADD_D, 0x43, 1, 4, 1, 2, 3),
+                    3,
+                    |a, b, c| {
+                        softfloat_flags(|| {
+                            F64::from_bits(a).fused_mul_add(
+                                F64::from_bits(b),
+                                F64::from_bits(c),
+                                RNE,
+                            );
+                        })
+                    },
+                ),
+                (
+                    "fmsub.d",
+                    RVCore::inst_fmsub_d,
+                    inst_fp_r4_code(InstID::FMSUB_D, 0x47, 1, 4, 1, 2, 3),
+                    3,
+                    |a, b, c| {
+                        softfloat_flags(|| {
+                            F64::from_bits(a).fused_mul_add(
+                                F64::from_bits(b),
+                                F64::from_bits(c).neg(),
+                                RNE,
+                            );
+                        })
+                    },
+                ),
+                (
+                    "fnmsub.d",
+                    RVCore::inst_fnmsub_d,
+                    inst_fp_r4_code(InstID::FNMSUB_D, 0x4b, 1, 4, 1, 2, 3),
+                    3,
+                    |a, b, c| {
+                        softfloat_flags(|| {
+                            F64::from_bits(a).neg().fused_mul_add(
+                                F64::from_bits(b),
+                                F64::from_bits(c),
+                                RNE,
+                            );
+                        })
+                    },
+                ),
+                (
+                    "fnmadd.d",
+                    RVCore::inst_fnmadd_d,
+                    inst_fp_r4_code(InstID::FNMADD_D, 0x4f, 1, 4, 1, 2, 3),
+                    3,
+                    |a, b, c| {
+                        softfloat_flags(|| {
+                            F64::from_bits(a).neg().fused_mul_add(
+                                F64::from_bits(b),
+                                F64::from_bits(c).neg(),
+                                RNE,
+                            );
+                        })
+                    },
+                ),
+                (
+                    "fmin.d",
+                    RVCore::inst_fmin_d,
+                    inst_fp_code(InstID::FMIN_D, 0x15, 4, 1, 2, 0),
+                    2,
+                    |a, b, _| {
+                        if F64::from_bits(a).is_signaling_nan()
+                            || F64::from_bits(b).is_signaling_nan()
+                        {
+                            0x10
+                        } else {
+                            0
+                        }
+                    },
+                ),
+                (
+                    "fmax.d",
+                    RVCore::inst_fmax_d,
+                    inst_fp_code(InstID::FMAX_D, 0x15, 4, 1, 2, 1),
+                    2,
+                    |a, b, _| {
+                        if F64::from_bits(a).is_signaling_nan()
+                            || F64::from_bits(b).is_signaling_nan()
+                        {
+                            0x10
+                        } else {
+                            0
+                        }
+                    },
+                ),
+                (
+                    "fle.d",
+                    RVCore::inst_fle_d,
+                    inst_fp_code(InstID::FLE_D, 0x51, 4, 1, 2, 0),
+                    2,
+                    |a, b, _| {
+                        softfloat_flags(|| {
+                            F64::from_bits(a).le(F64::from_bits(b));
+                        })
+                    },
+                ),
+                (
+                    "flt.d",
+                    RVCore::inst_flt_d,
+                    inst_fp_code(InstID::FLT_D, 0x51, 4, 1, 2, 1),
+                    2,
+                    |a, b, _| {
+                        softfloat_flags(|| {
+                            F64::from_bits(a).lt(F64::from_bits(b));
+                        })
+                    },
+                ),
+                (
+                    "feq.d",
+                    RVCore::inst_feq_d,
+                    inst_fp_code(InstID::FEQ_D, 0x51, 4, 1, 2, 2),
+                    2,
+                    |a, b, _| {
+                        softfloat_flags(|| {
+                            F64::from_bits(a).eq(F64::from_bits(b));
+                        })
+                    },
+                ),
+                (
+                    "fcvt.w.d",
+                    RVCore::inst_fcvt_w_d,
+                    inst_fp_code(InstID::FCVT_W_D, 0x61, 4, 1, 0, 0),
+                    1,
+                    |a, _, _| {
+                        softfloat_flags(|| {
+                            F64::from_bits(a).to_i32(RNE, true);
+                        })
+                    },
+                ),
+                (
+                    "fcvt.wu.d",
+                    RVCore::inst_fcvt_wu_d,
+                    inst_fp_code(InstID::FCVT_WU_D, 0x61, 4, 1, 1, 0),
+                    1,
+                    |a, _, _| {
+                        softfloat_flags(|| {
+                            F64::from_bits(a).to_u32(RNE, true);
+                        })
+                    },
+                ),
+                (
+                    "fcvt.l.d",
+                    RVCore::inst_fcvt_l_d,
+                    inst_fp_code(InstID::FCVT_L_D, 0x61, 4, 1, 2, 0),
+                    1,
+                    |a, _, _| {
+                        softfloat_flags(|| {
+                            F64::from_bits(a).to_i64(RNE, true);
+                        })
+                    },
+                ),
+                (
+                    "fcvt.lu.d",
+                    RVCore::inst_fcvt_lu_d,
+                    inst_fp_code(InstID::FCVT_LU_D, 0x61, 4, 1, 3, 0),
+                    1,
+                    |a, _, _| {
+                        softfloat_flags(|| {
+                            F64::from_bits(a).to_u64(RNE, true);
+                        })
+                    },
+                ),
+                (
+                    "fcvt.s.d",
+                    RVCore::inst_fcvt_s_d,
+                    inst_fp_code(InstID::FCVT_S_D, 0x20, 4, 1, 1, 0),
+                    1,
+                    |a, _, _| {
+                        softfloat_flags(|| {
+                            F64::from_bits(a).to_f32(RNE);
+                        })
+                    },
+                ),
+                (
+                    "fcvt.h.d",
+                    RVCore::inst_fcvt_h_d,
+                    inst_fp_code(InstID::FCVT_H_D, 0x22, 4, 1, 1, 0),
+                    1,
+                    |a, _, _| {
+                        softfloat_flags(|| {
+                            F64::from_bits(a).to_f16(RNE);
+                        })
+                    },
+                ),
+            ],
+            &edges,
+        );
+        check_fflags(
+            &[
+                (
+                    "fcvt.d.w",
+                    RVCore::inst_fcvt_d_w,
+                    inst_fp_code(InstID::FCVT_D_W, 0x69, 4, 1, 0, 0),
+                    1,
+                    |a, _, _| {
+                        softfloat_flags(|| {
+                            F64::from_i32(a as i32, RNE);
+                        })
+                    },
+                ),
+                (
+                    "fcvt.d.wu",
+                    RVCore::inst_fcvt_d_wu,
+                    inst_fp_code(InstID::FCVT_D_WU, 0x69, 4, 1, 1, 0),
+                    1,
+                    |a, _, _| {
+                        softfloat_flags(|| {
+                            F64::from_u32(a as u32, RNE);
+                        })
+                    },
+                ),
+                (
+                    "fcvt.d.l",
+                    RVCore::inst_fcvt_d_l,
+                    inst_fp_code(InstID::FCVT_D_L, 0x69, 4, 1, 2, 0),
+                    1,
+                    |a, _, _| {
+                        softfloat_flags(|| {
+                            F64::from_i64(a as i64, RNE);
+                        })
+                    },
+                ),
+                (
+                    "fcvt.d.lu",
+                    RVCore::inst_fcvt_d_lu,
+                    inst_fp_code(InstID::FCVT_D_LU, 0x69, 4, 1, 3, 0),
+                    1,
+                    |a, _, _| {
+                        softfloat_flags(|| {
+                            F64::from_u64(a, RNE);
+                        })
+                    },
+                ),
+            ],
+            &EDGE_INT,
+        );
+    }
+
+    const EDGE_H: [u64; 13] = [
+        0x0000, 0x8000, 0x3c00, 0x3555, 0xb800, 0xc200, 0x7bff, 0x0001, 0x0400, 0x7c00, 0xfc00,
+        0x7e00, 0x7c01,
+    ];
+
+    #[test]
+    fn test_inst_fflags_h() {
+        let edges: Vec<u64> = EDGE_H.iter().map(|x| 0xffff_ffff_ffff_0000 | x).collect();
+        check_fflags(
+            &[
+                (
+                    "fadd.h",
+                    RVCore::inst_fadd_h,
+                    inst_fp_code(InstID::FADD_H, 0x02, 4, 1, 2, 0),
+                    2,
+                    |a, b, _| {
+                        softfloat_flags(|| {
+                            F16::from_bits(a as u16).add(F16::from_bits(b as u16), RNE);
+                        })
+                    },
+                ),
+                (
+                    "fsub.h",
+                    RVCore::inst_fsub_h,
+                    inst_fp_code(InstID::FSUB_H, 0x06, 4, 1, 2, 0),
+                    2,
+                    |a, b, _| {
+                        softfloat_flags(|| {
+                            F16::from_bits(a as u16).sub(F16::from_bits(b as u16), RNE);
+                        })
+                    },
+                ),
+                (
+                    "fmul.h",
+                    RVCore::inst_fmul_h,
+                    inst_fp_code(InstID::FMUL_H, 0x0a, 4, 1, 2, 0),
+                    2,
+                    |a, b, _| {
+                        softfloat_flags(|| {
+                            F16::from_bits(a as u16).mul(F16::from_bits(b as u16), RNE);
+                        })
+                    },
+                ),
+                (
+                    "fdiv.h",
+                    RVCore::inst_fdiv_h,
+                    inst_fp_code(InstID::FDIV_H, 0x0e, 4, 1, 2, 0),
+                    2,
+                    |a, b, _| {
+                        softfloat_flags(|| {
+                            F16::from_bits(a as u16).div(F16::from_bits(b as u16), RNE);
+                        })
+                    },
+                ),
+                (
+                    "fsqrt.h",
+                    RVCore::inst_fsqrt_h,
+                    inst_fp_code(InstID::FSQRT_H, 0x2e, 4, 1, 0, 0),
+                    1,
+                    |a, _, _| {
+                        softfloat_flags(|| {
+                            F16::from_bits(a as u16).sqrt(RNE);
+                        })
+                    },
+                ),
+                (
+                    "fmadd.h",
+                    RVCore::inst_fmadd_h,
+                    inst_fp_r4_code(InstID::FMADD_H, 0x43, 2, 4, 1, 2, 3),
+                    3,
+                    |a, b, c| {
+                        softfloat_flags(|| {
+                            F16::from_bits(a as u16).fused_mul_add(
+                                F16::from_bits(b as u16),
+                                F16::from_bits(c as u16),
+                                RNE,
+                            );
+                        })
+                    },
+                ),
+                (
+                    "fmsub.h",
+                    RVCore::inst_fmsub_h,
+                    inst_fp_r4_code(InstID::FMSUB_H, 0x47, 2, 4, 1, 2, 3),
+                    3,
+                    |a, b, c| {
+                        softfloat_flags(|| {
+                            F16::from_bits(a as u16).fused_mul_add(
+                                F16::from_bits(b as u16),
+                                F16::from_bits(c as u16).neg(),
+                                RNE,
+                            );
+                        })
+                    },
+                ),
+                (
+                    "fnmsub.h",
+                    RVCore::inst_fnmsub_h,
+                    inst_fp_r4_code(InstID::FNMSUB_H, 0x4b, 2, 4, 1, 2, 3),
+                    3,
+                    |a, b, c| {
+                        softfloat_flags(|| {
+                            F16::from_bits(a as u16).neg().fused_mul_add(
+                                F16::from_bits(b as u16),
+                                F16::from_bits(c as u16),
+                                RNE,
+                            );
+                        })
+                    },
+                ),
+                (
+                    "fnmadd.h",
+                    RVCore::inst_fnmadd_h,
+                    inst_fp_r4_code(InstID::FNMADD_H, 0x4f, 2, 4, 1, 2, 3),
+                    3,
+                    |a, b, c| {
+                        softfloat_flags(|| {
+                            F16::from_bits(a as u16).neg().fused_mul_add(
+                                F16::from_bits(b as u16),
+                                F16::from_bits(c as u16).neg(),
+                                RNE,
+                            );
+                        })
+                    },
+                ),
+                (
+                    "fmin.h",
+                    RVCore::inst_fmin_h,
+                    inst_fp_code(InstID::FMIN_H, 0x16, 4, 1, 2, 0),
+                    2,
+                    |a, b, _| {
+                        if F16::from_bits(a as u16).is_signaling_nan()
+                            || F16::from_bits(b as u16).is_signaling_nan()
+                        {
+                            0x10
+                        } else {
+                            0
+                        }
+                    },
+                ),
+                (
+                    "fmax.h",
+                    RVCore::inst_fmax_h,
+                    inst_fp_code(InstID::FMAX_H, 0x16, 4, 1, 2, 1),
+                    2,
+                    |a, b, _| {
+                        if F16::from_bits(a as u16).is_signaling_nan()
+                            || F16::from_bits(b as u16).is_signaling_nan()
+                        {
+                            0x10
+                        } else {
+                            0
+                        }
+                    },
+                ),
+                (
+                    "fle.h",
+                    RVCore::inst_fle_h,
+                    inst_fp_code(InstID::FLE_H, 0x52, 4, 1, 2, 0),
+                    2,
+                    |a, b, _| {
+                        softfloat_flags(|| {
+                            F16::from_bits(a as u16).le(F16::from_bits(b as u16));
+                        })
+                    },
+                ),
+                (
+                    "flt.h",
+                    RVCore::inst_flt_h,
+                    inst_fp_code(InstID::FLT_H, 0x52, 4, 1, 2, 1),
+                    2,
+                    |a, b, _| {
+                        softfloat_flags(|| {
+                            F16::from_bits(a as u16).lt(F16::from_bits(b as u16));
+                        })
+                    },
+                ),
+                (
+                    "feq.h",
+                    RVCore::inst_feq_h,
+                    inst_fp_code(InstID::FEQ_H, 0x52, 4, 1, 2, 2),
+                    2,
+                    |a, b, _| {
+                        softfloat_flags(|| {
+                            F16::from_bits(a as u16).eq(F16::from_bits(b as u16));
+                        })
+                    },
+                ),
+                (
+                    "fcvt.w.h",
+                    RVCore::inst_fcvt_w_h,
+                    inst_fp_code(InstID::FCVT_W_H, 0x62, 4, 1, 0, 0),
+                    1,
+                    |a, _, _| {
+                        softfloat_flags(|| {
+                            F16::from_bits(a as u16).to_i32(RNE, true);
+                        })
+                    },
+                ),
+                (
+                    "fcvt.wu.h",
+                    RVCore::inst_fcvt_wu_h,
+                    inst_fp_code(InstID::FCVT_WU_H, 0x62, 4, 1, 1, 0),
+                    1,
+                    |a, _, _| {
+                        softfloat_flags(|| {
+                            F16::from_bits(a as u16).to_u32(RNE, true);
+                        })
+                    },
+                ),
+                (
+                    "fcvt.l.h",
+                    RVCore::inst_fcvt_l_h,
+                    inst_fp_code(InstID::FCVT_L_H, 0x62, 4, 1, 2, 0),
+                    1,
+                    |a, _, _| {
+                        softfloat_flags(|| {
+                            F16::from_bits(a as u16).to_i64(RNE, true);
+                        })
+                    },
+                ),
+                (
+                    "fcvt.lu.h",
+                    RVCore::inst_fcvt_lu_h,
+                    inst_fp_code(InstID::FCVT_LU_H, 0x62, 4, 1, 3, 0),
+                    1,
+                    |a, _, _| {
+                        softfloat_flags(|| {
+                            F16::from_bits(a as u16).to_u64(RNE, true);
+                        })
+                    },
+                ),
+                (
+                    "fcvt.s.h",
+                    RVCore::inst_fcvt_s_h,
+                    inst_fp_code(InstID::FCVT_S_H, 0x20, 4, 1, 2, 0),
+                    1,
+                    |a, _, _| {
+                        softfloat_flags(|| {
+                            F16::from_bits(a as u16).to_f32(RNE);
+                        })
+                    },
+                ),
+                (
+                    "fcvt.d.h",
+                    RVCore::inst_fcvt_d_h,
+                    inst_fp_code(InstID::FCVT_D_H, 0x21, 4, 1, 2, 0),
+                    1,
+                    |a, _, _| {
+                        softfloat_flags(|| {
+                            F16::from_bits(a as u16).to_f64(RNE);
+                        })
+                    },
+                ),
+            ],
+            &edges,
+        );
+        check_fflags(
+            &[
+                (
+                    "fcvt.h.w",
+                    RVCore::inst_fcvt_h_w,
+                    inst_fp_code(InstID::FCVT_H_W, 0x6a, 4, 1, 0, 0),
+                    1,
+                    |a, _, _| {
+                        softfloat_flags(|| {
+                            F16::from_i32(a as i32, RNE);
+                        })
+                    },
+                ),
+                (
+                    "fcvt.h.wu",
+                    RVCore::inst_fcvt_h_wu,
+                    inst_fp_code(InstID::FCVT_H_WU, 0x6a, 4, 1, 1, 0),
+                    1,
+                    |a, _, _| {
+                        softfloat_flags(|| {
+                            F16::from_u32(a as u32, RNE);
+                        })
+                    },
+                ),
+                (
+                    "fcvt.h.l",
+                    RVCore::inst_fcvt_h_l,
+                    inst_fp_code(InstID::FCVT_H_L, 0x6a, 4, 1, 2, 0),
+                    1,
+                    |a, _, _| {
+                        softfloat_flags(|| {
+                            F16::from_i64(a as i64, RNE);
+                        })
+                    },
+                ),
+                (
+                    "fcvt.h.lu",
+                    RVCore::inst_fcvt_h_lu,
+                    inst_fp_code(InstID::FCVT_H_LU, 0x6a, 4, 1, 3, 0),
+                    1,
+                    |a, _, _| {
+                        softfloat_flags(|| {
+                            F16::from_u64(a, RNE);
+                        })
+                    },
+                ),
+            ],
+            &EDGE_INT,
+        );
+    }
+
+    #[test]
+    fn test_inst_zfinx() {
+        let mut core: RVCore = RVCore::new();
+        core.set_isa("rv64imac_zfinx_zdinx").unwrap();
+
+        // Singles are read from and sign-extended into the x registers
+        core.regs.write(1, 0x3f80_0000);
+        core.regs.write(2, 0xffff_ffff_4000_0000);
+        core.inst_fadd_s(&inst_fadd_s_code(3, 1, 2, 0));
+        assert_eq!(0x4040_0000, core.regs.read(3));
+        core.inst_fsub_s(&inst_fp_code(InstID::FSUB_S, 0x04, 3, 1, 2, 0));
+        assert_eq!(0xffff_ffff_bf80_0000, core.regs.read(3));
+        core.inst_fcvt_d_s(&inst_fcvt_d_s_code(4, 3));
+        assert_eq!(0xbff0_0000_0000_0000, core.regs.read(4));
+        core.inst_fadd_d(&inst_fp_code(InstID::FADD_D, 0x01, 4, 4, 4, 0));
+        assert_eq!(0xc000_0000_0000_0000, core.regs.read(4));
+        assert_eq!(0, core.fregs.read_bits(4));
+
+        // x0 stays zero as a destination
+        core.inst_fadd_d(&inst_fp_code(InstID::FADD_D, 0x01, 0, 4, 4, 0));
+        assert_eq!(0, core.regs.read(0));
+
+        core.csregs.write(csregs::MSTATUS, csregs::MSTATUS_FS);
+        assert_eq!(0, core.csregs.read(csregs::MSTATUS));
+    }
+
+    #[test]
+    fn test_inst_zacas_zabha() {
+        let mut core = RVCore::new();
+        let mem = Rc::new(RefCell::new(crate::memory_model::MemoryModel::new()));
+        let mem_if: Rc<RefCell<dyn MemoryInterface>> = mem.clone();
+        core.bind_mem(mem_if);
+        mem.borrow_mut().write_word(0x1000, 0x1234_807f);
+        core.regs.write(2, 0x1000);
+
+        // Narrow AMOs sign-extend the loaded value and only touch their bytes
+        core.regs.write(3, 0x101);
+        core.inst_amoadd_b(&inst_amo_code(InstID::AMOADD_B, 0x00, 0x0, 1, 2, 3));
+        assert_eq!(0x7f, core.regs.read(1));
+        assert_eq!(0x1234_8080, mem.borrow_mut().read_word(0x1000));
+        core.inst_amoadd_b(&inst_amo_code(InstID::AMOADD_B, 0x00, 0x0, 1, 2, 3));
+        assert_eq!(0xffff_ffff_ffff_ff80, core.regs.read(1));
+        core.regs.write(3, 0x7f);
+        core.inst_amominu_b(&inst_amo_code(InstID::AMOMINU_B, 0x18, 0x0, 1, 2, 3));
+        assert_eq!(0x1234_807f, mem.borrow_mut().read_word(0x1000));
+        core.regs.write(3, 0x1);
+        core.inst_amomax_h(&inst_amo_code(InstID::AMOMAX_H, 0x14, 0x1, 1, 2, 3));
+        assert_eq!(0xffff_ffff_ffff_807f, core.regs.read(1));
+        assert_eq!(0x1234_0001, mem.borrow_mut().read_word(0x1000));
+
+        // amocas.w stores only on a match and always returns the old value
+        core.regs.write(1, 0x5555);
+        core.regs.write(3, 0x8765_4321);
+        core.inst_amocas_w(&inst_amo_code(InstID::AMOCAS_W, 0x05, 0x2, 1, 2, 3));
+        assert_eq!(0x1234_0001, core.regs.read(1));
+        assert_eq!(0x1234_0001, mem.borrow_mut().read_word(0x1000));
+        core.inst_amocas_w(&inst_amo_code(InstID::AMOCAS_W, 0x05, 0x2, 1, 2, 3));
+        assert_eq!(0x1234_0001, core.regs.read(1));
+        assert_eq!(0x8765_4321, mem.borrow_mut().read_word(0x1000));
+
+        // amocas.q compares and swaps even/odd register pairs
+        for i in 0..4 {
+            mem.borrow_mut().write_word(0x2000 + i * 4, i as u32 + 1);
+        }
+        core.regs.write(2, 0x2000);
+        core.regs.write(4, 0x0000_0002_0000_0001);
+        core.regs.write(5, 0x0000_0004_0000_0003);
+        core.regs.write(6, 0xaaaa);
+        core.regs.write(7, 0xbbbb);
+        core.inst_amocas_q(&inst_amo_code(InstID::AMOCAS_Q, 0x05, 0x4, 4, 2, 6));
+        assert_eq!(0x0000_0002_0000_0001, core.regs.read(4));
+        assert_eq!(0x0000_0004_0000_0003, core.regs.read(5));
+        assert_eq!(0xaaaa, mem.borrow_mut().read_word(0x2000));
+        assert_eq!(0xbbbb, mem.borrow_mut().read_word(0x2008));
+
+        // x0 as the source pair swaps in zero without writing x1
+        core.regs.write(4, 0xaaaa);
+        core.regs.write(5, 0xbbbb);
+        core.regs.write(1, 0x1111);
+        core.inst_amocas_q(&inst_amo_code(InstID::AMOCAS_Q, 0x05, 0x4, 4, 2, 0));
+        assert_eq!(0, mem.borrow_mut().read_word(0x2000));
+        assert_eq!(0, mem.borrow_mut().read_word(0x2008));
+        assert_eq!(0x1111, core.regs.read(1));
+
+        core.inst_amocas_q(&inst_amo_code(InstID::AMOCAS_Q, 0x05, 0x4, 5, 2, 6));
+        assert_eq!(csregs::EXC_ILLEGAL_INST, core.csregs.read(csregs::MCAUSE));
+    }
+
+    #[test]
+    fn test_inst_zicond_zimop() {
+        let mut fixture = Fixture::new();
+        fixture.core.regs.write(2, 0x1234);
+        fixture.core.regs.write(3, 0);
+        fixture.core.inst_czero_eqz(&inst_czero_eqz_code(1, 2, 3));
+        assert_eq!(0, fixture.core.regs.read(1));
+        fixture.core.inst_czero_nez(&inst_czero_nez_code(1, 2, 3));
+        assert_eq!(0x1234, fixture.core.regs.read(1));
+        fixture.core.regs.write(3, 1);
+        fixture.core.inst_czero_eqz(&inst_czero_eqz_code(1, 2, 3));
+        assert_eq!(0x1234, fixture.core.regs.read(1));
+        fixture.core.inst_czero_nez(&inst_czero_nez_code(1, 2, 3));
+        assert_eq!(0, fixture.core.regs.read(1));
+
+        fixture.core.regs.write(1, 0x5555);
+        fixture.core.inst_mop_r(&inst_mop_r_code(3, 1, 2));
+        assert_eq!(0, fixture.core.regs.read(1));
+        fixture.core.regs.write(1, 0x5555);
+        fixture.core.inst_mop_rr(&inst_mop_rr_code(3, 1, 2, 3));
+        assert_eq!(0, fixture.core.regs.read(1));
+    }
+
+    #[test]
+    fn test_inst_zcb() {
+        let mut core = RVCore::new();
+        let mem = Rc::new(RefCell::new(crate::memory_model::MemoryModel::new()));
+        let mem_if: Rc<RefCell<dyn MemoryInterface>> = mem.clone();
+        core.bind_mem(mem_if);
+        mem.borrow_mut().write_word(0x1000, 0x8081_8283);
+        core.regs.write(9, 0x1000);
+
+        core.inst_c_lbu(&inst_zcb_mem_code(InstID::C_LBU, 0x0, 8, 9, 3));
+        assert_eq!(0x80, core.regs.read(8));
+        core.inst_c_lhu(&inst_zcb_mem_code(InstID::C_LHU, 0x1, 8, 9, 2));
+        assert_eq!(0x8081, core.regs.read(8));
+        core.inst_c_lh(&inst_zcb_mem_code(InstID::C_LH, 0x1, 8, 9, 2));
+        assert_eq!(0xffff_ffff_ffff_8081, core.regs.read(8));
+        core.regs.write(10, 0x1234_5678);
+        core.inst_c_sb(&inst_zcb_mem_code(InstID::C_SB, 0x2, 10, 9, 1));
+        assert_eq!(0x8081_7883, mem.borrow_mut().read_word(0x1000));
+        core.inst_c_sh(&inst_zcb_mem_code(InstID::C_SH, 0x3, 10, 9, 2));
+        assert_eq!(0x5678_7883, mem.borrow_mut().read_word(0x1000));
+
+        core.regs.write(8, 0x1234_5678_9abc_def0);
+        core.inst_c_sext_h(&inst_zcb_unary_code(InstID::C_SEXT_H, 0x3, 8));
+        assert_eq!(0xffff_ffff_ffff_def0, core.regs.read(8));
+        core.inst_c_zext_w(&inst_zcb_unary_code(InstID::C_ZEXT_W, 0x4, 8));
+        assert_eq!(0xffff_def0, core.regs.read(8));
+        core.inst_c_sext_b(&inst_zcb_unary_code(InstID::C_SEXT_B, 0x1, 8));
+        assert_eq!(0xffff_ffff_ffff_fff0, core.regs.read(8));
+        core.inst_c_not(&inst_zcb_unary_code(InstID::C_NOT, 0x5, 8));
+        assert_eq!(0xf, core.regs.read(8));
+        core.regs.write(9, 3);
+        core.inst_c_mul(&inst_c_mul_code(8, 9));
+        assert_eq!(0x2d, core.regs.read(8));
+    }
+
+    #[test]
+    fn test_inst_zcmp_zcmt() {
+        let mut core = RVCore::new();
+        let mem = Rc::new(RefCell::new(crate::memory_model::MemoryModel::new()));
+        let mem_if: Rc<RefCell<dyn MemoryInterface>> = mem.clone();
+        core.bind_mem(mem_if);
+
+        // cm.push {ra, s0-s2}, -48 stores s2 just below sp and ra lowest
+        core.regs.write(2, 0x2000);
+        core.regs.write(1, 0x111);
+        core.regs.write(8, 0x888);
+        core.regs.write(9, 0x999);
+        core.regs.write(18, 0x1818);
+        core.inst_cm_push(&inst_cm_stack_code(InstID::CM_PUSH, 0x18, 7, 1));
+        assert_eq!(0x2000 - 48, core.regs.read(2));
+        assert_eq!(0x1818, mem.borrow_mut().read_word(0x2000 - 8));
+        assert_eq!(0x999, mem.borrow_mut().read_word(0x2000 - 16));
+        assert_eq!(0x888, mem.borrow_mut().read_word(0x2000 - 24));
+        assert_eq!(0x111, mem.borrow_mut().read_word(0x2000 - 32));
+
+        for reg in [1, 8, 9, 18] {
+            core.regs.write(reg, 0);
+        }
+        core.regs.write(10, 0x5555);
+        core.pc = 0x100;
+        core.inst_cm_popretz(&inst_cm_stack_code(InstID::CM_POPRETZ, 0x1c, 7, 1));
+        assert_eq!(0x2000, core.regs.read(2));
+        assert_eq!(0x111, core.regs.read(1));
+        assert_eq!(0x888, core.regs.read(8));
+        assert_eq!(0x999, core.regs.read(9));
+        assert_eq!(0x1818, core.regs.read(18));
+        assert_eq!(0, core.regs.read(10));
+        assert_eq!(0x111 - 2, core.pc);
+
+        // {ra, s0-s11} saves 13 registers in 112 bytes
+        core.inst_cm_push(&inst_cm_stack_code(InstID::CM_PUSH, 0x18, 15, 0));
+        assert_eq!(0x2000 - 112, core.regs.read(2));
+        assert_eq!(0x111, mem.borrow_mut().read_word(0x2000 - 104));
+
+        core.regs.write(10, 0xa0);
+        core.regs.write(11, 0xa1);
+        core.inst_cm_mvsa01(&inst_cm_mv_code(InstID::CM_MVSA01, 0x1, 1, 7));
+        assert_eq!(0xa0, core.regs.read(9));
+        assert_eq!(0xa1, core.regs.read(23));
+        core.inst_cm_mva01s(&inst_cm_mv_code(InstID::CM_MVA01S, 0x3, 7, 7));
+        assert_eq!(0xa1, core.regs.read(10));
+        assert_eq!(0xa1, core.regs.read(11));
+
+        // The low mode bits of jvt are not writable
+        core.csregs.write(csregs::JVT, 0x3000 | 0x3f);
+        assert_eq!(0x3000, core.csregs.read(csregs::JVT));
+        mem.borrow_mut().write_word(0x3000 + 5 * 8, 0x4001);
+        mem.borrow_mut().write_word(0x3000 + 40 * 8, 0x5000);
+        core.pc = 0x100;
+        core.inst_cm_jt(&inst_cm_jt_code(5));
+        assert_eq!(0x4000 - 2, core.pc);
+        core.pc = 0x100;
+        core.inst_cm_jalt(&inst_cm_jt_code(40));
+        assert_eq!(0x5000 - 2, core.pc);
+        assert_eq!(0x102, core.regs.read(1));
+    }
+
+    #[test]
+    fn test_inst_cbo() {
+        let mut fixture = Fixture::new();
+        let core = &mut fixture.core;
+        core.set_cache_block_size(32).unwrap();
+        core.regs.write(1, 0x1234);
+
+        core.inst_cbo_clean(&inst_cbo_code(InstID::CBO_CLEAN, 0x1, 1));
+        {
+            let payload = &fixture.mem_stub.borrow().buffer;
+            assert_eq!(MemoryOperation::CLEAN, payload.op);
+            assert_eq!(0x1220, payload.addr);
+            assert_eq!(32, payload.data.len());
+        }
+
+        core.inst_cbo_zero(&inst_cbo_code(InstID::CBO_ZERO, 0x4, 1));
+        assert_eq!(MemoryOperation::WRITE, fixture.mem_stub.borrow().buffer.op);
+        assert_eq!(vec![0; 32], fixture.mem_stub.borrow().buffer.data);
+
+        // Below M-mode every level has to enable the instructions
+        core.mode = PrivilegeMode::U;
+        core.csregs.write(csregs::MENVCFG, csregs::ENVCFG_CBZE);
+        core.inst_cbo_zero(&inst_cbo_code(InstID::CBO_ZERO, 0x4, 1));
+        assert_eq!(csregs::EXC_ILLEGAL_INST, core.csregs.read(csregs::MCAUSE));
+
+        core.mode = PrivilegeMode::S;
+        core.csregs.write(csregs::MCAUSE, 0);
+        core.inst_cbo_zero(&inst_cbo_code(InstID::CBO_ZERO, 0x4, 1));
+        assert_eq!(0, core.csregs.read(csregs::MCAUSE));
+        core.inst_cbo_flush(&inst_cbo_code(InstID::CBO_FLUSH, 0x2, 1));
+        assert_eq!(csregs::EXC_ILLEGAL_INST, core.csregs.read(csregs::MCAUSE));
+
+        // CBIE=1 performs a flush, the reserved CBIE=2 keeps the old value
+        core.mode = PrivilegeMode::S;
+        core.trap_taken = false;
+        core.csregs.write(csregs::MENVCFG, 1 << 4);
+        core.csregs.write(csregs::MENVCFG, 2 << 4);
+        assert_eq!(1 << 4, core.csregs.read(csregs::MENVCFG));
+        core.inst_cbo_inval(&inst_cbo_code(InstID::CBO_INVAL, 0x0, 1));
+        assert_eq!(MemoryOperation::FLUSH, fixture.mem_stub.borrow().buffer.op);
+        core.csregs.write(csregs::MENVCFG, 3 << 4);
+        core.inst_cbo_inval(&inst_cbo_code(InstID::CBO_INVAL, 0x0, 1));
+        assert_eq!(MemoryOperation::INVAL, fixture.mem_stub.borrow().buffer.op);
+        core.mode = PrivilegeMode::U;
+        core.csregs.write(csregs::SENVCFG, 1 << 4);
+        core.inst_cbo_inval(&inst_cbo_code(InstID::CBO_INVAL, 0x0, 1));
+        assert_eq!(MemoryOperation::FLUSH, fixture.mem_stub.borrow().buffer.op);
+
+        // With V=1 a clear menvcfg bit is illegal, a clear henvcfg or senvcfg bit virtual
+        core.set_isa("rv64gch").unwrap();
+        core.csregs.write(csregs::MENVCFG, 0);
+        core.csregs.write(csregs::HENVCFG, csregs::ENVCFG_CBZE);
+        core.csregs.write(csregs::SENVCFG, csregs::ENVCFG_CBZE);
+        core.mode = PrivilegeMode::S;
+        core.virt = true;
+        core.inst_cbo_zero(&inst_cbo_code(InstID::CBO_ZERO, 0x4, 1));
+        assert_eq!(csregs::EXC_ILLEGAL_INST, core.csregs.read(csregs::MCAUSE));
+        core.csregs
+            .write(csregs::MENVCFG, csregs::ENVCFG_CBZE | (3 << 4));
+        core.mode = PrivilegeMode::S;
+        core.virt = true;
+        core.inst_cbo_inval(&inst_cbo_code(InstID::CBO_INVAL, 0x0, 1));
+        assert_eq!(csregs::EXC_VIRTUAL_INST, core.csregs.read(csregs::MCAUSE));
+        core.mode = PrivilegeMode::S;
+        core.virt = true;
+        core.csregs.write(csregs::MCAUSE, 0);
+        core.inst_cbo_zero(&inst_cbo_code(InstID::CBO_ZERO, 0x4, 1));
+        assert_eq!(0, core.csregs.read(csregs::MCAUSE));
+        core.csregs
+            .write(csregs::HENVCFG, csregs::ENVCFG_CBZE | (1 << 4));
+        core.inst_cbo_inval(&inst_cbo_code(InstID::CBO_INVAL, 0x0, 1));
+        assert_eq!(MemoryOperation::FLUSH, fixture.mem_stub.borrow().buffer.op);
+
+        core.mode = PrivilegeMode::U;
+        core.csregs.write(csregs::SENVCFG, 0);
+        core.inst_cbo_zero(&inst_cbo_code(InstID::CBO_ZERO, 0x4, 1));
+        assert_eq!(csregs::EXC_VIRTUAL_INST, core.csregs.read(csregs::MCAUSE));
+        core.mode = PrivilegeMode::U;
+        core.virt = true;
+        core.csregs.write(csregs::MCAUSE, 0);
+        core.csregs.write(csregs::SENVCFG, csregs::ENVCFG_CBZE);
+        core.inst_cbo_zero(&inst_cbo_code(InstID::CBO_ZERO, 0x4, 1));
+        assert_eq!(0, core.csregs.read(csregs::MCAUSE));
+
+        assert!(core.set_cache_block_size(48).is_err());
+    }
+
+    #[test]
+    fn test_inst_seed() {
+        let mut core = RVCore::new();
+        let csrrw = inst_csr_code(InstID::CSRRW, 1, 1, 0, csregs::SEED);
+        core.inst_csrrw(&csrrw);
+        assert_eq!(csregs::EXC_ILLEGAL_INST, core.csregs.read(csregs::MCAUSE));
+
+        core.set_isa("rv64imac_zkr").unwrap();
+        core.csregs.write(csregs::MCAUSE, 0);
+        let mut polls = vec![];
+        for _ in 0..32 {
+            core.inst_csrrw(&csrrw);
+            polls.push(core.regs.read(1));
+        }
+        assert_eq!(0, core.csregs.read(csregs::MCAUSE));
+        assert_eq!(vec![csregs::SEED_OPST_BIST; 2], polls[..2].to_vec());
+        for val in &polls[2..] {
+            match val & (3 << 30) {
+                csregs::SEED_OPST_ES16 => assert_eq!(0, val & !(csregs::SEED_OPST_ES16 | 0xffff)),
+                opst => assert_eq!((csregs::SEED_OPST_WAIT, 0), (opst, val & 0xffff)),
+            }
+        }
+        assert!(polls.contains(&csregs::SEED_OPST_WAIT));
+
+        // The same seed replays the same sequence
+        core.set_entropy_seed(csregs::DEFAULT_ENTROPY_SEED);
+        for val in &polls {
+            core.inst_csrrwi(&inst_csr_code(InstID::CSRRWI, 5, 1, 0, csregs::SEED));
+            assert_eq!(*val, core.regs.read(1));
+        }
+
+        // A read without write is illegal
+        core.inst_csrrs(&inst_csr_code(InstID::CSRRS, 2, 1, 0, csregs::SEED));
+        assert_eq!(csregs::EXC_ILLEGAL_INST, core.csregs.read(csregs::MCAUSE));
+
+        // S/U-mode access is enabled by mseccfg
+        core.csregs.write(csregs::MSECCFG, AddressType::MAX);
+        assert_eq!(
+            csregs::MSECCFG_USEED | csregs::MSECCFG_SSEED,
+            core.csregs.read(csregs::MSECCFG)
+        );
+        core.csregs.write(csregs::MSECCFG, csregs::MSECCFG_SSEED);
+        core.csregs.write(csregs::MCAUSE, 0);
+        core.mode = PrivilegeMode::S;
+        core.inst_csrrw(&csrrw);
+        assert_eq!(0, core.csregs.read(csregs::MCAUSE));
+        core.mode = PrivilegeMode::U;
+        core.inst_csrrw(&csrrw);
+        assert_eq!(csregs::EXC_ILLEGAL_INST, core.csregs.read(csregs::MCAUSE));
+
+        // VS/VU-mode access is a virtual instruction where HS/U-mode access is allowed
+        core.set_isa("rv64gch_zkr").unwrap();
+        core.csregs.write(csregs::MSECCFG, csregs::MSECCFG_SSEED);
+        for (mode, cause) in [
+            (PrivilegeMode::S, csregs::EXC_VIRTUAL_INST),
+            (PrivilegeMode::U, csregs::EXC_ILLEGAL_INST),
+        ] {
+            core.csregs.write(csregs::MCAUSE, 0);
+            core.mode = mode;
+            core.virt = true;
+            core.inst_csrrw(&csrrw);
+            assert_eq!(cause, core.csregs.read(csregs::MCAUSE));
+        }
+        core.csregs.write(csregs::MSECCFG, csregs::MSECCFG_USEED);
+        core.csregs.write(csregs::MCAUSE, 0);
+        core.mode = PrivilegeMode::U;
+        core.virt = true;
+        core.inst_csrrw(&csrrw);
+        assert_eq!(csregs::EXC_VIRTUAL_INST, core.csregs.read(csregs::MCAUSE));
+    }
+
+    #[test]
+    fn test_aia_csrs() {
+        let mut fixture = Fixture::new();
+        let core = &mut fixture.core;
+        core.inst_csrrs(&inst_csr_code(InstID::CSRRS, 2, 1, 0, csregs::MTOPI));
+        assert_eq!(csregs::EXC_ILLEGAL_INST, core.csregs.read(csregs::MCAUSE));
+
+        core.set_isa("rv64gch_smaia").unwrap();
+        let aia = core.aia().unwrap();
+        let csrrw = |csr| inst_csr_code(InstID::CSRRW, 1, 2, 1, csr);
+        core.regs.write(1, aia::ISELECT_EIDELIVERY);
+        core.inst_csrrw(&csrrw(csregs::MISELECT));
+        core.regs.write(1, 1);
+        core.inst_csrrw(&csrrw(csregs::MIREG));
+        core.regs.write(1, aia::ISELECT_EIE0);
+        core.inst_csrrw(&csrrw(csregs::MISELECT));
+        core.regs.write(1, 1 << 7);
+        core.inst_csrrw(&csrrw(csregs::MIREG));
+        aia.borrow_mut().imsic_m.set_pending(7);
+        assert_eq!((7 << 16) | 7, core.csregs.read(csregs::MTOPEI));
+        assert_eq!(csregs::MIP_MEIP, core.csregs.read(csregs::MIP));
+
+        // The external interrupt is taken and claimed through mtopei
+        core.csregs.write(csregs::MIE, csregs::MIP_MEIP);
+        assert_eq!((11 << 16) | 1, core.csregs.read(csregs::MTOPI));
+        core.csregs.write(csregs::MSTATUS, csregs::MSTATUS_MIE);
+        core.csregs.write(csregs::MTVEC, 0x100);
+        core.step();
+        assert_eq!(
+            csregs::CAUSE_INTERRUPT | 11,
+            core.csregs.read(csregs::MCAUSE)
+        );
+        assert_eq!(0x100, core.pc);
+        core.inst_csrrw(&csrrw(csregs::MTOPEI));
+        assert_eq!((7 << 16) | 7, core.regs.read(2));
+        assert_eq!(0, core.csregs.read(csregs::MIP));
+
+        // The S-level interrupt file is reached through siselect/sireg
+        core.csregs.write(csregs::SISELECT, aia::ISELECT_EIP0);
+        core.mode = PrivilegeMode::S;
+        core.regs.write(1, 1 << 9);
+        core.inst_csrrw(&csrrw(csregs::SIREG));
+        assert_eq!(1 << 9, aia.borrow().imsic_s.read_reg(aia::ISELECT_EIP0));
+        core.csregs.write(csregs::MIDELEG, csregs::MIP_SEIP);
+        core.csregs.write(csregs::SIE, csregs::MIP_SEIP);
+        core.csregs.write(csregs::MIP, csregs::MIP_SEIP);
+        assert_eq!((9 << 16) | 1, core.csregs.read(csregs::STOPI));
+
+        // Only existing registers can be selected
+        core.csregs.write(csregs::MCAUSE, 0);
+        core.csregs.write(csregs::SISELECT, aia::ISELECT_EIP0 + 1);
+        core.inst_csrrw(&csrrw(csregs::SIREG));
+        assert_eq!(csregs::EXC_ILLEGAL_INST, core.csregs.read(csregs::MCAUSE));
+
+        // There are no guest interrupt files
+        core.mode = PrivilegeMode::S;
+        core.virt = true;
+        core.inst_csrrw(&csrrw(csregs::STOPEI));
+        assert_eq!(csregs::EXC_VIRTUAL_INST, core.csregs.read(csregs::MCAUSE));
+    }
+
+    #[test]
+    fn test_clic() {
+        let mut core = RVCore::new();
+        let mem = Rc::new(RefCell::new(crate::memory_model::MemoryModel::new()));
+        let mem_if: Rc<RefCell<dyn MemoryInterface>> = mem.clone();
+        core.bind_mem(mem_if);
+        core.inst_csrrs(&inst_csr_code(InstID::CSRRS, 2, 1, 0, csregs::MTVT));
+        assert_eq!(csregs::EXC_ILLEGAL_INST, core.csregs.read(csregs::MCAUSE));
+
+        core.set_isa("rv64imac_smclic").unwrap();
+        let clic = core.clic().unwrap();
+        let write_clic = |offset: AddressType, data: &[u8]| {
+            let mut payload = Payload {
+                addr: crate::clic::CLIC_BASE + offset,
+                data: data.to_vec(),
+                op: MemoryOperation::WRITE,
+                is_amo: false,
+            };
+            clic.borrow_mut().access_memory(&mut payload);
+        };
+        // Interrupt 20 is level-triggered and vectored, 21 is edge-triggered
+        write_clic(0, &[8]);
+        write_clic(0x1000 + 4 * 20, &[0, 1, 1, 0x80]);
+        write_clic(0x1000 + 4 * 21, &[0, 1, 2, 0x40]);
+        core.csregs.write(csregs::MTVEC, 0x1007);
+        assert_eq!(0x1003, core.csregs.read(csregs::MTVEC));
+        core.csregs.write(csregs::MTVT, 0x2000);
+        mem.borrow_mut().write_word(0x2000 + 8 * 20, 0x3001);
+        core.csregs.write(csregs::MIE, csregs::MIP_MEIP);
+        assert_eq!(0, core.csregs.read(csregs::MIE));
+
+        // Taken through the mtvt entry, mcause.mpp aliases mstatus.MPP
+        core.csregs.write(csregs::MSTATUS, csregs::MSTATUS_MIE);
+        clic.borrow_mut().set_input(20, true);
+        core.csregs.write(csregs::MINTTHRESH, 0x80);
+        assert_eq!(None, core.pending_interrupt());
+        core.csregs.write(csregs::MINTTHRESH, 0);
+        core.pc = 0x8000_0000;
+        core.step();
+        assert_eq!(0x3000, core.pc);
+        assert_eq!(
+            csregs::CAUSE_INTERRUPT | csregs::MCAUSE_MPP | csregs::MCAUSE_MPIE | 20,
+            core.csregs.read(csregs::MCAUSE)
+        );
+        assert_eq!(0x80 << 24, core.csregs.read(csregs::MINTSTATUS));
+
+        // A lower level interrupt waits for the handler to finish
+        core.csregs.write(csregs::MSTATUS, csregs::MSTATUS_MIE);
+        clic.borrow_mut().set_input(21, true);
+        assert_eq!(None, core.pending_interrupt());
+        let mnxti = inst_csr_code(InstID::CSRRSI, 6, 1, 8, csregs::MNXTI);
+        core.inst_csrrsi(&mnxti);
+        assert_eq!(0, core.regs.read(1));
+        clic.borrow_mut().set_input(20, false);
+        core.csregs.write(csregs::MSTATUS, csregs::MSTATUS_MPP);
+        core.inst_csrrs(&inst_csr_code(InstID::CSRRS, 2, 1, 0, csregs::MNXTI));
+        assert_eq!(0x2000 + 8 * 21, core.regs.read(1));
+        assert_eq!(0x80 << 24, core.csregs.read(csregs::MINTSTATUS));
+
+        // With a write, mnxti takes the interrupt over
+        core.inst_csrrsi(&mnxti);
+        assert_eq!(0x2000 + 8 * 21, core.regs.read(1));
+        assert_eq!(
+            csregs::MSTATUS_MIE,
+            core.csregs.read(csregs::MSTATUS) & csregs::MSTATUS_MIE
+        );
+        assert_eq!(0x40 << 24, core.csregs.read(csregs::MINTSTATUS));
+        assert_eq!(
+            21,
+            core.csregs.read(csregs::MCAUSE) & csregs::MCAUSE_EXCCODE
+        );
+        assert_eq!(None, clic.borrow().top());
+        core.inst_csrrsi(&mnxti);
+        assert_eq!(0, core.regs.read(1));
+
+        // mret restores the level, exceptions go to the mtvec base
+        core.csregs.write(csregs::MEPC, 0x8000_0000);
+        core.inst_mret(&inst_op_code(InstID::MRET, 0x73, 0x18, 0, 0, 0, 2));
+        assert_eq!(0, core.csregs.read(csregs::MINTSTATUS));
+        assert_eq!(PrivilegeMode::M, core.mode);
+        core.inst_csrrw(&inst_csr_code(InstID::CSRRW, 1, 1, 0, csregs::MNXTI));
+        assert_eq!(0x1000, core.pc);
+        assert_eq!(
+            csregs::EXC_ILLEGAL_INST,
+            core.csregs.read(csregs::MCAUSE) & 0xfff
+        );
+    }
+
+    #[test]
+    fn test_trap_vector() {
+        let mut core = RVCore::new();
+        core.set_isa("rv64gch").unwrap();
+        core.csregs.write(csregs::MTVEC, 0x1002);
+        assert_eq!(0x1000, core.csregs.read(csregs::MTVEC));
+        core.csregs.write(csregs::MTVEC, 0x1001);
+        assert_eq!(0x1001, core.csregs.read(csregs::MTVEC));
+        core.inst_ecall(&inst_op_code(InstID::ECALL, 0x73, 0, 0, 0, 0, 0));
+        assert_eq!(0x1000, core.pc);
+        core.raise_trap(csregs::CAUSE_INTERRUPT | 7, 0, 0, false);
+        assert_eq!(0x1000 + 4 * 7, core.pc);
+
+        // Delegated interrupts use stvec, or vstvec with the VS-level cause
+        core.csregs.write(csregs::STVEC, 0x2001);
+        core.csregs.write(csregs::VSTVEC, 0x3001);
+        core.csregs.write(csregs::MIDELEG, csregs::MIP_STIP);
+        core.csregs.write(csregs::HIDELEG, csregs::MIP_VSTIP);
+        core.mode = PrivilegeMode::U;
+        core.raise_trap(csregs::CAUSE_INTERRUPT | 5, 0, 0, false);
+        assert_eq!(0x2000 + 4 * 5, core.pc);
+        core.mode = PrivilegeMode::U;
+        core.virt = true;
+        core.raise_trap(csregs::CAUSE_INTERRUPT | 6, 0, 0, false);
+        assert_eq!(0x3000 + 4 * 5, core.pc);
+        core.raise_exception(csregs::EXC_ILLEGAL_INST, 0);
+        assert_eq!(0x1000, core.pc);
+    }
+
+    #[test]
+    fn test_rnmi() {
+        let mut fixture = Fixture::new();
+        let core = &mut fixture.core;
+        core.inst_csrrs(&inst_csr_code(InstID::CSRRS, 2, 1, 0, csregs::MNSTATUS));
+        assert_eq!(csregs::EXC_ILLEGAL_INST, core.csregs.read(csregs::MCAUSE));
+
+        core.set_isa("rv64gc_smrnmi").unwrap();
+        core.set_reset_vector(0x2000);
+        core.set_nmi_vectors(0x100, 0x200);
+        core.reset();
+        assert_eq!(0x2000, core.pc);
+        assert_eq!(PrivilegeMode::M, core.mode);
+
+        // Masked until NMIE is set, which software can't clear
+        core.raise_nmi(3);
+        core.csregs.write(csregs::MTVEC, 0x400);
+        core.csregs.write(csregs::MSTATUS, csregs::MSTATUS_MIE);
+        core.csregs.write(csregs::MIE, csregs::MIP_SSIP);
+        core.csregs.write(csregs::MIP, csregs::MIP_SSIP);
+        assert_eq!(None, core.pending_interrupt());
+        core.csregs.write(csregs::MNSTATUS, csregs::MNSTATUS_NMIE);
+        core.csregs.write(csregs::MNSTATUS, 0);
+        assert_eq!(Some(1), core.pending_interrupt());
+        core.mode = PrivilegeMode::U;
+        core.step();
+        assert_eq!(0x100, core.pc);
+        assert_eq!(PrivilegeMode::M, core.mode);
+        assert_eq!(0x2000, core.csregs.read(csregs::MNEPC));
+        assert_eq!(
+            csregs::CAUSE_INTERRUPT | 3,
+            core.csregs.read(csregs::MNCAUSE)
+        );
+        assert_eq!(0, core.csregs.read(csregs::MNSTATUS));
+        assert_eq!(None, core.pending_interrupt());
+
+        // Exceptions of the NMI handler have their own vector
+        core.inst_ecall(&inst_op_code(InstID::ECALL, 0x73, 0, 0, 0, 0, 0));
+        assert_eq!(0x200, core.pc);
+        assert_eq!(csregs::EXC_ECALL_FROM_M, core.csregs.read(csregs::MCAUSE));
+
+        let mnret = inst_op_code(InstID::MNRET, 0x73, 0x38, 0, 0, 0, 2);
+        core.inst_mnret(&mnret);
+        assert_eq!(0x2000 - 4, core.pc);
+        assert_eq!(PrivilegeMode::U, core.mode);
+        assert_eq!(csregs::MNSTATUS_NMIE, core.csregs.read(csregs::MNSTATUS));
+        assert_eq!(Some(1), core.pending_interrupt());
+        core.inst_mnret(&mnret);
+        assert_eq!(0x400, core.pc);
+        assert_eq!(csregs::EXC_ILLEGAL_INST, core.csregs.read(csregs::MCAUSE));
+    }
+
+    #[test]
+    fn test_double_trap() {
+        let mut fixture = Fixture::new();
+        let core = &mut fixture.core;
+        let ecall = inst_op_code(InstID::ECALL, 0x73, 0, 0, 0, 0, 0);
+        core.set_isa("rv64gch_ssdbltrp_smdbltrp").unwrap();
+        core.reset();
+        assert_ne!(0, core.csregs.read(csregs::MSTATUS) & csregs::MSTATUS_MDT);
+        // MIE can't be set along with MDT, SDT is read-only zero until DTE is set
+        core.csregs
+            .write(csregs::MSTATUS, csregs::MSTATUS_MDT | csregs::MSTATUS_MIE);
+        assert_eq!(csregs::MSTATUS_MDT, core.csregs.read(csregs::MSTATUS));
+        core.csregs.write(csregs::SSTATUS, csregs::MSTATUS_SDT);
+        assert_eq!(0, core.csregs.read(csregs::SSTATUS));
+        core.csregs.write(csregs::HENVCFG, csregs::ENVCFG_DTE);
+        assert_eq!(0, core.csregs.read(csregs::HENVCFG));
+        core.csregs.write(csregs::MENVCFG, csregs::ENVCFG_DTE);
+        assert_eq!(csregs::ENVCFG_DTE, core.csregs.read(csregs::HENVCFG));
+
+        // mret leaves the M-mode handler, a trap into S-mode sets SDT
+        core.csregs.write(csregs::MSTATUS, csregs::MSTATUS_MDT);
+        core.csregs.write(csregs::MEPC, 0x1000);
+        core.csregs.write(csregs::MTVEC, 0x400);
+        core.csregs.write(csregs::STVEC, 0x800);
+        let medeleg = (1 << csregs::EXC_ECALL_FROM_U) | (1 << csregs::EXC_ECALL_FROM_S);
+        core.csregs.write(csregs::MEDELEG, medeleg);
+        core.inst_mret(&inst_op_code(InstID::MRET, 0x73, 0x18, 0, 0, 2, 0));
+        assert_eq!(PrivilegeMode::U, core.mode);
+        assert_eq!(0, core.csregs.read(csregs::MSTATUS) & csregs::MSTATUS_MDT);
+        core.inst_ecall(&ecall);
+        assert_eq!(0x800, core.pc);
+        assert_eq!(PrivilegeMode::S, core.mode);
+        assert_ne!(0, core.csregs.read(csregs::SSTATUS) & csregs::MSTATUS_SDT);
+
+        // A second trap into S-mode goes to M-mode as a double trap
+        core.pc = 0x900;
+        core.inst_ecall(&ecall);
+        assert_eq!(0x400, core.pc);
+        assert_eq!(PrivilegeMode::M, core.mode);
+        assert_eq!(csregs::EXC_DOUBLE_TRAP, core.csregs.read(csregs::MCAUSE));
+        assert_eq!(csregs::EXC_ECALL_FROM_S, core.csregs.read(csregs::MTVAL2));
+        assert_ne!(0, core.csregs.read(csregs::MSTATUS) & csregs::MSTATUS_MDT);
+
+        // And a trap into M-mode while MDT is set stops the hart
+        core.pc = 0x404;
+        core.inst_ecall(&ecall);
+        assert_eq!(0x404, core.pc);
+        assert_eq!(
+            Some("double trap, cause 0xb at pc 0x404 while handling cause 0x10 from pc 0x900"),
+            core.critical_error()
+        );
+        core.run(10);
+        assert_eq!(0x404, core.pc);
+
+        // With Smrnmi the double trap is taken as an RNMI, unless already in the NMI handler
+        core.set_isa("rv64gc_smdbltrp_smrnmi").unwrap();
+        core.set_nmi_vectors(0x100, 0x200);
+        core.reset();
+        core.csregs.write(csregs::MNSTATUS, csregs::MNSTATUS_NMIE);
+        core.inst_ecall(&ecall);
+        assert_eq!(0x100, core.pc);
+        assert_eq!(csregs::EXC_DOUBLE_TRAP, core.csregs.read(csregs::MNCAUSE));
+        assert_eq!(None, core.critical_error());
+        core.inst_ecall(&ecall);
+        assert_eq!(
+            Some("double trap, cause 0xb at pc 0x100 while handling cause 0x10 from pc 0x1000"),
+            core.critical_error()
+        );
+    }
+
+    #[test]
+    fn test_triggers() {
+        let mut fixture = Fixture::new();
+        let core = &mut fixture.core;
+        let tinfo = inst_csr_code(InstID::CSRRS, 2, 1, 0, csregs::TINFO);
+        core.inst_csrrs(&tinfo);
+        assert_eq!(csregs::EXC_ILLEGAL_INST, core.csregs.read(csregs::MCAUSE));
+
+        core.set_isa("rv64gc_sdtrig").unwrap();
+        core.trap_taken = false;
+        core.inst_csrrs(&tinfo);
+        assert_eq!(0x100_8048, core.regs.read(1));
+        // Legacy mcontrol (type 2) is not supported
+        core.csregs.write(csregs::TDATA1, 2 << 60);
+        assert_eq!(15 << 60, core.csregs.read(csregs::TDATA1));
+        core.csregs.write(csregs::TSELECT, 4);
+        assert_eq!(0, core.csregs.read(csregs::TSELECT));
+
+        // Execute address match in U-mode, raised before the instruction is decoded
+        let mcontrol6 = 6 << 60;
+        core.csregs.write(csregs::MTVEC, 0x400);
+        core.csregs
+            .write(csregs::TDATA1, mcontrol6 | (1 << 3) | (1 << 2));
+        core.csregs.write(csregs::TDATA2, 0x1000);
+        core.mode = PrivilegeMode::U;
+        core.pc = 0x1000;
+        core.step();
+        assert_eq!(0x400, core.pc);
+        assert_eq!(csregs::EXC_BREAKPOINT, core.csregs.read(csregs::MCAUSE));
+        assert_eq!(0x1000, core.csregs.read(csregs::MTVAL));
+        assert_ne!(0, core.csregs.read(csregs::TDATA1) & (1 << 22));
+
+        // M-mode triggers don't fire while MIE is clear
+        core.csregs.write(csregs::TSELECT, 1);
+        core.csregs
+            .write(csregs::TDATA1, mcontrol6 | (1 << 7) | (1 << 6) | (1 << 0));
+        core.csregs.write(csregs::TDATA2, 0x2003);
+        core.trap_taken = false;
+        core.read_memory(0x2004, &mut [0; 4]);
+        assert!(!core.trap_taken);
+        // NAPOT load address match covering 0x2000..=0x2007
+        core.csregs.write(csregs::MSTATUS, csregs::MSTATUS_MIE);
+        core.read_memory(0x2008, &mut [0; 4]);
+        assert!(!core.trap_taken);
+        core.read_memory(0x2004, &mut [0; 4]);
+        assert!(core.trap_taken);
+        assert_eq!(0x2004, core.csregs.read(csregs::MTVAL));
+
+        // Store data match
+        core.csregs.write(csregs::TSELECT, 2);
+        core.csregs
+            .write(csregs::TDATA1, mcontrol6 | (1 << 21) | (1 << 6) | (1 << 1));
+        core.csregs.write(csregs::TDATA2, 0x1234);
+        core.csregs.write(csregs::MSTATUS, csregs::MSTATUS_MIE);
+        core.trap_taken = false;
+        core.write_memory(0x3000, &mut [0x35, 0x12]);
+        assert!(!core.trap_taken);
+        core.write_memory(0x3000, &mut [0x34, 0x12]);
+        assert!(core.trap_taken);
+        assert_eq!(0x3000, core.csregs.read(csregs::MTVAL));
+
+        // icount fires before the instruction following the count reaching zero
+        core.csregs.write(csregs::TSELECT, 3);
+        core.csregs
+            .write(csregs::TDATA1, (3 << 60) | (2 << 10) | (1 << 6));
+        let triggers = core.csregs.triggers();
+        triggers.retire_instruction(PrivilegeMode::U, false);
+        triggers.retire_instruction(PrivilegeMode::M, false);
+        assert_eq!(1 << 10, triggers.tdata1() & (0x3fff << 10));
+        triggers.retire_instruction(PrivilegeMode::U, false);
+        assert_eq!(1 << 8, triggers.tdata1() & (0x1ffff << 8));
+        core.mode = PrivilegeMode::U;
+        core.pc = 0x5000;
+        core.step();
+        assert_eq!(0x400, core.pc);
+        assert_eq!(0x5000, core.csregs.read(csregs::MEPC));
+        assert_eq!(1 << 24, core.csregs.read(csregs::TDATA1) & (0x1ffff << 8));
+    }
+
+    #[test]
+    fn test_debug_mode() {
+        let mut fixture = Fixture::new();
+        let core = &mut fixture.core;
+        let ebreak = inst_op_code(InstID::EBREAK, 0x73, 0, 0, 0, 0, 1);
+        let dret = inst_op_code(InstID::DRET, 0x73, 0x3d, 0, 0, 0, 0x12);
+        let cause = |core: &RVCore| {
+            (core.csregs.read(csregs::DCSR) & csregs::DCSR_CAUSE) >> csregs::DCSR_CAUSE_SHIFT
+        };
+        core.csregs.write(csregs::MTVEC, 0x400);
+        core.pc = 0x1000;
+        core.inst_ebreak(&ebreak);
+        assert_eq!(0x400, core.pc);
+        assert_eq!(csregs::EXC_BREAKPOINT, core.csregs.read(csregs::MCAUSE));
+        assert_eq!(0x1000, core.csregs.read(csregs::MTVAL));
+        core.inst_dret(&dret);
+        assert_eq!(csregs::EXC_ILLEGAL_INST, core.csregs.read(csregs::MCAUSE));
+
+        // ebreak from U-mode enters debug mode with dcsr.ebreaku
+        core.set_isa("rv64gc_sdext_sdtrig").unwrap();
+        let dcsr = core.csregs.read(csregs::DCSR);
+        core.csregs.write(csregs::DCSR, dcsr | csregs::DCSR_EBREAKU);
+        core.mode = PrivilegeMode::U;
+        core.pc = 0x1000;
+        core.inst_ebreak(&ebreak);
+        assert!(core.halted());
+        assert_eq!(PrivilegeMode::M, core.mode);
+        assert_eq!(0x1000, core.csregs.read(csregs::DPC));
+        assert_eq!(csregs::DCSR_CAUSE_EBREAK, cause(core));
+        assert_eq!(0, core.csregs.read(csregs::DCSR) & csregs::DCSR_PRV);
+
+        // Registers seen by the Debug Module
+        assert_eq!(Some(0x1000), core.read_debug_register(0x7b1));
+        assert!(core.write_debug_register(csregs::DSCRATCH0 as u32, 0x55));
+        assert_eq!(Some(0x55), core.read_debug_register(0x7b2));
+        assert!(core.write_debug_register(0x1005, 0x77));
+        assert_eq!(0x77, core.regs.read(5));
+        assert!(!core.write_debug_register(0xc00, 0));
+        assert_eq!(None, core.read_debug_register(0x2000));
+
+        // addi x5, x5, 1 then ebreak from the program buffer
+        assert!(core.execute_program_buffer(&[0x0012_8293, 0x0010_0073]));
+        assert_eq!(0x78, core.regs.read(5));
+        core.inst_dret(&dret);
+        assert!(!core.halted());
+        assert_eq!(0x1000 - 4, core.pc);
+        assert_eq!(PrivilegeMode::U, core.mode);
+
+        // Halt request, nothing runs until the hart resumes
+        core.pc = 0x2000;
+        core.set_halt_request(true);
+        core.step();
+        core.set_halt_request(false);
+        core.step();
+        assert!(core.halted());
+        assert_eq!(0x2000, core.csregs.read(csregs::DPC));
+        assert_eq!(csregs::DCSR_CAUSE_HALTREQ, cause(core));
+
+        // Single step, the instruction at 0x2000 is a compressed one
+        let dcsr = core.csregs.read(csregs::DCSR);
+        core.csregs.write(csregs::DCSR, dcsr | csregs::DCSR_STEP);
+        core.resume();
+        core.step();
+        assert!(core.halted());
+        assert_eq!(0x2002, core.csregs.read(csregs::DPC));
+        assert_eq!(csregs::DCSR_CAUSE_STEP, cause(core));
+
+        // Execute trigger entering debug mode, only writable from debug mode
+        let mcontrol6 = (6 << 60) | (1 << 59) | (1 << 12) | (1 << 3) | (1 << 2);
+        core.csregs.write(csregs::TDATA1, mcontrol6);
+        core.csregs.write(csregs::TDATA2, 0x3000);
+        let dcsr = core.csregs.read(csregs::DCSR);
+        core.csregs.write(csregs::DCSR, dcsr & !csregs::DCSR_STEP);
+        core.resume();
+        core.csregs.write(csregs::TDATA1, 15 << 60);
+        assert_eq!(mcontrol6, core.csregs.read(csregs::TDATA1));
+        core.pc = 0x3000;
+        core.step();
+        assert!(core.halted());
+        assert_eq!(0x3000, core.csregs.read(csregs::DPC));
+        assert_eq!(csregs::DCSR_CAUSE_TRIGGER, cause(core));
+    }
+
+    #[test]
+    fn test_interrupt_priority() {
+        let mut fixture = Fixture::new();
+        let core = &mut fixture.core;
+        // SSI goes before STI by default priority, but STI stays at M-level
+        core.csregs.write(csregs::MIDELEG, csregs::MIP_SSIP);
+        core.csregs
+            .write(csregs::MIE, csregs::MIP_SSIP | csregs::MIP_STIP);
+        core.csregs
+            .write(csregs::MIP, csregs::MIP_SSIP | csregs::MIP_STIP);
+        core.csregs.write(csregs::MSTATUS, csregs::MSTATUS_SIE);
+        core.mode = PrivilegeMode::S;
+        assert_eq!(Some(5), core.pending_interrupt());
+        core.csregs.write(csregs::MIP, csregs::MIP_SSIP);
+        assert_eq!(Some(1), core.pending_interrupt());
+
+        core.csregs
+            .write(csregs::MIP, csregs::MIP_SSIP | csregs::MIP_STIP);
+        core.csregs.write(csregs::MTVEC, 0x400);
+        core.pc = 0x1000;
+        core.step();
+        assert_eq!(0x400, core.pc);
+        assert_eq!(PrivilegeMode::M, core.mode);
+        assert_eq!(
+            csregs::CAUSE_INTERRUPT | 5,
+            core.csregs.read(csregs::MCAUSE)
+        );
+        // Neither is taken in M-mode with MIE clear
+        assert_eq!(None, core.pending_interrupt());
+    }
+
+    #[test]
+    fn test_sstc() {
+        let mut fixture = Fixture::new();
+        let core = &mut fixture.core;
+        let stimecmp = inst_csr_code(InstID::CSRRW, 1, 0, 1, csregs::STIMECMP);
+        core.inst_csrrw(&stimecmp);
+        assert_eq!(csregs::EXC_ILLEGAL_INST, core.csregs.read(csregs::MCAUSE));
+
+        core.set_isa("rv64gch_sstc").unwrap();
+        core.csregs.write(csregs::MCAUSE, 0);
+        core.csregs.write(csregs::MENVCFG, csregs::ENVCFG_STCE);
+        core.regs.write(1, 10);
+        core.inst_csrrw(&stimecmp);
+        assert_eq!(10, core.csregs.read(csregs::STIMECMP));
+        assert_eq!(0, core.csregs.read(csregs::MIP) & csregs::MIP_STIP);
+        // STIP is read-only once STCE is set
+        core.csregs.write(csregs::MIP, csregs::MIP_STIP);
+        assert_eq!(0, core.csregs.read(csregs::MIP) & csregs::MIP_STIP);
+        for _ in 0..10 {
+            core.tick();
+        }
+        assert_eq!(10, core.csregs.read(csregs::TIME));
+        assert_eq!(
+            csregs::MIP_STIP,
+            core.csregs.read(csregs::MIP) & csregs::MIP_STIP
+        );
+
+        // S-mode needs mcounteren.TM for time and stimecmp
+        core.mode = PrivilegeMode::S;
+        let time = inst_csr_code(InstID::CSRRS, 2, 3, 0, csregs::TIME);
+        core.inst_csrrs(&time);
+        assert_eq!(csregs::EXC_ILLEGAL_INST, core.csregs.read(csregs::MCAUSE));
+        core.mode = PrivilegeMode::S;
+        core.csregs.write(csregs::MCOUNTEREN, csregs::COUNTEREN_TM);
+        core.csregs.write(csregs::MCAUSE, 0);
+        core.inst_csrrs(&time);
+        assert_eq!(10, core.regs.read(3));
+        core.regs.write(1, 100);
+        core.inst_csrrw(&stimecmp);
+        assert_eq!(0, core.csregs.read(csregs::MCAUSE));
+        assert_eq!(0, core.csregs.read(csregs::SIP));
+
+        // The delegated timer interrupt is taken in S-mode once SIE is set
+        core.csregs.write(csregs::MIDELEG, csregs::MIP_STIP);
+        core.csregs.write(csregs::MIE, csregs::MIP_STIP);
+        core.csregs.write(csregs::STIMECMP, 0);
+        core.csregs.write(csregs::STVEC, 0x400);
+        core.pc = 0x1000;
+        assert_eq!(None, core.pending_interrupt());
+        core.csregs.write(csregs::SSTATUS, csregs::MSTATUS_SIE);
+        core.step();
+        assert_eq!(
+            csregs::CAUSE_INTERRUPT | 5,
+            core.csregs.read(csregs::SCAUSE)
+        );
+        assert_eq!(0x1000, core.csregs.read(csregs::SEPC));
+        assert_eq!(0x400, core.pc);
+        assert_eq!(0, core.csregs.read(csregs::SSTATUS) & csregs::MSTATUS_SIE);
+
+        // vstimecmp compares against time + htimedelta and is seen as STI by VS-mode
+        core.csregs.write(csregs::HENVCFG, csregs::ENVCFG_STCE);
+        core.csregs.write(csregs::HTIMEDELTA, 100);
+        core.csregs.write(csregs::VSTIMECMP, 200);
+        assert_eq!(0, core.csregs.read(csregs::HIP) & csregs::MIP_VSTIP);
+        core.csregs.write(csregs::HTIMEDELTA, 190);
+        assert_eq!(csregs::MIP_VSTIP, core.csregs.read(csregs::HIP));
+        core.csregs.write(csregs::HIDELEG, csregs::MIP_VSTIP);
+        core.csregs.write(csregs::MIE, csregs::MIP_VSTIP);
+        core.csregs.write(csregs::VSTVEC, 0x800);
+        core.csregs.write(csregs::VSSTATUS, csregs::MSTATUS_SIE);
+        core.mode = PrivilegeMode::U;
+        core.virt = true;
+        core.pc = 0x2000;
+        core.step();
+        assert_eq!(
+            csregs::CAUSE_INTERRUPT | 5,
+            core.csregs.read(csregs::VSCAUSE)
+        );
+        assert_eq!((PrivilegeMode::S, true), (core.mode, core.virt));
+        assert_eq!(0x800, core.pc);
+
+        // VS-mode accesses vstimecmp through stimecmp when allowed by hcounteren
+        core.inst_csrrs(&inst_csr_code(InstID::CSRRS, 2, 3, 0, csregs::STIMECMP));
+        assert_eq!(csregs::EXC_VIRTUAL_INST, core.csregs.read(csregs::MCAUSE));
+        core.mode = PrivilegeMode::S;
+        core.virt = true;
+        core.csregs.write(csregs::HCOUNTEREN, csregs::COUNTEREN_TM);
+        core.inst_csrrs(&inst_csr_code(InstID::CSRRS, 2, 3, 0, csregs::STIMECMP));
+        assert_eq!(200, core.regs.read(3));
+    }
+
+    fn write_pte(
+        mem: &Rc<RefCell<crate::memory_model::MemoryModel>>,
+        addr: AddressType,
+        pte: AddressType,
+    ) {
+        mem.borrow_mut().write_word(addr, pte as u32);
+        mem.borrow_mut().write_word(addr + 4, (pte >> 32) as u32);
+    }
+
+    #[test]
+    fn test_sv39_translation() {
+        let mut core = RVCore::new();
+        let mem = Rc::new(RefCell::new(crate::memory_model::MemoryModel::new()));
+        let mem_if: Rc<RefCell<dyn MemoryInterface>> = mem.clone();
+        core.bind_mem(mem_if);
+
+        // Gigapages VA 0x4000_0000 -> PA 0x8000_0000 (RW) and VA 0x8000_0000 -> PA 0xc000_0000 (R)
+        write_pte(&mem, 0x10008, (0x80000 << 10) | 0xc7);
+        write_pte(&mem, 0x10010, (0xc0000 << 10) | 0xc3);
+        mem.borrow_mut().write_word(0x8000_1230, 0x55);
+        core.csregs
+            .write(csregs::SATP, csregs::SATP_MODE_SV39 | (0x10000 >> 12));
+        core.mode = PrivilegeMode::S;
+        core.regs.write(2, 0x4000_1230);
+        core.inst_ld(&inst_ld_code(1, 2, 0));
+        assert_eq!(0x55, core.regs.read(1));
+        core.regs.write(3, 0xaa);
+        core.inst_sd(&inst_sd_code(3, 2, 8));
+        assert_eq!(0xaa, mem.borrow_mut().read_word(0x8000_1238));
+        // satp is not used in M-mode
+        core.mode = PrivilegeMode::M;
+        core.inst_ld(&inst_ld_code(1, 2, 0));
+        assert_eq!(0, core.regs.read(1));
+
+        // Stores to a read-only page fault into M-mode
+        core.mode = PrivilegeMode::S;
+        core.pc = 0x4000;
+        core.regs.write(2, 0x8000_0010);
+        core.inst_sd(&inst_sd_code(3, 2, 0));
+        assert_eq!(
+            csregs::EXC_STORE_PAGE_FAULT,
+            core.csregs.read(csregs::MCAUSE)
+        );
+        assert_eq!(0x8000_0010, core.csregs.read(csregs::MTVAL));
+        assert_eq!(0x4000, core.csregs.read(csregs::MEPC));
+        assert_eq!(PrivilegeMode::M, core.mode);
+
+        // Delegated page faults are taken in S-mode, U-mode can't access S pages
+        core.csregs
+            .write(csregs::MEDELEG, 1 << csregs::EXC_LOAD_PAGE_FAULT);
+        core.csregs.write(csregs::STVEC, 0x200);
+        core.mode = PrivilegeMode::U;
+        core.trap_taken = false;
+        core.regs.write(2, 0x4000_1230);
+        core.inst_ld(&inst_ld_code(1, 2, 0));
+        assert_eq!(
+            csregs::EXC_LOAD_PAGE_FAULT,
+            core.csregs.read(csregs::SCAUSE)
+        );
+        assert_eq!(0x4000_1230, core.csregs.read(csregs::STVAL));
+        assert_eq!(0x200, core.pc);
+        assert_eq!(PrivilegeMode::S, core.mode);
+        assert_eq!(0, core.csregs.read(csregs::SSTATUS) & csregs::MSTATUS_SPP);
+
+        // Non-canonical addresses fault
+        core.trap_taken = false;
+        core.regs.write(2, 0x40_0000_0000);
+        core.inst_ld(&inst_ld_code(1, 2, 0));
+        assert_eq!(0x40_0000_0000, core.csregs.read(csregs::STVAL));
+    }
+
+    #[test]
+    fn test_hypervisor_translation() {
+        let mut core = RVCore::new();
+        core.set_isa("rv64gch").unwrap();
+        let mem = Rc::new(RefCell::new(crate::memory_model::MemoryModel::new()));
+        let mem_if: Rc<RefCell<dyn MemoryInterface>> = mem.clone();
+        core.bind_mem(mem_if);
+
+        // G-stage: GPA 0 -> PA 0x8000_0000. VS-stage tables at GPA 0x1000 map
+        // VA 0x4000_0000 -> GPA 0 and VA 0x8000_0000 -> the unmapped GPA 0x4000_0000.
+        write_pte(&mem, 0x20000, (0x80000 << 10) | 0xdf);
+        write_pte(&mem, 0x8000_1008, 0xc7);
+        write_pte(&mem, 0x8000_1010, (0x40000 << 10) | 0xc7);
+        mem.borrow_mut().write_word(0x8000_2000, 0x1234);
+        core.csregs
+            .write(csregs::HGATP, csregs::SATP_MODE_SV39 | (0x20000 >> 12));
+        core.csregs
+            .write(csregs::VSATP, csregs::SATP_MODE_SV39 | (0x1000 >> 12));
+
+        core.mode = PrivilegeMode::S;
+        core.virt = true;
+        core.regs.write(2, 0x4000_2000);
+        core.inst_ld(&inst_ld_code(1, 2, 0));
+        assert_eq!(0x1234, core.regs.read(1));
+
+        core.pc = 0x4000;
+        core.regs.write(2, 0x8000_0010);
+        core.inst_ld(&inst_ld_code(1, 2, 0));
+        assert_eq!(
+            csregs::EXC_LOAD_GUEST_PAGE_FAULT,
+            core.csregs.read(csregs::MCAUSE)
+        );
+        assert_eq!(0x8000_0010, core.csregs.read(csregs::MTVAL));
+        assert_eq!(0x4000_0010 >> 2, core.csregs.read(csregs::MTVAL2));
+        let mstatus = core.csregs.read(csregs::MSTATUS);
+        assert_eq!(
+            csregs::MSTATUS_MPV | csregs::MSTATUS_GVA,
+            mstatus & (csregs::MSTATUS_MPV | csregs::MSTATUS_GVA)
+        );
+        assert_eq!((PrivilegeMode::M, false), (core.mode, core.virt));
+
+        // mret returns into VS-mode, the delegated fault is taken in HS-mode
+        core.inst_mret(&inst_op_code(InstID::MRET, 0x73, 0x18, 0, 0, 0, 2));
+        assert_eq!((PrivilegeMode::S, true), (core.mode, core.virt));
+        core.csregs
+            .write(csregs::MEDELEG, 1 << csregs::EXC_STORE_GUEST_PAGE_FAULT);
+        core.trap_taken = false;
+        core.inst_sd(&inst_sd_code(1, 2, 0));
+        assert_eq!(
+            csregs::EXC_STORE_GUEST_PAGE_FAULT,
+            core.csregs.read(csregs::SCAUSE)
+        );
+        assert_eq!(0x4000_0010 >> 2, core.csregs.read(csregs::HTVAL));
+        let hstatus = core.csregs.read(csregs::HSTATUS);
+        let bits = csregs::HSTATUS_GVA | csregs::HSTATUS_SPV | csregs::HSTATUS_SPVP;
+        assert_eq!(bits, hstatus & bits);
+        assert_eq!((PrivilegeMode::S, false), (core.mode, core.virt));
+
+        // hlv/hsv access the guest from HS-mode
+        core.trap_taken = false;
+        core.regs.write(2, 0x4000_2000);
+        core.inst_hlv_d(&inst_op_code(InstID::HLV_D, 0x73, 0x36, 4, 3, 2, 0));
+        assert_eq!(0x1234, core.regs.read(3));
+        core.regs.write(4, 0xff);
+        core.inst_hsv_b(&inst_op_code(InstID::HSV_B, 0x73, 0x31, 4, 0, 2, 4));
+        assert_eq!(0x12ff, mem.borrow_mut().read_word(0x8000_2000));
+        core.inst_hlv_b(&inst_op_code(InstID::HLV_B, 0x73, 0x30, 4, 3, 2, 0));
+        assert_eq!(AddressType::MAX, core.regs.read(3));
+
+        // sret goes back to VS-mode
+        core.csregs.write(csregs::SEPC, 0x3000);
+        core.inst_sret(&inst_op_code(InstID::SRET, 0x73, 0x08, 0, 0, 0, 2));
+        assert_eq!((PrivilegeMode::S, true), (core.mode, core.virt));
+        assert_eq!(0x3000 - 4, core.pc);
+        assert_eq!(0, core.csregs.read(csregs::HSTATUS) & csregs::HSTATUS_SPV);
+    }
+
+    #[test]
+    fn test_virtual_instruction() {
+        let mut core = RVCore::new();
+        core.set_isa("rv64gch").unwrap();
+        core.mode = PrivilegeMode::S;
+        core.virt = true;
+
+        // Supervisor CSRs are replaced by the VS ones
+        core.csregs.write(csregs::VSEPC, 0x77);
+        core.inst_csrrs(&inst_csr_code(InstID::CSRRS, 2, 1, 0, csregs::SEPC));
+        assert_eq!(0x77, core.regs.read(1));
+
+        core.inst_csrrs(&inst_csr_code(InstID::CSRRS, 2, 1, 0, csregs::HSTATUS));
+        assert_eq!(csregs::EXC_VIRTUAL_INST, core.csregs.read(csregs::MCAUSE));
+
+        core.mode = PrivilegeMode::S;
+        core.virt = true;
+        core.csregs.write(csregs::HSTATUS, csregs::HSTATUS_VTSR);
+        core.inst_sret(&inst_op_code(InstID::SRET, 0x73, 0x08, 0, 0, 0, 2));
+        assert_eq!(csregs::EXC_VIRTUAL_INST, core.csregs.read(csregs::MCAUSE));
+
+        core.mode = PrivilegeMode::U;
+        core.virt = true;
+        core.inst_wfi(&inst_op_code(InstID::WFI, 0x73, 0x08, 0, 0, 0, 5));
+        assert_eq!(csregs::EXC_VIRTUAL_INST, core.csregs.read(csregs::MCAUSE));
+
+        // M-mode only CSRs stay illegal
+        core.mode = PrivilegeMode::S;
+        core.virt = true;
+        core.inst_csrrs(&inst_csr_code(InstID::CSRRS, 2, 1, 0, csregs::MSTATUS));
+        assert_eq!(csregs::EXC_ILLEGAL_INST, core.csregs.read(csregs::MCAUSE));
+
+        core.mode = PrivilegeMode::S;
+        core.virt = true;
+        core.inst_ecall(&inst_op_code(InstID::ECALL, 0x73, 0, 0, 0, 0, 0));
+        assert_eq!(csregs::EXC_ECALL_FROM_VS, core.csregs.read(csregs::MCAUSE));
+    }
+
+    #[test]
+    fn test_inst_zfa() {
+        let mut core: RVCore = RVCore::new();
+
+        core.inst_fli_s(&inst_fp_code(InstID::FLI_S, 0x78, 1, 16, 1, 0));
+        assert_eq!(0xffff_ffff_3f80_0000, core.fregs.read_bits(1));
+        core.inst_fli_d(&inst_fp_code(InstID::FLI_D, 0x79, 1, 1, 1, 0));
+        assert_eq!(0x0010_0000_0000_0000, core.fregs.read_bits(1));
+        core.inst_fli_d(&inst_fp_code(InstID::FLI_D, 0x79, 1, 9, 1, 0));
+        assert_eq!(0x3fd4_0000_0000_0000, core.fregs.read_bits(1));
+        core.inst_fli_h(&inst_fp_code(InstID::FLI_H, 0x7a, 1, 2, 1, 0));
+        assert_eq!(0xffff_ffff_ffff_0100, core.fregs.read_bits(1));
+        core.inst_fli_h(&inst_fp_code(InstID::FLI_H, 0x7a, 1, 29, 1, 0));
+        assert_eq!(0xffff_ffff_ffff_7c00, core.fregs.read_bits(1));
+        assert_eq!(0, core.csregs.read(csregs::FFLAGS));
+
+        // fminm returns the canonical NaN where fmin returns the other operand
+        core.fregs.write_s(2, F32::from_bits(0x3f80_0000));
+        core.fregs.write_s(3, F32::from_bits(0x7fc0_0000));
+        core.inst_fmin_s(&inst_fp_code(InstID::FMIN_S, 0x14, 1, 2, 3, 0));
+        assert_eq!(0x3f80_0000, core.fregs.read_s(1).to_bits());
+        core.inst_fminm_s(&inst_fp_code(InstID::FMINM_S, 0x14, 1, 2, 3, 2));
+        assert_eq!(0x7fc0_0000, core.fregs.read_s(1).to_bits());
+        core.fregs.write(3, F64::from_bits(0x8000_0000_0000_0000));
+        core.fregs.write(2, F64::from_bits(0x0000_0000_0000_0000));
+        core.inst_fmaxm_d(&inst_fp_code(InstID::FMAXM_D, 0x15, 1, 2, 3, 3));
+        assert_eq!(0, core.fregs.read_bits(1));
+
+        // -1.5 rounded down, only froundnx reports the inexact result
+        core.fregs.write(2, F64::from_bits(0xbff8_0000_0000_0000));
+        core.inst_fround_d(&inst_fp_code(InstID::FROUND_D, 0x21, 1, 2, 4, 2));
+        assert_eq!(0xc000_0000_0000_0000, core.fregs.read_bits(1));
+        assert_eq!(0, core.csregs.read(csregs::FFLAGS));
+        core.inst_froundnx_d(&inst_fp_code(InstID::FROUNDNX_D, 0x21, 1, 2, 5, 2));
+        assert_eq!(0xc000_0000_0000_0000, core.fregs.read_bits(1));
+        assert_eq!(0x1, core.csregs.read(csregs::FFLAGS));
+        core.csregs.write(csregs::FFLAGS, 0);
+
+        // fcvtmod.w.d wraps modulo 2^32, raising NV when out of range
+        core.fregs.write(2, F64::from_bits(0x41f0_0000_0058_0000));
+        core.inst_fcvtmod_w_d(&inst_fp_code(InstID::FCVTMOD_W_D, 0x61, 4, 2, 8, 1));
+        assert_eq!(5, core.regs.read(4));
+        assert_eq!(0x10, core.csregs.read(csregs::FFLAGS));
+        core.csregs.write(csregs::FFLAGS, 0);
+        core.fregs.write(2, F64::from_bits(0xc00d_9999_9999_999a));
+        core.inst_fcvtmod_w_d(&inst_fp_code(InstID::FCVTMOD_W_D, 0x61, 4, 2, 8, 1));
+        assert_eq!(-3i64 as AddressType, core.regs.read(4));
+        assert_eq!(0x1, core.csregs.read(csregs::FFLAGS));
+        core.csregs.write(csregs::FFLAGS, 0);
+        core.fregs.write(2, F64::from_bits(0x7ff0_0000_0000_0000));
+        core.inst_fcvtmod_w_d(&inst_fp_code(InstID::FCVTMOD_W_D, 0x61, 4, 2, 8, 1));
+        assert_eq!(0, core.regs.read(4));
+        core.csregs.write(csregs::FFLAGS, 0);
+
+        // Quiet comparisons only signal for signaling NaNs
+        core.fregs.write_s(2, F32::from_bits(0x3f80_0000));
+        core.fregs.write_s(3, F32::from_bits(0x7fc0_0000));
+        core.inst_fltq_s(&inst_fp_code(InstID::FLTQ_S, 0x50, 4, 2, 3, 5));
+        assert_eq!(0, core.regs.read(4));
+        assert_eq!(0, core.csregs.read(csregs::FFLAGS));
+        core.inst_fleq_s(&inst_fp_code(InstID::FLEQ_S, 0x50, 4, 2, 2, 4));
+        assert_eq!(1, core.regs.read(4));
+        core.fregs.write_s(3, F32::from_bits(0x7f80_0001));
+        core.inst_fleq_s(&inst_fp_code(InstID::FLEQ_S, 0x50, 4, 2, 3, 4));
+        assert_eq!(0x10, core.csregs.read(csregs::FFLAGS));
+    }
+
+    #[test]
+    fn test_inst_zfh() {
+        let mut core: RVCore = RVCore::new();
+
+        // 1.5 + 2.25, results stay NaN-boxed
+        core.regs.write(2, 0x3e00);
+        core.regs.write(3, 0x4080);
+        core.inst_fmv_h_x(&inst_fmv_h_x_code(2, 2));
+        core.inst_fmv_h_x(&inst_fmv_h_x_code(3, 3));
+        core.inst_fadd_h(&inst_fadd_h_code(1, 2, 3));
+        assert_eq!(0xffff_ffff_ffff_4380, core.fregs.read_bits(1));
+        core.inst_fmv_x_h(&inst_fmv_x_h_code(4, 1));
+        assert_eq!(0x4380, core.regs.read(4));
+        core.inst_fcvt_w_h(&inst_fcvt_w_h_code(4, 1));
+        assert_eq!(3, core.regs.read(4));
+        core.inst_fcvt_s_h(&inst_fcvt_s_h_code(5, 1));
+        assert_eq!(0x4070_0000, core.fregs.read_s(5).to_bits());
+
+        // An improperly boxed operand reads as the canonical NaN
+        core.fregs.write_bits(6, 0x3ff0_0000_0000_3c00);
+        core.inst_fadd_h(&inst_fadd_h_code(1, 6, 3));
+        assert_eq!(0xffff_ffff_ffff_7e00, core.fregs.read_bits(1));
+        core.inst_fcvt_w_h(&inst_fcvt_w_h_code(4, 6));
+        assert_eq!(i32::MAX as AddressType, core.regs.read(4));
+        // NV accrues on top of NX from the inexact fcvt.w.h above
+        assert_eq!(0x11, core.csregs.read(csregs::FFLAGS));
+
+        // -inf saturates to the most negative value
+        core.regs.write(2, 0xfc00);
+        core.inst_fmv_h_x(&inst_fmv_h_x_code(2, 2));
+        core.inst_fcvt_w_h(&inst_fcvt_w_h_code(4, 2));
+        assert_eq!(0xffff_ffff_8000_0000, core.regs.read(4));
+        core.inst_fmv_x_h(&inst_fmv_x_h_code(4, 2));
+        assert_eq!(0xffff_ffff_ffff_fc00, core.regs.read(4));
+    }
+
+    #[test]
+    fn test_inst_zba() {
+        let mut core: RVCore = RVCore::new();
+
+        core.regs.write(2, 0xffff_ffff_8000_0001); // rs1
+        core.regs.write(3, 0x10); // rs2
+        core.inst_add_uw(&inst_add_uw_code(1, 2, 3));
+        assert_eq!(0x8000_0011, core.regs.read(1));
+        core.inst_sh2add(&inst_sh2add_code(1, 2, 3));
+        assert_eq!(0xffff_fffe_0000_0014, core.regs.read(1));
+        core.inst_sh3add_uw(&inst_sh3add_uw_code(1, 2, 3));
+        assert_eq!(0x4_0000_0018, core.regs.read(1));
+        core.inst_slli_uw(&inst_slli_uw_code(1, 2, 32));
+        assert_eq!(0x8000_0001_0000_0000, core.regs.read(1));
+    }
+
+    #[test]
+    fn test_inst_zbb() {
+        let mut core: RVCore = RVCore::new();
+
+        core.regs.write(2, 0x0000_00f0_0000_8000); // rs1
+        core.regs.write(3, 0xffff_ffff_ffff_ff00); // rs2
+        core.inst_andn(&inst_andn_code(1, 2, 3));
+        assert_eq!(0x0000_0000_0000_0000, core.regs.read(1));
+        core.inst_xnor(&inst_xnor_code(1, 2, 3));
+        assert_eq!(0x0000_00f0_0000_80ff, core.regs.read(1));
+        core.inst_clz(&inst_clz_code(1, 2));
+        assert_eq!(24, core.regs.read(1));
+        core.inst_ctz(&inst_ctz_code(1, 2));
+        assert_eq!(15, core.regs.read(1));
+        core.inst_cpopw(&inst_cpopw_code(1, 3));
+        assert_eq!(24, core.regs.read(1));
+        core.inst_max(&inst_max_code(1, 2, 3));
+        assert_eq!(0x0000_00f0_0000_8000, core.regs.read(1));
+        core.inst_minu(&inst_minu_code(1, 2, 3));
+        assert_eq!(0x0000_00f0_0000_8000, core.regs.read(1));
+        core.inst_orc_b(&inst_orc_b_code(1, 2));
+        assert_eq!(0x0000_00ff_0000_ff00, core.regs.read(1));
+        core.inst_rev8(&inst_rev8_code(1, 2));
+        assert_eq!(0x0080_0000_f000_0000, core.regs.read(1));
+        core.inst_sext_b(&inst_sext_b_code(1, 3));
+        assert_eq!(0, core.regs.read(1));
+        core.inst_zext_h(&inst_zext_h_code(1, 3));
+        assert_eq!(0xff00, core.regs.read(1));
+        core.inst_rori(&inst_rori_code(1, 2, 16));
+        assert_eq!(0x8000_0000_00f0_0000, core.regs.read(1));
+
+        core.regs.write(2, 0x8000_0001); // rs1
+        core.regs.write(3, 33); // rs2, only the low 5 bits are used
+        core.inst_rolw(&inst_rolw_code(1, 2, 3));
+        assert_eq!(0x3, core.regs.read(1));
+        core.regs.write(2, 0x4000_0001); // rs1
+        core.inst_rolw(&inst_rolw_code(1, 2, 3));
+        assert_eq!(0xffff_ffff_8000_0002, core.regs.read(1));
+    }
+
+    #[test]
+    fn test_inst_zbc() {
+        let mut core: RVCore = RVCore::new();
+
+        core.regs.write(2, 0x8000_0000_0000_0003); // rs1
+        core.regs.write(3, 0x8000_0000_0000_0005); // rs2
+        core.inst_clmul(&inst_clmul_code(1, 2, 3));
+        assert_eq!(0xf, core.regs.read(1));
+        core.inst_clmulh(&inst_clmulh_code(1, 2, 3));
+        assert_eq!(0x4000_0000_0000_0003, core.regs.read(1));
+        core.inst_clmulr(&inst_clmulr_code(1, 2, 3));
+        assert_eq!(0x8000_0000_0000_0006, core.regs.read(1));
+    }
+
+    #[test]
+    fn test_inst_zbs() {
+        let mut core: RVCore = RVCore::new();
+
+        core.regs.write(2, 0x1); // rs1
+        core.regs.write(3, 64 + 63); // rs2, only the low 6 bits are used
+        core.inst_binv(&inst_binv_code(1, 2, 3));
+        assert_eq!(0x8000_0000_0000_0001, core.regs.read(1));
+        core.inst_bseti(&inst_bseti_code(1, 2, 33));
+        assert_eq!(0x2_0000_0001, core.regs.read(1));
+        core.inst_bexti(&inst_bexti_code(1, 2, 0));
+        assert_eq!(1, core.regs.read(1));
+        core.inst_bexti(&inst_bexti_code(1, 2, 63));
+        assert_eq!(0, core.regs.read(1));
+    }
 }
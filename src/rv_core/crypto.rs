@@ -0,0 +1,731 @@
+use crate::rv_core::inst_type::InstType;
+use crate::rv_core::RVCore;
+
+type AddressType = u64;
+
+// Substitution tables from FIPS-197 and GB/T 32907
+const AES_SBOX: [u8; 256] = [
+    0x63, 0x7c, 0x77, 0x7b, 0xf2, 0x6b, 0x6f, 0xc5, 0x30, 0x01, 0x67, 0x2b, 0xfe, 0xd7, 0xab, 0x76,
+    0xca, 0x82, 0xc9, 0x7d, 0xfa, 0x59, 0x47, 0xf0, 0xad, 0xd4, 0xa2, 0xaf, 0x9c, 0xa4, 0x72, 0xc0,
+    0xb7, 0xfd, 0x93, 0x26, 0x36, 0x3f, 0xf7, 0xcc, 0x34, 0xa5, 0xe5, 0xf1, 0x71, 0xd8, 0x31, 0x15,
+    0x04, 0xc7, 0x23, 0xc3, 0x18, 0x96, 0x05, 0x9a, 0x07, 0x12, 0x80, 0xe2, 0xeb, 0x27, 0xb2, 0x75,
+    0x09, 0x83, 0x2c, 0x1a, 0x1b, 0x6e, 0x5a, 0xa0, 0x52, 0x3b, 0xd6, 0xb3, 0x29, 0xe3, 0x2f, 0x84,
+    0x53, 0xd1, 0x00, 0xed, 0x20, 0xfc, 0xb1, 0x5b, 0x6a, 0xcb, 0xbe, 0x39, 0x4a, 0x4c, 0x58, 0xcf,
+    0xd0, 0xef, 0xaa, 0xfb, 0x43, 0x4d, 0x33, 0x85, 0x45, 0xf9, 0x02, 0x7f, 0x50, 0x3c, 0x9f, 0xa8,
+    0x51, 0xa3, 0x40, 0x8f, 0x92, 0x9d, 0x38, 0xf5, 0xbc, 0xb6, 0xda, 0x21, 0x10, 0xff, 0xf3, 0xd2,
+    0xcd, 0x0c, 0x13, 0xec, 0x5f, 0x97, 0x44, 0x17, 0xc4, 0xa7, 0x7e, 0x3d, 0x64, 0x5d, 0x19, 0x73,
+    0x60, 0x81, 0x4f, 0xdc, 0x22, 0x2a, 0x90, 0x88, 0x46, 0xee, 0xb8, 0x14, 0xde, 0x5e, 0x0b, 0xdb,
+    0xe0, 0x32, 0x3a, 0x0a, 0x49, 0x06, 0x24, 0x5c, 0xc2, 0xd3, 0xac, 0x62, 0x91, 0x95, 0xe4, 0x79,
+    0xe7, 0xc8, 0x37, 0x6d, 0x8d, 0xd5, 0x4e, 0xa9, 0x6c, 0x56, 0xf4, 0xea, 0x65, 0x7a, 0xae, 0x08,
+    0xba, 0x78, 0x25, 0x2e, 0x1c, 0xa6, 0xb4, 0xc6, 0xe8, 0xdd, 0x74, 0x1f, 0x4b, 0xbd, 0x8b, 0x8a,
+    0x70, 0x3e, 0xb5, 0x66, 0x48, 0x03, 0xf6, 0x0e, 0x61, 0x35, 0x57, 0xb9, 0x86, 0xc1, 0x1d, 0x9e,
+    0xe1, 0xf8, 0x98, 0x11, 0x69, 0xd9, 0x8e, 0x94, 0x9b, 0x1e, 0x87, 0xe9, 0xce, 0x55, 0x28, 0xdf,
+    0x8c, 0xa1, 0x89, 0x0d, 0xbf, 0xe6, 0x42, 0x68, 0x41, 0x99, 0x2d, 0x0f, 0xb0, 0x54, 0xbb, 0x16,
+];
+const AES_INV_SBOX: [u8; 256] = [
+    0x52, 0x09, 0x6a, 0xd5, 0x30, 0x36, 0xa5, 0x38, 0xbf, 0x40, 0xa3, 0x9e, 0x81, 0xf3, 0xd7, 0xfb,
+    0x7c, 0xe3, 0x39, 0x82, 0x9b, 0x2f, 0xff, 0x87, 0x34, 0x8e, 0x43, 0x44, 0xc4, 0xde, 0xe9, 0xcb,
+    0x54, 0x7b, 0x94, 0x32, 0xa6, 0xc2, 0x23, 0x3d, 0xee, 0x4c, 0x95, 0x0b, 0x42, 0xfa, 0xc3, 0x4e,
+    0x08, 0x2e, 0xa1, 0x66, 0x28, 0xd9, 0x24, 0xb2, 0x76, 0x5b, 0xa2, 0x49, 0x6d, 0x8b, 0xd1, 0x25,
+    0x72, 0xf8, 0xf6, 0x64, 0x86, 0x68, 0x98, 0x16, 0xd4, 0xa4, 0x5c, 0xcc, 0x5d, 0x65, 0xb6, 0x92,
+    0x6c, 0x70, 0x48, 0x50, 0xfd, 0xed, 0xb9, 0xda, 0x5e, 0x15, 0x46, 0x57, 0xa7, 0x8d, 0x9d, 0x84,
+    0x90, 0xd8, 0xab, 0x00, 0x8c, 0xbc, 0xd3, 0x0a, 0xf7, 0xe4, 0x58, 0x05, 0xb8, 0xb3, 0x45, 0x06,
+    0xd0, 0x2c, 0x1e, 0x8f, 0xca, 0x3f, 0x0f, 0x02, 0xc1, 0xaf, 0xbd, 0x03, 0x01, 0x13, 0x8a, 0x6b,
+    0x3a, 0x91, 0x11, 0x41, 0x4f, 0x67, 0xdc, 0xea, 0x97, 0xf2, 0xcf, 0xce, 0xf0, 0xb4, 0xe6, 0x73,
+    0x96, 0xac, 0x74, 0x22, 0xe7, 0xad, 0x35, 0x85, 0xe2, 0xf9, 0x37, 0xe8, 0x1c, 0x75, 0xdf, 0x6e,
+    0x47, 0xf1, 0x1a, 0x71, 0x1d, 0x29, 0xc5, 0x89, 0x6f, 0xb7, 0x62, 0x0e, 0xaa, 0x18, 0xbe, 0x1b,
+    0xfc, 0x56, 0x3e, 0x4b, 0xc6, 0xd2, 0x79, 0x20, 0x9a, 0xdb, 0xc0, 0xfe, 0x78, 0xcd, 0x5a, 0xf4,
+    0x1f, 0xdd, 0xa8, 0x33, 0x88, 0x07, 0xc7, 0x31, 0xb1, 0x12, 0x10, 0x59, 0x27, 0x80, 0xec, 0x5f,
+    0x60, 0x51, 0x7f, 0xa9, 0x19, 0xb5, 0x4a, 0x0d, 0x2d, 0xe5, 0x7a, 0x9f, 0x93, 0xc9, 0x9c, 0xef,
+    0xa0, 0xe0, 0x3b, 0x4d, 0xae, 0x2a, 0xf5, 0xb0, 0xc8, 0xeb, 0xbb, 0x3c, 0x83, 0x53, 0x99, 0x61,
+    0x17, 0x2b, 0x04, 0x7e, 0xba, 0x77, 0xd6, 0x26, 0xe1, 0x69, 0x14, 0x63, 0x55, 0x21, 0x0c, 0x7d,
+];
+const SM4_SBOX: [u8; 256] = [
+    0xd6, 0x90, 0xe9, 0xfe, 0xcc, 0xe1, 0x3d, 0xb7, 0x16, 0xb6, 0x14, 0xc2, 0x28, 0xfb, 0x2c, 0x05,
+    0x2b, 0x67, 0x9a, 0x76, 0x2a, 0xbe, 0x04, 0xc3, 0xaa, 0x44, 0x13, 0x26, 0x49, 0x86, 0x06, 0x99,
+    0x9c, 0x42, 0x50, 0xf4, 0x91, 0xef, 0x98, 0x7a, 0x33, 0x54, 0x0b, 0x43, 0xed, 0xcf, 0xac, 0x62,
+    0xe4, 0xb3, 0x1c, 0xa9, 0xc9, 0x08, 0xe8, 0x95, 0x80, 0xdf, 0x94, 0xfa, 0x75, 0x8f, 0x3f, 0xa6,
+    0x47, 0x07, 0xa7, 0xfc, 0xf3, 0x73, 0x17, 0xba, 0x83, 0x59, 0x3c, 0x19, 0xe6, 0x85, 0x4f, 0xa8,
+    0x68, 0x6b, 0x81, 0xb2, 0x71, 0x64, 0xda, 0x8b, 0xf8, 0xeb, 0x0f, 0x4b, 0x70, 0x56, 0x9d, 0x35,
+    0x1e, 0x24, 0x0e, 0x5e, 0x63, 0x58, 0xd1, 0xa2, 0x25, 0x22, 0x7c, 0x3b, 0x01, 0x21, 0x78, 0x87,
+    0xd4, 0x00, 0x46, 0x57, 0x9f, 0xd3, 0x27, 0x52, 0x4c, 0x36, 0x02, 0xe7, 0xa0, 0xc4, 0xc8, 0x9e,
+    0xea, 0xbf, 0x8a, 0xd2, 0x40, 0xc7, 0x38, 0xb5, 0xa3, 0xf7, 0xf2, 0xce, 0xf9, 0x61, 0x15, 0xa1,
+    0xe0, 0xae, 0x5d, 0xa4, 0x9b, 0x34, 0x1a, 0x55, 0xad, 0x93, 0x32, 0x30, 0xf5, 0x8c, 0xb1, 0xe3,
+    0x1d, 0xf6, 0xe2, 0x2e, 0x82, 0x66, 0xca, 0x60, 0xc0, 0x29, 0x23, 0xab, 0x0d, 0x53, 0x4e, 0x6f,
+    0xd5, 0xdb, 0x37, 0x45, 0xde, 0xfd, 0x8e, 0x2f, 0x03, 0xff, 0x6a, 0x72, 0x6d, 0x6c, 0x5b, 0x51,
+    0x8d, 0x1b, 0xaf, 0x92, 0xbb, 0xdd, 0xbc, 0x7f, 0x11, 0xd9, 0x5c, 0x41, 0x1f, 0x10, 0x5a, 0xd8,
+    0x0a, 0xc1, 0x31, 0x88, 0xa5, 0xcd, 0x7b, 0xbd, 0x2d, 0x74, 0xd0, 0x12, 0xb8, 0xe5, 0xb4, 0xb0,
+    0x89, 0x69, 0x97, 0x4a, 0x0c, 0x96, 0x77, 0x7e, 0x65, 0xb9, 0xf1, 0x09, 0xc5, 0x6e, 0xc6, 0x84,
+    0x18, 0xf0, 0x7d, 0xec, 0x3a, 0xdc, 0x4d, 0x20, 0x79, 0xee, 0x5f, 0x3e, 0xd7, 0xcb, 0x39, 0x48,
+];
+
+const AES_RCON: [u8; 10] = [0x01, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40, 0x80, 0x1b, 0x36];
+
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut result = 0;
+    while b != 0 {
+        if b & 1 != 0 {
+            result ^= a;
+        }
+        a = (a << 1) ^ if a & 0x80 != 0 { 0x1b } else { 0 };
+        b >>= 1;
+    }
+    result
+}
+
+fn aes_mix_column(col: u32, inv: bool) -> u32 {
+    let bytes = col.to_le_bytes();
+    let coef: [u8; 4] = if inv {
+        [0x0e, 0x0b, 0x0d, 0x09]
+    } else {
+        [0x02, 0x03, 0x01, 0x01]
+    };
+    let mut result = [0u8; 4];
+    for (row, out) in result.iter_mut().enumerate() {
+        for (i, c) in coef.iter().enumerate() {
+            *out ^= gf_mul(bytes[(row + i) % 4], *c);
+        }
+    }
+    u32::from_le_bytes(result)
+}
+
+fn aes_mix_columns(val: AddressType, inv: bool) -> AddressType {
+    let lo = aes_mix_column(val as u32, inv) as AddressType;
+    let hi = aes_mix_column((val >> 32) as u32, inv) as AddressType;
+    (hi << 32) | lo
+}
+
+// Low two columns of (Inv)ShiftRows over the state {rs2, rs1}
+fn aes_shift_rows(rs1_val: AddressType, rs2_val: AddressType, inv: bool) -> AddressType {
+    let state = (((rs2_val as u128) << 64) | rs1_val as u128).to_le_bytes();
+    let mut result = [0u8; 8];
+    for col in 0..2 {
+        for row in 0..4 {
+            let src = if inv {
+                (col + 4 - row) % 4
+            } else {
+                (col + row) % 4
+            };
+            result[col * 4 + row] = state[src * 4 + row];
+        }
+    }
+    AddressType::from_le_bytes(result)
+}
+
+fn sub_bytes(val: AddressType, sbox: &[u8; 256]) -> AddressType {
+    AddressType::from_le_bytes(val.to_le_bytes().map(|b| sbox[b as usize]))
+}
+
+impl RVCore {
+    fn crypto_operands(&self, inst: &InstType) -> (AddressType, AddressType) {
+        (
+            self.regs.read(inst.get_rs1()),
+            self.regs.read(inst.get_rs2_rtype()),
+        )
+    }
+
+    fn write_sext32(&mut self, inst: &InstType, val: u32) {
+        self.regs.write(inst.get_rd(), val as i32 as AddressType);
+    }
+
+    pub(super) fn inst_aes64es(&mut self, inst: &InstType) {
+        let (rs1_val, rs2_val) = self.crypto_operands(inst);
+        let result = sub_bytes(aes_shift_rows(rs1_val, rs2_val, false), &AES_SBOX);
+        self.regs.write(inst.get_rd(), result);
+    }
+
+    pub(super) fn inst_aes64esm(&mut self, inst: &InstType) {
+        let (rs1_val, rs2_val) = self.crypto_operands(inst);
+        let result = sub_bytes(aes_shift_rows(rs1_val, rs2_val, false), &AES_SBOX);
+        self.regs
+            .write(inst.get_rd(), aes_mix_columns(result, false));
+    }
+
+    pub(super) fn inst_aes64ds(&mut self, inst: &InstType) {
+        let (rs1_val, rs2_val) = self.crypto_operands(inst);
+        let result = sub_bytes(aes_shift_rows(rs1_val, rs2_val, true), &AES_INV_SBOX);
+        self.regs.write(inst.get_rd(), result);
+    }
+
+    pub(super) fn inst_aes64dsm(&mut self, inst: &InstType) {
+        let (rs1_val, rs2_val) = self.crypto_operands(inst);
+        let result = sub_bytes(aes_shift_rows(rs1_val, rs2_val, true), &AES_INV_SBOX);
+        self.regs
+            .write(inst.get_rd(), aes_mix_columns(result, true));
+    }
+
+    pub(super) fn inst_aes64im(&mut self, inst: &InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1());
+        self.regs
+            .write(inst.get_rd(), aes_mix_columns(rs1_val, true));
+    }
+
+    pub(super) fn inst_aes64ks1i(&mut self, inst: &InstType) {
+        let rnum = ((inst.data >> 20) & 0xf) as usize;
+        if rnum > 0xa {
+            self.raise_illegal_inst(inst);
+            return;
+        }
+        let word = (self.regs.read(inst.get_rs1()) >> 32) as u32;
+        // rnum 0xa skips the rotation and round constant for AES-256
+        let (word, rcon) = if rnum == 0xa {
+            (word, 0)
+        } else {
+            (word.rotate_right(8), AES_RCON[rnum] as u32)
+        };
+        let word = (sub_bytes(word as AddressType, &AES_SBOX) as u32 ^ rcon) as AddressType;
+        self.regs.write(inst.get_rd(), (word << 32) | word);
+    }
+
+    pub(super) fn inst_aes64ks2(&mut self, inst: &InstType) {
+        let (rs1_val, rs2_val) = self.crypto_operands(inst);
+        let w0 = (rs1_val >> 32) ^ (rs2_val & 0xffff_ffff);
+        let w1 = w0 ^ (rs2_val >> 32);
+        self.regs.write(inst.get_rd(), (w1 << 32) | w0);
+    }
+
+    pub(super) fn inst_sha256sig0(&mut self, inst: &InstType) {
+        let x = self.regs.read(inst.get_rs1()) as u32;
+        self.write_sext32(inst, x.rotate_right(7) ^ x.rotate_right(18) ^ (x >> 3));
+    }
+
+    pub(super) fn inst_sha256sig1(&mut self, inst: &InstType) {
+        let x = self.regs.read(inst.get_rs1()) as u32;
+        self.write_sext32(inst, x.rotate_right(17) ^ x.rotate_right(19) ^ (x >> 10));
+    }
+
+    pub(super) fn inst_sha256sum0(&mut self, inst: &InstType) {
+        let x = self.regs.read(inst.get_rs1()) as u32;
+        self.write_sext32(
+            inst,
+            x.rotate_right(2) ^ x.rotate_right(13) ^ x.rotate_right(22),
+        );
+    }
+
+    pub(super) fn inst_sha256sum1(&mut self, inst: &InstType) {
+        let x = self.regs.read(inst.get_rs1()) as u32;
+        self.write_sext32(
+            inst,
+            x.rotate_right(6) ^ x.rotate_right(11) ^ x.rotate_right(25),
+        );
+    }
+
+    pub(super) fn inst_sha512sig0(&mut self, inst: &InstType) {
+        let x = self.regs.read(inst.get_rs1());
+        self.regs.write(
+            inst.get_rd(),
+            x.rotate_right(1) ^ x.rotate_right(8) ^ (x >> 7),
+        );
+    }
+
+    pub(super) fn inst_sha512sig1(&mut self, inst: &InstType) {
+        let x = self.regs.read(inst.get_rs1());
+        self.regs.write(
+            inst.get_rd(),
+            x.rotate_right(19) ^ x.rotate_right(61) ^ (x >> 6),
+        );
+    }
+
+    pub(super) fn inst_sha512sum0(&mut self, inst: &InstType) {
+        let x = self.regs.read(inst.get_rs1());
+        let result = x.rotate_right(28) ^ x.rotate_right(34) ^ x.rotate_right(39);
+        self.regs.write(inst.get_rd(), result);
+    }
+
+    pub(super) fn inst_sha512sum1(&mut self, inst: &InstType) {
+        let x = self.regs.read(inst.get_rs1());
+        let result = x.rotate_right(14) ^ x.rotate_right(18) ^ x.rotate_right(41);
+        self.regs.write(inst.get_rd(), result);
+    }
+
+    pub(super) fn inst_sm3p0(&mut self, inst: &InstType) {
+        let x = self.regs.read(inst.get_rs1()) as u32;
+        self.write_sext32(inst, x ^ x.rotate_left(9) ^ x.rotate_left(17));
+    }
+
+    pub(super) fn inst_sm3p1(&mut self, inst: &InstType) {
+        let x = self.regs.read(inst.get_rs1()) as u32;
+        self.write_sext32(inst, x ^ x.rotate_left(15) ^ x.rotate_left(23));
+    }
+
+    fn sm4_sbox_byte(&self, inst: &InstType) -> (u32, u32, u32) {
+        let (rs1_val, rs2_val) = self.crypto_operands(inst);
+        let shamt = ((inst.data >> 30) & 0x3) as u32 * 8;
+        let x = SM4_SBOX[((rs2_val >> shamt) & 0xff) as usize] as u32;
+        (rs1_val as u32, x, shamt)
+    }
+
+    pub(super) fn inst_sm4ed(&mut self, inst: &InstType) {
+        let (rs1_val, x, shamt) = self.sm4_sbox_byte(inst);
+        let y = x ^ (x << 2) ^ (x << 10) ^ (x << 18) ^ (x << 24);
+        self.write_sext32(inst, rs1_val ^ y.rotate_left(shamt));
+    }
+
+    pub(super) fn inst_sm4ks(&mut self, inst: &InstType) {
+        let (rs1_val, x, shamt) = self.sm4_sbox_byte(inst);
+        let y = x ^ (x << 13) ^ (x << 23);
+        self.write_sext32(inst, rs1_val ^ y.rotate_left(shamt));
+    }
+
+    pub(super) fn inst_pack(&mut self, inst: &InstType) {
+        let (rs1_val, rs2_val) = self.crypto_operands(inst);
+        self.regs
+            .write(inst.get_rd(), (rs2_val << 32) | (rs1_val & 0xffff_ffff));
+    }
+
+    pub(super) fn inst_packh(&mut self, inst: &InstType) {
+        let (rs1_val, rs2_val) = self.crypto_operands(inst);
+        self.regs
+            .write(inst.get_rd(), ((rs2_val & 0xff) << 8) | (rs1_val & 0xff));
+    }
+
+    pub(super) fn inst_packw(&mut self, inst: &InstType) {
+        let (rs1_val, rs2_val) = self.crypto_operands(inst);
+        self.write_sext32(
+            inst,
+            (((rs2_val & 0xffff) << 16) | (rs1_val & 0xffff)) as u32,
+        );
+    }
+
+    pub(super) fn inst_brev8(&mut self, inst: &InstType) {
+        let rs1_val = self.regs.read(inst.get_rs1());
+        let result = AddressType::from_le_bytes(rs1_val.to_le_bytes().map(u8::reverse_bits));
+        self.regs.write(inst.get_rd(), result);
+    }
+
+    fn xperm(&mut self, inst: &InstType, bits: usize) {
+        let (rs1_val, rs2_val) = self.crypto_operands(inst);
+        let mask = (1 << bits) - 1;
+        let mut result = 0;
+        for i in (0..64).step_by(bits) {
+            let pos = ((rs2_val >> i) & mask) as usize * bits;
+            if pos < 64 {
+                result |= ((rs1_val >> pos) & mask) << i;
+            }
+        }
+        self.regs.write(inst.get_rd(), result);
+    }
+
+    pub(super) fn inst_xperm4(&mut self, inst: &InstType) {
+        self.xperm(inst, 4);
+    }
+
+    pub(super) fn inst_xperm8(&mut self, inst: &InstType) {
+        self.xperm(inst, 8);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rv_core::csregs;
+    use crate::rv_core::inst_info::InstID;
+    use crate::rv_core::inst_type::tests::*;
+    use std::convert::TryInto;
+
+    const SHA256_K: [u32; 64] = [
+        0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4,
+        0xab1c5ed5, 0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe,
+        0x9bdc06a7, 0xc19bf174, 0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f,
+        0x4a7484aa, 0x5cb0a9dc, 0x76f988da, 0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7,
+        0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967, 0x27b70a85, 0x2e1b2138, 0x4d2c6dfc,
+        0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85, 0xa2bfe8a1, 0xa81a664b,
+        0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070, 0x19a4c116,
+        0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+        0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7,
+        0xc67178f2,
+    ];
+    const SHA512_K: [u64; 80] = [
+        0x428a2f98d728ae22,
+        0x7137449123ef65cd,
+        0xb5c0fbcfec4d3b2f,
+        0xe9b5dba58189dbbc,
+        0x3956c25bf348b538,
+        0x59f111f1b605d019,
+        0x923f82a4af194f9b,
+        0xab1c5ed5da6d8118,
+        0xd807aa98a3030242,
+        0x12835b0145706fbe,
+        0x243185be4ee4b28c,
+        0x550c7dc3d5ffb4e2,
+        0x72be5d74f27b896f,
+        0x80deb1fe3b1696b1,
+        0x9bdc06a725c71235,
+        0xc19bf174cf692694,
+        0xe49b69c19ef14ad2,
+        0xefbe4786384f25e3,
+        0x0fc19dc68b8cd5b5,
+        0x240ca1cc77ac9c65,
+        0x2de92c6f592b0275,
+        0x4a7484aa6ea6e483,
+        0x5cb0a9dcbd41fbd4,
+        0x76f988da831153b5,
+        0x983e5152ee66dfab,
+        0xa831c66d2db43210,
+        0xb00327c898fb213f,
+        0xbf597fc7beef0ee4,
+        0xc6e00bf33da88fc2,
+        0xd5a79147930aa725,
+        0x06ca6351e003826f,
+        0x142929670a0e6e70,
+        0x27b70a8546d22ffc,
+        0x2e1b21385c26c926,
+        0x4d2c6dfc5ac42aed,
+        0x53380d139d95b3df,
+        0x650a73548baf63de,
+        0x766a0abb3c77b2a8,
+        0x81c2c92e47edaee6,
+        0x92722c851482353b,
+        0xa2bfe8a14cf10364,
+        0xa81a664bbc423001,
+        0xc24b8b70d0f89791,
+        0xc76c51a30654be30,
+        0xd192e819d6ef5218,
+        0xd69906245565a910,
+        0xf40e35855771202a,
+        0x106aa07032bbd1b8,
+        0x19a4c116b8d2d0c8,
+        0x1e376c085141ab53,
+        0x2748774cdf8eeb99,
+        0x34b0bcb5e19b48a8,
+        0x391c0cb3c5c95a63,
+        0x4ed8aa4ae3418acb,
+        0x5b9cca4f7763e373,
+        0x682e6ff3d6b2b8a3,
+        0x748f82ee5defb2fc,
+        0x78a5636f43172f60,
+        0x84c87814a1f0ab72,
+        0x8cc702081a6439ec,
+        0x90befffa23631e28,
+        0xa4506cebde82bde9,
+        0xbef9a3f7b2c67915,
+        0xc67178f2e372532b,
+        0xca273eceea26619c,
+        0xd186b8c721c0c207,
+        0xeada7dd6cde0eb1e,
+        0xf57d4f7fee6ed178,
+        0x06f067aa72176fba,
+        0x0a637dc5a2c898a6,
+        0x113f9804bef90dae,
+        0x1b710b35131c471b,
+        0x28db77f523047d84,
+        0x32caab7b40c72493,
+        0x3c9ebe0a15c9bebc,
+        0x431d67c49c100d4c,
+        0x4cc5d4becb3e42b6,
+        0x597f299cfc657e2a,
+        0x5fcb6fab3ad6faec,
+        0x6c44198c4a475817,
+    ];
+
+    fn exec(
+        core: &mut RVCore,
+        inst: &InstType,
+        rs1_val: AddressType,
+        rs2_val: AddressType,
+    ) -> AddressType {
+        core.regs.write(1, rs1_val);
+        core.regs.write(2, rs2_val);
+        core.execute(inst);
+        core.regs.read(3)
+    }
+
+    fn op(id: InstID, funct7: AddressType, funct3: AddressType) -> InstType {
+        inst_op_code(id, 0x33, funct7, funct3, 3, 1, 2)
+    }
+
+    fn unary(id: InstID, imm: AddressType) -> InstType {
+        inst_op_imm_code(id, imm, 1, 3, 1)
+    }
+
+    fn to_words(bytes: &[u8]) -> Vec<u32> {
+        bytes
+            .chunks(4)
+            .map(|c| u32::from_be_bytes(c.try_into().unwrap()))
+            .collect()
+    }
+
+    // Single padded block holding "abc"
+    fn abc_block(len: usize) -> Vec<u8> {
+        let mut block = vec![0u8; len];
+        block[..3].copy_from_slice(b"abc");
+        block[3] = 0x80;
+        block[len - 1] = 24;
+        block
+    }
+
+    #[test]
+    fn test_inst_aes64() {
+        let mut core = RVCore::new();
+        // FIPS-197 appendix C.1
+        let key = 0x0f0e0d0c0b0a0908_0706050403020100u128;
+        let plaintext = 0xffeeddccbbaa9988_7766554433221100u128;
+        let ciphertext = 0x5ac5b47080b7cdd8_30047b6ad8e0c469u128;
+
+        let mut rk = vec![(key as AddressType, (key >> 64) as AddressType)];
+        for rnum in 0..10 {
+            let (lo, hi) = rk[rnum];
+            let tmp = exec(
+                &mut core,
+                &unary(InstID::AES64KS1I, 0x310 | rnum as AddressType),
+                hi,
+                0,
+            );
+            let lo = exec(&mut core, &op(InstID::AES64KS2, 0x3f, 0), tmp, lo);
+            let hi = exec(&mut core, &op(InstID::AES64KS2, 0x3f, 0), lo, hi);
+            rk.push((lo, hi));
+        }
+        assert_eq!((0x174a94e3_7f1d1113, 0xc5302b4d_8ba707f3), rk[10]);
+
+        let mut s0 = plaintext as AddressType ^ rk[0].0;
+        let mut s1 = (plaintext >> 64) as AddressType ^ rk[0].1;
+        for (round, (k0, k1)) in rk.iter().enumerate().skip(1) {
+            let inst = if round == 10 {
+                op(InstID::AES64ES, 0x19, 0)
+            } else {
+                op(InstID::AES64ESM, 0x1b, 0)
+            };
+            let n0 = exec(&mut core, &inst, s0, s1);
+            let n1 = exec(&mut core, &inst, s1, s0);
+            s0 = n0 ^ k0;
+            s1 = n1 ^ k1;
+        }
+        assert_eq!(ciphertext, ((s1 as u128) << 64) | s0 as u128);
+
+        s0 ^= rk[10].0;
+        s1 ^= rk[10].1;
+        for round in (0..10).rev() {
+            let (mut k0, mut k1) = rk[round];
+            let inst = if round == 0 {
+                op(InstID::AES64DS, 0x1d, 0)
+            } else {
+                k0 = exec(&mut core, &unary(InstID::AES64IM, 0x300), k0, 0);
+                k1 = exec(&mut core, &unary(InstID::AES64IM, 0x300), k1, 0);
+                op(InstID::AES64DSM, 0x1f, 0)
+            };
+            let n0 = exec(&mut core, &inst, s0, s1);
+            let n1 = exec(&mut core, &inst, s1, s0);
+            s0 = n0 ^ k0;
+            s1 = n1 ^ k1;
+        }
+        assert_eq!(plaintext, ((s1 as u128) << 64) | s0 as u128);
+
+        // rnum above 0xa is reserved
+        exec(&mut core, &unary(InstID::AES64KS1I, 0x31b), 0, 0);
+        assert_eq!(2, core.csregs.read(csregs::MCAUSE));
+    }
+
+    #[test]
+    fn test_inst_sha256() {
+        let mut core = RVCore::new();
+        let mut sig = |id, imm, x: u32| {
+            let result = exec(&mut core, &unary(id, imm), x as AddressType, 0);
+            assert_eq!(result, result as u32 as i32 as AddressType);
+            result as u32
+        };
+        let mut w = to_words(&abc_block(64));
+        for t in 16..64 {
+            let s0 = sig(InstID::SHA256SIG0, 0x102, w[t - 15]);
+            let s1 = sig(InstID::SHA256SIG1, 0x103, w[t - 2]);
+            w.push(
+                s1.wrapping_add(w[t - 7])
+                    .wrapping_add(s0)
+                    .wrapping_add(w[t - 16]),
+            );
+        }
+        let init: [u32; 8] = [
+            0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+            0x5be0cd19,
+        ];
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = init;
+        for t in 0..64 {
+            let ch = (e & f) ^ (!e & g);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let t1 = h
+                .wrapping_add(sig(InstID::SHA256SUM1, 0x101, e))
+                .wrapping_add(ch)
+                .wrapping_add(SHA256_K[t])
+                .wrapping_add(w[t]);
+            let t2 = sig(InstID::SHA256SUM0, 0x100, a).wrapping_add(maj);
+            (h, g, f, e) = (g, f, e, d.wrapping_add(t1));
+            (d, c, b, a) = (c, b, a, t1.wrapping_add(t2));
+        }
+        let digest: Vec<u32> = [a, b, c, d, e, f, g, h]
+            .iter()
+            .zip(init)
+            .map(|(x, y)| x.wrapping_add(y))
+            .collect();
+        let expected = [
+            0xba7816bf, 0x8f01cfea, 0x414140de, 0x5dae2223, 0xb00361a3, 0x96177a9c, 0xb410ff61,
+            0xf20015ad,
+        ];
+        assert_eq!(expected.to_vec(), digest);
+    }
+
+    #[test]
+    fn test_inst_sha512() {
+        let mut core = RVCore::new();
+        let mut sig = |id, imm, x| exec(&mut core, &unary(id, imm), x, 0);
+        let block = abc_block(128);
+        let mut w: Vec<u64> = block
+            .chunks(8)
+            .map(|c| u64::from_be_bytes(c.try_into().unwrap()))
+            .collect();
+        for t in 16..80 {
+            let s0 = sig(InstID::SHA512SIG0, 0x106, w[t - 15]);
+            let s1 = sig(InstID::SHA512SIG1, 0x107, w[t - 2]);
+            w.push(
+                s1.wrapping_add(w[t - 7])
+                    .wrapping_add(s0)
+                    .wrapping_add(w[t - 16]),
+            );
+        }
+        let init: [u64; 8] = [
+            0x6a09e667f3bcc908,
+            0xbb67ae8584caa73b,
+            0x3c6ef372fe94f82b,
+            0xa54ff53a5f1d36f1,
+            0x510e527fade682d1,
+            0x9b05688c2b3e6c1f,
+            0x1f83d9abfb41bd6b,
+            0x5be0cd19137e2179,
+        ];
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = init;
+        for t in 0..80 {
+            let ch = (e & f) ^ (!e & g);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let t1 = h
+                .wrapping_add(sig(InstID::SHA512SUM1, 0x105, e))
+                .wrapping_add(ch)
+                .wrapping_add(SHA512_K[t])
+                .wrapping_add(w[t]);
+            let t2 = sig(InstID::SHA512SUM0, 0x104, a).wrapping_add(maj);
+            (h, g, f, e) = (g, f, e, d.wrapping_add(t1));
+            (d, c, b, a) = (c, b, a, t1.wrapping_add(t2));
+        }
+        let digest: Vec<u64> = [a, b, c, d, e, f, g, h]
+            .iter()
+            .zip(init)
+            .map(|(x, y)| x.wrapping_add(y))
+            .collect();
+        let expected = [
+            0xddaf35a193617aba,
+            0xcc417349ae204131,
+            0x12e6fa4e89a97ea2,
+            0x0a9eeee64b55d39a,
+            0x2192992a274fc1a8,
+            0x36ba3c23a3feebbd,
+            0x454d4423643ce80e,
+            0x2a9ac94fa54ca49f,
+        ];
+        assert_eq!(expected.to_vec(), digest);
+    }
+
+    #[test]
+    fn test_inst_sm3() {
+        let mut core = RVCore::new();
+        let mut p = |id, imm, x: u32| exec(&mut core, &unary(id, imm), x as AddressType, 0) as u32;
+        let mut w = to_words(&abc_block(64));
+        for j in 16..68 {
+            let x = w[j - 16] ^ w[j - 9] ^ w[j - 3].rotate_left(15);
+            w.push(p(InstID::SM3P1, 0x109, x) ^ w[j - 13].rotate_left(7) ^ w[j - 6]);
+        }
+        let init: [u32; 8] = [
+            0x7380166f, 0x4914b2b9, 0x172442d7, 0xda8a0600, 0xa96f30bc, 0x163138aa, 0xe38dee4d,
+            0xb0fb0e4e,
+        ];
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = init;
+        for j in 0..64 {
+            let (t, ff, gg) = if j < 16 {
+                (0x79cc4519u32, a ^ b ^ c, e ^ f ^ g)
+            } else {
+                (0x7a879d8a, (a & b) | (a & c) | (b & c), (e & f) | (!e & g))
+            };
+            let ss1 = a
+                .rotate_left(12)
+                .wrapping_add(e)
+                .wrapping_add(t.rotate_left(j as u32 % 32))
+                .rotate_left(7);
+            let ss2 = ss1 ^ a.rotate_left(12);
+            let tt1 = ff
+                .wrapping_add(d)
+                .wrapping_add(ss2)
+                .wrapping_add(w[j] ^ w[j + 4]);
+            let tt2 = gg.wrapping_add(h).wrapping_add(ss1).wrapping_add(w[j]);
+            (d, c, b, a) = (c, b.rotate_left(9), a, tt1);
+            (h, g, f, e) = (g, f.rotate_left(19), e, p(InstID::SM3P0, 0x108, tt2));
+        }
+        let digest: Vec<u32> = [a, b, c, d, e, f, g, h]
+            .iter()
+            .zip(init)
+            .map(|(x, y)| x ^ y)
+            .collect();
+        let expected = [
+            0x66c7f0f4, 0x62eeedd9, 0xd1f2d46b, 0xdc10e4e2, 0x4167c487, 0x5cf2f7a2, 0x297da02b,
+            0x8f4ba8e0,
+        ];
+        assert_eq!(expected.to_vec(), digest);
+    }
+
+    #[test]
+    fn test_inst_sm4() {
+        let mut core = RVCore::new();
+        // GB/T 32907 appendix A.1
+        let block = [0x01234567, 0x89abcdef, 0xfedcba98, 0x76543210];
+        let fk = [0xa3b1bac6, 0x56aa3350, 0x677d9197, 0xb27022dc];
+        let mut round = |id, funct7, acc: u32, x: u32| {
+            (0..4).fold(acc, |acc, bs| {
+                let inst = op(id, ((bs as AddressType) << 5) | funct7, 0);
+                exec(&mut core, &inst, acc as AddressType, x as AddressType) as u32
+            })
+        };
+
+        let mut k: Vec<u32> = block.iter().zip(fk).map(|(m, f)| m ^ f).collect();
+        for i in 0..32 {
+            let ck = u32::from_be_bytes([0, 1, 2, 3].map(|j| ((4 * i + j) * 7) as u8));
+            let x = k[i + 1] ^ k[i + 2] ^ k[i + 3] ^ ck;
+            k.push(round(InstID::SM4KS, 0x1a, k[i], x));
+        }
+        let mut s = block.to_vec();
+        for i in 0..32 {
+            let x = s[i + 1] ^ s[i + 2] ^ s[i + 3] ^ k[i + 4];
+            s.push(round(InstID::SM4ED, 0x18, s[i], x));
+        }
+        assert_eq!(
+            vec![0x681edf34, 0xd206965e, 0x86b3e94f, 0x536e4246],
+            vec![s[35], s[34], s[33], s[32]]
+        );
+    }
+
+    #[test]
+    fn test_inst_zbkb_zbkx() {
+        let mut core = RVCore::new();
+        let rs1 = 0x0123_4567_89ab_cdef;
+        let rs2 = 0xfedc_ba98_7654_3210;
+        assert_eq!(
+            0x7654_3210_89ab_cdef,
+            exec(&mut core, &op(InstID::PACK, 0x4, 4), rs1, rs2)
+        );
+        assert_eq!(
+            0x10ef,
+            exec(&mut core, &op(InstID::PACKH, 0x4, 7), rs1, rs2)
+        );
+        let packw = inst_op_code(InstID::PACKW, 0x3b, 0x4, 4, 3, 1, 2);
+        assert_eq!(0x3210_cdef, exec(&mut core, &packw, rs1, rs2));
+        assert_eq!(0xffff_ffff_cdef_3210, exec(&mut core, &packw, rs2, rs1));
+        let brev8 = inst_op_imm_code(InstID::BREV8, 0x687, 5, 3, 1);
+        assert_eq!(0x80c4_a2e6_91d5_b3f7, exec(&mut core, &brev8, rs1, 0));
+
+        // Out of range indices select zero
+        let xperm8 = op(InstID::XPERM8, 0x14, 4);
+        assert_eq!(
+            0x0000_4523_0100_efef,
+            exec(&mut core, &xperm8, rs1, 0x0809_0506_0708_0000)
+        );
+        let xperm4 = op(InstID::XPERM4, 0x14, 2);
+        assert_eq!(
+            0xfedc_ba98_7654_3210,
+            exec(&mut core, &xperm4, rs1, 0x0123_4567_89ab_cdef)
+        );
+    }
+}
@@ -1,3 +1,9 @@
+use crate::aia::Aia;
+use crate::clic::Clic;
+use crate::rv_core::isa::{Extension, Isa};
+use crate::rv_core::triggers::Triggers;
+use std::cell::RefCell;
+use std::rc::Rc;
 type AddressType = u64;
 
 pub const FFLAGS: AddressType = 0x1;
@@ -5,33 +11,426 @@ pub const FFLAGS_RW_MASK: AddressType = 0x1f;
 pub const FRM: AddressType = 0x2;
 pub const FCSR: AddressType = 0x3;
 pub const FCSR_RW_MASK: AddressType = 0xff;
+pub const VSTART: AddressType = 0x8;
+pub const VXSAT: AddressType = 0x9;
+pub const VXRM: AddressType = 0xa;
+pub const VCSR: AddressType = 0xf;
+pub const SEED: AddressType = 0x15;
+pub const SEED_OPST_BIST: AddressType = 0;
+pub const SEED_OPST_WAIT: AddressType = 1 << 30;
+pub const SEED_OPST_ES16: AddressType = 2 << 30;
+pub const JVT: AddressType = 0x17;
+pub const JVT_MODE: AddressType = 0x3f;
+pub const SSTATUS: AddressType = 0x100;
+pub const SSTATUS_MASK: AddressType = 0x8000_0003_010d_e762;
+pub const SIE: AddressType = 0x104;
+pub const STVEC: AddressType = 0x105;
+pub const SCOUNTEREN: AddressType = 0x106;
+pub const SENVCFG: AddressType = 0x10a;
+pub const SEPC: AddressType = 0x141;
+pub const SCAUSE: AddressType = 0x142;
+pub const STVAL: AddressType = 0x143;
+pub const SIP: AddressType = 0x144;
+pub const STIMECMP: AddressType = 0x14d;
+pub const SISELECT: AddressType = 0x150;
+pub const SIREG: AddressType = 0x151;
+pub const STOPEI: AddressType = 0x15c;
+pub const SATP: AddressType = 0x180;
+pub const SATP_MODE: AddressType = 0xf << 60;
+pub const SATP_MODE_SV39: AddressType = 8 << 60;
+pub const SATP_PPN: AddressType = (1 << 44) - 1;
+pub const VSSTATUS: AddressType = 0x200;
+pub const VSIE: AddressType = 0x204;
+pub const VSTVEC: AddressType = 0x205;
+pub const VSEPC: AddressType = 0x241;
+pub const VSCAUSE: AddressType = 0x242;
+pub const VSTVAL: AddressType = 0x243;
+pub const VSIP: AddressType = 0x244;
+pub const VSTIMECMP: AddressType = 0x24d;
+pub const VSATP: AddressType = 0x280;
 pub const MSTATUS: AddressType = 0x300;
 pub const MISA: AddressType = 0x301;
-pub const MISA_A: AddressType = 1 << 0;
 pub const MISA_C: AddressType = 1 << 2;
-pub const MISA_M: AddressType = 1 << 12;
-pub const MISA_I: AddressType = 1 << 8;
-pub const MISA_S: AddressType = 1 << 18;
+pub const MISA_D: AddressType = 1 << 3;
+pub const MISA_F: AddressType = 1 << 5;
+pub const MSTATUS_SIE: AddressType = 1 << 1;
+pub const MSTATUS_MIE: AddressType = 1 << 3;
+pub const MSTATUS_SPIE: AddressType = 1 << 5;
+pub const MSTATUS_MPIE: AddressType = 1 << 7;
+pub const MSTATUS_SPP: AddressType = 1 << 8;
+pub const MSTATUS_MPP: AddressType = 3 << 11;
+pub const MSTATUS_FS: AddressType = 3 << 13;
+pub const MSTATUS_MPRV: AddressType = 1 << 17;
+pub const MSTATUS_SUM: AddressType = 1 << 18;
+pub const MSTATUS_MXR: AddressType = 1 << 19;
+pub const MSTATUS_TVM: AddressType = 1 << 20;
+pub const MSTATUS_TW: AddressType = 1 << 21;
+pub const MSTATUS_TSR: AddressType = 1 << 22;
+pub const MSTATUS_SDT: AddressType = 1 << 24;
+pub const MSTATUS_GVA: AddressType = 1 << 38;
+pub const MSTATUS_MPV: AddressType = 1 << 39;
+pub const MSTATUS_MDT: AddressType = 1 << 42;
+pub const MEDELEG: AddressType = 0x302;
+pub const MIDELEG: AddressType = 0x303;
+pub const MIE: AddressType = 0x304;
 pub const MTVEC: AddressType = 0x305;
+// MODE of mtvec/stvec/vstvec, Direct is 0
+pub const TVEC_MODE: AddressType = 3;
+pub const TVEC_MODE_VECTORED: AddressType = 1;
+pub const MTVEC_MODE_CLIC: AddressType = 3;
+// The CLIC mode base is 64-byte aligned, the submode bits are zero
+pub const MTVEC_CLIC_BASE: AddressType = !0x3f;
+pub const MCOUNTEREN: AddressType = 0x306;
+pub const MTVT: AddressType = 0x307;
+pub const COUNTEREN_TM: AddressType = 1 << 1;
+pub const MVIEN: AddressType = 0x308;
+pub const MVIP: AddressType = 0x309;
+pub const MENVCFG: AddressType = 0x30a;
+pub const ENVCFG_FIOM: AddressType = 1 << 0;
+pub const ENVCFG_CBIE: AddressType = 3 << 4;
+pub const ENVCFG_CBCFE: AddressType = 1 << 6;
+pub const ENVCFG_CBZE: AddressType = 1 << 7;
+pub const ENVCFG_DTE: AddressType = 1 << 59;
+pub const ENVCFG_STCE: AddressType = 1 << 63;
 pub const MEPC: AddressType = 0x341;
 pub const MCAUSE: AddressType = 0x342;
+// CLIC mode fields, mpp and mpie are aliases of the mstatus ones
+pub const MCAUSE_MINHV: AddressType = 1 << 30;
+pub const MCAUSE_MPP: AddressType = 3 << 28;
+pub const MCAUSE_MPIE: AddressType = 1 << 27;
+pub const MCAUSE_MPIL_SHIFT: AddressType = 16;
+pub const MCAUSE_MPIL: AddressType = 0xff << MCAUSE_MPIL_SHIFT;
+pub const MCAUSE_EXCCODE: AddressType = 0xfff;
+pub const MTVAL: AddressType = 0x343;
+pub const MIP: AddressType = 0x344;
+pub const MNXTI: AddressType = 0x345;
+pub const MINTTHRESH: AddressType = 0x347;
+pub const MIP_SSIP: AddressType = 1 << 1;
+pub const MIP_VSSIP: AddressType = 1 << 2;
+pub const MIP_STIP: AddressType = 1 << 5;
+pub const MIP_VSTIP: AddressType = 1 << 6;
+pub const MIP_SEIP: AddressType = 1 << 9;
+pub const MIP_MEIP: AddressType = 1 << 11;
+pub const MISELECT: AddressType = 0x350;
+pub const MIREG: AddressType = 0x351;
+pub const MTOPEI: AddressType = 0x35c;
+pub const MTINST: AddressType = 0x34a;
+pub const MTVAL2: AddressType = 0x34b;
+pub const HSTATUS: AddressType = 0x600;
+pub const HSTATUS_GVA: AddressType = 1 << 6;
+pub const HSTATUS_SPV: AddressType = 1 << 7;
+pub const HSTATUS_SPVP: AddressType = 1 << 8;
+pub const HSTATUS_HU: AddressType = 1 << 9;
+pub const HSTATUS_VTVM: AddressType = 1 << 20;
+pub const HSTATUS_VTW: AddressType = 1 << 21;
+pub const HSTATUS_VTSR: AddressType = 1 << 22;
+pub const HSTATUS_VSXL_64: AddressType = 2 << 32;
+pub const HEDELEG: AddressType = 0x602;
+pub const HIDELEG: AddressType = 0x603;
+pub const HIE: AddressType = 0x604;
+pub const HTIMEDELTA: AddressType = 0x605;
+pub const HCOUNTEREN: AddressType = 0x606;
+pub const HENVCFG: AddressType = 0x60a;
+pub const HTVAL: AddressType = 0x643;
+pub const HIP: AddressType = 0x644;
+pub const HVIP: AddressType = 0x645;
+pub const HTINST: AddressType = 0x64a;
+pub const HGATP: AddressType = 0x680;
+pub const MNSCRATCH: AddressType = 0x740;
+pub const MNEPC: AddressType = 0x741;
+pub const MNCAUSE: AddressType = 0x742;
+pub const MNSTATUS: AddressType = 0x744;
+pub const MNSTATUS_NMIE: AddressType = 1 << 3;
+pub const MNSTATUS_MNPV: AddressType = 1 << 7;
+pub const MNSTATUS_MNPP: AddressType = 3 << 11;
+pub const MSECCFG: AddressType = 0x747;
+pub const MSECCFG_USEED: AddressType = 1 << 8;
+pub const MSECCFG_SSEED: AddressType = 1 << 9;
+pub const TSELECT: AddressType = 0x7a0;
+pub const TDATA1: AddressType = 0x7a1;
+pub const TDATA2: AddressType = 0x7a2;
+pub const TDATA3: AddressType = 0x7a3;
+pub const TINFO: AddressType = 0x7a4;
+pub const DCSR: AddressType = 0x7b0;
+pub const DCSR_DEBUGVER: AddressType = 4 << 28;
+pub const DCSR_EBREAKVS: AddressType = 1 << 17;
+pub const DCSR_EBREAKVU: AddressType = 1 << 16;
+pub const DCSR_EBREAKM: AddressType = 1 << 15;
+pub const DCSR_EBREAKS: AddressType = 1 << 13;
+pub const DCSR_EBREAKU: AddressType = 1 << 12;
+pub const DCSR_STEPIE: AddressType = 1 << 11;
+pub const DCSR_STOPCOUNT: AddressType = 1 << 10;
+pub const DCSR_STOPTIME: AddressType = 1 << 9;
+pub const DCSR_CAUSE_SHIFT: u32 = 6;
+pub const DCSR_CAUSE: AddressType = 7 << DCSR_CAUSE_SHIFT;
+pub const DCSR_CAUSE_EBREAK: AddressType = 1;
+pub const DCSR_CAUSE_TRIGGER: AddressType = 2;
+pub const DCSR_CAUSE_HALTREQ: AddressType = 3;
+pub const DCSR_CAUSE_STEP: AddressType = 4;
+pub const DCSR_V: AddressType = 1 << 5;
+pub const DCSR_MPRVEN: AddressType = 1 << 4;
+pub const DCSR_STEP: AddressType = 1 << 2;
+pub const DCSR_PRV: AddressType = 3;
+pub const DPC: AddressType = 0x7b1;
+pub const DSCRATCH0: AddressType = 0x7b2;
+pub const DSCRATCH1: AddressType = 0x7b3;
 pub const MCYCLE: AddressType = 0xb00;
 pub const MINSTRET: AddressType = 0xb02;
+pub const TIME: AddressType = 0xc01;
+pub const VL: AddressType = 0xc20;
+pub const VTYPE: AddressType = 0xc21;
+pub const VLENB: AddressType = 0xc22;
+pub const VTYPE_VILL: AddressType = 1 << 63;
+pub const STOPI: AddressType = 0xdb0;
+pub const MTOPI: AddressType = 0xfb0;
+pub const MINTSTATUS: AddressType = 0xfb1;
+pub const MINTSTATUS_MIL_SHIFT: AddressType = 24;
+pub const CAUSE_INTERRUPT: AddressType = 1 << 63;
 pub const EXC_FETCH_ACCESS: AddressType = 1;
+pub const EXC_ILLEGAL_INST: AddressType = 2;
+pub const EXC_BREAKPOINT: AddressType = 3;
 pub const EXC_LOAD_ACCESS: AddressType = 5;
 pub const EXC_ECALL_FROM_U: AddressType = 8;
 pub const EXC_ECALL_FROM_S: AddressType = 9;
+pub const EXC_ECALL_FROM_VS: AddressType = 10;
 pub const EXC_ECALL_FROM_M: AddressType = 11;
+pub const EXC_FETCH_PAGE_FAULT: AddressType = 12;
+pub const EXC_LOAD_PAGE_FAULT: AddressType = 13;
+pub const EXC_STORE_PAGE_FAULT: AddressType = 15;
+pub const EXC_DOUBLE_TRAP: AddressType = 16;
+pub const EXC_FETCH_GUEST_PAGE_FAULT: AddressType = 20;
+pub const EXC_LOAD_GUEST_PAGE_FAULT: AddressType = 21;
+pub const EXC_VIRTUAL_INST: AddressType = 22;
+pub const EXC_STORE_GUEST_PAGE_FAULT: AddressType = 23;
+pub const DEFAULT_ENTROPY_SEED: u64 = 0x5eed;
+// Exceptions that can be delegated, ecall from M-mode always stays in M-mode
+const MEDELEG_WMASK: AddressType = 0xf0_b7ff;
+// ecall from VS/M-mode and guest-page/virtual-instruction faults stay in HS-mode
+const HEDELEG_WMASK: AddressType = 0xb1ff;
+// SSIP, STIP and SEIP
+const S_INTERRUPTS: AddressType = 0x222;
+// MSIP, MTIP and MEIP
+const M_INTERRUPTS: AddressType = 0x888;
+// VSSIP, VSTIP and VSEIP
+const VS_INTERRUPTS: AddressType = 0x444;
+// SGEIP, and the VS-level interrupts make up hip/hie
+const H_INTERRUPTS: AddressType = 0x1444;
+// Default priority order of the major interrupts: MEI, MSI, MTI, SEI, SSI, STI, SGEI,
+// VSEI, VSSI and VSTI
+const INTERRUPT_PRIORITY: [AddressType; 10] = [11, 3, 7, 9, 1, 5, 12, 10, 2, 6];
+// Polls answered with BIST after reset before entropy is delivered
+const SEED_BIST_POLLS: u64 = 2;
 
 pub struct CSRegisters {
     reg_bank: [AddressType; 4096],
+    misa_wmask: AddressType,
+    mstatus_wmask: AddressType,
+    has_h: bool,
+    has_sstc: bool,
+    has_aia: bool,
+    aia: Rc<RefCell<Aia>>,
+    has_clic: bool,
+    clic: Rc<RefCell<Clic>>,
+    has_ssdbltrp: bool,
+    triggers: Triggers,
+    entropy_state: u64,
+    seed_polls: u64,
 }
 
 impl CSRegisters {
     pub fn new() -> CSRegisters {
-        CSRegisters {
+        let mut csregs = CSRegisters {
             reg_bank: [0; 4096],
+            misa_wmask: 0,
+            mstatus_wmask: 0,
+            has_h: false,
+            has_sstc: false,
+            has_aia: false,
+            aia: Rc::new(RefCell::new(Aia::new())),
+            has_clic: false,
+            clic: Rc::new(RefCell::new(Clic::new())),
+            has_ssdbltrp: false,
+            triggers: Triggers::new(),
+            entropy_state: DEFAULT_ENTROPY_SEED,
+            seed_polls: 0,
+        };
+        csregs.set_isa(&Isa::default());
+        csregs.reg_bank[VTYPE as usize] = VTYPE_VILL;
+        csregs.reg_bank[HSTATUS as usize] = HSTATUS_VSXL_64;
+        // Counters and timers never advance in debug mode
+        csregs.reg_bank[DCSR as usize] = DCSR_DEBUGVER | DCSR_STOPCOUNT | DCSR_STOPTIME | 3;
+        csregs
+    }
+
+    pub fn set_isa(&mut self, isa: &Isa) {
+        self.reg_bank[MISA as usize] = isa.misa();
+        self.misa_wmask = isa.misa_writable_mask();
+        // Without the f registers (e.g. Zfinx) there is no FP state to track
+        self.mstatus_wmask = if isa.has(Extension::F) {
+            AddressType::MAX
+        } else {
+            !MSTATUS_FS
+        };
+        if !isa.has(Extension::Smdbltrp) {
+            self.mstatus_wmask &= !MSTATUS_MDT;
+        }
+        if !isa.has(Extension::Ssdbltrp) {
+            self.mstatus_wmask &= !MSTATUS_SDT;
+        }
+        self.reg_bank[MSTATUS as usize] &= self.mstatus_wmask;
+        self.has_h = isa.has(Extension::H);
+        self.has_sstc = isa.has(Extension::Sstc);
+        self.has_aia = isa.has(Extension::Smaia);
+        self.has_clic = isa.has(Extension::Smclic);
+        self.has_ssdbltrp = isa.has(Extension::Ssdbltrp);
+        self.triggers.set_has_h(self.has_h);
+    }
+
+    pub fn aia(&self) -> Rc<RefCell<Aia>> {
+        self.aia.clone()
+    }
+
+    pub fn clic(&self) -> Rc<RefCell<Clic>> {
+        self.clic.clone()
+    }
+
+    pub fn triggers(&mut self) -> &mut Triggers {
+        &mut self.triggers
+    }
+
+    // The cause and mode of a debug mode entry, read-only for software
+    pub fn set_debug_cause(&mut self, cause: AddressType, prv: AddressType, virt: bool) {
+        let mut dcsr = self.reg_bank[DCSR as usize] & !(DCSR_CAUSE | DCSR_V | DCSR_PRV);
+        dcsr |= (cause << DCSR_CAUSE_SHIFT) | prv;
+        if virt {
+            dcsr |= DCSR_V;
+        }
+        self.reg_bank[DCSR as usize] = dcsr;
+    }
+
+    // mtvec.MODE=3 hands interrupts over to the CLIC, mip/mie are then unused
+    pub fn clic_mode(&self) -> bool {
+        self.has_clic && self.reg_bank[MTVEC as usize] & TVEC_MODE == MTVEC_MODE_CLIC
+    }
+
+    // SDT is read-only zero unless enabled by DTE of menvcfg (henvcfg for vsstatus)
+    fn sdt_mask(&self, envcfg: AddressType) -> AddressType {
+        if self.read(envcfg) & ENVCFG_DTE != 0 {
+            AddressType::MAX
+        } else {
+            !MSTATUS_SDT
+        }
+    }
+
+    // MDT and SDT being set mask M-mode and S-mode interrupts, MIE and SIE are cleared
+    fn double_trap_status(status: AddressType) -> AddressType {
+        let mut new_status = status;
+        if status & MSTATUS_MDT != 0 {
+            new_status &= !MSTATUS_MIE;
+        }
+        if status & MSTATUS_SDT != 0 {
+            new_status &= !MSTATUS_SIE;
         }
+        new_status
+    }
+
+    // The local interrupts of mip are the CLIC inputs 0..=15
+    pub fn update_clic(&mut self) {
+        let mip = self.pending_interrupts();
+        let mut clic = self.clic.borrow_mut();
+        for id in 0..16 {
+            clic.set_input(id, (mip >> id) & 1 != 0);
+        }
+    }
+
+    // mintstatus is read-only, only trap entry, mret and mnxti change mil
+    pub fn set_interrupt_level(&mut self, level: AddressType) {
+        self.reg_bank[MINTSTATUS as usize] = (level & 0xff) << MINTSTATUS_MIL_SHIFT;
+    }
+
+    // Software can only set mnstatus.NMIE, the hart clears it on reset and NMI entry
+    pub fn set_nmie(&mut self, enabled: bool) {
+        self.reg_bank[MNSTATUS as usize] &= !MNSTATUS_NMIE;
+        if enabled {
+            self.reg_bank[MNSTATUS as usize] |= MNSTATUS_NMIE;
+        }
+    }
+
+    pub fn set_vlenb(&mut self, vlenb: AddressType) {
+        self.reg_bank[VLENB as usize] = vlenb;
+    }
+
+    pub fn set_vl_vtype(&mut self, vl: AddressType, vtype: AddressType) {
+        self.reg_bank[VL as usize] = vl;
+        self.reg_bank[VTYPE as usize] = vtype;
+    }
+
+    pub fn set_entropy_seed(&mut self, seed: u64) {
+        self.entropy_state = seed;
+        self.seed_polls = 0;
+    }
+
+    // Reading seed consumes entropy, so it is polled instead of going through read()
+    pub fn poll_seed(&mut self) -> AddressType {
+        self.seed_polls += 1;
+        if self.seed_polls <= SEED_BIST_POLLS {
+            return SEED_OPST_BIST;
+        }
+        // splitmix64 keeps runs reproducible for a given seed
+        self.entropy_state = self.entropy_state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.entropy_state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        // Roughly one poll in eight finds the pool still refilling, the
+        // generator never fails so DEAD is not reported
+        if z >> 61 == 0 {
+            SEED_OPST_WAIT
+        } else {
+            SEED_OPST_ES16 | (z & 0xffff)
+        }
+    }
+
+    // mip with the timer interrupts of Sstc, STIP/VSTIP follow stimecmp/vstimecmp
+    // once menvcfg/henvcfg.STCE is set
+    fn pending_interrupts(&self) -> AddressType {
+        let mut mip = self.reg_bank[MIP as usize] | (self.reg_bank[HVIP as usize] & VS_INTERRUPTS);
+        if self.reg_bank[MENVCFG as usize] & ENVCFG_STCE != 0 {
+            let time = self.reg_bank[TIME as usize];
+            mip &= !MIP_STIP;
+            if time >= self.reg_bank[STIMECMP as usize] {
+                mip |= MIP_STIP;
+            }
+            let vstime = time.wrapping_add(self.reg_bank[HTIMEDELTA as usize]);
+            if self.reg_bank[HENVCFG as usize] & ENVCFG_STCE != 0
+                && vstime >= self.reg_bank[VSTIMECMP as usize]
+            {
+                mip |= MIP_VSTIP;
+            }
+        }
+        if self.has_aia {
+            let aia = self.aia.borrow();
+            if aia.m_external() {
+                mip |= MIP_MEIP;
+            }
+            if aia.s_external() {
+                mip |= MIP_SEIP;
+            }
+        }
+        mip
+    }
+
+    // mtopi/stopi, the iprio array is read-only zero so IPRIO reads 1
+    fn topi(interrupts: AddressType) -> AddressType {
+        match highest_priority(interrupts) {
+            Some(iid) => (iid << 16) | 1,
+            None => 0,
+        }
+    }
+
+    fn write_masked(&mut self, idx: AddressType, val: AddressType, wmask: AddressType) {
+        let old_val = self.reg_bank[idx as usize];
+        self.reg_bank[idx as usize] = (old_val & !wmask) | (val & wmask);
     }
 
     pub fn read(&self, idx: AddressType) -> AddressType {
@@ -39,8 +438,75 @@ impl CSRegisters {
             self.reg_bank[FCSR as usize] & FFLAGS_RW_MASK
         } else if idx == FRM {
             (self.reg_bank[FCSR as usize] >> 5) & 7
-        } else if idx == MISA {
-            MISA_S | MISA_I | MISA_M | MISA_C | MISA_A
+        } else if idx == VCSR {
+            (self.reg_bank[VXRM as usize] << 1) | self.reg_bank[VXSAT as usize]
+        } else if idx == SSTATUS {
+            self.reg_bank[MSTATUS as usize] & SSTATUS_MASK & self.sdt_mask(MENVCFG)
+        } else if idx == MSTATUS {
+            self.reg_bank[idx as usize] & self.sdt_mask(MENVCFG)
+        } else if idx == VSSTATUS {
+            self.reg_bank[idx as usize] & self.sdt_mask(HENVCFG)
+        } else if (idx == MIP || idx == MIE) && self.clic_mode() {
+            0
+        } else if idx == MCAUSE && self.clic_mode() {
+            let mstatus = self.reg_bank[MSTATUS as usize];
+            let mpp = ((mstatus & MSTATUS_MPP) >> 11) << 28;
+            let mpie = ((mstatus & MSTATUS_MPIE) >> 7) << 27;
+            self.reg_bank[idx as usize] | mpp | mpie
+        } else if idx == MIP {
+            self.pending_interrupts()
+        } else if idx == SIP {
+            self.pending_interrupts() & self.read(MIDELEG) & S_INTERRUPTS
+        } else if idx == HIP {
+            self.pending_interrupts() & H_INTERRUPTS
+        } else if idx == VSIP {
+            (self.pending_interrupts() & self.reg_bank[HIDELEG as usize]) >> 1
+        } else if idx == SIE {
+            self.reg_bank[MIE as usize] & self.read(MIDELEG) & S_INTERRUPTS
+        } else if idx == HIE {
+            self.reg_bank[MIE as usize] & H_INTERRUPTS
+        } else if idx == VSIE {
+            (self.reg_bank[MIE as usize] & self.reg_bank[HIDELEG as usize]) >> 1
+        } else if idx == MVIP {
+            self.pending_interrupts() & S_INTERRUPTS
+        } else if idx == MIREG {
+            self.aia
+                .borrow()
+                .imsic_m
+                .read_reg(self.reg_bank[MISELECT as usize])
+        } else if idx == SIREG {
+            self.aia
+                .borrow()
+                .imsic_s
+                .read_reg(self.reg_bank[SISELECT as usize])
+        } else if idx == MTOPEI {
+            self.aia.borrow().imsic_m.topei()
+        } else if idx == STOPEI {
+            self.aia.borrow().imsic_s.topei()
+        } else if idx == MTOPI {
+            let enabled = self.pending_interrupts() & self.reg_bank[MIE as usize];
+            CSRegisters::topi(enabled & !self.read(MIDELEG))
+        } else if idx == STOPI {
+            let enabled = self.pending_interrupts() & self.reg_bank[MIE as usize];
+            CSRegisters::topi(enabled & self.read(MIDELEG) & !self.reg_bank[HIDELEG as usize])
+        } else if idx == TSELECT {
+            self.triggers.tselect()
+        } else if idx == TDATA1 {
+            self.triggers.tdata1()
+        } else if idx == TDATA2 {
+            self.triggers.tdata2()
+        } else if idx == TDATA3 {
+            // tdata3 (textra) is not implemented
+            0
+        } else if idx == TINFO {
+            self.triggers.tinfo()
+        } else if idx == MIDELEG && self.has_h {
+            // VS-level interrupts and SGEI are always delegated to HS-mode
+            self.reg_bank[idx as usize] | H_INTERRUPTS
+        } else if idx == HENVCFG {
+            // STCE and DTE of henvcfg are read-only zero when clear in menvcfg
+            let menvcfg = self.reg_bank[MENVCFG as usize];
+            self.reg_bank[idx as usize] & (menvcfg | !(ENVCFG_STCE | ENVCFG_DTE))
         } else {
             self.reg_bank[idx as usize]
         }
@@ -55,6 +521,192 @@ impl CSRegisters {
         } else if idx == FRM {
             self.reg_bank[FCSR as usize] &= !(0x7 << 5);
             self.reg_bank[FCSR as usize] |= val << 5;
+        } else if idx == VXSAT {
+            self.reg_bank[idx as usize] = val & 1;
+        } else if idx == VXRM {
+            self.reg_bank[idx as usize] = val & 3;
+        } else if idx == VCSR {
+            self.reg_bank[VXSAT as usize] = val & 1;
+            self.reg_bank[VXRM as usize] = (val >> 1) & 3;
+        } else if idx == JVT {
+            // Only the jump table mode 0 is implemented
+            self.reg_bank[idx as usize] = val & !JVT_MODE;
+        } else if idx == MENVCFG || idx == SENVCFG || idx == HENVCFG {
+            let old_val = self.reg_bank[idx as usize];
+            let mut wmask = ENVCFG_FIOM | ENVCFG_CBIE | ENVCFG_CBCFE | ENVCFG_CBZE;
+            if idx != SENVCFG && self.has_sstc {
+                wmask |= ENVCFG_STCE;
+            }
+            if idx != SENVCFG && self.has_ssdbltrp {
+                wmask |= ENVCFG_DTE;
+            }
+            let mut new_val = val & wmask;
+            // CBIE=2 is reserved, keep the previous setting
+            if new_val & ENVCFG_CBIE == 2 << 4 {
+                new_val = (new_val & !ENVCFG_CBIE) | (old_val & ENVCFG_CBIE);
+            }
+            self.reg_bank[idx as usize] = new_val;
+        } else if idx == MNSTATUS {
+            let old_val = self.reg_bank[idx as usize];
+            let mut new_val = (val | old_val) & MNSTATUS_NMIE;
+            new_val |= val & MNSTATUS_MNPV;
+            // MNPP=2 is reserved, keep the previous mode
+            if val & MNSTATUS_MNPP == 2 << 11 {
+                new_val |= old_val & MNSTATUS_MNPP;
+            } else {
+                new_val |= val & MNSTATUS_MNPP;
+            }
+            self.reg_bank[idx as usize] = new_val;
+        } else if idx == MNEPC {
+            self.reg_bank[idx as usize] = val & !1;
+        } else if idx == TSELECT {
+            self.triggers.set_tselect(val);
+        } else if idx == TDATA1 {
+            self.triggers.set_tdata1(val);
+        } else if idx == TDATA2 {
+            self.triggers.set_tdata2(val);
+        } else if idx == TDATA3 || idx == TINFO {
+            // tdata3 is not implemented and tinfo is read-only
+        } else if idx == DCSR {
+            let old_val = self.reg_bank[idx as usize];
+            let mut wmask = DCSR_EBREAKM | DCSR_EBREAKS | DCSR_EBREAKU | DCSR_STEPIE;
+            wmask |= DCSR_MPRVEN | DCSR_STEP | DCSR_PRV;
+            if self.has_h {
+                wmask |= DCSR_EBREAKVS | DCSR_EBREAKVU | DCSR_V;
+            }
+            let mut new_val = (old_val & !wmask) | (val & wmask);
+            // prv=2 is reserved, keep the previous mode
+            if val & DCSR_PRV == 2 {
+                new_val = (new_val & !DCSR_PRV) | (old_val & DCSR_PRV);
+            }
+            self.reg_bank[idx as usize] = new_val;
+        } else if idx == DPC {
+            self.reg_bank[idx as usize] = val & !1;
+        } else if idx == MSECCFG {
+            self.reg_bank[idx as usize] = val & (MSECCFG_USEED | MSECCFG_SSEED);
+        } else if idx == MSTATUS {
+            let wmask = self.mstatus_wmask & self.sdt_mask(MENVCFG);
+            self.reg_bank[idx as usize] = CSRegisters::double_trap_status(val & wmask);
+        } else if idx == MTVEC && val & TVEC_MODE == MTVEC_MODE_CLIC && self.has_clic {
+            self.reg_bank[idx as usize] = val & (MTVEC_CLIC_BASE | TVEC_MODE);
+        } else if idx == MTVEC || idx == STVEC || idx == VSTVEC {
+            // BASE is 4-byte aligned, the reserved modes fall back to Direct
+            let mode = match val & TVEC_MODE {
+                TVEC_MODE_VECTORED => TVEC_MODE_VECTORED,
+                _ => 0,
+            };
+            self.reg_bank[idx as usize] = (val & !TVEC_MODE) | mode;
+        } else if idx == MTVT {
+            self.reg_bank[idx as usize] = val & MTVEC_CLIC_BASE;
+        } else if idx == MINTTHRESH {
+            self.reg_bank[idx as usize] = val & 0xff;
+        } else if idx == MCAUSE && self.clic_mode() {
+            let wmask = CAUSE_INTERRUPT | MCAUSE_MINHV | MCAUSE_MPIL | MCAUSE_EXCCODE;
+            self.reg_bank[idx as usize] = val & wmask;
+            let mpp = ((val & MCAUSE_MPP) >> 28) << 11;
+            let mpie = ((val & MCAUSE_MPIE) >> 27) << 7;
+            let mstatus = self.reg_bank[MSTATUS as usize] & !(MSTATUS_MPP | MSTATUS_MPIE);
+            self.reg_bank[MSTATUS as usize] = mstatus | mpp | mpie;
+        } else if idx == MINTSTATUS || ((idx == MIP || idx == MIE) && self.clic_mode()) {
+            // mintstatus is read-only and mip/mie are unused in CLIC mode
+        } else if idx == SSTATUS {
+            let mstatus = self.reg_bank[MSTATUS as usize] & !SSTATUS_MASK;
+            let wmask = self.mstatus_wmask & self.sdt_mask(MENVCFG);
+            let new_val = (mstatus | (val & SSTATUS_MASK)) & wmask;
+            self.reg_bank[MSTATUS as usize] = CSRegisters::double_trap_status(new_val);
+        } else if idx == VSSTATUS {
+            let wmask = SSTATUS_MASK & self.mstatus_wmask & self.sdt_mask(HENVCFG);
+            self.reg_bank[idx as usize] = CSRegisters::double_trap_status(val & wmask);
+        } else if idx == SATP || idx == VSATP || idx == HGATP {
+            // Only Bare and Sv39 (Sv39x4 for hgatp) are implemented, other modes leave it unchanged
+            let mode = val & SATP_MODE;
+            if mode == 0 || mode == SATP_MODE_SV39 {
+                // hgatp has a 14-bit VMID and a 16KiB aligned root table
+                let wmask = if idx == HGATP {
+                    SATP_MODE | (0x3fff << 44) | (SATP_PPN & !3)
+                } else {
+                    AddressType::MAX
+                };
+                self.reg_bank[idx as usize] = val & wmask;
+            }
+        } else if idx == MIP {
+            let mut wmask = MIP_SSIP | MIP_SEIP;
+            if self.reg_bank[MENVCFG as usize] & ENVCFG_STCE == 0 {
+                wmask |= MIP_STIP;
+            }
+            self.write_masked(idx, val, wmask);
+            if self.has_h {
+                self.write_masked(HVIP, val, MIP_VSSIP);
+            }
+        } else if idx == MVIP {
+            // mvien is zero, so these are the mip bits
+            let vssip = self.reg_bank[HVIP as usize] & MIP_VSSIP;
+            self.write(MIP, (val & S_INTERRUPTS) | vssip);
+        } else if idx == MVIEN {
+            // Read-only zero
+        } else if idx == MISELECT || idx == SISELECT {
+            self.reg_bank[idx as usize] = val & 0xfff;
+        } else if idx == MIREG {
+            let iselect = self.reg_bank[MISELECT as usize];
+            self.aia.borrow_mut().imsic_m.write_reg(iselect, val);
+        } else if idx == SIREG {
+            let iselect = self.reg_bank[SISELECT as usize];
+            self.aia.borrow_mut().imsic_s.write_reg(iselect, val);
+        } else if idx == MTOPEI {
+            // Any write claims the interrupt that was reported
+            self.aia.borrow_mut().imsic_m.claim();
+        } else if idx == STOPEI {
+            self.aia.borrow_mut().imsic_s.claim();
+        } else if idx == SIP {
+            let wmask = MIP_SSIP & self.read(MIDELEG);
+            self.write_masked(MIP, val, wmask);
+        } else if idx == HIP {
+            self.write_masked(HVIP, val, MIP_VSSIP);
+        } else if idx == VSIP {
+            let wmask = MIP_VSSIP & self.reg_bank[HIDELEG as usize];
+            self.write_masked(HVIP, val << 1, wmask);
+        } else if idx == MIE {
+            let wmask = if self.has_h {
+                M_INTERRUPTS | S_INTERRUPTS | H_INTERRUPTS
+            } else {
+                M_INTERRUPTS | S_INTERRUPTS
+            };
+            self.write_masked(idx, val, wmask);
+        } else if idx == SIE {
+            let wmask = self.read(MIDELEG) & S_INTERRUPTS;
+            self.write_masked(MIE, val, wmask);
+        } else if idx == HIE {
+            self.write_masked(MIE, val, H_INTERRUPTS);
+        } else if idx == VSIE {
+            let wmask = self.reg_bank[HIDELEG as usize];
+            self.write_masked(MIE, val << 1, wmask);
+        } else if idx == MIDELEG {
+            self.reg_bank[idx as usize] = val & S_INTERRUPTS;
+        } else if idx == MCOUNTEREN || idx == SCOUNTEREN || idx == HCOUNTEREN {
+            self.reg_bank[idx as usize] = val & 0xffff_ffff;
+        } else if idx == MEDELEG {
+            self.reg_bank[idx as usize] = val & MEDELEG_WMASK;
+        } else if idx == HEDELEG {
+            self.reg_bank[idx as usize] = val & HEDELEG_WMASK;
+        } else if idx == HIDELEG || idx == HVIP {
+            self.reg_bank[idx as usize] = val & VS_INTERRUPTS;
+        } else if idx == HSTATUS {
+            let wmask = HSTATUS_GVA
+                | HSTATUS_SPV
+                | HSTATUS_SPVP
+                | HSTATUS_HU
+                | HSTATUS_VTVM
+                | HSTATUS_VTW
+                | HSTATUS_VTSR;
+            self.reg_bank[idx as usize] = (val & wmask) | HSTATUS_VSXL_64;
+        } else if idx == MISA {
+            let old_val = self.reg_bank[idx as usize];
+            let mut new_val = (old_val & !self.misa_wmask) | (val & self.misa_wmask);
+            // D depends on F
+            if new_val & MISA_F == 0 {
+                new_val &= !MISA_D;
+            }
+            self.reg_bank[idx as usize] = new_val;
         } else {
             self.reg_bank[idx as usize] = val;
         }
@@ -67,3 +719,10 @@ impl CSRegisters {
         }
     }*/
 }
+
+pub fn highest_priority(interrupts: AddressType) -> Option<AddressType> {
+    INTERRUPT_PRIORITY
+        .iter()
+        .find(|&&irq| (interrupts >> irq) & 1 != 0)
+        .copied()
+}
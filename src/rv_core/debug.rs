@@ -0,0 +1,166 @@
+use crate::rv_core::inst_info::InstID;
+use crate::rv_core::{csregs, isa, PrivilegeMode, RVCore};
+
+type AddressType = u64;
+
+// Register numbers of the abstract commands, CSRs come first
+pub const REGNO_GPR: u32 = 0x1000;
+pub const REGNO_FPR: u32 = 0x1020;
+const REGNO_FPR_END: u32 = 0x103f;
+// Guards against program buffers looping forever
+const PROGBUF_MAX_INSTS: usize = 1024;
+
+// Debug mode (Sdext) as seen by the Debug Module. The hart stays in M-mode while halted,
+// dpc and dcsr keep the state to resume.
+impl RVCore {
+    pub fn has_debug_mode(&self) -> bool {
+        self.debugger || self.isa.has(isa::Extension::Sdext)
+    }
+
+    // The simulator's debugger halts the hart through debug mode whatever the ISA
+    pub fn enable_debug_mode(&mut self) {
+        self.debugger = true;
+    }
+
+    pub fn set_halt_request(&mut self, halt: bool) {
+        self.halt_request = halt;
+    }
+
+    pub fn halted(&self) -> bool {
+        self.debug_mode
+    }
+
+    // dpc is the instruction to execute on resume, the current one aborts
+    pub(super) fn enter_debug_mode(&mut self, cause: AddressType) {
+        self.csregs
+            .set_debug_cause(cause, self.mode as AddressType, self.virt);
+        self.csregs.write(csregs::DPC, self.pc);
+        self.mode = PrivilegeMode::M;
+        self.virt = false;
+        self.debug_mode = true;
+        self.csregs.triggers().set_debug_mode(true);
+        self.trap_taken = true;
+    }
+
+    // Back at dpc in the mode saved in dcsr, as dret does
+    pub fn resume(&mut self) {
+        let dcsr = self.csregs.read(csregs::DCSR);
+        self.mode = match dcsr & csregs::DCSR_PRV {
+            0 => PrivilegeMode::U,
+            1 => PrivilegeMode::S,
+            _ => PrivilegeMode::M,
+        };
+        self.virt = self.mode != PrivilegeMode::M && dcsr & csregs::DCSR_V != 0;
+        if self.mode != PrivilegeMode::M {
+            let mstatus = self.csregs.read(csregs::MSTATUS);
+            self.csregs
+                .write(csregs::MSTATUS, mstatus & !csregs::MSTATUS_MPRV);
+        }
+        self.pc = self.csregs.read(csregs::DPC);
+        self.debug_mode = false;
+        self.csregs.triggers().set_debug_mode(false);
+    }
+
+    pub(super) fn single_stepping(&self) -> bool {
+        self.has_debug_mode() && self.csregs.read(csregs::DCSR) & csregs::DCSR_STEP != 0
+    }
+
+    // Interrupts and NMIs are only taken while single-stepping with stepie set
+    pub(super) fn step_masks_interrupts(&self) -> bool {
+        self.single_stepping() && self.csregs.read(csregs::DCSR) & csregs::DCSR_STEPIE == 0
+    }
+
+    // dcsr, dpc and the dscratch registers only exist in debug mode
+    pub(super) fn debug_csr_fault(&self, csr: AddressType) -> Option<AddressType> {
+        let debug_csrs = [
+            csregs::DCSR,
+            csregs::DPC,
+            csregs::DSCRATCH0,
+            csregs::DSCRATCH1,
+        ];
+        if debug_csrs.contains(&csr) && !self.debug_mode {
+            Some(csregs::EXC_ILLEGAL_INST)
+        } else {
+            None
+        }
+    }
+
+    fn debug_csr_allowed(&self, csr: AddressType, writes: bool) -> bool {
+        let level = (csr >> 8) & 3;
+        let fault = self
+            .aia_fault(csr)
+            .or_else(|| self.extension_csr_fault(csr))
+            .or_else(|| self.debug_csr_fault(csr));
+        !(writes && csr >> 10 == 3)
+            && (level != 2 || self.isa.has(isa::Extension::H))
+            && fault.is_none()
+    }
+
+    // Access Register abstract command, None or false when the register doesn't exist
+    pub fn read_debug_register(&self, regno: u32) -> Option<AddressType> {
+        let csr = regno as AddressType;
+        match regno {
+            0..=0xfff if self.debug_csr_allowed(csr, false) => Some(self.csregs.read(csr)),
+            REGNO_GPR..=0x101f => Some(self.regs.read((regno - REGNO_GPR) as usize)),
+            REGNO_FPR..=REGNO_FPR_END if self.isa.has(isa::Extension::F) => {
+                Some(self.fregs.read_bits((regno - REGNO_FPR) as usize))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn write_debug_register(&mut self, regno: u32, val: AddressType) -> bool {
+        let csr = regno as AddressType;
+        match regno {
+            0..=0xfff if self.debug_csr_allowed(csr, true) => self.write_csr(csr, val),
+            REGNO_GPR..=0x101f => self.regs.write((regno - REGNO_GPR) as usize, val),
+            REGNO_FPR..=REGNO_FPR_END if self.isa.has(isa::Extension::F) => {
+                self.fregs.write_bits((regno - REGNO_FPR) as usize, val)
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    // Runs the program buffer up to an ebreak or its end, the implicit ebreak. false when
+    // an instruction raised an exception, its effects are dropped.
+    pub fn execute_program_buffer(&mut self, progbuf: &[u32]) -> bool {
+        if !self.debug_mode {
+            return false;
+        }
+        let bytes: Vec<u8> = progbuf.iter().flat_map(|word| word.to_le_bytes()).collect();
+        self.pc = 0;
+        for _ in 0..PROGBUF_MAX_INSTS {
+            let offset = self.pc as usize;
+            if offset >= bytes.len() {
+                return true;
+            }
+            let inst_bytes = bytes[offset..]
+                .iter()
+                .take(4)
+                .rev()
+                .fold(0, |val, byte| (val << 8) | *byte as AddressType);
+            let inst = self
+                .id_instance
+                .decode(inst_bytes, |ext| self.extension_enabled(ext));
+            match inst.id {
+                InstID::EBREAK | InstID::C_EBREAK => return true,
+                InstID::INVALID => return false,
+                _ => (),
+            }
+            let regs = self.regs.clone();
+            self.trap_taken = false;
+            self.execute(&inst);
+            if self.trap_taken {
+                self.regs = regs;
+                return false;
+            }
+            self.pc += inst.len;
+            // dret left debug mode
+            if !self.debug_mode {
+                return true;
+            }
+        }
+        false
+    }
+}
@@ -1,24 +1,63 @@
-use softfloat_wrapper::F64;
+use softfloat_wrapper::{Float, F16, F32, F64};
 
+type AddressType = u64;
+
+const NAN_BOX_S: AddressType = 0xffff_ffff_0000_0000;
+const NAN_BOX_H: AddressType = 0xffff_ffff_ffff_0000;
+
+// Registers hold raw 64-bit contents, narrower values are NaN-boxed
+// and anything not properly boxed reads as the canonical NaN
 pub struct FRegisters {
-    reg_bank: [F64; 32],
+    reg_bank: [AddressType; 32],
 }
 
 impl FRegisters {
     pub fn new() -> FRegisters {
-        FRegisters {
-            reg_bank: [F64::from_f64(0.0); 32],
-        }
+        FRegisters { reg_bank: [0; 32] }
     }
 
     pub fn read(&self, i: usize) -> F64 {
-        self.reg_bank[i]
+        F64::from_bits(self.reg_bank[i])
     }
 
     pub fn write(&mut self, i: usize, val: F64) {
+        self.reg_bank[i] = val.to_bits();
+    }
+
+    pub fn read_bits(&self, i: usize) -> AddressType {
+        self.reg_bank[i]
+    }
+
+    pub fn write_bits(&mut self, i: usize, val: AddressType) {
         self.reg_bank[i] = val;
     }
 
+    pub fn read_s(&self, i: usize) -> F32 {
+        let bits = self.reg_bank[i];
+        if (bits & NAN_BOX_S) == NAN_BOX_S {
+            F32::from_bits(bits as u32)
+        } else {
+            F32::quiet_nan()
+        }
+    }
+
+    pub fn write_s(&mut self, i: usize, val: F32) {
+        self.reg_bank[i] = NAN_BOX_S | val.to_bits() as AddressType;
+    }
+
+    pub fn read_h(&self, i: usize) -> F16 {
+        let bits = self.reg_bank[i];
+        if (bits & NAN_BOX_H) == NAN_BOX_H {
+            F16::from_bits(bits as u16)
+        } else {
+            F16::quiet_nan()
+        }
+    }
+
+    pub fn write_h(&mut self, i: usize, val: F16) {
+        self.reg_bank[i] = NAN_BOX_H | val.to_bits() as AddressType;
+    }
+
     /*fn name(i: usize) -> &'static str {
         match i {
             0 => "ft0",
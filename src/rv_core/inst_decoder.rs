@@ -1,5 +1,6 @@
 use crate::rv_core::inst_info::InstID;
 use crate::rv_core::inst_type::InstType;
+use crate::rv_core::isa::Extension;
 
 type AddressType = u64;
 
@@ -10,7 +11,11 @@ impl InstDecoder {
         InstDecoder {}
     }
 
-    pub fn decode(&self, inst_bytes: AddressType) -> InstType {
+    // Instructions from extensions rejected by is_enabled decode as INVALID
+    pub fn decode<F>(&self, inst_bytes: AddressType, is_enabled: F) -> InstType
+    where
+        F: Fn(Extension) -> bool,
+    {
         let mut new_inst = InstType {
             data: inst_bytes,
             len: 0,
@@ -27,6 +32,23 @@ impl InstDecoder {
             }
         }
 
+        let uses_fregs = new_inst.id.uses_fregs();
+        let is_enabled = |ext: Extension| {
+            is_enabled(ext) || (!uses_fregs && ext.finx_equivalent().is_some_and(&is_enabled))
+        };
+        let id_enabled = |id: InstID| {
+            id.extensions().iter().all(|ext| is_enabled(*ext))
+                || id.alt_extensions().iter().any(|ext| is_enabled(*ext))
+        };
+        if !id_enabled(new_inst.id) {
+            if let Some(base) = new_inst.id.fallback() {
+                new_inst.id = base;
+            }
+        }
+        if !id_enabled(new_inst.id) {
+            self.dump_invalid_inst(&mut new_inst);
+        }
+
         new_inst
     }
 
@@ -38,6 +60,14 @@ impl InstDecoder {
                 0x0 => inst.id = InstID::C_ADDI4SPN,
                 0x2 => inst.id = InstID::C_LW,
                 0x3 => inst.id = InstID::C_LD,
+                0x4 => match ((inst_bytes >> 10) & 0x7, (inst_bytes >> 6) & 1) {
+                    (0x0, _) => inst.id = InstID::C_LBU,
+                    (0x1, 0) => inst.id = InstID::C_LHU,
+                    (0x1, 1) => inst.id = InstID::C_LH,
+                    (0x2, _) => inst.id = InstID::C_SB,
+                    (0x3, 0) => inst.id = InstID::C_SH,
+                    (_, _) => self.dump_invalid_inst(inst),
+                },
                 0x6 => inst.id = InstID::C_SW,
                 0x7 => inst.id = InstID::C_SD,
                 _ => self.dump_invalid_inst(inst),
@@ -47,9 +77,13 @@ impl InstDecoder {
                 0x1 => inst.id = InstID::C_ADDIW,
                 0x3 => {
                     let rd = inst.get_rd();
+                    let nzimm = ((inst_bytes >> 12) & 1, (inst_bytes >> 2) & 0x1f);
                     match rd {
                         0 => self.dump_invalid_inst(inst),
                         2 => inst.id = InstID::C_ADDI16SP,
+                        1 | 3 | 5 | 7 | 9 | 11 | 13 | 15 if nzimm == (0, 0) => {
+                            inst.id = InstID::C_MOP
+                        }
                         _ => inst.id = InstID::C_LUI,
                     }
                 }
@@ -67,6 +101,16 @@ impl InstDecoder {
                             (0, 3) => inst.id = InstID::C_AND,
                             (1, 0) => inst.id = InstID::C_SUBW,
                             (1, 1) => inst.id = InstID::C_ADDW,
+                            (1, 2) => inst.id = InstID::C_MUL,
+                            (1, 3) => match (inst_bytes >> 2) & 0x7 {
+                                0x0 => inst.id = InstID::C_ZEXT_B,
+                                0x1 => inst.id = InstID::C_SEXT_B,
+                                0x2 => inst.id = InstID::C_ZEXT_H,
+                                0x3 => inst.id = InstID::C_SEXT_H,
+                                0x4 => inst.id = InstID::C_ZEXT_W,
+                                0x5 => inst.id = InstID::C_NOT,
+                                _ => self.dump_invalid_inst(inst),
+                            },
                             (_, _) => self.dump_invalid_inst(inst),
                         },
                         _ => self.dump_invalid_inst(inst),
@@ -84,11 +128,19 @@ impl InstDecoder {
                 0x4 => match ((inst_bytes >> 12) & 1, (inst_bytes >> 2) & 0x1f) {
                     (0, 0) => inst.id = InstID::C_JR,
                     (0, _) => inst.id = InstID::C_MV,
+                    (1, 0) if (inst_bytes >> 7) & 0x1f == 0 => inst.id = InstID::C_EBREAK,
                     (1, 0) => inst.id = InstID::C_JALR,
+                    (1, rs2) if (inst_bytes >> 7) & 0x1f == 0 => match rs2 {
+                        2 => inst.id = InstID::C_NTL_P1,
+                        3 => inst.id = InstID::C_NTL_PALL,
+                        4 => inst.id = InstID::C_NTL_S1,
+                        5 => inst.id = InstID::C_NTL_ALL,
+                        _ => inst.id = InstID::C_ADD,
+                    },
                     (1, _) => inst.id = InstID::C_ADD,
                     (_, _) => self.dump_invalid_inst(inst),
                 },
-                0x5 => inst.id = InstID::C_FSDSP,
+                0x5 => self.decode_inst_zcm(inst_bytes, inst),
                 0x6 => inst.id = InstID::C_SWSP,
                 0x7 => inst.id = InstID::C_SDSP,
                 _ => self.dump_invalid_inst(inst),
@@ -112,21 +164,69 @@ impl InstDecoder {
                 _ => self.dump_invalid_inst(inst),
             },
             0x7 => match funct3 {
+                0x1 => inst.id = InstID::FLH,
                 0x2 => inst.id = InstID::FLW,
                 0x3 => inst.id = InstID::FLD,
+                0x0 | 0x5 | 0x6 | 0x7 => self.decode_inst_vector_mem(inst_bytes, inst, false),
+                _ => self.dump_invalid_inst(inst),
+            },
+            0x0f => match funct3 {
+                0x0 if inst_bytes == 0x0100000f => inst.id = InstID::PAUSE,
+                0x0 => inst.id = InstID::FENCE,
+                0x1 => inst.id = InstID::FENCE_I,
+                0x2 if (inst_bytes >> 7) & 0x1f == 0 => match inst_bytes >> 20 {
+                    0x0 => inst.id = InstID::CBO_INVAL,
+                    0x1 => inst.id = InstID::CBO_CLEAN,
+                    0x2 => inst.id = InstID::CBO_FLUSH,
+                    0x4 => inst.id = InstID::CBO_ZERO,
+                    _ => self.dump_invalid_inst(inst),
+                },
                 _ => self.dump_invalid_inst(inst),
             },
-            0x0f => inst.id = InstID::FENCE,
             0x13 => match funct3 {
                 0x0 => inst.id = InstID::ADDI,
-                0x1 => inst.id = InstID::SLLI,
+                0x1 => match ((inst_bytes >> 26) & 0x3f, (inst_bytes >> 20) & 0xfff) {
+                    (0x0, _) => inst.id = InstID::SLLI,
+                    (0x0a, _) => inst.id = InstID::BSETI,
+                    (0x12, _) => inst.id = InstID::BCLRI,
+                    (0x1a, _) => inst.id = InstID::BINVI,
+                    (_, 0x600) => inst.id = InstID::CLZ,
+                    (_, 0x601) => inst.id = InstID::CTZ,
+                    (_, 0x602) => inst.id = InstID::CPOP,
+                    (_, 0x604) => inst.id = InstID::SEXT_B,
+                    (_, 0x605) => inst.id = InstID::SEXT_H,
+                    (_, 0x100) => inst.id = InstID::SHA256SUM0,
+                    (_, 0x101) => inst.id = InstID::SHA256SUM1,
+                    (_, 0x102) => inst.id = InstID::SHA256SIG0,
+                    (_, 0x103) => inst.id = InstID::SHA256SIG1,
+                    (_, 0x104) => inst.id = InstID::SHA512SUM0,
+                    (_, 0x105) => inst.id = InstID::SHA512SUM1,
+                    (_, 0x106) => inst.id = InstID::SHA512SIG0,
+                    (_, 0x107) => inst.id = InstID::SHA512SIG1,
+                    (_, 0x108) => inst.id = InstID::SM3P0,
+                    (_, 0x109) => inst.id = InstID::SM3P1,
+                    (_, 0x300) => inst.id = InstID::AES64IM,
+                    (_, 0x310..=0x31a) => inst.id = InstID::AES64KS1I,
+                    (_, _) => self.dump_invalid_inst(inst),
+                },
                 0x2 => inst.id = InstID::SLTI,
                 0x3 => inst.id = InstID::SLTIU,
                 0x4 => inst.id = InstID::XORI,
-                0x5 => match (inst_bytes >> 26) & 0x3f {
-                    0x0 => inst.id = InstID::SRLI,
-                    0x10 => inst.id = InstID::SRAI,
-                    _ => self.dump_invalid_inst(inst),
+                0x5 => match ((inst_bytes >> 26) & 0x3f, (inst_bytes >> 20) & 0xfff) {
+                    (_, 0x287) => inst.id = InstID::ORC_B,
+                    (_, 0x6b8) => inst.id = InstID::REV8,
+                    (_, 0x687) => inst.id = InstID::BREV8,
+                    (0x0, _) => inst.id = InstID::SRLI,
+                    (0x10, _) => inst.id = InstID::SRAI,
+                    (0x12, _) => inst.id = InstID::BEXTI,
+                    (0x18, _) => inst.id = InstID::RORI,
+                    (_, _) => self.dump_invalid_inst(inst),
+                },
+                0x6 if (inst_bytes >> 7) & 0x1f == 0 => match (inst_bytes >> 20) & 0x1f {
+                    0x0 => inst.id = InstID::PREFETCH_I,
+                    0x1 => inst.id = InstID::PREFETCH_R,
+                    0x3 => inst.id = InstID::PREFETCH_W,
+                    _ => inst.id = InstID::ORI,
                 },
                 0x6 => inst.id = InstID::ORI,
                 0x7 => inst.id = InstID::ANDI,
@@ -137,12 +237,20 @@ impl InstDecoder {
             }
             0x1b => match funct3 {
                 0x0 => inst.id = InstID::ADDIW,
-                0x1 => inst.id = InstID::SLLIW,
+                0x1 => match ((inst_bytes >> 26) & 0x3f, (inst_bytes >> 20) & 0xfff) {
+                    (0x0, _) if (inst_bytes >> 25) & 1 == 0 => inst.id = InstID::SLLIW,
+                    (0x2, _) => inst.id = InstID::SLLI_UW,
+                    (_, 0x600) => inst.id = InstID::CLZW,
+                    (_, 0x601) => inst.id = InstID::CTZW,
+                    (_, 0x602) => inst.id = InstID::CPOPW,
+                    (_, _) => self.dump_invalid_inst(inst),
+                },
                 0x5 => {
                     let funct7 = (inst_bytes >> 25) & 0x7f;
                     match funct7 {
                         0x0 => inst.id = InstID::SRLIW,
                         0x20 => inst.id = InstID::SRAIW,
+                        0x30 => inst.id = InstID::RORIW,
                         _ => self.dump_invalid_inst(inst),
                     }
                 }
@@ -156,19 +264,48 @@ impl InstDecoder {
                 _ => self.dump_invalid_inst(inst),
             },
             0x27 => match funct3 {
+                0x1 => inst.id = InstID::FSH,
                 0x2 => inst.id = InstID::FSW,
                 0x3 => inst.id = InstID::FSD,
+                0x0 | 0x5 | 0x6 | 0x7 => self.decode_inst_vector_mem(inst_bytes, inst, true),
                 _ => self.dump_invalid_inst(inst),
             }
             0x2f => {
                 let funct7 = (inst_bytes >> 27) & 0x1f;
                 match funct3 {
+                    0x0 => match funct7 {
+                        0x0 => inst.id = InstID::AMOADD_B,
+                        0x1 => inst.id = InstID::AMOSWAP_B,
+                        0x4 => inst.id = InstID::AMOXOR_B,
+                        0x5 => inst.id = InstID::AMOCAS_B,
+                        0x8 => inst.id = InstID::AMOMIN_B,
+                        0xc => inst.id = InstID::AMOAND_B,
+                        0x10 => inst.id = InstID::AMOMAX_B,
+                        0x14 => inst.id = InstID::AMOOR_B,
+                        0x18 => inst.id = InstID::AMOMINU_B,
+                        0x1c => inst.id = InstID::AMOMAXU_B,
+                        _ => self.dump_invalid_inst(inst),
+                    },
+                    0x1 => match funct7 {
+                        0x0 => inst.id = InstID::AMOADD_H,
+                        0x1 => inst.id = InstID::AMOSWAP_H,
+                        0x4 => inst.id = InstID::AMOXOR_H,
+                        0x5 => inst.id = InstID::AMOCAS_H,
+                        0x8 => inst.id = InstID::AMOMIN_H,
+                        0xc => inst.id = InstID::AMOAND_H,
+                        0x10 => inst.id = InstID::AMOMAX_H,
+                        0x14 => inst.id = InstID::AMOOR_H,
+                        0x18 => inst.id = InstID::AMOMINU_H,
+                        0x1c => inst.id = InstID::AMOMAXU_H,
+                        _ => self.dump_invalid_inst(inst),
+                    },
                     0x2 => match funct7 {
                         0x0 => inst.id = InstID::AMOADD_W,
                         0x1 => inst.id = InstID::AMOSWAP_W,
                         0x2 => inst.id = InstID::LR_W,
                         0x3 => inst.id = InstID::SC_W,
                         0x4 => inst.id = InstID::AMOXOR_W,
+                        0x5 => inst.id = InstID::AMOCAS_W,
                         0x8 => inst.id = InstID::AMOMIN_W,
                         0xc => inst.id = InstID::AMOAND_W,
                         0x10 => inst.id = InstID::AMOMAX_W,
@@ -183,6 +320,7 @@ impl InstDecoder {
                         0x2 => inst.id = InstID::LR_D,
                         0x3 => inst.id = InstID::SC_D,
                         0x4 => inst.id = InstID::AMOXOR_D,
+                        0x5 => inst.id = InstID::AMOCAS_D,
                         0x8 => inst.id = InstID::AMOMIN_D,
                         0xc => inst.id = InstID::AMOAND_D,
                         0x10 => inst.id = InstID::AMOMAX_D,
@@ -191,6 +329,10 @@ impl InstDecoder {
                         0x1c => inst.id = InstID::AMOMAXU_D,
                         _ => self.dump_invalid_inst(inst),
                     }
+                    0x4 => match funct7 {
+                        0x5 => inst.id = InstID::AMOCAS_Q,
+                        _ => self.dump_invalid_inst(inst),
+                    },
                     _ => self.dump_invalid_inst(inst),
                 }
             }
@@ -198,8 +340,22 @@ impl InstDecoder {
                 0x0 => {
                     let funct7 = (inst_bytes >> 25) & 0x7f;
                     match funct7 {
+                        0x0 if inst_bytes & 0xf_ff80 == 0 => match (inst_bytes >> 20) & 0x1f {
+                            2 => inst.id = InstID::NTL_P1,
+                            3 => inst.id = InstID::NTL_PALL,
+                            4 => inst.id = InstID::NTL_S1,
+                            5 => inst.id = InstID::NTL_ALL,
+                            _ => inst.id = InstID::ADD,
+                        },
                         0x0 => inst.id = InstID::ADD,
                         0x1 => inst.id = InstID::MUL,
+                        0x19 => inst.id = InstID::AES64ES,
+                        0x1b => inst.id = InstID::AES64ESM,
+                        0x1d => inst.id = InstID::AES64DS,
+                        0x1f => inst.id = InstID::AES64DSM,
+                        0x3f => inst.id = InstID::AES64KS2,
+                        0x18 | 0x38 | 0x58 | 0x78 => inst.id = InstID::SM4ED,
+                        0x1a | 0x3a | 0x5a | 0x7a => inst.id = InstID::SM4KS,
                         0x20 => inst.id = InstID::SUB,
                         _ => self.dump_invalid_inst(inst),
                     }
@@ -209,6 +365,11 @@ impl InstDecoder {
                     match funct7 {
                         0x0 => inst.id = InstID::SLL,
                         0x1 => inst.id = InstID::MULH,
+                        0x5 => inst.id = InstID::CLMUL,
+                        0x14 => inst.id = InstID::BSET,
+                        0x24 => inst.id = InstID::BCLR,
+                        0x30 => inst.id = InstID::ROL,
+                        0x34 => inst.id = InstID::BINV,
                         _ => self.dump_invalid_inst(inst),
                     }
                 }
@@ -217,6 +378,9 @@ impl InstDecoder {
                     match funct7 {
                         0x0 => inst.id = InstID::SLT,
                         0x1 => inst.id = InstID::MULHSU,
+                        0x5 => inst.id = InstID::CLMULR,
+                        0x10 => inst.id = InstID::SH1ADD,
+                        0x14 => inst.id = InstID::XPERM4,
                         _ => self.dump_invalid_inst(inst),
                     }
                 }
@@ -225,6 +389,7 @@ impl InstDecoder {
                     match funct7 {
                         0x0 => inst.id = InstID::SLTU,
                         0x1 => inst.id = InstID::MULHU,
+                        0x5 => inst.id = InstID::CLMULH,
                         _ => self.dump_invalid_inst(inst),
                     }
                 }
@@ -233,6 +398,11 @@ impl InstDecoder {
                     match funct7 {
                         0x0 => inst.id = InstID::XOR,
                         0x1 => inst.id = InstID::DIV,
+                        0x4 => inst.id = InstID::PACK,
+                        0x5 => inst.id = InstID::MIN,
+                        0x10 => inst.id = InstID::SH2ADD,
+                        0x14 => inst.id = InstID::XPERM8,
+                        0x20 => inst.id = InstID::XNOR,
                         _ => self.dump_invalid_inst(inst),
                     }
                 }
@@ -241,7 +411,11 @@ impl InstDecoder {
                     match funct7 {
                         0x0 => inst.id = InstID::SRL,
                         0x1 => inst.id = InstID::DIVU,
+                        0x5 => inst.id = InstID::MINU,
+                        0x7 => inst.id = InstID::CZERO_EQZ,
                         0x20 => inst.id = InstID::SRA,
+                        0x24 => inst.id = InstID::BEXT,
+                        0x30 => inst.id = InstID::ROR,
                         _ => self.dump_invalid_inst(inst),
                     }
                 }
@@ -250,6 +424,9 @@ impl InstDecoder {
                     match funct7 {
                         0x0 => inst.id = InstID::OR,
                         0x1 => inst.id = InstID::REM,
+                        0x5 => inst.id = InstID::MAX,
+                        0x10 => inst.id = InstID::SH3ADD,
+                        0x20 => inst.id = InstID::ORN,
                         _ => self.dump_invalid_inst(inst),
                     }
                 }
@@ -258,6 +435,10 @@ impl InstDecoder {
                     match funct7 {
                         0x0 => inst.id = InstID::AND,
                         0x1 => inst.id = InstID::REMU,
+                        0x4 => inst.id = InstID::PACKH,
+                        0x5 => inst.id = InstID::MAXU,
+                        0x7 => inst.id = InstID::CZERO_NEZ,
+                        0x20 => inst.id = InstID::ANDN,
                         _ => self.dump_invalid_inst(inst),
                     }
                 }
@@ -272,6 +453,7 @@ impl InstDecoder {
                     match funct7 {
                         0x0 => inst.id = InstID::ADDW,
                         0x1 => inst.id = InstID::MULW,
+                        0x4 => inst.id = InstID::ADD_UW,
                         0x20 => inst.id = InstID::SUBW,
                         _ => self.dump_invalid_inst(inst),
                     }
@@ -280,20 +462,36 @@ impl InstDecoder {
                     let funct7 = (inst_bytes >> 25) & 0x7f;
                     match funct7 {
                         0x0 => inst.id = InstID::SLLW,
+                        0x30 => inst.id = InstID::ROLW,
                         _ => self.dump_invalid_inst(inst),
                     }
                 }
-                0x4 => inst.id = InstID::DIVW,
+                0x2 => match (inst_bytes >> 25) & 0x7f {
+                    0x10 => inst.id = InstID::SH1ADD_UW,
+                    _ => self.dump_invalid_inst(inst),
+                },
+                0x4 => match ((inst_bytes >> 25) & 0x7f, (inst_bytes >> 20) & 0x1f) {
+                    (0x1, _) => inst.id = InstID::DIVW,
+                    (0x4, 0) => inst.id = InstID::ZEXT_H,
+                    (0x4, _) => inst.id = InstID::PACKW,
+                    (0x10, _) => inst.id = InstID::SH2ADD_UW,
+                    (_, _) => self.dump_invalid_inst(inst),
+                },
                 0x5 => {
                     let funct7 = (inst_bytes >> 25) & 0x7f;
                     match funct7 {
                         0x0 => inst.id = InstID::SRLW,
                         0x1 => inst.id = InstID::DIVUW,
                         0x20 => inst.id = InstID::SRAW,
+                        0x30 => inst.id = InstID::RORW,
                         _ => self.dump_invalid_inst(inst),
                     }
                 }
-                0x6 => inst.id = InstID::REMW,
+                0x6 => match (inst_bytes >> 25) & 0x7f {
+                    0x1 => inst.id = InstID::REMW,
+                    0x10 => inst.id = InstID::SH3ADD_UW,
+                    _ => self.dump_invalid_inst(inst),
+                },
                 0x7 => inst.id = InstID::REMUW,
                 _ => self.dump_invalid_inst(inst),
             },
@@ -302,6 +500,7 @@ impl InstDecoder {
                 match funct2 {
                     0 => inst.id = InstID::FMADD_S,
                     1 => inst.id = InstID::FMADD_D,
+                    2 => inst.id = InstID::FMADD_H,
                     _ => self.dump_invalid_inst(inst),
                 }
             }
@@ -310,6 +509,7 @@ impl InstDecoder {
                 match funct2 {
                     0 => inst.id = InstID::FMSUB_S,
                     1 => inst.id = InstID::FMSUB_D,
+                    2 => inst.id = InstID::FMSUB_H,
                     _ => self.dump_invalid_inst(inst),
                 }
             }
@@ -318,6 +518,7 @@ impl InstDecoder {
                 match funct2 {
                     0 => inst.id = InstID::FNMSUB_S,
                     1 => inst.id = InstID::FNMSUB_D,
+                    2 => inst.id = InstID::FNMSUB_H,
                     _ => self.dump_invalid_inst(inst),
                 }
             }
@@ -326,6 +527,7 @@ impl InstDecoder {
                 match funct2 {
                     0 => inst.id = InstID::FNMADD_S,
                     1 => inst.id = InstID::FNMADD_D,
+                    2 => inst.id = InstID::FNMADD_H,
                     _ => self.dump_invalid_inst(inst),
                 }
             }
@@ -334,12 +536,16 @@ impl InstDecoder {
                 match funct7 {
                     0x0 => inst.id = InstID::FADD_S,
                     0x1 => inst.id = InstID::FADD_D,
+                    0x2 => inst.id = InstID::FADD_H,
                     0x4 => inst.id = InstID::FSUB_S,
                     0x5 => inst.id = InstID::FSUB_D,
+                    0x6 => inst.id = InstID::FSUB_H,
                     0x8 => inst.id = InstID::FMUL_S,
                     0x9 => inst.id = InstID::FMUL_D,
+                    0xa => inst.id = InstID::FMUL_H,
                     0xc => inst.id = InstID::FDIV_S,
                     0xd => inst.id = InstID::FDIV_D,
+                    0xe => inst.id = InstID::FDIV_H,
                     0x10 => match funct3 {
                         0x0 => inst.id = InstID::FSGNJ_S,
                         0x1 => inst.id = InstID::FSGNJN_S,
@@ -352,34 +558,77 @@ impl InstDecoder {
                         0x2 => inst.id = InstID::FSGNJX_D,
                         _ => self.dump_invalid_inst(inst),
                     },
-                    0x14 => {
-                        if funct3 == 0 {
-                            inst.id = InstID::FMIN_S;
-                        } else {
-                            inst.id = InstID::FMAX_S;
-                        }
-                    }
-                    0x15 => {
-                        if funct3 == 0 {
-                            inst.id = InstID::FMIN_D;
-                        } else {
-                            inst.id = InstID::FMAX_D;
-                        }
-                    }
-                    0x20 => inst.id = InstID::FCVT_S_D,
-                    0x21 => inst.id = InstID::FCVT_D_S,
+                    0x12 => match funct3 {
+                        0x0 => inst.id = InstID::FSGNJ_H,
+                        0x1 => inst.id = InstID::FSGNJN_H,
+                        0x2 => inst.id = InstID::FSGNJX_H,
+                        _ => self.dump_invalid_inst(inst),
+                    },
+                    0x14 => match funct3 {
+                        0x0 => inst.id = InstID::FMIN_S,
+                        0x1 => inst.id = InstID::FMAX_S,
+                        0x2 => inst.id = InstID::FMINM_S,
+                        0x3 => inst.id = InstID::FMAXM_S,
+                        _ => self.dump_invalid_inst(inst),
+                    },
+                    0x15 => match funct3 {
+                        0x0 => inst.id = InstID::FMIN_D,
+                        0x1 => inst.id = InstID::FMAX_D,
+                        0x2 => inst.id = InstID::FMINM_D,
+                        0x3 => inst.id = InstID::FMAXM_D,
+                        _ => self.dump_invalid_inst(inst),
+                    },
+                    0x16 => match funct3 {
+                        0x0 => inst.id = InstID::FMIN_H,
+                        0x1 => inst.id = InstID::FMAX_H,
+                        0x2 => inst.id = InstID::FMINM_H,
+                        0x3 => inst.id = InstID::FMAXM_H,
+                        _ => self.dump_invalid_inst(inst),
+                    },
+                    0x20 => match inst.get_rs2_stype() {
+                        0x2 => inst.id = InstID::FCVT_S_H,
+                        0x4 => inst.id = InstID::FROUND_S,
+                        0x5 => inst.id = InstID::FROUNDNX_S,
+                        _ => inst.id = InstID::FCVT_S_D,
+                    },
+                    0x21 => match inst.get_rs2_stype() {
+                        0x2 => inst.id = InstID::FCVT_D_H,
+                        0x4 => inst.id = InstID::FROUND_D,
+                        0x5 => inst.id = InstID::FROUNDNX_D,
+                        _ => inst.id = InstID::FCVT_D_S,
+                    },
+                    0x22 => match inst.get_rs2_stype() {
+                        0x0 => inst.id = InstID::FCVT_H_S,
+                        0x1 => inst.id = InstID::FCVT_H_D,
+                        0x4 => inst.id = InstID::FROUND_H,
+                        0x5 => inst.id = InstID::FROUNDNX_H,
+                        _ => self.dump_invalid_inst(inst),
+                    },
                     0x2c => inst.id = InstID::FSQRT_S,
                     0x2d => inst.id = InstID::FSQRT_D,
+                    0x2e => inst.id = InstID::FSQRT_H,
                     0x50 => match funct3 {
                         0x0 => inst.id = InstID::FLE_S,
                         0x1 => inst.id = InstID::FLT_S,
                         0x2 => inst.id = InstID::FEQ_S,
+                        0x4 => inst.id = InstID::FLEQ_S,
+                        0x5 => inst.id = InstID::FLTQ_S,
                         _ => self.dump_invalid_inst(inst),
                     },
                     0x51 => match funct3 {
                         0x0 => inst.id = InstID::FLE_D,
                         0x1 => inst.id = InstID::FLT_D,
                         0x2 => inst.id = InstID::FEQ_D,
+                        0x4 => inst.id = InstID::FLEQ_D,
+                        0x5 => inst.id = InstID::FLTQ_D,
+                        _ => self.dump_invalid_inst(inst),
+                    },
+                    0x52 => match funct3 {
+                        0x0 => inst.id = InstID::FLE_H,
+                        0x1 => inst.id = InstID::FLT_H,
+                        0x2 => inst.id = InstID::FEQ_H,
+                        0x4 => inst.id = InstID::FLEQ_H,
+                        0x5 => inst.id = InstID::FLTQ_H,
                         _ => self.dump_invalid_inst(inst),
                     },
                     0x60 => {
@@ -399,6 +648,18 @@ impl InstDecoder {
                             0x1 => inst.id = InstID::FCVT_WU_D,
                             0x2 => inst.id = InstID::FCVT_L_D,
                             0x3 => inst.id = InstID::FCVT_LU_D,
+                            // Only defined with the static RTZ rounding mode
+                            0x8 if funct3 == 0x1 => inst.id = InstID::FCVTMOD_W_D,
+                            _ => self.dump_invalid_inst(inst),
+                        }
+                    }
+                    0x62 => {
+                        let rs2 = (inst_bytes >> 20) & 0x1f;
+                        match rs2 {
+                            0x0 => inst.id = InstID::FCVT_W_H,
+                            0x1 => inst.id = InstID::FCVT_WU_H,
+                            0x2 => inst.id = InstID::FCVT_L_H,
+                            0x3 => inst.id = InstID::FCVT_LU_H,
                             _ => self.dump_invalid_inst(inst),
                         }
                     }
@@ -422,6 +683,16 @@ impl InstDecoder {
                             _ => self.dump_invalid_inst(inst),
                         }
                     }
+                    0x6a => {
+                        let rs2 = (inst_bytes >> 20) & 0x1f;
+                        match rs2 {
+                            0x0 => inst.id = InstID::FCVT_H_W,
+                            0x1 => inst.id = InstID::FCVT_H_WU,
+                            0x2 => inst.id = InstID::FCVT_H_L,
+                            0x3 => inst.id = InstID::FCVT_H_LU,
+                            _ => self.dump_invalid_inst(inst),
+                        }
+                    }
                     0x70 => match funct3 {
                         0x0 => inst.id = InstID::FMV_X_W,
                         0x1 => inst.id = InstID::FCLASS_S,
@@ -432,11 +703,27 @@ impl InstDecoder {
                         (0, 1) => inst.id = InstID::FCLASS_D,
                         _ => self.dump_invalid_inst(inst),
                     },
-                    0x78 => inst.id = InstID::FMV_W_X,
-                    0x79 => inst.id = InstID::FMV_D_X,
+                    0x72 => match (inst.get_rs2_stype(), funct3) {
+                        (0, 0) => inst.id = InstID::FMV_X_H,
+                        (0, 1) => inst.id = InstID::FCLASS_H,
+                        _ => self.dump_invalid_inst(inst),
+                    },
+                    0x78 => match inst.get_rs2_stype() {
+                        0x1 => inst.id = InstID::FLI_S,
+                        _ => inst.id = InstID::FMV_W_X,
+                    },
+                    0x79 => match inst.get_rs2_stype() {
+                        0x1 => inst.id = InstID::FLI_D,
+                        _ => inst.id = InstID::FMV_D_X,
+                    },
+                    0x7a => match inst.get_rs2_stype() {
+                        0x1 => inst.id = InstID::FLI_H,
+                        _ => inst.id = InstID::FMV_H_X,
+                    },
                     _ => self.dump_invalid_inst(inst),
                 }
             }
+            0x57 => self.decode_inst_vector(inst_bytes, inst),
             0x63 => match funct3 {
                 0x0 => inst.id = InstID::BEQ,
                 0x1 => inst.id = InstID::BNE,
@@ -453,13 +740,40 @@ impl InstDecoder {
                     let funct12 = (inst_bytes >> 20) & 0xfff;
                     match funct12 {
                         0x000 => inst.id = InstID::ECALL,
+                        0x001 => inst.id = InstID::EBREAK,
+                        0x102 => inst.id = InstID::SRET,
                         0x105 => inst.id = InstID::WFI,
                         0x302 => inst.id = InstID::MRET,
-                        _ => self.dump_invalid_inst(inst),
+                        0x702 => inst.id = InstID::MNRET,
+                        0x7b2 => inst.id = InstID::DRET,
+                        _ => match funct12 >> 5 {
+                            0x09 => inst.id = InstID::SFENCE_VMA,
+                            0x11 => inst.id = InstID::HFENCE_VVMA,
+                            0x31 => inst.id = InstID::HFENCE_GVMA,
+                            _ => self.dump_invalid_inst(inst),
+                        },
                     }
                 }
                 0x1 => inst.id = InstID::CSRRW,
                 0x2 => inst.id = InstID::CSRRS,
+                0x4 if inst_bytes & 0xb3c0_0000 == 0x81c0_0000 => inst.id = InstID::MOP_R,
+                0x4 if inst_bytes & 0xb200_0000 == 0x8200_0000 => inst.id = InstID::MOP_RR,
+                0x4 => match ((inst_bytes >> 25) & 0x7f, (inst_bytes >> 20) & 0x1f) {
+                    (0x30, 0x0) => inst.id = InstID::HLV_B,
+                    (0x30, 0x1) => inst.id = InstID::HLV_BU,
+                    (0x32, 0x0) => inst.id = InstID::HLV_H,
+                    (0x32, 0x1) => inst.id = InstID::HLV_HU,
+                    (0x32, 0x3) => inst.id = InstID::HLVX_HU,
+                    (0x34, 0x0) => inst.id = InstID::HLV_W,
+                    (0x34, 0x1) => inst.id = InstID::HLV_WU,
+                    (0x34, 0x3) => inst.id = InstID::HLVX_WU,
+                    (0x36, 0x0) => inst.id = InstID::HLV_D,
+                    (0x31, _) => inst.id = InstID::HSV_B,
+                    (0x33, _) => inst.id = InstID::HSV_H,
+                    (0x35, _) => inst.id = InstID::HSV_W,
+                    (0x37, _) => inst.id = InstID::HSV_D,
+                    _ => self.dump_invalid_inst(inst),
+                },
                 0x5 => inst.id = InstID::CSRRWI,
                 0x6 => inst.id = InstID::CSRRSI,
                 0x7 => inst.id = InstID::CSRRCI,
@@ -469,6 +783,275 @@ impl InstDecoder {
         }
     }
 
+    // Zcmp/Zcmt live in the c.fsdsp encoding space, reserved encodings are left as c.fsdsp
+    fn decode_inst_zcm(&self, inst_bytes: AddressType, inst: &mut InstType) {
+        let rlist = (inst_bytes >> 4) & 0xf;
+        let r1s = (inst_bytes >> 7) & 0x7;
+        let r2s = (inst_bytes >> 2) & 0x7;
+        inst.id = match ((inst_bytes >> 10) & 0x7, (inst_bytes >> 8) & 0x1f) {
+            (0x0, _) if (inst_bytes >> 2) & 0xff < 32 => InstID::CM_JT,
+            (0x0, _) => InstID::CM_JALT,
+            (0x3, _) => match (inst_bytes >> 5) & 0x3 {
+                0x1 if r1s != r2s => InstID::CM_MVSA01,
+                0x3 => InstID::CM_MVA01S,
+                _ => InstID::C_FSDSP,
+            },
+            (_, 0x18) if rlist >= 4 => InstID::CM_PUSH,
+            (_, 0x1a) if rlist >= 4 => InstID::CM_POP,
+            (_, 0x1c) if rlist >= 4 => InstID::CM_POPRETZ,
+            (_, 0x1e) if rlist >= 4 => InstID::CM_POPRET,
+            (_, _) => InstID::C_FSDSP,
+        };
+    }
+
+    // Vector loads/stores share LOAD-FP/STORE-FP, the width field selects the EEW
+    fn decode_inst_vector_mem(&self, inst_bytes: AddressType, inst: &mut InstType, is_store: bool) {
+        let mew = (inst_bytes >> 28) & 1;
+        let mop = (inst_bytes >> 26) & 0x3;
+        let umop = (inst_bytes >> 20) & 0x1f;
+        if mew != 0 {
+            self.dump_invalid_inst(inst);
+            return;
+        }
+        match (is_store, mop, umop) {
+            (false, 0, 0x0) => inst.id = InstID::VLE_V,
+            (false, 0, 0x8) => inst.id = InstID::VLR_V,
+            (false, 0, 0xb) => inst.id = InstID::VLM_V,
+            (false, 0, 0x10) => inst.id = InstID::VLEFF_V,
+            (false, 1, _) => inst.id = InstID::VLUXEI_V,
+            (false, 2, _) => inst.id = InstID::VLSE_V,
+            (false, 3, _) => inst.id = InstID::VLOXEI_V,
+            (true, 0, 0x0) => inst.id = InstID::VSE_V,
+            (true, 0, 0x8) => inst.id = InstID::VSR_V,
+            (true, 0, 0xb) => inst.id = InstID::VSM_V,
+            (true, 1, _) => inst.id = InstID::VSUXEI_V,
+            (true, 2, _) => inst.id = InstID::VSSE_V,
+            (true, 3, _) => inst.id = InstID::VSOXEI_V,
+            _ => self.dump_invalid_inst(inst),
+        }
+    }
+
+    // funct3 selects the operand category: OPIVV=0, OPFVV=1, OPMVV=2, OPIVI=3,
+    // OPIVX=4, OPFVF=5, OPMVX=6, OPCFG=7
+    fn decode_inst_vector(&self, inst_bytes: AddressType, inst: &mut InstType) {
+        let funct3 = (inst_bytes >> 12) & 0x7;
+        let funct6 = (inst_bytes >> 26) & 0x3f;
+        let vm = (inst_bytes >> 25) & 1;
+        let vs1 = (inst_bytes >> 15) & 0x1f;
+        let vs2 = (inst_bytes >> 20) & 0x1f;
+        if funct3 == 7 {
+            match inst_bytes >> 30 {
+                0 | 1 => inst.id = InstID::VSETVLI,
+                3 => inst.id = InstID::VSETIVLI,
+                _ if funct6 == 0x20 && vm == 0 => inst.id = InstID::VSETVL,
+                _ => self.dump_invalid_inst(inst),
+            }
+            return;
+        }
+
+        inst.id = match (funct3, funct6) {
+            (0 | 3 | 4, 0x00) => InstID::VADD,
+            (0 | 4, 0x02) => InstID::VSUB,
+            (3 | 4, 0x03) => InstID::VRSUB,
+            (0 | 4, 0x04) => InstID::VMINU,
+            (0 | 4, 0x05) => InstID::VMIN,
+            (0 | 4, 0x06) => InstID::VMAXU,
+            (0 | 4, 0x07) => InstID::VMAX,
+            (0 | 3 | 4, 0x09) => InstID::VAND,
+            (0 | 3 | 4, 0x0a) => InstID::VOR,
+            (0 | 3 | 4, 0x0b) => InstID::VXOR,
+            (0 | 3 | 4, 0x0c) => InstID::VRGATHER,
+            (0, 0x0e) => InstID::VRGATHEREI16,
+            (3 | 4, 0x0e) => InstID::VSLIDEUP,
+            (3 | 4, 0x0f) => InstID::VSLIDEDOWN,
+            (0 | 3 | 4, 0x10) if vm == 0 => InstID::VADC,
+            (0 | 3 | 4, 0x11) => InstID::VMADC,
+            (0 | 4, 0x12) if vm == 0 => InstID::VSBC,
+            (0 | 4, 0x13) => InstID::VMSBC,
+            (0 | 3 | 4, 0x17) if vm == 0 => InstID::VMERGE,
+            (0 | 3 | 4, 0x17) if vs2 == 0 => InstID::VMV_V,
+            (0 | 3 | 4, 0x18) => InstID::VMSEQ,
+            (0 | 3 | 4, 0x19) => InstID::VMSNE,
+            (0 | 4, 0x1a) => InstID::VMSLTU,
+            (0 | 4, 0x1b) => InstID::VMSLT,
+            (0 | 3 | 4, 0x1c) => InstID::VMSLEU,
+            (0 | 3 | 4, 0x1d) => InstID::VMSLE,
+            (3 | 4, 0x1e) => InstID::VMSGTU,
+            (3 | 4, 0x1f) => InstID::VMSGT,
+            (0 | 3 | 4, 0x20) => InstID::VSADDU,
+            (0 | 3 | 4, 0x21) => InstID::VSADD,
+            (0 | 4, 0x22) => InstID::VSSUBU,
+            (0 | 4, 0x23) => InstID::VSSUB,
+            (0 | 3 | 4, 0x25) => InstID::VSLL,
+            (0 | 4, 0x27) => InstID::VSMUL,
+            (3, 0x27) if vm == 1 => InstID::VMVNR_V,
+            (0 | 3 | 4, 0x28) => InstID::VSRL,
+            (0 | 3 | 4, 0x29) => InstID::VSRA,
+            (0 | 3 | 4, 0x2a) => InstID::VSSRL,
+            (0 | 3 | 4, 0x2b) => InstID::VSSRA,
+            (0 | 3 | 4, 0x2c) => InstID::VNSRL,
+            (0 | 3 | 4, 0x2d) => InstID::VNSRA,
+            (0 | 3 | 4, 0x2e) => InstID::VNCLIPU,
+            (0 | 3 | 4, 0x2f) => InstID::VNCLIP,
+            (0, 0x30) => InstID::VWREDSUMU,
+            (0, 0x31) => InstID::VWREDSUM,
+
+            (2, 0x00) => InstID::VREDSUM,
+            (2, 0x01) => InstID::VREDAND,
+            (2, 0x02) => InstID::VREDOR,
+            (2, 0x03) => InstID::VREDXOR,
+            (2, 0x04) => InstID::VREDMINU,
+            (2, 0x05) => InstID::VREDMIN,
+            (2, 0x06) => InstID::VREDMAXU,
+            (2, 0x07) => InstID::VREDMAX,
+            (2 | 6, 0x08) => InstID::VAADDU,
+            (2 | 6, 0x09) => InstID::VAADD,
+            (2 | 6, 0x0a) => InstID::VASUBU,
+            (2 | 6, 0x0b) => InstID::VASUB,
+            (6, 0x0e) => InstID::VSLIDE1UP,
+            (6, 0x0f) => InstID::VSLIDE1DOWN,
+            (2, 0x10) if vm == 1 => match vs1 {
+                0x00 => InstID::VMV_X_S,
+                0x10 => InstID::VCPOP_M,
+                0x11 => InstID::VFIRST_M,
+                _ => InstID::INVALID,
+            },
+            (6, 0x10) if vm == 1 && vs2 == 0 => InstID::VMV_S_X,
+            (2, 0x12) => match vs1 {
+                0x02 => InstID::VZEXT_VF8,
+                0x03 => InstID::VSEXT_VF8,
+                0x04 => InstID::VZEXT_VF4,
+                0x05 => InstID::VSEXT_VF4,
+                0x06 => InstID::VZEXT_VF2,
+                0x07 => InstID::VSEXT_VF2,
+                _ => InstID::INVALID,
+            },
+            (2, 0x14) => match vs1 {
+                0x01 => InstID::VMSBF_M,
+                0x02 => InstID::VMSOF_M,
+                0x03 => InstID::VMSIF_M,
+                0x10 => InstID::VIOTA_M,
+                0x11 if vs2 == 0 => InstID::VID_V,
+                _ => InstID::INVALID,
+            },
+            (2, 0x17) if vm == 1 => InstID::VCOMPRESS,
+            (2, 0x18) if vm == 1 => InstID::VMANDN,
+            (2, 0x19) if vm == 1 => InstID::VMAND,
+            (2, 0x1a) if vm == 1 => InstID::VMOR,
+            (2, 0x1b) if vm == 1 => InstID::VMXOR,
+            (2, 0x1c) if vm == 1 => InstID::VMORN,
+            (2, 0x1d) if vm == 1 => InstID::VMNAND,
+            (2, 0x1e) if vm == 1 => InstID::VMNOR,
+            (2, 0x1f) if vm == 1 => InstID::VMXNOR,
+            (2 | 6, 0x20) => InstID::VDIVU,
+            (2 | 6, 0x21) => InstID::VDIV,
+            (2 | 6, 0x22) => InstID::VREMU,
+            (2 | 6, 0x23) => InstID::VREM,
+            (2 | 6, 0x24) => InstID::VMULHU,
+            (2 | 6, 0x25) => InstID::VMUL,
+            (2 | 6, 0x26) => InstID::VMULHSU,
+            (2 | 6, 0x27) => InstID::VMULH,
+            (2 | 6, 0x29) => InstID::VMADD,
+            (2 | 6, 0x2b) => InstID::VNMSUB,
+            (2 | 6, 0x2d) => InstID::VMACC,
+            (2 | 6, 0x2f) => InstID::VNMSAC,
+            (2 | 6, 0x30) => InstID::VWADDU,
+            (2 | 6, 0x31) => InstID::VWADD,
+            (2 | 6, 0x32) => InstID::VWSUBU,
+            (2 | 6, 0x33) => InstID::VWSUB,
+            (2 | 6, 0x34) => InstID::VWADDU_W,
+            (2 | 6, 0x35) => InstID::VWADD_W,
+            (2 | 6, 0x36) => InstID::VWSUBU_W,
+            (2 | 6, 0x37) => InstID::VWSUB_W,
+            (2 | 6, 0x38) => InstID::VWMULU,
+            (2 | 6, 0x3a) => InstID::VWMULSU,
+            (2 | 6, 0x3b) => InstID::VWMUL,
+            (2 | 6, 0x3c) => InstID::VWMACCU,
+            (2 | 6, 0x3d) => InstID::VWMACC,
+            (6, 0x3e) => InstID::VWMACCUS,
+            (2 | 6, 0x3f) => InstID::VWMACCSU,
+
+            (1 | 5, 0x00) => InstID::VFADD,
+            (1, 0x01) => InstID::VFREDUSUM,
+            (1 | 5, 0x02) => InstID::VFSUB,
+            (1, 0x03) => InstID::VFREDOSUM,
+            (1 | 5, 0x04) => InstID::VFMIN,
+            (1, 0x05) => InstID::VFREDMIN,
+            (1 | 5, 0x06) => InstID::VFMAX,
+            (1, 0x07) => InstID::VFREDMAX,
+            (1 | 5, 0x08) => InstID::VFSGNJ,
+            (1 | 5, 0x09) => InstID::VFSGNJN,
+            (1 | 5, 0x0a) => InstID::VFSGNJX,
+            (5, 0x0e) => InstID::VFSLIDE1UP,
+            (5, 0x0f) => InstID::VFSLIDE1DOWN,
+            (1, 0x10) if vm == 1 && vs1 == 0 => InstID::VFMV_F_S,
+            (5, 0x10) if vm == 1 && vs2 == 0 => InstID::VFMV_S_F,
+            (1, 0x12) => match vs1 {
+                0x00 => InstID::VFCVT_XU_F_V,
+                0x01 => InstID::VFCVT_X_F_V,
+                0x02 => InstID::VFCVT_F_XU_V,
+                0x03 => InstID::VFCVT_F_X_V,
+                0x06 => InstID::VFCVT_RTZ_XU_F_V,
+                0x07 => InstID::VFCVT_RTZ_X_F_V,
+                0x08 => InstID::VFWCVT_XU_F_V,
+                0x09 => InstID::VFWCVT_X_F_V,
+                0x0a => InstID::VFWCVT_F_XU_V,
+                0x0b => InstID::VFWCVT_F_X_V,
+                0x0c => InstID::VFWCVT_F_F_V,
+                0x0e => InstID::VFWCVT_RTZ_XU_F_V,
+                0x0f => InstID::VFWCVT_RTZ_X_F_V,
+                0x10 => InstID::VFNCVT_XU_F_W,
+                0x11 => InstID::VFNCVT_X_F_W,
+                0x12 => InstID::VFNCVT_F_XU_W,
+                0x13 => InstID::VFNCVT_F_X_W,
+                0x14 => InstID::VFNCVT_F_F_W,
+                0x15 => InstID::VFNCVT_ROD_F_F_W,
+                0x16 => InstID::VFNCVT_RTZ_XU_F_W,
+                0x17 => InstID::VFNCVT_RTZ_X_F_W,
+                _ => InstID::INVALID,
+            },
+            (1, 0x13) => match vs1 {
+                0x00 => InstID::VFSQRT_V,
+                0x04 => InstID::VFRSQRT7_V,
+                0x05 => InstID::VFREC7_V,
+                0x10 => InstID::VFCLASS_V,
+                _ => InstID::INVALID,
+            },
+            (5, 0x17) if vm == 0 => InstID::VFMERGE,
+            (5, 0x17) if vs2 == 0 => InstID::VFMV_V_F,
+            (1 | 5, 0x18) => InstID::VMFEQ,
+            (1 | 5, 0x19) => InstID::VMFLE,
+            (1 | 5, 0x1b) => InstID::VMFLT,
+            (1 | 5, 0x1c) => InstID::VMFNE,
+            (5, 0x1d) => InstID::VMFGT,
+            (5, 0x1f) => InstID::VMFGE,
+            (1 | 5, 0x20) => InstID::VFDIV,
+            (5, 0x21) => InstID::VFRDIV,
+            (1 | 5, 0x24) => InstID::VFMUL,
+            (5, 0x27) => InstID::VFRSUB,
+            (1 | 5, 0x28) => InstID::VFMADD,
+            (1 | 5, 0x29) => InstID::VFNMADD,
+            (1 | 5, 0x2a) => InstID::VFMSUB,
+            (1 | 5, 0x2b) => InstID::VFNMSUB,
+            (1 | 5, 0x2c) => InstID::VFMACC,
+            (1 | 5, 0x2d) => InstID::VFNMACC,
+            (1 | 5, 0x2e) => InstID::VFMSAC,
+            (1 | 5, 0x2f) => InstID::VFNMSAC,
+            (1 | 5, 0x30) => InstID::VFWADD,
+            (1, 0x31) => InstID::VFWREDUSUM,
+            (1 | 5, 0x32) => InstID::VFWSUB,
+            (1, 0x33) => InstID::VFWREDOSUM,
+            (1 | 5, 0x34) => InstID::VFWADD_W,
+            (1 | 5, 0x36) => InstID::VFWSUB_W,
+            (1 | 5, 0x38) => InstID::VFWMUL,
+            (1 | 5, 0x3c) => InstID::VFWMACC,
+            (1 | 5, 0x3d) => InstID::VFWNMACC,
+            (1 | 5, 0x3e) => InstID::VFWMSAC,
+            (1 | 5, 0x3f) => InstID::VFWNMSAC,
+            _ => InstID::INVALID,
+        };
+    }
+
     fn dump_invalid_inst(&self, inst: &mut InstType) {
         inst.id = InstID::INVALID;
     }
@@ -483,10 +1066,455 @@ mod tests {
     fn test_decode() {
         let decoder = InstDecoder::new();
         let inst_golden = inst_auipc_code(0, 0);
-        let inst = decoder.decode(inst_golden.data);
+        let inst = decoder.decode(inst_golden.data, |_| true);
 
         assert_eq!(4, inst.len);
         assert_eq!(InstID::AUIPC, inst.id);
         assert_eq!(inst_golden.data, inst.data);
     }
+
+    #[test]
+    fn test_decode_disabled_extension() {
+        let decoder = InstDecoder::new();
+        let inst_golden = inst_c_add_code(2, 3);
+        let inst = decoder.decode(inst_golden.data, |ext| ext != Extension::C);
+        assert_eq!(InstID::INVALID, inst.id);
+
+        let inst = decoder.decode(inst_golden.data, |_| true);
+        assert_eq!(InstID::C_ADD, inst.id);
+    }
+
+    #[test]
+    fn test_decode_bitmanip() {
+        let decoder = InstDecoder::new();
+        let golden = [
+            inst_add_uw_code(1, 2, 3),
+            inst_andn_code(1, 2, 3),
+            inst_bexti_code(1, 2, 63),
+            inst_binv_code(1, 2, 3),
+            inst_bseti_code(1, 2, 33),
+            inst_clmul_code(1, 2, 3),
+            inst_clmulh_code(1, 2, 3),
+            inst_clmulr_code(1, 2, 3),
+            inst_clz_code(1, 2),
+            inst_cpopw_code(1, 2),
+            inst_ctz_code(1, 2),
+            inst_max_code(1, 2, 3),
+            inst_minu_code(1, 2, 3),
+            inst_orc_b_code(1, 2),
+            inst_rev8_code(1, 2),
+            inst_rolw_code(1, 2, 3),
+            inst_rori_code(1, 2, 40),
+            inst_sext_b_code(1, 2),
+            inst_sh2add_code(1, 2, 3),
+            inst_sh3add_uw_code(1, 2, 3),
+            inst_slli_uw_code(1, 2, 35),
+            inst_xnor_code(1, 2, 3),
+            inst_zext_h_code(1, 2),
+        ];
+        for inst_golden in golden.iter() {
+            let inst = decoder.decode(inst_golden.data, |_| true);
+            assert_eq!(inst_golden.id, inst.id);
+        }
+
+        let inst = decoder.decode(inst_clz_code(1, 2).data, |ext| ext != Extension::Zbb);
+        assert_eq!(InstID::INVALID, inst.id);
+    }
+
+    #[test]
+    fn test_decode_zfh() {
+        let decoder = InstDecoder::new();
+        let golden = [
+            inst_fadd_h_code(1, 2, 3),
+            inst_fcvt_s_h_code(1, 2),
+            inst_fcvt_w_h_code(1, 2),
+            inst_flh_code(1, 2, 0x10),
+            inst_fmv_h_x_code(1, 2),
+            inst_fmv_x_h_code(1, 2),
+        ];
+        for inst_golden in golden.iter() {
+            let inst = decoder.decode(inst_golden.data, |_| true);
+            assert_eq!(inst_golden.id, inst.id);
+        }
+
+        // Zfhmin only provides moves, loads/stores and conversions
+        let is_enabled = |ext| ext != Extension::Zfh;
+        let inst = decoder.decode(inst_fadd_h_code(1, 2, 3).data, is_enabled);
+        assert_eq!(InstID::INVALID, inst.id);
+        let inst = decoder.decode(inst_fcvt_s_h_code(1, 2).data, is_enabled);
+        assert_eq!(InstID::FCVT_S_H, inst.id);
+    }
+
+    #[test]
+    fn test_decode_zfa() {
+        let decoder = InstDecoder::new();
+        let golden = [
+            inst_fp_code(InstID::FLI_S, 0x78, 1, 16, 1, 0),
+            inst_fp_code(InstID::FLI_D, 0x79, 1, 31, 1, 0),
+            inst_fp_code(InstID::FLI_H, 0x7a, 1, 0, 1, 0),
+            inst_fp_code(InstID::FMINM_S, 0x14, 1, 2, 3, 2),
+            inst_fp_code(InstID::FMAXM_D, 0x15, 1, 2, 3, 3),
+            inst_fp_code(InstID::FROUND_S, 0x20, 1, 2, 4, 7),
+            inst_fp_code(InstID::FROUNDNX_H, 0x22, 1, 2, 5, 0),
+            inst_fp_code(InstID::FCVTMOD_W_D, 0x61, 1, 2, 8, 1),
+            inst_fp_code(InstID::FLEQ_D, 0x51, 1, 2, 3, 4),
+            inst_fp_code(InstID::FLTQ_S, 0x50, 1, 2, 3, 5),
+        ];
+        for inst_golden in golden.iter() {
+            let inst = decoder.decode(inst_golden.data, |_| true);
+            assert_eq!(inst_golden.id, inst.id);
+        }
+
+        // fcvtmod.w.d is reserved for any rounding mode other than RTZ
+        let fcvtmod_rne = inst_fp_code(InstID::FCVTMOD_W_D, 0x61, 1, 2, 8, 0);
+        let inst = decoder.decode(fcvtmod_rne.data, |_| true);
+        assert_eq!(InstID::INVALID, inst.id);
+
+        let is_enabled = |ext| ext != Extension::Zfa;
+        let fli = inst_fp_code(InstID::FLI_S, 0x78, 1, 16, 1, 0);
+        let inst = decoder.decode(fli.data, is_enabled);
+        assert_eq!(InstID::INVALID, inst.id);
+    }
+
+    #[test]
+    fn test_decode_zfinx() {
+        let decoder = InstDecoder::new();
+        let is_enabled = |ext| ext != Extension::F && ext != Extension::D;
+
+        // Computational instructions come from Zfinx/Zdinx, moves and loads do not
+        let inst = decoder.decode(inst_fadd_s_code(1, 2, 3, 0).data, is_enabled);
+        assert_eq!(InstID::FADD_S, inst.id);
+        let fadd_d = inst_fp_code(InstID::FADD_D, 0x01, 1, 2, 3, 0);
+        let inst = decoder.decode(fadd_d.data, is_enabled);
+        assert_eq!(InstID::FADD_D, inst.id);
+        let inst = decoder.decode(inst_fmv_w_x_code(1, 2).data, is_enabled);
+        assert_eq!(InstID::INVALID, inst.id);
+        let inst = decoder.decode(inst_fmv_x_w_code(1, 2).data, is_enabled);
+        assert_eq!(InstID::INVALID, inst.id);
+        let vfadd = inst_vector_code(InstID::VFADD, 0x00, 5, 1, 1, 2, 3);
+        let inst = decoder.decode(vfadd.data, is_enabled);
+        assert_eq!(InstID::INVALID, inst.id);
+
+        let is_enabled = |ext| is_enabled(ext) && ext != Extension::Zdinx;
+        let inst = decoder.decode(fadd_d.data, is_enabled);
+        assert_eq!(InstID::INVALID, inst.id);
+    }
+
+    #[test]
+    fn test_decode_zacas_zabha() {
+        let decoder = InstDecoder::new();
+        let golden = [
+            inst_amo_code(InstID::AMOADD_B, 0x00, 0x0, 1, 2, 3),
+            inst_amo_code(InstID::AMOSWAP_H, 0x01, 0x1, 1, 2, 3),
+            inst_amo_code(InstID::AMOMAXU_B, 0x1c, 0x0, 1, 2, 3),
+            inst_amo_code(InstID::AMOCAS_B, 0x05, 0x0, 1, 2, 3),
+            inst_amo_code(InstID::AMOCAS_H, 0x05, 0x1, 1, 2, 3),
+            inst_amo_code(InstID::AMOCAS_W, 0x05, 0x2, 1, 2, 3),
+            inst_amo_code(InstID::AMOCAS_D, 0x05, 0x3, 1, 2, 3),
+            inst_amo_code(InstID::AMOCAS_Q, 0x05, 0x4, 2, 2, 4),
+        ];
+        for code in golden {
+            let inst = decoder.decode(code.data, |_| true);
+            assert_eq!(code.id, inst.id);
+        }
+
+        // LR/SC have no byte or halfword forms
+        let inst = decoder.decode(
+            inst_amo_code(InstID::INVALID, 0x02, 0x0, 1, 2, 0).data,
+            |_| true,
+        );
+        assert_eq!(InstID::INVALID, inst.id);
+
+        // Byte/halfword CAS needs both extensions
+        let is_enabled = |ext| ext != Extension::Zabha;
+        let amocas_b = inst_amo_code(InstID::AMOCAS_B, 0x05, 0x0, 1, 2, 3);
+        assert_eq!(
+            InstID::INVALID,
+            decoder.decode(amocas_b.data, is_enabled).id
+        );
+        let amocas_w = inst_amo_code(InstID::AMOCAS_W, 0x05, 0x2, 1, 2, 3);
+        assert_eq!(
+            InstID::AMOCAS_W,
+            decoder.decode(amocas_w.data, is_enabled).id
+        );
+    }
+
+    #[test]
+    fn test_decode_zicond_hints() {
+        let decoder = InstDecoder::new();
+        let golden = [
+            inst_czero_eqz_code(1, 2, 3),
+            inst_czero_nez_code(1, 2, 3),
+            inst_mop_r_code(0, 1, 2),
+            inst_mop_r_code(31, 1, 2),
+            inst_mop_rr_code(0, 1, 2, 3),
+            inst_mop_rr_code(7, 1, 2, 31),
+        ];
+        for code in golden {
+            let inst = decoder.decode(code.data, |_| true);
+            assert_eq!(code.id, inst.id);
+        }
+
+        // pause, ntl.p1, c.ntl.all, c.mop.1 and c.mop.15
+        let hints = [
+            (0x0100000f, InstID::PAUSE),
+            (0x00200033, InstID::NTL_P1),
+            (inst_c_add_code(0, 5).data, InstID::C_NTL_ALL),
+            (0x6081, InstID::C_MOP),
+            (0x6781, InstID::C_MOP),
+        ];
+        for (data, id) in hints {
+            assert_eq!(id, decoder.decode(data, |_| true).id);
+        }
+        assert_eq!(InstID::C_LUI, decoder.decode(0x6085, |_| true).id);
+
+        // Disabled hints fall back to their base instruction, the others are reserved
+        let is_enabled = |ext| {
+            !matches!(
+                ext,
+                Extension::Zicond
+                    | Extension::Zihintpause
+                    | Extension::Zihintntl
+                    | Extension::Zimop
+                    | Extension::Zcmop
+            )
+        };
+        assert_eq!(InstID::FENCE, decoder.decode(0x0100000f, is_enabled).id);
+        assert_eq!(InstID::ADD, decoder.decode(0x00200033, is_enabled).id);
+        let c_ntl = inst_c_add_code(0, 5).data;
+        assert_eq!(InstID::C_ADD, decoder.decode(c_ntl, is_enabled).id);
+        assert_eq!(InstID::INVALID, decoder.decode(0x6081, is_enabled).id);
+        let mop = inst_mop_r_code(0, 1, 2).data;
+        assert_eq!(InstID::INVALID, decoder.decode(mop, is_enabled).id);
+        let czero = inst_czero_eqz_code(1, 2, 3).data;
+        assert_eq!(InstID::INVALID, decoder.decode(czero, is_enabled).id);
+        let is_enabled = |ext| ext != Extension::C;
+        assert_eq!(InstID::INVALID, decoder.decode(c_ntl, is_enabled).id);
+    }
+
+    #[test]
+    fn test_decode_zcb_zcmp_zcmt() {
+        let decoder = InstDecoder::new();
+        let golden = [
+            inst_zcb_mem_code(InstID::C_LBU, 0x0, 8, 9, 3),
+            inst_zcb_mem_code(InstID::C_LHU, 0x1, 8, 9, 2),
+            inst_zcb_mem_code(InstID::C_LH, 0x1, 8, 9, 2),
+            inst_zcb_mem_code(InstID::C_SB, 0x2, 8, 9, 1),
+            inst_zcb_mem_code(InstID::C_SH, 0x3, 8, 9, 2),
+            inst_zcb_unary_code(InstID::C_ZEXT_B, 0x0, 8),
+            inst_zcb_unary_code(InstID::C_SEXT_B, 0x1, 8),
+            inst_zcb_unary_code(InstID::C_ZEXT_H, 0x2, 8),
+            inst_zcb_unary_code(InstID::C_SEXT_H, 0x3, 8),
+            inst_zcb_unary_code(InstID::C_ZEXT_W, 0x4, 8),
+            inst_zcb_unary_code(InstID::C_NOT, 0x5, 8),
+            inst_c_mul_code(8, 9),
+            inst_cm_stack_code(InstID::CM_PUSH, 0x18, 4, 0),
+            inst_cm_stack_code(InstID::CM_POP, 0x1a, 15, 3),
+            inst_cm_stack_code(InstID::CM_POPRETZ, 0x1c, 5, 1),
+            inst_cm_stack_code(InstID::CM_POPRET, 0x1e, 6, 0),
+            inst_cm_mv_code(InstID::CM_MVSA01, 0x1, 0, 1),
+            inst_cm_mv_code(InstID::CM_MVA01S, 0x3, 2, 2),
+            inst_cm_jt_code(5),
+            inst_cm_jt_code(32),
+        ];
+        let is_enabled = |ext| ext != Extension::D;
+        for code in golden {
+            let inst = decoder.decode(code.data, is_enabled);
+            assert_eq!(code.id, inst.id);
+        }
+
+        // Reserved Zcmp encodings and disabled Zcmp/Zcmt are left to c.fsdsp
+        let push = inst_cm_stack_code(InstID::CM_PUSH, 0x18, 3, 0);
+        assert_eq!(InstID::INVALID, decoder.decode(push.data, is_enabled).id);
+        assert_eq!(InstID::C_FSDSP, decoder.decode(push.data, |_| true).id);
+        let mvsa01 = inst_cm_mv_code(InstID::CM_MVSA01, 0x1, 2, 2);
+        assert_eq!(InstID::INVALID, decoder.decode(mvsa01.data, is_enabled).id);
+        let is_enabled = |ext| ext != Extension::Zcmp && ext != Extension::Zcmt;
+        let push = inst_cm_stack_code(InstID::CM_PUSH, 0x18, 4, 0);
+        assert_eq!(InstID::C_FSDSP, decoder.decode(push.data, is_enabled).id);
+        let jt = inst_cm_jt_code(5);
+        assert_eq!(InstID::C_FSDSP, decoder.decode(jt.data, is_enabled).id);
+
+        // Zcb forms of Zbb/Zba/M instructions need those extensions too
+        let is_enabled = |ext| ext != Extension::Zbb && ext != Extension::M;
+        let sext_b = inst_zcb_unary_code(InstID::C_SEXT_B, 0x1, 8);
+        assert_eq!(InstID::INVALID, decoder.decode(sext_b.data, is_enabled).id);
+        let mul = inst_c_mul_code(8, 9);
+        assert_eq!(InstID::INVALID, decoder.decode(mul.data, is_enabled).id);
+        let zext_b = inst_zcb_unary_code(InstID::C_ZEXT_B, 0x0, 8);
+        assert_eq!(InstID::C_ZEXT_B, decoder.decode(zext_b.data, is_enabled).id);
+    }
+
+    #[test]
+    fn test_decode_zicbom_zicboz_zicbop() {
+        let decoder = InstDecoder::new();
+        let golden = [
+            inst_cbo_code(InstID::CBO_INVAL, 0x0, 1),
+            inst_cbo_code(InstID::CBO_CLEAN, 0x1, 1),
+            inst_cbo_code(InstID::CBO_FLUSH, 0x2, 1),
+            inst_cbo_code(InstID::CBO_ZERO, 0x4, 1),
+            inst_prefetch_code(InstID::PREFETCH_I, 0x0, 1, 0x40),
+            inst_prefetch_code(InstID::PREFETCH_R, 0x1, 1, 0x7e0),
+            inst_prefetch_code(InstID::PREFETCH_W, 0x3, 1, 0),
+        ];
+        for code in golden {
+            let inst = decoder.decode(code.data, |_| true);
+            assert_eq!(code.id, inst.id);
+        }
+        let inst = decoder.decode(inst_cbo_code(InstID::INVALID, 0x3, 1).data, |_| true);
+        assert_eq!(InstID::INVALID, inst.id);
+
+        // Without Zicbop the prefetches are plain ori hints, cbo.* are illegal
+        let is_enabled = |ext| !matches!(ext, Extension::Zicbom | Extension::Zicbop);
+        let prefetch = inst_prefetch_code(InstID::PREFETCH_W, 0x3, 1, 0);
+        assert_eq!(InstID::ORI, decoder.decode(prefetch.data, is_enabled).id);
+        let flush = inst_cbo_code(InstID::CBO_FLUSH, 0x2, 1);
+        assert_eq!(InstID::INVALID, decoder.decode(flush.data, is_enabled).id);
+        let zero = inst_cbo_code(InstID::CBO_ZERO, 0x4, 1);
+        assert_eq!(InstID::CBO_ZERO, decoder.decode(zero.data, is_enabled).id);
+    }
+
+    #[test]
+    fn test_decode_crypto() {
+        let decoder = InstDecoder::new();
+        let golden = [
+            inst_op_code(InstID::AES64ES, 0x33, 0x19, 0, 1, 2, 3),
+            inst_op_code(InstID::AES64ESM, 0x33, 0x1b, 0, 1, 2, 3),
+            inst_op_code(InstID::AES64DS, 0x33, 0x1d, 0, 1, 2, 3),
+            inst_op_code(InstID::AES64DSM, 0x33, 0x1f, 0, 1, 2, 3),
+            inst_op_code(InstID::AES64KS2, 0x33, 0x3f, 0, 1, 2, 3),
+            inst_op_code(InstID::SM4ED, 0x33, 0x78, 0, 1, 2, 3),
+            inst_op_code(InstID::SM4KS, 0x33, 0x1a, 0, 1, 2, 3),
+            inst_op_code(InstID::XPERM4, 0x33, 0x14, 2, 1, 2, 3),
+            inst_op_code(InstID::XPERM8, 0x33, 0x14, 4, 1, 2, 3),
+            inst_op_code(InstID::PACK, 0x33, 0x4, 4, 1, 2, 3),
+            inst_op_code(InstID::PACKH, 0x33, 0x4, 7, 1, 2, 3),
+            inst_op_code(InstID::PACKW, 0x3b, 0x4, 4, 1, 2, 3),
+            inst_op_imm_code(InstID::AES64IM, 0x300, 1, 1, 2),
+            inst_op_imm_code(InstID::AES64KS1I, 0x31a, 1, 1, 2),
+            inst_op_imm_code(InstID::SHA256SIG0, 0x102, 1, 1, 2),
+            inst_op_imm_code(InstID::SHA256SUM1, 0x101, 1, 1, 2),
+            inst_op_imm_code(InstID::SHA512SIG1, 0x107, 1, 1, 2),
+            inst_op_imm_code(InstID::SHA512SUM0, 0x104, 1, 1, 2),
+            inst_op_imm_code(InstID::SM3P0, 0x108, 1, 1, 2),
+            inst_op_imm_code(InstID::SM3P1, 0x109, 1, 1, 2),
+            inst_op_imm_code(InstID::BREV8, 0x687, 5, 1, 2),
+        ];
+        for code in golden {
+            let inst = decoder.decode(code.data, |_| true);
+            assert_eq!(code.id, inst.id);
+        }
+        let ks1i = inst_op_imm_code(InstID::INVALID, 0x31b, 1, 1, 2);
+        assert_eq!(InstID::INVALID, decoder.decode(ks1i.data, |_| true).id);
+
+        // Instructions shared with Zbb/Zbc are also enabled by Zbkb/Zbkc
+        let is_enabled = |ext| !matches!(ext, Extension::Zbb | Extension::Zbc);
+        let rol = inst_op_code(InstID::ROL, 0x33, 0x30, 1, 1, 2, 3);
+        assert_eq!(InstID::ROL, decoder.decode(rol.data, is_enabled).id);
+        let clmul = inst_op_code(InstID::CLMUL, 0x33, 0x5, 1, 1, 2, 3);
+        assert_eq!(InstID::CLMUL, decoder.decode(clmul.data, is_enabled).id);
+        let is_enabled = |ext| !matches!(ext, Extension::Zbb | Extension::Zbkb);
+        assert_eq!(InstID::INVALID, decoder.decode(rol.data, is_enabled).id);
+        let is_enabled = |ext| !matches!(ext, Extension::Zkne);
+        let ks2 = inst_op_code(InstID::AES64KS2, 0x33, 0x3f, 0, 1, 2, 3);
+        assert_eq!(InstID::AES64KS2, decoder.decode(ks2.data, is_enabled).id);
+        let es = inst_op_code(InstID::AES64ES, 0x33, 0x19, 0, 1, 2, 3);
+        assert_eq!(InstID::INVALID, decoder.decode(es.data, is_enabled).id);
+    }
+
+    #[test]
+    fn test_decode_hypervisor() {
+        let decoder = InstDecoder::new();
+        let golden = [
+            inst_op_code(InstID::SRET, 0x73, 0x08, 0, 0, 0, 2),
+            inst_op_code(InstID::SFENCE_VMA, 0x73, 0x09, 0, 0, 1, 2),
+            inst_op_code(InstID::HFENCE_VVMA, 0x73, 0x11, 0, 0, 1, 2),
+            inst_op_code(InstID::HFENCE_GVMA, 0x73, 0x31, 0, 0, 1, 2),
+            inst_op_code(InstID::HLV_B, 0x73, 0x30, 4, 1, 2, 0),
+            inst_op_code(InstID::HLV_BU, 0x73, 0x30, 4, 1, 2, 1),
+            inst_op_code(InstID::HLV_H, 0x73, 0x32, 4, 1, 2, 0),
+            inst_op_code(InstID::HLV_HU, 0x73, 0x32, 4, 1, 2, 1),
+            inst_op_code(InstID::HLVX_HU, 0x73, 0x32, 4, 1, 2, 3),
+            inst_op_code(InstID::HLV_W, 0x73, 0x34, 4, 1, 2, 0),
+            inst_op_code(InstID::HLV_WU, 0x73, 0x34, 4, 1, 2, 1),
+            inst_op_code(InstID::HLVX_WU, 0x73, 0x34, 4, 1, 2, 3),
+            inst_op_code(InstID::HLV_D, 0x73, 0x36, 4, 1, 2, 0),
+            inst_op_code(InstID::HSV_B, 0x73, 0x31, 4, 0, 2, 3),
+            inst_op_code(InstID::HSV_H, 0x73, 0x33, 4, 0, 2, 3),
+            inst_op_code(InstID::HSV_W, 0x73, 0x35, 4, 0, 2, 3),
+            inst_op_code(InstID::HSV_D, 0x73, 0x37, 4, 0, 2, 3),
+        ];
+        for code in golden {
+            let inst = decoder.decode(code.data, |_| true);
+            assert_eq!(code.id, inst.id);
+        }
+        let is_enabled = |ext| ext != Extension::H;
+        let hlv = inst_op_code(InstID::HLV_D, 0x73, 0x36, 4, 1, 2, 0);
+        assert_eq!(InstID::INVALID, decoder.decode(hlv.data, is_enabled).id);
+        let sfence = inst_op_code(InstID::SFENCE_VMA, 0x73, 0x09, 0, 0, 1, 2);
+        assert_eq!(
+            InstID::SFENCE_VMA,
+            decoder.decode(sfence.data, is_enabled).id
+        );
+    }
+
+    #[test]
+    fn test_decode_mnret() {
+        let decoder = InstDecoder::new();
+        let mnret = inst_op_code(InstID::MNRET, 0x73, 0x38, 0, 0, 0, 2);
+        assert_eq!(InstID::MNRET, decoder.decode(mnret.data, |_| true).id);
+        let is_enabled = |ext| ext != Extension::Smrnmi;
+        assert_eq!(InstID::INVALID, decoder.decode(mnret.data, is_enabled).id);
+    }
+
+    #[test]
+    fn test_decode_debug() {
+        let decoder = InstDecoder::new();
+        assert_eq!(InstID::EBREAK, decoder.decode(0x0010_0073, |_| true).id);
+        assert_eq!(InstID::C_EBREAK, decoder.decode(0x9002, |_| true).id);
+        assert_eq!(InstID::C_JALR, decoder.decode(0x9082, |_| true).id);
+        assert_eq!(InstID::DRET, decoder.decode(0x7b20_0073, |_| true).id);
+        let is_enabled = |ext| ext != Extension::Sdext;
+        assert_eq!(InstID::INVALID, decoder.decode(0x7b20_0073, is_enabled).id);
+    }
+
+    #[test]
+    fn test_decode_vector() {
+        let decoder = InstDecoder::new();
+        let golden = [
+            inst_vsetvli_code(1, 2, 0xd1),
+            inst_vsetivli_code(1, 8, 0xd1),
+            inst_vle_v_code(1, 2, 6),
+            inst_vse_v_code(1, 2, 0),
+            inst_vector_code(InstID::VADD, 0x00, 0, 1, 1, 2, 3),
+            inst_vector_code(InstID::VADD, 0x00, 3, 0, 1, 2, 0x1f),
+            inst_vector_code(InstID::VRSUB, 0x03, 4, 1, 1, 2, 3),
+            inst_vector_code(InstID::VMERGE, 0x17, 0, 0, 1, 2, 3),
+            inst_vector_code(InstID::VMV_V, 0x17, 0, 1, 1, 0, 3),
+            inst_vector_code(InstID::VMVNR_V, 0x27, 3, 1, 2, 4, 1),
+            inst_vector_code(InstID::VWADDU, 0x30, 2, 1, 2, 4, 6),
+            inst_vector_code(InstID::VREDSUM, 0x00, 2, 1, 1, 2, 3),
+            inst_vector_code(InstID::VMV_X_S, 0x10, 2, 1, 1, 2, 0),
+            inst_vector_code(InstID::VZEXT_VF2, 0x12, 2, 1, 1, 2, 6),
+            inst_vector_code(InstID::VCPOP_M, 0x10, 2, 1, 1, 2, 0x10),
+            inst_vector_code(InstID::VID_V, 0x14, 2, 1, 1, 0, 0x11),
+            inst_vector_code(InstID::VCOMPRESS, 0x17, 2, 1, 1, 2, 3),
+            inst_vector_code(InstID::VSADDU, 0x20, 0, 1, 1, 2, 3),
+            inst_vector_code(InstID::VFADD, 0x00, 5, 1, 1, 2, 3),
+            inst_vector_code(InstID::VFSQRT_V, 0x13, 1, 1, 1, 2, 0),
+            inst_vector_code(InstID::VFNCVT_ROD_F_F_W, 0x12, 1, 1, 1, 2, 0x15),
+            inst_vector_code(InstID::VMFEQ, 0x18, 1, 1, 1, 2, 3),
+            inst_vector_code(InstID::VFMV_S_F, 0x10, 5, 1, 1, 0, 3),
+        ];
+        for inst_golden in golden.iter() {
+            let inst = decoder.decode(inst_golden.data, |_| true);
+            assert_eq!(inst_golden.id, inst.id);
+        }
+
+        let vfadd = inst_vector_code(InstID::VFADD, 0x00, 5, 1, 1, 2, 3);
+        let inst = decoder.decode(vfadd.data, |ext| ext != Extension::F);
+        assert_eq!(InstID::INVALID, inst.id);
+        let inst = decoder.decode(inst_vle_v_code(1, 2, 6).data, |ext| ext != Extension::Zve64d);
+        assert_eq!(InstID::INVALID, inst.id);
+    }
 }
@@ -1,3 +1,5 @@
+use crate::rv_core::isa::Extension;
+
 #[derive(PartialEq, Debug, Copy, Clone)]
 #[allow(non_camel_case_types)]
 pub enum InstID {
@@ -6,32 +8,77 @@ pub enum InstID {
     ADDI,
     ADDIW,
     ADDW,
+    ADD_UW,
+    AES64DS,
+    AES64DSM,
+    AES64ES,
+    AES64ESM,
+    AES64IM,
+    AES64KS1I,
+    AES64KS2,
     AMOADD_D,
     AMOADD_W,
+    AMOADD_B,
+    AMOADD_H,
     AMOAND_D,
     AMOAND_W,
+    AMOAND_B,
+    AMOAND_H,
+    AMOCAS_B,
+    AMOCAS_H,
+    AMOCAS_W,
+    AMOCAS_D,
+    AMOCAS_Q,
     AMOMAX_D,
     AMOMAX_W,
+    AMOMAX_B,
+    AMOMAX_H,
     AMOMAXU_D,
     AMOMAXU_W,
+    AMOMAXU_B,
+    AMOMAXU_H,
     AMOMIN_D,
     AMOMIN_W,
+    AMOMIN_B,
+    AMOMIN_H,
     AMOMINU_D,
     AMOMINU_W,
+    AMOMINU_B,
+    AMOMINU_H,
     AMOOR_D,
     AMOOR_W,
+    AMOOR_B,
+    AMOOR_H,
     AMOSWAP_D,
     AMOSWAP_W,
+    AMOSWAP_B,
+    AMOSWAP_H,
     AMOXOR_D,
     AMOXOR_W,
+    AMOXOR_B,
+    AMOXOR_H,
     AND,
+    ANDN,
     ANDI,
+    BCLR,
+    BCLRI,
     BEQ,
+    BEXT,
+    BEXTI,
     BGE,
     BGEU,
+    BINV,
+    BINVI,
+    BREV8,
     BLT,
     BLTU,
     BNE,
+    BSET,
+    BSETI,
+    CBO_CLEAN,
+    CBO_FLUSH,
+    CBO_INVAL,
+    CBO_ZERO,
     C_ADD,
     C_ADDI,
     C_ADDIW,
@@ -42,6 +89,7 @@ pub enum InstID {
     C_ANDI,
     C_BEQZ,
     C_BNEZ,
+    C_EBREAK,
     C_FSDSP,
     C_J,
     C_JALR,
@@ -58,85 +106,195 @@ pub enum InstID {
     C_LWSP,
     C_LI,
     C_LUI,
+    C_MOP,
     C_MV,
+    C_NTL_P1,
+    C_NTL_PALL,
+    C_NTL_S1,
+    C_NTL_ALL,
     C_OR,
     C_SD,
     C_SUB,
     C_SUBW,
     C_XOR,
+    C_LBU,
+    C_LHU,
+    C_LH,
+    C_SB,
+    C_SH,
+    C_ZEXT_B,
+    C_SEXT_B,
+    C_ZEXT_H,
+    C_SEXT_H,
+    C_ZEXT_W,
+    C_NOT,
+    C_MUL,
+    CM_PUSH,
+    CM_POP,
+    CM_POPRETZ,
+    CM_POPRET,
+    CM_MVSA01,
+    CM_MVA01S,
+    CM_JT,
+    CM_JALT,
+    CLMUL,
+    CLMULH,
+    CLMULR,
+    CLZ,
+    CLZW,
+    CPOP,
+    CPOPW,
     CSRRCI,
     CSRRS,
     CSRRSI,
     CSRRW,
     CSRRWI,
+    CTZ,
+    CTZW,
+    CZERO_EQZ,
+    CZERO_NEZ,
     DIV,
     DIVU,
     DIVUW,
     DIVW,
+    DRET,
+    EBREAK,
     ECALL,
     FADD_D,
     FADD_S,
+    FADD_H,
     FCLASS_D,
     FCLASS_S,
+    FCLASS_H,
     FCVT_D_L,
     FCVT_D_LU,
     FCVT_D_S,
     FCVT_D_W,
     FCVT_D_WU,
+    FCVT_D_H,
     FCVT_L_D,
     FCVT_L_S,
+    FCVT_L_H,
     FCVT_LU_D,
     FCVT_LU_S,
+    FCVT_LU_H,
     FCVT_S_D,
     FCVT_S_L,
     FCVT_S_LU,
     FCVT_S_W,
     FCVT_S_WU,
+    FCVT_S_H,
+    FCVT_H_D,
+    FCVT_H_L,
+    FCVT_H_LU,
+    FCVT_H_S,
+    FCVT_H_W,
+    FCVT_H_WU,
     FCVT_W_D,
     FCVT_W_S,
+    FCVT_W_H,
     FCVT_WU_D,
     FCVT_WU_S,
+    FCVT_WU_H,
+    FCVTMOD_W_D,
     FDIV_D,
     FDIV_S,
+    FDIV_H,
     FMADD_D,
     FMADD_S,
+    FMADD_H,
     FMAX_D,
     FMAX_S,
+    FMAX_H,
+    FMAXM_D,
+    FMAXM_S,
+    FMAXM_H,
     FMIN_D,
     FMIN_S,
+    FMIN_H,
+    FMINM_D,
+    FMINM_S,
+    FMINM_H,
     FMSUB_D,
     FMSUB_S,
+    FMSUB_H,
     FNMADD_D,
     FNMADD_S,
+    FNMADD_H,
     FNMSUB_D,
     FNMSUB_S,
+    FNMSUB_H,
     FSQRT_D,
     FSQRT_S,
+    FSQRT_H,
+    FROUND_D,
+    FROUND_S,
+    FROUND_H,
+    FROUNDNX_D,
+    FROUNDNX_S,
+    FROUNDNX_H,
     FSGNJ_D,
     FSGNJ_S,
+    FSGNJ_H,
     FSGNJN_D,
     FSGNJN_S,
+    FSGNJN_H,
     FSGNJX_D,
     FSGNJX_S,
+    FSGNJX_H,
     FENCE,
+    FENCE_I,
     FEQ_D,
     FEQ_S,
+    FEQ_H,
     FLD,
     FLE_D,
     FLE_S,
+    FLE_H,
+    FLEQ_D,
+    FLEQ_S,
+    FLEQ_H,
     FLT_D,
     FLT_S,
+    FLT_H,
+    FLTQ_D,
+    FLTQ_S,
+    FLTQ_H,
     FLW,
+    FLH,
     FSD,
     FSW,
+    FSH,
     FMUL_D,
     FMUL_S,
+    FMUL_H,
     FMV_D_X,
+    FMV_H_X,
     FMV_W_X,
     FMV_X_D,
+    FMV_X_H,
     FMV_X_W,
+    FLI_D,
+    FLI_S,
+    FLI_H,
     FSUB_D,
     FSUB_S,
+    FSUB_H,
+    HFENCE_GVMA,
+    HFENCE_VVMA,
+    HLV_B,
+    HLV_BU,
+    HLV_D,
+    HLV_H,
+    HLV_HU,
+    HLV_W,
+    HLV_WU,
+    HLVX_HU,
+    HLVX_WU,
+    HSV_B,
+    HSV_D,
+    HSV_H,
+    HSV_W,
     JAL,
     JALR,
     LB,
@@ -149,27 +307,76 @@ pub enum InstID {
     LUI,
     LW,
     LWU,
+    MAX,
+    MAXU,
+    MIN,
+    MINU,
+    MOP_R,
+    MOP_RR,
     MUL,
     MULH,
     MULHSU,
     MULHU,
     MULW,
     MRET,
+    MNRET,
+    NTL_P1,
+    NTL_PALL,
+    NTL_S1,
+    NTL_ALL,
     OR,
     ORI,
+    ORC_B,
+    ORN,
+    PACK,
+    PACKH,
+    PACKW,
+    PAUSE,
+    PREFETCH_I,
+    PREFETCH_R,
+    PREFETCH_W,
     REM,
     REMU,
     REMUW,
     REMW,
+    REV8,
+    ROL,
+    ROLW,
+    ROR,
+    RORI,
+    RORIW,
+    RORW,
     SB,
     SC_D,
     SC_W,
     SD,
+    SEXT_B,
+    SEXT_H,
+    SFENCE_VMA,
+    SHA256SIG0,
+    SHA256SIG1,
+    SHA256SUM0,
+    SHA256SUM1,
+    SHA512SIG0,
+    SHA512SIG1,
+    SHA512SUM0,
+    SHA512SUM1,
+    SM3P0,
+    SM3P1,
+    SM4ED,
+    SM4KS,
+    SH1ADD,
+    SH1ADD_UW,
+    SH2ADD,
+    SH2ADD_UW,
+    SH3ADD,
+    SH3ADD_UW,
     SH,
     SW,
     SLL,
     SLLI,
     SLLIW,
+    SLLI_UW,
     SLLW,
     SLT,
     SLTI,
@@ -183,15 +390,813 @@ pub enum InstID {
     SRAI,
     SRAIW,
     SRAW,
+    SRET,
     SUB,
     SUBW,
+    VAADD,
+    VAADDU,
+    VADC,
+    VADD,
+    VAND,
+    VASUB,
+    VASUBU,
+    VCOMPRESS,
+    VCPOP_M,
+    VDIV,
+    VDIVU,
+    VFADD,
+    VFCLASS_V,
+    VFCVT_F_X_V,
+    VFCVT_F_XU_V,
+    VFCVT_RTZ_X_F_V,
+    VFCVT_RTZ_XU_F_V,
+    VFCVT_X_F_V,
+    VFCVT_XU_F_V,
+    VFDIV,
+    VFIRST_M,
+    VFMACC,
+    VFMADD,
+    VFMAX,
+    VFMERGE,
+    VFMIN,
+    VFMSAC,
+    VFMSUB,
+    VFMUL,
+    VFMV_F_S,
+    VFMV_S_F,
+    VFMV_V_F,
+    VFNCVT_F_F_W,
+    VFNCVT_F_X_W,
+    VFNCVT_F_XU_W,
+    VFNCVT_ROD_F_F_W,
+    VFNCVT_RTZ_X_F_W,
+    VFNCVT_RTZ_XU_F_W,
+    VFNCVT_X_F_W,
+    VFNCVT_XU_F_W,
+    VFNMACC,
+    VFNMADD,
+    VFNMSAC,
+    VFNMSUB,
+    VFRDIV,
+    VFREC7_V,
+    VFREDMAX,
+    VFREDMIN,
+    VFREDOSUM,
+    VFREDUSUM,
+    VFRSQRT7_V,
+    VFRSUB,
+    VFSGNJ,
+    VFSGNJN,
+    VFSGNJX,
+    VFSLIDE1DOWN,
+    VFSLIDE1UP,
+    VFSQRT_V,
+    VFSUB,
+    VFWADD,
+    VFWADD_W,
+    VFWCVT_F_F_V,
+    VFWCVT_F_X_V,
+    VFWCVT_F_XU_V,
+    VFWCVT_RTZ_X_F_V,
+    VFWCVT_RTZ_XU_F_V,
+    VFWCVT_X_F_V,
+    VFWCVT_XU_F_V,
+    VFWMACC,
+    VFWMSAC,
+    VFWMUL,
+    VFWNMACC,
+    VFWNMSAC,
+    VFWREDOSUM,
+    VFWREDUSUM,
+    VFWSUB,
+    VFWSUB_W,
+    VID_V,
+    VIOTA_M,
+    VLE_V,
+    VLEFF_V,
+    VLM_V,
+    VLOXEI_V,
+    VLR_V,
+    VLSE_V,
+    VLUXEI_V,
+    VMACC,
+    VMADC,
+    VMADD,
+    VMAND,
+    VMANDN,
+    VMAX,
+    VMAXU,
+    VMERGE,
+    VMFEQ,
+    VMFGE,
+    VMFGT,
+    VMFLE,
+    VMFLT,
+    VMFNE,
+    VMIN,
+    VMINU,
+    VMNAND,
+    VMNOR,
+    VMOR,
+    VMORN,
+    VMSBC,
+    VMSBF_M,
+    VMSEQ,
+    VMSGT,
+    VMSGTU,
+    VMSIF_M,
+    VMSLE,
+    VMSLEU,
+    VMSLT,
+    VMSLTU,
+    VMSNE,
+    VMSOF_M,
+    VMUL,
+    VMULH,
+    VMULHSU,
+    VMULHU,
+    VMV_S_X,
+    VMV_V,
+    VMV_X_S,
+    VMVNR_V,
+    VMXNOR,
+    VMXOR,
+    VNCLIP,
+    VNCLIPU,
+    VNMSAC,
+    VNMSUB,
+    VNSRA,
+    VNSRL,
+    VOR,
+    VREDAND,
+    VREDMAX,
+    VREDMAXU,
+    VREDMIN,
+    VREDMINU,
+    VREDOR,
+    VREDSUM,
+    VREDXOR,
+    VREM,
+    VREMU,
+    VRGATHER,
+    VRGATHEREI16,
+    VRSUB,
+    VSADD,
+    VSADDU,
+    VSBC,
+    VSE_V,
+    VSETIVLI,
+    VSETVL,
+    VSETVLI,
+    VSEXT_VF2,
+    VSEXT_VF4,
+    VSEXT_VF8,
+    VSLIDE1DOWN,
+    VSLIDE1UP,
+    VSLIDEDOWN,
+    VSLIDEUP,
+    VSLL,
+    VSM_V,
+    VSMUL,
+    VSOXEI_V,
+    VSR_V,
+    VSRA,
+    VSRL,
+    VSSE_V,
+    VSSRA,
+    VSSRL,
+    VSSUB,
+    VSSUBU,
+    VSUB,
+    VSUXEI_V,
+    VWADD,
+    VWADD_W,
+    VWADDU,
+    VWADDU_W,
+    VWMACC,
+    VWMACCSU,
+    VWMACCU,
+    VWMACCUS,
+    VWMUL,
+    VWMULSU,
+    VWMULU,
+    VWREDSUM,
+    VWREDSUMU,
+    VWSUB,
+    VWSUB_W,
+    VWSUBU,
+    VWSUBU_W,
+    VXOR,
+    VZEXT_VF2,
+    VZEXT_VF4,
+    VZEXT_VF8,
     NOP,
     WFI,
     XOR,
     XORI,
+    XPERM4,
+    XPERM8,
+    XNOR,
+    ZEXT_H,
     INVALID,
 }
 
+impl InstID {
+    // Loads, stores and moves of the f registers, which Zfinx does not provide.
+    // Vector floating-point instructions also take their scalars from there.
+    pub fn uses_fregs(&self) -> bool {
+        self.extensions().contains(&Extension::Zve64d)
+            || matches!(
+            self,
+            InstID::C_FSDSP
+                | InstID::FLD
+                | InstID::FLH
+                | InstID::FLI_D
+                | InstID::FLI_H
+                | InstID::FLI_S
+                | InstID::FLW
+                | InstID::FMV_D_X
+                | InstID::FMV_H_X
+                | InstID::FMV_W_X
+                | InstID::FMV_X_D
+                | InstID::FMV_X_H
+                | InstID::FMV_X_W
+                | InstID::FSD
+                | InstID::FSH
+                | InstID::FSW
+        )
+    }
+
+    // Extensions that must all be enabled for the instruction to be legal
+    pub fn extensions(&self) -> &'static [Extension] {
+        match self {
+            InstID::MUL
+            | InstID::MULH
+            | InstID::MULHSU
+            | InstID::MULHU
+            | InstID::MULW
+            | InstID::DIV
+            | InstID::DIVU
+            | InstID::DIVUW
+            | InstID::DIVW
+            | InstID::REM
+            | InstID::REMU
+            | InstID::REMUW
+            | InstID::REMW => &[Extension::M],
+            InstID::AMOADD_D
+            | InstID::AMOADD_W
+            | InstID::AMOAND_D
+            | InstID::AMOAND_W
+            | InstID::AMOMAX_D
+            | InstID::AMOMAX_W
+            | InstID::AMOMAXU_D
+            | InstID::AMOMAXU_W
+            | InstID::AMOMIN_D
+            | InstID::AMOMIN_W
+            | InstID::AMOMINU_D
+            | InstID::AMOMINU_W
+            | InstID::AMOOR_D
+            | InstID::AMOOR_W
+            | InstID::AMOSWAP_D
+            | InstID::AMOSWAP_W
+            | InstID::AMOXOR_D
+            | InstID::AMOXOR_W
+            | InstID::LR_D
+            | InstID::LR_W
+            | InstID::SC_D
+            | InstID::SC_W => &[Extension::A],
+            InstID::AMOCAS_D | InstID::AMOCAS_Q | InstID::AMOCAS_W => &[Extension::Zacas],
+            InstID::AMOCAS_B | InstID::AMOCAS_H => &[Extension::Zacas, Extension::Zabha],
+            InstID::AMOADD_B
+            | InstID::AMOADD_H
+            | InstID::AMOAND_B
+            | InstID::AMOAND_H
+            | InstID::AMOMAX_B
+            | InstID::AMOMAX_H
+            | InstID::AMOMAXU_B
+            | InstID::AMOMAXU_H
+            | InstID::AMOMIN_B
+            | InstID::AMOMIN_H
+            | InstID::AMOMINU_B
+            | InstID::AMOMINU_H
+            | InstID::AMOOR_B
+            | InstID::AMOOR_H
+            | InstID::AMOSWAP_B
+            | InstID::AMOSWAP_H
+            | InstID::AMOXOR_B
+            | InstID::AMOXOR_H => &[Extension::Zabha],
+            InstID::FADD_S
+            | InstID::FCLASS_S
+            | InstID::FCVT_L_S
+            | InstID::FCVT_LU_S
+            | InstID::FCVT_S_L
+            | InstID::FCVT_S_LU
+            | InstID::FCVT_S_W
+            | InstID::FCVT_S_WU
+            | InstID::FCVT_W_S
+            | InstID::FCVT_WU_S
+            | InstID::FDIV_S
+            | InstID::FMADD_S
+            | InstID::FMAX_S
+            | InstID::FMIN_S
+            | InstID::FMSUB_S
+            | InstID::FNMADD_S
+            | InstID::FNMSUB_S
+            | InstID::FSQRT_S
+            | InstID::FSGNJ_S
+            | InstID::FSGNJN_S
+            | InstID::FSGNJX_S
+            | InstID::FEQ_S
+            | InstID::FLE_S
+            | InstID::FLT_S
+            | InstID::FLW
+            | InstID::FSW
+            | InstID::FMUL_S
+            | InstID::FMV_W_X
+            | InstID::FMV_X_W
+            | InstID::FSUB_S => &[Extension::F],
+            InstID::FADD_D
+            | InstID::FCLASS_D
+            | InstID::FCVT_D_L
+            | InstID::FCVT_D_LU
+            | InstID::FCVT_D_S
+            | InstID::FCVT_D_W
+            | InstID::FCVT_D_WU
+            | InstID::FCVT_L_D
+            | InstID::FCVT_LU_D
+            | InstID::FCVT_S_D
+            | InstID::FCVT_W_D
+            | InstID::FCVT_WU_D
+            | InstID::FDIV_D
+            | InstID::FMADD_D
+            | InstID::FMAX_D
+            | InstID::FMIN_D
+            | InstID::FMSUB_D
+            | InstID::FNMADD_D
+            | InstID::FNMSUB_D
+            | InstID::FSQRT_D
+            | InstID::FSGNJ_D
+            | InstID::FSGNJN_D
+            | InstID::FSGNJX_D
+            | InstID::FEQ_D
+            | InstID::FLD
+            | InstID::FLE_D
+            | InstID::FLT_D
+            | InstID::FSD
+            | InstID::FMUL_D
+            | InstID::FMV_D_X
+            | InstID::FMV_X_D
+            | InstID::FSUB_D => &[Extension::D],
+            InstID::FLH
+            | InstID::FSH
+            | InstID::FMV_H_X
+            | InstID::FMV_X_H
+            | InstID::FCVT_H_S
+            | InstID::FCVT_S_H => &[Extension::Zfhmin],
+            InstID::FCVT_D_H
+            | InstID::FCVT_H_D => &[Extension::Zfhmin, Extension::D],
+            InstID::FADD_H
+            | InstID::FCLASS_H
+            | InstID::FCVT_H_L
+            | InstID::FCVT_H_LU
+            | InstID::FCVT_H_W
+            | InstID::FCVT_H_WU
+            | InstID::FCVT_L_H
+            | InstID::FCVT_LU_H
+            | InstID::FCVT_W_H
+            | InstID::FCVT_WU_H
+            | InstID::FDIV_H
+            | InstID::FEQ_H
+            | InstID::FLE_H
+            | InstID::FLT_H
+            | InstID::FMADD_H
+            | InstID::FMAX_H
+            | InstID::FMIN_H
+            | InstID::FMSUB_H
+            | InstID::FMUL_H
+            | InstID::FNMADD_H
+            | InstID::FNMSUB_H
+            | InstID::FSGNJ_H
+            | InstID::FSGNJN_H
+            | InstID::FSGNJX_H
+            | InstID::FSQRT_H
+            | InstID::FSUB_H => &[Extension::Zfh],
+            InstID::FLEQ_S
+            | InstID::FLI_S
+            | InstID::FLTQ_S
+            | InstID::FMAXM_S
+            | InstID::FMINM_S
+            | InstID::FROUND_S
+            | InstID::FROUNDNX_S => &[Extension::Zfa],
+            InstID::FCVTMOD_W_D
+            | InstID::FLEQ_D
+            | InstID::FLI_D
+            | InstID::FLTQ_D
+            | InstID::FMAXM_D
+            | InstID::FMINM_D
+            | InstID::FROUND_D
+            | InstID::FROUNDNX_D => &[Extension::Zfa, Extension::D],
+            InstID::FLEQ_H
+            | InstID::FLI_H
+            | InstID::FLTQ_H
+            | InstID::FMAXM_H
+            | InstID::FMINM_H
+            | InstID::FROUND_H
+            | InstID::FROUNDNX_H => &[Extension::Zfa, Extension::Zfh],
+            InstID::C_FSDSP => &[Extension::C, Extension::D],
+            InstID::C_ADD
+            | InstID::C_ADDI
+            | InstID::C_ADDIW
+            | InstID::C_ADDI16SP
+            | InstID::C_ADDI4SPN
+            | InstID::C_ADDW
+            | InstID::C_AND
+            | InstID::C_ANDI
+            | InstID::C_BEQZ
+            | InstID::C_BNEZ
+            | InstID::C_EBREAK
+            | InstID::C_J
+            | InstID::C_JALR
+            | InstID::C_JR
+            | InstID::C_SDSP
+            | InstID::C_SLLI
+            | InstID::C_SRAI
+            | InstID::C_SRLI
+            | InstID::C_SW
+            | InstID::C_SWSP
+            | InstID::C_LD
+            | InstID::C_LDSP
+            | InstID::C_LW
+            | InstID::C_LWSP
+            | InstID::C_LI
+            | InstID::C_LUI
+            | InstID::C_MV
+            | InstID::C_OR
+            | InstID::C_SD
+            | InstID::C_SUB
+            | InstID::C_SUBW
+            | InstID::C_XOR => &[Extension::C],
+            InstID::CSRRCI
+            | InstID::CSRRS
+            | InstID::CSRRSI
+            | InstID::CSRRW
+            | InstID::CSRRWI => &[Extension::Zicsr],
+            InstID::HFENCE_GVMA
+            | InstID::HFENCE_VVMA
+            | InstID::HLV_B
+            | InstID::HLV_BU
+            | InstID::HLV_D
+            | InstID::HLV_H
+            | InstID::HLV_HU
+            | InstID::HLV_W
+            | InstID::HLV_WU
+            | InstID::HLVX_HU
+            | InstID::HLVX_WU
+            | InstID::HSV_B
+            | InstID::HSV_D
+            | InstID::HSV_H
+            | InstID::HSV_W => &[Extension::H],
+            InstID::MNRET => &[Extension::Smrnmi],
+            InstID::DRET => &[Extension::Sdext],
+            InstID::FENCE_I => &[Extension::Zifencei],
+            InstID::CBO_CLEAN | InstID::CBO_FLUSH | InstID::CBO_INVAL => &[Extension::Zicbom],
+            InstID::CBO_ZERO => &[Extension::Zicboz],
+            InstID::PREFETCH_I | InstID::PREFETCH_R | InstID::PREFETCH_W => &[Extension::Zicbop],
+            InstID::ADD_UW
+            | InstID::SH1ADD
+            | InstID::SH1ADD_UW
+            | InstID::SH2ADD
+            | InstID::SH2ADD_UW
+            | InstID::SH3ADD
+            | InstID::SH3ADD_UW
+            | InstID::SLLI_UW => &[Extension::Zba],
+            InstID::ANDN
+            | InstID::CLZ
+            | InstID::CLZW
+            | InstID::CPOP
+            | InstID::CPOPW
+            | InstID::CTZ
+            | InstID::CTZW
+            | InstID::MAX
+            | InstID::MAXU
+            | InstID::MIN
+            | InstID::MINU
+            | InstID::ORC_B
+            | InstID::ORN
+            | InstID::REV8
+            | InstID::ROL
+            | InstID::ROLW
+            | InstID::ROR
+            | InstID::RORI
+            | InstID::RORIW
+            | InstID::RORW
+            | InstID::SEXT_B
+            | InstID::SEXT_H
+            | InstID::XNOR
+            | InstID::ZEXT_H => &[Extension::Zbb],
+            InstID::CLMUL | InstID::CLMULH | InstID::CLMULR => &[Extension::Zbc],
+            InstID::BREV8 | InstID::PACK | InstID::PACKH | InstID::PACKW => &[Extension::Zbkb],
+            InstID::XPERM4 | InstID::XPERM8 => &[Extension::Zbkx],
+            InstID::AES64DS | InstID::AES64DSM | InstID::AES64IM => &[Extension::Zknd],
+            InstID::AES64ES | InstID::AES64ESM | InstID::AES64KS1I | InstID::AES64KS2 => {
+                &[Extension::Zkne]
+            }
+            InstID::SHA256SIG0
+            | InstID::SHA256SIG1
+            | InstID::SHA256SUM0
+            | InstID::SHA256SUM1
+            | InstID::SHA512SIG0
+            | InstID::SHA512SIG1
+            | InstID::SHA512SUM0
+            | InstID::SHA512SUM1 => &[Extension::Zknh],
+            InstID::SM4ED | InstID::SM4KS => &[Extension::Zksed],
+            InstID::SM3P0 | InstID::SM3P1 => &[Extension::Zksh],
+            InstID::BCLR
+            | InstID::BCLRI
+            | InstID::BEXT
+            | InstID::BEXTI
+            | InstID::BINV
+            | InstID::BINVI
+            | InstID::BSET
+            | InstID::BSETI => &[Extension::Zbs],
+            InstID::CZERO_EQZ | InstID::CZERO_NEZ => &[Extension::Zicond],
+            InstID::PAUSE => &[Extension::Zihintpause],
+            InstID::NTL_ALL | InstID::NTL_P1 | InstID::NTL_PALL | InstID::NTL_S1 => {
+                &[Extension::Zihintntl]
+            }
+            InstID::C_NTL_ALL | InstID::C_NTL_P1 | InstID::C_NTL_PALL | InstID::C_NTL_S1 => {
+                &[Extension::Zihintntl, Extension::C]
+            }
+            InstID::MOP_R | InstID::MOP_RR => &[Extension::Zimop],
+            InstID::C_MOP => &[Extension::Zcmop],
+            InstID::C_LBU
+            | InstID::C_LH
+            | InstID::C_LHU
+            | InstID::C_NOT
+            | InstID::C_SB
+            | InstID::C_SH
+            | InstID::C_ZEXT_B => &[Extension::Zcb],
+            InstID::C_SEXT_B | InstID::C_SEXT_H | InstID::C_ZEXT_H => {
+                &[Extension::Zcb, Extension::Zbb]
+            }
+            InstID::C_ZEXT_W => &[Extension::Zcb, Extension::Zba],
+            InstID::C_MUL => &[Extension::Zcb, Extension::M],
+            InstID::CM_MVA01S
+            | InstID::CM_MVSA01
+            | InstID::CM_POP
+            | InstID::CM_POPRET
+            | InstID::CM_POPRETZ
+            | InstID::CM_PUSH => &[Extension::Zcmp],
+            InstID::CM_JALT | InstID::CM_JT => &[Extension::Zcmt],
+            InstID::VAADD
+            | InstID::VAADDU
+            | InstID::VADC
+            | InstID::VADD
+            | InstID::VAND
+            | InstID::VASUB
+            | InstID::VASUBU
+            | InstID::VCOMPRESS
+            | InstID::VCPOP_M
+            | InstID::VDIV
+            | InstID::VDIVU
+            | InstID::VID_V
+            | InstID::VIOTA_M
+            | InstID::VLE_V
+            | InstID::VLEFF_V
+            | InstID::VLM_V
+            | InstID::VLOXEI_V
+            | InstID::VLR_V
+            | InstID::VLSE_V
+            | InstID::VLUXEI_V
+            | InstID::VMACC
+            | InstID::VMADC
+            | InstID::VMADD
+            | InstID::VMAND
+            | InstID::VMANDN
+            | InstID::VMAX
+            | InstID::VMAXU
+            | InstID::VMERGE
+            | InstID::VMIN
+            | InstID::VMINU
+            | InstID::VMNAND
+            | InstID::VMNOR
+            | InstID::VMOR
+            | InstID::VMORN
+            | InstID::VMSBC
+            | InstID::VMSBF_M
+            | InstID::VMSEQ
+            | InstID::VMSGT
+            | InstID::VMSGTU
+            | InstID::VMSIF_M
+            | InstID::VMSLE
+            | InstID::VMSLEU
+            | InstID::VMSLT
+            | InstID::VMSLTU
+            | InstID::VMSNE
+            | InstID::VMSOF_M
+            | InstID::VMUL
+            | InstID::VMULH
+            | InstID::VMULHSU
+            | InstID::VMULHU
+            | InstID::VMV_S_X
+            | InstID::VMV_V
+            | InstID::VMV_X_S
+            | InstID::VMVNR_V
+            | InstID::VMXNOR
+            | InstID::VMXOR
+            | InstID::VNCLIP
+            | InstID::VNCLIPU
+            | InstID::VNMSAC
+            | InstID::VNMSUB
+            | InstID::VNSRA
+            | InstID::VNSRL
+            | InstID::VOR
+            | InstID::VREDAND
+            | InstID::VREDMAX
+            | InstID::VREDMAXU
+            | InstID::VREDMIN
+            | InstID::VREDMINU
+            | InstID::VREDOR
+            | InstID::VREDSUM
+            | InstID::VREDXOR
+            | InstID::VREM
+            | InstID::VREMU
+            | InstID::VRGATHER
+            | InstID::VRGATHEREI16
+            | InstID::VRSUB
+            | InstID::VSADD
+            | InstID::VSADDU
+            | InstID::VSBC
+            | InstID::VSE_V
+            | InstID::VSETIVLI
+            | InstID::VSETVL
+            | InstID::VSETVLI
+            | InstID::VSEXT_VF2
+            | InstID::VSEXT_VF4
+            | InstID::VSEXT_VF8
+            | InstID::VSLIDE1DOWN
+            | InstID::VSLIDE1UP
+            | InstID::VSLIDEDOWN
+            | InstID::VSLIDEUP
+            | InstID::VSLL
+            | InstID::VSM_V
+            | InstID::VSMUL
+            | InstID::VSOXEI_V
+            | InstID::VSR_V
+            | InstID::VSRA
+            | InstID::VSRL
+            | InstID::VSSE_V
+            | InstID::VSSRA
+            | InstID::VSSRL
+            | InstID::VSSUB
+            | InstID::VSSUBU
+            | InstID::VSUB
+            | InstID::VSUXEI_V
+            | InstID::VWADD
+            | InstID::VWADD_W
+            | InstID::VWADDU
+            | InstID::VWADDU_W
+            | InstID::VWMACC
+            | InstID::VWMACCSU
+            | InstID::VWMACCU
+            | InstID::VWMACCUS
+            | InstID::VWMUL
+            | InstID::VWMULSU
+            | InstID::VWMULU
+            | InstID::VWREDSUM
+            | InstID::VWREDSUMU
+            | InstID::VWSUB
+            | InstID::VWSUB_W
+            | InstID::VWSUBU
+            | InstID::VWSUBU_W
+            | InstID::VXOR
+            | InstID::VZEXT_VF2
+            | InstID::VZEXT_VF4
+            | InstID::VZEXT_VF8 => &[Extension::Zve64d],
+            InstID::VFADD
+            | InstID::VFCLASS_V
+            | InstID::VFCVT_F_X_V
+            | InstID::VFCVT_F_XU_V
+            | InstID::VFCVT_RTZ_X_F_V
+            | InstID::VFCVT_RTZ_XU_F_V
+            | InstID::VFCVT_X_F_V
+            | InstID::VFCVT_XU_F_V
+            | InstID::VFDIV
+            | InstID::VFIRST_M
+            | InstID::VFMACC
+            | InstID::VFMADD
+            | InstID::VFMAX
+            | InstID::VFMERGE
+            | InstID::VFMIN
+            | InstID::VFMSAC
+            | InstID::VFMSUB
+            | InstID::VFMUL
+            | InstID::VFMV_F_S
+            | InstID::VFMV_S_F
+            | InstID::VFMV_V_F
+            | InstID::VFNCVT_F_F_W
+            | InstID::VFNCVT_F_X_W
+            | InstID::VFNCVT_F_XU_W
+            | InstID::VFNCVT_ROD_F_F_W
+            | InstID::VFNCVT_RTZ_X_F_W
+            | InstID::VFNCVT_RTZ_XU_F_W
+            | InstID::VFNCVT_X_F_W
+            | InstID::VFNCVT_XU_F_W
+            | InstID::VFNMACC
+            | InstID::VFNMADD
+            | InstID::VFNMSAC
+            | InstID::VFNMSUB
+            | InstID::VFRDIV
+            | InstID::VFREC7_V
+            | InstID::VFREDMAX
+            | InstID::VFREDMIN
+            | InstID::VFREDOSUM
+            | InstID::VFREDUSUM
+            | InstID::VFRSQRT7_V
+            | InstID::VFRSUB
+            | InstID::VFSGNJ
+            | InstID::VFSGNJN
+            | InstID::VFSGNJX
+            | InstID::VFSLIDE1DOWN
+            | InstID::VFSLIDE1UP
+            | InstID::VFSQRT_V
+            | InstID::VFSUB
+            | InstID::VFWADD
+            | InstID::VFWADD_W
+            | InstID::VFWCVT_F_F_V
+            | InstID::VFWCVT_F_X_V
+            | InstID::VFWCVT_F_XU_V
+            | InstID::VFWCVT_RTZ_X_F_V
+            | InstID::VFWCVT_RTZ_XU_F_V
+            | InstID::VFWCVT_X_F_V
+            | InstID::VFWCVT_XU_F_V
+            | InstID::VFWMACC
+            | InstID::VFWMSAC
+            | InstID::VFWMUL
+            | InstID::VFWNMACC
+            | InstID::VFWNMSAC
+            | InstID::VFWREDOSUM
+            | InstID::VFWREDUSUM
+            | InstID::VFWSUB
+            | InstID::VFWSUB_W
+            | InstID::VMFEQ
+            | InstID::VMFGE
+            | InstID::VMFGT
+            | InstID::VMFLE
+            | InstID::VMFLT
+            | InstID::VMFNE => &[Extension::Zve64d, Extension::F],
+            _ => &[Extension::I],
+        }
+    }
+
+    // Extensions that provide the instruction on their own as well, the
+    // crypto subsets Zbkb/Zbkc share these with Zbb/Zbc and the AES key
+    // schedule is in both Zknd and Zkne
+    pub fn alt_extensions(&self) -> &'static [Extension] {
+        match self {
+            InstID::ANDN
+            | InstID::ORN
+            | InstID::REV8
+            | InstID::ROL
+            | InstID::ROLW
+            | InstID::ROR
+            | InstID::RORI
+            | InstID::RORIW
+            | InstID::RORW
+            | InstID::XNOR
+            | InstID::ZEXT_H => &[Extension::Zbkb],
+            InstID::CLMUL | InstID::CLMULH => &[Extension::Zbkc],
+            InstID::AES64KS1I | InstID::AES64KS2 => &[Extension::Zknd],
+            _ => &[],
+        }
+    }
+
+    // HINT encodings still execute as their base instruction when the
+    // extension giving them a meaning is disabled, likewise Zcmp/Zcmt give
+    // way to c.fsdsp
+    pub fn fallback(&self) -> Option<InstID> {
+        match self {
+            InstID::PAUSE => Some(InstID::FENCE),
+            InstID::PREFETCH_I | InstID::PREFETCH_R | InstID::PREFETCH_W => Some(InstID::ORI),
+            InstID::NTL_ALL | InstID::NTL_P1 | InstID::NTL_PALL | InstID::NTL_S1 => {
+                Some(InstID::ADD)
+            }
+            InstID::C_NTL_ALL | InstID::C_NTL_P1 | InstID::C_NTL_PALL | InstID::C_NTL_S1 => {
+                Some(InstID::C_ADD)
+            }
+            InstID::CM_JALT
+            | InstID::CM_JT
+            | InstID::CM_MVA01S
+            | InstID::CM_MVSA01
+            | InstID::CM_POP
+            | InstID::CM_POPRET
+            | InstID::CM_POPRETZ
+            | InstID::CM_PUSH => Some(InstID::C_FSDSP),
+            _ => None,
+        }
+    }
+}
+
 pub struct InstInfo<'a> {
     pub name: &'a str,
 }
@@ -203,32 +1208,77 @@ pub const inst_info_table: &[InstInfo] = &[
     InstInfo { name: "addi" },
     InstInfo { name: "addiw" },
     InstInfo { name: "addw" },
+    InstInfo { name: "add.uw" },
+    InstInfo { name: "aes64ds" },
+    InstInfo { name: "aes64dsm" },
+    InstInfo { name: "aes64es" },
+    InstInfo { name: "aes64esm" },
+    InstInfo { name: "aes64im" },
+    InstInfo { name: "aes64ks1i" },
+    InstInfo { name: "aes64ks2" },
     InstInfo { name: "amoadd.d" },
     InstInfo { name: "amoadd.w" },
+    InstInfo { name: "amoadd.b" },
+    InstInfo { name: "amoadd.h" },
     InstInfo { name: "amoand.d" },
     InstInfo { name: "amoand.w" },
+    InstInfo { name: "amoand.b" },
+    InstInfo { name: "amoand.h" },
+    InstInfo { name: "amocas.b" },
+    InstInfo { name: "amocas.h" },
+    InstInfo { name: "amocas.w" },
+    InstInfo { name: "amocas.d" },
+    InstInfo { name: "amocas.q" },
     InstInfo { name: "amomax.d" },
     InstInfo { name: "amomax.w" },
+    InstInfo { name: "amomax.b" },
+    InstInfo { name: "amomax.h" },
     InstInfo { name: "amomaxu.d" },
     InstInfo { name: "amomaxu.w" },
+    InstInfo { name: "amomaxu.b" },
+    InstInfo { name: "amomaxu.h" },
     InstInfo { name: "amomin.d" },
     InstInfo { name: "amomin.w" },
+    InstInfo { name: "amomin.b" },
+    InstInfo { name: "amomin.h" },
     InstInfo { name: "amominu.d" },
     InstInfo { name: "amominu.w" },
+    InstInfo { name: "amominu.b" },
+    InstInfo { name: "amominu.h" },
     InstInfo { name: "amoor.d" },
     InstInfo { name: "amoor.w" },
+    InstInfo { name: "amoor.b" },
+    InstInfo { name: "amoor.h" },
     InstInfo { name: "amoswap.d" },
     InstInfo { name: "amoswap.w" },
+    InstInfo { name: "amoswap.b" },
+    InstInfo { name: "amoswap.h" },
     InstInfo { name: "amoxor.d" },
     InstInfo { name: "amoxor.w" },
+    InstInfo { name: "amoxor.b" },
+    InstInfo { name: "amoxor.h" },
     InstInfo { name: "and" },
+    InstInfo { name: "andn" },
     InstInfo { name: "andi" },
+    InstInfo { name: "bclr" },
+    InstInfo { name: "bclri" },
     InstInfo { name: "beq" },
+    InstInfo { name: "bext" },
+    InstInfo { name: "bexti" },
     InstInfo { name: "bge" },
     InstInfo { name: "bgeu" },
+    InstInfo { name: "binv" },
+    InstInfo { name: "binvi" },
+    InstInfo { name: "brev8" },
     InstInfo { name: "blt" },
     InstInfo { name: "bltu" },
     InstInfo { name: "bne" },
+    InstInfo { name: "bset" },
+    InstInfo { name: "bseti" },
+    InstInfo { name: "cbo.clean" },
+    InstInfo { name: "cbo.flush" },
+    InstInfo { name: "cbo.inval" },
+    InstInfo { name: "cbo.zero" },
     InstInfo { name: "c.add" },
     InstInfo { name: "c.addi" },
     InstInfo { name: "c.addiw" },
@@ -239,6 +1289,7 @@ pub const inst_info_table: &[InstInfo] = &[
     InstInfo { name: "c.andi" },
     InstInfo { name: "c.beqz" },
     InstInfo { name: "c.bnez" },
+    InstInfo { name: "c.ebreak" },
     InstInfo { name: "c.fsdsp" },
     InstInfo { name: "c.j" },
     InstInfo { name: "c.jalr" },
@@ -255,85 +1306,195 @@ pub const inst_info_table: &[InstInfo] = &[
     InstInfo { name: "c.lwsp" },
     InstInfo { name: "c.li" },
     InstInfo { name: "c.lui" },
+    InstInfo { name: "c.mop.n" },
     InstInfo { name: "c.mv" },
+    InstInfo { name: "c.ntl.p1" },
+    InstInfo { name: "c.ntl.pall" },
+    InstInfo { name: "c.ntl.s1" },
+    InstInfo { name: "c.ntl.all" },
     InstInfo { name: "c.or" },
     InstInfo { name: "c.sd" },
     InstInfo { name: "c.sub" },
     InstInfo { name: "c.subw" },
     InstInfo { name: "c.xor" },
+    InstInfo { name: "c.lbu" },
+    InstInfo { name: "c.lhu" },
+    InstInfo { name: "c.lh" },
+    InstInfo { name: "c.sb" },
+    InstInfo { name: "c.sh" },
+    InstInfo { name: "c.zext.b" },
+    InstInfo { name: "c.sext.b" },
+    InstInfo { name: "c.zext.h" },
+    InstInfo { name: "c.sext.h" },
+    InstInfo { name: "c.zext.w" },
+    InstInfo { name: "c.not" },
+    InstInfo { name: "c.mul" },
+    InstInfo { name: "cm.push" },
+    InstInfo { name: "cm.pop" },
+    InstInfo { name: "cm.popretz" },
+    InstInfo { name: "cm.popret" },
+    InstInfo { name: "cm.mvsa01" },
+    InstInfo { name: "cm.mva01s" },
+    InstInfo { name: "cm.jt" },
+    InstInfo { name: "cm.jalt" },
+    InstInfo { name: "clmul" },
+    InstInfo { name: "clmulh" },
+    InstInfo { name: "clmulr" },
+    InstInfo { name: "clz" },
+    InstInfo { name: "clzw" },
+    InstInfo { name: "cpop" },
+    InstInfo { name: "cpopw" },
     InstInfo { name: "csrrci" },
     InstInfo { name: "csrrs" },
     InstInfo { name: "csrrsi" },
     InstInfo { name: "csrrw" },
     InstInfo { name: "csrrwi" },
+    InstInfo { name: "ctz" },
+    InstInfo { name: "ctzw" },
+    InstInfo { name: "czero.eqz" },
+    InstInfo { name: "czero.nez" },
     InstInfo { name: "div" },
     InstInfo { name: "divu" },
     InstInfo { name: "divuw" },
     InstInfo { name: "divw" },
+    InstInfo { name: "dret" },
+    InstInfo { name: "ebreak" },
     InstInfo { name: "ecall" },
     InstInfo { name: "fadd.d" },
     InstInfo { name: "fadd.s" },
+    InstInfo { name: "fadd.h" },
     InstInfo { name: "fclass.d" },
     InstInfo { name: "fclass.s" },
+    InstInfo { name: "fclass.h" },
     InstInfo { name: "fcvt.d.l" },
     InstInfo { name: "fcvt.d.lu" },
     InstInfo { name: "fcvt.d.s" },
     InstInfo { name: "fcvt.d.w" },
     InstInfo { name: "fcvt.d.wu" },
+    InstInfo { name: "fcvt.d.h" },
     InstInfo { name: "fcvt.l.d" },
     InstInfo { name: "fcvt.l.s" },
+    InstInfo { name: "fcvt.l.h" },
     InstInfo { name: "fcvt.lu.d" },
     InstInfo { name: "fcvt.lu.s" },
+    InstInfo { name: "fcvt.lu.h" },
     InstInfo { name: "fcvt.s.d" },
     InstInfo { name: "fcvt.s.l" },
     InstInfo { name: "fcvt.s.lu" },
     InstInfo { name: "fcvt.s.w" },
     InstInfo { name: "fcvt.s.wu" },
+    InstInfo { name: "fcvt.s.h" },
+    InstInfo { name: "fcvt.h.d" },
+    InstInfo { name: "fcvt.h.l" },
+    InstInfo { name: "fcvt.h.lu" },
+    InstInfo { name: "fcvt.h.s" },
+    InstInfo { name: "fcvt.h.w" },
+    InstInfo { name: "fcvt.h.wu" },
     InstInfo { name: "fcvt.w.d" },
     InstInfo { name: "fcvt.w.s" },
+    InstInfo { name: "fcvt.w.h" },
     InstInfo { name: "fcvt.wu.d" },
     InstInfo { name: "fcvt.wu.s" },
+    InstInfo { name: "fcvt.wu.h" },
+    InstInfo { name: "fcvtmod.w.d" },
     InstInfo { name: "fdiv.d" },
     InstInfo { name: "fdiv.s" },
+    InstInfo { name: "fdiv.h" },
     InstInfo { name: "fmadd.d" },
     InstInfo { name: "fmadd.s" },
+    InstInfo { name: "fmadd.h" },
     InstInfo { name: "fmax.d" },
     InstInfo { name: "fmax.s" },
+    InstInfo { name: "fmax.h" },
+    InstInfo { name: "fmaxm.d" },
+    InstInfo { name: "fmaxm.s" },
+    InstInfo { name: "fmaxm.h" },
     InstInfo { name: "fmin.d" },
     InstInfo { name: "fmin.s" },
+    InstInfo { name: "fmin.h" },
+    InstInfo { name: "fminm.d" },
+    InstInfo { name: "fminm.s" },
+    InstInfo { name: "fminm.h" },
     InstInfo { name: "fmsub.d" },
     InstInfo { name: "fmsub.s" },
+    InstInfo { name: "fmsub.h" },
     InstInfo { name: "fnmadd.d" },
     InstInfo { name: "fnmadd.s" },
+    InstInfo { name: "fnmadd.h" },
     InstInfo { name: "fnmsub.d" },
     InstInfo { name: "fnmsub.s" },
+    InstInfo { name: "fnmsub.h" },
     InstInfo { name: "fsqrt.d" },
     InstInfo { name: "fsqrt.s" },
+    InstInfo { name: "fsqrt.h" },
+    InstInfo { name: "fround.d" },
+    InstInfo { name: "fround.s" },
+    InstInfo { name: "fround.h" },
+    InstInfo { name: "froundnx.d" },
+    InstInfo { name: "froundnx.s" },
+    InstInfo { name: "froundnx.h" },
     InstInfo { name: "fsgnj.d" },
     InstInfo { name: "fsgnj.s" },
+    InstInfo { name: "fsgnj.h" },
     InstInfo { name: "fsgnjn.d" },
     InstInfo { name: "fsgnjn.s" },
+    InstInfo { name: "fsgnjn.h" },
     InstInfo { name: "fsgnjx.d" },
     InstInfo { name: "fsgnjx.s" },
+    InstInfo { name: "fsgnjx.h" },
     InstInfo { name: "fence" },
+    InstInfo { name: "fence.i" },
     InstInfo { name: "feq.d" },
     InstInfo { name: "feq.s" },
+    InstInfo { name: "feq.h" },
     InstInfo { name: "fld" },
     InstInfo { name: "fle.d" },
     InstInfo { name: "fle.s" },
+    InstInfo { name: "fle.h" },
+    InstInfo { name: "fleq.d" },
+    InstInfo { name: "fleq.s" },
+    InstInfo { name: "fleq.h" },
     InstInfo { name: "flt.d" },
     InstInfo { name: "flt.s" },
+    InstInfo { name: "flt.h" },
+    InstInfo { name: "fltq.d" },
+    InstInfo { name: "fltq.s" },
+    InstInfo { name: "fltq.h" },
     InstInfo { name: "flw" },
+    InstInfo { name: "flh" },
     InstInfo { name: "fsd" },
     InstInfo { name: "fsw" },
+    InstInfo { name: "fsh" },
     InstInfo { name: "fmul.d" },
     InstInfo { name: "fmul.s" },
+    InstInfo { name: "fmul.h" },
     InstInfo { name: "fmv.d.x" },
+    InstInfo { name: "fmv.h.x" },
     InstInfo { name: "fmv.w.x" },
     InstInfo { name: "fmv.x.d" },
+    InstInfo { name: "fmv.x.h" },
     InstInfo { name: "fmv.x.w" },
+    InstInfo { name: "fli.d" },
+    InstInfo { name: "fli.s" },
+    InstInfo { name: "fli.h" },
     InstInfo { name: "fsub.d" },
     InstInfo { name: "fsub.s" },
+    InstInfo { name: "fsub.h" },
+    InstInfo { name: "hfence.gvma" },
+    InstInfo { name: "hfence.vvma" },
+    InstInfo { name: "hlv.b" },
+    InstInfo { name: "hlv.bu" },
+    InstInfo { name: "hlv.d" },
+    InstInfo { name: "hlv.h" },
+    InstInfo { name: "hlv.hu" },
+    InstInfo { name: "hlv.w" },
+    InstInfo { name: "hlv.wu" },
+    InstInfo { name: "hlvx.hu" },
+    InstInfo { name: "hlvx.wu" },
+    InstInfo { name: "hsv.b" },
+    InstInfo { name: "hsv.d" },
+    InstInfo { name: "hsv.h" },
+    InstInfo { name: "hsv.w" },
     InstInfo { name: "jal" },
     InstInfo { name: "jalr" },
     InstInfo { name: "lb" },
@@ -346,27 +1507,76 @@ pub const inst_info_table: &[InstInfo] = &[
     InstInfo { name: "lui" },
     InstInfo { name: "lw" },
     InstInfo { name: "lwu" },
+    InstInfo { name: "max" },
+    InstInfo { name: "maxu" },
+    InstInfo { name: "min" },
+    InstInfo { name: "minu" },
+    InstInfo { name: "mop.r.n" },
+    InstInfo { name: "mop.rr.n" },
     InstInfo { name: "mul" },
     InstInfo { name: "mulh" },
     InstInfo { name: "mulhsu" },
     InstInfo { name: "mulhu" },
     InstInfo { name: "mulw" },
     InstInfo { name: "mret" },
+    InstInfo { name: "mnret" },
+    InstInfo { name: "ntl.p1" },
+    InstInfo { name: "ntl.pall" },
+    InstInfo { name: "ntl.s1" },
+    InstInfo { name: "ntl.all" },
     InstInfo { name: "or" },
     InstInfo { name: "ori" },
+    InstInfo { name: "orc.b" },
+    InstInfo { name: "orn" },
+    InstInfo { name: "pack" },
+    InstInfo { name: "packh" },
+    InstInfo { name: "packw" },
+    InstInfo { name: "pause" },
+    InstInfo { name: "prefetch.i" },
+    InstInfo { name: "prefetch.r" },
+    InstInfo { name: "prefetch.w" },
     InstInfo { name: "rem" },
     InstInfo { name: "remu" },
     InstInfo { name: "remuw" },
     InstInfo { name: "remw" },
+    InstInfo { name: "rev8" },
+    InstInfo { name: "rol" },
+    InstInfo { name: "rolw" },
+    InstInfo { name: "ror" },
+    InstInfo { name: "rori" },
+    InstInfo { name: "roriw" },
+    InstInfo { name: "rorw" },
     InstInfo { name: "sb" },
     InstInfo { name: "sc.c" },
     InstInfo { name: "sc.d" },
     InstInfo { name: "sd" },
+    InstInfo { name: "sext.b" },
+    InstInfo { name: "sext.h" },
+    InstInfo { name: "sfence.vma" },
+    InstInfo { name: "sha256sig0" },
+    InstInfo { name: "sha256sig1" },
+    InstInfo { name: "sha256sum0" },
+    InstInfo { name: "sha256sum1" },
+    InstInfo { name: "sha512sig0" },
+    InstInfo { name: "sha512sig1" },
+    InstInfo { name: "sha512sum0" },
+    InstInfo { name: "sha512sum1" },
+    InstInfo { name: "sm3p0" },
+    InstInfo { name: "sm3p1" },
+    InstInfo { name: "sm4ed" },
+    InstInfo { name: "sm4ks" },
+    InstInfo { name: "sh1add" },
+    InstInfo { name: "sh1add.uw" },
+    InstInfo { name: "sh2add" },
+    InstInfo { name: "sh2add.uw" },
+    InstInfo { name: "sh3add" },
+    InstInfo { name: "sh3add.uw" },
     InstInfo { name: "sh" },
     InstInfo { name: "sw" },
     InstInfo { name: "sll" },
     InstInfo { name: "slli" },
     InstInfo { name: "slliw" },
+    InstInfo { name: "slli.uw" },
     InstInfo { name: "sllw" },
     InstInfo { name: "slt" },
     InstInfo { name: "slti" },
@@ -380,11 +1590,213 @@ pub const inst_info_table: &[InstInfo] = &[
     InstInfo { name: "srai" },
     InstInfo { name: "sraiw" },
     InstInfo { name: "sraw" },
+    InstInfo { name: "sret" },
     InstInfo { name: "sub" },
     InstInfo { name: "subw" },
+    InstInfo { name: "vaadd" },
+    InstInfo { name: "vaaddu" },
+    InstInfo { name: "vadc" },
+    InstInfo { name: "vadd" },
+    InstInfo { name: "vand" },
+    InstInfo { name: "vasub" },
+    InstInfo { name: "vasubu" },
+    InstInfo { name: "vcompress" },
+    InstInfo { name: "vcpop.m" },
+    InstInfo { name: "vdiv" },
+    InstInfo { name: "vdivu" },
+    InstInfo { name: "vfadd" },
+    InstInfo { name: "vfclass.v" },
+    InstInfo { name: "vfcvt.f.x.v" },
+    InstInfo { name: "vfcvt.f.xu.v" },
+    InstInfo { name: "vfcvt.rtz.x.f.v" },
+    InstInfo { name: "vfcvt.rtz.xu.f.v" },
+    InstInfo { name: "vfcvt.x.f.v" },
+    InstInfo { name: "vfcvt.xu.f.v" },
+    InstInfo { name: "vfdiv" },
+    InstInfo { name: "vfirst.m" },
+    InstInfo { name: "vfmacc" },
+    InstInfo { name: "vfmadd" },
+    InstInfo { name: "vfmax" },
+    InstInfo { name: "vfmerge" },
+    InstInfo { name: "vfmin" },
+    InstInfo { name: "vfmsac" },
+    InstInfo { name: "vfmsub" },
+    InstInfo { name: "vfmul" },
+    InstInfo { name: "vfmv.f.s" },
+    InstInfo { name: "vfmv.s.f" },
+    InstInfo { name: "vfmv.v.f" },
+    InstInfo { name: "vfncvt.f.f.w" },
+    InstInfo { name: "vfncvt.f.x.w" },
+    InstInfo { name: "vfncvt.f.xu.w" },
+    InstInfo { name: "vfncvt.rod.f.f.w" },
+    InstInfo { name: "vfncvt.rtz.x.f.w" },
+    InstInfo { name: "vfncvt.rtz.xu.f.w" },
+    InstInfo { name: "vfncvt.x.f.w" },
+    InstInfo { name: "vfncvt.xu.f.w" },
+    InstInfo { name: "vfnmacc" },
+    InstInfo { name: "vfnmadd" },
+    InstInfo { name: "vfnmsac" },
+    InstInfo { name: "vfnmsub" },
+    InstInfo { name: "vfrdiv" },
+    InstInfo { name: "vfrec7.v" },
+    InstInfo { name: "vfredmax" },
+    InstInfo { name: "vfredmin" },
+    InstInfo { name: "vfredosum" },
+    InstInfo { name: "vfredusum" },
+    InstInfo { name: "vfrsqrt7.v" },
+    InstInfo { name: "vfrsub" },
+    InstInfo { name: "vfsgnj" },
+    InstInfo { name: "vfsgnjn" },
+    InstInfo { name: "vfsgnjx" },
+    InstInfo { name: "vfslide1down" },
+    InstInfo { name: "vfslide1up" },
+    InstInfo { name: "vfsqrt.v" },
+    InstInfo { name: "vfsub" },
+    InstInfo { name: "vfwadd" },
+    InstInfo { name: "vfwadd.w" },
+    InstInfo { name: "vfwcvt.f.f.v" },
+    InstInfo { name: "vfwcvt.f.x.v" },
+    InstInfo { name: "vfwcvt.f.xu.v" },
+    InstInfo { name: "vfwcvt.rtz.x.f.v" },
+    InstInfo { name: "vfwcvt.rtz.xu.f.v" },
+    InstInfo { name: "vfwcvt.x.f.v" },
+    InstInfo { name: "vfwcvt.xu.f.v" },
+    InstInfo { name: "vfwmacc" },
+    InstInfo { name: "vfwmsac" },
+    InstInfo { name: "vfwmul" },
+    InstInfo { name: "vfwnmacc" },
+    InstInfo { name: "vfwnmsac" },
+    InstInfo { name: "vfwredosum" },
+    InstInfo { name: "vfwredusum" },
+    InstInfo { name: "vfwsub" },
+    InstInfo { name: "vfwsub.w" },
+    InstInfo { name: "vid.v" },
+    InstInfo { name: "viota.m" },
+    InstInfo { name: "vle.v" },
+    InstInfo { name: "vleff.v" },
+    InstInfo { name: "vlm.v" },
+    InstInfo { name: "vloxei.v" },
+    InstInfo { name: "vlr.v" },
+    InstInfo { name: "vlse.v" },
+    InstInfo { name: "vluxei.v" },
+    InstInfo { name: "vmacc" },
+    InstInfo { name: "vmadc" },
+    InstInfo { name: "vmadd" },
+    InstInfo { name: "vmand" },
+    InstInfo { name: "vmandn" },
+    InstInfo { name: "vmax" },
+    InstInfo { name: "vmaxu" },
+    InstInfo { name: "vmerge" },
+    InstInfo { name: "vmfeq" },
+    InstInfo { name: "vmfge" },
+    InstInfo { name: "vmfgt" },
+    InstInfo { name: "vmfle" },
+    InstInfo { name: "vmflt" },
+    InstInfo { name: "vmfne" },
+    InstInfo { name: "vmin" },
+    InstInfo { name: "vminu" },
+    InstInfo { name: "vmnand" },
+    InstInfo { name: "vmnor" },
+    InstInfo { name: "vmor" },
+    InstInfo { name: "vmorn" },
+    InstInfo { name: "vmsbc" },
+    InstInfo { name: "vmsbf.m" },
+    InstInfo { name: "vmseq" },
+    InstInfo { name: "vmsgt" },
+    InstInfo { name: "vmsgtu" },
+    InstInfo { name: "vmsif.m" },
+    InstInfo { name: "vmsle" },
+    InstInfo { name: "vmsleu" },
+    InstInfo { name: "vmslt" },
+    InstInfo { name: "vmsltu" },
+    InstInfo { name: "vmsne" },
+    InstInfo { name: "vmsof.m" },
+    InstInfo { name: "vmul" },
+    InstInfo { name: "vmulh" },
+    InstInfo { name: "vmulhsu" },
+    InstInfo { name: "vmulhu" },
+    InstInfo { name: "vmv.s.x" },
+    InstInfo { name: "vmv.v" },
+    InstInfo { name: "vmv.x.s" },
+    InstInfo { name: "vmvnr.v" },
+    InstInfo { name: "vmxnor" },
+    InstInfo { name: "vmxor" },
+    InstInfo { name: "vnclip" },
+    InstInfo { name: "vnclipu" },
+    InstInfo { name: "vnmsac" },
+    InstInfo { name: "vnmsub" },
+    InstInfo { name: "vnsra" },
+    InstInfo { name: "vnsrl" },
+    InstInfo { name: "vor" },
+    InstInfo { name: "vredand" },
+    InstInfo { name: "vredmax" },
+    InstInfo { name: "vredmaxu" },
+    InstInfo { name: "vredmin" },
+    InstInfo { name: "vredminu" },
+    InstInfo { name: "vredor" },
+    InstInfo { name: "vredsum" },
+    InstInfo { name: "vredxor" },
+    InstInfo { name: "vrem" },
+    InstInfo { name: "vremu" },
+    InstInfo { name: "vrgather" },
+    InstInfo { name: "vrgatherei16" },
+    InstInfo { name: "vrsub" },
+    InstInfo { name: "vsadd" },
+    InstInfo { name: "vsaddu" },
+    InstInfo { name: "vsbc" },
+    InstInfo { name: "vse.v" },
+    InstInfo { name: "vsetivli" },
+    InstInfo { name: "vsetvl" },
+    InstInfo { name: "vsetvli" },
+    InstInfo { name: "vsext.vf2" },
+    InstInfo { name: "vsext.vf4" },
+    InstInfo { name: "vsext.vf8" },
+    InstInfo { name: "vslide1down" },
+    InstInfo { name: "vslide1up" },
+    InstInfo { name: "vslidedown" },
+    InstInfo { name: "vslideup" },
+    InstInfo { name: "vsll" },
+    InstInfo { name: "vsm.v" },
+    InstInfo { name: "vsmul" },
+    InstInfo { name: "vsoxei.v" },
+    InstInfo { name: "vsr.v" },
+    InstInfo { name: "vsra" },
+    InstInfo { name: "vsrl" },
+    InstInfo { name: "vsse.v" },
+    InstInfo { name: "vssra" },
+    InstInfo { name: "vssrl" },
+    InstInfo { name: "vssub" },
+    InstInfo { name: "vssubu" },
+    InstInfo { name: "vsub" },
+    InstInfo { name: "vsuxei.v" },
+    InstInfo { name: "vwadd" },
+    InstInfo { name: "vwadd.w" },
+    InstInfo { name: "vwaddu" },
+    InstInfo { name: "vwaddu.w" },
+    InstInfo { name: "vwmacc" },
+    InstInfo { name: "vwmaccsu" },
+    InstInfo { name: "vwmaccu" },
+    InstInfo { name: "vwmaccus" },
+    InstInfo { name: "vwmul" },
+    InstInfo { name: "vwmulsu" },
+    InstInfo { name: "vwmulu" },
+    InstInfo { name: "vwredsum" },
+    InstInfo { name: "vwredsumu" },
+    InstInfo { name: "vwsub" },
+    InstInfo { name: "vwsub.w" },
+    InstInfo { name: "vwsubu" },
+    InstInfo { name: "vwsubu.w" },
+    InstInfo { name: "vxor" },
+    InstInfo { name: "vzext.vf2" },
+    InstInfo { name: "vzext.vf4" },
+    InstInfo { name: "vzext.vf8" },
     InstInfo { name: "nop" },
     InstInfo { name: "wfi" },
     InstInfo { name: "xor" },
     InstInfo { name: "xori" },
+    InstInfo { name: "xperm4" },
+    InstInfo { name: "xperm8" },
+    InstInfo { name: "xnor" },
+    InstInfo { name: "zext.h" },
     InstInfo { name: "invalid" },
 ];
@@ -60,10 +60,18 @@ impl InstType {
         ((self.data >> 27) & 0x1f) as usize
     }
 
+    pub fn get_rm(&self) -> AddressType {
+        (self.data >> 12) & 0x7
+    }
+
     pub fn get_shamt_itype(&self) -> AddressType {
         (self.data >> 20) & 0x1f
     }
 
+    pub fn get_shamt6_itype(&self) -> AddressType {
+        (self.data >> 20) & 0x3f
+    }
+
     pub fn get_imm_itype(&self) -> AddressType {
         (self.data >> 20) & 0xfff
     }
@@ -326,6 +334,87 @@ pub mod tests {
         }
     }
 
+    // c.lbu/c.lhu/c.lh/c.sb/c.sh, funct3 selects the form and uimm is at most 3
+    pub fn inst_zcb_mem_code(
+        id: InstID,
+        funct3: AddressType,
+        rd_rs2: AddressType,
+        rs1: AddressType,
+        uimm: AddressType,
+    ) -> InstType {
+        let uimm_bits = match id {
+            InstID::C_LBU | InstID::C_SB => ((uimm & 1) << 6) | (((uimm >> 1) & 1) << 5),
+            InstID::C_LH => (1 << 6) | (((uimm >> 1) & 1) << 5),
+            _ => ((uimm >> 1) & 1) << 5,
+        };
+        InstType {
+            data: (0x8 << 12)
+                | (funct3 << 10)
+                | ((rs1 & 0x7) << 7)
+                | uimm_bits
+                | ((rd_rs2 & 0x7) << 2),
+            len: 2,
+            id,
+        }
+    }
+
+    // c.zext.b .. c.not, funct selects the operation
+    pub fn inst_zcb_unary_code(id: InstID, funct: AddressType, rd: AddressType) -> InstType {
+        InstType {
+            data: (0x27 << 10) | ((rd & 0x7) << 7) | (0x3 << 5) | (funct << 2) | 0x1,
+            len: 2,
+            id,
+        }
+    }
+
+    pub fn inst_c_mul_code(rd: AddressType, rs2: AddressType) -> InstType {
+        InstType {
+            data: (0x27 << 10) | ((rd & 0x7) << 7) | (0x2 << 5) | ((rs2 & 0x7) << 2) | 0x1,
+            len: 2,
+            id: InstID::C_MUL,
+        }
+    }
+
+    // cm.push/pop/popretz/popret, funct5 is 0x18/0x1a/0x1c/0x1e
+    pub fn inst_cm_stack_code(
+        id: InstID,
+        funct5: AddressType,
+        rlist: AddressType,
+        spimm: AddressType,
+    ) -> InstType {
+        InstType {
+            data: (0x5 << 13) | (funct5 << 8) | (rlist << 4) | (spimm << 2) | 0x2,
+            len: 2,
+            id,
+        }
+    }
+
+    // cm.mvsa01 (funct2 1) and cm.mva01s (funct2 3) with s-register indexes
+    pub fn inst_cm_mv_code(
+        id: InstID,
+        funct2: AddressType,
+        r1s: AddressType,
+        r2s: AddressType,
+    ) -> InstType {
+        InstType {
+            data: (0x2b << 10) | (r1s << 7) | (funct2 << 5) | (r2s << 2) | 0x2,
+            len: 2,
+            id,
+        }
+    }
+
+    pub fn inst_cm_jt_code(index: AddressType) -> InstType {
+        InstType {
+            data: (0x28 << 10) | (index << 2) | 0x2,
+            len: 2,
+            id: if index < 32 {
+                InstID::CM_JT
+            } else {
+                InstID::CM_JALT
+            },
+        }
+    }
+
     pub fn inst_jal_code(rd: AddressType, imm: AddressType) -> InstType {
         InstType {
             data: ((rd & 0x1f) << 7)
@@ -420,4 +509,520 @@ pub mod tests {
             id: InstID::SRAI,
         }
     }
+
+    pub fn inst_add_uw_code(rd: AddressType, rs1: AddressType, rs2: AddressType) -> InstType {
+        InstType {
+            data: (0x4 << 25) | (rs2 << 20) | (rs1 << 15) | (0x0 << 12) | (rd << 7) | 0x3b,
+            len: 4,
+            id: InstID::ADD_UW,
+        }
+    }
+
+    pub fn inst_andn_code(rd: AddressType, rs1: AddressType, rs2: AddressType) -> InstType {
+        InstType {
+            data: (0x20 << 25) | (rs2 << 20) | (rs1 << 15) | (0x7 << 12) | (rd << 7) | 0x33,
+            len: 4,
+            id: InstID::ANDN,
+        }
+    }
+
+    pub fn inst_bexti_code(rd: AddressType, rs1: AddressType, shamt: AddressType) -> InstType {
+        InstType {
+            data: (0x12 << 26) | (shamt << 20) | (rs1 << 15) | (0x5 << 12) | (rd << 7) | 0x13,
+            len: 4,
+            id: InstID::BEXTI,
+        }
+    }
+
+    pub fn inst_binv_code(rd: AddressType, rs1: AddressType, rs2: AddressType) -> InstType {
+        InstType {
+            data: (0x34 << 25) | (rs2 << 20) | (rs1 << 15) | (0x1 << 12) | (rd << 7) | 0x33,
+            len: 4,
+            id: InstID::BINV,
+        }
+    }
+
+    pub fn inst_bseti_code(rd: AddressType, rs1: AddressType, shamt: AddressType) -> InstType {
+        InstType {
+            data: (0xa << 26) | (shamt << 20) | (rs1 << 15) | (0x1 << 12) | (rd << 7) | 0x13,
+            len: 4,
+            id: InstID::BSETI,
+        }
+    }
+
+    pub fn inst_clmul_code(rd: AddressType, rs1: AddressType, rs2: AddressType) -> InstType {
+        InstType {
+            data: (0x5 << 25) | (rs2 << 20) | (rs1 << 15) | (0x1 << 12) | (rd << 7) | 0x33,
+            len: 4,
+            id: InstID::CLMUL,
+        }
+    }
+
+    pub fn inst_clmulh_code(rd: AddressType, rs1: AddressType, rs2: AddressType) -> InstType {
+        InstType {
+            data: (0x5 << 25) | (rs2 << 20) | (rs1 << 15) | (0x3 << 12) | (rd << 7) | 0x33,
+            len: 4,
+            id: InstID::CLMULH,
+        }
+    }
+
+    pub fn inst_clmulr_code(rd: AddressType, rs1: AddressType, rs2: AddressType) -> InstType {
+        InstType {
+            data: (0x5 << 25) | (rs2 << 20) | (rs1 << 15) | (0x2 << 12) | (rd << 7) | 0x33,
+            len: 4,
+            id: InstID::CLMULR,
+        }
+    }
+
+    pub fn inst_clz_code(rd: AddressType, rs1: AddressType) -> InstType {
+        InstType {
+            data: (0x600 << 20) | (rs1 << 15) | (0x1 << 12) | (rd << 7) | 0x13,
+            len: 4,
+            id: InstID::CLZ,
+        }
+    }
+
+    pub fn inst_cpopw_code(rd: AddressType, rs1: AddressType) -> InstType {
+        InstType {
+            data: (0x602 << 20) | (rs1 << 15) | (0x1 << 12) | (rd << 7) | 0x1b,
+            len: 4,
+            id: InstID::CPOPW,
+        }
+    }
+
+    pub fn inst_ctz_code(rd: AddressType, rs1: AddressType) -> InstType {
+        InstType {
+            data: (0x601 << 20) | (rs1 << 15) | (0x1 << 12) | (rd << 7) | 0x13,
+            len: 4,
+            id: InstID::CTZ,
+        }
+    }
+
+    pub fn inst_max_code(rd: AddressType, rs1: AddressType, rs2: AddressType) -> InstType {
+        InstType {
+            data: (0x5 << 25) | (rs2 << 20) | (rs1 << 15) | (0x6 << 12) | (rd << 7) | 0x33,
+            len: 4,
+            id: InstID::MAX,
+        }
+    }
+
+    pub fn inst_minu_code(rd: AddressType, rs1: AddressType, rs2: AddressType) -> InstType {
+        InstType {
+            data: (0x5 << 25) | (rs2 << 20) | (rs1 << 15) | (0x5 << 12) | (rd << 7) | 0x33,
+            len: 4,
+            id: InstID::MINU,
+        }
+    }
+
+    pub fn inst_czero_eqz_code(rd: AddressType, rs1: AddressType, rs2: AddressType) -> InstType {
+        InstType {
+            data: (0x7 << 25) | (rs2 << 20) | (rs1 << 15) | (0x5 << 12) | (rd << 7) | 0x33,
+            len: 4,
+            id: InstID::CZERO_EQZ,
+        }
+    }
+
+    pub fn inst_czero_nez_code(rd: AddressType, rs1: AddressType, rs2: AddressType) -> InstType {
+        InstType {
+            data: (0x7 << 25) | (rs2 << 20) | (rs1 << 15) | (0x7 << 12) | (rd << 7) | 0x33,
+            len: 4,
+            id: InstID::CZERO_NEZ,
+        }
+    }
+
+    // n is scattered over bits 30, 27:26 and 21:20
+    pub fn inst_mop_r_code(n: AddressType, rd: AddressType, rs1: AddressType) -> InstType {
+        let fixed = (1 << 31) | (((n >> 4) & 1) << 30) | (((n >> 2) & 3) << 26) | (0x7 << 22);
+        InstType {
+            data: fixed | ((n & 3) << 20) | (rs1 << 15) | (0x4 << 12) | (rd << 7) | 0x73,
+            len: 4,
+            id: InstID::MOP_R,
+        }
+    }
+
+    pub fn inst_mop_rr_code(
+        n: AddressType,
+        rd: AddressType,
+        rs1: AddressType,
+        rs2: AddressType,
+    ) -> InstType {
+        let fixed = (1 << 31) | (((n >> 2) & 1) << 30) | ((n & 3) << 26) | (1 << 25);
+        InstType {
+            data: fixed | (rs2 << 20) | (rs1 << 15) | (0x4 << 12) | (rd << 7) | 0x73,
+            len: 4,
+            id: InstID::MOP_RR,
+        }
+    }
+
+    pub fn inst_orc_b_code(rd: AddressType, rs1: AddressType) -> InstType {
+        InstType {
+            data: (0x287 << 20) | (rs1 << 15) | (0x5 << 12) | (rd << 7) | 0x13,
+            len: 4,
+            id: InstID::ORC_B,
+        }
+    }
+
+    pub fn inst_rev8_code(rd: AddressType, rs1: AddressType) -> InstType {
+        InstType {
+            data: (0x6b8 << 20) | (rs1 << 15) | (0x5 << 12) | (rd << 7) | 0x13,
+            len: 4,
+            id: InstID::REV8,
+        }
+    }
+
+    pub fn inst_rolw_code(rd: AddressType, rs1: AddressType, rs2: AddressType) -> InstType {
+        InstType {
+            data: (0x30 << 25) | (rs2 << 20) | (rs1 << 15) | (0x1 << 12) | (rd << 7) | 0x3b,
+            len: 4,
+            id: InstID::ROLW,
+        }
+    }
+
+    pub fn inst_rori_code(rd: AddressType, rs1: AddressType, shamt: AddressType) -> InstType {
+        InstType {
+            data: (0x18 << 26) | (shamt << 20) | (rs1 << 15) | (0x5 << 12) | (rd << 7) | 0x13,
+            len: 4,
+            id: InstID::RORI,
+        }
+    }
+
+    pub fn inst_sext_b_code(rd: AddressType, rs1: AddressType) -> InstType {
+        InstType {
+            data: (0x604 << 20) | (rs1 << 15) | (0x1 << 12) | (rd << 7) | 0x13,
+            len: 4,
+            id: InstID::SEXT_B,
+        }
+    }
+
+    pub fn inst_sh2add_code(rd: AddressType, rs1: AddressType, rs2: AddressType) -> InstType {
+        InstType {
+            data: (0x10 << 25) | (rs2 << 20) | (rs1 << 15) | (0x4 << 12) | (rd << 7) | 0x33,
+            len: 4,
+            id: InstID::SH2ADD,
+        }
+    }
+
+    pub fn inst_sh3add_uw_code(rd: AddressType, rs1: AddressType, rs2: AddressType) -> InstType {
+        InstType {
+            data: (0x10 << 25) | (rs2 << 20) | (rs1 << 15) | (0x6 << 12) | (rd << 7) | 0x3b,
+            len: 4,
+            id: InstID::SH3ADD_UW,
+        }
+    }
+
+    pub fn inst_slli_uw_code(rd: AddressType, rs1: AddressType, shamt: AddressType) -> InstType {
+        InstType {
+            data: (0x2 << 26) | (shamt << 20) | (rs1 << 15) | (0x1 << 12) | (rd << 7) | 0x1b,
+            len: 4,
+            id: InstID::SLLI_UW,
+        }
+    }
+
+    pub fn inst_xnor_code(rd: AddressType, rs1: AddressType, rs2: AddressType) -> InstType {
+        InstType {
+            data: (0x20 << 25) | (rs2 << 20) | (rs1 << 15) | (0x4 << 12) | (rd << 7) | 0x33,
+            len: 4,
+            id: InstID::XNOR,
+        }
+    }
+
+    pub fn inst_zext_h_code(rd: AddressType, rs1: AddressType) -> InstType {
+        InstType {
+            data: (0x80 << 20) | (rs1 << 15) | (0x4 << 12) | (rd << 7) | 0x3b,
+            len: 4,
+            id: InstID::ZEXT_H,
+        }
+    }
+
+    pub fn inst_fadd_s_code(
+        rd: AddressType,
+        rs1: AddressType,
+        rs2: AddressType,
+        rm: AddressType,
+    ) -> InstType {
+        InstType {
+            data: (rs2 << 20) | (rs1 << 15) | (rm << 12) | (rd << 7) | 0x53,
+            len: 4,
+            id: InstID::FADD_S,
+        }
+    }
+
+    pub fn inst_fcvt_w_s_code(rd: AddressType, rs1: AddressType, rm: AddressType) -> InstType {
+        InstType {
+            data: (0x60 << 25) | (rs1 << 15) | (rm << 12) | (rd << 7) | 0x53,
+            len: 4,
+            id: InstID::FCVT_W_S,
+        }
+    }
+
+    pub fn inst_fcvt_d_s_code(rd: AddressType, rs1: AddressType) -> InstType {
+        InstType {
+            data: (0x21 << 25) | (rs1 << 15) | (rd << 7) | 0x53,
+            len: 4,
+            id: InstID::FCVT_D_S,
+        }
+    }
+
+    pub fn inst_fmv_w_x_code(rd: AddressType, rs1: AddressType) -> InstType {
+        InstType {
+            data: (0x78 << 25) | (rs1 << 15) | (rd << 7) | 0x53,
+            len: 4,
+            id: InstID::FMV_W_X,
+        }
+    }
+
+    pub fn inst_fmv_x_w_code(rd: AddressType, rs1: AddressType) -> InstType {
+        InstType {
+            data: (0x70 << 25) | (rs1 << 15) | (rd << 7) | 0x53,
+            len: 4,
+            id: InstID::FMV_X_W,
+        }
+    }
+
+    // OP-FP encoding shared by the table-driven fflags tests
+    pub fn inst_fp_code(
+        id: InstID,
+        funct7: AddressType,
+        rd: AddressType,
+        rs1: AddressType,
+        rs2: AddressType,
+        rm: AddressType,
+    ) -> InstType {
+        InstType {
+            data: (funct7 << 25) | (rs2 << 20) | (rs1 << 15) | (rm << 12) | (rd << 7) | 0x53,
+            len: 4,
+            id,
+        }
+    }
+
+    // fmadd/fmsub/fnmsub/fnmadd, fmt is 0 for S, 1 for D and 2 for H
+    pub fn inst_fp_r4_code(
+        id: InstID,
+        opcode: AddressType,
+        fmt: AddressType,
+        rd: AddressType,
+        rs1: AddressType,
+        rs2: AddressType,
+        rs3: AddressType,
+    ) -> InstType {
+        InstType {
+            data: (rs3 << 27) | (fmt << 25) | (rs2 << 20) | (rs1 << 15) | (rd << 7) | opcode,
+            len: 4,
+            id,
+        }
+    }
+
+    // AMO encoding with aq/rl clear, funct3 selects the access width
+    pub fn inst_amo_code(
+        id: InstID,
+        funct5: AddressType,
+        funct3: AddressType,
+        rd: AddressType,
+        rs1: AddressType,
+        rs2: AddressType,
+    ) -> InstType {
+        InstType {
+            data: (funct5 << 27) | (rs2 << 20) | (rs1 << 15) | (funct3 << 12) | (rd << 7) | 0x2f,
+            len: 4,
+            id,
+        }
+    }
+
+    // cbo.inval/clean/flush/zero are funct12 0/1/2/4
+    pub fn inst_cbo_code(id: InstID, funct12: AddressType, rs1: AddressType) -> InstType {
+        InstType {
+            data: (funct12 << 20) | (rs1 << 15) | (0x2 << 12) | 0x0f,
+            len: 4,
+            id,
+        }
+    }
+
+    // prefetch.i/r/w are ori hints with rd=0, imm[4:0] is 0/1/3
+    pub fn inst_prefetch_code(
+        id: InstID,
+        funct5: AddressType,
+        rs1: AddressType,
+        offset: AddressType,
+    ) -> InstType {
+        InstType {
+            data: ((offset & 0xfe0) << 20) | (funct5 << 20) | (rs1 << 15) | (0x6 << 12) | 0x13,
+            len: 4,
+            id,
+        }
+    }
+
+    pub fn inst_fadd_h_code(rd: AddressType, rs1: AddressType, rs2: AddressType) -> InstType {
+        InstType {
+            data: (0x02 << 25) | (rs2 << 20) | (rs1 << 15) | (0x7 << 12) | (rd << 7) | 0x53,
+            len: 4,
+            id: InstID::FADD_H,
+        }
+    }
+
+    pub fn inst_fcvt_s_h_code(rd: AddressType, rs1: AddressType) -> InstType {
+        InstType {
+            data: (0x20 << 25) | (0x2 << 20) | (rs1 << 15) | (rd << 7) | 0x53,
+            len: 4,
+            id: InstID::FCVT_S_H,
+        }
+    }
+
+    pub fn inst_fcvt_w_h_code(rd: AddressType, rs1: AddressType) -> InstType {
+        InstType {
+            data: (0x62 << 25) | (rs1 << 15) | (0x1 << 12) | (rd << 7) | 0x53,
+            len: 4,
+            id: InstID::FCVT_W_H,
+        }
+    }
+
+    pub fn inst_flh_code(rd: AddressType, rs1: AddressType, imm: AddressType) -> InstType {
+        InstType {
+            data: (imm << 20) | (rs1 << 15) | (0x1 << 12) | (rd << 7) | 0x07,
+            len: 4,
+            id: InstID::FLH,
+        }
+    }
+
+    pub fn inst_fmv_h_x_code(rd: AddressType, rs1: AddressType) -> InstType {
+        InstType {
+            data: (0x7a << 25) | (rs1 << 15) | (rd << 7) | 0x53,
+            len: 4,
+            id: InstID::FMV_H_X,
+        }
+    }
+
+    pub fn inst_fmv_x_h_code(rd: AddressType, rs1: AddressType) -> InstType {
+        InstType {
+            data: (0x72 << 25) | (rs1 << 15) | (rd << 7) | 0x53,
+            len: 4,
+            id: InstID::FMV_X_H,
+        }
+    }
+
+    pub fn inst_vsetvli_code(rd: AddressType, rs1: AddressType, vtypei: AddressType) -> InstType {
+        InstType {
+            data: (vtypei << 20) | (rs1 << 15) | (0x7 << 12) | (rd << 7) | 0x57,
+            len: 4,
+            id: InstID::VSETVLI,
+        }
+    }
+
+    pub fn inst_vsetivli_code(rd: AddressType, uimm: AddressType, vtypei: AddressType) -> InstType {
+        InstType {
+            data: (0x3 << 30) | (vtypei << 20) | (uimm << 15) | (0x7 << 12) | (rd << 7) | 0x57,
+            len: 4,
+            id: InstID::VSETIVLI,
+        }
+    }
+
+    // Unit-stride vle<eew>.v/vse<eew>.v, width is the funct3 encoding of EEW
+    pub fn inst_vle_v_code(vd: AddressType, rs1: AddressType, width: AddressType) -> InstType {
+        InstType {
+            data: (0x1 << 25) | (rs1 << 15) | (width << 12) | (vd << 7) | 0x07,
+            len: 4,
+            id: InstID::VLE_V,
+        }
+    }
+
+    pub fn inst_vleff_v_code(vd: AddressType, rs1: AddressType, width: AddressType) -> InstType {
+        InstType {
+            data: (0x1 << 25) | (0x10 << 20) | (rs1 << 15) | (width << 12) | (vd << 7) | 0x07,
+            len: 4,
+            id: InstID::VLEFF_V,
+        }
+    }
+
+    pub fn inst_vlse_v_code(
+        vd: AddressType,
+        rs1: AddressType,
+        rs2: AddressType,
+        width: AddressType,
+    ) -> InstType {
+        InstType {
+            data: (0x2 << 26)
+                | (0x1 << 25)
+                | (rs2 << 20)
+                | (rs1 << 15)
+                | (width << 12)
+                | (vd << 7)
+                | 0x07,
+            len: 4,
+            id: InstID::VLSE_V,
+        }
+    }
+
+    pub fn inst_vse_v_code(vs3: AddressType, rs1: AddressType, width: AddressType) -> InstType {
+        InstType {
+            data: (0x1 << 25) | (rs1 << 15) | (width << 12) | (vs3 << 7) | 0x27,
+            len: 4,
+            id: InstID::VSE_V,
+        }
+    }
+
+    // OP-V arithmetic, funct3 selects the .vv/.vx/.vi/.vf form, vm is 1 when unmasked
+    pub fn inst_vector_code(
+        id: InstID,
+        funct6: AddressType,
+        funct3: AddressType,
+        vm: AddressType,
+        vd: AddressType,
+        vs2: AddressType,
+        vs1: AddressType,
+    ) -> InstType {
+        InstType {
+            data: (funct6 << 26)
+                | (vm << 25)
+                | (vs2 << 20)
+                | (vs1 << 15)
+                | (funct3 << 12)
+                | (vd << 7)
+                | 0x57,
+            len: 4,
+            id,
+        }
+    }
+
+    pub fn inst_op_code(
+        id: InstID,
+        opcode: AddressType,
+        funct7: AddressType,
+        funct3: AddressType,
+        rd: AddressType,
+        rs1: AddressType,
+        rs2: AddressType,
+    ) -> InstType {
+        InstType {
+            data: (funct7 << 25) | (rs2 << 20) | (rs1 << 15) | (funct3 << 12) | (rd << 7) | opcode,
+            len: 4,
+            id,
+        }
+    }
+
+    pub fn inst_op_imm_code(
+        id: InstID,
+        imm: AddressType,
+        funct3: AddressType,
+        rd: AddressType,
+        rs1: AddressType,
+    ) -> InstType {
+        InstType {
+            data: (imm << 20) | (rs1 << 15) | (funct3 << 12) | (rd << 7) | 0x13,
+            len: 4,
+            id,
+        }
+    }
+
+    pub fn inst_csr_code(
+        id: InstID,
+        funct3: AddressType,
+        rd: AddressType,
+        rs1: AddressType,
+        csr: AddressType,
+    ) -> InstType {
+        InstType {
+            data: (csr << 20) | (rs1 << 15) | (funct3 << 12) | (rd << 7) | 0x73,
+            len: 4,
+            id,
+        }
+    }
 }
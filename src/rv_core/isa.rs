@@ -0,0 +1,466 @@
+type AddressType = u64;
+
+pub const DEFAULT_ISA: &str = "rv64imafdc_zicsr_zifencei";
+
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum Extension {
+    I,
+    M,
+    A,
+    F,
+    D,
+    C,
+    V,
+    H,
+    Zicsr,
+    Zifencei,
+    Zfh,
+    Zfhmin,
+    Zfa,
+    Zacas,
+    Zabha,
+    Zicond,
+    Zihintpause,
+    Zihintntl,
+    Zimop,
+    Zcmop,
+    Zcb,
+    Zcmp,
+    Zcmt,
+    Zicbom,
+    Zicboz,
+    Zicbop,
+    Zbkb,
+    Zbkc,
+    Zbkx,
+    Zknd,
+    Zkne,
+    Zknh,
+    Zksed,
+    Zksh,
+    Zkn,
+    Zks,
+    Zkr,
+    Zk,
+    Zkt,
+    Zfinx,
+    Zdinx,
+    Zba,
+    Zbb,
+    Zbc,
+    Zbs,
+    Zve64d,
+    Sstc,
+    Smaia,
+    Ssaia,
+    Smclic,
+    Smrnmi,
+    Ssdbltrp,
+    Smdbltrp,
+    Sdtrig,
+    Sdext,
+}
+
+impl Extension {
+    fn from_name(name: &str) -> Option<Extension> {
+        match name {
+            "i" => Some(Extension::I),
+            "m" => Some(Extension::M),
+            "a" => Some(Extension::A),
+            "f" => Some(Extension::F),
+            "d" => Some(Extension::D),
+            "c" => Some(Extension::C),
+            "v" => Some(Extension::V),
+            "h" => Some(Extension::H),
+            "zicsr" => Some(Extension::Zicsr),
+            "zifencei" => Some(Extension::Zifencei),
+            "zfh" => Some(Extension::Zfh),
+            "zfhmin" => Some(Extension::Zfhmin),
+            "zfa" => Some(Extension::Zfa),
+            "zacas" => Some(Extension::Zacas),
+            "zabha" => Some(Extension::Zabha),
+            "zicond" => Some(Extension::Zicond),
+            "zihintpause" => Some(Extension::Zihintpause),
+            "zihintntl" => Some(Extension::Zihintntl),
+            "zimop" => Some(Extension::Zimop),
+            "zcmop" => Some(Extension::Zcmop),
+            "zcb" => Some(Extension::Zcb),
+            "zcmp" => Some(Extension::Zcmp),
+            "zcmt" => Some(Extension::Zcmt),
+            "zicbom" => Some(Extension::Zicbom),
+            "zicboz" => Some(Extension::Zicboz),
+            "zicbop" => Some(Extension::Zicbop),
+            "zbkb" => Some(Extension::Zbkb),
+            "zbkc" => Some(Extension::Zbkc),
+            "zbkx" => Some(Extension::Zbkx),
+            "zknd" => Some(Extension::Zknd),
+            "zkne" => Some(Extension::Zkne),
+            "zknh" => Some(Extension::Zknh),
+            "zksed" => Some(Extension::Zksed),
+            "zksh" => Some(Extension::Zksh),
+            "zkn" => Some(Extension::Zkn),
+            "zks" => Some(Extension::Zks),
+            "zkr" => Some(Extension::Zkr),
+            "zk" => Some(Extension::Zk),
+            "zkt" => Some(Extension::Zkt),
+            "zfinx" => Some(Extension::Zfinx),
+            "zdinx" => Some(Extension::Zdinx),
+            "zba" => Some(Extension::Zba),
+            "zbb" => Some(Extension::Zbb),
+            "zbc" => Some(Extension::Zbc),
+            "zbs" => Some(Extension::Zbs),
+            "zve64d" => Some(Extension::Zve64d),
+            "sstc" => Some(Extension::Sstc),
+            "smaia" => Some(Extension::Smaia),
+            "ssaia" => Some(Extension::Ssaia),
+            "smclic" => Some(Extension::Smclic),
+            "smrnmi" => Some(Extension::Smrnmi),
+            "ssdbltrp" => Some(Extension::Ssdbltrp),
+            "smdbltrp" => Some(Extension::Smdbltrp),
+            "sdtrig" => Some(Extension::Sdtrig),
+            "sdext" => Some(Extension::Sdext),
+            _ => None,
+        }
+    }
+
+    // Single-letter extensions are reflected in misa, the others are fixed at startup
+    pub fn misa_bit(&self) -> Option<AddressType> {
+        match self {
+            Extension::I => Some(1 << 8),
+            Extension::M => Some(1 << 12),
+            Extension::A => Some(1 << 0),
+            Extension::F => Some(1 << 5),
+            Extension::D => Some(1 << 3),
+            Extension::C => Some(1 << 2),
+            Extension::V => Some(1 << 21),
+            Extension::H => Some(1 << 7),
+            _ => None,
+        }
+    }
+
+    // Extensions this one cannot be enabled without
+    fn depends_on(&self) -> &'static [Extension] {
+        match self {
+            Extension::D | Extension::Zfh | Extension::Zfhmin | Extension::Zfa => &[Extension::F],
+            Extension::V | Extension::Zve64d => &[Extension::D],
+            Extension::Zdinx => &[Extension::Zfinx],
+            Extension::Zacas | Extension::Zabha => &[Extension::A],
+            Extension::Zcmop | Extension::Zcb | Extension::Zcmp => &[Extension::C],
+            Extension::Zcmt => &[Extension::C, Extension::Zicsr],
+            Extension::Zkr
+            | Extension::Sstc
+            | Extension::Smaia
+            | Extension::Ssaia
+            | Extension::Smclic
+            | Extension::Smrnmi
+            | Extension::Ssdbltrp
+            | Extension::Smdbltrp
+            | Extension::Sdtrig
+            | Extension::Sdext => &[Extension::Zicsr],
+            _ => &[],
+        }
+    }
+
+    // Zfinx/Zdinx provide the F/D computational instructions on the x registers
+    pub fn finx_equivalent(&self) -> Option<Extension> {
+        match self {
+            Extension::F => Some(Extension::Zfinx),
+            Extension::D => Some(Extension::Zdinx),
+            _ => None,
+        }
+    }
+
+    // Extensions that come along with this one
+    fn implies(&self) -> &'static [Extension] {
+        match self {
+            Extension::V => &[Extension::Zve64d],
+            Extension::Zfh => &[Extension::Zfhmin],
+            Extension::Zkn => &[
+                Extension::Zbkb,
+                Extension::Zbkc,
+                Extension::Zbkx,
+                Extension::Zkne,
+                Extension::Zknd,
+                Extension::Zknh,
+            ],
+            Extension::Zks => &[
+                Extension::Zbkb,
+                Extension::Zbkc,
+                Extension::Zbkx,
+                Extension::Zksed,
+                Extension::Zksh,
+            ],
+            Extension::Zk => &[Extension::Zkn, Extension::Zkr, Extension::Zkt],
+            // The S-level CSRs are part of Smaia
+            Extension::Smaia => &[Extension::Ssaia],
+            _ => &[],
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Isa {
+    extensions: Vec<Extension>,
+}
+
+impl Isa {
+    pub fn parse(isa_str: &str) -> Result<Isa, String> {
+        let lower = isa_str.to_lowercase();
+        let rest = match lower.strip_prefix("rv64") {
+            Some(rest) => rest,
+            None if lower.starts_with("rv32") => {
+                return Err(format!("{}: only RV64 is supported", isa_str))
+            }
+            None => return Err(format!("{}: ISA string must start with rv64", isa_str)),
+        };
+
+        // Like Spike, Zicsr and Zifencei are implied for strings predating their split from I
+        let mut isa = Isa {
+            extensions: vec![Extension::Zicsr, Extension::Zifencei],
+        };
+
+        let mut tokens = rest.split('_');
+        let single_letters = tokens.next().unwrap_or("");
+        let mut chars = single_letters.chars().peekable();
+        match chars.next() {
+            Some('i') => isa.add(Extension::I),
+            Some('g') => {
+                for ext in [
+                    Extension::I,
+                    Extension::M,
+                    Extension::A,
+                    Extension::F,
+                    Extension::D,
+                ]
+                .iter()
+                {
+                    isa.add(*ext);
+                }
+            }
+            Some('e') => return Err(format!("{}: RV64E is not supported", isa_str)),
+            _ => return Err(format!("{}: missing base ISA (i, e or g)", isa_str)),
+        }
+        Isa::skip_version(&mut chars);
+
+        while let Some(c) = chars.next() {
+            if c == 'z' || c == 's' || c == 'x' {
+                return Err(format!(
+                    "{}: multi-letter extensions must be separated by '_'",
+                    isa_str
+                ));
+            }
+            match Extension::from_name(&c.to_string()) {
+                Some(ext) => isa.add(ext),
+                None => return Err(format!("{}: unsupported extension '{}'", isa_str, c)),
+            }
+            Isa::skip_version(&mut chars);
+        }
+
+        for token in tokens {
+            match Extension::from_name(Isa::strip_version(token)) {
+                Some(ext) => isa.add(ext),
+                None => return Err(format!("{}: unsupported extension '{}'", isa_str, token)),
+            }
+        }
+
+        if isa.has(Extension::F) && isa.has(Extension::Zfinx) {
+            return Err(format!("{}: F and Zfinx are mutually exclusive", isa_str));
+        }
+        // Zcmp/Zcmt reuse the encodings of the compressed double-precision loads/stores
+        if (isa.has(Extension::Zcmp) || isa.has(Extension::Zcmt))
+            && isa.has(Extension::C)
+            && isa.has(Extension::D)
+        {
+            return Err(format!(
+                "{}: Zcmp/Zcmt are incompatible with C and D",
+                isa_str
+            ));
+        }
+        for ext in isa.extensions.iter() {
+            for dep in ext.depends_on() {
+                if !isa.has(*dep) {
+                    return Err(format!("{}: {:?} requires {:?}", isa_str, ext, dep));
+                }
+            }
+        }
+
+        Ok(isa)
+    }
+
+    fn skip_version(chars: &mut std::iter::Peekable<std::str::Chars>) {
+        while let Some(c) = chars.peek() {
+            if c.is_ascii_digit() || *c == 'p' {
+                chars.next();
+            } else {
+                break;
+            }
+        }
+    }
+
+    // "zba1p0" -> "zba", "zicbop" stays untouched
+    fn strip_version(name: &str) -> &str {
+        let without_minor = name.trim_end_matches(|c: char| c.is_ascii_digit());
+        if without_minor.len() == name.len() {
+            return name;
+        }
+        match without_minor.strip_suffix('p') {
+            Some(major) if major.ends_with(|c: char| c.is_ascii_digit()) => {
+                major.trim_end_matches(|c: char| c.is_ascii_digit())
+            }
+            _ => without_minor,
+        }
+    }
+
+    fn add(&mut self, ext: Extension) {
+        if !self.extensions.contains(&ext) {
+            self.extensions.push(ext);
+        }
+        for implied in ext.implies() {
+            self.add(*implied);
+        }
+    }
+
+    pub fn has(&self, ext: Extension) -> bool {
+        self.extensions.contains(&ext)
+    }
+
+    // Value of misa at reset: MXL plus every configured single-letter extension.
+    // S and U mode are always implemented.
+    pub fn misa(&self) -> AddressType {
+        let mut misa = (2 as AddressType) << 62;
+        misa |= (1 << 18) | (1 << 20);
+        for ext in self.extensions.iter() {
+            if let Some(bit) = ext.misa_bit() {
+                misa |= bit;
+            }
+        }
+        misa
+    }
+
+    // misa bits software is allowed to toggle at runtime
+    pub fn misa_writable_mask(&self) -> AddressType {
+        [
+            Extension::M,
+            Extension::A,
+            Extension::F,
+            Extension::D,
+            Extension::C,
+        ]
+        .iter()
+        .filter(|ext| self.has(**ext))
+        .fold(0, |mask, ext| mask | ext.misa_bit().unwrap())
+    }
+}
+
+impl Default for Isa {
+    fn default() -> Isa {
+        Isa::parse(DEFAULT_ISA).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_isa() {
+        let isa = Isa::parse("rv64imafdc_zicsr_zifencei_zba_zbb").unwrap();
+        assert!(isa.has(Extension::Zba));
+        assert!(!isa.has(Extension::Zbc));
+        assert!(isa.has(Extension::F));
+        assert!(isa.has(Extension::D));
+        assert!(isa.has(Extension::Zifencei));
+        assert_eq!(
+            (2 << 62)
+                | (1 << 0)
+                | (1 << 2)
+                | (1 << 3)
+                | (1 << 5)
+                | (1 << 8)
+                | (1 << 12)
+                | (1 << 18)
+                | (1 << 20),
+            isa.misa()
+        );
+
+        let isa = Isa::parse("RV64GC").unwrap();
+        assert!(isa.has(Extension::D));
+        assert!(isa.has(Extension::C));
+
+        let isa = Isa::parse("rv64i2p1m_zifencei2p0").unwrap();
+        assert!(isa.has(Extension::Zicsr));
+        assert!(isa.has(Extension::M));
+        assert!(!isa.has(Extension::A));
+
+        let isa = Isa::parse("rv64gcv").unwrap();
+        assert!(isa.has(Extension::Zve64d));
+        assert_eq!(1 << 21, isa.misa() & (1 << 21));
+        let isa = Isa::parse("rv64gc_zve64d").unwrap();
+        assert!(!isa.has(Extension::V));
+
+        let isa = Isa::parse("rv64gc_zfh").unwrap();
+        assert!(isa.has(Extension::Zfhmin));
+        let isa = Isa::parse("rv64gc_zfa").unwrap();
+        assert!(isa.has(Extension::Zfa));
+        let isa = Isa::parse("rv64gc_zacas_zabha").unwrap();
+        assert!(isa.has(Extension::Zabha));
+        let isa = Isa::parse("rv64gc_zicond_zihintpause_zihintntl_zimop_zcmop").unwrap();
+        assert!(isa.has(Extension::Zicond));
+        assert!(isa.has(Extension::Zcmop));
+        let isa = Isa::parse("rv64imac_zcb_zcmp_zcmt").unwrap();
+        assert!(isa.has(Extension::Zcmp));
+        assert!(isa.has(Extension::Zcmt));
+        let isa = Isa::parse("rv64gc_zicbom_zicboz_zicbop").unwrap();
+        assert!(isa.has(Extension::Zicbom));
+        assert!(isa.has(Extension::Zicboz));
+        assert!(isa.has(Extension::Zicbop));
+        let isa = Isa::parse("rv64imac_zkn_zks_zkt").unwrap();
+        assert!(isa.has(Extension::Zknh));
+        assert!(isa.has(Extension::Zksed));
+        assert!(!isa.has(Extension::Zbb));
+        let isa = Isa::parse("rv64gch").unwrap();
+        assert!(isa.has(Extension::H));
+        assert_eq!(1 << 7, isa.misa() & (1 << 7));
+        let isa = Isa::parse("rv64imac_zk").unwrap();
+        assert!(isa.has(Extension::Zkr));
+        assert!(isa.has(Extension::Zkne));
+        assert!(!isa.has(Extension::Zksh));
+        let isa = Isa::parse("rv64gch_sstc").unwrap();
+        assert!(isa.has(Extension::Sstc));
+        let isa = Isa::parse("rv64gc_smaia").unwrap();
+        assert!(isa.has(Extension::Ssaia));
+        let isa = Isa::parse("rv64imac_smclic").unwrap();
+        assert!(isa.has(Extension::Smclic));
+        let isa = Isa::parse("rv64gc_smrnmi").unwrap();
+        assert!(isa.has(Extension::Smrnmi));
+        let isa = Isa::parse("rv64gch_ssdbltrp_smdbltrp").unwrap();
+        assert!(isa.has(Extension::Ssdbltrp));
+        assert!(isa.has(Extension::Smdbltrp));
+        let isa = Isa::parse("rv64gc_sdtrig").unwrap();
+        assert!(isa.has(Extension::Sdtrig));
+        let isa = Isa::parse("rv64gc_sdext_sdtrig").unwrap();
+        assert!(isa.has(Extension::Sdext));
+        let isa = Isa::parse("rv64imac_zfinx_zdinx").unwrap();
+        assert!(isa.has(Extension::Zdinx));
+        assert_eq!(0, isa.misa() & ((1 << 5) | (1 << 3)));
+    }
+
+    #[test]
+    fn test_parse_isa_error() {
+        assert!(Isa::parse("rv32imac").is_err());
+        assert!(Isa::parse("rv64mac").is_err());
+        assert!(Isa::parse("rv64iq").is_err());
+        assert!(Isa::parse("rv64id").is_err());
+        assert!(Isa::parse("rv64i_zfoo").is_err());
+        assert!(Isa::parse("rv64imacv").is_err());
+        assert!(Isa::parse("rv64imac_zfhmin").is_err());
+        assert!(Isa::parse("rv64imac_zfa").is_err());
+        assert!(Isa::parse("rv64gc_zfinx").is_err());
+        assert!(Isa::parse("rv64imc_zacas").is_err());
+        assert!(Isa::parse("rv64ima_zcmop").is_err());
+        assert!(Isa::parse("rv64ima_zcb").is_err());
+        assert!(Isa::parse("rv64gc_zcmp").is_err());
+        assert!(Isa::parse("rv64gc_zcmt").is_err());
+        assert!(Isa::parse("rv64imac_zdinx").is_err());
+    }
+}
@@ -0,0 +1,273 @@
+use crate::memory_interface::MemoryOperation;
+use crate::rv_core::{csregs, PrivilegeMode, RVCore};
+
+type AddressType = u64;
+
+const PAGE_SHIFT: usize = 12;
+const PTE_V: AddressType = 1 << 0;
+const PTE_R: AddressType = 1 << 1;
+const PTE_W: AddressType = 1 << 2;
+const PTE_X: AddressType = 1 << 3;
+const PTE_U: AddressType = 1 << 4;
+const PTE_A: AddressType = 1 << 6;
+const PTE_D: AddressType = 1 << 7;
+// N and PBMT are not implemented, so they are reserved like bits 60:54
+const PTE_RESERVED: AddressType = 0xffc0_0000_0000_0000;
+
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub(super) enum AccessType {
+    Fetch,
+    Load,
+    Store,
+}
+
+impl AccessType {
+    fn page_fault(&self) -> AddressType {
+        match self {
+            AccessType::Fetch => csregs::EXC_FETCH_PAGE_FAULT,
+            AccessType::Load => csregs::EXC_LOAD_PAGE_FAULT,
+            AccessType::Store => csregs::EXC_STORE_PAGE_FAULT,
+        }
+    }
+
+    fn guest_page_fault(&self) -> AddressType {
+        match self {
+            AccessType::Fetch => csregs::EXC_FETCH_GUEST_PAGE_FAULT,
+            AccessType::Load => csregs::EXC_LOAD_GUEST_PAGE_FAULT,
+            AccessType::Store => csregs::EXC_STORE_GUEST_PAGE_FAULT,
+        }
+    }
+}
+
+// Privilege and virtualization mode an access is translated for
+#[derive(Copy, Clone)]
+pub(super) struct Translation {
+    pub mode: PrivilegeMode,
+    pub virt: bool,
+    // hlvx.* needs execute instead of read permission
+    pub hlvx: bool,
+}
+
+// gpa is only set for guest-page faults
+struct PageFault {
+    cause: AddressType,
+    gpa: AddressType,
+}
+
+impl RVCore {
+    pub(super) fn fetch_translation(&self) -> Translation {
+        Translation {
+            mode: self.mode,
+            virt: self.virt,
+            hlvx: false,
+        }
+    }
+
+    // Loads and stores from M-mode use the MPP/MPV privilege when MPRV is set
+    pub(super) fn data_translation(&self) -> Translation {
+        let mstatus = self.csregs.read(csregs::MSTATUS);
+        // MPRV is ignored in debug mode unless dcsr.mprven is set
+        let mprven = !self.debug_mode || self.csregs.read(csregs::DCSR) & csregs::DCSR_MPRVEN != 0;
+        if self.mode != PrivilegeMode::M || mstatus & csregs::MSTATUS_MPRV == 0 || !mprven {
+            return self.fetch_translation();
+        }
+        let mode = match (mstatus & csregs::MSTATUS_MPP) >> 11 {
+            0 => PrivilegeMode::U,
+            1 => PrivilegeMode::S,
+            _ => PrivilegeMode::M,
+        };
+        Translation {
+            mode,
+            virt: mode != PrivilegeMode::M && mstatus & csregs::MSTATUS_MPV != 0,
+            hlvx: false,
+        }
+    }
+
+    // Physical address of vaddr, None when a page fault was raised instead
+    pub(super) fn translate(
+        &mut self,
+        vaddr: AddressType,
+        access: AccessType,
+        translation: Translation,
+    ) -> Option<AddressType> {
+        match self.two_stage(vaddr, access, translation) {
+            Ok(paddr) => Some(paddr),
+            Err(fault) => {
+                self.raise_trap(fault.cause, vaddr, fault.gpa >> 2, translation.virt);
+                None
+            }
+        }
+    }
+
+    // Whether a load or store of vaddr would page fault, without raising it
+    pub(super) fn data_access_faults(&mut self, vaddr: AddressType, access: AccessType) -> bool {
+        let translation = self.data_translation();
+        self.two_stage(vaddr, access, translation).is_err()
+    }
+
+    fn two_stage(
+        &mut self,
+        vaddr: AddressType,
+        access: AccessType,
+        translation: Translation,
+    ) -> Result<AddressType, PageFault> {
+        if translation.mode == PrivilegeMode::M {
+            return Ok(vaddr);
+        }
+        self.vs_stage(vaddr, access, translation).and_then(|gpa| {
+            if translation.virt {
+                self.g_stage(gpa, access, translation.hlvx)
+            } else {
+                Ok(gpa)
+            }
+        })
+    }
+
+    // Sv39 translation through satp, or through vsatp for the VS-stage when V=1
+    fn vs_stage(
+        &mut self,
+        vaddr: AddressType,
+        access: AccessType,
+        translation: Translation,
+    ) -> Result<AddressType, PageFault> {
+        let mstatus = self.csregs.read(csregs::MSTATUS);
+        let (atp, status) = if translation.virt {
+            let vsstatus = self.csregs.read(csregs::VSSTATUS);
+            // mstatus.MXR also applies to the VS-stage
+            (
+                self.csregs.read(csregs::VSATP),
+                vsstatus | (mstatus & csregs::MSTATUS_MXR),
+            )
+        } else {
+            (self.csregs.read(csregs::SATP), mstatus)
+        };
+        if atp & csregs::SATP_MODE == 0 {
+            return Ok(vaddr);
+        }
+
+        let page_fault = PageFault {
+            cause: access.page_fault(),
+            gpa: 0,
+        };
+        // Bits 63:39 have to be copies of bit 38
+        if RVCore::sign_extend(vaddr, 39) != vaddr {
+            return Err(page_fault);
+        }
+        let root = (atp & csregs::SATP_PPN) << PAGE_SHIFT;
+        let (pte, level) = match self.walk(root, vaddr, 9, access, translation.virt)? {
+            Some(leaf) => leaf,
+            None => return Err(page_fault),
+        };
+        let user_ok = match translation.mode {
+            PrivilegeMode::U => pte & PTE_U != 0,
+            _ => {
+                pte & PTE_U == 0
+                    || (access != AccessType::Fetch && status & csregs::MSTATUS_SUM != 0)
+            }
+        };
+        let mxr = status & csregs::MSTATUS_MXR != 0;
+        if !user_ok || !RVCore::leaf_permits(pte, access, mxr, translation.hlvx) {
+            return Err(page_fault);
+        }
+        RVCore::leaf_address(pte, level, vaddr, access).ok_or(page_fault)
+    }
+
+    // Sv39x4 translation of guest physical addresses through hgatp
+    fn g_stage(
+        &mut self,
+        gpa: AddressType,
+        access: AccessType,
+        hlvx: bool,
+    ) -> Result<AddressType, PageFault> {
+        let hgatp = self.csregs.read(csregs::HGATP);
+        if hgatp & csregs::SATP_MODE == 0 {
+            return Ok(gpa);
+        }
+
+        let guest_page_fault = PageFault {
+            cause: access.guest_page_fault(),
+            gpa,
+        };
+        if gpa >> 41 != 0 {
+            return Err(guest_page_fault);
+        }
+        let root = (hgatp & csregs::SATP_PPN) << PAGE_SHIFT;
+        let (pte, level) = match self.walk(root, gpa, 11, access, false)? {
+            Some(leaf) => leaf,
+            None => return Err(guest_page_fault),
+        };
+        // G-stage accesses are all treated as U-mode ones
+        let mxr = self.csregs.read(csregs::MSTATUS) & csregs::MSTATUS_MXR != 0;
+        if pte & PTE_U == 0 || !RVCore::leaf_permits(pte, access, mxr, hlvx) {
+            return Err(guest_page_fault);
+        }
+        RVCore::leaf_address(pte, level, gpa, access).ok_or(guest_page_fault)
+    }
+
+    // Leaf PTE and its level, None when the walk hits an invalid PTE. The VS-stage
+    // tables are in guest physical memory and are read through the G-stage.
+    fn walk(
+        &mut self,
+        root: AddressType,
+        addr: AddressType,
+        root_bits: usize,
+        access: AccessType,
+        guest_tables: bool,
+    ) -> Result<Option<(AddressType, usize)>, PageFault> {
+        let mut table = root;
+        for level in (0..3).rev() {
+            let bits = if level == 2 { root_bits } else { 9 };
+            let index = (addr >> (PAGE_SHIFT + level * 9)) & ((1 << bits) - 1);
+            let mut pte_addr = table + index * 8;
+            if guest_tables {
+                // Implicit accesses are G-stage loads, faults are reported for the original access
+                pte_addr = self
+                    .g_stage(pte_addr, AccessType::Load, false)
+                    .map_err(|fault| PageFault {
+                        cause: access.guest_page_fault(),
+                        gpa: fault.gpa,
+                    })?;
+            }
+            let mut data = [0; 8];
+            self.access_physical(pte_addr, &mut data, MemoryOperation::READ, false);
+            let pte = AddressType::from_le_bytes(data);
+            if pte & PTE_V == 0 || (pte & PTE_R == 0 && pte & PTE_W != 0) || pte & PTE_RESERVED != 0
+            {
+                return Ok(None);
+            }
+            if pte & (PTE_R | PTE_X) != 0 {
+                return Ok(Some((pte, level)));
+            }
+            table = ((pte >> 10) & csregs::SATP_PPN) << PAGE_SHIFT;
+        }
+        Ok(None)
+    }
+
+    fn leaf_permits(pte: AddressType, access: AccessType, mxr: bool, hlvx: bool) -> bool {
+        match access {
+            AccessType::Fetch => pte & PTE_X != 0,
+            AccessType::Load if hlvx => pte & PTE_X != 0,
+            AccessType::Load => pte & PTE_R != 0 || (mxr && pte & PTE_X != 0),
+            AccessType::Store => pte & PTE_W != 0,
+        }
+    }
+
+    // None for misaligned superpages, and for clear A/D bits as they are not
+    // updated by hardware (Svade)
+    fn leaf_address(
+        pte: AddressType,
+        level: usize,
+        addr: AddressType,
+        access: AccessType,
+    ) -> Option<AddressType> {
+        let base = ((pte >> 10) & csregs::SATP_PPN) << PAGE_SHIFT;
+        let offset_mask = (1 << (PAGE_SHIFT + level * 9)) - 1;
+        if base & offset_mask != 0
+            || pte & PTE_A == 0
+            || (access == AccessType::Store && pte & PTE_D == 0)
+        {
+            return None;
+        }
+        Some(base | (addr & offset_mask))
+    }
+}
@@ -0,0 +1,324 @@
+use crate::rv_core::PrivilegeMode;
+use std::cmp;
+
+type AddressType = u64;
+
+pub const NUM_TRIGGERS: usize = 4;
+
+pub const TDATA1_TYPE_SHIFT: u32 = 60;
+// Only debug mode can write the triggers with dmode set
+pub const TDATA1_DMODE: AddressType = 1 << 59;
+const TYPE_ICOUNT: AddressType = 3;
+pub const TYPE_MCONTROL6: AddressType = 6;
+pub const TYPE_DISABLED: AddressType = 15;
+// Sdtrig 1.0
+const TINFO_VERSION: AddressType = 1 << 24;
+pub const ACTION_BREAKPOINT: AddressType = 0;
+pub const ACTION_DEBUG_MODE: AddressType = 1;
+
+pub const MCONTROL6_VS: AddressType = 1 << 24;
+pub const MCONTROL6_VU: AddressType = 1 << 23;
+pub const MCONTROL6_HIT0: AddressType = 1 << 22;
+const MCONTROL6_SELECT: AddressType = 1 << 21;
+pub const MCONTROL6_ACTION_SHIFT: u32 = 12;
+// The trigger only matches together with the next one, e.g. a ge/lt address range
+pub const MCONTROL6_CHAIN: AddressType = 1 << 11;
+const MCONTROL6_ACTION: AddressType = 0xf << MCONTROL6_ACTION_SHIFT;
+pub const MCONTROL6_MATCH_SHIFT: u32 = 7;
+pub const MCONTROL6_MATCH: AddressType = 0xf << MCONTROL6_MATCH_SHIFT;
+pub const MCONTROL6_M: AddressType = 1 << 6;
+pub const MCONTROL6_S: AddressType = 1 << 4;
+pub const MCONTROL6_U: AddressType = 1 << 3;
+pub const MCONTROL6_EXECUTE: AddressType = 1 << 2;
+pub const MCONTROL6_STORE: AddressType = 1 << 1;
+pub const MCONTROL6_LOAD: AddressType = 1 << 0;
+const MATCH_EQUAL: AddressType = 0;
+pub const MATCH_NAPOT: AddressType = 1;
+pub const MATCH_GE: AddressType = 2;
+pub const MATCH_LT: AddressType = 3;
+const MATCH_NOT_EQUAL: AddressType = 8;
+const MATCH_NOT_NAPOT: AddressType = 9;
+
+const ICOUNT_VS: AddressType = 1 << 26;
+const ICOUNT_VU: AddressType = 1 << 25;
+const ICOUNT_HIT: AddressType = 1 << 24;
+const ICOUNT_COUNT_SHIFT: u32 = 10;
+const ICOUNT_COUNT: AddressType = 0x3fff << ICOUNT_COUNT_SHIFT;
+const ICOUNT_M: AddressType = 1 << 9;
+const ICOUNT_PENDING: AddressType = 1 << 8;
+const ICOUNT_S: AddressType = 1 << 7;
+const ICOUNT_U: AddressType = 1 << 6;
+const ICOUNT_ACTION: AddressType = 0x3f;
+
+// mcontrol6 and icount triggers, selected by tselect. They raise a breakpoint exception
+// or enter debug mode, size, tdata3 and the context registers are not implemented.
+pub struct Triggers {
+    tselect: usize,
+    tdata1: [AddressType; NUM_TRIGGERS],
+    tdata2: [AddressType; NUM_TRIGGERS],
+    has_h: bool,
+    debug_mode: bool,
+}
+
+impl Triggers {
+    pub fn new() -> Triggers {
+        Triggers {
+            tselect: 0,
+            tdata1: [TYPE_DISABLED << TDATA1_TYPE_SHIFT; NUM_TRIGGERS],
+            tdata2: [0; NUM_TRIGGERS],
+            has_h: false,
+            debug_mode: false,
+        }
+    }
+
+    // The vs/vu mode bits are read-only zero without the hypervisor extension
+    pub fn set_has_h(&mut self, has_h: bool) {
+        self.has_h = has_h;
+    }
+
+    pub fn set_debug_mode(&mut self, debug_mode: bool) {
+        self.debug_mode = debug_mode;
+    }
+
+    // Triggers with dmode set are reserved to the debugger
+    fn writable(&self) -> bool {
+        self.debug_mode || self.tdata1[self.tselect] & TDATA1_DMODE == 0
+    }
+
+    pub fn tselect(&self) -> AddressType {
+        self.tselect as AddressType
+    }
+
+    pub fn set_tselect(&mut self, val: AddressType) {
+        if val < NUM_TRIGGERS as AddressType {
+            self.tselect = val as usize;
+        }
+    }
+
+    pub fn tdata1(&self) -> AddressType {
+        self.tdata1[self.tselect]
+    }
+
+    // Unsupported types disable the trigger, unsupported match values fall back to equal.
+    // Entering debug mode is only possible with dmode set.
+    pub fn set_tdata1(&mut self, val: AddressType) {
+        if !self.writable() {
+            return;
+        }
+        let vmodes = if self.has_h { AddressType::MAX } else { 0 };
+        let dmode = if self.debug_mode {
+            val & TDATA1_DMODE
+        } else {
+            0
+        };
+        let action = |action| {
+            if dmode != 0 && action == ACTION_DEBUG_MODE {
+                ACTION_DEBUG_MODE
+            } else {
+                ACTION_BREAKPOINT
+            }
+        };
+        let new_val = match val >> TDATA1_TYPE_SHIFT {
+            TYPE_MCONTROL6 => {
+                let wmask = MCONTROL6_HIT0
+                    | MCONTROL6_SELECT
+                    | MCONTROL6_M
+                    | MCONTROL6_S
+                    | MCONTROL6_U
+                    | MCONTROL6_EXECUTE
+                    | MCONTROL6_STORE
+                    | MCONTROL6_LOAD
+                    | (vmodes & (MCONTROL6_VS | MCONTROL6_VU));
+                // The last trigger has no next one to chain with
+                let wmask = if self.tselect + 1 < NUM_TRIGGERS {
+                    wmask | MCONTROL6_CHAIN
+                } else {
+                    wmask
+                };
+                let match_type = match (val & MCONTROL6_MATCH) >> MCONTROL6_MATCH_SHIFT {
+                    m @ (MATCH_NAPOT | MATCH_GE | MATCH_LT | MATCH_NOT_EQUAL | MATCH_NOT_NAPOT) => {
+                        m
+                    }
+                    _ => MATCH_EQUAL,
+                };
+                let action = action((val & MCONTROL6_ACTION) >> MCONTROL6_ACTION_SHIFT);
+                (TYPE_MCONTROL6 << TDATA1_TYPE_SHIFT)
+                    | dmode
+                    | (val & wmask)
+                    | (action << MCONTROL6_ACTION_SHIFT)
+                    | (match_type << MCONTROL6_MATCH_SHIFT)
+            }
+            TYPE_ICOUNT => {
+                let wmask = ICOUNT_HIT
+                    | ICOUNT_COUNT
+                    | ICOUNT_M
+                    | ICOUNT_PENDING
+                    | ICOUNT_S
+                    | ICOUNT_U
+                    | (vmodes & (ICOUNT_VS | ICOUNT_VU));
+                let action = action(val & ICOUNT_ACTION);
+                (TYPE_ICOUNT << TDATA1_TYPE_SHIFT) | dmode | (val & wmask) | action
+            }
+            _ => TYPE_DISABLED << TDATA1_TYPE_SHIFT,
+        };
+        self.tdata1[self.tselect] = new_val;
+    }
+
+    pub fn tdata2(&self) -> AddressType {
+        self.tdata2[self.tselect]
+    }
+
+    pub fn set_tdata2(&mut self, val: AddressType) {
+        if self.writable() {
+            self.tdata2[self.tselect] = val;
+        }
+    }
+
+    // Bit N of info tells type N is supported
+    pub fn tinfo(&self) -> AddressType {
+        TINFO_VERSION | (1 << TYPE_ICOUNT) | (1 << TYPE_MCONTROL6) | (1 << TYPE_DISABLED)
+    }
+
+    // mcontrol6 triggers matching an access of the given kind (execute, load or store),
+    // on its address or, with select, on its data. A chain fires when all its triggers
+    // match, with the action of its last one. The hit bit of those firing is set and
+    // the action is returned, entering debug mode wins over a breakpoint exception.
+    // Without breakpoints only the triggers entering debug mode can fire.
+    pub(super) fn match_access(
+        &mut self,
+        kind: AddressType,
+        mode: PrivilegeMode,
+        virt: bool,
+        addr: AddressType,
+        data: Option<AddressType>,
+        breakpoints: bool,
+    ) -> Option<AddressType> {
+        let mut fired = None;
+        let mut chain_start = 0;
+        let mut chain_matches = true;
+        for i in 0..NUM_TRIGGERS {
+            let tdata1 = self.tdata1[i];
+            let matches = self.access_matches(i, kind, mode, virt, addr, data);
+            let is_mcontrol6 = tdata1 >> TDATA1_TYPE_SHIFT == TYPE_MCONTROL6;
+            if is_mcontrol6 && tdata1 & MCONTROL6_CHAIN != 0 {
+                chain_matches &= matches;
+                continue;
+            }
+            let action = (tdata1 & MCONTROL6_ACTION) >> MCONTROL6_ACTION_SHIFT;
+            if matches && chain_matches && (action != ACTION_BREAKPOINT || breakpoints) {
+                for tdata1 in self.tdata1[chain_start..=i].iter_mut() {
+                    *tdata1 |= MCONTROL6_HIT0;
+                }
+                fired = cmp::max(fired, Some(action));
+            }
+            chain_start = i + 1;
+            chain_matches = true;
+        }
+        fired
+    }
+
+    fn access_matches(
+        &self,
+        i: usize,
+        kind: AddressType,
+        mode: PrivilegeMode,
+        virt: bool,
+        addr: AddressType,
+        data: Option<AddressType>,
+    ) -> bool {
+        let mode_bits = [
+            MCONTROL6_M,
+            MCONTROL6_S,
+            MCONTROL6_U,
+            MCONTROL6_VS,
+            MCONTROL6_VU,
+        ];
+        let tdata1 = self.tdata1[i];
+        if tdata1 >> TDATA1_TYPE_SHIFT != TYPE_MCONTROL6 || tdata1 & kind == 0 {
+            return false;
+        }
+        if !Triggers::mode_enabled(tdata1, mode_bits, mode, virt) {
+            return false;
+        }
+        let val = if tdata1 & MCONTROL6_SELECT != 0 {
+            match data {
+                Some(data) => data,
+                None => return false,
+            }
+        } else {
+            addr
+        };
+        let match_type = (tdata1 & MCONTROL6_MATCH) >> MCONTROL6_MATCH_SHIFT;
+        Triggers::value_matches(match_type, val, self.tdata2[i])
+    }
+
+    // icount triggers count the instructions retired in their modes, reaching zero
+    // makes them pending until they fire before the next instruction
+    pub(super) fn retire_instruction(&mut self, mode: PrivilegeMode, virt: bool) {
+        let mode_bits = [ICOUNT_M, ICOUNT_S, ICOUNT_U, ICOUNT_VS, ICOUNT_VU];
+        for tdata1 in self.tdata1.iter_mut() {
+            if *tdata1 >> TDATA1_TYPE_SHIFT != TYPE_ICOUNT {
+                continue;
+            }
+            let count = (*tdata1 & ICOUNT_COUNT) >> ICOUNT_COUNT_SHIFT;
+            if count == 0 || !Triggers::mode_enabled(*tdata1, mode_bits, mode, virt) {
+                continue;
+            }
+            *tdata1 = (*tdata1 & !ICOUNT_COUNT) | ((count - 1) << ICOUNT_COUNT_SHIFT);
+            if count == 1 {
+                *tdata1 |= ICOUNT_PENDING;
+            }
+        }
+    }
+
+    // Pending icount triggers fire, their hit bit is set and the action is returned
+    // like for match_access
+    pub fn fire_icount(&mut self, breakpoints: bool) -> Option<AddressType> {
+        let mut fired = None;
+        for tdata1 in self.tdata1.iter_mut() {
+            if *tdata1 >> TDATA1_TYPE_SHIFT != TYPE_ICOUNT || *tdata1 & ICOUNT_PENDING == 0 {
+                continue;
+            }
+            let action = *tdata1 & ICOUNT_ACTION;
+            if action == ACTION_BREAKPOINT && !breakpoints {
+                continue;
+            }
+            *tdata1 = (*tdata1 & !ICOUNT_PENDING) | ICOUNT_HIT;
+            fired = cmp::max(fired, Some(action));
+        }
+        fired
+    }
+
+    // mode_bits are the M, S, U, VS and VU enables of the trigger type
+    fn mode_enabled(
+        tdata1: AddressType,
+        mode_bits: [AddressType; 5],
+        mode: PrivilegeMode,
+        virt: bool,
+    ) -> bool {
+        let bit = match (mode, virt) {
+            (PrivilegeMode::M, _) => mode_bits[0],
+            (PrivilegeMode::S, false) => mode_bits[1],
+            (PrivilegeMode::U, false) => mode_bits[2],
+            (PrivilegeMode::S, true) => mode_bits[3],
+            (PrivilegeMode::U, true) => mode_bits[4],
+        };
+        tdata1 & bit != 0
+    }
+
+    // NAPOT ignores the bits up to and including the lowest zero bit of tdata2
+    fn value_matches(match_type: AddressType, val: AddressType, tdata2: AddressType) -> bool {
+        let napot = || {
+            let bits = tdata2.trailing_ones() + 1;
+            bits >= 64 || val >> bits == tdata2 >> bits
+        };
+        match match_type {
+            MATCH_NAPOT => napot(),
+            MATCH_GE => val >= tdata2,
+            MATCH_LT => val < tdata2,
+            MATCH_NOT_EQUAL => val != tdata2,
+            MATCH_NOT_NAPOT => !napot(),
+            _ => val == tdata2,
+        }
+    }
+}
@@ -0,0 +1,2607 @@
+use crate::rv_core::inst_type::InstType;
+use crate::rv_core::mmu::AccessType;
+use crate::rv_core::{csregs, RVCore};
+use softfloat_wrapper::{ExceptionFlags, Float, RoundingMode, F32, F64};
+use std::cmp;
+
+type AddressType = u64;
+
+// Element widths of (vd, vs2, vs1/rs1) as multiples of SEW
+type Widths = (usize, usize, usize);
+const SINGLE: Widths = (1, 1, 1);
+const WIDEN: Widths = (2, 1, 1);
+const WIDEN_W: Widths = (2, 2, 1);
+const NARROW: Widths = (1, 2, 1);
+
+// 7-bit estimate tables from the vfrec7.v/vfrsqrt7.v definitions in the V spec
+const REC7_TABLE: [AddressType; 128] = [
+    127, 125, 123, 121, 119, 117, 116, 114, 112, 110, 109, 107, 105, 104, 102, 100, 99, 97, 96, 94,
+    93, 91, 90, 88, 87, 85, 84, 83, 81, 80, 79, 77, 76, 75, 74, 72, 71, 70, 69, 68, 66, 65, 64, 63,
+    62, 61, 60, 59, 58, 57, 56, 55, 54, 53, 52, 51, 50, 49, 48, 47, 46, 45, 44, 43, 42, 41, 40, 40,
+    39, 38, 37, 36, 35, 35, 34, 33, 32, 31, 31, 30, 29, 28, 28, 27, 26, 25, 25, 24, 23, 23, 22, 21,
+    21, 20, 19, 19, 18, 17, 17, 16, 15, 15, 14, 14, 13, 12, 12, 11, 11, 10, 9, 9, 8, 8, 7, 7, 6, 5,
+    5, 4, 4, 3, 3, 2, 2, 1, 1, 0,
+];
+const RSQRT7_TABLE: [AddressType; 128] = [
+    52, 51, 50, 48, 47, 46, 44, 43, 42, 41, 40, 39, 38, 36, 35, 34, 33, 32, 31, 30, 30, 29, 28, 27,
+    26, 25, 24, 23, 23, 22, 21, 20, 19, 19, 18, 17, 16, 16, 15, 14, 14, 13, 12, 12, 11, 10, 10, 9,
+    9, 8, 7, 7, 6, 6, 5, 4, 4, 3, 3, 2, 2, 1, 1, 0, 127, 125, 123, 121, 119, 118, 116, 114, 113,
+    111, 109, 108, 106, 105, 103, 102, 100, 99, 97, 96, 95, 93, 92, 91, 90, 88, 87, 86, 85, 84, 83,
+    82, 80, 79, 78, 77, 76, 75, 74, 73, 72, 71, 70, 70, 69, 68, 67, 66, 65, 64, 63, 63, 62, 61, 60,
+    59, 59, 58, 57, 56, 56, 55, 54, 53,
+];
+
+const FFLAGS_NX: AddressType = 0x1;
+const FFLAGS_OF: AddressType = 0x4;
+const FFLAGS_DZ: AddressType = 0x8;
+const FFLAGS_NV: AddressType = 0x10;
+
+#[derive(Copy, Clone)]
+struct VState {
+    sew: usize,
+    lmul_x8: usize,
+    vl: usize,
+    vstart: usize,
+    vlmax: usize,
+}
+
+impl VState {
+    // Registers taken by a group of eew-bit elements, None when EMUL is outside [1/8, 8]
+    fn group_regs(&self, eew: usize) -> Option<usize> {
+        let emul_x8 = self.lmul_x8 * eew / self.sew;
+        if self.lmul_x8 * eew < self.sew || emul_x8 > 64 {
+            None
+        } else {
+            Some(cmp::max(1, emul_x8 / 8))
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum VMemMode {
+    UnitStride,
+    // Unit-stride loads trimming vl at a faulting element other than the first
+    FaultOnlyFirst,
+    Strided,
+    Indexed,
+}
+
+#[derive(Copy, Clone)]
+enum FpOp {
+    Add,
+    Sub,
+    RSub,
+    Mul,
+    Div,
+    RDiv,
+    Min,
+    Max,
+    Sgnj,
+    Sgnjn,
+    Sgnjx,
+    Macc,
+    Nmacc,
+    Msac,
+    Nmsac,
+    Madd,
+    Nmadd,
+    Msub,
+    Nmsub,
+}
+
+#[derive(Copy, Clone)]
+enum FpCmp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum FpCvt {
+    FloatToUint,
+    FloatToInt,
+    UintToFloat,
+    IntToFloat,
+    FloatToFloat,
+    FloatToFloatRod,
+}
+
+trait VectorFloat: Float + Copy {
+    fn from_elem(val: AddressType) -> Self;
+    fn to_elem(&self) -> AddressType;
+}
+
+impl VectorFloat for F32 {
+    fn from_elem(val: AddressType) -> F32 {
+        F32::from_bits(val as u32)
+    }
+
+    fn to_elem(&self) -> AddressType {
+        self.to_bits() as AddressType
+    }
+}
+
+impl VectorFloat for F64 {
+    fn from_elem(val: AddressType) -> F64 {
+        F64::from_bits(val)
+    }
+
+    fn to_elem(&self) -> AddressType {
+        self.to_bits()
+    }
+}
+
+fn vsext(val: AddressType, eew: usize) -> AddressType {
+    if eew >= 64 {
+        val
+    } else {
+        RVCore::sign_extend(val, eew)
+    }
+}
+
+fn vtrunc(val: AddressType, eew: usize) -> AddressType {
+    if eew >= 64 {
+        val
+    } else {
+        val & ((1 << eew) - 1)
+    }
+}
+
+fn signed_range(eew: usize) -> (i128, i128) {
+    (-(1i128 << (eew - 1)), (1i128 << (eew - 1)) - 1)
+}
+
+// Rounding increment for shifting v right by d bits under vxrm
+fn rounding_increment(v: u128, d: usize, vxrm: AddressType) -> u128 {
+    if d == 0 {
+        return 0;
+    }
+    let bit = |n: usize| (v >> n) & 1;
+    let low_bits_set = |n: usize| n > 0 && (v & ((1u128 << n) - 1)) != 0;
+    match vxrm {
+        0 => bit(d - 1),
+        1 => bit(d - 1) & ((low_bits_set(d - 1) as u128) | bit(d)),
+        2 => 0,
+        _ => (1 - bit(d)) & (low_bits_set(d) as u128),
+    }
+}
+
+fn roundoff_unsigned(v: u128, d: usize, vxrm: AddressType) -> u128 {
+    (v >> d) + rounding_increment(v, d, vxrm)
+}
+
+fn roundoff_signed(v: i128, d: usize, vxrm: AddressType) -> i128 {
+    (v >> d) + rounding_increment(v as u128, d, vxrm) as i128
+}
+
+// Runs a softfloat operation and returns its result with the raised fflags
+fn softfloat_with_flags<T, F: FnOnce() -> T>(op: F) -> (T, AddressType) {
+    let mut flags = ExceptionFlags::default();
+    flags.set();
+    let result = op();
+    flags.get();
+    (result, flags.to_bits() as AddressType)
+}
+
+fn fp_canonical<T: VectorFloat>(val: T) -> T {
+    if val.is_nan() {
+        T::quiet_nan()
+    } else {
+        val
+    }
+}
+
+fn fp_min_max<T: VectorFloat>(a: T, b: T, is_max: bool) -> T {
+    // lt_quiet only signals on sNaN, which is what vfmin/vfmax require
+    let less = a.lt_quiet(b);
+    if a.is_nan() && b.is_nan() {
+        T::quiet_nan()
+    } else if a.is_nan() {
+        b
+    } else if b.is_nan() {
+        a
+    } else if a.is_zero() && b.is_zero() {
+        if a.is_negative() != is_max {
+            a
+        } else {
+            b
+        }
+    } else if less != is_max {
+        a
+    } else {
+        b
+    }
+}
+
+// a is the vs2 element, b the vs1 element or scalar, d the old vd element
+fn fp_compute<T: VectorFloat>(op: FpOp, a: T, b: T, d: T, rm: RoundingMode) -> T {
+    let result = match op {
+        FpOp::Add => a.add(b, rm),
+        FpOp::Sub => a.sub(b, rm),
+        FpOp::RSub => b.sub(a, rm),
+        FpOp::Mul => a.mul(b, rm),
+        FpOp::Div => a.div(b, rm),
+        FpOp::RDiv => b.div(a, rm),
+        FpOp::Min => return fp_min_max(a, b, false),
+        FpOp::Max => return fp_min_max(a, b, true),
+        FpOp::Sgnj | FpOp::Sgnjn | FpOp::Sgnjx => {
+            let sign_bit = T::from_elem(0).neg().to_elem();
+            let (a_bits, b_bits) = (a.to_elem(), b.to_elem());
+            let sign = match op {
+                FpOp::Sgnj => b_bits & sign_bit,
+                FpOp::Sgnjn => !b_bits & sign_bit,
+                _ => (a_bits ^ b_bits) & sign_bit,
+            };
+            return T::from_elem((a_bits & !sign_bit) | sign);
+        }
+        FpOp::Macc => b.fused_mul_add(a, d, rm),
+        FpOp::Nmacc => b.neg().fused_mul_add(a, d.neg(), rm),
+        FpOp::Msac => b.fused_mul_add(a, d.neg(), rm),
+        FpOp::Nmsac => b.neg().fused_mul_add(a, d, rm),
+        FpOp::Madd => b.fused_mul_add(d, a, rm),
+        FpOp::Nmadd => b.neg().fused_mul_add(d, a.neg(), rm),
+        FpOp::Msub => b.fused_mul_add(d, a.neg(), rm),
+        FpOp::Nmsub => b.neg().fused_mul_add(d, a, rm),
+    };
+    fp_canonical(result)
+}
+
+fn fp_compare<T: VectorFloat>(cmp: FpCmp, a: T, b: T) -> bool {
+    match cmp {
+        FpCmp::Eq => a.eq(b),
+        FpCmp::Ne => !a.eq(b),
+        FpCmp::Lt => a.lt(b),
+        FpCmp::Le => a.le(b),
+        FpCmp::Gt => b.lt(a),
+        FpCmp::Ge => b.le(a),
+    }
+}
+
+fn fp_classify<T: VectorFloat>(val: T) -> AddressType {
+    if val.is_negative_infinity() {
+        1 << 0
+    } else if val.is_negative_normal() {
+        1 << 1
+    } else if val.is_negative_subnormal() {
+        1 << 2
+    } else if val.is_negative_zero() {
+        1 << 3
+    } else if val.is_positive_zero() {
+        1 << 4
+    } else if val.is_positive_subnormal() {
+        1 << 5
+    } else if val.is_positive_normal() {
+        1 << 6
+    } else if val.is_positive_infinity() {
+        1 << 7
+    } else if val.is_signaling_nan() {
+        1 << 8
+    } else {
+        1 << 9
+    }
+}
+
+// Converts to a width-bit integer, saturating like the scalar fcvt instructions
+fn fp_to_int<T: VectorFloat>(
+    val: T,
+    signed: bool,
+    width: usize,
+    rm: RoundingMode,
+) -> (AddressType, AddressType) {
+    let (min, max) = if signed {
+        signed_range(width)
+    } else {
+        (0, (1i128 << width) - 1)
+    };
+    let (result, flags) = softfloat_with_flags(|| {
+        if signed {
+            val.to_i64(rm, true) as i128
+        } else {
+            val.to_u64(rm, true) as i128
+        }
+    });
+    if val.is_nan() {
+        (vtrunc(max as AddressType, width), FFLAGS_NV)
+    } else if (flags & FFLAGS_NV) != 0 {
+        let saturated = if val.is_negative() { min } else { max };
+        (vtrunc(saturated as AddressType, width), FFLAGS_NV)
+    } else if result > max {
+        (vtrunc(max as AddressType, width), FFLAGS_NV)
+    } else if result < min {
+        (vtrunc(min as AddressType, width), FFLAGS_NV)
+    } else {
+        (vtrunc(result as AddressType, width), flags)
+    }
+}
+
+// Splits a float into sign, exponent and significand, normalizing subnormals
+fn fp_unpack(
+    bits: AddressType,
+    exp_bits: usize,
+    sig_bits: usize,
+) -> (AddressType, i64, AddressType) {
+    let sig_mask = (1 << sig_bits) - 1;
+    let sign = (bits >> (exp_bits + sig_bits)) & 1;
+    let mut exp = ((bits >> sig_bits) & ((1 << exp_bits) - 1)) as i64;
+    let mut sig = bits & sig_mask;
+    if exp == 0 {
+        while (sig & (1 << (sig_bits - 1))) == 0 {
+            sig <<= 1;
+            exp -= 1;
+        }
+        sig = (sig << 1) & sig_mask;
+    }
+    (sign, exp, sig)
+}
+
+fn fp_rec7(
+    bits: AddressType,
+    exp_bits: usize,
+    sig_bits: usize,
+    rm: RoundingMode,
+) -> (AddressType, AddressType) {
+    let bias = (1i64 << (exp_bits - 1)) - 1;
+    let max_exp = (1 << exp_bits) - 1;
+    let sign_bit = 1 << (exp_bits + sig_bits);
+    let sign = bits & sign_bit;
+    let exp = (bits >> sig_bits) & max_exp;
+    let sig = bits & ((1 << sig_bits) - 1);
+    let inf = sign | (max_exp << sig_bits);
+    let canonical_nan = (max_exp << sig_bits) | (1 << (sig_bits - 1));
+
+    if exp == max_exp {
+        if sig == 0 {
+            return (sign, 0);
+        }
+        let is_snan = (sig & (1 << (sig_bits - 1))) == 0;
+        return (canonical_nan, if is_snan { FFLAGS_NV } else { 0 });
+    }
+    if exp == 0 && sig == 0 {
+        return (inf, FFLAGS_DZ);
+    }
+
+    let (_, norm_exp, norm_sig) = fp_unpack(bits, exp_bits, sig_bits);
+    if norm_exp < -1 {
+        // 1/x overflows, the result depends on the rounding direction
+        let max_finite = sign | ((max_exp - 1) << sig_bits) | ((1 << sig_bits) - 1);
+        let to_inf = match rm {
+            RoundingMode::TowardZero => false,
+            RoundingMode::TowardNegative => sign != 0,
+            RoundingMode::TowardPositive => sign == 0,
+            _ => true,
+        };
+        return (if to_inf { inf } else { max_finite }, FFLAGS_OF | FFLAGS_NX);
+    }
+
+    let mut out_exp = 2 * bias - 1 - norm_exp;
+    let mut out_sig = REC7_TABLE[(norm_sig >> (sig_bits - 7)) as usize] << (sig_bits - 7);
+    if out_exp <= 0 {
+        out_sig = ((1 << sig_bits) | out_sig) >> (1 - out_exp);
+        out_exp = 0;
+    }
+    (sign | ((out_exp as AddressType) << sig_bits) | out_sig, 0)
+}
+
+fn fp_rsqrt7(bits: AddressType, exp_bits: usize, sig_bits: usize) -> (AddressType, AddressType) {
+    let bias = (1i64 << (exp_bits - 1)) - 1;
+    let max_exp = (1 << exp_bits) - 1;
+    let sign_bit = 1 << (exp_bits + sig_bits);
+    let sign = bits & sign_bit;
+    let exp = (bits >> sig_bits) & max_exp;
+    let sig = bits & ((1 << sig_bits) - 1);
+    let canonical_nan = (max_exp << sig_bits) | (1 << (sig_bits - 1));
+
+    if exp == max_exp && sig != 0 {
+        let is_snan = (sig & (1 << (sig_bits - 1))) == 0;
+        return (canonical_nan, if is_snan { FFLAGS_NV } else { 0 });
+    }
+    if exp == 0 && sig == 0 {
+        return (sign | (max_exp << sig_bits), FFLAGS_DZ);
+    }
+    if sign != 0 {
+        return (canonical_nan, FFLAGS_NV);
+    }
+    if exp == max_exp {
+        return (0, 0);
+    }
+
+    let (_, norm_exp, norm_sig) = fp_unpack(bits, exp_bits, sig_bits);
+    let idx = (((norm_exp & 1) as AddressType) << 6) | (norm_sig >> (sig_bits - 6));
+    let out_exp = (3 * bias - 1 - norm_exp) / 2;
+    let out_sig = RSQRT7_TABLE[idx as usize] << (sig_bits - 7);
+    (((out_exp as AddressType) << sig_bits) | out_sig, 0)
+}
+
+impl RVCore {
+    // (SEW, LMUL * 8) of a vtype value, None if this configuration is unsupported
+    fn vector_parse_vtype(&self, vtype: AddressType) -> Option<(usize, usize)> {
+        let vsew = (vtype >> 3) & 0x7;
+        let vlmul = vtype & 0x7;
+        if (vtype >> 8) != 0 || vsew > 3 || vlmul == 4 {
+            return None;
+        }
+        let sew = 8 << vsew;
+        let lmul_x8 = if vlmul < 4 {
+            8 << vlmul
+        } else {
+            8 >> (8 - vlmul)
+        };
+        let elen = self.vregs.elen();
+        if sew > elen || sew * 8 > lmul_x8 * elen {
+            return None;
+        }
+        Some((sew, lmul_x8))
+    }
+
+    // Current vector configuration, raises illegal instruction when vill is set
+    fn vector_state(&mut self, inst: &InstType) -> Option<VState> {
+        match self.vector_parse_vtype(self.csregs.read(csregs::VTYPE)) {
+            Some((sew, lmul_x8)) => Some(VState {
+                sew,
+                lmul_x8,
+                vl: self.csregs.read(csregs::VL) as usize,
+                vstart: self.csregs.read(csregs::VSTART) as usize,
+                vlmax: lmul_x8 * self.vregs.vlen() / 8 / sew,
+            }),
+            None => {
+                self.raise_illegal_inst(inst);
+                None
+            }
+        }
+    }
+
+    // A group of eew-bit elements starting at reg must fit in EMUL and be aligned to it
+    fn vector_group_ok(&self, st: &VState, reg: usize, eew: usize) -> bool {
+        if eew > self.vregs.elen() {
+            return false;
+        }
+        match st.group_regs(eew) {
+            Some(regs) => reg.is_multiple_of(regs),
+            None => false,
+        }
+    }
+
+    fn vector_groups_overlap(
+        st: &VState,
+        reg_a: usize,
+        eew_a: usize,
+        reg_b: usize,
+        eew_b: usize,
+    ) -> bool {
+        let regs_a = st.group_regs(eew_a).unwrap_or(1);
+        let regs_b = st.group_regs(eew_b).unwrap_or(1);
+        reg_a < reg_b + regs_b && reg_b < reg_a + regs_a
+    }
+
+    fn vector_unmasked(inst: &InstType) -> bool {
+        ((inst.data >> 25) & 1) == 1
+    }
+
+    fn vector_is_vv(inst: &InstType) -> bool {
+        ((inst.data >> 12) & 0x7) <= 2
+    }
+
+    fn vector_masked_off(&self, inst: &InstType, i: usize) -> bool {
+        !RVCore::vector_unmasked(inst) && !self.vregs.read_mask(0, i)
+    }
+
+    fn vector_read_fp_scalar(&self, reg: usize, eew: usize) -> AddressType {
+        if eew == 32 {
+            self.fregs.read_s(reg).to_elem()
+        } else {
+            self.fregs.read_bits(reg)
+        }
+    }
+
+    fn vector_write_fp_scalar(&mut self, reg: usize, eew: usize, val: AddressType) {
+        if eew == 32 {
+            self.fregs.write_s(reg, F32::from_elem(val));
+        } else {
+            self.fregs.write_bits(reg, val);
+        }
+    }
+
+    // Scalar operand of .vx/.vi/.vf forms, truncated to eew bits
+    fn vector_scalar(&self, inst: &InstType, eew: usize) -> AddressType {
+        let val = match (inst.data >> 12) & 0x7 {
+            3 => RVCore::sign_extend(inst.get_rs1() as AddressType, 5),
+            5 => self.vector_read_fp_scalar(inst.get_rs1(), eew),
+            _ => self.regs.read(inst.get_rs1()),
+        };
+        vtrunc(val, eew)
+    }
+
+    fn vector_op1(&self, inst: &InstType, i: usize, eew: usize) -> AddressType {
+        if RVCore::vector_is_vv(inst) {
+            self.vregs.read(inst.get_rs1(), i, eew)
+        } else {
+            self.vector_scalar(inst, eew)
+        }
+    }
+
+    // Element-wise vd[i] = f(vs2[i], vs1[i]/scalar, vd[i], SEW) over the active body elements
+    fn vector_arith<F>(&mut self, inst: &InstType, widths: Widths, mut f: F)
+    where
+        F: FnMut(AddressType, AddressType, AddressType, usize) -> AddressType,
+    {
+        let st = match self.vector_state(inst) {
+            Some(st) => st,
+            None => return,
+        };
+        let (vd, vs2, vs1) = (inst.get_rd(), inst.get_rs2_rtype(), inst.get_rs1());
+        let (eew_d, eew_2, eew_1) = (st.sew * widths.0, st.sew * widths.1, st.sew * widths.2);
+        let legal = self.vector_group_ok(&st, vd, eew_d)
+            && self.vector_group_ok(&st, vs2, eew_2)
+            && (!RVCore::vector_is_vv(inst) || self.vector_group_ok(&st, vs1, eew_1))
+            && (RVCore::vector_unmasked(inst) || vd != 0);
+        if !legal {
+            self.raise_illegal_inst(inst);
+            return;
+        }
+
+        for i in st.vstart..st.vl {
+            if self.vector_masked_off(inst, i) {
+                continue;
+            }
+            let a = self.vregs.read(vs2, i, eew_2);
+            let b = self.vector_op1(inst, i, eew_1);
+            let d = self.vregs.read(vd, i, eew_d);
+            let result = f(a, b, d, st.sew);
+            self.vregs.write(vd, i, eew_d, result);
+        }
+        self.csregs.write(csregs::VSTART, 0);
+    }
+
+    // Element-wise compare writing one mask bit per element
+    fn vector_compare<F>(&mut self, inst: &InstType, mut f: F)
+    where
+        F: FnMut(AddressType, AddressType, usize) -> bool,
+    {
+        let st = match self.vector_state(inst) {
+            Some(st) => st,
+            None => return,
+        };
+        let (vd, vs2, vs1) = (inst.get_rd(), inst.get_rs2_rtype(), inst.get_rs1());
+        let legal = self.vector_group_ok(&st, vs2, st.sew)
+            && (!RVCore::vector_is_vv(inst) || self.vector_group_ok(&st, vs1, st.sew));
+        if !legal {
+            self.raise_illegal_inst(inst);
+            return;
+        }
+
+        let mut results = Vec::new();
+        for i in st.vstart..st.vl {
+            if self.vector_masked_off(inst, i) {
+                continue;
+            }
+            let a = self.vregs.read(vs2, i, st.sew);
+            let b = self.vector_op1(inst, i, st.sew);
+            results.push((i, f(a, b, st.sew)));
+        }
+        for (i, bit) in results {
+            self.vregs.write_mask(vd, i, bit);
+        }
+        self.csregs.write(csregs::VSTART, 0);
+    }
+
+    // vd[0] = fold of f over vs1[0] and the active elements of vs2
+    fn vector_reduce<F>(&mut self, inst: &InstType, widen: bool, mut f: F)
+    where
+        F: FnMut(AddressType, AddressType, usize) -> AddressType,
+    {
+        let st = match self.vector_state(inst) {
+            Some(st) => st,
+            None => return,
+        };
+        let (vd, vs2, vs1) = (inst.get_rd(), inst.get_rs2_rtype(), inst.get_rs1());
+        let acc_eew = if widen { st.sew * 2 } else { st.sew };
+        if st.vstart != 0 || acc_eew > self.vregs.elen() || !self.vector_group_ok(&st, vs2, st.sew)
+        {
+            self.raise_illegal_inst(inst);
+            return;
+        }
+        if st.vl == 0 {
+            return;
+        }
+
+        let mut acc = self.vregs.read(vs1, 0, acc_eew);
+        for i in 0..st.vl {
+            if self.vector_masked_off(inst, i) {
+                continue;
+            }
+            acc = f(acc, self.vregs.read(vs2, i, st.sew), st.sew);
+        }
+        self.vregs.write(vd, 0, acc_eew, acc);
+    }
+
+    // Mask-register logical operation, vd.mask[i] = f(vs2.mask[i], vs1.mask[i])
+    fn vector_mask_logical<F>(&mut self, inst: &InstType, f: F)
+    where
+        F: Fn(bool, bool) -> bool,
+    {
+        let st = match self.vector_state(inst) {
+            Some(st) => st,
+            None => return,
+        };
+        let (vd, vs2, vs1) = (inst.get_rd(), inst.get_rs2_rtype(), inst.get_rs1());
+        for i in st.vstart..st.vl {
+            let bit = f(self.vregs.read_mask(vs2, i), self.vregs.read_mask(vs1, i));
+            self.vregs.write_mask(vd, i, bit);
+        }
+        self.csregs.write(csregs::VSTART, 0);
+    }
+
+    // vzext.vf*/vsext.vf*, source elements are SEW/factor bits wide
+    fn vector_extend(&mut self, inst: &InstType, factor: usize, signed: bool) {
+        let st = match self.vector_state(inst) {
+            Some(st) => st,
+            None => return,
+        };
+        let (vd, vs2) = (inst.get_rd(), inst.get_rs2_rtype());
+        let src_eew = st.sew / factor;
+        let legal = src_eew >= 8
+            && self.vector_group_ok(&st, vd, st.sew)
+            && self.vector_group_ok(&st, vs2, src_eew)
+            && (RVCore::vector_unmasked(inst) || vd != 0);
+        if !legal {
+            self.raise_illegal_inst(inst);
+            return;
+        }
+
+        for i in st.vstart..st.vl {
+            if self.vector_masked_off(inst, i) {
+                continue;
+            }
+            let val = self.vregs.read(vs2, i, src_eew);
+            let result = if signed { vsext(val, src_eew) } else { val };
+            self.vregs.write(vd, i, st.sew, result);
+        }
+        self.csregs.write(csregs::VSTART, 0);
+    }
+
+    fn vector_merge(&mut self, inst: &InstType) {
+        let st = match self.vector_state(inst) {
+            Some(st) => st,
+            None => return,
+        };
+        let (vd, vs2, vs1) = (inst.get_rd(), inst.get_rs2_rtype(), inst.get_rs1());
+        let masked = !RVCore::vector_unmasked(inst);
+        let legal = self.vector_group_ok(&st, vd, st.sew)
+            && (!masked || (self.vector_group_ok(&st, vs2, st.sew) && vd != 0))
+            && (!RVCore::vector_is_vv(inst) || self.vector_group_ok(&st, vs1, st.sew));
+        if !legal {
+            self.raise_illegal_inst(inst);
+            return;
+        }
+
+        for i in st.vstart..st.vl {
+            let val = if self.vector_masked_off(inst, i) {
+                self.vregs.read(vs2, i, st.sew)
+            } else {
+                self.vector_op1(inst, i, st.sew)
+            };
+            self.vregs.write(vd, i, st.sew, val);
+        }
+        self.csregs.write(csregs::VSTART, 0);
+    }
+
+    // vadc/vsbc take v0 as carry/borrow input, vmadc/vmsbc produce the carry/borrow mask
+    fn vector_carry(&mut self, inst: &InstType, subtract: bool, mask_out: bool) {
+        let st = match self.vector_state(inst) {
+            Some(st) => st,
+            None => return,
+        };
+        let (vd, vs2, vs1) = (inst.get_rd(), inst.get_rs2_rtype(), inst.get_rs1());
+        let use_carry = !RVCore::vector_unmasked(inst);
+        let legal = (mask_out || (self.vector_group_ok(&st, vd, st.sew) && vd != 0))
+            && self.vector_group_ok(&st, vs2, st.sew)
+            && (!RVCore::vector_is_vv(inst) || self.vector_group_ok(&st, vs1, st.sew));
+        if !legal {
+            self.raise_illegal_inst(inst);
+            return;
+        }
+
+        let mut results = Vec::new();
+        for i in st.vstart..st.vl {
+            let a = self.vregs.read(vs2, i, st.sew) as u128;
+            let b = self.vector_op1(inst, i, st.sew) as u128;
+            let c = (use_carry && self.vregs.read_mask(0, i)) as u128;
+            if subtract {
+                let diff = a.wrapping_sub(b).wrapping_sub(c);
+                results.push((i, diff as AddressType, a < b + c));
+            } else {
+                let sum = a + b + c;
+                results.push((i, sum as AddressType, (sum >> st.sew) & 1 == 1));
+            }
+        }
+        for (i, val, carry) in results {
+            if mask_out {
+                self.vregs.write_mask(vd, i, carry);
+            } else {
+                self.vregs.write(vd, i, st.sew, val);
+            }
+        }
+        self.csregs.write(csregs::VSTART, 0);
+    }
+
+    fn vector_fp_sew_ok(&self, widths: Widths) -> bool {
+        let sew = match self.vector_parse_vtype(self.csregs.read(csregs::VTYPE)) {
+            Some((sew, _)) => sew,
+            None => return true,
+        };
+        if widths == SINGLE {
+            sew == 32 || sew == 64
+        } else {
+            sew == 32
+        }
+    }
+
+    fn vector_fp_arith(&mut self, inst: &InstType, widths: Widths, op: FpOp) {
+        let rm = match self.frm_rounding_mode() {
+            Some(rm) if self.vector_fp_sew_ok(widths) => rm,
+            _ => {
+                self.raise_illegal_inst(inst);
+                return;
+            }
+        };
+        let mut flags = 0;
+        self.vector_arith(inst, widths, |a, b, d, sew| {
+            let (result, raised) = softfloat_with_flags(|| {
+                if sew == 64 {
+                    fp_compute(
+                        op,
+                        F64::from_elem(a),
+                        F64::from_elem(b),
+                        F64::from_elem(d),
+                        rm,
+                    )
+                    .to_elem()
+                } else if widths == SINGLE {
+                    fp_compute(
+                        op,
+                        F32::from_elem(a),
+                        F32::from_elem(b),
+                        F32::from_elem(d),
+                        rm,
+                    )
+                    .to_elem()
+                } else {
+                    // Widening ops compute in double precision, narrow operands are converted exactly
+                    let widen = |val, width| {
+                        if width == 1 {
+                            F32::from_elem(val).to_f64(rm)
+                        } else {
+                            F64::from_elem(val)
+                        }
+                    };
+                    fp_compute(
+                        op,
+                        widen(a, widths.1),
+                        widen(b, widths.2),
+                        F64::from_elem(d),
+                        rm,
+                    )
+                    .to_elem()
+                }
+            });
+            flags |= raised;
+            result
+        });
+        self.accrue_fflags(flags);
+    }
+
+    fn vector_fp_compare(&mut self, inst: &InstType, cmp: FpCmp) {
+        if !self.vector_fp_sew_ok(SINGLE) {
+            self.raise_illegal_inst(inst);
+            return;
+        }
+        let mut flags = 0;
+        self.vector_compare(inst, |a, b, sew| {
+            let (result, raised) = softfloat_with_flags(|| {
+                if sew == 64 {
+                    fp_compare(cmp, F64::from_elem(a), F64::from_elem(b))
+                } else {
+                    fp_compare(cmp, F32::from_elem(a), F32::from_elem(b))
+                }
+            });
+            flags |= raised;
+            result
+        });
+        self.accrue_fflags(flags);
+    }
+
+    fn vector_fp_reduce(&mut self, inst: &InstType, widen: bool, op: FpOp) {
+        let widths = if widen { WIDEN } else { SINGLE };
+        let rm = match self.frm_rounding_mode() {
+            Some(rm) if self.vector_fp_sew_ok(widths) => rm,
+            _ => {
+                self.raise_illegal_inst(inst);
+                return;
+            }
+        };
+        let mut flags = 0;
+        self.vector_reduce(inst, widen, |acc, elem, sew| {
+            let (result, raised) = softfloat_with_flags(|| {
+                if sew == 64 {
+                    let zero = F64::from_elem(0);
+                    fp_compute(op, F64::from_elem(acc), F64::from_elem(elem), zero, rm).to_elem()
+                } else if widen {
+                    let elem = F32::from_elem(elem).to_f64(rm);
+                    fp_compute(op, F64::from_elem(acc), elem, F64::from_elem(0), rm).to_elem()
+                } else {
+                    let zero = F32::from_elem(0);
+                    fp_compute(op, F32::from_elem(acc), F32::from_elem(elem), zero, rm).to_elem()
+                }
+            });
+            flags |= raised;
+            result
+        });
+        self.accrue_fflags(flags);
+    }
+
+    fn vector_fp_unary<F>(&mut self, inst: &InstType, mut f: F)
+    where
+        F: FnMut(AddressType, usize) -> (AddressType, AddressType),
+    {
+        if !self.vector_fp_sew_ok(SINGLE) {
+            self.raise_illegal_inst(inst);
+            return;
+        }
+        let mut flags = 0;
+        self.vector_arith(inst, SINGLE, |a, _, _, sew| {
+            let (result, raised) = f(a, sew);
+            flags |= raised;
+            result
+        });
+        self.accrue_fflags(flags);
+    }
+
+    fn vector_fp_convert(&mut self, inst: &InstType, widths: Widths, kind: FpCvt, rtz: bool) {
+        let sew = self
+            .vector_parse_vtype(self.csregs.read(csregs::VTYPE))
+            .map_or(0, |(sew, _)| sew);
+        let int_src = kind == FpCvt::UintToFloat || kind == FpCvt::IntToFloat;
+        let sew_ok = match widths {
+            SINGLE => sew == 32 || sew == 64,
+            WIDEN if int_src => sew == 16 || sew == 32,
+            NARROW if !int_src && kind != FpCvt::FloatToFloat && kind != FpCvt::FloatToFloatRod => {
+                sew == 16 || sew == 32
+            }
+            _ => sew == 32,
+        };
+        let rm = match self.frm_rounding_mode() {
+            Some(_) if rtz => RoundingMode::TowardZero,
+            Some(rm) if sew_ok || sew == 0 => rm,
+            _ => {
+                self.raise_illegal_inst(inst);
+                return;
+            }
+        };
+
+        let mut flags = 0;
+        self.vector_arith(inst, widths, |a, _, _, sew| {
+            let src_eew = sew * widths.1;
+            let dst_eew = sew * widths.0;
+            let (result, raised) = match kind {
+                FpCvt::FloatToUint | FpCvt::FloatToInt => {
+                    let signed = kind == FpCvt::FloatToInt;
+                    if src_eew == 64 {
+                        fp_to_int(F64::from_elem(a), signed, dst_eew, rm)
+                    } else {
+                        fp_to_int(F32::from_elem(a), signed, dst_eew, rm)
+                    }
+                }
+                FpCvt::UintToFloat | FpCvt::IntToFloat => softfloat_with_flags(|| {
+                    let signed = kind == FpCvt::IntToFloat;
+                    if dst_eew == 64 {
+                        if signed {
+                            F64::from_i64(vsext(a, src_eew) as i64, rm).to_elem()
+                        } else {
+                            F64::from_u64(a, rm).to_elem()
+                        }
+                    } else if signed {
+                        F32::from_i64(vsext(a, src_eew) as i64, rm).to_elem()
+                    } else {
+                        F32::from_u64(a, rm).to_elem()
+                    }
+                }),
+                FpCvt::FloatToFloat | FpCvt::FloatToFloatRod => {
+                    let round = if kind == FpCvt::FloatToFloatRod {
+                        RoundingMode::TowardZero
+                    } else {
+                        rm
+                    };
+                    let (result, raised) = softfloat_with_flags(|| {
+                        if dst_eew == 64 {
+                            fp_canonical(F32::from_elem(a).to_f64(round)).to_elem()
+                        } else {
+                            fp_canonical(F64::from_elem(a).to_f32(round)).to_elem()
+                        }
+                    });
+                    // Round-to-odd: truncate and jam the sticky bit into the LSB
+                    let is_nan = F32::from_elem(result).is_nan();
+                    if kind == FpCvt::FloatToFloatRod && (raised & FFLAGS_NX) != 0 && !is_nan {
+                        (result | 1, raised)
+                    } else {
+                        (result, raised)
+                    }
+                }
+            };
+            flags |= raised;
+            result
+        });
+        self.accrue_fflags(flags);
+    }
+
+    fn vector_load_store(&mut self, inst: &InstType, mode: VMemMode, is_store: bool) {
+        let st = match self.vector_state(inst) {
+            Some(st) => st,
+            None => return,
+        };
+        let vd = inst.get_rd();
+        let vs2 = inst.get_rs2_rtype();
+        let width_eew = match (inst.data >> 12) & 0x7 {
+            0 => 8,
+            5 => 16,
+            6 => 32,
+            _ => 64,
+        };
+        let nf = ((inst.data >> 29) & 0x7) as usize + 1;
+        // Indexed accesses take the data EEW from SEW and the index EEW from the encoding
+        let data_eew = if mode == VMemMode::Indexed {
+            st.sew
+        } else {
+            width_eew
+        };
+        let regs = st.group_regs(data_eew).unwrap_or(0);
+        let legal = self.vector_group_ok(&st, vd, data_eew)
+            && nf * regs <= 8
+            && vd + nf * regs <= 32
+            && (mode != VMemMode::Indexed || self.vector_group_ok(&st, vs2, width_eew))
+            && (is_store || RVCore::vector_unmasked(inst) || vd != 0);
+        if !legal {
+            self.raise_illegal_inst(inst);
+            return;
+        }
+
+        let base = self.regs.read(inst.get_rs1());
+        let stride = self.regs.read(inst.get_rs2_rtype());
+        let bytes = data_eew / 8;
+        for i in st.vstart..st.vl {
+            if self.vector_masked_off(inst, i) {
+                continue;
+            }
+            for field in 0..nf {
+                let offset = match mode {
+                    VMemMode::UnitStride | VMemMode::FaultOnlyFirst => {
+                        ((i * nf + field) * bytes) as AddressType
+                    }
+                    VMemMode::Strided => (i as AddressType)
+                        .wrapping_mul(stride)
+                        .wrapping_add((field * bytes) as AddressType),
+                    VMemMode::Indexed => self
+                        .vregs
+                        .read(vs2, i, width_eew)
+                        .wrapping_add((field * bytes) as AddressType),
+                };
+                let addr = base.wrapping_add(offset);
+                let reg = vd + field * regs;
+                if mode == VMemMode::FaultOnlyFirst
+                    && i > 0
+                    && self.data_access_faults(addr, AccessType::Load)
+                {
+                    let vtype = self.csregs.read(csregs::VTYPE);
+                    self.csregs.set_vl_vtype(i as AddressType, vtype);
+                    self.csregs.write(csregs::VSTART, 0);
+                    return;
+                }
+                if is_store {
+                    let val = self.vregs.read(reg, i, data_eew);
+                    self.write_memory(addr, &mut val.to_le_bytes()[..bytes]);
+                } else {
+                    let mut data = [0; 8];
+                    self.read_memory(addr, &mut data[..bytes]);
+                    if !self.trap_taken {
+                        self.vregs
+                            .write(reg, i, data_eew, AddressType::from_le_bytes(data));
+                    }
+                }
+                // The trap handler resumes at the faulting element
+                if self.trap_taken {
+                    self.csregs.write(csregs::VSTART, i as AddressType);
+                    return;
+                }
+            }
+        }
+        self.csregs.write(csregs::VSTART, 0);
+    }
+
+    // vlm.v/vsm.v move ceil(vl / 8) bytes of a mask register
+    fn vector_load_store_mask(&mut self, inst: &InstType, is_store: bool) {
+        let st = match self.vector_state(inst) {
+            Some(st) => st,
+            None => return,
+        };
+        let vd = inst.get_rd();
+        let base = self.regs.read(inst.get_rs1());
+        let evl = st.vl.div_ceil(8);
+        for i in st.vstart..evl {
+            let addr = base.wrapping_add(i as AddressType);
+            if is_store {
+                let val = self.vregs.read(vd, i, 8);
+                self.write_memory(addr, &mut [val as u8]);
+            } else {
+                let mut data = [0; 1];
+                self.read_memory(addr, &mut data);
+                if !self.trap_taken {
+                    self.vregs.write(vd, i, 8, data[0] as AddressType);
+                }
+            }
+            if self.trap_taken {
+                self.csregs.write(csregs::VSTART, i as AddressType);
+                return;
+            }
+        }
+        self.csregs.write(csregs::VSTART, 0);
+    }
+
+    // Whole register moves ignore vtype and vl, nf registers are transferred
+    fn vector_load_store_whole(&mut self, inst: &InstType, is_store: bool) {
+        let vd = inst.get_rd();
+        let nf = ((inst.data >> 29) & 0x7) as usize + 1;
+        if !nf.is_power_of_two() || !vd.is_multiple_of(nf) {
+            self.raise_illegal_inst(inst);
+            return;
+        }
+        let eew = match (inst.data >> 12) & 0x7 {
+            0 => 8,
+            5 => 16,
+            6 => 32,
+            _ => 64,
+        };
+        let base = self.regs.read(inst.get_rs1());
+        let bytes = eew / 8;
+        let vstart = self.csregs.read(csregs::VSTART) as usize;
+        for i in vstart..nf * self.vregs.vlenb() / bytes {
+            let addr = base.wrapping_add((i * bytes) as AddressType);
+            if is_store {
+                let val = self.vregs.read(vd, i, eew);
+                self.write_memory(addr, &mut val.to_le_bytes()[..bytes]);
+            } else {
+                let mut data = [0; 8];
+                self.read_memory(addr, &mut data[..bytes]);
+                if !self.trap_taken {
+                    self.vregs
+                        .write(vd, i, eew, AddressType::from_le_bytes(data));
+                }
+            }
+            if self.trap_taken {
+                self.csregs.write(csregs::VSTART, i as AddressType);
+                return;
+            }
+        }
+        self.csregs.write(csregs::VSTART, 0);
+    }
+
+    // Moves vs2[src(i)] to vd[i] for the active elements, out of range sources read as 0
+    fn vector_permute<F>(&mut self, inst: &InstType, index_eew: usize, first: usize, src: F)
+    where
+        F: Fn(&RVCore, usize) -> Option<AddressType>,
+    {
+        let st = match self.vector_state(inst) {
+            Some(st) => st,
+            None => return,
+        };
+        let (vd, vs2, vs1) = (inst.get_rd(), inst.get_rs2_rtype(), inst.get_rs1());
+        let legal = self.vector_group_ok(&st, vd, st.sew)
+            && self.vector_group_ok(&st, vs2, st.sew)
+            && !RVCore::vector_groups_overlap(&st, vd, st.sew, vs2, st.sew)
+            && (!RVCore::vector_is_vv(inst)
+                || (self.vector_group_ok(&st, vs1, index_eew)
+                    && !RVCore::vector_groups_overlap(&st, vd, st.sew, vs1, index_eew)))
+            && (RVCore::vector_unmasked(inst) || vd != 0);
+        if !legal {
+            self.raise_illegal_inst(inst);
+            return;
+        }
+
+        let mut results = Vec::new();
+        for i in cmp::max(st.vstart, first)..st.vl {
+            if self.vector_masked_off(inst, i) {
+                continue;
+            }
+            let val = match src(self, i) {
+                Some(idx) if idx < st.vlmax as AddressType => {
+                    self.vregs.read(vs2, idx as usize, st.sew)
+                }
+                _ => 0,
+            };
+            results.push((i, val));
+        }
+        for (i, val) in results {
+            self.vregs.write(vd, i, st.sew, val);
+        }
+        self.csregs.write(csregs::VSTART, 0);
+    }
+
+    // vslide1up/vslide1down, the vacated element is filled with the scalar operand
+    fn vector_slide1(&mut self, inst: &InstType, up: bool) {
+        let st = match self.vector_state(inst) {
+            Some(st) => st,
+            None => return,
+        };
+        let (vd, vs2) = (inst.get_rd(), inst.get_rs2_rtype());
+        let legal = self.vector_group_ok(&st, vd, st.sew)
+            && self.vector_group_ok(&st, vs2, st.sew)
+            && (!up || !RVCore::vector_groups_overlap(&st, vd, st.sew, vs2, st.sew))
+            && (RVCore::vector_unmasked(inst) || vd != 0);
+        if !legal {
+            self.raise_illegal_inst(inst);
+            return;
+        }
+
+        let scalar = self.vector_scalar(inst, st.sew);
+        for i in st.vstart..st.vl {
+            if self.vector_masked_off(inst, i) {
+                continue;
+            }
+            let val = if up && i == 0 || !up && i == st.vl - 1 {
+                scalar
+            } else if up {
+                self.vregs.read(vs2, i - 1, st.sew)
+            } else {
+                self.vregs.read(vs2, i + 1, st.sew)
+            };
+            self.vregs.write(vd, i, st.sew, val);
+        }
+        self.csregs.write(csregs::VSTART, 0);
+    }
+
+    // vmsbf/vmsif/vmsof: mark elements before, up to, or only at the first set bit
+    fn vector_set_first(&mut self, inst: &InstType, before: bool, at: bool, after: bool) {
+        let st = match self.vector_state(inst) {
+            Some(st) => st,
+            None => return,
+        };
+        let (vd, vs2) = (inst.get_rd(), inst.get_rs2_rtype());
+        if st.vstart != 0 || vd == vs2 || (!RVCore::vector_unmasked(inst) && vd == 0) {
+            self.raise_illegal_inst(inst);
+            return;
+        }
+
+        let mut found = false;
+        for i in 0..st.vl {
+            if self.vector_masked_off(inst, i) {
+                continue;
+            }
+            let bit = if found {
+                after
+            } else if self.vregs.read_mask(vs2, i) {
+                found = true;
+                at
+            } else {
+                before
+            };
+            self.vregs.write_mask(vd, i, bit);
+        }
+    }
+
+    pub(super) fn inst_vsetvli(&mut self, inst: &InstType) {
+        let avl = self.vector_avl(inst);
+        self.vector_set_vl(inst.get_rd(), avl, (inst.data >> 20) & 0x7ff);
+    }
+
+    pub(super) fn inst_vsetivli(&mut self, inst: &InstType) {
+        let avl = inst.get_rs1() as AddressType;
+        self.vector_set_vl(inst.get_rd(), avl, (inst.data >> 20) & 0x3ff);
+    }
+
+    pub(super) fn inst_vsetvl(&mut self, inst: &InstType) {
+        let avl = self.vector_avl(inst);
+        let vtype = self.regs.read(inst.get_rs2_rtype());
+        self.vector_set_vl(inst.get_rd(), avl, vtype);
+    }
+
+    // rs1 = x0 requests VLMAX, unless rd is also x0 which keeps the current vl
+    fn vector_avl(&self, inst: &InstType) -> AddressType {
+        if inst.get_rs1() != 0 {
+            self.regs.read(inst.get_rs1())
+        } else if inst.get_rd() != 0 {
+            AddressType::MAX
+        } else {
+            self.csregs.read(csregs::VL)
+        }
+    }
+
+    fn vector_set_vl(&mut self, rd: usize, avl: AddressType, vtype: AddressType) {
+        let vl = match self.vector_parse_vtype(vtype) {
+            Some((sew, lmul_x8)) => {
+                let vlmax = (lmul_x8 * self.vregs.vlen() / 8 / sew) as AddressType;
+                let vl = cmp::min(avl, vlmax);
+                self.csregs.set_vl_vtype(vl, vtype);
+                vl
+            }
+            None => {
+                self.csregs.set_vl_vtype(0, csregs::VTYPE_VILL);
+                0
+            }
+        };
+        self.regs.write(rd, vl);
+        self.csregs.write(csregs::VSTART, 0);
+    }
+
+    pub(super) fn inst_vle_v(&mut self, inst: &InstType) {
+        self.vector_load_store(inst, VMemMode::UnitStride, false);
+    }
+
+    pub(super) fn inst_vleff_v(&mut self, inst: &InstType) {
+        self.vector_load_store(inst, VMemMode::FaultOnlyFirst, false);
+    }
+
+    pub(super) fn inst_vlm_v(&mut self, inst: &InstType) {
+        self.vector_load_store_mask(inst, false);
+    }
+
+    pub(super) fn inst_vloxei_v(&mut self, inst: &InstType) {
+        self.vector_load_store(inst, VMemMode::Indexed, false);
+    }
+
+    pub(super) fn inst_vlr_v(&mut self, inst: &InstType) {
+        self.vector_load_store_whole(inst, false);
+    }
+
+    pub(super) fn inst_vlse_v(&mut self, inst: &InstType) {
+        self.vector_load_store(inst, VMemMode::Strided, false);
+    }
+
+    pub(super) fn inst_vluxei_v(&mut self, inst: &InstType) {
+        self.vector_load_store(inst, VMemMode::Indexed, false);
+    }
+
+    pub(super) fn inst_vse_v(&mut self, inst: &InstType) {
+        self.vector_load_store(inst, VMemMode::UnitStride, true);
+    }
+
+    pub(super) fn inst_vsm_v(&mut self, inst: &InstType) {
+        self.vector_load_store_mask(inst, true);
+    }
+
+    pub(super) fn inst_vsoxei_v(&mut self, inst: &InstType) {
+        self.vector_load_store(inst, VMemMode::Indexed, true);
+    }
+
+    pub(super) fn inst_vsr_v(&mut self, inst: &InstType) {
+        self.vector_load_store_whole(inst, true);
+    }
+
+    pub(super) fn inst_vsse_v(&mut self, inst: &InstType) {
+        self.vector_load_store(inst, VMemMode::Strided, true);
+    }
+
+    pub(super) fn inst_vsuxei_v(&mut self, inst: &InstType) {
+        self.vector_load_store(inst, VMemMode::Indexed, true);
+    }
+
+    pub(super) fn inst_vadd(&mut self, inst: &InstType) {
+        self.vector_arith(inst, SINGLE, |a, b, _, _| a.wrapping_add(b));
+    }
+
+    pub(super) fn inst_vsub(&mut self, inst: &InstType) {
+        self.vector_arith(inst, SINGLE, |a, b, _, _| a.wrapping_sub(b));
+    }
+
+    pub(super) fn inst_vrsub(&mut self, inst: &InstType) {
+        self.vector_arith(inst, SINGLE, |a, b, _, _| b.wrapping_sub(a));
+    }
+
+    pub(super) fn inst_vand(&mut self, inst: &InstType) {
+        self.vector_arith(inst, SINGLE, |a, b, _, _| a & b);
+    }
+
+    pub(super) fn inst_vor(&mut self, inst: &InstType) {
+        self.vector_arith(inst, SINGLE, |a, b, _, _| a | b);
+    }
+
+    pub(super) fn inst_vxor(&mut self, inst: &InstType) {
+        self.vector_arith(inst, SINGLE, |a, b, _, _| a ^ b);
+    }
+
+    pub(super) fn inst_vminu(&mut self, inst: &InstType) {
+        self.vector_arith(inst, SINGLE, |a, b, _, _| cmp::min(a, b));
+    }
+
+    pub(super) fn inst_vmin(&mut self, inst: &InstType) {
+        self.vector_arith(inst, SINGLE, |a, b, _, sew| {
+            cmp::min(vsext(a, sew) as i64, vsext(b, sew) as i64) as AddressType
+        });
+    }
+
+    pub(super) fn inst_vmaxu(&mut self, inst: &InstType) {
+        self.vector_arith(inst, SINGLE, |a, b, _, _| cmp::max(a, b));
+    }
+
+    pub(super) fn inst_vmax(&mut self, inst: &InstType) {
+        self.vector_arith(inst, SINGLE, |a, b, _, sew| {
+            cmp::max(vsext(a, sew) as i64, vsext(b, sew) as i64) as AddressType
+        });
+    }
+
+    pub(super) fn inst_vsll(&mut self, inst: &InstType) {
+        self.vector_arith(inst, SINGLE, |a, b, _, sew| {
+            a << (b & (sew as AddressType - 1))
+        });
+    }
+
+    pub(super) fn inst_vsrl(&mut self, inst: &InstType) {
+        self.vector_arith(inst, SINGLE, |a, b, _, sew| {
+            a >> (b & (sew as AddressType - 1))
+        });
+    }
+
+    pub(super) fn inst_vsra(&mut self, inst: &InstType) {
+        self.vector_arith(inst, SINGLE, |a, b, _, sew| {
+            ((vsext(a, sew) as i64) >> (b & (sew as AddressType - 1))) as AddressType
+        });
+    }
+
+    pub(super) fn inst_vnsrl(&mut self, inst: &InstType) {
+        self.vector_arith(inst, NARROW, |a, b, _, sew| {
+            a >> (b & (2 * sew as AddressType - 1))
+        });
+    }
+
+    pub(super) fn inst_vnsra(&mut self, inst: &InstType) {
+        self.vector_arith(inst, NARROW, |a, b, _, sew| {
+            ((vsext(a, 2 * sew) as i64) >> (b & (2 * sew as AddressType - 1))) as AddressType
+        });
+    }
+
+    pub(super) fn inst_vmul(&mut self, inst: &InstType) {
+        self.vector_arith(inst, SINGLE, |a, b, _, _| a.wrapping_mul(b));
+    }
+
+    pub(super) fn inst_vmulh(&mut self, inst: &InstType) {
+        self.vector_arith(inst, SINGLE, |a, b, _, sew| {
+            let product = (vsext(a, sew) as i64 as i128) * (vsext(b, sew) as i64 as i128);
+            (product >> sew) as AddressType
+        });
+    }
+
+    pub(super) fn inst_vmulhu(&mut self, inst: &InstType) {
+        self.vector_arith(inst, SINGLE, |a, b, _, sew| {
+            ((a as u128 * b as u128) >> sew) as AddressType
+        });
+    }
+
+    pub(super) fn inst_vmulhsu(&mut self, inst: &InstType) {
+        self.vector_arith(inst, SINGLE, |a, b, _, sew| {
+            let product = (vsext(a, sew) as i64 as i128) * (b as i128);
+            (product >> sew) as AddressType
+        });
+    }
+
+    pub(super) fn inst_vdivu(&mut self, inst: &InstType) {
+        self.vector_arith(inst, SINGLE, |a, b, _, _| {
+            a.checked_div(b).unwrap_or(AddressType::MAX)
+        });
+    }
+
+    pub(super) fn inst_vdiv(&mut self, inst: &InstType) {
+        self.vector_arith(inst, SINGLE, |a, b, _, sew| {
+            let (a, b) = (vsext(a, sew) as i64, vsext(b, sew) as i64);
+            if b == 0 {
+                AddressType::MAX
+            } else {
+                a.wrapping_div(b) as AddressType
+            }
+        });
+    }
+
+    pub(super) fn inst_vremu(&mut self, inst: &InstType) {
+        self.vector_arith(inst, SINGLE, |a, b, _, _| if b == 0 { a } else { a % b });
+    }
+
+    pub(super) fn inst_vrem(&mut self, inst: &InstType) {
+        self.vector_arith(inst, SINGLE, |a, b, _, sew| {
+            let (a, b) = (vsext(a, sew) as i64, vsext(b, sew) as i64);
+            if b == 0 {
+                a as AddressType
+            } else {
+                a.wrapping_rem(b) as AddressType
+            }
+        });
+    }
+
+    pub(super) fn inst_vmacc(&mut self, inst: &InstType) {
+        self.vector_arith(inst, SINGLE, |a, b, d, _| d.wrapping_add(b.wrapping_mul(a)));
+    }
+
+    pub(super) fn inst_vnmsac(&mut self, inst: &InstType) {
+        self.vector_arith(inst, SINGLE, |a, b, d, _| d.wrapping_sub(b.wrapping_mul(a)));
+    }
+
+    pub(super) fn inst_vmadd(&mut self, inst: &InstType) {
+        self.vector_arith(inst, SINGLE, |a, b, d, _| b.wrapping_mul(d).wrapping_add(a));
+    }
+
+    pub(super) fn inst_vnmsub(&mut self, inst: &InstType) {
+        self.vector_arith(inst, SINGLE, |a, b, d, _| a.wrapping_sub(b.wrapping_mul(d)));
+    }
+
+    pub(super) fn inst_vwaddu(&mut self, inst: &InstType) {
+        self.vector_arith(inst, WIDEN, |a, b, _, _| a.wrapping_add(b));
+    }
+
+    pub(super) fn inst_vwadd(&mut self, inst: &InstType) {
+        self.vector_arith(inst, WIDEN, |a, b, _, sew| {
+            vsext(a, sew).wrapping_add(vsext(b, sew))
+        });
+    }
+
+    pub(super) fn inst_vwsubu(&mut self, inst: &InstType) {
+        self.vector_arith(inst, WIDEN, |a, b, _, _| a.wrapping_sub(b));
+    }
+
+    pub(super) fn inst_vwsub(&mut self, inst: &InstType) {
+        self.vector_arith(inst, WIDEN, |a, b, _, sew| {
+            vsext(a, sew).wrapping_sub(vsext(b, sew))
+        });
+    }
+
+    pub(super) fn inst_vwaddu_w(&mut self, inst: &InstType) {
+        self.vector_arith(inst, WIDEN_W, |a, b, _, _| a.wrapping_add(b));
+    }
+
+    pub(super) fn inst_vwadd_w(&mut self, inst: &InstType) {
+        self.vector_arith(inst, WIDEN_W, |a, b, _, sew| a.wrapping_add(vsext(b, sew)));
+    }
+
+    pub(super) fn inst_vwsubu_w(&mut self, inst: &InstType) {
+        self.vector_arith(inst, WIDEN_W, |a, b, _, _| a.wrapping_sub(b));
+    }
+
+    pub(super) fn inst_vwsub_w(&mut self, inst: &InstType) {
+        self.vector_arith(inst, WIDEN_W, |a, b, _, sew| a.wrapping_sub(vsext(b, sew)));
+    }
+
+    pub(super) fn inst_vwmulu(&mut self, inst: &InstType) {
+        self.vector_arith(inst, WIDEN, |a, b, _, _| a.wrapping_mul(b));
+    }
+
+    pub(super) fn inst_vwmul(&mut self, inst: &InstType) {
+        self.vector_arith(inst, WIDEN, |a, b, _, sew| {
+            vsext(a, sew).wrapping_mul(vsext(b, sew))
+        });
+    }
+
+    pub(super) fn inst_vwmulsu(&mut self, inst: &InstType) {
+        self.vector_arith(inst, WIDEN, |a, b, _, sew| vsext(a, sew).wrapping_mul(b));
+    }
+
+    pub(super) fn inst_vwmaccu(&mut self, inst: &InstType) {
+        self.vector_arith(inst, WIDEN, |a, b, d, _| d.wrapping_add(b.wrapping_mul(a)));
+    }
+
+    pub(super) fn inst_vwmacc(&mut self, inst: &InstType) {
+        self.vector_arith(inst, WIDEN, |a, b, d, sew| {
+            d.wrapping_add(vsext(b, sew).wrapping_mul(vsext(a, sew)))
+        });
+    }
+
+    pub(super) fn inst_vwmaccsu(&mut self, inst: &InstType) {
+        self.vector_arith(inst, WIDEN, |a, b, d, sew| {
+            d.wrapping_add(vsext(b, sew).wrapping_mul(a))
+        });
+    }
+
+    pub(super) fn inst_vwmaccus(&mut self, inst: &InstType) {
+        self.vector_arith(inst, WIDEN, |a, b, d, sew| {
+            d.wrapping_add(b.wrapping_mul(vsext(a, sew)))
+        });
+    }
+
+    pub(super) fn inst_vzext_vf2(&mut self, inst: &InstType) {
+        self.vector_extend(inst, 2, false);
+    }
+
+    pub(super) fn inst_vzext_vf4(&mut self, inst: &InstType) {
+        self.vector_extend(inst, 4, false);
+    }
+
+    pub(super) fn inst_vzext_vf8(&mut self, inst: &InstType) {
+        self.vector_extend(inst, 8, false);
+    }
+
+    pub(super) fn inst_vsext_vf2(&mut self, inst: &InstType) {
+        self.vector_extend(inst, 2, true);
+    }
+
+    pub(super) fn inst_vsext_vf4(&mut self, inst: &InstType) {
+        self.vector_extend(inst, 4, true);
+    }
+
+    pub(super) fn inst_vsext_vf8(&mut self, inst: &InstType) {
+        self.vector_extend(inst, 8, true);
+    }
+
+    pub(super) fn inst_vadc(&mut self, inst: &InstType) {
+        self.vector_carry(inst, false, false);
+    }
+
+    pub(super) fn inst_vmadc(&mut self, inst: &InstType) {
+        self.vector_carry(inst, false, true);
+    }
+
+    pub(super) fn inst_vsbc(&mut self, inst: &InstType) {
+        self.vector_carry(inst, true, false);
+    }
+
+    pub(super) fn inst_vmsbc(&mut self, inst: &InstType) {
+        self.vector_carry(inst, true, true);
+    }
+
+    pub(super) fn inst_vmerge(&mut self, inst: &InstType) {
+        self.vector_merge(inst);
+    }
+
+    pub(super) fn inst_vmv_v(&mut self, inst: &InstType) {
+        self.vector_merge(inst);
+    }
+
+    pub(super) fn inst_vmseq(&mut self, inst: &InstType) {
+        self.vector_compare(inst, |a, b, _| a == b);
+    }
+
+    pub(super) fn inst_vmsne(&mut self, inst: &InstType) {
+        self.vector_compare(inst, |a, b, _| a != b);
+    }
+
+    pub(super) fn inst_vmsltu(&mut self, inst: &InstType) {
+        self.vector_compare(inst, |a, b, _| a < b);
+    }
+
+    pub(super) fn inst_vmslt(&mut self, inst: &InstType) {
+        self.vector_compare(inst, |a, b, sew| {
+            (vsext(a, sew) as i64) < (vsext(b, sew) as i64)
+        });
+    }
+
+    pub(super) fn inst_vmsleu(&mut self, inst: &InstType) {
+        self.vector_compare(inst, |a, b, _| a <= b);
+    }
+
+    pub(super) fn inst_vmsle(&mut self, inst: &InstType) {
+        self.vector_compare(inst, |a, b, sew| {
+            (vsext(a, sew) as i64) <= (vsext(b, sew) as i64)
+        });
+    }
+
+    pub(super) fn inst_vmsgtu(&mut self, inst: &InstType) {
+        self.vector_compare(inst, |a, b, _| a > b);
+    }
+
+    pub(super) fn inst_vmsgt(&mut self, inst: &InstType) {
+        self.vector_compare(inst, |a, b, sew| {
+            (vsext(a, sew) as i64) > (vsext(b, sew) as i64)
+        });
+    }
+
+    pub(super) fn inst_vsaddu(&mut self, inst: &InstType) {
+        let mut sat = false;
+        self.vector_arith(inst, SINGLE, |a, b, _, sew| {
+            let sum = a as u128 + b as u128;
+            if sum > vtrunc(AddressType::MAX, sew) as u128 {
+                sat = true;
+                AddressType::MAX
+            } else {
+                sum as AddressType
+            }
+        });
+        self.vector_set_vxsat(sat);
+    }
+
+    pub(super) fn inst_vsadd(&mut self, inst: &InstType) {
+        let mut sat = false;
+        self.vector_arith(inst, SINGLE, |a, b, _, sew| {
+            let sum = vsext(a, sew) as i64 as i128 + vsext(b, sew) as i64 as i128;
+            RVCore::vector_clip_signed(sum, sew, &mut sat)
+        });
+        self.vector_set_vxsat(sat);
+    }
+
+    pub(super) fn inst_vssubu(&mut self, inst: &InstType) {
+        let mut sat = false;
+        self.vector_arith(inst, SINGLE, |a, b, _, _| {
+            if a < b {
+                sat = true;
+                0
+            } else {
+                a - b
+            }
+        });
+        self.vector_set_vxsat(sat);
+    }
+
+    pub(super) fn inst_vssub(&mut self, inst: &InstType) {
+        let mut sat = false;
+        self.vector_arith(inst, SINGLE, |a, b, _, sew| {
+            let diff = vsext(a, sew) as i64 as i128 - vsext(b, sew) as i64 as i128;
+            RVCore::vector_clip_signed(diff, sew, &mut sat)
+        });
+        self.vector_set_vxsat(sat);
+    }
+
+    pub(super) fn inst_vaaddu(&mut self, inst: &InstType) {
+        let vxrm = self.csregs.read(csregs::VXRM);
+        self.vector_arith(inst, SINGLE, |a, b, _, _| {
+            roundoff_unsigned(a as u128 + b as u128, 1, vxrm) as AddressType
+        });
+    }
+
+    pub(super) fn inst_vaadd(&mut self, inst: &InstType) {
+        let vxrm = self.csregs.read(csregs::VXRM);
+        self.vector_arith(inst, SINGLE, |a, b, _, sew| {
+            let sum = vsext(a, sew) as i64 as i128 + vsext(b, sew) as i64 as i128;
+            roundoff_signed(sum, 1, vxrm) as AddressType
+        });
+    }
+
+    pub(super) fn inst_vasubu(&mut self, inst: &InstType) {
+        let vxrm = self.csregs.read(csregs::VXRM);
+        self.vector_arith(inst, SINGLE, |a, b, _, _| {
+            roundoff_signed(a as i128 - b as i128, 1, vxrm) as AddressType
+        });
+    }
+
+    pub(super) fn inst_vasub(&mut self, inst: &InstType) {
+        let vxrm = self.csregs.read(csregs::VXRM);
+        self.vector_arith(inst, SINGLE, |a, b, _, sew| {
+            let diff = vsext(a, sew) as i64 as i128 - vsext(b, sew) as i64 as i128;
+            roundoff_signed(diff, 1, vxrm) as AddressType
+        });
+    }
+
+    pub(super) fn inst_vsmul(&mut self, inst: &InstType) {
+        let vxrm = self.csregs.read(csregs::VXRM);
+        let mut sat = false;
+        self.vector_arith(inst, SINGLE, |a, b, _, sew| {
+            let product = (vsext(a, sew) as i64 as i128) * (vsext(b, sew) as i64 as i128);
+            let result = roundoff_signed(product, sew - 1, vxrm);
+            RVCore::vector_clip_signed(result, sew, &mut sat)
+        });
+        self.vector_set_vxsat(sat);
+    }
+
+    pub(super) fn inst_vssrl(&mut self, inst: &InstType) {
+        let vxrm = self.csregs.read(csregs::VXRM);
+        self.vector_arith(inst, SINGLE, |a, b, _, sew| {
+            let shamt = (b & (sew as AddressType - 1)) as usize;
+            roundoff_unsigned(a as u128, shamt, vxrm) as AddressType
+        });
+    }
+
+    pub(super) fn inst_vssra(&mut self, inst: &InstType) {
+        let vxrm = self.csregs.read(csregs::VXRM);
+        self.vector_arith(inst, SINGLE, |a, b, _, sew| {
+            let shamt = (b & (sew as AddressType - 1)) as usize;
+            roundoff_signed(vsext(a, sew) as i64 as i128, shamt, vxrm) as AddressType
+        });
+    }
+
+    pub(super) fn inst_vnclipu(&mut self, inst: &InstType) {
+        let vxrm = self.csregs.read(csregs::VXRM);
+        let mut sat = false;
+        self.vector_arith(inst, NARROW, |a, b, _, sew| {
+            let shamt = (b & (2 * sew as AddressType - 1)) as usize;
+            let result = roundoff_unsigned(a as u128, shamt, vxrm);
+            if result > vtrunc(AddressType::MAX, sew) as u128 {
+                sat = true;
+                AddressType::MAX
+            } else {
+                result as AddressType
+            }
+        });
+        self.vector_set_vxsat(sat);
+    }
+
+    pub(super) fn inst_vnclip(&mut self, inst: &InstType) {
+        let vxrm = self.csregs.read(csregs::VXRM);
+        let mut sat = false;
+        self.vector_arith(inst, NARROW, |a, b, _, sew| {
+            let shamt = (b & (2 * sew as AddressType - 1)) as usize;
+            let result = roundoff_signed(vsext(a, 2 * sew) as i64 as i128, shamt, vxrm);
+            RVCore::vector_clip_signed(result, sew, &mut sat)
+        });
+        self.vector_set_vxsat(sat);
+    }
+
+    fn vector_clip_signed(val: i128, sew: usize, sat: &mut bool) -> AddressType {
+        let (min, max) = signed_range(sew);
+        if val > max {
+            *sat = true;
+            max as AddressType
+        } else if val < min {
+            *sat = true;
+            min as AddressType
+        } else {
+            val as AddressType
+        }
+    }
+
+    // vxsat is sticky, it is only ever set by instructions
+    fn vector_set_vxsat(&mut self, sat: bool) {
+        if sat {
+            self.csregs.write(csregs::VXSAT, 1);
+        }
+    }
+
+    pub(super) fn inst_vredsum(&mut self, inst: &InstType) {
+        self.vector_reduce(inst, false, |acc, elem, _| acc.wrapping_add(elem));
+    }
+
+    pub(super) fn inst_vredand(&mut self, inst: &InstType) {
+        self.vector_reduce(inst, false, |acc, elem, _| acc & elem);
+    }
+
+    pub(super) fn inst_vredor(&mut self, inst: &InstType) {
+        self.vector_reduce(inst, false, |acc, elem, _| acc | elem);
+    }
+
+    pub(super) fn inst_vredxor(&mut self, inst: &InstType) {
+        self.vector_reduce(inst, false, |acc, elem, _| acc ^ elem);
+    }
+
+    pub(super) fn inst_vredminu(&mut self, inst: &InstType) {
+        self.vector_reduce(inst, false, |acc, elem, _| cmp::min(acc, elem));
+    }
+
+    pub(super) fn inst_vredmin(&mut self, inst: &InstType) {
+        self.vector_reduce(inst, false, |acc, elem, sew| {
+            cmp::min(vsext(acc, sew) as i64, vsext(elem, sew) as i64) as AddressType
+        });
+    }
+
+    pub(super) fn inst_vredmaxu(&mut self, inst: &InstType) {
+        self.vector_reduce(inst, false, |acc, elem, _| cmp::max(acc, elem));
+    }
+
+    pub(super) fn inst_vredmax(&mut self, inst: &InstType) {
+        self.vector_reduce(inst, false, |acc, elem, sew| {
+            cmp::max(vsext(acc, sew) as i64, vsext(elem, sew) as i64) as AddressType
+        });
+    }
+
+    pub(super) fn inst_vwredsumu(&mut self, inst: &InstType) {
+        self.vector_reduce(inst, true, |acc, elem, _| acc.wrapping_add(elem));
+    }
+
+    pub(super) fn inst_vwredsum(&mut self, inst: &InstType) {
+        self.vector_reduce(inst, true, |acc, elem, sew| {
+            acc.wrapping_add(vsext(elem, sew))
+        });
+    }
+
+    pub(super) fn inst_vmand(&mut self, inst: &InstType) {
+        self.vector_mask_logical(inst, |a, b| a & b);
+    }
+
+    pub(super) fn inst_vmnand(&mut self, inst: &InstType) {
+        self.vector_mask_logical(inst, |a, b| !(a & b));
+    }
+
+    pub(super) fn inst_vmandn(&mut self, inst: &InstType) {
+        self.vector_mask_logical(inst, |a, b| a & !b);
+    }
+
+    pub(super) fn inst_vmor(&mut self, inst: &InstType) {
+        self.vector_mask_logical(inst, |a, b| a | b);
+    }
+
+    pub(super) fn inst_vmnor(&mut self, inst: &InstType) {
+        self.vector_mask_logical(inst, |a, b| !(a | b));
+    }
+
+    pub(super) fn inst_vmorn(&mut self, inst: &InstType) {
+        self.vector_mask_logical(inst, |a, b| a | !b);
+    }
+
+    pub(super) fn inst_vmxor(&mut self, inst: &InstType) {
+        self.vector_mask_logical(inst, |a, b| a ^ b);
+    }
+
+    pub(super) fn inst_vmxnor(&mut self, inst: &InstType) {
+        self.vector_mask_logical(inst, |a, b| !(a ^ b));
+    }
+
+    pub(super) fn inst_vcpop_m(&mut self, inst: &InstType) {
+        let st = match self.vector_state(inst) {
+            Some(st) => st,
+            None => return,
+        };
+        if st.vstart != 0 {
+            self.raise_illegal_inst(inst);
+            return;
+        }
+        let vs2 = inst.get_rs2_rtype();
+        let count = (0..st.vl)
+            .filter(|i| !self.vector_masked_off(inst, *i) && self.vregs.read_mask(vs2, *i))
+            .count();
+        self.regs.write(inst.get_rd(), count as AddressType);
+    }
+
+    pub(super) fn inst_vfirst_m(&mut self, inst: &InstType) {
+        let st = match self.vector_state(inst) {
+            Some(st) => st,
+            None => return,
+        };
+        if st.vstart != 0 {
+            self.raise_illegal_inst(inst);
+            return;
+        }
+        let vs2 = inst.get_rs2_rtype();
+        let first = (0..st.vl)
+            .find(|i| !self.vector_masked_off(inst, *i) && self.vregs.read_mask(vs2, *i))
+            .map_or(AddressType::MAX, |i| i as AddressType);
+        self.regs.write(inst.get_rd(), first);
+    }
+
+    pub(super) fn inst_vmsbf_m(&mut self, inst: &InstType) {
+        self.vector_set_first(inst, true, false, false);
+    }
+
+    pub(super) fn inst_vmsif_m(&mut self, inst: &InstType) {
+        self.vector_set_first(inst, true, true, false);
+    }
+
+    pub(super) fn inst_vmsof_m(&mut self, inst: &InstType) {
+        self.vector_set_first(inst, false, true, false);
+    }
+
+    pub(super) fn inst_viota_m(&mut self, inst: &InstType) {
+        let st = match self.vector_state(inst) {
+            Some(st) => st,
+            None => return,
+        };
+        let (vd, vs2) = (inst.get_rd(), inst.get_rs2_rtype());
+        let legal = st.vstart == 0
+            && self.vector_group_ok(&st, vd, st.sew)
+            && !RVCore::vector_groups_overlap(&st, vd, st.sew, vs2, 1)
+            && (RVCore::vector_unmasked(inst) || vd != 0);
+        if !legal {
+            self.raise_illegal_inst(inst);
+            return;
+        }
+
+        let mut count = 0;
+        for i in 0..st.vl {
+            if self.vector_masked_off(inst, i) {
+                continue;
+            }
+            self.vregs.write(vd, i, st.sew, count);
+            if self.vregs.read_mask(vs2, i) {
+                count += 1;
+            }
+        }
+    }
+
+    pub(super) fn inst_vid_v(&mut self, inst: &InstType) {
+        self.vector_arith(inst, SINGLE, |_, _, _, _| 0);
+        // vector_arith has no notion of the element index, fill it in afterwards
+        if let Some(st) = self.vector_state(inst) {
+            let vd = inst.get_rd();
+            for i in 0..st.vl {
+                if !self.vector_masked_off(inst, i) {
+                    self.vregs.write(vd, i, st.sew, i as AddressType);
+                }
+            }
+        }
+    }
+
+    pub(super) fn inst_vmv_x_s(&mut self, inst: &InstType) {
+        if let Some(st) = self.vector_state(inst) {
+            let val = self.vregs.read(inst.get_rs2_rtype(), 0, st.sew);
+            self.regs.write(inst.get_rd(), vsext(val, st.sew));
+        }
+    }
+
+    pub(super) fn inst_vmv_s_x(&mut self, inst: &InstType) {
+        if let Some(st) = self.vector_state(inst) {
+            if st.vstart < st.vl {
+                let val = self.regs.read(inst.get_rs1());
+                self.vregs.write(inst.get_rd(), 0, st.sew, val);
+            }
+            self.csregs.write(csregs::VSTART, 0);
+        }
+    }
+
+    pub(super) fn inst_vfmv_f_s(&mut self, inst: &InstType) {
+        if !self.vector_fp_sew_ok(SINGLE) {
+            self.raise_illegal_inst(inst);
+            return;
+        }
+        if let Some(st) = self.vector_state(inst) {
+            let val = self.vregs.read(inst.get_rs2_rtype(), 0, st.sew);
+            self.vector_write_fp_scalar(inst.get_rd(), st.sew, val);
+        }
+    }
+
+    pub(super) fn inst_vfmv_s_f(&mut self, inst: &InstType) {
+        if !self.vector_fp_sew_ok(SINGLE) {
+            self.raise_illegal_inst(inst);
+            return;
+        }
+        if let Some(st) = self.vector_state(inst) {
+            if st.vstart < st.vl {
+                let val = self.vector_read_fp_scalar(inst.get_rs1(), st.sew);
+                self.vregs.write(inst.get_rd(), 0, st.sew, val);
+            }
+            self.csregs.write(csregs::VSTART, 0);
+        }
+    }
+
+    // vmv<nr>r.v copies whole registers regardless of vl
+    pub(super) fn inst_vmvnr_v(&mut self, inst: &InstType) {
+        let nr = inst.get_rs1() + 1;
+        let (vd, vs2) = (inst.get_rd(), inst.get_rs2_rtype());
+        if !nr.is_power_of_two() || !vd.is_multiple_of(nr) || !vs2.is_multiple_of(nr) {
+            self.raise_illegal_inst(inst);
+            return;
+        }
+        for i in 0..nr * self.vregs.vlenb() {
+            let val = self.vregs.read(vs2, i, 8);
+            self.vregs.write(vd, i, 8, val);
+        }
+        self.csregs.write(csregs::VSTART, 0);
+    }
+
+    pub(super) fn inst_vslideup(&mut self, inst: &InstType) {
+        let offset = if ((inst.data >> 12) & 0x7) == 3 {
+            inst.get_rs1() as AddressType
+        } else {
+            self.regs.read(inst.get_rs1())
+        };
+        let first = cmp::min(offset, AddressType::from(u32::MAX)) as usize;
+        self.vector_permute(inst, 0, first, |_, i| Some((i - first) as AddressType));
+    }
+
+    pub(super) fn inst_vslidedown(&mut self, inst: &InstType) {
+        let offset = if ((inst.data >> 12) & 0x7) == 3 {
+            inst.get_rs1() as AddressType
+        } else {
+            self.regs.read(inst.get_rs1())
+        };
+        self.vector_permute(inst, 0, 0, |_, i| offset.checked_add(i as AddressType));
+    }
+
+    pub(super) fn inst_vslide1up(&mut self, inst: &InstType) {
+        self.vector_slide1(inst, true);
+    }
+
+    pub(super) fn inst_vslide1down(&mut self, inst: &InstType) {
+        self.vector_slide1(inst, false);
+    }
+
+    pub(super) fn inst_vfslide1up(&mut self, inst: &InstType) {
+        if !self.vector_fp_sew_ok(SINGLE) {
+            self.raise_illegal_inst(inst);
+            return;
+        }
+        self.vector_slide1(inst, true);
+    }
+
+    pub(super) fn inst_vfslide1down(&mut self, inst: &InstType) {
+        if !self.vector_fp_sew_ok(SINGLE) {
+            self.raise_illegal_inst(inst);
+            return;
+        }
+        self.vector_slide1(inst, false);
+    }
+
+    pub(super) fn inst_vrgather(&mut self, inst: &InstType) {
+        let sew = self
+            .vector_parse_vtype(self.csregs.read(csregs::VTYPE))
+            .map_or(8, |(sew, _)| sew);
+        let scalar_idx = match (inst.data >> 12) & 0x7 {
+            3 => inst.get_rs1() as AddressType,
+            _ => self.regs.read(inst.get_rs1()),
+        };
+        let vs1 = inst.get_rs1();
+        let is_vv = RVCore::vector_is_vv(inst);
+        self.vector_permute(inst, sew, 0, |core, i| {
+            if is_vv {
+                Some(core.vregs.read(vs1, i, sew))
+            } else {
+                Some(scalar_idx)
+            }
+        });
+    }
+
+    pub(super) fn inst_vrgatherei16(&mut self, inst: &InstType) {
+        let vs1 = inst.get_rs1();
+        self.vector_permute(inst, 16, 0, |core, i| Some(core.vregs.read(vs1, i, 16)));
+    }
+
+    pub(super) fn inst_vcompress(&mut self, inst: &InstType) {
+        let st = match self.vector_state(inst) {
+            Some(st) => st,
+            None => return,
+        };
+        let (vd, vs2, vs1) = (inst.get_rd(), inst.get_rs2_rtype(), inst.get_rs1());
+        let legal = st.vstart == 0
+            && self.vector_group_ok(&st, vd, st.sew)
+            && self.vector_group_ok(&st, vs2, st.sew)
+            && !RVCore::vector_groups_overlap(&st, vd, st.sew, vs2, st.sew)
+            && !RVCore::vector_groups_overlap(&st, vd, st.sew, vs1, 1);
+        if !legal {
+            self.raise_illegal_inst(inst);
+            return;
+        }
+
+        let mut packed = 0;
+        for i in 0..st.vl {
+            if self.vregs.read_mask(vs1, i) {
+                let val = self.vregs.read(vs2, i, st.sew);
+                self.vregs.write(vd, packed, st.sew, val);
+                packed += 1;
+            }
+        }
+    }
+
+    pub(super) fn inst_vfadd(&mut self, inst: &InstType) {
+        self.vector_fp_arith(inst, SINGLE, FpOp::Add);
+    }
+
+    pub(super) fn inst_vfsub(&mut self, inst: &InstType) {
+        self.vector_fp_arith(inst, SINGLE, FpOp::Sub);
+    }
+
+    pub(super) fn inst_vfrsub(&mut self, inst: &InstType) {
+        self.vector_fp_arith(inst, SINGLE, FpOp::RSub);
+    }
+
+    pub(super) fn inst_vfmul(&mut self, inst: &InstType) {
+        self.vector_fp_arith(inst, SINGLE, FpOp::Mul);
+    }
+
+    pub(super) fn inst_vfdiv(&mut self, inst: &InstType) {
+        self.vector_fp_arith(inst, SINGLE, FpOp::Div);
+    }
+
+    pub(super) fn inst_vfrdiv(&mut self, inst: &InstType) {
+        self.vector_fp_arith(inst, SINGLE, FpOp::RDiv);
+    }
+
+    pub(super) fn inst_vfmin(&mut self, inst: &InstType) {
+        self.vector_fp_arith(inst, SINGLE, FpOp::Min);
+    }
+
+    pub(super) fn inst_vfmax(&mut self, inst: &InstType) {
+        self.vector_fp_arith(inst, SINGLE, FpOp::Max);
+    }
+
+    pub(super) fn inst_vfsgnj(&mut self, inst: &InstType) {
+        self.vector_fp_arith(inst, SINGLE, FpOp::Sgnj);
+    }
+
+    pub(super) fn inst_vfsgnjn(&mut self, inst: &InstType) {
+        self.vector_fp_arith(inst, SINGLE, FpOp::Sgnjn);
+    }
+
+    pub(super) fn inst_vfsgnjx(&mut self, inst: &InstType) {
+        self.vector_fp_arith(inst, SINGLE, FpOp::Sgnjx);
+    }
+
+    pub(super) fn inst_vfmacc(&mut self, inst: &InstType) {
+        self.vector_fp_arith(inst, SINGLE, FpOp::Macc);
+    }
+
+    pub(super) fn inst_vfnmacc(&mut self, inst: &InstType) {
+        self.vector_fp_arith(inst, SINGLE, FpOp::Nmacc);
+    }
+
+    pub(super) fn inst_vfmsac(&mut self, inst: &InstType) {
+        self.vector_fp_arith(inst, SINGLE, FpOp::Msac);
+    }
+
+    pub(super) fn inst_vfnmsac(&mut self, inst: &InstType) {
+        self.vector_fp_arith(inst, SINGLE, FpOp::Nmsac);
+    }
+
+    pub(super) fn inst_vfmadd(&mut self, inst: &InstType) {
+        self.vector_fp_arith(inst, SINGLE, FpOp::Madd);
+    }
+
+    pub(super) fn inst_vfnmadd(&mut self, inst: &InstType) {
+        self.vector_fp_arith(inst, SINGLE, FpOp::Nmadd);
+    }
+
+    pub(super) fn inst_vfmsub(&mut self, inst: &InstType) {
+        self.vector_fp_arith(inst, SINGLE, FpOp::Msub);
+    }
+
+    pub(super) fn inst_vfnmsub(&mut self, inst: &InstType) {
+        self.vector_fp_arith(inst, SINGLE, FpOp::Nmsub);
+    }
+
+    pub(super) fn inst_vfwadd(&mut self, inst: &InstType) {
+        self.vector_fp_arith(inst, WIDEN, FpOp::Add);
+    }
+
+    pub(super) fn inst_vfwsub(&mut self, inst: &InstType) {
+        self.vector_fp_arith(inst, WIDEN, FpOp::Sub);
+    }
+
+    pub(super) fn inst_vfwadd_w(&mut self, inst: &InstType) {
+        self.vector_fp_arith(inst, WIDEN_W, FpOp::Add);
+    }
+
+    pub(super) fn inst_vfwsub_w(&mut self, inst: &InstType) {
+        self.vector_fp_arith(inst, WIDEN_W, FpOp::Sub);
+    }
+
+    pub(super) fn inst_vfwmul(&mut self, inst: &InstType) {
+        self.vector_fp_arith(inst, WIDEN, FpOp::Mul);
+    }
+
+    pub(super) fn inst_vfwmacc(&mut self, inst: &InstType) {
+        self.vector_fp_arith(inst, WIDEN, FpOp::Macc);
+    }
+
+    pub(super) fn inst_vfwnmacc(&mut self, inst: &InstType) {
+        self.vector_fp_arith(inst, WIDEN, FpOp::Nmacc);
+    }
+
+    pub(super) fn inst_vfwmsac(&mut self, inst: &InstType) {
+        self.vector_fp_arith(inst, WIDEN, FpOp::Msac);
+    }
+
+    pub(super) fn inst_vfwnmsac(&mut self, inst: &InstType) {
+        self.vector_fp_arith(inst, WIDEN, FpOp::Nmsac);
+    }
+
+    pub(super) fn inst_vfmerge(&mut self, inst: &InstType) {
+        if !self.vector_fp_sew_ok(SINGLE) {
+            self.raise_illegal_inst(inst);
+            return;
+        }
+        self.vector_merge(inst);
+    }
+
+    pub(super) fn inst_vfmv_v_f(&mut self, inst: &InstType) {
+        if !self.vector_fp_sew_ok(SINGLE) {
+            self.raise_illegal_inst(inst);
+            return;
+        }
+        self.vector_merge(inst);
+    }
+
+    pub(super) fn inst_vmfeq(&mut self, inst: &InstType) {
+        self.vector_fp_compare(inst, FpCmp::Eq);
+    }
+
+    pub(super) fn inst_vmfne(&mut self, inst: &InstType) {
+        self.vector_fp_compare(inst, FpCmp::Ne);
+    }
+
+    pub(super) fn inst_vmflt(&mut self, inst: &InstType) {
+        self.vector_fp_compare(inst, FpCmp::Lt);
+    }
+
+    pub(super) fn inst_vmfle(&mut self, inst: &InstType) {
+        self.vector_fp_compare(inst, FpCmp::Le);
+    }
+
+    pub(super) fn inst_vmfgt(&mut self, inst: &InstType) {
+        self.vector_fp_compare(inst, FpCmp::Gt);
+    }
+
+    pub(super) fn inst_vmfge(&mut self, inst: &InstType) {
+        self.vector_fp_compare(inst, FpCmp::Ge);
+    }
+
+    // Sequential summation is a valid order for the unordered reduction as well
+    pub(super) fn inst_vfredusum(&mut self, inst: &InstType) {
+        self.vector_fp_reduce(inst, false, FpOp::Add);
+    }
+
+    pub(super) fn inst_vfredosum(&mut self, inst: &InstType) {
+        self.vector_fp_reduce(inst, false, FpOp::Add);
+    }
+
+    pub(super) fn inst_vfredmin(&mut self, inst: &InstType) {
+        self.vector_fp_reduce(inst, false, FpOp::Min);
+    }
+
+    pub(super) fn inst_vfredmax(&mut self, inst: &InstType) {
+        self.vector_fp_reduce(inst, false, FpOp::Max);
+    }
+
+    pub(super) fn inst_vfwredusum(&mut self, inst: &InstType) {
+        self.vector_fp_reduce(inst, true, FpOp::Add);
+    }
+
+    pub(super) fn inst_vfwredosum(&mut self, inst: &InstType) {
+        self.vector_fp_reduce(inst, true, FpOp::Add);
+    }
+
+    pub(super) fn inst_vfsqrt_v(&mut self, inst: &InstType) {
+        let rm = match self.frm_rounding_mode() {
+            Some(rm) => rm,
+            None => {
+                self.raise_illegal_inst(inst);
+                return;
+            }
+        };
+        self.vector_fp_unary(inst, |a, sew| {
+            softfloat_with_flags(|| {
+                if sew == 64 {
+                    fp_canonical(F64::from_elem(a).sqrt(rm)).to_elem()
+                } else {
+                    fp_canonical(F32::from_elem(a).sqrt(rm)).to_elem()
+                }
+            })
+        });
+    }
+
+    pub(super) fn inst_vfrec7_v(&mut self, inst: &InstType) {
+        let rm = match self.frm_rounding_mode() {
+            Some(rm) => rm,
+            None => {
+                self.raise_illegal_inst(inst);
+                return;
+            }
+        };
+        self.vector_fp_unary(inst, |a, sew| {
+            if sew == 64 {
+                fp_rec7(a, 11, 52, rm)
+            } else {
+                fp_rec7(a, 8, 23, rm)
+            }
+        });
+    }
+
+    pub(super) fn inst_vfrsqrt7_v(&mut self, inst: &InstType) {
+        self.vector_fp_unary(inst, |a, sew| {
+            if sew == 64 {
+                fp_rsqrt7(a, 11, 52)
+            } else {
+                fp_rsqrt7(a, 8, 23)
+            }
+        });
+    }
+
+    pub(super) fn inst_vfclass_v(&mut self, inst: &InstType) {
+        self.vector_fp_unary(inst, |a, sew| {
+            if sew == 64 {
+                (fp_classify(F64::from_elem(a)), 0)
+            } else {
+                (fp_classify(F32::from_elem(a)), 0)
+            }
+        });
+    }
+
+    pub(super) fn inst_vfcvt_xu_f_v(&mut self, inst: &InstType) {
+        self.vector_fp_convert(inst, SINGLE, FpCvt::FloatToUint, false);
+    }
+
+    pub(super) fn inst_vfcvt_x_f_v(&mut self, inst: &InstType) {
+        self.vector_fp_convert(inst, SINGLE, FpCvt::FloatToInt, false);
+    }
+
+    pub(super) fn inst_vfcvt_rtz_xu_f_v(&mut self, inst: &InstType) {
+        self.vector_fp_convert(inst, SINGLE, FpCvt::FloatToUint, true);
+    }
+
+    pub(super) fn inst_vfcvt_rtz_x_f_v(&mut self, inst: &InstType) {
+        self.vector_fp_convert(inst, SINGLE, FpCvt::FloatToInt, true);
+    }
+
+    pub(super) fn inst_vfcvt_f_xu_v(&mut self, inst: &InstType) {
+        self.vector_fp_convert(inst, SINGLE, FpCvt::UintToFloat, false);
+    }
+
+    pub(super) fn inst_vfcvt_f_x_v(&mut self, inst: &InstType) {
+        self.vector_fp_convert(inst, SINGLE, FpCvt::IntToFloat, false);
+    }
+
+    pub(super) fn inst_vfwcvt_xu_f_v(&mut self, inst: &InstType) {
+        self.vector_fp_convert(inst, WIDEN, FpCvt::FloatToUint, false);
+    }
+
+    pub(super) fn inst_vfwcvt_x_f_v(&mut self, inst: &InstType) {
+        self.vector_fp_convert(inst, WIDEN, FpCvt::FloatToInt, false);
+    }
+
+    pub(super) fn inst_vfwcvt_rtz_xu_f_v(&mut self, inst: &InstType) {
+        self.vector_fp_convert(inst, WIDEN, FpCvt::FloatToUint, true);
+    }
+
+    pub(super) fn inst_vfwcvt_rtz_x_f_v(&mut self, inst: &InstType) {
+        self.vector_fp_convert(inst, WIDEN, FpCvt::FloatToInt, true);
+    }
+
+    pub(super) fn inst_vfwcvt_f_xu_v(&mut self, inst: &InstType) {
+        self.vector_fp_convert(inst, WIDEN, FpCvt::UintToFloat, false);
+    }
+
+    pub(super) fn inst_vfwcvt_f_x_v(&mut self, inst: &InstType) {
+        self.vector_fp_convert(inst, WIDEN, FpCvt::IntToFloat, false);
+    }
+
+    pub(super) fn inst_vfwcvt_f_f_v(&mut self, inst: &InstType) {
+        self.vector_fp_convert(inst, WIDEN, FpCvt::FloatToFloat, false);
+    }
+
+    pub(super) fn inst_vfncvt_xu_f_w(&mut self, inst: &InstType) {
+        self.vector_fp_convert(inst, NARROW, FpCvt::FloatToUint, false);
+    }
+
+    pub(super) fn inst_vfncvt_x_f_w(&mut self, inst: &InstType) {
+        self.vector_fp_convert(inst, NARROW, FpCvt::FloatToInt, false);
+    }
+
+    pub(super) fn inst_vfncvt_rtz_xu_f_w(&mut self, inst: &InstType) {
+        self.vector_fp_convert(inst, NARROW, FpCvt::FloatToUint, true);
+    }
+
+    pub(super) fn inst_vfncvt_rtz_x_f_w(&mut self, inst: &InstType) {
+        self.vector_fp_convert(inst, NARROW, FpCvt::FloatToInt, true);
+    }
+
+    pub(super) fn inst_vfncvt_f_xu_w(&mut self, inst: &InstType) {
+        self.vector_fp_convert(inst, NARROW, FpCvt::UintToFloat, false);
+    }
+
+    pub(super) fn inst_vfncvt_f_x_w(&mut self, inst: &InstType) {
+        self.vector_fp_convert(inst, NARROW, FpCvt::IntToFloat, false);
+    }
+
+    pub(super) fn inst_vfncvt_f_f_w(&mut self, inst: &InstType) {
+        self.vector_fp_convert(inst, NARROW, FpCvt::FloatToFloat, false);
+    }
+
+    pub(super) fn inst_vfncvt_rod_f_f_w(&mut self, inst: &InstType) {
+        self.vector_fp_convert(inst, NARROW, FpCvt::FloatToFloatRod, false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory_interface::MemoryInterface;
+    use crate::memory_model::MemoryModel;
+    use crate::rv_core::inst_info::InstID;
+    use crate::rv_core::inst_type::tests::*;
+    use crate::rv_core::PrivilegeMode;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    // e32, m1, tail and mask agnostic
+    const VTYPE_E32M1: AddressType = 0xd0;
+
+    fn set_elements(core: &mut RVCore, reg: usize, eew: usize, vals: &[AddressType]) {
+        for (i, val) in vals.iter().enumerate() {
+            core.vregs.write(reg, i, eew, *val);
+        }
+    }
+
+    fn get_elements(core: &RVCore, reg: usize, eew: usize, num: usize) -> Vec<AddressType> {
+        (0..num).map(|i| core.vregs.read(reg, i, eew)).collect()
+    }
+
+    #[test]
+    fn test_inst_vsetvl() {
+        let mut core: RVCore = RVCore::new();
+        assert_eq!(csregs::VTYPE_VILL, core.csregs.read(csregs::VTYPE));
+        assert_eq!(16, core.csregs.read(csregs::VLENB));
+
+        core.regs.write(2, 10);
+        core.inst_vsetvli(&inst_vsetvli_code(1, 2, VTYPE_E32M1));
+        assert_eq!(4, core.regs.read(1));
+        assert_eq!(4, core.csregs.read(csregs::VL));
+        assert_eq!(VTYPE_E32M1, core.csregs.read(csregs::VTYPE));
+
+        // rs1 = x0 with rd != x0 sets vl to VLMAX, e32 m2 gives 8
+        core.inst_vsetvli(&inst_vsetvli_code(1, 0, 0xd1));
+        assert_eq!(8, core.regs.read(1));
+
+        core.inst_vsetivli(&inst_vsetivli_code(1, 3, 0xc0));
+        assert_eq!(3, core.regs.read(1));
+
+        // e64 with mf8 is unsupported, vill is set and vl cleared
+        core.inst_vsetvli(&inst_vsetvli_code(1, 2, 0x1d));
+        assert_eq!(0, core.regs.read(1));
+        assert_eq!(csregs::VTYPE_VILL, core.csregs.read(csregs::VTYPE));
+
+        // Any vector operation with vill set is illegal
+        core.inst_vadd(&inst_vector_code(InstID::VADD, 0x00, 0, 1, 1, 2, 3));
+        assert_eq!(csregs::EXC_ILLEGAL_INST, core.csregs.read(csregs::MCAUSE));
+
+        // vl, vtype and vlenb are read-only, only a read without write is allowed
+        core.inst_vsetivli(&inst_vsetivli_code(1, 3, 0xc0));
+        for csr in [csregs::VL, csregs::VTYPE, csregs::VLENB] {
+            let val = core.csregs.read(csr);
+            core.csregs.write(csregs::MCAUSE, 0);
+            core.inst_csrrs(&inst_csr_code(InstID::CSRRS, 2, 1, 0, csr));
+            assert_eq!(0, core.csregs.read(csregs::MCAUSE));
+            assert_eq!(val, core.regs.read(1));
+            core.regs.write(2, 1);
+            core.inst_csrrs(&inst_csr_code(InstID::CSRRS, 2, 1, 2, csr));
+            assert_eq!(csregs::EXC_ILLEGAL_INST, core.csregs.read(csregs::MCAUSE));
+            core.csregs.write(csregs::MCAUSE, 0);
+            core.inst_csrrw(&inst_csr_code(InstID::CSRRW, 1, 1, 0, csr));
+            assert_eq!(csregs::EXC_ILLEGAL_INST, core.csregs.read(csregs::MCAUSE));
+            assert_eq!(val, core.csregs.read(csr));
+        }
+        assert!(core.set_vlen(96, 64).is_err());
+        assert!(core.set_vlen(256, 16).is_err());
+        assert!(core.set_vlen(256, 64).is_ok());
+        assert_eq!(32, core.csregs.read(csregs::VLENB));
+    }
+
+    #[test]
+    fn test_inst_vector_int() {
+        let mut core: RVCore = RVCore::new();
+        core.inst_vsetivli(&inst_vsetivli_code(0, 4, VTYPE_E32M1));
+        set_elements(&mut core, 2, 32, &[1, 2, 3, 0xffff_ffff]);
+        set_elements(&mut core, 3, 32, &[10, 20, 30, 1]);
+
+        core.inst_vadd(&inst_vector_code(InstID::VADD, 0x00, 0, 1, 1, 2, 3));
+        assert_eq!(vec![11, 22, 33, 0], get_elements(&core, 1, 32, 4));
+        core.inst_vadd(&inst_vector_code(InstID::VADD, 0x00, 3, 1, 1, 2, 0x1f));
+        assert_eq!(vec![0, 1, 2, 0xffff_fffe], get_elements(&core, 1, 32, 4));
+
+        // Masked vsub.vx only updates elements 0 and 2
+        core.vregs.write(0, 0, 8, 0b0101);
+        core.regs.write(5, 1);
+        core.inst_vsub(&inst_vector_code(InstID::VSUB, 0x02, 4, 0, 4, 2, 5));
+        assert_eq!(vec![0, 0, 2, 0], get_elements(&core, 4, 32, 4));
+
+        core.inst_vmsltu(&inst_vector_code(InstID::VMSLTU, 0x1a, 0, 1, 5, 2, 3));
+        assert_eq!(0b0111, core.vregs.read(5, 0, 8) & 0xf);
+
+        core.inst_vwaddu(&inst_vector_code(InstID::VWADDU, 0x30, 2, 1, 8, 2, 3));
+        assert_eq!(
+            vec![11, 22, 33, 0x1_0000_0000],
+            get_elements(&core, 8, 64, 4)
+        );
+
+        core.inst_vsaddu(&inst_vector_code(InstID::VSADDU, 0x20, 0, 1, 1, 2, 3));
+        assert_eq!(vec![11, 22, 33, 0xffff_ffff], get_elements(&core, 1, 32, 4));
+        assert_eq!(1, core.csregs.read(csregs::VXSAT));
+
+        core.inst_vredsum(&inst_vector_code(InstID::VREDSUM, 0x00, 2, 1, 6, 2, 3));
+        assert_eq!(15, core.vregs.read(6, 0, 32));
+
+        // A widening destination must be aligned to its 2-register group
+        core.inst_vwaddu(&inst_vector_code(InstID::VWADDU, 0x30, 2, 1, 9, 2, 3));
+        assert_eq!(csregs::EXC_ILLEGAL_INST, core.csregs.read(csregs::MCAUSE));
+    }
+
+    #[test]
+    fn test_inst_vector_fp() {
+        let mut core: RVCore = RVCore::new();
+        core.inst_vsetivli(&inst_vsetivli_code(0, 2, VTYPE_E32M1));
+        // 1.5, 1.0
+        set_elements(&mut core, 2, 32, &[0x3fc0_0000, 0x3f80_0000]);
+        // 0.5, 3.0
+        set_elements(&mut core, 3, 32, &[0x3f00_0000, 0x4040_0000]);
+
+        core.inst_vfadd(&inst_vector_code(InstID::VFADD, 0x00, 1, 1, 1, 2, 3));
+        assert_eq!(
+            vec![0x4000_0000, 0x4080_0000],
+            get_elements(&core, 1, 32, 2)
+        );
+
+        // vd = f1 * vs2 + vd with f1 = 2.0
+        core.fregs.write_s(1, F32::from_bits(0x4000_0000));
+        core.inst_vfmacc(&inst_vector_code(InstID::VFMACC, 0x2c, 5, 1, 1, 2, 1));
+        assert_eq!(
+            vec![0x40a0_0000, 0x40c0_0000],
+            get_elements(&core, 1, 32, 2)
+        );
+
+        core.inst_vmflt(&inst_vector_code(InstID::VMFLT, 0x1b, 1, 1, 5, 2, 3));
+        assert_eq!(0b10, core.vregs.read(5, 0, 8) & 0x3);
+
+        core.inst_vfrec7_v(&inst_vector_code(InstID::VFREC7_V, 0x13, 1, 1, 1, 2, 5));
+        assert_eq!(
+            vec![0x3f2a_0000, 0x3f7f_0000],
+            get_elements(&core, 1, 32, 2)
+        );
+
+        // 1.0 / 0.0 raises DZ, accrued on top of the flags already set
+        core.csregs.write(csregs::FFLAGS, FFLAGS_NX);
+        set_elements(&mut core, 3, 32, &[0, 0]);
+        core.inst_vfdiv(&inst_vector_code(InstID::VFDIV, 0x20, 1, 1, 1, 2, 3));
+        assert_eq!(
+            vec![0x7f80_0000, 0x7f80_0000],
+            get_elements(&core, 1, 32, 2)
+        );
+        assert_eq!(FFLAGS_NX | FFLAGS_DZ, core.csregs.read(csregs::FFLAGS));
+
+        core.csregs.write(csregs::FRM, 5);
+        core.inst_vfadd(&inst_vector_code(InstID::VFADD, 0x00, 1, 1, 1, 2, 3));
+        assert_eq!(csregs::EXC_ILLEGAL_INST, core.csregs.read(csregs::MCAUSE));
+    }
+
+    #[test]
+    fn test_inst_vector_load_store() {
+        let mut core: RVCore = RVCore::new();
+        let mem = Rc::new(RefCell::new(MemoryModel::new()));
+        let mem_if: Rc<RefCell<dyn MemoryInterface>> = mem.clone();
+        core.bind_mem(mem_if);
+        for i in 0..4 {
+            mem.borrow_mut()
+                .write_word(0x1000 + i * 4, 0x1111_1111 * (i as u32 + 1));
+        }
+
+        core.inst_vsetivli(&inst_vsetivli_code(0, 3, VTYPE_E32M1));
+        core.regs.write(2, 0x1000);
+        core.inst_vle_v(&inst_vle_v_code(1, 2, 6));
+        assert_eq!(
+            vec![0x1111_1111, 0x2222_2222, 0x3333_3333, 0],
+            get_elements(&core, 1, 32, 4)
+        );
+
+        core.regs.write(3, 0x2000);
+        core.inst_vse_v(&inst_vse_v_code(1, 3, 6));
+        assert_eq!(0x2222_2222, mem.borrow_mut().read_word(0x2004));
+        assert_eq!(0, mem.borrow_mut().read_word(0x200c));
+    }
+
+    #[test]
+    fn test_inst_vector_load_fault() {
+        let mut core: RVCore = RVCore::new();
+        let mem = Rc::new(RefCell::new(MemoryModel::new()));
+        let mem_if: Rc<RefCell<dyn MemoryInterface>> = mem.clone();
+        core.bind_mem(mem_if);
+        // Only the gigapage VA 0x4000_0000 -> PA 0x8000_0000 is mapped
+        mem.borrow_mut().write_word(0x10008, (0x80000 << 10) | 0xc7);
+        mem.borrow_mut().write_word(0xbfff_fff8, 0x1111_1111);
+        mem.borrow_mut().write_word(0xbfff_fffc, 0x2222_2222);
+        core.csregs
+            .write(csregs::SATP, csregs::SATP_MODE_SV39 | (0x10000 >> 12));
+        core.csregs.write(csregs::MTVEC, 0x400);
+        core.inst_vsetivli(&inst_vsetivli_code(0, 4, VTYPE_E32M1));
+        set_elements(&mut core, 1, 32, &[5, 6, 7, 8]);
+
+        // The strided load stops at the third element, the later ones are left as they were
+        core.mode = PrivilegeMode::S;
+        core.regs.write(2, 0x7fff_fff8);
+        core.regs.write(3, 4);
+        core.inst_vlse_v(&inst_vlse_v_code(1, 2, 3, 6));
+        assert!(core.trap_taken);
+        assert_eq!(
+            csregs::EXC_LOAD_PAGE_FAULT,
+            core.csregs.read(csregs::MCAUSE)
+        );
+        assert_eq!(0x8000_0000, core.csregs.read(csregs::MTVAL));
+        assert_eq!(2, core.csregs.read(csregs::VSTART));
+        assert_eq!(
+            vec![0x1111_1111, 0x2222_2222, 7, 8],
+            get_elements(&core, 1, 32, 4)
+        );
+
+        // Fault-only-first loads trim vl past the first element
+        core.trap_taken = false;
+        core.mode = PrivilegeMode::S;
+        core.csregs.write(csregs::VSTART, 0);
+        core.inst_vleff_v(&inst_vleff_v_code(4, 2, 6));
+        assert!(!core.trap_taken);
+        assert_eq!(2, core.csregs.read(csregs::VL));
+        assert_eq!(0, core.csregs.read(csregs::VSTART));
+        assert_eq!(
+            vec![0x1111_1111, 0x2222_2222],
+            get_elements(&core, 4, 32, 2)
+        );
+        core.regs.write(2, 0x8000_0000);
+        core.inst_vleff_v(&inst_vleff_v_code(4, 2, 6));
+        assert!(core.trap_taken);
+        assert_eq!(0x8000_0000, core.csregs.read(csregs::MTVAL));
+        assert_eq!(2, core.csregs.read(csregs::VL));
+    }
+}
@@ -0,0 +1,88 @@
+type AddressType = u64;
+
+pub const DEFAULT_VLEN: usize = 128;
+pub const DEFAULT_ELEN: usize = 64;
+
+// The 32 vector registers are stored back to back, so the elements of a
+// register group are simply contiguous bytes starting at the base register.
+pub struct VRegisters {
+    vlen: usize,
+    elen: usize,
+    reg_bank: Vec<u8>,
+}
+
+impl VRegisters {
+    pub fn new(vlen: usize, elen: usize) -> VRegisters {
+        VRegisters {
+            vlen,
+            elen,
+            reg_bank: vec![0; vlen / 8 * 32],
+        }
+    }
+
+    pub fn vlen(&self) -> usize {
+        self.vlen
+    }
+
+    pub fn elen(&self) -> usize {
+        self.elen
+    }
+
+    pub fn vlenb(&self) -> usize {
+        self.vlen / 8
+    }
+
+    // Element idx of the group starting at reg, eew in bits
+    pub fn read(&self, reg: usize, idx: usize, eew: usize) -> AddressType {
+        let offset = reg * self.vlenb() + idx * eew / 8;
+        let mut val: AddressType = 0;
+        for i in (0..eew / 8).rev() {
+            val = (val << 8) | self.reg_bank[offset + i] as AddressType;
+        }
+        val
+    }
+
+    pub fn write(&mut self, reg: usize, idx: usize, eew: usize, val: AddressType) {
+        let offset = reg * self.vlenb() + idx * eew / 8;
+        for i in 0..eew / 8 {
+            self.reg_bank[offset + i] = (val >> (i * 8)) as u8;
+        }
+    }
+
+    pub fn read_mask(&self, reg: usize, idx: usize) -> bool {
+        let byte = self.reg_bank[reg * self.vlenb() + idx / 8];
+        (byte >> (idx % 8)) & 1 == 1
+    }
+
+    pub fn write_mask(&mut self, reg: usize, idx: usize, val: bool) {
+        let offset = reg * self.vlenb() + idx / 8;
+        if val {
+            self.reg_bank[offset] |= 1 << (idx % 8);
+        } else {
+            self.reg_bank[offset] &= !(1 << (idx % 8));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vregs_group() {
+        let mut vregs = VRegisters::new(128, 64);
+        vregs.write(2, 3, 32, 0x1234_5678);
+        // Element 3 of a 32-bit group at v2 is the last element of v2
+        assert_eq!(0x1234_5678, vregs.read(2, 3, 32));
+        assert_eq!(0x78, vregs.read(2, 12, 8));
+        vregs.write(2, 4, 32, 0xdead_beef);
+        assert_eq!(0xdead_beef, vregs.read(3, 0, 32));
+        assert_eq!(0x1234_5678_0000_0000, vregs.read(2, 1, 64));
+
+        vregs.write_mask(0, 9, true);
+        assert!(vregs.read_mask(0, 9));
+        assert_eq!(0x2, vregs.read(0, 1, 8));
+        vregs.write_mask(0, 9, false);
+        assert!(!vregs.read_mask(0, 9));
+    }
+}
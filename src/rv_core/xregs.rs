@@ -1,5 +1,6 @@
 type AddressType = u64;
 
+#[derive(Clone)]
 pub struct XRegisters {
     reg_bank: [AddressType; 32],
 }
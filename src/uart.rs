@@ -0,0 +1,255 @@
+use crate::aia::InterruptLine;
+use crate::memory_interface::{MemoryInterface, MemoryOperation, Payload};
+use std::collections::VecDeque;
+use std::io::{self, Write};
+
+type AddressType = u64;
+
+// The ns16550a of the dtb, its interrupt is APLIC source 10
+pub const UART_BASE: AddressType = 0x1000_0000;
+pub const UART_SIZE: AddressType = 0x100;
+pub const UART_IRQ: usize = 10;
+
+// RBR/THR and IER are DLL and DLM while LCR.DLAB is set
+const UART_RBR_THR: AddressType = 0;
+const UART_IER: AddressType = 1;
+const UART_IIR_FCR: AddressType = 2;
+const UART_LCR: AddressType = 3;
+const UART_MCR: AddressType = 4;
+const UART_LSR: AddressType = 5;
+const UART_MSR: AddressType = 6;
+const UART_SCR: AddressType = 7;
+const IER_RDI: u8 = 1 << 0;
+const IER_THRI: u8 = 1 << 1;
+const IIR_NO_INT: u8 = 0x01;
+const IIR_THRI: u8 = 0x02;
+const IIR_RDI: u8 = 0x04;
+const IIR_FIFO_ENABLED: u8 = 0xc0;
+const FCR_FIFO_ENABLE: u8 = 1 << 0;
+const FCR_CLEAR_RCVR: u8 = 1 << 1;
+const LCR_DLAB: u8 = 1 << 7;
+const LSR_DR: u8 = 1 << 0;
+const LSR_THRE: u8 = 1 << 5;
+const LSR_TEMT: u8 = 1 << 6;
+// DCD, DSR and CTS, the other end is always there
+const MSR_CONNECTED: u8 = 0xb0;
+
+// Transmitted characters go to stdout straight away, so the transmitter is always
+// empty. Received characters are queued by the host.
+pub struct Uart {
+    dll: u8,
+    dlm: u8,
+    ier: u8,
+    fcr: u8,
+    lcr: u8,
+    mcr: u8,
+    scr: u8,
+    rx: VecDeque<u8>,
+    // THR empty interrupt, cleared by reading IIR or writing THR
+    thr_pending: bool,
+    irq: Option<InterruptLine>,
+    irq_level: bool,
+}
+
+impl Uart {
+    pub fn new(irq: Option<InterruptLine>) -> Uart {
+        Uart {
+            dll: 0,
+            dlm: 0,
+            ier: 0,
+            fcr: 0,
+            lcr: 0,
+            mcr: 0,
+            scr: 0,
+            rx: VecDeque::new(),
+            thr_pending: false,
+            irq,
+            irq_level: false,
+        }
+    }
+
+    pub fn receive(&mut self, byte: u8) {
+        self.rx.push_back(byte);
+        self.update_irq();
+    }
+
+    // Received data goes before the THR empty interrupt
+    fn iir(&self) -> u8 {
+        let fifo = if self.fcr & FCR_FIFO_ENABLE != 0 {
+            IIR_FIFO_ENABLED
+        } else {
+            0
+        };
+        if self.ier & IER_RDI != 0 && !self.rx.is_empty() {
+            fifo | IIR_RDI
+        } else if self.ier & IER_THRI != 0 && self.thr_pending {
+            fifo | IIR_THRI
+        } else {
+            fifo | IIR_NO_INT
+        }
+    }
+
+    fn update_irq(&mut self) {
+        let level = self.iir() & IIR_NO_INT == 0;
+        if level != self.irq_level {
+            self.irq_level = level;
+            if let Some(irq) = &self.irq {
+                irq.set(level);
+            }
+        }
+    }
+
+    fn read_byte(&mut self, offset: AddressType) -> u8 {
+        let dlab = self.lcr & LCR_DLAB != 0;
+        match offset {
+            UART_RBR_THR if dlab => self.dll,
+            UART_RBR_THR => self.rx.pop_front().unwrap_or(0),
+            UART_IER if dlab => self.dlm,
+            UART_IER => self.ier,
+            UART_IIR_FCR => {
+                let iir = self.iir();
+                if iir & !IIR_FIFO_ENABLED == IIR_THRI {
+                    self.thr_pending = false;
+                }
+                iir
+            }
+            UART_LCR => self.lcr,
+            UART_MCR => self.mcr,
+            UART_LSR => LSR_THRE | LSR_TEMT | if self.rx.is_empty() { 0 } else { LSR_DR },
+            UART_MSR => MSR_CONNECTED,
+            UART_SCR => self.scr,
+            _ => 0,
+        }
+    }
+
+    fn write_byte(&mut self, offset: AddressType, val: u8) {
+        let dlab = self.lcr & LCR_DLAB != 0;
+        match offset {
+            UART_RBR_THR if dlab => self.dll = val,
+            UART_RBR_THR => {
+                let mut stdout = io::stdout();
+                let _ = stdout.write_all(&[val]);
+                let _ = stdout.flush();
+                self.thr_pending = true;
+            }
+            UART_IER if dlab => self.dlm = val,
+            UART_IER => {
+                // Enabling the THR empty interrupt raises it, the transmitter is empty
+                if val & IER_THRI != 0 && self.ier & IER_THRI == 0 {
+                    self.thr_pending = true;
+                }
+                self.ier = val & 0xf;
+            }
+            UART_IIR_FCR => {
+                if val & FCR_CLEAR_RCVR != 0 {
+                    self.rx.clear();
+                }
+                self.fcr = val & FCR_FIFO_ENABLE;
+            }
+            UART_LCR => self.lcr = val,
+            UART_MCR => self.mcr = val & 0x1f,
+            UART_SCR => self.scr = val,
+            _ => (),
+        }
+    }
+}
+
+impl MemoryInterface for Uart {
+    // Byte registers without reg-shift, wider accesses cover consecutive registers.
+    // Cache-block operations don't touch the registers.
+    fn access_memory(&mut self, payload: &mut Payload) {
+        let offset = payload.addr - UART_BASE;
+        for (i, byte) in payload.data.iter_mut().enumerate() {
+            match payload.op {
+                MemoryOperation::READ => *byte = self.read_byte(offset + i as AddressType),
+                MemoryOperation::WRITE => self.write_byte(offset + i as AddressType, *byte),
+                _ => (),
+            }
+        }
+        self.update_irq();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aia::Aia;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    fn write(uart: &mut Uart, offset: AddressType, val: u8) {
+        let mut payload = Payload {
+            addr: UART_BASE + offset,
+            data: vec![val],
+            op: MemoryOperation::WRITE,
+            is_amo: false,
+        };
+        uart.access_memory(&mut payload);
+    }
+
+    fn read(uart: &mut Uart, offset: AddressType) -> u8 {
+        let mut payload = Payload {
+            addr: UART_BASE + offset,
+            data: vec![0],
+            op: MemoryOperation::READ,
+            is_amo: false,
+        };
+        uart.access_memory(&mut payload);
+        payload.data[0]
+    }
+
+    #[test]
+    fn test_uart() {
+        let aia = Rc::new(RefCell::new(Aia::new()));
+        let mut uart = Uart::new(Some(InterruptLine::new(aia.clone(), UART_IRQ)));
+        assert_eq!(LSR_THRE | LSR_TEMT, read(&mut uart, UART_LSR));
+        write(&mut uart, UART_LCR, LCR_DLAB);
+        write(&mut uart, UART_RBR_THR, 0x12);
+        write(&mut uart, UART_IER, 0x34);
+        assert_eq!(0x12, read(&mut uart, UART_RBR_THR));
+        assert_eq!(0x34, read(&mut uart, UART_IER));
+        write(&mut uart, UART_LCR, 0x03);
+        assert_eq!(0, read(&mut uart, UART_IER));
+
+        // domaincfg.IE, the source as Level1, setienum and idelivery of the M-level domain
+        let aplic = |offset: AddressType, val: u32| {
+            let mut payload = Payload {
+                addr: crate::aia::APLIC_M_BASE + offset,
+                data: val.to_le_bytes().to_vec(),
+                op: MemoryOperation::WRITE,
+                is_amo: false,
+            };
+            aia.borrow_mut().access_memory(&mut payload);
+        };
+        aplic(0, 1 << 8);
+        aplic(4 * UART_IRQ as AddressType, 6);
+        aplic(0x1edc, UART_IRQ as u32);
+        aplic(0x4000, 1);
+        assert!(!aia.borrow().m_external());
+
+        // Received data
+        write(&mut uart, UART_IER, IER_RDI);
+        assert_eq!(IIR_NO_INT, read(&mut uart, UART_IIR_FCR));
+        uart.receive(b'a');
+        assert_eq!(LSR_THRE | LSR_TEMT | LSR_DR, read(&mut uart, UART_LSR));
+        assert_eq!(IIR_RDI, read(&mut uart, UART_IIR_FCR));
+        let mut inval = Payload {
+            addr: UART_BASE,
+            data: vec![0; 64],
+            op: MemoryOperation::INVAL,
+            is_amo: false,
+        };
+        uart.access_memory(&mut inval);
+        assert!(aia.borrow().m_external());
+        assert_eq!(b'a', read(&mut uart, UART_RBR_THR));
+        assert!(!aia.borrow().m_external());
+
+        // THR empty, raised when enabled and cleared by reading IIR
+        write(&mut uart, UART_IIR_FCR, FCR_FIFO_ENABLE);
+        write(&mut uart, UART_IER, IER_THRI);
+        assert!(aia.borrow().m_external());
+        assert_eq!(IIR_FIFO_ENABLED | IIR_THRI, read(&mut uart, UART_IIR_FCR));
+        assert!(!aia.borrow().m_external());
+        assert_eq!(IIR_FIFO_ENABLED | IIR_NO_INT, read(&mut uart, UART_IIR_FCR));
+    }
+}